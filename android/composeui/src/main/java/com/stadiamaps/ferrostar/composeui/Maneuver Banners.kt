@@ -146,7 +146,10 @@ fun PreviewBannerView() {
                   text = "Hyde Street",
                   maneuverType = ManeuverType.TURN,
                   maneuverModifier = ManeuverModifier.LEFT,
-                  roundaboutExitDegrees = null),
+                  roundaboutExitDegrees = null,
+                  exitNumbers = listOf(),
+                  destinationRefs = listOf(),
+                  destinations = listOf()),
           secondaryContent = null,
           triggerDistanceBeforeManeuver = 42.0)
 
@@ -15,11 +15,23 @@ import okhttp3.mock.url
 import org.junit.Assert.assertEquals
 import org.junit.Assert.fail
 import org.junit.Test
+import uniffi.ferrostar.AccuracyGating
+import uniffi.ferrostar.AnnouncementTiming
+import uniffi.ferrostar.ArrivalDetection
 import uniffi.ferrostar.BoundingBox
+import uniffi.ferrostar.BreadcrumbRecording
+import uniffi.ferrostar.DistanceMeasurement
 import uniffi.ferrostar.GeographicCoordinate
+import uniffi.ferrostar.GeometryRetention
+import uniffi.ferrostar.GradeEtaAdjustment
+import uniffi.ferrostar.GuidanceVerbosity
 import uniffi.ferrostar.ManeuverModifier
 import uniffi.ferrostar.ManeuverType
+import uniffi.ferrostar.MotionDerivation
 import uniffi.ferrostar.NavigationControllerConfig
+import uniffi.ferrostar.NearbyArrivalDetection
+import uniffi.ferrostar.PowerMode
+import uniffi.ferrostar.RoadNameLookup
 import uniffi.ferrostar.Route
 import uniffi.ferrostar.RouteAdapter
 import uniffi.ferrostar.RouteDeviation
@@ -29,12 +41,17 @@ import uniffi.ferrostar.RouteRequest
 import uniffi.ferrostar.RouteRequestGenerator
 import uniffi.ferrostar.RouteResponseParser
 import uniffi.ferrostar.RouteStep
+import uniffi.ferrostar.SnappingMode
+import uniffi.ferrostar.StaleLocationRejection
+import uniffi.ferrostar.StepAdvanceCooldown
 import uniffi.ferrostar.StepAdvanceMode
+import uniffi.ferrostar.TunnelMode
 import uniffi.ferrostar.UserLocation
 import uniffi.ferrostar.VisualInstruction
 import uniffi.ferrostar.VisualInstructionContent
 import uniffi.ferrostar.Waypoint
 import uniffi.ferrostar.WaypointKind
+import uniffi.ferrostar.WrongDirectionDetection
 
 private val valhallaEndpointUrl = "https://api.stadiamaps.com/navigate/v1"
 
@@ -69,12 +86,16 @@ class FerrostarCoreTest {
           text = "Sail straight",
           maneuverType = ManeuverType.DEPART,
           maneuverModifier = ManeuverModifier.STRAIGHT,
-          roundaboutExitDegrees = null)
+          roundaboutExitDegrees = null,
+          exitNumbers = listOf(),
+          destinationRefs = listOf(),
+          destinations = listOf())
   private val mockRoute =
       Route(
           geometry = mockGeom,
           bbox = BoundingBox(sw = mockGeom.first(), ne = mockGeom.last()),
           distance = 1.0,
+          duration = 0.0,
           waypoints = mockGeom.map { Waypoint(coordinate = it, kind = WaypointKind.BREAK) },
           steps =
               listOf(
@@ -90,7 +111,17 @@ class FerrostarCoreTest {
                                   secondaryContent = null,
                                   triggerDistanceBeforeManeuver = 42.0)),
                       spokenInstructions = listOf(),
-                      duration = 0.0)))
+                      duration = 0.0,
+                      tunnels = listOf(),
+                      countryChanges = listOf(),
+                      intersections = listOf(),
+                      junctionViews = listOf(),
+                      shields = listOf(),
+                      extras = mapOf())),
+          incidents = listOf(),
+          chargingStops = listOf(),
+          notices = listOf(),
+          extras = mapOf())
 
   @Test
   fun test401UnauthorizedRouteResponse() = runTest {
@@ -302,6 +333,7 @@ class FerrostarCoreTest {
         routes.first(),
         NavigationControllerConfig(
             stepAdvance = StepAdvanceMode.RelativeLineStringDistance(16U, 16U),
+            stepAdvanceCooldown = StepAdvanceCooldown.Disabled,
             routeDeviationTracking =
                 RouteDeviationTracking.Custom(
                     detector =
@@ -313,7 +345,23 @@ class FerrostarCoreTest {
                           ): RouteDeviation {
                             return RouteDeviation.OffRoute(42.0)
                           }
-                        })))
+                        },
+            wrongDirectionDetection = WrongDirectionDetection.None,
+            gradeEtaAdjustment = GradeEtaAdjustment.None,
+            arrivalDetection = ArrivalDetection.StepCompletion,
+            nearbyArrivalDetection = NearbyArrivalDetection.None,
+            tunnelMode = TunnelMode.None,
+            announcementTiming = AnnouncementTiming.Static,
+            guidanceVerbosity = GuidanceVerbosity.ALL,
+            staleLocationRejection = StaleLocationRejection.None,
+            accuracyGating = AccuracyGating.None,
+            motionDerivation = MotionDerivation.None,
+            snapping = SnappingMode.NEAREST_POINT,
+            distanceMeasurement = DistanceMeasurement.HAVERSINE,
+            geometryRetention = GeometryRetention.Full,
+            breadcrumbRecording = BreadcrumbRecording.Disabled,
+            powerMode = PowerMode.Foreground,
+            roadNameLookup = RoadNameLookup.None))
 
     assert(deviationHandler.called)
 
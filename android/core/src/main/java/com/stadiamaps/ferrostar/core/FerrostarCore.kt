@@ -158,26 +158,32 @@ class FerrostarCore(
           is RouteProvider.CustomProvider ->
               routeProvider.provider.getRoutes(initialLocation, waypoints)
           is RouteProvider.RouteAdapter -> {
-            when (val request = routeProvider.adapter.generateRequest(initialLocation, waypoints)) {
-              is RouteRequest.HttpPost -> {
-                val httpRequest =
-                    Request.Builder()
-                        .url(request.url)
-                        .post(request.body.toRequestBody())
-                        .apply { request.headers.map { (name, value) -> header(name, value) } }
-                        .build()
-
-                val res = httpClient.newCall(httpRequest).await()
-                val bodyBytes = res.body?.bytes()
-                if (!res.isSuccessful) {
-                  throw InvalidStatusCodeException(res.code)
-                } else if (bodyBytes == null) {
-                  throw NoResponseBodyException()
+            val httpRequest =
+                when (val request =
+                    routeProvider.adapter.generateRequest(initialLocation, waypoints)) {
+                  is RouteRequest.HttpPost ->
+                      Request.Builder()
+                          .url(request.url)
+                          .post(request.body.toRequestBody())
+                          .apply { request.headers.map { (name, value) -> header(name, value) } }
+                          .build()
+                  is RouteRequest.HttpGet ->
+                      Request.Builder()
+                          .url(request.url)
+                          .get()
+                          .apply { request.headers.map { (name, value) -> header(name, value) } }
+                          .build()
                 }
 
-                routeProvider.adapter.parseResponse(bodyBytes)
-              }
+            val res = httpClient.newCall(httpRequest).await()
+            val bodyBytes = res.body?.bytes()
+            if (!res.isSuccessful) {
+              throw InvalidStatusCodeException(res.code)
+            } else if (bodyBytes == null) {
+              throw NoResponseBodyException()
             }
+
+            routeProvider.adapter.parseResponse(bodyBytes)
           }
         }
       } finally {
@@ -282,6 +288,9 @@ class FerrostarCore(
    */
   private fun handleStateUpdate(newState: TripState, location: UserLocation) {
     if (newState is TripState.Navigating) {
+      // Only going off route triggers recalculation.
+      // When traveling in the wrong direction (RouteDeviation.WrongDirection), the user is still on
+      // the route line, so apps can prompt a U-turn based on the route deviation instead.
       if (newState.deviation is RouteDeviation.OffRoute) {
         if (!_routeRequestInFlight &&
             _lastAutomaticRecalculation?.let {
@@ -37,6 +37,7 @@ class NavigationViewModel(
                 when (coreState.tripState) {
                   is TripState.Navigating -> coreState.tripState.snappedUserLocation
                   is TripState.Complete -> lastLocation
+                  is TripState.FreeDrive -> coreState.tripState.userLocation
                 }
 
             uiState(coreState, lastLocation)
@@ -65,14 +66,16 @@ class NavigationViewModel(
 private fun distanceForState(newState: TripState) =
     when (newState) {
       is TripState.Navigating -> newState.progress.distanceToNextManeuver
-      is TripState.Complete -> null
+      is TripState.Complete,
+      is TripState.FreeDrive -> null
     }
 
 private fun visualInstructionForState(newState: TripState) =
     try {
       when (newState) {
         is TripState.Navigating -> newState.visualInstruction
-        is TripState.Complete -> null
+        is TripState.Complete,
+        is TripState.FreeDrive -> null
       }
     } catch (_: NoSuchElementException) {
       null
@@ -81,5 +84,6 @@ private fun visualInstructionForState(newState: TripState) =
 private fun deviationForState(newState: TripState) =
     when (newState) {
       is TripState.Navigating -> newState.deviation
-      is TripState.Complete -> null
+      is TripState.Complete,
+      is TripState.FreeDrive -> null
     }
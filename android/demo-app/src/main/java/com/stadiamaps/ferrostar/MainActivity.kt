@@ -42,13 +42,30 @@ import java.util.Locale
 import kotlinx.coroutines.Dispatchers
 import kotlinx.coroutines.launch
 import okhttp3.OkHttpClient
+import uniffi.ferrostar.AccuracyGating
+import uniffi.ferrostar.AnnouncementTiming
+import uniffi.ferrostar.ArrivalDetection
+import uniffi.ferrostar.BreadcrumbRecording
+import uniffi.ferrostar.DistanceMeasurement
 import uniffi.ferrostar.GeographicCoordinate
+import uniffi.ferrostar.GeometryRetention
+import uniffi.ferrostar.GradeEtaAdjustment
+import uniffi.ferrostar.GuidanceVerbosity
+import uniffi.ferrostar.MotionDerivation
 import uniffi.ferrostar.NavigationControllerConfig
+import uniffi.ferrostar.NearbyArrivalDetection
+import uniffi.ferrostar.PowerMode
+import uniffi.ferrostar.RoadNameLookup
 import uniffi.ferrostar.RouteDeviationTracking
+import uniffi.ferrostar.SnappingMode
+import uniffi.ferrostar.StaleLocationRejection
+import uniffi.ferrostar.StepAdvanceCooldown
 import uniffi.ferrostar.StepAdvanceMode
+import uniffi.ferrostar.TunnelMode
 import uniffi.ferrostar.UserLocation
 import uniffi.ferrostar.Waypoint
 import uniffi.ferrostar.WaypointKind
+import uniffi.ferrostar.WrongDirectionDetection
 
 class MainActivity : ComponentActivity(), AndroidTtsStatusListener {
   companion object {
@@ -115,9 +132,27 @@ class MainActivity : ComponentActivity(), AndroidTtsStatusListener {
         core.replaceRoute(
             routes.first(),
             NavigationControllerConfig(
-                StepAdvanceMode.RelativeLineStringDistance(
-                    minimumHorizontalAccuracy = 25U, automaticAdvanceDistance = 10U),
-                RouteDeviationTracking.StaticThreshold(25U, 10.0)))
+                stepAdvance =
+                    StepAdvanceMode.RelativeLineStringDistance(
+                        minimumHorizontalAccuracy = 25U, automaticAdvanceDistance = 10U),
+                stepAdvanceCooldown = StepAdvanceCooldown.Disabled,
+                routeDeviationTracking = RouteDeviationTracking.StaticThreshold(25U, 10.0),
+                wrongDirectionDetection = WrongDirectionDetection.None,
+                gradeEtaAdjustment = GradeEtaAdjustment.None,
+                arrivalDetection = ArrivalDetection.StepCompletion,
+                nearbyArrivalDetection = NearbyArrivalDetection.None,
+                tunnelMode = TunnelMode.None,
+                announcementTiming = AnnouncementTiming.Static,
+                guidanceVerbosity = GuidanceVerbosity.ALL,
+                staleLocationRejection = StaleLocationRejection.None,
+                accuracyGating = AccuracyGating.None,
+                motionDerivation = MotionDerivation.None,
+                snapping = SnappingMode.NEAREST_POINT,
+                distanceMeasurement = DistanceMeasurement.HAVERSINE,
+                geometryRetention = GeometryRetention.Full,
+                breadcrumbRecording = BreadcrumbRecording.Disabled,
+                powerMode = PowerMode.Foreground,
+                roadNameLookup = RoadNameLookup.None))
       }
     }
 
@@ -171,9 +206,28 @@ class MainActivity : ComponentActivity(), AndroidTtsStatusListener {
                   route = route,
                   config =
                       NavigationControllerConfig(
-                          StepAdvanceMode.RelativeLineStringDistance(
-                              minimumHorizontalAccuracy = 25U, automaticAdvanceDistance = 10U),
-                          RouteDeviationTracking.StaticThreshold(25U, 10.0)),
+                          stepAdvance =
+                              StepAdvanceMode.RelativeLineStringDistance(
+                                  minimumHorizontalAccuracy = 25U, automaticAdvanceDistance = 10U),
+                          stepAdvanceCooldown = StepAdvanceCooldown.Disabled,
+                          routeDeviationTracking =
+                              RouteDeviationTracking.StaticThreshold(25U, 10.0),
+                          wrongDirectionDetection = WrongDirectionDetection.None,
+                          gradeEtaAdjustment = GradeEtaAdjustment.None,
+                          arrivalDetection = ArrivalDetection.StepCompletion,
+                          nearbyArrivalDetection = NearbyArrivalDetection.None,
+                          tunnelMode = TunnelMode.None,
+                          announcementTiming = AnnouncementTiming.Static,
+                          guidanceVerbosity = GuidanceVerbosity.ALL,
+                          staleLocationRejection = StaleLocationRejection.None,
+                          accuracyGating = AccuracyGating.None,
+                          motionDerivation = MotionDerivation.None,
+                          snapping = SnappingMode.NEAREST_POINT,
+                          distanceMeasurement = DistanceMeasurement.HAVERSINE,
+                          geometryRetention = GeometryRetention.Full,
+                          breadcrumbRecording = BreadcrumbRecording.Disabled,
+                          powerMode = PowerMode.Foreground,
+                          roadNameLookup = RoadNameLookup.None),
               )
 
           locationProvider.setSimulatedRoute(route)
@@ -178,29 +178,43 @@ public protocol FerrostarCoreDelegate: AnyObject {
                 waypoints: waypoints
             )
 
+            var urlRequest: URLRequest
             switch routeRequest {
             case let .httpPost(url: url, headers: headers, body: body):
                 guard let url = URL(string: url) else {
                     throw FerrostarCoreError.invalidRequestUrl
                 }
 
-                var urlRequest = URLRequest(url: url)
-                urlRequest.httpMethod = "POST"
+                var request = URLRequest(url: url)
+                request.httpMethod = "POST"
                 for (header, value) in headers {
-                    urlRequest.setValue(value, forHTTPHeaderField: header)
+                    request.setValue(value, forHTTPHeaderField: header)
+                }
+                request.httpBody = Data(body)
+                urlRequest = request
+            case let .httpGet(url: url, headers: headers):
+                guard let url = URL(string: url) else {
+                    throw FerrostarCoreError.invalidRequestUrl
                 }
-                urlRequest.httpBody = Data(body)
-                urlRequest.timeoutInterval = 15
 
-                let (data, response) = try await networkSession.loadData(with: urlRequest)
+                var request = URLRequest(url: url)
+                request.httpMethod = "GET"
+                for (header, value) in headers {
+                    request.setValue(value, forHTTPHeaderField: header)
+                }
+                urlRequest = request
+            }
 
-                if let res = response as? HTTPURLResponse, res.statusCode < 200 || res.statusCode >= 300 {
-                    throw FerrostarCoreError.httpStatusCode(res.statusCode)
-                } else {
-                    let routes = try routeAdapter.parseResponse(response: data)
+            urlRequest.timeoutInterval = 15
 
-                    return routes
-                }
+            let (data, response) = try await networkSession.loadData(with: urlRequest)
+
+            if let res = response as? HTTPURLResponse, res.statusCode < 200 || res.statusCode >= 300 {
+                throw FerrostarCoreError.httpStatusCode(res.statusCode)
+            } else {
+                let routes = try routeAdapter.parseResponse(response: data)
+
+                return routes
             }
         }
     }
@@ -267,7 +281,14 @@ public protocol FerrostarCoreDelegate: AnyObject {
                 progress: progress,
                 deviation: deviation,
                 visualInstruction: visualInstruction,
-                spokenInstruction: spokenInstruction
+                thenStep: _,
+                spokenInstruction: spokenInstruction,
+                prefetchedSpokenInstructions: _,
+                upcomingIncident: _,
+                upcomingClimb: _,
+                currentRoadName: _,
+                tracking: _,
+                positioning: _
             ):
                 self.state?.snappedLocation = snappedLocation
                 self.state?.currentStep = remainingSteps.first
@@ -280,6 +301,10 @@ public protocol FerrostarCoreDelegate: AnyObject {
                 case .noDeviation:
                     // No action
                     break
+                case .wrongDirection:
+                    // The user is still on the route line, so there is no need to recalculate;
+                    // apps can prompt a U-turn based on the route deviation in the navigation state.
+                    break
                 case let .offRoute(deviationFromRouteLine: deviationFromRouteLine):
                     guard !self.routeRequestInFlight,
                           self.lastAutomaticRecalculation?.timeIntervalSinceNow ?? -TimeInterval
@@ -339,6 +364,17 @@ public protocol FerrostarCoreDelegate: AnyObject {
                 self.state?.snappedLocation = location
                 self.state?.spokenInstruction = nil
                 self.state?.routeDeviation = nil
+            case let .freeDrive(
+                userLocation: userLocation,
+                currentRoadName: _,
+                speedLimit: _,
+                positioning: _
+            ):
+                // There is no route to follow, so only the location is tracked
+                self.state?.visualInstruction = nil
+                self.state?.snappedLocation = userLocation
+                self.state?.spokenInstruction = nil
+                self.state?.routeDeviation = nil
             }
         }
     }
@@ -51,12 +51,21 @@ public extension NavigationState {
                             text: "Hyde Street",
                             maneuverType: .turn,
                             maneuverModifier: .left,
-                            roundaboutExitDegrees: nil
+                            roundaboutExitDegrees: nil,
+                            exitNumbers: [],
+                            destinationRefs: [],
+                            destinations: []
                         ),
                         secondaryContent: nil, triggerDistanceBeforeManeuver: 42.0
                     ),
                 ],
-                spokenInstructions: []
+                spokenInstructions: [],
+                tunnels: [],
+                countryChanges: [],
+                intersections: [],
+                junctionViews: [],
+                shields: [],
+                extras: [:]
             )],
             progress: TripProgress(
                 distanceToNextManeuver: 5,
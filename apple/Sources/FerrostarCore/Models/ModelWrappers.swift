@@ -47,11 +47,30 @@ public enum SwiftRouteDeviationTracking {
 }
 
 /// A Swift wrapper around `UniFFI.NavigationControllerConfig`.
+///
+/// Behaviors other than step advance and route deviation tracking are disabled.
 public struct SwiftNavigationControllerConfig {
     public init(stepAdvance: StepAdvanceMode, routeDeviationTracking: SwiftRouteDeviationTracking) {
         ffiValue = FerrostarCoreFFI.NavigationControllerConfig(
             stepAdvance: stepAdvance,
-            routeDeviationTracking: routeDeviationTracking.ffiValue
+            stepAdvanceCooldown: .disabled,
+            routeDeviationTracking: routeDeviationTracking.ffiValue,
+            wrongDirectionDetection: .none,
+            gradeEtaAdjustment: .none,
+            arrivalDetection: .stepCompletion,
+            nearbyArrivalDetection: .none,
+            tunnelMode: .none,
+            announcementTiming: .static,
+            guidanceVerbosity: .all,
+            staleLocationRejection: .none,
+            accuracyGating: .none,
+            motionDerivation: .none,
+            snapping: .nearestPoint,
+            distanceMeasurement: .haversine,
+            geometryRetention: .full,
+            breadcrumbRecording: .disabled,
+            powerMode: .foreground,
+            roadNameLookup: .none
         )
     }
 
@@ -104,13 +104,19 @@ public struct InstructionsView: View {
                     text: "Turn right on Something Dr.",
                     maneuverType: .turn,
                     maneuverModifier: .right,
-                    roundaboutExitDegrees: nil
+                    roundaboutExitDegrees: nil,
+                    exitNumbers: [],
+                    destinationRefs: [],
+                    destinations: []
                 ),
                 secondaryContent: VisualInstructionContent(
                     text: "Merge onto Hwy 123",
                     maneuverType: .merge,
                     maneuverModifier: .right,
-                    roundaboutExitDegrees: nil
+                    roundaboutExitDegrees: nil,
+                    exitNumbers: [],
+                    destinationRefs: [],
+                    destinations: []
                 ),
                 triggerDistanceBeforeManeuver: 123
             )
@@ -122,7 +128,10 @@ public struct InstructionsView: View {
                     text: "Use the second exit to leave the roundabout.",
                     maneuverType: .rotary,
                     maneuverModifier: .slightRight,
-                    roundaboutExitDegrees: nil
+                    roundaboutExitDegrees: nil,
+                    exitNumbers: [],
+                    destinationRefs: [],
+                    destinations: []
                 ),
                 secondaryContent: nil,
                 triggerDistanceBeforeManeuver: 123
@@ -135,7 +144,10 @@ public struct InstructionsView: View {
                     text: "Links einfädeln.",
                     maneuverType: .merge,
                     maneuverModifier: .slightLeft,
-                    roundaboutExitDegrees: nil
+                    roundaboutExitDegrees: nil,
+                    exitNumbers: [],
+                    destinationRefs: [],
+                    destinations: []
                 ),
                 secondaryContent: nil,
                 triggerDistanceBeforeManeuver: 123
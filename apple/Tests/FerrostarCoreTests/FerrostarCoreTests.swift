@@ -22,12 +22,16 @@ let instructionContent = VisualInstructionContent(
     text: "Sail straight",
     maneuverType: .depart,
     maneuverModifier: .straight,
-    roundaboutExitDegrees: nil
+    roundaboutExitDegrees: nil,
+    exitNumbers: [],
+    destinationRefs: [],
+    destinations: []
 )
 let mockRoute = Route(
     geometry: mockGeom,
     bbox: BoundingBox(sw: mockGeom.first!, ne: mockGeom.last!),
     distance: 1,
+    duration: 0,
     waypoints: mockGeom.map { Waypoint(coordinate: $0, kind: .break) },
     steps: [RouteStep(
         geometry: mockGeom,
@@ -40,8 +44,18 @@ let mockRoute = Route(
             secondaryContent: nil,
             triggerDistanceBeforeManeuver: 42
         )],
-        spokenInstructions: []
-    )]
+        spokenInstructions: [],
+        tunnels: [],
+        countryChanges: [],
+        intersections: [],
+        junctionViews: [],
+        shields: [],
+        extras: [:]
+    )],
+    incidents: [],
+    chargingStops: [],
+    notices: [],
+    extras: [:]
 )
 
 // Mocked route adapter
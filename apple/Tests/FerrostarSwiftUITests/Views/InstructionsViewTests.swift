@@ -11,13 +11,19 @@ final class InstructionsViewTests: XCTestCase {
                         text: "Turn right on Something Dr.",
                         maneuverType: .turn,
                         maneuverModifier: .right,
-                        roundaboutExitDegrees: nil
+                        roundaboutExitDegrees: nil,
+                        exitNumbers: [],
+                        destinationRefs: [],
+                        destinations: []
                     ),
                     secondaryContent: VisualInstructionContent(
                         text: "Merge onto Hwy 123",
                         maneuverType: .merge,
                         maneuverModifier: .right,
-                        roundaboutExitDegrees: nil
+                        roundaboutExitDegrees: nil,
+                        exitNumbers: [],
+                        destinationRefs: [],
+                        destinations: []
                     ),
                     triggerDistanceBeforeManeuver: 123
                 ),
@@ -36,7 +42,10 @@ final class InstructionsViewTests: XCTestCase {
                         text: "Use the second exit to leave the roundabout.",
                         maneuverType: .rotary,
                         maneuverModifier: .slightRight,
-                        roundaboutExitDegrees: nil
+                        roundaboutExitDegrees: nil,
+                        exitNumbers: [],
+                        destinationRefs: [],
+                        destinations: []
                     ),
                     secondaryContent: nil,
                     triggerDistanceBeforeManeuver: 123
@@ -56,7 +65,10 @@ final class InstructionsViewTests: XCTestCase {
                         text: "Use the second exit to leave the roundabout.",
                         maneuverType: .rotary,
                         maneuverModifier: .slightRight,
-                        roundaboutExitDegrees: nil
+                        roundaboutExitDegrees: nil,
+                        exitNumbers: [],
+                        destinationRefs: [],
+                        destinations: []
                     ),
                     secondaryContent: nil,
                     triggerDistanceBeforeManeuver: 123
@@ -77,7 +89,10 @@ final class InstructionsViewTests: XCTestCase {
                         text: "Links einfädeln",
                         maneuverType: .turn,
                         maneuverModifier: .left,
-                        roundaboutExitDegrees: nil
+                        roundaboutExitDegrees: nil,
+                        exitNumbers: [],
+                        destinationRefs: [],
+                        destinations: []
                     ),
                     secondaryContent: nil,
                     triggerDistanceBeforeManeuver: 123
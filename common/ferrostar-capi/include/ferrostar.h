@@ -0,0 +1,211 @@
+#ifndef FERROSTAR_H
+#define FERROSTAR_H
+
+/* Generated by cbindgen; do not edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// Whether the user is following the route.
+typedef enum FerrostarRouteDeviation {
+  FERROSTAR_ROUTE_DEVIATION_NO_DEVIATION = 0,
+  FERROSTAR_ROUTE_DEVIATION_OFF_ROUTE = 1,
+  FERROSTAR_ROUTE_DEVIATION_WRONG_DIRECTION = 2,
+} FerrostarRouteDeviation;
+
+// The outcome of a fallible call.
+typedef enum FerrostarStatus {
+  FERROSTAR_STATUS_OK = 0,
+  // A required pointer argument was `NULL`.
+  FERROSTAR_STATUS_NULL_POINTER = 1,
+  // The routing response could not be parsed.
+  FERROSTAR_STATUS_PARSE_ERROR = 2,
+  // The requested route index does not exist.
+  FERROSTAR_STATUS_INDEX_OUT_OF_BOUNDS = 3,
+  // An unexpected internal error (panic) occurred.
+  FERROSTAR_STATUS_INTERNAL_ERROR = 4,
+} FerrostarStatus;
+
+typedef struct FerrostarNavigationController FerrostarNavigationController;
+
+// The routes parsed from a routing response.
+typedef struct FerrostarRoutes FerrostarRoutes;
+
+typedef struct FerrostarTripState FerrostarTripState;
+
+// The subset of the navigation controller configuration which is available over the C API.
+//
+// This configures distance-based step advance and static threshold route deviation tracking.
+typedef struct FerrostarNavigationControllerConfig {
+  // The distance to the end of a step, in meters, at which to advance to the next one.
+  uint16_t step_advance_distance;
+  // The minimum required horizontal accuracy, in meters, for step advance and deviation detection.
+  uint16_t minimum_horizontal_accuracy;
+  // The maximum acceptable deviation from the route line, in meters.
+  double max_acceptable_deviation;
+} FerrostarNavigationControllerConfig;
+
+// A location update from the positioning system.
+//
+// Optional values are signaled with negative numbers (ex: -1), since none of them can be negative.
+typedef struct FerrostarUserLocation {
+  double lat;
+  double lng;
+  // The estimated accuracy of the coordinate, in meters.
+  double horizontal_accuracy;
+  // The course over ground, in clockwise degrees from true north, or a negative value if unknown.
+  int32_t course_degrees;
+  // The accuracy of the course, in degrees, or a negative value if unknown.
+  int32_t course_accuracy;
+  // The speed, in meters per second, or a negative value if unknown.
+  double speed;
+  // The accuracy of the speed, in meters per second, or a negative value if unknown.
+  double speed_accuracy;
+  // The time at which the location was recorded, in milliseconds since the Unix epoch.
+  uint64_t timestamp_ms;
+} FerrostarUserLocation;
+
+typedef struct FerrostarTripProgress {
+  // The distance to the next maneuver, in meters.
+  double distance_to_next_maneuver;
+  // The total distance remaining in the trip, in meters.
+  double distance_remaining;
+  // The total duration remaining in the trip, in seconds.
+  double duration_remaining;
+} FerrostarTripProgress;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Parses an OSRM (or Valhalla OSRM-compatible) response.
+//
+// # Safety
+//
+// `data` must point to `len` readable bytes.
+FerrostarRoutes *ferrostar_parse_osrm_response(const uint8_t *data,
+                                               size_t len,
+                                               uint32_t polyline_precision,
+                                               FerrostarStatus *status);
+
+// Returns the number of routes (zero if `routes` is `NULL`).
+//
+// # Safety
+//
+// `routes` must be `NULL` or a pointer returned by [`ferrostar_parse_osrm_response`].
+size_t ferrostar_routes_len(const FerrostarRoutes *routes);
+
+// # Safety
+//
+// `routes` must be `NULL` or a pointer returned by [`ferrostar_parse_osrm_response`]
+// which has not already been freed.
+void ferrostar_routes_free(FerrostarRoutes *routes);
+
+// Creates a navigation controller for the route at `index`.
+//
+// # Safety
+//
+// `routes` must be a pointer returned by [`ferrostar_parse_osrm_response`],
+// and `config` must point to a valid config.
+FerrostarNavigationController *ferrostar_navigation_controller_new(const FerrostarRoutes *routes,
+                                                                   size_t index,
+                                                                   const FerrostarNavigationControllerConfig *config,
+                                                                   FerrostarStatus *status);
+
+// # Safety
+//
+// `controller` must be `NULL` or a pointer returned by [`ferrostar_navigation_controller_new`]
+// which has not already been freed.
+void ferrostar_navigation_controller_free(FerrostarNavigationController *controller);
+
+// Returns the initial trip state, given the user's current location.
+//
+// # Safety
+//
+// `controller` must be a pointer returned by [`ferrostar_navigation_controller_new`],
+// and `location` must point to a valid location.
+FerrostarTripState *ferrostar_navigation_controller_get_initial_state(const FerrostarNavigationController *controller,
+                                                                      const FerrostarUserLocation *location,
+                                                                      FerrostarStatus *status);
+
+// Returns the trip state after a location update.
+//
+// # Safety
+//
+// `controller` must be a pointer returned by [`ferrostar_navigation_controller_new`],
+// `location` must point to a valid location,
+// and `state` must be a trip state returned by this controller.
+FerrostarTripState *ferrostar_navigation_controller_update_user_location(const FerrostarNavigationController *controller,
+                                                                         const FerrostarUserLocation *location,
+                                                                         const FerrostarTripState *state,
+                                                                         FerrostarStatus *status);
+
+// Returns the trip state after manually advancing to the next step.
+//
+// # Safety
+//
+// `controller` must be a pointer returned by [`ferrostar_navigation_controller_new`],
+// and `state` must be a trip state returned by this controller.
+FerrostarTripState *ferrostar_navigation_controller_advance_to_next_step(const FerrostarNavigationController *controller,
+                                                                         const FerrostarTripState *state,
+                                                                         FerrostarStatus *status);
+
+// # Safety
+//
+// `state` must be `NULL` or a trip state pointer which has not already been freed.
+void ferrostar_trip_state_free(FerrostarTripState *state);
+
+// Returns true if the trip is complete (or `state` is `NULL`).
+//
+// # Safety
+//
+// `state` must be `NULL` or a valid trip state pointer.
+bool ferrostar_trip_state_is_complete(const FerrostarTripState *state);
+
+// Writes the trip progress to `progress`.
+//
+// Returns false (leaving `progress` untouched) unless the trip is in progress.
+//
+// # Safety
+//
+// `state` must be `NULL` or a valid trip state pointer,
+// and `progress` must be `NULL` or writable.
+bool ferrostar_trip_state_progress(const FerrostarTripState *state,
+                                   FerrostarTripProgress *progress);
+
+// Returns whether the user is following the route.
+//
+// This is always [`FerrostarRouteDeviation::NoDeviation`] unless the trip is in progress.
+//
+// # Safety
+//
+// `state` must be `NULL` or a valid trip state pointer.
+FerrostarRouteDeviation ferrostar_trip_state_deviation(const FerrostarTripState *state);
+
+// Returns the primary text of the current visual instruction, or `NULL` if there is none.
+//
+// # Safety
+//
+// `state` must be `NULL` or a valid trip state pointer.
+char *ferrostar_trip_state_visual_instruction(const FerrostarTripState *state);
+
+// Returns the text of the current spoken instruction, or `NULL` if there is none.
+//
+// # Safety
+//
+// `state` must be `NULL` or a valid trip state pointer.
+char *ferrostar_trip_state_spoken_instruction(const FerrostarTripState *state);
+
+// # Safety
+//
+// `string` must be `NULL` or a string returned by this library which has not already been freed.
+void ferrostar_string_free(char *string);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* FERROSTAR_H */
@@ -0,0 +1,676 @@
+//! A plain C API for Ferrostar.
+//!
+//! This is intended for integrators without Swift or Kotlin toolchains
+//! (ex: automotive Linux or RTOS targets).
+//! It covers route response parsing and the navigation controller;
+//! the header lives in `include/ferrostar.h` and is generated by cbindgen.
+//!
+//! Conventions:
+//!
+//! - Objects are opaque pointers which are owned by the caller,
+//!   and must be released with the matching `_free` function.
+//! - Inputs are never consumed; a new object is returned whenever the state changes.
+//! - Functions which can fail accept a (nullable) [`FerrostarStatus`] out parameter and return `NULL` on failure.
+//! - Strings are NUL-terminated UTF-8, and must be released with [`ferrostar_string_free`].
+//! - Panics never unwind into the caller. Fallible calls report them as
+//!   [`FerrostarStatus::InternalError`], and the others return the same value as for `NULL` inputs.
+
+use ferrostar::deviation_detection::{RouteDeviation, RouteDeviationTracking};
+use ferrostar::models::{CourseOverGround, GeographicCoordinate, Route, Speed, UserLocation};
+use ferrostar::navigation_controller::models::{
+    NavigationControllerConfig, SnappingMode, StepAdvanceMode, TripState,
+};
+use ferrostar::navigation_controller::NavigationController;
+use ferrostar::road_name::RoadNameLookup;
+use ferrostar::routing_adapters::osrm::OsrmResponseParser;
+use ferrostar::routing_adapters::RouteResponseParser;
+use std::ffi::{c_char, CString};
+use std::panic::{self, AssertUnwindSafe};
+use std::time::{Duration, SystemTime};
+
+/// The outcome of a fallible call.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FerrostarStatus {
+    Ok = 0,
+    /// A required pointer argument was `NULL`.
+    NullPointer = 1,
+    /// The routing response could not be parsed.
+    ParseError = 2,
+    /// The requested route index does not exist.
+    IndexOutOfBounds = 3,
+    /// An unexpected internal error (panic) occurred.
+    InternalError = 4,
+}
+
+/// The routes parsed from a routing response.
+pub struct FerrostarRoutes {
+    routes: Vec<Route>,
+}
+
+pub struct FerrostarNavigationController {
+    controller: NavigationController,
+}
+
+pub struct FerrostarTripState {
+    state: TripState,
+}
+
+/// A location update from the positioning system.
+///
+/// Optional values are signaled with negative numbers (ex: -1), since none of them can be negative.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct FerrostarUserLocation {
+    pub lat: f64,
+    pub lng: f64,
+    /// The estimated accuracy of the coordinate, in meters.
+    pub horizontal_accuracy: f64,
+    /// The course over ground, in clockwise degrees from true north, or a negative value if unknown.
+    pub course_degrees: i32,
+    /// The accuracy of the course, in degrees, or a negative value if unknown.
+    pub course_accuracy: i32,
+    /// The speed, in meters per second, or a negative value if unknown.
+    pub speed: f64,
+    /// The accuracy of the speed, in meters per second, or a negative value if unknown.
+    pub speed_accuracy: f64,
+    /// The time at which the location was recorded, in milliseconds since the Unix epoch.
+    pub timestamp_ms: u64,
+}
+
+impl From<FerrostarUserLocation> for UserLocation {
+    fn from(location: FerrostarUserLocation) -> Self {
+        let non_negative = |value: f64| (value >= 0.0).then_some(value);
+        Self {
+            coordinates: GeographicCoordinate {
+                lat: location.lat,
+                lng: location.lng,
+            },
+            horizontal_accuracy: location.horizontal_accuracy,
+            course_over_ground: u16::try_from(location.course_degrees).ok().map(|degrees| {
+                CourseOverGround::new(degrees, u16::try_from(location.course_accuracy).ok())
+            }),
+            timestamp: SystemTime::UNIX_EPOCH + Duration::from_millis(location.timestamp_ms),
+            speed: non_negative(location.speed).map(|value| Speed {
+                value,
+                accuracy: non_negative(location.speed_accuracy),
+            }),
+            altitude: None,
+            vertical_accuracy: None,
+        }
+    }
+}
+
+/// The subset of the navigation controller configuration which is available over the C API.
+///
+/// This configures distance-based step advance and static threshold route deviation tracking.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct FerrostarNavigationControllerConfig {
+    /// The distance to the end of a step, in meters, at which to advance to the next one.
+    pub step_advance_distance: u16,
+    /// The minimum required horizontal accuracy, in meters, for step advance and deviation detection.
+    pub minimum_horizontal_accuracy: u16,
+    /// The maximum acceptable deviation from the route line, in meters.
+    pub max_acceptable_deviation: f64,
+}
+
+impl From<FerrostarNavigationControllerConfig> for NavigationControllerConfig {
+    fn from(config: FerrostarNavigationControllerConfig) -> Self {
+        Self {
+            step_advance: StepAdvanceMode::DistanceToEndOfStep {
+                distance: config.step_advance_distance,
+                minimum_horizontal_accuracy: config.minimum_horizontal_accuracy,
+            },
+            route_deviation_tracking: RouteDeviationTracking::StaticThreshold {
+                minimum_horizontal_accuracy: config.minimum_horizontal_accuracy,
+                max_acceptable_deviation: config.max_acceptable_deviation,
+            },
+            snapping: SnappingMode::NearestPointWithHeading,
+            road_name_lookup: RoadNameLookup::StepName,
+            ..Default::default()
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct FerrostarTripProgress {
+    /// The distance to the next maneuver, in meters.
+    pub distance_to_next_maneuver: f64,
+    /// The total distance remaining in the trip, in meters.
+    pub distance_remaining: f64,
+    /// The total duration remaining in the trip, in seconds.
+    pub duration_remaining: f64,
+}
+
+/// Whether the user is following the route.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FerrostarRouteDeviation {
+    NoDeviation = 0,
+    OffRoute = 1,
+    WrongDirection = 2,
+}
+
+/// Writes `value` to `status` unless it is `NULL`.
+unsafe fn set_status(status: *mut FerrostarStatus, value: FerrostarStatus) {
+    if let Some(status) = status.as_mut() {
+        *status = value;
+    }
+}
+
+/// Runs `body`, catching any panic so that it does not unwind across the FFI boundary.
+///
+/// On panic, `status` is set to [`FerrostarStatus::InternalError`] and `fallback` is returned.
+unsafe fn catch_panic<T>(status: *mut FerrostarStatus, fallback: T, body: impl FnOnce() -> T) -> T {
+    panic::catch_unwind(AssertUnwindSafe(body)).unwrap_or_else(|_| {
+        set_status(status, FerrostarStatus::InternalError);
+        fallback
+    })
+}
+
+fn into_c_string(value: &str) -> *mut c_char {
+    CString::new(value).map_or(std::ptr::null_mut(), CString::into_raw)
+}
+
+/// Parses an OSRM (or Valhalla OSRM-compatible) response.
+///
+/// # Safety
+///
+/// `data` must point to `len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn ferrostar_parse_osrm_response(
+    data: *const u8,
+    len: usize,
+    polyline_precision: u32,
+    status: *mut FerrostarStatus,
+) -> *mut FerrostarRoutes {
+    catch_panic(status, std::ptr::null_mut(), || {
+        if data.is_null() {
+            set_status(status, FerrostarStatus::NullPointer);
+            return std::ptr::null_mut();
+        }
+
+        let response = std::slice::from_raw_parts(data, len).to_vec();
+        match OsrmResponseParser::new(polyline_precision).parse_response(response) {
+            Ok(routes) => {
+                set_status(status, FerrostarStatus::Ok);
+                Box::into_raw(Box::new(FerrostarRoutes { routes }))
+            }
+            Err(_) => {
+                set_status(status, FerrostarStatus::ParseError);
+                std::ptr::null_mut()
+            }
+        }
+    })
+}
+
+/// Returns the number of routes (zero if `routes` is `NULL`).
+///
+/// # Safety
+///
+/// `routes` must be `NULL` or a pointer returned by [`ferrostar_parse_osrm_response`].
+#[no_mangle]
+pub unsafe extern "C" fn ferrostar_routes_len(routes: *const FerrostarRoutes) -> usize {
+    catch_panic(std::ptr::null_mut(), 0, || {
+        routes.as_ref().map_or(0, |routes| routes.routes.len())
+    })
+}
+
+/// # Safety
+///
+/// `routes` must be `NULL` or a pointer returned by [`ferrostar_parse_osrm_response`]
+/// which has not already been freed.
+#[no_mangle]
+pub unsafe extern "C" fn ferrostar_routes_free(routes: *mut FerrostarRoutes) {
+    catch_panic(std::ptr::null_mut(), (), || {
+        if !routes.is_null() {
+            drop(Box::from_raw(routes));
+        }
+    })
+}
+
+/// Creates a navigation controller for the route at `index`.
+///
+/// # Safety
+///
+/// `routes` must be a pointer returned by [`ferrostar_parse_osrm_response`],
+/// and `config` must point to a valid config.
+#[no_mangle]
+pub unsafe extern "C" fn ferrostar_navigation_controller_new(
+    routes: *const FerrostarRoutes,
+    index: usize,
+    config: *const FerrostarNavigationControllerConfig,
+    status: *mut FerrostarStatus,
+) -> *mut FerrostarNavigationController {
+    catch_panic(status, std::ptr::null_mut(), || {
+        let (Some(routes), Some(config)) = (routes.as_ref(), config.as_ref()) else {
+            set_status(status, FerrostarStatus::NullPointer);
+            return std::ptr::null_mut();
+        };
+        let Some(route) = routes.routes.get(index) else {
+            set_status(status, FerrostarStatus::IndexOutOfBounds);
+            return std::ptr::null_mut();
+        };
+
+        set_status(status, FerrostarStatus::Ok);
+        Box::into_raw(Box::new(FerrostarNavigationController {
+            controller: NavigationController::new(route.clone(), (*config).into()),
+        }))
+    })
+}
+
+/// # Safety
+///
+/// `controller` must be `NULL` or a pointer returned by [`ferrostar_navigation_controller_new`]
+/// which has not already been freed.
+#[no_mangle]
+pub unsafe extern "C" fn ferrostar_navigation_controller_free(
+    controller: *mut FerrostarNavigationController,
+) {
+    catch_panic(std::ptr::null_mut(), (), || {
+        if !controller.is_null() {
+            drop(Box::from_raw(controller));
+        }
+    })
+}
+
+/// Returns the initial trip state, given the user's current location.
+///
+/// # Safety
+///
+/// `controller` must be a pointer returned by [`ferrostar_navigation_controller_new`],
+/// and `location` must point to a valid location.
+#[no_mangle]
+pub unsafe extern "C" fn ferrostar_navigation_controller_get_initial_state(
+    controller: *const FerrostarNavigationController,
+    location: *const FerrostarUserLocation,
+    status: *mut FerrostarStatus,
+) -> *mut FerrostarTripState {
+    catch_panic(status, std::ptr::null_mut(), || {
+        let (Some(controller), Some(location)) = (controller.as_ref(), location.as_ref()) else {
+            set_status(status, FerrostarStatus::NullPointer);
+            return std::ptr::null_mut();
+        };
+
+        set_status(status, FerrostarStatus::Ok);
+        Box::into_raw(Box::new(FerrostarTripState {
+            state: controller.controller.get_initial_state((*location).into()),
+        }))
+    })
+}
+
+/// Returns the trip state after a location update.
+///
+/// # Safety
+///
+/// `controller` must be a pointer returned by [`ferrostar_navigation_controller_new`],
+/// `location` must point to a valid location,
+/// and `state` must be a trip state returned by this controller.
+#[no_mangle]
+pub unsafe extern "C" fn ferrostar_navigation_controller_update_user_location(
+    controller: *const FerrostarNavigationController,
+    location: *const FerrostarUserLocation,
+    state: *const FerrostarTripState,
+    status: *mut FerrostarStatus,
+) -> *mut FerrostarTripState {
+    catch_panic(status, std::ptr::null_mut(), || {
+        let (Some(controller), Some(location), Some(state)) =
+            (controller.as_ref(), location.as_ref(), state.as_ref())
+        else {
+            set_status(status, FerrostarStatus::NullPointer);
+            return std::ptr::null_mut();
+        };
+
+        set_status(status, FerrostarStatus::Ok);
+        Box::into_raw(Box::new(FerrostarTripState {
+            state: controller
+                .controller
+                .update_user_location((*location).into(), &state.state),
+        }))
+    })
+}
+
+/// Returns the trip state after manually advancing to the next step.
+///
+/// # Safety
+///
+/// `controller` must be a pointer returned by [`ferrostar_navigation_controller_new`],
+/// and `state` must be a trip state returned by this controller.
+#[no_mangle]
+pub unsafe extern "C" fn ferrostar_navigation_controller_advance_to_next_step(
+    controller: *const FerrostarNavigationController,
+    state: *const FerrostarTripState,
+    status: *mut FerrostarStatus,
+) -> *mut FerrostarTripState {
+    catch_panic(status, std::ptr::null_mut(), || {
+        let (Some(controller), Some(state)) = (controller.as_ref(), state.as_ref()) else {
+            set_status(status, FerrostarStatus::NullPointer);
+            return std::ptr::null_mut();
+        };
+
+        set_status(status, FerrostarStatus::Ok);
+        Box::into_raw(Box::new(FerrostarTripState {
+            state: controller.controller.advance_to_next_step(&state.state),
+        }))
+    })
+}
+
+/// # Safety
+///
+/// `state` must be `NULL` or a trip state pointer which has not already been freed.
+#[no_mangle]
+pub unsafe extern "C" fn ferrostar_trip_state_free(state: *mut FerrostarTripState) {
+    catch_panic(std::ptr::null_mut(), (), || {
+        if !state.is_null() {
+            drop(Box::from_raw(state));
+        }
+    })
+}
+
+/// Returns true if the trip is complete (or `state` is `NULL`).
+///
+/// # Safety
+///
+/// `state` must be `NULL` or a valid trip state pointer.
+#[no_mangle]
+pub unsafe extern "C" fn ferrostar_trip_state_is_complete(
+    state: *const FerrostarTripState,
+) -> bool {
+    catch_panic(std::ptr::null_mut(), true, || {
+        matches!(
+            state.as_ref(),
+            None | Some(FerrostarTripState {
+                state: TripState::Complete
+            })
+        )
+    })
+}
+
+/// Writes the trip progress to `progress`.
+///
+/// Returns false (leaving `progress` untouched) unless the trip is in progress.
+///
+/// # Safety
+///
+/// `state` must be `NULL` or a valid trip state pointer,
+/// and `progress` must be `NULL` or writable.
+#[no_mangle]
+pub unsafe extern "C" fn ferrostar_trip_state_progress(
+    state: *const FerrostarTripState,
+    progress: *mut FerrostarTripProgress,
+) -> bool {
+    catch_panic(std::ptr::null_mut(), false, || {
+        let (
+            Some(FerrostarTripState {
+                state:
+                    TripState::Navigating {
+                        progress: trip_progress,
+                        ..
+                    },
+            }),
+            Some(progress),
+        ) = (state.as_ref(), progress.as_mut())
+        else {
+            return false;
+        };
+
+        *progress = FerrostarTripProgress {
+            distance_to_next_maneuver: trip_progress.distance_to_next_maneuver,
+            distance_remaining: trip_progress.distance_remaining,
+            duration_remaining: trip_progress.duration_remaining,
+        };
+        true
+    })
+}
+
+/// Returns whether the user is following the route.
+///
+/// This is always [`FerrostarRouteDeviation::NoDeviation`] unless the trip is in progress.
+///
+/// # Safety
+///
+/// `state` must be `NULL` or a valid trip state pointer.
+#[no_mangle]
+pub unsafe extern "C" fn ferrostar_trip_state_deviation(
+    state: *const FerrostarTripState,
+) -> FerrostarRouteDeviation {
+    catch_panic(
+        std::ptr::null_mut(),
+        FerrostarRouteDeviation::NoDeviation,
+        || match state.as_ref() {
+            Some(FerrostarTripState {
+                state: TripState::Navigating { deviation, .. },
+            }) => match deviation {
+                RouteDeviation::NoDeviation => FerrostarRouteDeviation::NoDeviation,
+                RouteDeviation::OffRoute { .. } => FerrostarRouteDeviation::OffRoute,
+                RouteDeviation::WrongDirection { .. } => FerrostarRouteDeviation::WrongDirection,
+            },
+            _ => FerrostarRouteDeviation::NoDeviation,
+        },
+    )
+}
+
+/// Returns the primary text of the current visual instruction, or `NULL` if there is none.
+///
+/// # Safety
+///
+/// `state` must be `NULL` or a valid trip state pointer.
+#[no_mangle]
+pub unsafe extern "C" fn ferrostar_trip_state_visual_instruction(
+    state: *const FerrostarTripState,
+) -> *mut c_char {
+    catch_panic(std::ptr::null_mut(), std::ptr::null_mut(), || {
+        match state.as_ref() {
+            Some(FerrostarTripState {
+                state:
+                    TripState::Navigating {
+                        visual_instruction: Some(instruction),
+                        ..
+                    },
+            }) => into_c_string(&instruction.primary_content.text),
+            _ => std::ptr::null_mut(),
+        }
+    })
+}
+
+/// Returns the text of the current spoken instruction, or `NULL` if there is none.
+///
+/// # Safety
+///
+/// `state` must be `NULL` or a valid trip state pointer.
+#[no_mangle]
+pub unsafe extern "C" fn ferrostar_trip_state_spoken_instruction(
+    state: *const FerrostarTripState,
+) -> *mut c_char {
+    catch_panic(std::ptr::null_mut(), std::ptr::null_mut(), || {
+        match state.as_ref() {
+            Some(FerrostarTripState {
+                state:
+                    TripState::Navigating {
+                        spoken_instruction: Some(instruction),
+                        ..
+                    },
+            }) => into_c_string(&instruction.text),
+            _ => std::ptr::null_mut(),
+        }
+    })
+}
+
+/// # Safety
+///
+/// `string` must be `NULL` or a string returned by this library which has not already been freed.
+#[no_mangle]
+pub unsafe extern "C" fn ferrostar_string_free(string: *mut c_char) {
+    catch_panic(std::ptr::null_mut(), (), || {
+        if !string.is_null() {
+            drop(CString::from_raw(string));
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CStr;
+    use std::ptr;
+
+    const OSRM_RESPONSE: &str = r#"{"code":"Ok","routes":[{"geometry":"qikdcB{~dpXmxRbaBuqAoqKyy@svFwNcfKzsAysMdr@evD`m@qrAohBi}A{OkdGjg@ajDZww@lJ}Jrs@}`CvzBq`E`PiB`~A|l@z@feA","legs":[{"steps":[{"geometry":"qikdcB{~dpXmxRbaBuqAoqKyy@svFwNcfKzsAysMdr@evD`m@qrAohBi}A{OkdGjg@ajDZww@lJ}Jrs@}`CvzBq`E`PiB`~A|l@z@feA","distance":4731.8,"duration":630.7,"name":"Friedrichstraße","mode":"driving","maneuver":{"location":[13.388798,52.517033],"bearing_before":0,"bearing_after":0,"type":"depart","instruction":"Drive north on Friedrichstraße."},"intersections":[]}],"summary":"","weight":633.6,"duration":630.7,"distance":4731.8}],"weight_name":"routability","weight":633.6,"duration":630.7,"distance":4731.8}],"waypoints":[{"name":"Friedrichstraße","location":[13.388798,52.517033]},{"name":"Platz der Vereinten Nationen","location":[13.428554,52.523239]}]}"#;
+
+    const CONFIG: FerrostarNavigationControllerConfig = FerrostarNavigationControllerConfig {
+        step_advance_distance: 10,
+        minimum_horizontal_accuracy: 25,
+        max_acceptable_deviation: 50.0,
+    };
+
+    fn location(lat: f64, lng: f64) -> FerrostarUserLocation {
+        FerrostarUserLocation {
+            lat,
+            lng,
+            horizontal_accuracy: 5.0,
+            course_degrees: -1,
+            course_accuracy: -1,
+            speed: -1.0,
+            speed_accuracy: -1.0,
+            timestamp_ms: 1_700_000_000_000,
+        }
+    }
+
+    #[test]
+    fn user_location_conversion() {
+        let location: UserLocation = FerrostarUserLocation {
+            course_degrees: 90,
+            speed: 12.5,
+            ..location(52.517033, 13.388798)
+        }
+        .into();
+
+        assert_eq!(
+            location.course_over_ground,
+            Some(CourseOverGround::new(90, None))
+        );
+        assert_eq!(
+            location.speed,
+            Some(Speed {
+                value: 12.5,
+                accuracy: None
+            })
+        );
+        assert_eq!(
+            location.timestamp,
+            SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000)
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        let mut status = FerrostarStatus::Ok;
+        let response = b"{}";
+        let routes = unsafe {
+            ferrostar_parse_osrm_response(response.as_ptr(), response.len(), 6, &mut status)
+        };
+        assert!(routes.is_null());
+        assert_eq!(status, FerrostarStatus::ParseError);
+
+        let routes = unsafe { ferrostar_parse_osrm_response(ptr::null(), 0, 6, &mut status) };
+        assert!(routes.is_null());
+        assert_eq!(status, FerrostarStatus::NullPointer);
+    }
+
+    #[test]
+    fn navigate_route() {
+        let mut status = FerrostarStatus::ParseError;
+        unsafe {
+            let routes = ferrostar_parse_osrm_response(
+                OSRM_RESPONSE.as_ptr(),
+                OSRM_RESPONSE.len(),
+                6,
+                &mut status,
+            );
+            assert_eq!(status, FerrostarStatus::Ok);
+            assert_eq!(ferrostar_routes_len(routes), 1);
+
+            let controller = ferrostar_navigation_controller_new(routes, 1, &CONFIG, &mut status);
+            assert!(controller.is_null());
+            assert_eq!(status, FerrostarStatus::IndexOutOfBounds);
+
+            let controller = ferrostar_navigation_controller_new(routes, 0, &CONFIG, &mut status);
+            assert_eq!(status, FerrostarStatus::Ok);
+            // The controller owns a copy of the route
+            ferrostar_routes_free(routes);
+
+            let state = ferrostar_navigation_controller_get_initial_state(
+                controller,
+                &location(52.517033, 13.388798),
+                ptr::null_mut(),
+            );
+            assert!(!ferrostar_trip_state_is_complete(state));
+            assert_eq!(
+                ferrostar_trip_state_deviation(state),
+                FerrostarRouteDeviation::NoDeviation
+            );
+
+            let mut progress = FerrostarTripProgress {
+                distance_to_next_maneuver: 0.0,
+                distance_remaining: 0.0,
+                duration_remaining: 0.0,
+            };
+            assert!(ferrostar_trip_state_progress(state, &mut progress));
+            assert!(progress.distance_remaining > 0.0);
+
+            let instruction = ferrostar_trip_state_visual_instruction(state);
+            assert!(instruction.is_null());
+            ferrostar_string_free(instruction);
+
+            let next_state = ferrostar_navigation_controller_update_user_location(
+                controller,
+                &location(52.6, 13.5),
+                state,
+                &mut status,
+            );
+            assert_eq!(status, FerrostarStatus::Ok);
+            assert_eq!(
+                ferrostar_trip_state_deviation(next_state),
+                FerrostarRouteDeviation::OffRoute
+            );
+            ferrostar_trip_state_free(state);
+
+            let final_state = ferrostar_navigation_controller_advance_to_next_step(
+                controller,
+                next_state,
+                &mut status,
+            );
+            assert_eq!(status, FerrostarStatus::Ok);
+            assert!(ferrostar_trip_state_is_complete(final_state));
+            assert!(!ferrostar_trip_state_progress(final_state, &mut progress));
+
+            ferrostar_trip_state_free(next_state);
+            ferrostar_trip_state_free(final_state);
+            ferrostar_navigation_controller_free(controller);
+        }
+    }
+
+    #[test]
+    fn panics_are_caught() {
+        let mut status = FerrostarStatus::Ok;
+        let value = unsafe { catch_panic(&mut status, 0, || panic!("Test panic")) };
+        assert_eq!(value, 0);
+        assert_eq!(status, FerrostarStatus::InternalError);
+
+        let value = unsafe { catch_panic(ptr::null_mut(), 0, || 1) };
+        assert_eq!(value, 1);
+    }
+
+    #[test]
+    fn c_strings() {
+        let string = into_c_string("Turn left onto Friedrichstraße");
+        assert_eq!(
+            unsafe { CStr::from_ptr(string) }.to_str(),
+            Ok("Turn left onto Friedrichstraße")
+        );
+        unsafe { ferrostar_string_free(string) };
+
+        assert!(into_c_string("nul\0byte").is_null());
+    }
+}
@@ -0,0 +1,42 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use ferrostar::algorithms::decode_polyline;
+use geo::{Coord, LineString};
+
+/// Encodes a winding line with the given number of points, roughly 10m apart.
+fn encoded_line(points: usize) -> String {
+    let line: LineString = (0..points)
+        .map(|index| {
+            let t = index as f64;
+            Coord {
+                x: -149.5 + t * 0.0001,
+                y: 60.5 + (t / 50.0).sin() * 0.01,
+            }
+        })
+        .collect();
+    polyline::encode_coordinates(line, 6).expect("Unable to encode polyline")
+}
+
+fn decode(c: &mut Criterion) {
+    let mut group = c.benchmark_group("decode_polyline6");
+    for points in [100, 1_000, 10_000] {
+        let encoded = encoded_line(points);
+        group.bench_with_input(
+            BenchmarkId::new("ferrostar", points),
+            &encoded,
+            |b, encoded| {
+                b.iter(|| decode_polyline(black_box(encoded), 6));
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("polyline", points),
+            &encoded,
+            |b, encoded| {
+                b.iter(|| polyline::decode_polyline(black_box(encoded), 6));
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, decode);
+criterion_main!(benches);
@@ -0,0 +1,61 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use ferrostar::algorithms::{
+    snap_user_location_to_line, snap_user_location_to_line_with_course, DistanceMeasurement,
+};
+use ferrostar::models::{CourseOverGround, GeographicCoordinate, UserLocation};
+use geo::{Coord, LineString};
+use std::time::SystemTime;
+
+/// A winding line with the given number of points, roughly 10m apart.
+fn line(points: usize) -> LineString {
+    (0..points)
+        .map(|index| {
+            let t = index as f64;
+            Coord {
+                x: -149.5 + t * 0.0001,
+                y: 60.5 + (t / 50.0).sin() * 0.01,
+            }
+        })
+        .collect()
+}
+
+/// A location just off the middle of the line.
+fn location(line: &LineString) -> UserLocation {
+    let middle = line.0[line.0.len() / 2];
+    UserLocation {
+        coordinates: GeographicCoordinate {
+            lat: middle.y + 0.0001,
+            lng: middle.x,
+        },
+        horizontal_accuracy: 5.0,
+        course_over_ground: Some(CourseOverGround::new(90, Some(10))),
+        timestamp: SystemTime::now(),
+        speed: None,
+        altitude: None,
+        vertical_accuracy: None,
+    }
+}
+
+fn snap(c: &mut Criterion) {
+    let mut group = c.benchmark_group("snap_user_location");
+    for points in [100, 1_000, 10_000] {
+        let line = line(points);
+        let location = location(&line);
+        group.bench_with_input(BenchmarkId::new("nearest", points), &line, |b, line| {
+            b.iter(|| snap_user_location_to_line(black_box(location), line));
+        });
+        group.bench_with_input(BenchmarkId::new("with_course", points), &line, |b, line| {
+            b.iter(|| {
+                snap_user_location_to_line_with_course(
+                    black_box(location),
+                    line,
+                    DistanceMeasurement::Haversine,
+                )
+            });
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, snap);
+criterion_main!(benches);
@@ -1,10 +1,10 @@
 use crate::{
-    models::{GeographicCoordinate, RouteStep, UserLocation},
+    models::{CourseOverGround, GeographicCoordinate, RouteStep, UserLocation},
     navigation_controller::models::TripProgress,
 };
 use geo::{
-    Closest, ClosestPoint, EuclideanDistance, HaversineDistance, HaversineLength, LineLocatePoint,
-    LineString, Point,
+    Closest, ClosestPoint, Coord, EuclideanDistance, GeodesicBearing, GeodesicDistance,
+    GeodesicLength, HaversineDistance, HaversineLength, LineLocatePoint, LineString, Point,
 };
 
 use crate::navigation_controller::models::{
@@ -20,6 +20,62 @@ use {
     std::time::SystemTime,
 };
 
+/// The mean radius of the earth, in meters (the same one used by the haversine formula).
+pub(crate) const MEAN_EARTH_RADIUS: f64 = 6_371_008.8;
+
+/// How distances between coordinates are measured.
+///
+/// Every distance computed by the navigation controller
+/// (distance to the next maneuver, deviation from the route, distance to the destination, etc.)
+/// uses the same measurement, so that thresholds are consistent with one another.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, uniffi::Enum)]
+pub enum DistanceMeasurement {
+    /// Great circle distances on a spherical earth.
+    ///
+    /// The error is at most about 0.5%, which is usually well within GPS accuracy.
+    Haversine,
+    /// Geodesic distances on the WGS84 ellipsoid.
+    ///
+    /// This is the most accurate option (particularly at high latitudes),
+    /// but it is several times slower than haversine.
+    Geodesic,
+    /// Distances on a plane tangent to the earth at the midpoint of the two coordinates
+    /// (an equirectangular approximation).
+    ///
+    /// This is the fastest option.
+    /// It is accurate for short distances (such as route step segments),
+    /// but the error grows with distance and close to the poles.
+    Planar,
+}
+
+impl DistanceMeasurement {
+    /// Measures the distance between two points, in meters.
+    pub fn distance(self, a: &Point, b: &Point) -> f64 {
+        match self {
+            DistanceMeasurement::Haversine => a.haversine_distance(b),
+            DistanceMeasurement::Geodesic => a.geodesic_distance(b),
+            DistanceMeasurement::Planar => {
+                let mean_latitude = ((a.y() + b.y()) / 2.0).to_radians();
+                let dx = (b.x() - a.x()).to_radians() * mean_latitude.cos();
+                let dy = (b.y() - a.y()).to_radians();
+                MEAN_EARTH_RADIUS * dx.hypot(dy)
+            }
+        }
+    }
+
+    /// Measures the length of a line, in meters.
+    pub fn length(self, line: &LineString) -> f64 {
+        match self {
+            DistanceMeasurement::Haversine => line.haversine_length(),
+            DistanceMeasurement::Geodesic => line.geodesic_length(),
+            DistanceMeasurement::Planar => line
+                .lines()
+                .map(|segment| self.distance(&segment.start_point(), &segment.end_point()))
+                .sum(),
+        }
+    }
+}
+
 /// Snaps a user location to the closest point on a route line.
 pub fn snap_user_location_to_line(location: UserLocation, line: &LineString) -> UserLocation {
     let original_point = Point::from(location);
@@ -36,6 +92,266 @@ pub fn snap_user_location_to_line(location: UserLocation, line: &LineString) ->
     )
 }
 
+/// Snaps a user location to a route line, using the altitude to choose between stacked segments.
+///
+/// Where a line passes over or under itself (ex: at an interchange),
+/// several segments may be about equally close to the user horizontally.
+/// Segments within `tolerance` meters of the closest one are treated as candidates,
+/// and the one whose road elevation best matches the user's altitude wins.
+/// `elevation_at` gives the road elevation at a distance (in meters) along the line.
+/// Differences within the vertical accuracy of the location are not significant,
+/// in which case the candidate which best matches the user's course wins if `use_course` is set
+/// (see [`snap_user_location_to_line_with_course`]), and the horizontally closest one otherwise.
+///
+/// Without an altitude or road elevations, this is the same as [`snap_user_location_to_line`]
+/// (or [`snap_user_location_to_line_with_course`] if `use_course` is set).
+pub fn snap_user_location_to_stacked_line(
+    location: UserLocation,
+    line: &LineString,
+    tolerance: f64,
+    use_course: bool,
+    elevation_at: impl Fn(f64) -> Option<f64>,
+    measurement: DistanceMeasurement,
+) -> UserLocation {
+    let fallback = |location| {
+        if use_course {
+            snap_user_location_to_line_with_course(location, line, measurement)
+        } else {
+            snap_user_location_to_line(location, line)
+        }
+    };
+    let Some(altitude) = location.altitude else {
+        return fallback(location);
+    };
+    let original_point = Point::from(location);
+    let vertical_accuracy = location
+        .vertical_accuracy
+        .filter(|accuracy| *accuracy > 0.0);
+    let course = location.course_over_ground.filter(|_| use_course);
+
+    // (horizontal distance, snapped point, elevation difference, heading difference)
+    let candidates: Vec<_> = segment_candidates(&original_point, line, measurement)
+        .into_iter()
+        .filter_map(|candidate| {
+            let elevation = elevation_at(candidate.distance_along_line)?;
+            let elevation_difference = (elevation - altitude).abs();
+            Some((
+                candidate.distance,
+                candidate.snapped,
+                // Differences within the vertical accuracy are indistinguishable
+                vertical_accuracy.map_or(elevation_difference, |accuracy| {
+                    (elevation_difference / accuracy).floor()
+                }),
+                course.map_or(0.0, |course| heading_difference(&candidate, course)),
+            ))
+        })
+        .collect();
+
+    let Some(closest_distance) = candidates
+        .iter()
+        .map(|(distance, _, _, _)| *distance)
+        .min_by(f64::total_cmp)
+    else {
+        return fallback(location);
+    };
+
+    candidates
+        .into_iter()
+        .filter(|(distance, _, _, _)| *distance <= closest_distance + tolerance)
+        .min_by(|a, b| {
+            a.2.total_cmp(&b.2)
+                .then(a.3.total_cmp(&b.3))
+                .then(a.0.total_cmp(&b.0))
+        })
+        .map_or_else(
+            || fallback(location),
+            |(_, snapped, _, _)| UserLocation {
+                coordinates: GeographicCoordinate {
+                    lng: snapped.x(),
+                    lat: snapped.y(),
+                },
+                ..location
+            },
+        )
+}
+
+/// Snaps a user location to a route line, using the course over ground to choose between parallel segments.
+///
+/// Where a line runs alongside itself (ex: a frontage road next to a highway, or a there-and-back route),
+/// the geometrically closest segment is frequently the wrong one.
+/// Segments within the horizontal accuracy of the closest one are treated as candidates,
+/// and the one whose bearing best matches the user's course wins.
+/// Differences within the course accuracy are not significant,
+/// in which case the horizontally closest candidate wins.
+///
+/// Without a course, this is the same as [`snap_user_location_to_line`].
+pub fn snap_user_location_to_line_with_course(
+    location: UserLocation,
+    line: &LineString,
+    measurement: DistanceMeasurement,
+) -> UserLocation {
+    let Some(course) = location.course_over_ground else {
+        return snap_user_location_to_line(location, line);
+    };
+    let original_point = Point::from(location);
+
+    let candidates = segment_candidates(&original_point, line, measurement);
+    let Some(closest_distance) = candidates
+        .iter()
+        .map(|candidate| candidate.distance)
+        .min_by(f64::total_cmp)
+    else {
+        return snap_user_location_to_line(location, line);
+    };
+    let tolerance = location.horizontal_accuracy.max(0.0);
+
+    candidates
+        .into_iter()
+        .filter(|candidate| candidate.distance <= closest_distance + tolerance)
+        .map(|candidate| {
+            let difference = heading_difference(&candidate, course);
+            (candidate, difference)
+        })
+        .min_by(|(a, a_difference), (b, b_difference)| {
+            a_difference
+                .total_cmp(b_difference)
+                .then(a.distance.total_cmp(&b.distance))
+        })
+        .map_or_else(
+            || snap_user_location_to_line(location, line),
+            |(candidate, _)| UserLocation {
+                coordinates: GeographicCoordinate {
+                    lng: candidate.snapped.x(),
+                    lat: candidate.snapped.y(),
+                },
+                ..location
+            },
+        )
+}
+
+/// Snaps a user location to a route line, keeping it consistent with the previous position along the line.
+///
+/// This is a simple form of map matching, constrained to the route line.
+/// Where a line passes close to itself (ex: a hairpin, or a loop around a block),
+/// the geometrically closest segment is frequently not the one the user is traveling on.
+/// Segments within the horizontal accuracy of the closest one are treated as candidates,
+/// and the one whose distance along the line is closest to where the user is expected to be wins.
+/// The expected position is `previous_distance_along_line` plus the distance traveled since the
+/// `previous_location` (from the speed and elapsed time if known, or the straight line distance otherwise).
+pub fn snap_user_location_to_line_with_history(
+    location: UserLocation,
+    line: &LineString,
+    previous_location: &UserLocation,
+    previous_distance_along_line: f64,
+    measurement: DistanceMeasurement,
+) -> UserLocation {
+    let original_point = Point::from(location);
+    let elapsed = location
+        .timestamp
+        .duration_since(previous_location.timestamp)
+        .map_or(0.0, |duration| duration.as_secs_f64());
+    let distance_traveled = location.speed.map_or_else(
+        || measurement.distance(&Point::from(*previous_location), &original_point),
+        |speed| speed.value.max(0.0) * elapsed,
+    );
+    let expected_distance_along_line = previous_distance_along_line + distance_traveled;
+
+    let candidates = segment_candidates(&original_point, line, measurement);
+    let Some(closest_distance) = candidates
+        .iter()
+        .map(|candidate| candidate.distance)
+        .min_by(f64::total_cmp)
+    else {
+        return snap_user_location_to_line(location, line);
+    };
+    let tolerance = location.horizontal_accuracy.max(0.0);
+
+    candidates
+        .into_iter()
+        .filter(|candidate| candidate.distance <= closest_distance + tolerance)
+        .min_by(|a, b| {
+            let a_difference = (a.distance_along_line - expected_distance_along_line).abs();
+            let b_difference = (b.distance_along_line - expected_distance_along_line).abs();
+            a_difference
+                .total_cmp(&b_difference)
+                .then(a.distance.total_cmp(&b.distance))
+        })
+        .map_or_else(
+            || snap_user_location_to_line(location, line),
+            |candidate| UserLocation {
+                coordinates: GeographicCoordinate {
+                    lng: candidate.snapped.x(),
+                    lat: candidate.snapped.y(),
+                },
+                ..location
+            },
+        )
+}
+
+/// The difference between a segment's bearing and the user's course, for ranking candidates.
+///
+/// Differences within the course accuracy are indistinguishable, so they are rounded down to whole multiples of it.
+fn heading_difference(candidate: &SegmentCandidate, course: CourseOverGround) -> f64 {
+    // Degenerate segments have no bearing, so they never match
+    let difference = candidate.bearing.map_or(180.0, |bearing| {
+        angular_difference(bearing, f64::from(course.degrees))
+    });
+    course
+        .accuracy
+        .filter(|accuracy| *accuracy > 0)
+        .map_or(difference, |accuracy| {
+            (difference / f64::from(accuracy)).floor()
+        })
+}
+
+/// The closest point to a location on one segment of a line.
+struct SegmentCandidate {
+    /// The index of the segment (which is also the index of its first vertex).
+    segment_index: usize,
+    snapped: Point,
+    /// The distance from the location to the snapped point, in meters.
+    distance: f64,
+    /// The distance of the snapped point along the line, in meters.
+    distance_along_line: f64,
+    /// The bearing of the segment, in degrees, or `None` if the segment has no length.
+    bearing: Option<f64>,
+}
+
+/// Snaps a point to each segment of a line.
+fn segment_candidates(
+    point: &Point,
+    line: &LineString,
+    measurement: DistanceMeasurement,
+) -> Vec<SegmentCandidate> {
+    let mut candidates = Vec::new();
+    let mut distance_along_line = 0.0;
+    for (segment_index, segment) in line.lines().enumerate() {
+        let segment_start = Point::from(segment.start);
+        let segment_end = Point::from(segment.end);
+        let segment_length = measurement.distance(&segment_start, &segment_end);
+        let segment_line = LineString::new(vec![segment.start, segment.end]);
+        if let Some(snapped) = snap_point_to_line(point, &segment_line) {
+            candidates.push(SegmentCandidate {
+                segment_index,
+                snapped,
+                distance: measurement.distance(&snapped, point),
+                distance_along_line: distance_along_line
+                    + measurement.distance(&segment_start, &snapped),
+                bearing: (segment_length > 0.0)
+                    .then(|| segment_start.geodesic_bearing(segment_end)),
+            });
+        }
+        distance_along_line += segment_length;
+    }
+    candidates
+}
+
+/// The smallest angle between two bearings, in degrees.
+fn angular_difference(a: f64, b: f64) -> f64 {
+    let difference = (a - b).rem_euclid(360.0);
+    difference.min(360.0 - difference)
+}
+
 /// Internal function that truncates a float to 6 digits.
 ///
 /// Note that this approach is not a substitute for fixed precision decimals,
@@ -83,9 +399,13 @@ fn snap_point_to_line(point: &Point, line: &LineString) -> Option<Point> {
     }
 }
 
-pub fn deviation_from_line(point: &Point, line: &LineString) -> Option<f64> {
+pub fn deviation_from_line(
+    point: &Point,
+    line: &LineString,
+    measurement: DistanceMeasurement,
+) -> Option<f64> {
     snap_point_to_line(point, line).and_then(|snapped| {
-        let distance = snapped.haversine_distance(point);
+        let distance = measurement.distance(&snapped, point);
 
         if distance.is_nan() || distance.is_infinite() {
             None
@@ -99,10 +419,11 @@ fn is_close_enough_to_end_of_linestring(
     current_position: &Point,
     current_step_linestring: &LineString,
     threshold: f64,
+    measurement: DistanceMeasurement,
 ) -> bool {
     if let Some(end_coord) = current_step_linestring.coords().last() {
         let end_point = Point::from(*end_coord);
-        let distance_to_end = end_point.haversine_distance(current_position);
+        let distance_to_end = measurement.distance(&end_point, current_position);
 
         distance_to_end <= threshold
     } else {
@@ -119,6 +440,7 @@ pub fn should_advance_to_next_step(
     next_route_step: Option<&RouteStep>,
     user_location: &UserLocation,
     step_advance_mode: StepAdvanceMode,
+    measurement: DistanceMeasurement,
 ) -> bool {
     let current_position = Point::from(user_location.coordinates);
 
@@ -135,6 +457,7 @@ pub fn should_advance_to_next_step(
                     &current_position,
                     current_step_linestring,
                     f64::from(distance),
+                    measurement,
                 )
             }
         }
@@ -151,6 +474,7 @@ pub fn should_advance_to_next_step(
                         &current_position,
                         current_step_linestring,
                         f64::from(distance),
+                        measurement,
                     ) {
                         return true;
                     }
@@ -169,8 +493,8 @@ pub fn should_advance_to_next_step(
                         // If the user's distance to the snapped location on the *next* step is <=
                         // the user's distance to the snapped location on the *current* step,
                         // advance to the next step
-                        current_position.haversine_distance(&next_step_closest_point)
-                            <= current_position.haversine_distance(&current_step_closest_point)
+                        measurement.distance(&current_position, &next_step_closest_point)
+                            <= measurement.distance(&current_position, &current_step_closest_point)
                     } else {
                         // The user's location couldn't be mapped to a single point on both the current and next step.
                         // Fall back to the distance to end of step mode, which has some graceful fallbacks.
@@ -183,6 +507,7 @@ pub fn should_advance_to_next_step(
                                 distance: minimum_horizontal_accuracy,
                                 minimum_horizontal_accuracy,
                             },
+                            measurement,
                         )
                     }
                 } else {
@@ -195,11 +520,168 @@ pub fn should_advance_to_next_step(
                             distance: minimum_horizontal_accuracy,
                             minimum_horizontal_accuracy,
                         },
+                        measurement,
                     )
                 }
             }
         }
+        StepAdvanceMode::ManeuverGeometryIndex {
+            minimum_horizontal_accuracy,
+        } => {
+            if user_location.horizontal_accuracy > minimum_horizontal_accuracy.into() {
+                false
+            } else if let Some(next_step) = next_route_step {
+                has_passed_maneuver(
+                    &current_position,
+                    current_step_linestring,
+                    &next_step.get_linestring(),
+                    measurement,
+                )
+            } else {
+                // There is no maneuver point to pass at the end of the route,
+                // so trigger arrival when the user gets within a circle of the minimum horizontal accuracy
+                should_advance_to_next_step(
+                    current_step_linestring,
+                    None,
+                    user_location,
+                    StepAdvanceMode::DistanceToEndOfStep {
+                        distance: minimum_horizontal_accuracy,
+                        minimum_horizontal_accuracy,
+                    },
+                    measurement,
+                )
+            }
+        }
+        StepAdvanceMode::ManeuverConfirmation {
+            minimum_horizontal_accuracy,
+            maximum_course_difference,
+        } => {
+            if let (Some(next_step), Some(course)) =
+                (next_route_step, user_location.course_over_ground)
+            {
+                let is_heading_elsewhere = initial_bearing(&next_step.get_linestring())
+                    .is_some_and(|bearing| {
+                        angular_difference(f64::from(course.degrees), bearing)
+                            > f64::from(maximum_course_difference)
+                    });
+                if is_heading_elsewhere {
+                    return false;
+                }
+            }
+
+            should_advance_to_next_step(
+                current_step_linestring,
+                next_route_step,
+                user_location,
+                StepAdvanceMode::ManeuverGeometryIndex {
+                    minimum_horizontal_accuracy,
+                },
+                measurement,
+            )
+        }
+    }
+}
+
+/// Determines whether the user has fallen back behind the end of the previous route step
+/// (ex: after a step advance, due to noisy positions while waiting at a corner).
+///
+/// This is the reverse of [`should_advance_to_next_step`]:
+/// the user must be closer to the previous step than the current one,
+/// and must no longer meet the step advance condition for the previous step.
+/// Steps never regress in [`StepAdvanceMode::Manual`],
+/// or when the location is too inaccurate to trigger a step advance.
+///
+/// NOTE: The [`UserLocation`] should *not* be snapped.
+pub(crate) fn should_regress_to_previous_step(
+    previous_step_linestring: &LineString,
+    current_route_step: &RouteStep,
+    user_location: &UserLocation,
+    step_advance_mode: StepAdvanceMode,
+    measurement: DistanceMeasurement,
+) -> bool {
+    let (StepAdvanceMode::DistanceToEndOfStep {
+        minimum_horizontal_accuracy,
+        ..
+    }
+    | StepAdvanceMode::RelativeLineStringDistance {
+        minimum_horizontal_accuracy,
+        ..
+    }
+    | StepAdvanceMode::ManeuverGeometryIndex {
+        minimum_horizontal_accuracy,
     }
+    | StepAdvanceMode::ManeuverConfirmation {
+        minimum_horizontal_accuracy,
+        ..
+    }) = step_advance_mode
+    else {
+        return false;
+    };
+    if user_location.horizontal_accuracy > minimum_horizontal_accuracy.into() {
+        return false;
+    }
+    // The course only confirms that a maneuver was made; moving back is judged by position alone
+    let step_advance_mode = match step_advance_mode {
+        StepAdvanceMode::ManeuverConfirmation { .. } => StepAdvanceMode::ManeuverGeometryIndex {
+            minimum_horizontal_accuracy,
+        },
+        mode => mode,
+    };
+
+    let current_position = Point::from(user_location.coordinates);
+    let is_closer_to_previous_step = match (
+        deviation_from_line(&current_position, previous_step_linestring, measurement),
+        deviation_from_line(
+            &current_position,
+            &current_route_step.get_linestring(),
+            measurement,
+        ),
+    ) {
+        (Some(previous_deviation), Some(current_deviation)) => {
+            previous_deviation < current_deviation
+        }
+        _ => false,
+    };
+
+    is_closer_to_previous_step
+        && !should_advance_to_next_step(
+            previous_step_linestring,
+            Some(current_route_step),
+            user_location,
+            step_advance_mode,
+            measurement,
+        )
+}
+
+/// The bearing of the first segment of a line which has a nonzero length.
+fn initial_bearing(linestring: &LineString) -> Option<f64> {
+    linestring
+        .lines()
+        .find(|line| line.start != line.end)
+        .map(|line| Point::from(line.start).geodesic_bearing(Point::from(line.end)))
+}
+
+/// Determines whether a point is past the maneuver point at the end of the current step.
+///
+/// The point is matched to the closest segment of the current and next step geometries (combined).
+/// Ties go to the earlier segment, so a point exactly at the maneuver point has not passed it.
+fn has_passed_maneuver(
+    point: &Point,
+    current_step_linestring: &LineString,
+    next_step_linestring: &LineString,
+    measurement: DistanceMeasurement,
+) -> bool {
+    let maneuver_index = current_step_linestring.0.len().saturating_sub(1);
+    let combined_linestring: LineString = current_step_linestring
+        .coords()
+        .chain(next_step_linestring.coords())
+        .copied()
+        .collect();
+
+    segment_candidates(point, &combined_linestring, measurement)
+        .into_iter()
+        .min_by(|a, b| a.distance.total_cmp(&b.distance))
+        .is_some_and(|candidate| candidate.segment_index >= maneuver_index)
 }
 
 /// Runs a state machine transformation to advance one step.
@@ -223,8 +705,12 @@ pub(crate) fn advance_step(remaining_steps: &[RouteStep]) -> StepAdvanceStatus {
 /// assuming that units are latitude and longitude for the geometries.
 ///
 /// The result is given in meters.
-fn distance_along(point: &Point, linestring: &LineString) -> Option<f64> {
-    let total_length = linestring.haversine_length();
+fn distance_along(
+    point: &Point,
+    linestring: &LineString,
+    measurement: DistanceMeasurement,
+) -> Option<f64> {
+    let total_length = measurement.length(linestring);
     if total_length == 0.0 {
         return Some(0.0);
     }
@@ -232,14 +718,11 @@ fn distance_along(point: &Point, linestring: &LineString) -> Option<f64> {
     let (_, _, traversed) = linestring.lines().try_fold(
         (0f64, f64::INFINITY, 06f64),
         |(cum_length, closest_dist_to_point, traversed), segment| {
-            // Convert to a LineString so we get haversine ops
-            let segment_linestring = LineString::from(segment);
-
             // Compute distance to the line (sadly Euclidean only; no haversine_distance in GeoRust
             // but this is probably OK for now)
             let segment_distance_to_point = segment.euclidean_distance(point);
             // Compute total segment length in meters
-            let segment_length = segment_linestring.haversine_length();
+            let segment_length = measurement.distance(&segment.start_point(), &segment.end_point());
 
             if segment_distance_to_point < closest_dist_to_point {
                 let segment_fraction = segment.line_locate_point(point)?; // if any segment has a None fraction, return None
@@ -262,15 +745,70 @@ fn distance_along(point: &Point, linestring: &LineString) -> Option<f64> {
 
 /// Computes the distance between a location and the end of the current route step.
 /// We assume that input location is pre-snapped to route step's linestring.
-fn distance_to_end_of_step(snapped_location: &Point, current_step_linestring: &LineString) -> f64 {
-    let step_length = current_step_linestring.haversine_length();
-    if let Some(traversed) = distance_along(snapped_location, current_step_linestring) {
+fn distance_to_end_of_step(
+    snapped_location: &Point,
+    current_step_linestring: &LineString,
+    measurement: DistanceMeasurement,
+) -> f64 {
+    let step_length = measurement.length(current_step_linestring);
+    if let Some(traversed) = distance_along(snapped_location, current_step_linestring, measurement)
+    {
         step_length - traversed
     } else {
         0.0
     }
 }
 
+/// Drops the start of the step geometry which is more than `rewind_distance` meters
+/// behind a location snapped to it.
+///
+/// The distance and duration of the step are reduced in proportion to the geometry dropped,
+/// so that the trip progress along the rest of the step is unchanged.
+pub(crate) fn trim_traveled_geometry(
+    step: &mut RouteStep,
+    snapped_location: &Point,
+    rewind_distance: f64,
+    measurement: DistanceMeasurement,
+) {
+    let linestring = step.get_linestring();
+    let Some(traversed) = distance_along(snapped_location, &linestring, measurement) else {
+        return;
+    };
+    let trim_distance = traversed - rewind_distance.max(0.0);
+    let segment_lengths: Vec<f64> = linestring
+        .lines()
+        .map(|segment| measurement.distance(&segment.start_point(), &segment.end_point()))
+        .collect();
+
+    // Find the last vertex which is at least the rewind distance behind the location,
+    // always keeping at least one segment
+    let mut trimmed_length = 0.0;
+    let mut first_kept_index = 0;
+    for &segment_length in segment_lengths
+        .iter()
+        .take(segment_lengths.len().saturating_sub(1))
+    {
+        if trimmed_length + segment_length > trim_distance {
+            break;
+        }
+        trimmed_length += segment_length;
+        first_kept_index += 1;
+    }
+    if first_kept_index == 0 {
+        return;
+    }
+
+    let step_length: f64 = segment_lengths.iter().sum();
+    let remaining_fraction = if step_length > 0.0 {
+        (step_length - trimmed_length) / step_length
+    } else {
+        1.0
+    };
+    step.geometry.drain(..first_kept_index);
+    step.distance *= remaining_fraction;
+    step.duration *= remaining_fraction;
+}
+
 /// Computes the arrival state for a snapped location along the route.
 /// This includes distances and durations.
 pub fn calculate_trip_progress(
@@ -278,23 +816,30 @@ pub fn calculate_trip_progress(
     current_step: &RouteStep,
     current_step_linestring: &LineString,
     remaining_steps: &[RouteStep],
+    measurement: DistanceMeasurement,
 ) -> TripProgress {
     if remaining_steps.is_empty() {
         return TripProgress {
             distance_to_next_maneuver: 0.0,
             distance_remaining: 0.0,
             duration_remaining: 0.0,
+            consumption: None,
         };
     }
 
     // Calculate the distance and duration till the end of the current route step.
     let distance_to_next_maneuver =
-        distance_to_end_of_step(snapped_location, current_step_linestring);
+        distance_to_end_of_step(snapped_location, current_step_linestring, measurement);
 
     // This could be improved with live traffic data along the route.
     // TODO: Figure out the best way to enable this use case
-    let pct_remaining_current_step =
-        distance_to_next_maneuver / current_step_linestring.haversine_length();
+    let current_step_length = measurement.length(current_step_linestring);
+    let pct_remaining_current_step = if current_step_length > 0.0 {
+        distance_to_next_maneuver / current_step_length
+    } else {
+        // Zero length steps (ex: arrival) have nothing remaining
+        0.0
+    };
 
     // Get the percentage of duration remaining in the current step.
     let duration_to_next_maneuver = pct_remaining_current_step * current_step.duration;
@@ -305,6 +850,7 @@ pub fn calculate_trip_progress(
             distance_to_next_maneuver,
             distance_remaining: distance_to_next_maneuver,
             duration_remaining: duration_to_next_maneuver,
+            consumption: None,
         };
     }
 
@@ -325,11 +871,111 @@ pub fn calculate_trip_progress(
         distance_to_next_maneuver,
         distance_remaining,
         duration_remaining,
+        consumption: None,
+    }
+}
+
+/// The highest polyline precision which is supported.
+///
+/// Higher precisions overflow the decoder (and are far beyond the accuracy of any backend).
+const MAX_POLYLINE_PRECISION: u32 = 9;
+
+/// The most characters that a single value in a polyline may use.
+///
+/// Even at the highest supported precision, a coordinate delta spanning the globe fits in 8.
+const MAX_POLYLINE_VALUE_LENGTH: u32 = 8;
+
+/// Decodes an [encoded polyline](https://developers.google.com/maps/documentation/utilities/polylinealgorithm).
+///
+/// The result is identical to [`polyline::decode_polyline`],
+/// but this is considerably faster for long geometries, which are decoded on every reroute.
+/// Malformed input is always rejected with an error rather than a panic,
+/// as polylines usually come straight from the network.
+pub fn decode_polyline(polyline: &str, precision: u32) -> Result<LineString, String> {
+    if precision > MAX_POLYLINE_PRECISION {
+        return Err(format!("unsupported polyline precision {precision}"));
+    }
+    let factor = f64::from(10_u32.pow(precision));
+
+    let bytes = polyline.as_bytes();
+    // Every coordinate takes at least two characters
+    let mut coords = Vec::with_capacity(bytes.len() / 2);
+    let (mut lat, mut lng) = (0_i64, 0_i64);
+    let mut position = 0;
+    while position < bytes.len() {
+        let start = position;
+        let overflow = || format!("coordinate out of range at position {start}");
+        lat = lat
+            .checked_add(decode_polyline_value(bytes, &mut position)?)
+            .ok_or_else(overflow)?;
+        lng = lng
+            .checked_add(decode_polyline_value(bytes, &mut position)?)
+            .ok_or_else(overflow)?;
+        coords.push(Coord {
+            x: lng as f64 / factor,
+            y: lat as f64 / factor,
+        });
+    }
+
+    Ok(LineString::new(coords))
+}
+
+/// Decodes the polyline value starting at `position`, advancing past it.
+fn decode_polyline_value(bytes: &[u8], position: &mut usize) -> Result<i64, String> {
+    let mut result = 0_i64;
+    for chunk_index in 0..MAX_POLYLINE_VALUE_LENGTH {
+        let Some(&byte) = bytes.get(*position) else {
+            return Err("truncated polyline".to_string());
+        };
+        if !(63..=126).contains(&byte) {
+            return Err(format!("invalid character at position {position}"));
+        }
+        *position += 1;
+
+        // Each character holds 5 bits of the value; the 6th bit is set on all but the last
+        let chunk = byte - 63;
+        result |= i64::from(chunk & 0x1f) << (chunk_index * 5);
+        if chunk < 0x20 {
+            // The sign is stored in the lowest bit
+            return Ok(if result & 1 == 1 {
+                !(result >> 1)
+            } else {
+                result >> 1
+            });
+        }
     }
+
+    Err(format!("value too long at position {position}"))
 }
 
 #[cfg(test)]
 proptest! {
+    #[test]
+    fn decode_polyline_matches_reference(
+        coordinates in prop::collection::vec((-90f64..=90.0, -180f64..=180.0), 0..100),
+        precision in 0u32..=7,
+    ) {
+        let line: LineString = coordinates
+            .into_iter()
+            .map(|(lat, lng)| Coord { x: lng, y: lat })
+            .collect();
+        let encoded = polyline::encode_coordinates(line, precision).expect("Unable to encode");
+        prop_assert_eq!(
+            decode_polyline(&encoded, precision),
+            polyline::decode_polyline(&encoded, precision)
+        );
+    }
+
+    #[test]
+    fn arbitrary_polylines_do_not_panic(polyline: String, precision in 0u32..16) {
+        let _ = decode_polyline(&polyline, precision);
+    }
+
+    #[test]
+    fn polyline_alphabet_does_not_panic(polyline in "[?-~]{0,64}", precision in 0u32..=9) {
+        let _ = decode_polyline(&polyline, precision);
+    }
+
     #[test]
     fn snap_point_to_line_intersection(
         x1: f64, y1: f64,
@@ -391,7 +1037,9 @@ proptest! {
                 horizontal_accuracy: 0.0,
                 course_over_ground: None,
                 timestamp: SystemTime::now(),
-                speed: None
+                speed: None,
+                altitude: None,
+                vertical_accuracy: None,
             };
 
             let inaccurate_user_location = UserLocation {
@@ -400,30 +1048,30 @@ proptest! {
             };
 
             // Never advance to the next step when StepAdvanceMode is Manual
-            prop_assert!(!should_advance_to_next_step(&current_route_step.get_linestring(), next_route_step.as_ref(), &exact_user_location, StepAdvanceMode::Manual));
-            prop_assert!(!should_advance_to_next_step(&current_route_step.get_linestring(), next_route_step.as_ref(), &inaccurate_user_location, StepAdvanceMode::Manual));
+            prop_assert!(!should_advance_to_next_step(&current_route_step.get_linestring(), next_route_step.as_ref(), &exact_user_location, StepAdvanceMode::Manual, DistanceMeasurement::Haversine));
+            prop_assert!(!should_advance_to_next_step(&current_route_step.get_linestring(), next_route_step.as_ref(), &inaccurate_user_location, StepAdvanceMode::Manual, DistanceMeasurement::Haversine));
 
             // Always succeeds in the base case in distance to end of step mode
             let cond = should_advance_to_next_step(&current_route_step.get_linestring(), next_route_step.as_ref(), &exact_user_location, StepAdvanceMode::DistanceToEndOfStep {
                 distance, minimum_horizontal_accuracy
-            });
+            }, DistanceMeasurement::Haversine);
             prop_assert!(cond);
 
             // Same when looking at the relative distances between the two step geometries
             let cond = should_advance_to_next_step(&current_route_step.get_linestring(), next_route_step.as_ref(), &exact_user_location, StepAdvanceMode::RelativeLineStringDistance {
                 minimum_horizontal_accuracy,
                 automatic_advance_distance
-            });
+            }, DistanceMeasurement::Haversine);
             prop_assert!(cond);
 
             // Should always fail (unless excess_inaccuracy is zero), as the horizontal accuracy is worse than (>) than the desired error threshold
             prop_assert_eq!(should_advance_to_next_step(&current_route_step.get_linestring(), next_route_step.as_ref(), &inaccurate_user_location, StepAdvanceMode::DistanceToEndOfStep {
                 distance, minimum_horizontal_accuracy
-            }), excess_inaccuracy == 0.0, "Expected that the navigation would not advance to the next step except when excess_inaccuracy is 0");
+            }, DistanceMeasurement::Haversine), excess_inaccuracy == 0.0, "Expected that the navigation would not advance to the next step except when excess_inaccuracy is 0");
             prop_assert_eq!(should_advance_to_next_step(&current_route_step.get_linestring(), next_route_step.as_ref(), &inaccurate_user_location, StepAdvanceMode::RelativeLineStringDistance {
                 minimum_horizontal_accuracy,
                 automatic_advance_distance
-            }), excess_inaccuracy == 0.0, "Expected that the navigation would not advance to the next step except when excess_inaccuracy is 0");
+            }, DistanceMeasurement::Haversine), excess_inaccuracy == 0.0, "Expected that the navigation would not advance to the next step except when excess_inaccuracy is 0");
         }
     }
 
@@ -453,18 +1101,20 @@ proptest! {
             horizontal_accuracy: 0.0,
             course_over_ground: None,
             timestamp: SystemTime::now(),
-            speed: None
+            speed: None,
+            altitude: None,
+            vertical_accuracy: None,
         };
         let user_location_point = Point::from(user_location);
         let distance_from_end_of_current_step = user_location_point.haversine_distance(&end_of_step.into());
 
         // Never advance to the next step when StepAdvanceMode is Manual
-        prop_assert!(!should_advance_to_next_step(&current_route_step.get_linestring(), next_route_step.as_ref(), &user_location, StepAdvanceMode::Manual));
+        prop_assert!(!should_advance_to_next_step(&current_route_step.get_linestring(), next_route_step.as_ref(), &user_location, StepAdvanceMode::Manual, DistanceMeasurement::Haversine));
 
         // Assumes that underlying distance calculations in GeoRust are correct is correct
         prop_assert_eq!(should_advance_to_next_step(&current_route_step.get_linestring(), next_route_step.as_ref(), &user_location, StepAdvanceMode::DistanceToEndOfStep {
             distance, minimum_horizontal_accuracy
-        }), distance_from_end_of_current_step <= distance.into(), "Expected that the step should advance in this case as we are closer to the end of the step than the threshold.");
+        }, DistanceMeasurement::Haversine), distance_from_end_of_current_step <= distance.into(), "Expected that the step should advance in this case as we are closer to the end of the step than the threshold.");
 
         // Similar test for automatic advance on the relative line string distance mode
         if automatic_advance_distance.map_or(false, |advance_distance| {
@@ -473,7 +1123,7 @@ proptest! {
             prop_assert!(should_advance_to_next_step(&current_route_step.get_linestring(), next_route_step.as_ref(), &user_location, StepAdvanceMode::RelativeLineStringDistance {
                 minimum_horizontal_accuracy,
                 automatic_advance_distance,
-            }), "Expected that the step should advance any time that the haversine distance to the end of the step is within the automatic advance threshold.");
+            }, DistanceMeasurement::Haversine), "Expected that the step should advance any time that the haversine distance to the end of the step is within the automatic advance threshold.");
         }
     }
 }
@@ -481,7 +1131,535 @@ proptest! {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::navigation_controller::test_helpers::gen_user_location;
     use geo::{coord, point};
+    use std::time::Duration;
+
+    /// A line which heads east, then loops back to pass over itself heading north.
+    fn stacked_line() -> LineString {
+        LineString::new(vec![
+            coord! {x: 0.0, y: 0.0},
+            coord! {x: 0.002, y: 0.0},
+            coord! {x: 0.002, y: 0.001},
+            coord! {x: 0.001, y: 0.001},
+            coord! {x: 0.001, y: -0.001},
+        ])
+    }
+
+    /// The first ~222m are at ground level, after which the road climbs onto a bridge.
+    fn stacked_line_elevation(distance_along_line: f64) -> Option<f64> {
+        Some(if distance_along_line < 250.0 {
+            10.0
+        } else {
+            20.0
+        })
+    }
+
+    fn location_at_crossing(altitude: Option<f64>, vertical_accuracy: Option<f64>) -> UserLocation {
+        // ~1m from the ground level segment, and ~2m from the bridge
+        UserLocation {
+            altitude,
+            vertical_accuracy,
+            ..gen_user_location(0.001_02, 0.000_01, 0).with_horizontal_accuracy(10.0)
+        }
+    }
+
+    #[test]
+    fn stacked_snapping_without_altitude() {
+        let location = location_at_crossing(None, None);
+        assert_eq!(
+            snap_user_location_to_stacked_line(
+                location,
+                &stacked_line(),
+                20.0,
+                false,
+                stacked_line_elevation,
+                DistanceMeasurement::Haversine
+            ),
+            snap_user_location_to_line(location, &stacked_line())
+        );
+    }
+
+    #[test]
+    fn stacked_snapping_prefers_matching_elevation() {
+        let line = stacked_line();
+
+        let on_ground = snap_user_location_to_stacked_line(
+            location_at_crossing(Some(11.0), Some(3.0)),
+            &line,
+            20.0,
+            false,
+            stacked_line_elevation,
+            DistanceMeasurement::Haversine,
+        );
+        assert!(on_ground.coordinates.lat.abs() < 1e-9, "{on_ground:?}");
+
+        let on_bridge = snap_user_location_to_stacked_line(
+            location_at_crossing(Some(19.0), Some(3.0)),
+            &line,
+            20.0,
+            false,
+            stacked_line_elevation,
+            DistanceMeasurement::Haversine,
+        );
+        assert!(
+            (on_bridge.coordinates.lng - 0.001).abs() < 1e-9,
+            "{on_bridge:?}"
+        );
+
+        // The altitude is too inaccurate to tell the difference
+        let ambiguous = snap_user_location_to_stacked_line(
+            location_at_crossing(Some(19.0), Some(50.0)),
+            &line,
+            20.0,
+            false,
+            stacked_line_elevation,
+            DistanceMeasurement::Haversine,
+        );
+        assert!(ambiguous.coordinates.lat.abs() < 1e-9, "{ambiguous:?}");
+    }
+
+    /// Eastbound along the equator, then back westbound ~11m to the north.
+    fn parallel_line() -> LineString {
+        LineString::new(vec![
+            coord! {x: 0.0, y: 0.0},
+            coord! {x: 0.002, y: 0.0},
+            coord! {x: 0.002, y: 0.0001},
+            coord! {x: 0.0, y: 0.0001},
+        ])
+    }
+
+    fn location_between_parallel_roads(
+        course_over_ground: Option<CourseOverGround>,
+    ) -> UserLocation {
+        // ~7m from the eastbound segment, and ~4m from the westbound one
+        UserLocation {
+            course_over_ground,
+            ..gen_user_location(0.001, 0.000_06, 0).with_horizontal_accuracy(10.0)
+        }
+    }
+
+    #[test]
+    fn course_snapping_without_course() {
+        let location = location_between_parallel_roads(None);
+        assert_eq!(
+            snap_user_location_to_line_with_course(
+                location,
+                &parallel_line(),
+                DistanceMeasurement::Haversine
+            ),
+            snap_user_location_to_line(location, &parallel_line())
+        );
+    }
+
+    #[test]
+    fn course_snapping_prefers_matching_bearing() {
+        let line = parallel_line();
+
+        let eastbound = snap_user_location_to_line_with_course(
+            location_between_parallel_roads(Some(CourseOverGround::new(85, Some(10)))),
+            &line,
+            DistanceMeasurement::Haversine,
+        );
+        assert!(eastbound.coordinates.lat.abs() < 1e-9, "{eastbound:?}");
+
+        let westbound = snap_user_location_to_line_with_course(
+            location_between_parallel_roads(Some(CourseOverGround::new(275, Some(10)))),
+            &line,
+            DistanceMeasurement::Haversine,
+        );
+        assert!(
+            (westbound.coordinates.lat - 0.0001).abs() < 1e-9,
+            "{westbound:?}"
+        );
+
+        // The course is too inaccurate to tell the difference
+        let ambiguous = snap_user_location_to_line_with_course(
+            location_between_parallel_roads(Some(CourseOverGround::new(85, Some(360)))),
+            &line,
+            DistanceMeasurement::Haversine,
+        );
+        assert!(
+            (ambiguous.coordinates.lat - 0.0001).abs() < 1e-9,
+            "{ambiguous:?}"
+        );
+    }
+
+    #[test]
+    fn course_snapping_ignores_distant_segments() {
+        let location = UserLocation {
+            horizontal_accuracy: 1.0,
+            ..location_between_parallel_roads(Some(CourseOverGround::new(90, Some(5))))
+        };
+        let snapped = snap_user_location_to_line_with_course(
+            location,
+            &parallel_line(),
+            DistanceMeasurement::Haversine,
+        );
+        assert!(
+            (snapped.coordinates.lat - 0.0001).abs() < 1e-9,
+            "{snapped:?}"
+        );
+    }
+
+    #[test]
+    fn history_snapping_follows_previous_position() {
+        let line = parallel_line();
+        let location = location_between_parallel_roads(None);
+        // ~11m back along the eastbound segment, a second ago
+        let previous = UserLocation {
+            coordinates: GeographicCoordinate {
+                lng: 0.0009,
+                lat: 0.0,
+            },
+            timestamp: location.timestamp - Duration::from_secs(1),
+            ..location
+        };
+
+        // The westbound segment is closer, but the user can't have gotten there in a second
+        let snapped = snap_user_location_to_line_with_history(
+            location,
+            &line,
+            &previous,
+            100.0,
+            DistanceMeasurement::Haversine,
+        );
+        assert!(snapped.coordinates.lat.abs() < 1e-9, "{snapped:?}");
+
+        // Later on, the user is expected to be on the westbound segment
+        let snapped = snap_user_location_to_line_with_history(
+            location,
+            &line,
+            &previous,
+            320.0,
+            DistanceMeasurement::Haversine,
+        );
+        assert!(
+            (snapped.coordinates.lat - 0.0001).abs() < 1e-9,
+            "{snapped:?}"
+        );
+    }
+
+    #[test]
+    fn stacked_snapping_uses_course_when_elevations_match() {
+        let location = UserLocation {
+            altitude: Some(10.0),
+            vertical_accuracy: Some(3.0),
+            ..location_between_parallel_roads(Some(CourseOverGround::new(85, Some(10))))
+        };
+        let flat = |_| Some(10.0);
+
+        let with_course = snap_user_location_to_stacked_line(
+            location,
+            &parallel_line(),
+            20.0,
+            true,
+            flat,
+            DistanceMeasurement::Haversine,
+        );
+        assert!(with_course.coordinates.lat.abs() < 1e-9, "{with_course:?}");
+
+        let without_course = snap_user_location_to_stacked_line(
+            location,
+            &parallel_line(),
+            20.0,
+            false,
+            flat,
+            DistanceMeasurement::Haversine,
+        );
+        assert!(
+            (without_course.coordinates.lat - 0.0001).abs() < 1e-9,
+            "{without_course:?}"
+        );
+    }
+
+    #[test]
+    fn test_angular_difference() {
+        for (a, b, expected) in [
+            (10.0, 350.0, 20.0),
+            (350.0, 10.0, 20.0),
+            (90.0, 270.0, 180.0),
+            (-90.0, 270.0, 0.0),
+        ] {
+            assert!(
+                (angular_difference(a, b) - expected).abs() < 1e-9,
+                "{a} {b}"
+            );
+        }
+    }
+
+    /// A hairpin: north ~220m, then back south on a parallel road ~22m to the east,
+    /// ending level with the start of the return leg, followed by a step heading east.
+    fn switchback_steps() -> (RouteStep, RouteStep) {
+        let mut current_step = gen_dummy_route_step(0.0, 0.0, 0.0, 0.002);
+        current_step.geometry.extend([
+            GeographicCoordinate {
+                lng: 0.0002,
+                lat: 0.002,
+            },
+            GeographicCoordinate {
+                lng: 0.0002,
+                lat: 0.0005,
+            },
+        ]);
+        let next_step = gen_dummy_route_step(0.0002, 0.0005, 0.002, 0.0005);
+        (current_step, next_step)
+    }
+
+    #[test]
+    fn maneuver_geometry_index_on_switchback() {
+        let (current_step, next_step) = switchback_steps();
+        let linestring = current_step.get_linestring();
+        let mode = StepAdvanceMode::ManeuverGeometryIndex {
+            minimum_horizontal_accuracy: 10,
+        };
+
+        // On the way up, ~22m from the end of the step
+        let outbound = gen_user_location(0.0, 0.0005, 0);
+        assert!(should_advance_to_next_step(
+            &linestring,
+            Some(&next_step),
+            &outbound,
+            StepAdvanceMode::DistanceToEndOfStep {
+                distance: 30,
+                minimum_horizontal_accuracy: 10,
+            },
+            DistanceMeasurement::Haversine
+        ));
+        assert!(!should_advance_to_next_step(
+            &linestring,
+            Some(&next_step),
+            &outbound,
+            mode,
+            DistanceMeasurement::Haversine
+        ));
+
+        // On the way back, before the maneuver
+        assert!(!should_advance_to_next_step(
+            &linestring,
+            Some(&next_step),
+            &gen_user_location(0.0002, 0.0008, 0),
+            mode,
+            DistanceMeasurement::Haversine
+        ));
+
+        // Exactly at the maneuver point
+        assert!(!should_advance_to_next_step(
+            &linestring,
+            Some(&next_step),
+            &gen_user_location(0.0002, 0.0005, 0),
+            mode,
+            DistanceMeasurement::Haversine
+        ));
+
+        // Past the maneuver point
+        assert!(should_advance_to_next_step(
+            &linestring,
+            Some(&next_step),
+            &gen_user_location(0.0004, 0.0005, 0),
+            mode,
+            DistanceMeasurement::Haversine
+        ));
+
+        // Too inaccurate
+        assert!(!should_advance_to_next_step(
+            &linestring,
+            Some(&next_step),
+            &gen_user_location(0.0004, 0.0005, 0).with_horizontal_accuracy(20.0),
+            mode,
+            DistanceMeasurement::Haversine
+        ));
+    }
+
+    #[test]
+    fn maneuver_confirmation_requires_matching_course() {
+        let (current_step, next_step) = switchback_steps();
+        let linestring = current_step.get_linestring();
+        let mode = StepAdvanceMode::ManeuverConfirmation {
+            minimum_horizontal_accuracy: 10,
+            maximum_course_difference: 45,
+        };
+        // Past the maneuver point, where the next step heads east
+        let past_maneuver = |course: Option<u16>| UserLocation {
+            course_over_ground: course.map(|degrees| CourseOverGround::new(degrees, None)),
+            ..gen_user_location(0.0004, 0.0005, 0)
+        };
+
+        // Turning onto the next step
+        for course in [Some(90), Some(120), None] {
+            assert!(
+                should_advance_to_next_step(
+                    &linestring,
+                    Some(&next_step),
+                    &past_maneuver(course),
+                    mode,
+                    DistanceMeasurement::Haversine
+                ),
+                "{course:?}"
+            );
+        }
+        // Overshooting the intersection while still heading south
+        assert!(!should_advance_to_next_step(
+            &linestring,
+            Some(&next_step),
+            &past_maneuver(Some(180)),
+            mode,
+            DistanceMeasurement::Haversine
+        ));
+        // Heading east, but before the maneuver point
+        assert!(!should_advance_to_next_step(
+            &linestring,
+            Some(&next_step),
+            &gen_user_location(0.0002, 0.0008, 0).with_course(CourseOverGround::new(90, None)),
+            mode,
+            DistanceMeasurement::Haversine
+        ));
+    }
+
+    #[test]
+    fn maneuver_geometry_index_on_final_step() {
+        let (current_step, _) = switchback_steps();
+        let linestring = current_step.get_linestring();
+        let mode = StepAdvanceMode::ManeuverGeometryIndex {
+            minimum_horizontal_accuracy: 10,
+        };
+
+        assert!(should_advance_to_next_step(
+            &linestring,
+            None,
+            &gen_user_location(0.0002, 0.0005, 0),
+            mode,
+            DistanceMeasurement::Haversine
+        ));
+        assert!(!should_advance_to_next_step(
+            &linestring,
+            None,
+            &gen_user_location(0.0002, 0.0008, 0),
+            mode,
+            DistanceMeasurement::Haversine
+        ));
+    }
+
+    #[test]
+    fn regression_to_previous_step() {
+        // East ~111m, then north
+        let previous_step = gen_dummy_route_step(0.0, 0.0, 0.001, 0.0);
+        let current_step = gen_dummy_route_step(0.001, 0.0, 0.001, 0.001);
+        let linestring = previous_step.get_linestring();
+        let mode = StepAdvanceMode::DistanceToEndOfStep {
+            distance: 10,
+            minimum_horizontal_accuracy: 10,
+        };
+        let should_regress = |location: &UserLocation, mode| {
+            should_regress_to_previous_step(
+                &linestring,
+                &current_step,
+                location,
+                mode,
+                DistanceMeasurement::Haversine,
+            )
+        };
+        // ~50m back along the previous step
+        let behind = gen_user_location(0.000_55, 0.0, 0);
+
+        assert!(should_regress(&behind, mode));
+        // Still close enough to the end of the previous step to advance from it
+        assert!(!should_regress(&gen_user_location(0.000_95, 0.0, 0), mode));
+        // On the current step
+        assert!(!should_regress(&gen_user_location(0.001, 0.000_5, 0), mode));
+        // Too inaccurate to tell
+        assert!(!should_regress(
+            &behind.with_horizontal_accuracy(50.0),
+            mode
+        ));
+        assert!(!should_regress(&behind, StepAdvanceMode::Manual));
+        // The course doesn't matter when moving back
+        assert!(should_regress(
+            &behind.with_course(CourseOverGround::new(270, None)),
+            StepAdvanceMode::ManeuverConfirmation {
+                minimum_horizontal_accuracy: 10,
+                maximum_course_difference: 45,
+            }
+        ));
+    }
+
+    #[test]
+    fn distance_measurements() {
+        // One degree of longitude along the equator
+        let a = point!(x: 0.0, y: 0.0);
+        let b = point!(x: 1.0, y: 0.0);
+        assert!((DistanceMeasurement::Haversine.distance(&a, &b) - 111_195.08).abs() < 0.01);
+        assert!((DistanceMeasurement::Planar.distance(&a, &b) - 111_195.08).abs() < 0.01);
+        // The ellipsoid is wider at the equator than the mean sphere
+        assert!((DistanceMeasurement::Geodesic.distance(&a, &b) - 111_319.49).abs() < 0.01);
+
+        // A short segment at a high latitude
+        let a = point!(x: 15.6, y: 78.2);
+        let b = point!(x: 15.61, y: 78.201);
+        let haversine = DistanceMeasurement::Haversine.distance(&a, &b);
+        let planar = DistanceMeasurement::Planar.distance(&a, &b);
+        let geodesic = DistanceMeasurement::Geodesic.distance(&a, &b);
+        assert!((planar - haversine).abs() < 0.001);
+        assert!((geodesic - haversine).abs() / haversine < 0.005);
+
+        let line = LineString::from(vec![(0.0, 0.0), (0.5, 0.0), (1.0, 0.0)]);
+        for measurement in [
+            DistanceMeasurement::Haversine,
+            DistanceMeasurement::Geodesic,
+            DistanceMeasurement::Planar,
+        ] {
+            let length = measurement.length(&line);
+            assert!(
+                (length - measurement.distance(&line.0[0].into(), &line.0[2].into())).abs() < 0.01
+            );
+        }
+    }
+
+    #[test]
+    fn trims_traveled_geometry() {
+        let mut step = gen_dummy_route_step(0.0, 0.0, 0.001, 0.0);
+        step.geometry.extend([
+            GeographicCoordinate {
+                lng: 0.002,
+                lat: 0.0,
+            },
+            GeographicCoordinate {
+                lng: 0.003,
+                lat: 0.0,
+            },
+        ]);
+        step.distance = 330.0;
+        step.duration = 30.0;
+        let original = step.clone();
+
+        // Nothing is more than the rewind distance behind the location
+        trim_traveled_geometry(
+            &mut step,
+            &point!(x: 0.00225, y: 0.0),
+            300.0,
+            DistanceMeasurement::Haversine,
+        );
+        assert_eq!(step, original);
+
+        trim_traveled_geometry(
+            &mut step,
+            &point!(x: 0.00225, y: 0.0),
+            50.0,
+            DistanceMeasurement::Haversine,
+        );
+        assert_eq!(step.geometry, original.geometry[1..]);
+        assert!((step.distance - 220.0).abs() < 1e-9);
+        assert!((step.duration - 20.0).abs() < 1e-9);
+
+        // The final segment is always kept
+        trim_traveled_geometry(
+            &mut step,
+            &point!(x: 0.003, y: 0.0),
+            0.0,
+            DistanceMeasurement::Haversine,
+        );
+        assert_eq!(step.geometry, original.geometry[2..]);
+    }
 
     #[test]
     fn test_deviation_from_line() {
@@ -502,16 +1680,61 @@ mod tests {
         };
 
         // The origin is directly on the line
-        assert_eq!(deviation_from_line(&origin, &linestring), Some(0.0));
+        assert_eq!(
+            deviation_from_line(&origin, &linestring, DistanceMeasurement::Haversine),
+            Some(0.0)
+        );
 
         // The midpoint is also directly on the line
-        assert_eq!(deviation_from_line(&midpoint, &linestring), Some(0.0));
+        assert_eq!(
+            deviation_from_line(&midpoint, &linestring, DistanceMeasurement::Haversine),
+            Some(0.0)
+        );
 
         // This point however is off the line.
         // We assume the underlying library functions are tested and this is just a sanity check.
-        assert!(deviation_from_line(&off_line, &linestring)
-            .map_or(false, |deviation| deviation - 39312.21257675703
-                < f64::EPSILON));
+        assert!(
+            deviation_from_line(&off_line, &linestring, DistanceMeasurement::Haversine)
+                .map_or(false, |deviation| deviation - 39312.21257675703
+                    < f64::EPSILON)
+        );
+    }
+
+    #[test]
+    fn decode_polyline_example() {
+        // The example from the polyline algorithm documentation
+        assert_eq!(
+            decode_polyline("_p~iF~ps|U_ulLnnqC_mqNvxq`@", 5),
+            Ok(LineString::new(vec![
+                coord! {x: -120.2, y: 38.5},
+                coord! {x: -120.95, y: 40.7},
+                coord! {x: -126.453, y: 43.252},
+            ]))
+        );
+        assert_eq!(decode_polyline("", 6), Ok(LineString::new(vec![])));
+    }
+
+    #[test]
+    fn malformed_polylines() {
+        for (polyline, precision) in [
+            // Out of range characters
+            ("_p~iF ps|U", 5),
+            ("_p~iF~ps|U\u{7f}", 5),
+            // A value which never ends
+            ("_p~iF~ps|U_ulLnnqC_mqNvxq", 5),
+            // A latitude without a longitude
+            ("_p~iF~ps|U_ulL", 5),
+            // Far too many characters for one value
+            ("~~~~~~~~~~~~~~~~~~~~~~~~?", 5),
+            // Precisions which would overflow
+            ("_p~iF~ps|U", 10),
+            ("_p~iF~ps|U", u32::MAX),
+        ] {
+            assert!(
+                decode_polyline(polyline, precision).is_err(),
+                "{polyline} at precision {precision}"
+            );
+        }
     }
 }
 // TODO: Unit tests
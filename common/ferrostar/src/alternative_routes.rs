@@ -0,0 +1,347 @@
+//! Proactive monitoring for faster routes.
+//!
+//! Traffic conditions change over the course of a trip,
+//! so the route which was fastest at the start may not be by the time the user gets there.
+//! The [`AlternativeRouteMonitor`] periodically requests a new route for the remainder of the trip,
+//! and reports it if it is sufficiently faster.
+//! It never switches routes by itself; that is left to the app (ex: after asking the driver),
+//! typically via [`SynchronizedNavigationController::replace_route`].
+
+use crate::models::Route;
+use crate::navigation_controller::models::{NavigationEvent, RouteChange, TripProgress, TripState};
+use crate::navigation_controller::SynchronizedNavigationController;
+use crate::routing_adapters::error::RouteFetchError;
+use crate::routing_adapters::http::{spawn_blocking, CancellationToken, HttpExecutor};
+use crate::routing_adapters::RouteAdapter;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Determines when an alternative route is worth suggesting.
+///
+/// Both criteria must be met.
+#[derive(Debug, Copy, Clone, PartialEq, uniffi::Record)]
+pub struct FasterRouteThreshold {
+    /// The minimum time saved compared to the current route, in seconds.
+    pub min_time_savings: f64,
+    /// The minimum time saved as a fraction of the duration remaining (ex: 0.1 for 10%).
+    pub min_relative_time_savings: f64,
+}
+
+impl FasterRouteThreshold {
+    /// Compares a route for the remainder of the trip with the current progress,
+    /// returning a [`NavigationEvent::FasterRouteAvailable`] event if the route is faster.
+    pub(crate) fn check(&self, route: Route, progress: &TripProgress) -> Option<NavigationEvent> {
+        let duration: f64 = route.steps.iter().map(|step| step.duration).sum();
+        let change = RouteChange {
+            distance_delta: route.distance - progress.distance_remaining,
+            duration_delta: duration - progress.duration_remaining,
+        };
+
+        let time_savings = -change.duration_delta;
+        let is_faster = time_savings >= self.min_time_savings
+            && time_savings >= self.min_relative_time_savings * progress.duration_remaining;
+        is_faster.then_some(NavigationEvent::FasterRouteAvailable { route, change })
+    }
+}
+
+/// The shortest interval between checks for faster routes, in seconds.
+///
+/// Shorter (or invalid) intervals passed to [`AlternativeRouteMonitor::run`] are raised to this,
+/// so that a misconfiguration can't flood the routing backend.
+pub const MIN_CHECK_INTERVAL: f64 = 30.0;
+
+/// Converts a check interval in seconds to a [`Duration`] of at least `minimum` seconds.
+fn clamp_check_interval(seconds: f64, minimum: f64) -> Duration {
+    // f64::max also replaces NaN
+    Duration::try_from_secs_f64(seconds.max(minimum)).unwrap_or(Duration::MAX)
+}
+
+/// A listener for events which happen outside of location updates (ex: in the background).
+#[uniffi::export(with_foreign)]
+pub trait NavigationEventListener: Send + Sync {
+    fn on_navigation_event(&self, event: NavigationEvent);
+}
+
+/// Periodically checks for faster routes to the remaining waypoints.
+#[derive(uniffi::Object)]
+pub struct AlternativeRouteMonitor {
+    route_adapter: Arc<RouteAdapter>,
+    executor: Arc<HttpExecutor>,
+    threshold: FasterRouteThreshold,
+    /// Always [`MIN_CHECK_INTERVAL`], except in tests.
+    min_check_interval: f64,
+}
+
+#[uniffi::export]
+impl AlternativeRouteMonitor {
+    #[uniffi::constructor]
+    pub fn new(
+        route_adapter: Arc<RouteAdapter>,
+        executor: Arc<HttpExecutor>,
+        threshold: FasterRouteThreshold,
+    ) -> Self {
+        Self {
+            route_adapter,
+            executor,
+            threshold,
+            min_check_interval: MIN_CHECK_INTERVAL,
+        }
+    }
+
+    /// Requests a route from the user's current position to the remaining waypoints,
+    /// returning a [`NavigationEvent::FasterRouteAvailable`] event if it meets the threshold.
+    ///
+    /// Returns `None` if the trip is complete or the route is not sufficiently faster.
+    pub async fn check_for_faster_route(
+        &self,
+        state: TripState,
+        cancellation_token: Option<Arc<CancellationToken>>,
+    ) -> Result<Option<NavigationEvent>, RouteFetchError> {
+        let TripState::Navigating {
+            snapped_user_location,
+            remaining_waypoints,
+            progress,
+            ..
+        } = state
+        else {
+            return Ok(None);
+        };
+
+        let route = self
+            .route_adapter
+            .get_route(
+                self.executor.clone(),
+                snapped_user_location,
+                remaining_waypoints,
+                cancellation_token,
+            )
+            .await?;
+        Ok(self.threshold.check(route, &progress))
+    }
+
+    /// Checks for faster routes every `check_interval` seconds until the trip is complete
+    /// or the cancellation token is cancelled.
+    /// Intervals shorter than [`MIN_CHECK_INTERVAL`] (including zero, negative, and NaN) are raised to it.
+    ///
+    /// Faster routes are reported to the listener.
+    /// Failed checks (ex: due to a network error) are skipped, so the next check may succeed.
+    pub async fn run(
+        &self,
+        controller: Arc<SynchronizedNavigationController>,
+        listener: Arc<dyn NavigationEventListener>,
+        check_interval: f64,
+        cancellation_token: Arc<CancellationToken>,
+    ) {
+        let check_interval = clamp_check_interval(check_interval, self.min_check_interval);
+        loop {
+            let token = cancellation_token.clone();
+            if spawn_blocking(move || token.wait(check_interval))
+                .await
+                .unwrap_or(true)
+            {
+                return;
+            }
+
+            let state = controller.get_state();
+            if state == TripState::Complete {
+                return;
+            }
+            match self
+                .check_for_faster_route(state, Some(cancellation_token.clone()))
+                .await
+            {
+                Ok(Some(event)) => listener.on_navigation_event(event),
+                Err(RouteFetchError::Cancelled) => return,
+                Ok(None) | Err(_) => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::UserLocation;
+    use crate::navigation_controller::models::{
+        NavigationControllerConfig, SnappingMode, StepAdvanceMode,
+    };
+    use crate::navigation_controller::presets::NavigationPreset;
+    use crate::navigation_controller::test_helpers::{
+        gen_dummy_route_step, gen_route_from_steps, gen_user_location,
+    };
+    use crate::routing_adapters::http::test_helpers::{block_on, executor, MockHttpClient};
+    use crate::{create_osrm_response_parser, create_valhalla_request_generator};
+    use std::sync::Mutex;
+    use std::time::SystemTime;
+
+    #[derive(Default)]
+    struct RecordingListener {
+        events: Mutex<Vec<NavigationEvent>>,
+    }
+
+    impl NavigationEventListener for RecordingListener {
+        fn on_navigation_event(&self, event: NavigationEvent) {
+            self.events
+                .lock()
+                .expect("Unable to lock events")
+                .push(event);
+        }
+    }
+
+    fn progress(duration_remaining: f64) -> TripProgress {
+        TripProgress {
+            distance_to_next_maneuver: 100.0,
+            distance_remaining: 1000.0,
+            duration_remaining,
+            consumption: None,
+        }
+    }
+
+    fn route_with_duration(duration: f64) -> Route {
+        let mut step = gen_dummy_route_step(0.0, 0.0, 0.01, 0.0);
+        step.distance = 1100.0;
+        step.duration = duration;
+        gen_route_from_steps(vec![step])
+    }
+
+    fn monitor(threshold: FasterRouteThreshold) -> AlternativeRouteMonitor {
+        AlternativeRouteMonitor::new(
+            Arc::new(RouteAdapter::new(
+                create_valhalla_request_generator(
+                    "https://valhalla.example.com/route".to_string(),
+                    "auto".to_string(),
+                    None,
+                )
+                .expect("Unable to create request generator"),
+                create_osrm_response_parser(6),
+            )),
+            executor(MockHttpClient::osrm()),
+            threshold,
+        )
+    }
+
+    fn controller() -> SynchronizedNavigationController {
+        let route = route_with_duration(600.0);
+        let location = UserLocation {
+            timestamp: SystemTime::now(),
+            ..gen_user_location(route.geometry[0].lng, route.geometry[0].lat, 0)
+        };
+        SynchronizedNavigationController::new(
+            route,
+            NavigationControllerConfig {
+                step_advance: StepAdvanceMode::Manual,
+                snapping: SnappingMode::NearestPoint,
+                ..NavigationControllerConfig::from_preset(NavigationPreset::Driving)
+            },
+            location,
+        )
+    }
+
+    const THRESHOLD: FasterRouteThreshold = FasterRouteThreshold {
+        min_time_savings: 120.0,
+        min_relative_time_savings: 0.1,
+    };
+
+    #[test]
+    fn faster_route_threshold() {
+        let Some(NavigationEvent::FasterRouteAvailable { change, .. }) =
+            THRESHOLD.check(route_with_duration(400.0), &progress(600.0))
+        else {
+            panic!("Expected a faster route");
+        };
+        assert_eq!(
+            change,
+            RouteChange {
+                distance_delta: 100.0,
+                duration_delta: -200.0
+            }
+        );
+
+        // Not enough time saved
+        assert_eq!(
+            THRESHOLD.check(route_with_duration(500.0), &progress(600.0)),
+            None
+        );
+        // Enough time saved, but not relative to the length of the trip
+        assert_eq!(
+            THRESHOLD.check(route_with_duration(4800.0), &progress(5000.0)),
+            None
+        );
+        // Slower
+        assert_eq!(
+            THRESHOLD.check(route_with_duration(700.0), &progress(600.0)),
+            None
+        );
+    }
+
+    #[test]
+    fn check_for_faster_route() {
+        let monitor = monitor(THRESHOLD);
+        let controller = controller();
+
+        let event = block_on(monitor.check_for_faster_route(controller.get_state(), None))
+            .expect("Unable to check for a faster route");
+        assert!(matches!(
+            event,
+            Some(NavigationEvent::FasterRouteAvailable { .. })
+        ));
+
+        let event = block_on(monitor.check_for_faster_route(TripState::Complete, None))
+            .expect("Unable to check for a faster route");
+        assert_eq!(event, None);
+    }
+
+    #[test]
+    fn check_interval_is_clamped() {
+        let minimum = Duration::from_secs(30);
+        assert_eq!(clamp_check_interval(0.0, MIN_CHECK_INTERVAL), minimum);
+        assert_eq!(clamp_check_interval(-5.0, MIN_CHECK_INTERVAL), minimum);
+        assert_eq!(clamp_check_interval(f64::NAN, MIN_CHECK_INTERVAL), minimum);
+        assert_eq!(
+            clamp_check_interval(90.0, MIN_CHECK_INTERVAL),
+            Duration::from_secs(90)
+        );
+        assert_eq!(
+            clamp_check_interval(f64::INFINITY, MIN_CHECK_INTERVAL),
+            Duration::MAX
+        );
+    }
+
+    #[test]
+    fn run_reports_faster_routes_until_cancelled() {
+        let monitor = AlternativeRouteMonitor {
+            min_check_interval: 0.0,
+            ..monitor(THRESHOLD)
+        };
+        let controller = Arc::new(controller());
+        let listener = Arc::new(RecordingListener::default());
+        let token = Arc::new(CancellationToken::new());
+
+        let cancel_after_first_event = {
+            let listener = listener.clone();
+            let token = token.clone();
+            std::thread::spawn(move || loop {
+                if !listener
+                    .events
+                    .lock()
+                    .expect("Unable to lock events")
+                    .is_empty()
+                {
+                    token.cancel();
+                    return;
+                }
+                std::thread::sleep(Duration::from_millis(1));
+            })
+        };
+        block_on(monitor.run(controller, listener.clone(), 0.01, token));
+        cancel_after_first_event
+            .join()
+            .expect("Unable to join the cancelling thread");
+
+        assert!(!listener
+            .events
+            .lock()
+            .expect("Unable to lock events")
+            .is_empty());
+    }
+}
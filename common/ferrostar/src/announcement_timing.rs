@@ -0,0 +1,339 @@
+//! Speed-dependent timing of spoken instructions.
+//!
+//! Routing backends compute the trigger distances of spoken instructions ahead of time,
+//! without knowing how fast the user will actually be traveling.
+//! At highway speeds, a fixed distance leaves little time to react,
+//! while in slow traffic, the same instruction comes long before it is useful.
+
+use crate::models::{RouteStep, Speed, SpokenInstruction};
+
+/// The tolerance, in meters, for reaching a trigger distance.
+///
+/// This accounts for imprecision in calculation methodologies from different engines and CPUs,
+/// particularly at the start of a step.
+const TRIGGER_TOLERANCE: f64 = 5.0;
+
+/// Determines when spoken instructions are announced.
+#[derive(Debug, Copy, Clone, PartialEq, uniffi::Enum)]
+pub enum AnnouncementTiming {
+    /// Announces spoken instructions at the trigger distances from the route.
+    Static,
+    /// Scales the trigger distances by the user's speed relative to a reference speed,
+    /// so that instructions are announced earlier at high speeds (and later at low speeds).
+    ///
+    /// The scale is clamped to `min_scale..=max_scale`,
+    /// and trigger distances are never pushed beyond the start of the step.
+    /// When the user's speed is unknown, the trigger distances are used as is.
+    ///
+    /// NOTE: Instructions which state the distance to the maneuver (ex: "In 200 feet, turn left")
+    /// will not be announced at exactly the stated distance.
+    SpeedScaled {
+        /// The speed, in meters per second, at which the trigger distances are used as is.
+        reference_speed: f64,
+        /// The minimum scale (ex: 0.75 to announce no later than at 75% of the trigger distance).
+        min_scale: f64,
+        /// The maximum scale (ex: 2 to announce no earlier than at twice the trigger distance).
+        max_scale: f64,
+    },
+}
+
+impl AnnouncementTiming {
+    /// The factor by which trigger distances are scaled at the user's speed.
+    fn scale(&self, speed: Option<Speed>) -> f64 {
+        match self {
+            AnnouncementTiming::Static => 1.0,
+            AnnouncementTiming::SpeedScaled {
+                reference_speed,
+                min_scale,
+                max_scale,
+            } => speed
+                .filter(|speed| speed.value >= 0.0 && *reference_speed > 0.0)
+                .map_or(1.0, |speed| {
+                    (speed.value / reference_speed)
+                        .min(*max_scale)
+                        .max(*min_scale)
+                }),
+        }
+    }
+
+    /// The distance before the maneuver at which to announce an instruction.
+    fn trigger_distance(step: &RouteStep, instruction: &SpokenInstruction, scale: f64) -> f64 {
+        let trigger_distance = instruction.trigger_distance_before_maneuver;
+        if scale > 1.0 {
+            (trigger_distance * scale)
+                .min(step.distance)
+                .max(trigger_distance)
+        } else {
+            trigger_distance * scale
+        }
+    }
+
+    /// Gets the current spoken instruction given the user's progress along the step and speed.
+    pub(crate) fn spoken_instruction<'a>(
+        &self,
+        step: &'a RouteStep,
+        distance_to_end_of_step: f64,
+        speed: Option<Speed>,
+    ) -> Option<&'a SpokenInstruction> {
+        let scale = self.scale(speed);
+        // Finds the *last* instruction where we are past the (scaled) trigger distance.
+        step.spoken_instructions.iter().rev().find(|instruction| {
+            distance_to_end_of_step - Self::trigger_distance(step, instruction, scale)
+                <= TRIGGER_TOLERANCE
+        })
+    }
+
+    /// Gets the spoken instruction which is announced as soon as the user begins a step,
+    /// if the step is too short for it to be announced any later.
+    fn immediate_spoken_instruction<'a>(
+        &self,
+        step: &'a RouteStep,
+        speed: Option<Speed>,
+    ) -> Option<&'a SpokenInstruction> {
+        self.spoken_instruction(step, step.distance, speed)
+            .filter(|instruction| step.spoken_instructions.last() == Some(*instruction))
+    }
+
+    /// Gets the current spoken instruction for the first of the remaining steps.
+    ///
+    /// When the following step is shorter than the lead distance of its announcement,
+    /// the final announcement of the current step is combined with it
+    /// (ex: "Turn left, then bear right") rather than announcing both in quick succession.
+    /// The combined instruction takes the utterance ID of the following announcement,
+    /// so that it is not repeated after the first maneuver.
+    pub(crate) fn spoken_instruction_for_remaining_steps(
+        &self,
+        remaining_steps: &[RouteStep],
+        distance_to_end_of_step: f64,
+        speed: Option<Speed>,
+    ) -> Option<SpokenInstruction> {
+        let (current_step, following_steps) = remaining_steps.split_first()?;
+        let instruction = self.spoken_instruction(current_step, distance_to_end_of_step, speed)?;
+
+        let is_final_announcement = current_step.spoken_instructions.last() == Some(instruction);
+        // A short step was already announced together with the maneuver leading onto it
+        let was_combined = self
+            .immediate_spoken_instruction(current_step, speed)
+            .is_some();
+        let following = following_steps
+            .first()
+            .filter(|_| is_final_announcement && !was_combined)
+            .and_then(|next_step| self.immediate_spoken_instruction(next_step, speed));
+
+        Some(match following {
+            Some(following) => combine_spoken_instructions(instruction, following),
+            None => instruction.clone(),
+        })
+    }
+}
+
+/// Combines two consecutive spoken instructions into one (ex: "Turn left, then bear right").
+fn combine_spoken_instructions(
+    first: &SpokenInstruction,
+    second: &SpokenInstruction,
+) -> SpokenInstruction {
+    let text = format!(
+        "{}, then {}",
+        first.text.trim_end_matches(['.', '!']),
+        lowercase_first(&second.text)
+    );
+    let ssml = match (&first.ssml, &second.ssml) {
+        (Some(first_ssml), Some(second_ssml)) => Some(format!(
+            "<speak>{}, then {}</speak>",
+            ssml_body(first_ssml).trim_end_matches(['.', '!']),
+            lowercase_first(ssml_body(second_ssml))
+        )),
+        _ => None,
+    };
+
+    SpokenInstruction {
+        text,
+        ssml,
+        trigger_distance_before_maneuver: first.trigger_distance_before_maneuver,
+        utterance_id: second.utterance_id,
+    }
+}
+
+/// Strips the `<speak>` root element (if any) from SSML.
+fn ssml_body(ssml: &str) -> &str {
+    let ssml = ssml.trim();
+    ssml.strip_prefix("<speak>")
+        .and_then(|body| body.strip_suffix("</speak>"))
+        .unwrap_or(ssml)
+        .trim()
+}
+
+/// Lowercases the first character of an instruction, so that it can follow another.
+fn lowercase_first(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_lowercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::navigation_controller::test_helpers::gen_dummy_route_step;
+    use uuid::Uuid;
+
+    const TIMING: AnnouncementTiming = AnnouncementTiming::SpeedScaled {
+        // 50 km/h
+        reference_speed: 13.9,
+        min_scale: 0.5,
+        max_scale: 2.0,
+    };
+
+    fn step() -> RouteStep {
+        let mut step = gen_dummy_route_step(0.0, 0.0, 0.01, 0.0);
+        step.distance = 1000.0;
+        step.spoken_instructions = [800.0, 300.0, 50.0]
+            .into_iter()
+            .map(|trigger_distance_before_maneuver| SpokenInstruction {
+                text: format!("In {trigger_distance_before_maneuver} meters"),
+                ssml: None,
+                trigger_distance_before_maneuver,
+                utterance_id: Uuid::new_v4(),
+            })
+            .collect();
+        step
+    }
+
+    fn speed(kilometers_per_hour: f64) -> Option<Speed> {
+        Some(Speed {
+            value: kilometers_per_hour / 3.6,
+            accuracy: None,
+        })
+    }
+
+    fn text(instruction: Option<&SpokenInstruction>) -> Option<&str> {
+        instruction.map(|instruction| instruction.text.as_str())
+    }
+
+    #[test]
+    fn static_timing() {
+        let step = step();
+        let timing = AnnouncementTiming::Static;
+        assert_eq!(
+            text(timing.spoken_instruction(&step, 500.0, speed(120.0))),
+            Some("In 800 meters")
+        );
+        assert_eq!(
+            text(timing.spoken_instruction(&step, 500.0, None)),
+            text(step.get_current_spoken_instruction(500.0))
+        );
+    }
+
+    #[test]
+    fn earlier_at_high_speeds() {
+        let step = step();
+        // At 120 km/h, the 300m instruction is announced 600m before the maneuver
+        assert_eq!(
+            text(TIMING.spoken_instruction(&step, 600.0, speed(120.0))),
+            Some("In 300 meters")
+        );
+        assert_eq!(
+            text(TIMING.spoken_instruction(&step, 600.0, speed(50.0))),
+            Some("In 800 meters")
+        );
+        // The 50m instruction is announced at twice the distance (the maximum scale)
+        assert_eq!(
+            text(TIMING.spoken_instruction(&step, 100.0, speed(200.0))),
+            Some("In 50 meters")
+        );
+    }
+
+    #[test]
+    fn later_at_low_speeds() {
+        let step = step();
+        // At 30 km/h, the 300m instruction is announced about 180m before the maneuver
+        assert_eq!(
+            text(TIMING.spoken_instruction(&step, 250.0, speed(30.0))),
+            Some("In 800 meters")
+        );
+        assert_eq!(
+            text(TIMING.spoken_instruction(&step, 180.0, speed(30.0))),
+            Some("In 300 meters")
+        );
+        // Unknown speeds use the trigger distances as is
+        assert_eq!(
+            text(TIMING.spoken_instruction(&step, 250.0, None)),
+            Some("In 300 meters")
+        );
+    }
+
+    #[test]
+    fn clamped_to_step_length() {
+        let step = step();
+        // The 800m instruction would be announced 1,600m before the maneuver,
+        // but the step is only 1,000m long
+        assert_eq!(
+            text(TIMING.spoken_instruction(&step, 1000.0, speed(100.0))),
+            Some("In 800 meters")
+        );
+        assert_eq!(TIMING.spoken_instruction(&step, 1100.0, speed(100.0)), None);
+    }
+
+    fn short_step(distance: f64, text: &str) -> RouteStep {
+        let mut step = gen_dummy_route_step(0.0, 0.0, 0.0001, 0.0);
+        step.distance = distance;
+        step.spoken_instructions = vec![SpokenInstruction {
+            text: text.to_string(),
+            ssml: Some(format!("<speak>{text}</speak>")),
+            trigger_distance_before_maneuver: 50.0,
+            utterance_id: Uuid::new_v4(),
+        }];
+        step
+    }
+
+    #[test]
+    fn combines_announcements_of_short_steps() {
+        let mut first = step();
+        first.spoken_instructions[2].text = "Turn left.".to_string();
+        first.spoken_instructions[2].ssml = Some("<speak>Turn left.</speak>".to_string());
+        let steps = vec![
+            first,
+            short_step(20.0, "Bear right"),
+            short_step(30.0, "Turn right"),
+        ];
+        let timing = AnnouncementTiming::Static;
+
+        // Earlier announcements are not combined
+        assert_eq!(
+            timing
+                .spoken_instruction_for_remaining_steps(&steps, 250.0, None)
+                .map(|instruction| instruction.text),
+            Some("In 300 meters".to_string())
+        );
+
+        let combined = timing
+            .spoken_instruction_for_remaining_steps(&steps, 40.0, None)
+            .expect("Expected an instruction");
+        assert_eq!(combined.text, "Turn left, then bear right");
+        assert_eq!(
+            combined.ssml.as_deref(),
+            Some("<speak>Turn left, then bear right</speak>")
+        );
+        assert_eq!(
+            combined.utterance_id,
+            steps[1].spoken_instructions[0].utterance_id
+        );
+
+        // The short step was already announced, so it is not combined again
+        assert_eq!(
+            timing.spoken_instruction_for_remaining_steps(&steps[1..], 20.0, None),
+            Some(steps[1].spoken_instructions[0].clone())
+        );
+    }
+
+    #[test]
+    fn does_not_combine_announcements_of_long_steps() {
+        let steps = vec![step(), short_step(200.0, "Bear right")];
+
+        assert_eq!(
+            AnnouncementTiming::Static.spoken_instruction_for_remaining_steps(&steps, 40.0, None),
+            Some(steps[0].spoken_instructions[2].clone())
+        );
+    }
+}
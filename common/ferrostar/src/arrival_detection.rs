@@ -0,0 +1,335 @@
+use crate::algorithms::DistanceMeasurement;
+use crate::models::{GeographicCoordinate, UserLocation};
+use geo::Point;
+use std::time::SystemTime;
+
+/// Determines when the user has arrived at their destination.
+///
+/// Arrival is only checked once the user is on the final step of the route.
+#[derive(Debug, Copy, Clone, PartialEq, uniffi::Enum)]
+pub enum ArrivalDetection {
+    /// The trip is complete as soon as the final step would be advanced according to the
+    /// [`crate::navigation_controller::models::StepAdvanceMode`].
+    StepCompletion,
+    /// The trip is complete when *all* of the criteria are met.
+    ///
+    /// This prevents premature arrival when the user passes close to the destination without
+    /// stopping (ex: driving by on the opposite carriageway).
+    Criteria {
+        /// The maximum distance between the user's (unsnapped) location and the destination, in meters.
+        max_distance_to_destination: f64,
+        /// The maximum speed of the user, in meters per second.
+        ///
+        /// Locations without a speed always pass this check.
+        max_speed: Option<f64>,
+        /// The minimum time that the user must continuously satisfy the other criteria, in seconds.
+        ///
+        /// This is measured using the location timestamps.
+        min_dwell_time: Option<f64>,
+    },
+}
+
+/// Determines when the user has stopped near the destination without completing the route
+/// (ex: at a locked gate, or where the final meters can only be covered on foot).
+///
+/// This is reported with a
+/// [`NavigationEvent::ArrivedNearby`](crate::navigation_controller::models::NavigationEvent::ArrivedNearby)
+/// event, so that apps can offer to complete the trip.
+/// The trip itself continues until it is completed according to the [`ArrivalDetection`].
+#[derive(Debug, Copy, Clone, PartialEq, uniffi::Enum)]
+pub enum NearbyArrivalDetection {
+    /// Never reports nearby arrivals.
+    None,
+    /// Reports a nearby arrival when the user is within a distance of the destination
+    /// and (optionally) slow enough to have stopped.
+    WithinRadius {
+        /// The maximum distance between the user's (snapped) location and the destination, in meters.
+        max_distance_to_destination: f64,
+        /// The maximum speed of the user, in meters per second.
+        ///
+        /// Locations without a speed always pass this check.
+        max_speed: Option<f64>,
+    },
+}
+
+impl NearbyArrivalDetection {
+    /// Returns the distance to the destination, in meters,
+    /// if the user is near enough to it to have arrived nearby.
+    pub(crate) fn check_nearby_arrival(
+        &self,
+        location: &UserLocation,
+        destination: GeographicCoordinate,
+        measurement: DistanceMeasurement,
+    ) -> Option<f64> {
+        let NearbyArrivalDetection::WithinRadius {
+            max_distance_to_destination,
+            max_speed,
+        } = self
+        else {
+            return None;
+        };
+
+        let distance_to_destination =
+            measurement.distance(&Point::from(*location), &Point::from(destination));
+        let is_slow_enough = match (max_speed, location.speed) {
+            (Some(max_speed), Some(speed)) => speed.value <= *max_speed,
+            _ => true,
+        };
+        (distance_to_destination <= *max_distance_to_destination && is_slow_enough)
+            .then_some(distance_to_destination)
+    }
+}
+
+/// The result of checking the [`ArrivalDetection`] criteria.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) enum ArrivalStatus {
+    /// The user has arrived at the destination.
+    Arrived,
+    /// The user meets the criteria, but has not done so for long enough.
+    Dwelling {
+        /// When the user first met the criteria.
+        since: SystemTime,
+    },
+    /// The user has not arrived.
+    NotArrived,
+}
+
+impl ArrivalDetection {
+    /// Checks whether the user has arrived at the destination.
+    ///
+    /// `step_completed` indicates whether the final step would be advanced
+    /// according to the step advance mode,
+    /// and `dwell_start` is the time at which the user first met the criteria (if ever).
+    pub(crate) fn check_arrival(
+        &self,
+        location: &UserLocation,
+        destination: GeographicCoordinate,
+        dwell_start: Option<SystemTime>,
+        step_completed: bool,
+        measurement: DistanceMeasurement,
+    ) -> ArrivalStatus {
+        match self {
+            ArrivalDetection::StepCompletion => {
+                if step_completed {
+                    ArrivalStatus::Arrived
+                } else {
+                    ArrivalStatus::NotArrived
+                }
+            }
+            ArrivalDetection::Criteria {
+                max_distance_to_destination,
+                max_speed,
+                min_dwell_time,
+            } => {
+                let distance_to_destination =
+                    measurement.distance(&Point::from(*location), &Point::from(destination));
+                let is_close_enough = distance_to_destination <= *max_distance_to_destination;
+                let is_slow_enough = match (max_speed, location.speed) {
+                    (Some(max_speed), Some(speed)) => speed.value <= *max_speed,
+                    _ => true,
+                };
+
+                if !(is_close_enough && is_slow_enough) {
+                    return ArrivalStatus::NotArrived;
+                }
+
+                let since = dwell_start.unwrap_or(location.timestamp);
+                let dwell_time = location
+                    .timestamp
+                    .duration_since(since)
+                    .map_or(0.0, |duration| duration.as_secs_f64());
+                if dwell_time >= min_dwell_time.unwrap_or(0.0) {
+                    ArrivalStatus::Arrived
+                } else {
+                    ArrivalStatus::Dwelling { since }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::navigation_controller::test_helpers::gen_user_location;
+    use std::time::Duration;
+
+    const DESTINATION: GeographicCoordinate = GeographicCoordinate { lat: 0.0, lng: 0.0 };
+
+    #[test]
+    fn step_completion() {
+        let detection = ArrivalDetection::StepCompletion;
+        let far_away = gen_user_location(1.0, 0.0, 0);
+
+        assert_eq!(
+            detection.check_arrival(
+                &far_away,
+                DESTINATION,
+                None,
+                true,
+                DistanceMeasurement::Haversine
+            ),
+            ArrivalStatus::Arrived
+        );
+        assert_eq!(
+            detection.check_arrival(
+                &far_away,
+                DESTINATION,
+                None,
+                false,
+                DistanceMeasurement::Haversine
+            ),
+            ArrivalStatus::NotArrived
+        );
+    }
+
+    #[test]
+    fn distance_and_speed_gating() {
+        let detection = ArrivalDetection::Criteria {
+            max_distance_to_destination: 20.0,
+            max_speed: Some(2.0),
+            min_dwell_time: None,
+        };
+
+        // ~111m away
+        assert_eq!(
+            detection.check_arrival(
+                &gen_user_location(0.001, 0.0, 0).with_speed(0.0),
+                DESTINATION,
+                None,
+                true,
+                DistanceMeasurement::Haversine
+            ),
+            ArrivalStatus::NotArrived
+        );
+        // Close, but driving past
+        assert_eq!(
+            detection.check_arrival(
+                &gen_user_location(0.0001, 0.0, 0).with_speed(15.0),
+                DESTINATION,
+                None,
+                true,
+                DistanceMeasurement::Haversine
+            ),
+            ArrivalStatus::NotArrived
+        );
+        // Close and stopped; the step completion does not matter
+        assert_eq!(
+            detection.check_arrival(
+                &gen_user_location(0.0001, 0.0, 0).with_speed(1.0),
+                DESTINATION,
+                None,
+                false,
+                DistanceMeasurement::Haversine
+            ),
+            ArrivalStatus::Arrived
+        );
+        // Close with unknown speed
+        assert_eq!(
+            detection.check_arrival(
+                &gen_user_location(0.0001, 0.0, 0),
+                DESTINATION,
+                None,
+                false,
+                DistanceMeasurement::Haversine
+            ),
+            ArrivalStatus::Arrived
+        );
+    }
+
+    #[test]
+    fn nearby_arrival() {
+        let detection = NearbyArrivalDetection::WithinRadius {
+            max_distance_to_destination: 50.0,
+            max_speed: Some(1.0),
+        };
+
+        // ~22m away and stopped
+        let distance = detection
+            .check_nearby_arrival(
+                &gen_user_location(0.0002, 0.0, 0).with_speed(0.5),
+                DESTINATION,
+                DistanceMeasurement::Haversine,
+            )
+            .expect("Expected a nearby arrival");
+        assert!((distance - 22.2).abs() < 0.1);
+        // Still moving
+        assert_eq!(
+            detection.check_nearby_arrival(
+                &gen_user_location(0.0002, 0.0, 0).with_speed(5.0),
+                DESTINATION,
+                DistanceMeasurement::Haversine
+            ),
+            None
+        );
+        // ~111m away
+        assert_eq!(
+            detection.check_nearby_arrival(
+                &gen_user_location(0.001, 0.0, 0),
+                DESTINATION,
+                DistanceMeasurement::Haversine
+            ),
+            None
+        );
+        assert_eq!(
+            NearbyArrivalDetection::None.check_nearby_arrival(
+                &gen_user_location(0.0, 0.0, 0).with_speed(0.0),
+                DESTINATION,
+                DistanceMeasurement::Haversine
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn dwell_time() {
+        let detection = ArrivalDetection::Criteria {
+            max_distance_to_destination: 20.0,
+            max_speed: None,
+            min_dwell_time: Some(10.0),
+        };
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(100);
+
+        assert_eq!(
+            detection.check_arrival(
+                &gen_user_location(0.0, 0.0, 100),
+                DESTINATION,
+                None,
+                false,
+                DistanceMeasurement::Haversine
+            ),
+            ArrivalStatus::Dwelling { since: start }
+        );
+        assert_eq!(
+            detection.check_arrival(
+                &gen_user_location(0.0, 0.0, 105),
+                DESTINATION,
+                Some(start),
+                false,
+                DistanceMeasurement::Haversine
+            ),
+            ArrivalStatus::Dwelling { since: start }
+        );
+        assert_eq!(
+            detection.check_arrival(
+                &gen_user_location(0.0, 0.0, 110),
+                DESTINATION,
+                Some(start),
+                false,
+                DistanceMeasurement::Haversine
+            ),
+            ArrivalStatus::Arrived
+        );
+        // Moving away resets the dwell
+        assert_eq!(
+            detection.check_arrival(
+                &gen_user_location(0.001, 0.0, 110),
+                DESTINATION,
+                Some(start),
+                false,
+                DistanceMeasurement::Haversine
+            ),
+            ArrivalStatus::NotArrived
+        );
+    }
+}
@@ -0,0 +1,153 @@
+//! A simplified trail of where the user actually went (as opposed to the route).
+//!
+//! This is useful for "your track" displays,
+//! and to settle disputes about the path taken (ex: on a delivery or ride-hailing trip).
+
+use crate::models::GeographicCoordinate;
+use geo::{Coord, EuclideanDistance, Line, Point};
+
+/// The approximate number of meters per degree of latitude.
+const METERS_PER_DEGREE: f64 = 111_320.0;
+
+/// The maximum number of points which may be simplified into a single segment of the trail.
+///
+/// This bounds the work done for each location, as each new point is checked against them.
+const MAX_PENDING_POINTS: usize = 100;
+
+/// Determines whether a breadcrumb trail of the user's locations is kept.
+#[derive(Debug, Copy, Clone, PartialEq, uniffi::Enum)]
+pub enum BreadcrumbRecording {
+    /// No trail is kept.
+    Disabled,
+    /// The user's locations are kept as a polyline,
+    /// which is simplified as it grows to limit the memory it uses.
+    Simplified {
+        /// The maximum distance between the trail and any location it simplifies away, in meters.
+        tolerance: f64,
+    },
+}
+
+/// A polyline of the user's locations, which is simplified as points are added.
+///
+/// Each segment of the trail is extended for as long as it stays within the tolerance
+/// of every location it replaces (a sliding window simplification).
+#[derive(Debug, Clone, Default)]
+pub(crate) struct BreadcrumbTrail {
+    /// The tolerance in meters, or `None` if recording is disabled.
+    tolerance: Option<f64>,
+    /// The points which are kept in the simplified trail.
+    points: Vec<GeographicCoordinate>,
+    /// The points after the last kept point, which may yet be simplified away.
+    ///
+    /// The last of these is the current end of the trail.
+    pending: Vec<GeographicCoordinate>,
+}
+
+impl BreadcrumbTrail {
+    pub(crate) fn new(recording: BreadcrumbRecording) -> Self {
+        Self {
+            tolerance: match recording {
+                BreadcrumbRecording::Disabled => None,
+                BreadcrumbRecording::Simplified { tolerance } => Some(tolerance.max(0.0)),
+            },
+            points: vec![],
+            pending: vec![],
+        }
+    }
+
+    /// Adds a location to the end of the trail.
+    pub(crate) fn push(&mut self, coordinate: GeographicCoordinate) {
+        let Some(tolerance) = self.tolerance else {
+            return;
+        };
+        let Some(&anchor) = self.points.last() else {
+            self.points.push(coordinate);
+            return;
+        };
+
+        let fits = self.pending.len() < MAX_PENDING_POINTS
+            && self
+                .pending
+                .iter()
+                .all(|point| distance_to_segment(*point, anchor, coordinate) <= tolerance);
+        if !fits {
+            // The segment ends at the last point which it could cover
+            if let Some(end) = self.pending.pop() {
+                self.points.push(end);
+            }
+            self.pending.clear();
+        }
+        self.pending.push(coordinate);
+    }
+
+    /// The simplified trail, from the first location to the latest one.
+    pub(crate) fn coordinates(&self) -> Vec<GeographicCoordinate> {
+        self.points
+            .iter()
+            .chain(self.pending.last())
+            .copied()
+            .collect()
+    }
+}
+
+/// Finds the distance from a point to a segment, in meters.
+///
+/// Distances are measured on a plane tangent to the start of the segment,
+/// which is accurate enough for the short segments of a trail.
+fn distance_to_segment(
+    point: GeographicCoordinate,
+    start: GeographicCoordinate,
+    end: GeographicCoordinate,
+) -> f64 {
+    let scale = start.lat.to_radians().cos();
+    let project = |coordinate: GeographicCoordinate| Coord {
+        x: (coordinate.lng - start.lng) * scale * METERS_PER_DEGREE,
+        y: (coordinate.lat - start.lat) * METERS_PER_DEGREE,
+    };
+    Point::from(project(point)).euclidean_distance(&Line::new(project(start), project(end)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn coordinate(lat: f64, lng: f64) -> GeographicCoordinate {
+        GeographicCoordinate { lat, lng }
+    }
+
+    #[test]
+    fn simplifies_straight_lines() {
+        let mut trail = BreadcrumbTrail::new(BreadcrumbRecording::Simplified { tolerance: 5.0 });
+        // Heading east with a little GPS noise (0.00002 degrees is about 2m)
+        for (i, noise) in [0.0, 0.00002, -0.00001, 0.00002, 0.0]
+            .into_iter()
+            .enumerate()
+        {
+            trail.push(coordinate(noise, i as f64 * 0.001));
+        }
+        assert_eq!(
+            trail.coordinates(),
+            vec![coordinate(0.0, 0.0), coordinate(0.0, 0.004)]
+        );
+
+        // Then turning north keeps the corner
+        trail.push(coordinate(0.001, 0.004));
+        trail.push(coordinate(0.002, 0.004));
+        assert_eq!(
+            trail.coordinates(),
+            vec![
+                coordinate(0.0, 0.0),
+                coordinate(0.0, 0.004),
+                coordinate(0.002, 0.004)
+            ]
+        );
+    }
+
+    #[test]
+    fn disabled() {
+        let mut trail = BreadcrumbTrail::new(BreadcrumbRecording::Disabled);
+        trail.push(coordinate(0.0, 0.0));
+        trail.push(coordinate(0.0, 0.001));
+        assert!(trail.coordinates().is_empty());
+    }
+}
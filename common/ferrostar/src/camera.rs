@@ -0,0 +1,366 @@
+//! Helpers for driving the map camera during navigation.
+//!
+//! The platform layers are responsible for actually moving the camera,
+//! but the policy deciding *where* to look is shared here so that it behaves identically everywhere.
+
+use crate::models::{BoundingBox, GeographicCoordinate, Route};
+use crate::navigation_controller::models::TripState;
+use geo::{BoundingRect, EuclideanDistance, LineString, Point};
+
+/// A row in the zoom table of a [`CameraLookaheadPolicy`].
+///
+/// Zoom levels are linearly interpolated between rows.
+#[derive(Clone, Copy, Debug, PartialEq, uniffi::Record)]
+pub struct ManeuverZoomStop {
+    /// The distance to the next maneuver, in meters.
+    pub distance_to_maneuver: f64,
+    /// The map zoom level to use at this distance.
+    pub zoom: f64,
+}
+
+/// A row in the lookahead table of a [`CameraLookaheadPolicy`].
+///
+/// Lookahead distances are linearly interpolated between rows.
+#[derive(Clone, Copy, Debug, PartialEq, uniffi::Record)]
+pub struct SpeedLookaheadStop {
+    /// The user's speed, in meters per second.
+    pub speed: f64,
+    /// How far ahead of the user (along the route) the camera should look, in meters.
+    pub lookahead_distance: f64,
+}
+
+/// A data-driven policy describing how the camera should follow the user.
+///
+/// The policy is expressed as a pair of tables rather than code so that apps can tune it
+/// (or load it from remote configuration) without changing any logic.
+/// Rows do not need to be sorted, and values outside the table are clamped to the nearest row.
+#[derive(Clone, Debug, PartialEq, uniffi::Record)]
+pub struct CameraLookaheadPolicy {
+    /// Zoom levels keyed by the distance to the next maneuver.
+    ///
+    /// Typically the zoom increases as the maneuver gets closer.
+    pub zoom_table: Vec<ManeuverZoomStop>,
+    /// Lookahead distances keyed by the user's speed.
+    ///
+    /// Typically the lookahead increases with speed so that the user sees more of the road ahead.
+    pub lookahead_table: Vec<SpeedLookaheadStop>,
+}
+
+impl Default for CameraLookaheadPolicy {
+    fn default() -> Self {
+        Self {
+            zoom_table: vec![
+                ManeuverZoomStop {
+                    distance_to_maneuver: 50.0,
+                    zoom: 18.0,
+                },
+                ManeuverZoomStop {
+                    distance_to_maneuver: 200.0,
+                    zoom: 17.0,
+                },
+                ManeuverZoomStop {
+                    distance_to_maneuver: 1_000.0,
+                    zoom: 16.0,
+                },
+                ManeuverZoomStop {
+                    distance_to_maneuver: 5_000.0,
+                    zoom: 14.5,
+                },
+            ],
+            lookahead_table: vec![
+                SpeedLookaheadStop {
+                    speed: 0.0,
+                    lookahead_distance: 0.0,
+                },
+                // ~50 km/h
+                SpeedLookaheadStop {
+                    speed: 14.0,
+                    lookahead_distance: 100.0,
+                },
+                // ~110 km/h
+                SpeedLookaheadStop {
+                    speed: 30.0,
+                    lookahead_distance: 300.0,
+                },
+            ],
+        }
+    }
+}
+
+/// The camera parameters computed from a [`CameraLookaheadPolicy`].
+#[derive(Clone, Copy, Debug, PartialEq, uniffi::Record)]
+pub struct CameraLookahead {
+    /// The map zoom level.
+    pub zoom: f64,
+    /// How far ahead of the user (along the route) the camera should look, in meters.
+    pub lookahead_distance: f64,
+}
+
+impl CameraLookaheadPolicy {
+    /// Computes the camera parameters for the given trip progress.
+    ///
+    /// Returns `None` if either table is empty.
+    pub fn lookahead(&self, distance_to_maneuver: f64, speed: f64) -> Option<CameraLookahead> {
+        let zoom = interpolate(
+            self.zoom_table
+                .iter()
+                .map(|stop| (stop.distance_to_maneuver, stop.zoom)),
+            distance_to_maneuver,
+        )?;
+        let lookahead_distance = interpolate(
+            self.lookahead_table
+                .iter()
+                .map(|stop| (stop.speed, stop.lookahead_distance)),
+            speed,
+        )?;
+
+        Some(CameraLookahead {
+            zoom,
+            lookahead_distance,
+        })
+    }
+}
+
+/// Piecewise linear interpolation over a table of `(x, y)` pairs.
+///
+/// Inputs outside the range of the table are clamped to the first or last row.
+fn interpolate(table: impl Iterator<Item = (f64, f64)>, x: f64) -> Option<f64> {
+    let mut rows: Vec<_> = table.collect();
+    rows.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let (first, last) = (rows.first()?, rows.last()?);
+    if x <= first.0 {
+        return Some(first.1);
+    }
+    if x >= last.0 {
+        return Some(last.1);
+    }
+
+    rows.windows(2).find_map(|window| {
+        let ((x0, y0), (x1, y1)) = (window[0], window[1]);
+        // NOTE: x1 is strictly greater than x0 whenever this matches, so there is no division by zero
+        if x >= x0 && x < x1 {
+            Some(y0 + (y1 - y0) * (x - x0) / (x1 - x0))
+        } else {
+            None
+        }
+    })
+}
+
+/// Creates a [`CameraLookaheadPolicy`] with reasonable defaults for driving.
+///
+/// This is mostly useful as a starting point for apps which want to tweak individual rows.
+#[uniffi::export]
+pub fn create_default_camera_lookahead_policy() -> CameraLookaheadPolicy {
+    CameraLookaheadPolicy::default()
+}
+
+/// Computes the camera zoom and lookahead distance using the given policy.
+///
+/// A missing speed is treated as stationary.
+/// Returns `None` if the policy tables are empty.
+#[uniffi::export]
+pub fn calculate_camera_lookahead(
+    policy: &CameraLookaheadPolicy,
+    distance_to_maneuver: f64,
+    speed: Option<f64>,
+) -> Option<CameraLookahead> {
+    policy.lookahead(distance_to_maneuver, speed.unwrap_or(0.0))
+}
+
+/// Computes the bounding box for an "overview" of the full route.
+///
+/// The optional padding (in meters) is added on every side
+/// so that the route line is not drawn right up against the edge of the map.
+#[uniffi::export]
+pub fn calculate_route_overview_bounding_box(route: &Route, padding: Option<f64>) -> BoundingBox {
+    let bbox = route.bbox;
+    padding.map_or(bbox, |meters| bbox.padded(meters))
+}
+
+/// Computes the bounding box for an "overview" of the *remaining* route.
+///
+/// This includes the user's snapped location and everything that is left to travel,
+/// with optional padding (in meters) on every side.
+/// Returns `None` when the trip is complete.
+#[uniffi::export]
+pub fn calculate_remaining_route_overview_bounding_box(
+    trip_state: &TripState,
+    padding: Option<f64>,
+) -> Option<BoundingBox> {
+    let TripState::Navigating {
+        snapped_user_location,
+        remaining_steps,
+        ..
+    } = trip_state
+    else {
+        return None;
+    };
+
+    let remaining_coordinates =
+        remaining_steps
+            .split_first()
+            .map_or_else(Vec::new, |(current, rest)| {
+                let snapped_point = Point::from(snapped_user_location.coordinates);
+                let untraveled = untraveled_coordinates(&current.geometry, &snapped_point);
+                untraveled
+                    .iter()
+                    .chain(rest.iter().flat_map(|step| step.geometry.iter()))
+                    .copied()
+                    .collect()
+            });
+
+    let linestring: LineString = std::iter::once(snapped_user_location.coordinates)
+        .chain(remaining_coordinates)
+        .map(Point::from)
+        .collect();
+    let bbox = BoundingBox::from(linestring.bounding_rect()?);
+    Some(padding.map_or(bbox, |meters| bbox.padded(meters)))
+}
+
+/// Returns the coordinates of a step geometry which lie after the segment closest to `point`.
+fn untraveled_coordinates<'a>(
+    geometry: &'a [GeographicCoordinate],
+    point: &Point,
+) -> &'a [GeographicCoordinate] {
+    let linestring: LineString = geometry.iter().map(|coord| Point::from(*coord)).collect();
+    let closest_segment = linestring
+        .lines()
+        .enumerate()
+        .min_by(|(_, a), (_, b)| {
+            a.euclidean_distance(point)
+                .total_cmp(&b.euclidean_distance(point))
+        })
+        .map_or(0, |(index, _)| index);
+
+    // The end of the closest segment is the first coordinate that has not been passed yet
+    geometry.get(closest_segment + 1..).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deviation_detection::RouteDeviation;
+    use crate::navigation_controller::test_helpers::{
+        gen_dummy_route_step, gen_navigating_state, gen_route_from_steps, gen_user_location,
+    };
+
+    #[test]
+    fn zoom_increases_near_maneuver() {
+        let policy = CameraLookaheadPolicy::default();
+        let far = policy.lookahead(10_000.0, 0.0).unwrap();
+        let mid = policy.lookahead(600.0, 0.0).unwrap();
+        let near = policy.lookahead(10.0, 0.0).unwrap();
+
+        assert_eq!(far.zoom, 14.5);
+        assert_eq!(mid.zoom, 16.5);
+        assert_eq!(near.zoom, 18.0);
+    }
+
+    #[test]
+    fn lookahead_increases_with_speed() {
+        let policy = CameraLookaheadPolicy::default();
+
+        assert_eq!(
+            policy.lookahead(100.0, 0.0).unwrap().lookahead_distance,
+            0.0
+        );
+        assert_eq!(
+            policy.lookahead(100.0, 7.0).unwrap().lookahead_distance,
+            50.0
+        );
+        assert_eq!(
+            policy.lookahead(100.0, 22.0).unwrap().lookahead_distance,
+            200.0
+        );
+        assert_eq!(
+            policy.lookahead(100.0, 50.0).unwrap().lookahead_distance,
+            300.0
+        );
+    }
+
+    #[test]
+    fn unsorted_tables() {
+        let policy = CameraLookaheadPolicy {
+            zoom_table: vec![
+                ManeuverZoomStop {
+                    distance_to_maneuver: 100.0,
+                    zoom: 15.0,
+                },
+                ManeuverZoomStop {
+                    distance_to_maneuver: 0.0,
+                    zoom: 17.0,
+                },
+            ],
+            lookahead_table: vec![SpeedLookaheadStop {
+                speed: 10.0,
+                lookahead_distance: 42.0,
+            }],
+        };
+
+        let lookahead = policy.lookahead(50.0, 0.0).unwrap();
+        assert_eq!(lookahead.zoom, 16.0);
+        assert_eq!(lookahead.lookahead_distance, 42.0);
+    }
+
+    #[test]
+    fn empty_tables() {
+        let policy = CameraLookaheadPolicy {
+            zoom_table: vec![],
+            lookahead_table: vec![],
+        };
+
+        assert_eq!(calculate_camera_lookahead(&policy, 100.0, Some(10.0)), None);
+    }
+
+    #[test]
+    fn route_overview_padding() {
+        let route = gen_route_from_steps(vec![gen_dummy_route_step(0.0, 0.0, 1.0, 1.0)]);
+
+        let unpadded = calculate_route_overview_bounding_box(&route, None);
+        assert_eq!(unpadded, route.bbox);
+
+        let padded = calculate_route_overview_bounding_box(&route, Some(1_113.2));
+        assert!((padded.sw.lat - -0.01).abs() < 1e-9);
+        assert!(padded.sw.lng < -0.01);
+        assert!((padded.ne.lat - 1.01).abs() < 1e-9);
+        assert!(padded.ne.lng > 1.01);
+    }
+
+    #[test]
+    fn route_overview_padding_near_antimeridian() {
+        let route = gen_route_from_steps(vec![gen_dummy_route_step(179.95, 0.0, 179.99, 0.0)]);
+
+        let padded = calculate_route_overview_bounding_box(&route, Some(11_132.0));
+        assert!((padded.sw.lng - 179.85).abs() < 1e-9);
+        assert_eq!(padded.ne.lng, 180.0);
+
+        let route = gen_route_from_steps(vec![gen_dummy_route_step(-179.99, 0.0, -179.95, 0.0)]);
+        let padded = calculate_route_overview_bounding_box(&route, Some(11_132.0));
+        assert_eq!(padded.sw.lng, -180.0);
+        assert!((padded.ne.lng - -179.85).abs() < 1e-9);
+    }
+
+    #[test]
+    fn remaining_route_overview() {
+        let steps = vec![
+            gen_dummy_route_step(0.0, 0.0, 1.0, 0.0),
+            gen_dummy_route_step(1.0, 0.0, 1.0, 1.0),
+        ];
+        let state = gen_navigating_state(
+            gen_user_location(0.5, 0.0, 0),
+            steps,
+            RouteDeviation::NoDeviation,
+        );
+
+        let bbox = calculate_remaining_route_overview_bounding_box(&state, None)
+            .expect("Expected a bounding box");
+        assert_eq!(bbox.sw, GeographicCoordinate { lng: 0.5, lat: 0.0 });
+        assert_eq!(bbox.ne, GeographicCoordinate { lng: 1.0, lat: 1.0 });
+
+        assert_eq!(
+            calculate_remaining_route_overview_bounding_box(&TripState::Complete, None),
+            None
+        );
+    }
+}
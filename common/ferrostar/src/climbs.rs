@@ -0,0 +1,305 @@
+//! Categorized climbs along a route, for cycling computer-style UIs.
+//!
+//! Climbs are found by grouping sustained ascents in the [`ElevationProfile`],
+//! tolerating short dips and flat sections along the way.
+//! They are scored by multiplying the length (in meters) by the average grade (in percent)
+//! and categorized using the thresholds popularized by cycling apps and bike computers,
+//! which loosely follow the mountain categories of professional races.
+
+use crate::elevation::{grades, ElevationProfile, ElevationSample};
+#[cfg(feature = "wasm-js")]
+use serde::{Deserialize, Serialize};
+
+/// A climb ends when the elevation drops by more than this many meters below the top.
+const MAX_DIP: f64 = 10.0;
+/// A climb ends after this many meters without gaining any elevation.
+const MAX_PAUSE: f64 = 500.0;
+/// The minimum length of a climb, in meters.
+const MIN_LENGTH: f64 = 500.0;
+/// The minimum average grade of a climb, in percent.
+const MIN_AVERAGE_GRADE: f64 = 3.0;
+
+/// The difficulty of a climb, from easiest to hardest.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, uniffi::Enum)]
+#[cfg_attr(feature = "wasm-js", derive(Serialize, Deserialize))]
+pub enum ClimbCategory {
+    /// A noticeable climb which is too small to be categorized (score of at least 1,500).
+    Uncategorized,
+    /// Category 4 (score of at least 8,000).
+    Category4,
+    /// Category 3 (score of at least 16,000).
+    Category3,
+    /// Category 2 (score of at least 32,000).
+    Category2,
+    /// Category 1 (score of at least 64,000).
+    Category1,
+    /// Hors catégorie, the hardest climbs (score of at least 80,000).
+    HorsCategorie,
+}
+
+impl ClimbCategory {
+    /// Categorizes a climb by its score (length in meters times average grade in percent).
+    fn from_score(score: f64) -> Option<Self> {
+        match score {
+            score if score >= 80_000.0 => Some(ClimbCategory::HorsCategorie),
+            score if score >= 64_000.0 => Some(ClimbCategory::Category1),
+            score if score >= 32_000.0 => Some(ClimbCategory::Category2),
+            score if score >= 16_000.0 => Some(ClimbCategory::Category3),
+            score if score >= 8_000.0 => Some(ClimbCategory::Category4),
+            score if score >= 1_500.0 => Some(ClimbCategory::Uncategorized),
+            _ => None,
+        }
+    }
+}
+
+/// A sustained ascent along the route.
+#[derive(Debug, Clone, PartialEq, uniffi::Record)]
+#[cfg_attr(feature = "wasm-js", derive(Serialize, Deserialize))]
+pub struct Climb {
+    /// The distance from the start of the route to the bottom of the climb, in meters.
+    pub start_distance_along_route: f64,
+    /// The distance from the start of the route to the top of the climb, in meters.
+    pub end_distance_along_route: f64,
+    /// The length of the climb, in meters.
+    pub length: f64,
+    /// The elevation at the top of the climb, in meters.
+    pub summit_elevation: f64,
+    /// The elevation gained from the bottom to the top, in meters.
+    pub elevation_gain: f64,
+    /// The average grade, in percent.
+    pub average_grade: f64,
+    /// The steepest grade along the climb, in percent.
+    pub max_grade: f64,
+    pub category: ClimbCategory,
+}
+
+impl Climb {
+    /// Builds a climb between two samples, if it qualifies as one.
+    fn between(samples: &[ElevationSample], start: usize, end: usize) -> Option<Self> {
+        let (bottom, top) = (samples[start], samples[end]);
+        let length = top.distance_along_route - bottom.distance_along_route;
+        let elevation_gain = top.elevation - bottom.elevation;
+        if length < MIN_LENGTH {
+            return None;
+        }
+
+        let average_grade = elevation_gain / length * 100.0;
+        if average_grade < MIN_AVERAGE_GRADE {
+            return None;
+        }
+
+        Some(Self {
+            start_distance_along_route: bottom.distance_along_route,
+            end_distance_along_route: top.distance_along_route,
+            length,
+            summit_elevation: top.elevation,
+            elevation_gain,
+            average_grade,
+            max_grade: grades(&samples[start..=end])
+                .into_iter()
+                .fold(average_grade, f64::max),
+            category: ClimbCategory::from_score(length * average_grade)?,
+        })
+    }
+}
+
+/// A climb that the user is approaching (or currently riding up).
+#[derive(Debug, Clone, PartialEq, uniffi::Record)]
+#[cfg_attr(feature = "wasm-js", derive(Serialize, Deserialize))]
+pub struct UpcomingClimb {
+    pub climb: Climb,
+    /// The distance along the route to the bottom of the climb, in meters.
+    ///
+    /// This is zero once the user is on the climb.
+    pub distance_to_climb: f64,
+    /// The distance along the route to the top of the climb, in meters.
+    pub distance_to_summit: f64,
+    /// The elevation left to gain before the top of the climb, in meters.
+    pub elevation_gain_remaining: f64,
+}
+
+impl ElevationProfile {
+    /// Finds the climbs along the profile, in order.
+    pub fn climbs(&self) -> Vec<Climb> {
+        let samples = &self.samples;
+        let mut climbs = vec![];
+        let (mut start, mut top) = (0, 0);
+
+        for (index, sample) in samples.iter().enumerate().skip(1) {
+            let summit = samples[top];
+            if sample.elevation > summit.elevation {
+                top = index;
+            } else if summit.elevation - sample.elevation > MAX_DIP
+                || sample.distance_along_route - summit.distance_along_route > MAX_PAUSE
+            {
+                climbs.extend(Climb::between(samples, start, top));
+                (start, top) = (index, index);
+            } else if sample.elevation <= samples[start].elevation {
+                // Still heading down (or along) to the bottom of the next climb
+                (start, top) = (index, index);
+            }
+        }
+        if !samples.is_empty() {
+            climbs.extend(Climb::between(samples, start, top));
+        }
+
+        climbs
+    }
+
+    /// Finds the next climb (or the current one) within the alert distance (if any).
+    pub(crate) fn upcoming_climb(
+        &self,
+        climbs: &[Climb],
+        distance_along_route: f64,
+        alert_distance: f64,
+    ) -> Option<UpcomingClimb> {
+        let climb = climbs
+            .iter()
+            .find(|climb| climb.end_distance_along_route > distance_along_route)?;
+        let distance_to_climb = (climb.start_distance_along_route - distance_along_route).max(0.0);
+        if distance_to_climb > alert_distance {
+            return None;
+        }
+
+        let elevation = self
+            .elevation_at(distance_along_route.max(climb.start_distance_along_route))
+            .unwrap_or(climb.summit_elevation - climb.elevation_gain);
+        Some(UpcomingClimb {
+            climb: climb.clone(),
+            distance_to_climb,
+            distance_to_summit: climb.end_distance_along_route - distance_along_route,
+            elevation_gain_remaining: (climb.summit_elevation - elevation)
+                .clamp(0.0, climb.elevation_gain),
+        })
+    }
+}
+
+/// Finds the climbs along an elevation profile, in order.
+///
+/// This is useful for showing the climbs in a route overview before the trip starts.
+/// During navigation, the next climb is reported in the trip state
+/// when [`crate::navigation_controller::models::NavigationControllerConfig::climb_alert_distance`] is set.
+#[uniffi::export]
+pub fn find_climbs(profile: &ElevationProfile) -> Vec<Climb> {
+    profile.climbs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(points: &[(f64, f64)]) -> ElevationProfile {
+        ElevationProfile::from_samples(
+            points
+                .iter()
+                .map(|&(distance_along_route, elevation)| ElevationSample {
+                    distance_along_route,
+                    elevation,
+                })
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn categories() {
+        assert_eq!(ClimbCategory::from_score(1_000.0), None);
+        assert_eq!(
+            ClimbCategory::from_score(1_500.0),
+            Some(ClimbCategory::Uncategorized)
+        );
+        assert_eq!(
+            ClimbCategory::from_score(20_000.0),
+            Some(ClimbCategory::Category3)
+        );
+        assert_eq!(
+            ClimbCategory::from_score(100_000.0),
+            Some(ClimbCategory::HorsCategorie)
+        );
+        assert!(ClimbCategory::Category1 > ClimbCategory::Category2);
+    }
+
+    #[test]
+    fn single_climb() {
+        // Flat, then 2km at 5% (with a steep kick), then down again
+        let climbs = profile(&[
+            (0.0, 100.0),
+            (1000.0, 100.0),
+            (2000.0, 140.0),
+            (2500.0, 170.0),
+            (3000.0, 200.0),
+            (4000.0, 100.0),
+        ])
+        .climbs();
+
+        assert_eq!(
+            climbs,
+            vec![Climb {
+                start_distance_along_route: 1000.0,
+                end_distance_along_route: 3000.0,
+                length: 2000.0,
+                summit_elevation: 200.0,
+                elevation_gain: 100.0,
+                average_grade: 5.0,
+                max_grade: 6.0,
+                category: ClimbCategory::Category4,
+            }]
+        );
+    }
+
+    #[test]
+    fn short_dips_do_not_split_climbs() {
+        let climbs =
+            profile(&[(0.0, 0.0), (1000.0, 60.0), (1100.0, 55.0), (2000.0, 120.0)]).climbs();
+        assert_eq!(climbs.len(), 1);
+        assert_eq!(climbs[0].elevation_gain, 120.0);
+
+        // A longer descent separates two climbs
+        let climbs =
+            profile(&[(0.0, 0.0), (1000.0, 60.0), (1500.0, 30.0), (2500.0, 90.0)]).climbs();
+        assert_eq!(climbs.len(), 2);
+        assert_eq!(climbs[1].start_distance_along_route, 1500.0);
+        assert_eq!(climbs[1].elevation_gain, 60.0);
+
+        // As does a long flat section
+        let climbs =
+            profile(&[(0.0, 0.0), (1000.0, 60.0), (2000.0, 60.0), (3000.0, 120.0)]).climbs();
+        assert_eq!(climbs.len(), 2);
+        assert_eq!(climbs[1].start_distance_along_route, 2000.0);
+    }
+
+    #[test]
+    fn small_and_gentle_climbs_are_ignored() {
+        // Too short
+        assert!(profile(&[(0.0, 0.0), (400.0, 40.0)]).climbs().is_empty());
+        // Too gentle
+        assert!(profile(&[(0.0, 0.0), (5000.0, 100.0)]).climbs().is_empty());
+        assert!(find_climbs(&profile(&[])).is_empty());
+    }
+
+    #[test]
+    fn upcoming_climbs() {
+        let profile = profile(&[(0.0, 100.0), (1000.0, 100.0), (3000.0, 200.0)]);
+        let climbs = profile.climbs();
+
+        // Too far away
+        assert_eq!(profile.upcoming_climb(&climbs, 0.0, 500.0), None);
+
+        let upcoming = profile
+            .upcoming_climb(&climbs, 600.0, 500.0)
+            .expect("Expected an upcoming climb");
+        assert_eq!(upcoming.distance_to_climb, 400.0);
+        assert_eq!(upcoming.distance_to_summit, 2400.0);
+        assert_eq!(upcoming.elevation_gain_remaining, 100.0);
+
+        // Halfway up
+        let upcoming = profile
+            .upcoming_climb(&climbs, 2000.0, 500.0)
+            .expect("Expected an upcoming climb");
+        assert_eq!(upcoming.distance_to_climb, 0.0);
+        assert_eq!(upcoming.distance_to_summit, 1000.0);
+        assert_eq!(upcoming.elevation_gain_remaining, 50.0);
+
+        // Over the top
+        assert_eq!(profile.upcoming_climb(&climbs, 3000.0, 500.0), None);
+    }
+}
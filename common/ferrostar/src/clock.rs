@@ -0,0 +1,124 @@
+//! Sources of the current time.
+//!
+//! Most time-based logic works from location timestamps,
+//! but some of it needs the current time (ex: to reject stale locations).
+//! Reading the time through a [`Clock`] makes that logic deterministic in tests,
+//! and lets recorded sessions be replayed as they happened.
+//!
+//! The clock is set with [`crate::navigation_controller::models::NavigationControllerConfig::clock`].
+
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, SystemTime};
+
+/// Provides the current time.
+///
+/// This is called synchronously during location updates, so implementations must return quickly.
+#[uniffi::export(with_foreign)]
+pub trait Clock: Send + Sync {
+    fn now(&self) -> SystemTime;
+}
+
+/// The system clock, which is used when no other clock is configured.
+#[derive(Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        crate::now()
+    }
+}
+
+/// A clock which only changes when it is told to (ex: in tests).
+#[derive(Debug)]
+pub struct MockClock {
+    time: Mutex<SystemTime>,
+}
+
+impl MockClock {
+    pub fn new(time: SystemTime) -> Self {
+        Self {
+            time: Mutex::new(time),
+        }
+    }
+
+    pub fn set(&self, time: SystemTime) {
+        *self.lock_time() = time;
+    }
+
+    /// Moves the clock forward.
+    pub fn advance(&self, duration: Duration) {
+        let mut time = self.lock_time();
+        *time += duration;
+    }
+
+    fn lock_time(&self) -> MutexGuard<'_, SystemTime> {
+        self.time.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> SystemTime {
+        *self.lock_time()
+    }
+}
+
+/// A clock for replaying a recorded session,
+/// which follows the timestamps of the recorded locations rather than the system clock.
+///
+/// Call [`ReplayClock::observe`] with each location's timestamp before processing it,
+/// so that the location is as fresh as it was when it was recorded.
+#[derive(Debug)]
+pub struct ReplayClock {
+    time: Mutex<SystemTime>,
+}
+
+impl ReplayClock {
+    /// Creates a clock starting at the beginning of the recording.
+    pub fn new(start: SystemTime) -> Self {
+        Self {
+            time: Mutex::new(start),
+        }
+    }
+
+    /// Advances the clock to a recorded timestamp.
+    ///
+    /// The clock never goes backward, so out of order timestamps are ignored.
+    pub fn observe(&self, timestamp: SystemTime) {
+        let mut time = self.lock_time();
+        *time = (*time).max(timestamp);
+    }
+
+    fn lock_time(&self) -> MutexGuard<'_, SystemTime> {
+        self.time.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Clock for ReplayClock {
+    fn now(&self) -> SystemTime {
+        *self.lock_time()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mock_clock() {
+        let clock = MockClock::new(SystemTime::UNIX_EPOCH);
+        clock.advance(Duration::from_secs(5));
+        assert_eq!(clock.now(), SystemTime::UNIX_EPOCH + Duration::from_secs(5));
+        clock.set(SystemTime::UNIX_EPOCH);
+        assert_eq!(clock.now(), SystemTime::UNIX_EPOCH);
+    }
+
+    #[test]
+    fn replay_clock_never_goes_backward() {
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(100);
+        let clock = ReplayClock::new(start);
+        assert_eq!(clock.now(), start);
+        clock.observe(start + Duration::from_secs(10));
+        clock.observe(start + Duration::from_secs(5));
+        assert_eq!(clock.now(), start + Duration::from_secs(10));
+    }
+}
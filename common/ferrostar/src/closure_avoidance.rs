@@ -0,0 +1,249 @@
+//! Rerouting around road closures.
+//!
+//! Apps may learn of a closure which the routing backend does not know about yet
+//! (ex: the user taps "road closed", or incident data arrives from another source).
+//! The areas to avoid are collected in an [`AvoidedAreas`] set,
+//! which is shared with the request generator
+//! (see [`crate::routing_adapters::valhalla::ValhallaHttpRequestGenerator::with_avoided_areas`]),
+//! so that every route requested for the rest of the session avoids them,
+//! including ordinary reroutes.
+//! The [`ClosureRerouter`] records a closure and immediately requests a route around it.
+
+use crate::models::{GeographicCoordinate, Route};
+use crate::navigation_controller::models::TripState;
+use crate::routing_adapters::error::RouteFetchError;
+use crate::routing_adapters::http::{CancellationToken, HttpExecutor};
+use crate::routing_adapters::RouteAdapter;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+/// An area which routes should avoid.
+#[derive(Debug, Clone, PartialEq, uniffi::Enum)]
+pub enum AvoidArea {
+    /// The roads within a polygon (ex: the extent of a closure from incident data).
+    ///
+    /// The ring does not need to be closed.
+    Polygon { ring: Vec<GeographicCoordinate> },
+    /// The road nearest to a location (ex: where the user reported a closure).
+    Location { coordinate: GeographicCoordinate },
+}
+
+/// The areas which routes should avoid for the rest of the session.
+///
+/// This is shared between the request generator and the app,
+/// so areas which are added apply to all subsequent requests.
+#[derive(Debug, Default, uniffi::Object)]
+pub struct AvoidedAreas {
+    areas: Mutex<Vec<AvoidArea>>,
+}
+
+#[uniffi::export]
+impl AvoidedAreas {
+    #[uniffi::constructor]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an area to avoid. Areas which are already avoided are not added again.
+    pub fn add(&self, area: AvoidArea) {
+        let mut areas = self.lock_areas();
+        if !areas.contains(&area) {
+            areas.push(area);
+        }
+    }
+
+    /// Returns the areas to avoid, in the order they were added.
+    pub fn get_areas(&self) -> Vec<AvoidArea> {
+        self.lock_areas().clone()
+    }
+
+    /// Stops avoiding all areas (ex: when the trip ends, or the closures are lifted).
+    pub fn clear(&self) {
+        self.lock_areas().clear();
+    }
+}
+
+impl AvoidedAreas {
+    fn lock_areas(&self) -> MutexGuard<'_, Vec<AvoidArea>> {
+        self.areas.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Requests routes around closures as they are reported.
+///
+/// The route adapter's request generator must be configured with the same [`AvoidedAreas`];
+/// otherwise the backend will not know about the closures.
+#[derive(uniffi::Object)]
+pub struct ClosureRerouter {
+    route_adapter: Arc<RouteAdapter>,
+    executor: Arc<HttpExecutor>,
+    avoided_areas: Arc<AvoidedAreas>,
+}
+
+#[uniffi::export]
+impl ClosureRerouter {
+    #[uniffi::constructor]
+    pub fn new(
+        route_adapter: Arc<RouteAdapter>,
+        executor: Arc<HttpExecutor>,
+        avoided_areas: Arc<AvoidedAreas>,
+    ) -> Self {
+        Self {
+            route_adapter,
+            executor,
+            avoided_areas,
+        }
+    }
+
+    /// Avoids the closed area for the rest of the session,
+    /// and requests a route around it from the user's current position to the remaining waypoints.
+    ///
+    /// The closure is kept even if the request fails, so the next reroute avoids it.
+    /// Returns `None` if the trip is not in progress.
+    /// Switching to the new route is left to the app
+    /// (ex: via [`crate::navigation_controller::SynchronizedNavigationController::replace_route`]).
+    pub async fn reroute_around_closure(
+        &self,
+        closure: AvoidArea,
+        state: TripState,
+        cancellation_token: Option<Arc<CancellationToken>>,
+    ) -> Result<Option<Route>, RouteFetchError> {
+        self.avoided_areas.add(closure);
+        let TripState::Navigating {
+            snapped_user_location,
+            remaining_waypoints,
+            ..
+        } = state
+        else {
+            return Ok(None);
+        };
+
+        let route = self
+            .route_adapter
+            .get_route(
+                self.executor.clone(),
+                snapped_user_location,
+                remaining_waypoints,
+                cancellation_token,
+            )
+            .await?;
+        Ok(Some(route))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::create_osrm_response_parser;
+    use crate::models::{Waypoint, WaypointKind};
+    use crate::navigation_controller::models::NavigationControllerConfig;
+    use crate::navigation_controller::presets::NavigationPreset;
+    use crate::navigation_controller::test_helpers::{
+        gen_dummy_route_step, gen_route_from_steps, gen_user_location,
+    };
+    use crate::navigation_controller::SynchronizedNavigationController;
+    use crate::routing_adapters::http::test_helpers::{block_on, executor, MockHttpClient};
+    use crate::routing_adapters::valhalla::ValhallaHttpRequestGenerator;
+    use serde_json::{from_slice, json, Value as JsonValue};
+
+    const CLOSED_ROAD: GeographicCoordinate = GeographicCoordinate { lat: 0.5, lng: 0.5 };
+
+    fn setup() -> (ClosureRerouter, Arc<RouteAdapter>, Arc<MockHttpClient>) {
+        let avoided_areas = Arc::new(AvoidedAreas::new());
+        let route_adapter = Arc::new(RouteAdapter::new(
+            Arc::new(
+                ValhallaHttpRequestGenerator::new(
+                    "https://valhalla.example.com/route".to_string(),
+                    "auto".to_string(),
+                    None,
+                )
+                .with_avoided_areas(avoided_areas.clone()),
+            ),
+            create_osrm_response_parser(6),
+        ));
+        let client = MockHttpClient::osrm();
+        (
+            ClosureRerouter::new(
+                route_adapter.clone(),
+                executor(client.clone()),
+                avoided_areas,
+            ),
+            route_adapter,
+            client,
+        )
+    }
+
+    #[test]
+    fn avoided_areas_are_not_duplicated() {
+        let avoided_areas = AvoidedAreas::new();
+        let closure = AvoidArea::Location {
+            coordinate: CLOSED_ROAD,
+        };
+        avoided_areas.add(closure.clone());
+        avoided_areas.add(closure.clone());
+        assert_eq!(avoided_areas.get_areas(), vec![closure]);
+
+        avoided_areas.clear();
+        assert_eq!(avoided_areas.get_areas(), vec![]);
+    }
+
+    #[test]
+    fn closures_are_avoided_by_subsequent_reroutes() {
+        let (rerouter, route_adapter, client) = setup();
+        let controller = SynchronizedNavigationController::new(
+            gen_route_from_steps(vec![gen_dummy_route_step(0.0, 0.0, 1.0, 1.0)]),
+            NavigationControllerConfig::from_preset(NavigationPreset::Driving),
+            gen_user_location(0.0, 0.0, 0),
+        );
+
+        let route = block_on(rerouter.reroute_around_closure(
+            AvoidArea::Location {
+                coordinate: CLOSED_ROAD,
+            },
+            controller.get_state(),
+            None,
+        ))
+        .expect("Unable to reroute");
+        assert!(route.is_some());
+
+        // An ordinary reroute later in the session
+        block_on(route_adapter.get_route(
+            rerouter.executor.clone(),
+            gen_user_location(0.0, 0.0, 0),
+            vec![Waypoint {
+                coordinate: GeographicCoordinate { lat: 1.0, lng: 1.0 },
+                kind: WaypointKind::Break,
+            }],
+            None,
+        ))
+        .expect("Unable to reroute");
+
+        let bodies = client.request_bodies();
+        assert_eq!(bodies.len(), 2);
+        for body in bodies {
+            let body: JsonValue = from_slice(&body).expect("Failed to parse request body as JSON");
+            assert_eq!(
+                body["exclude_locations"],
+                json!([{ "lat": 0.5, "lon": 0.5 }])
+            );
+        }
+    }
+
+    #[test]
+    fn closure_is_kept_when_not_navigating() {
+        let (rerouter, _, client) = setup();
+        let closure = AvoidArea::Polygon {
+            ring: vec![
+                GeographicCoordinate { lat: 0.0, lng: 0.0 },
+                GeographicCoordinate { lat: 0.0, lng: 1.0 },
+                GeographicCoordinate { lat: 1.0, lng: 1.0 },
+            ],
+        };
+
+        let route =
+            block_on(rerouter.reroute_around_closure(closure.clone(), TripState::Complete, None))
+                .expect("Unable to reroute");
+        assert_eq!(route, None);
+        assert!(client.requests().is_empty());
+        assert_eq!(rerouter.avoided_areas.get_areas(), vec![closure]);
+    }
+}
@@ -0,0 +1,350 @@
+//! Fuel and energy consumption estimates.
+//!
+//! Consumption is estimated with a simple physical model of the vehicle
+//! (rolling resistance, aerodynamic drag, and climbing),
+//! using the average speed of each route step
+//! and the grade from the route's [`ElevationProfile`] when one is available.
+//! This is good enough to answer "will I make it?",
+//! but real-world consumption varies a lot with driving style, weather, and traffic.
+
+use crate::elevation::ElevationProfile;
+use crate::models::Route;
+#[cfg(feature = "wasm-js")]
+use serde::{Deserialize, Serialize};
+
+/// Standard gravity, in meters per second squared.
+const GRAVITY: f64 = 9.81;
+/// The density of air at sea level and 15°C, in kilograms per cubic meter.
+const AIR_DENSITY: f64 = 1.225;
+const JOULES_PER_KILOWATT_HOUR: f64 = 3_600_000.0;
+
+/// How the vehicle turns its energy source into motion.
+#[derive(Clone, Copy, Debug, PartialEq, uniffi::Enum)]
+pub enum Powertrain {
+    /// An internal combustion engine.
+    ///
+    /// Consumption is measured in liters of fuel.
+    /// No energy is recovered on descents, though no fuel is used either when coasting.
+    Combustion {
+        /// The fraction of the fuel's energy which reaches the wheels (typically 0.2 to 0.3).
+        efficiency: f64,
+        /// The energy content of the fuel, in kilowatt-hours per liter
+        /// (ex: 8.9 for gasoline or 10.0 for diesel).
+        fuel_energy_density: f64,
+    },
+    /// An electric motor.
+    ///
+    /// Consumption is measured in kilowatt-hours.
+    Electric {
+        /// The fraction of the battery's energy which reaches the wheels (typically 0.85 to 0.9).
+        efficiency: f64,
+        /// The fraction of braking energy which is recovered on descents (typically 0.5 to 0.7).
+        regeneration_efficiency: f64,
+    },
+}
+
+/// A simple description of a vehicle for consumption estimates.
+#[derive(Clone, Copy, Debug, PartialEq, uniffi::Record)]
+pub struct VehicleEnergyProfile {
+    /// The mass of the vehicle including passengers and cargo, in kilograms.
+    pub mass: f64,
+    /// The drag coefficient multiplied by the frontal area, in square meters
+    /// (typically 0.6 to 0.8 for cars).
+    pub drag_area: f64,
+    /// The rolling resistance coefficient (typically around 0.01 for cars on asphalt).
+    pub rolling_resistance: f64,
+    /// The constant power draw of accessories (ex: climate control), in kilowatts.
+    pub auxiliary_power: f64,
+    pub powertrain: Powertrain,
+    /// The fuel (in liters) or energy (in kilowatt-hours) available at the start of the route.
+    pub initial_energy: f64,
+}
+
+/// Estimated fuel or energy consumption along a route.
+///
+/// Amounts are in liters for [`Powertrain::Combustion`] and kilowatt-hours for [`Powertrain::Electric`].
+#[derive(Clone, Copy, Debug, PartialEq, uniffi::Record)]
+#[cfg_attr(feature = "wasm-js", derive(Serialize, Deserialize))]
+pub struct ConsumptionEstimate {
+    /// The amount used so far along the route.
+    pub used: f64,
+    /// The amount needed for the rest of the route.
+    pub remaining: f64,
+    /// The amount left on arrival at the destination.
+    ///
+    /// This is negative when the vehicle is not expected to make it.
+    pub at_arrival: f64,
+    /// The distance, in meters, that the vehicle can travel after arriving,
+    /// assuming the same average consumption as the route.
+    ///
+    /// This is `None` if the route does not consume any energy on average (ex: a long descent).
+    pub range_at_arrival: Option<f64>,
+}
+
+impl VehicleEnergyProfile {
+    /// Estimates the consumption along the route, given how far the user has traveled.
+    pub fn estimate(&self, route: &Route, distance_along_route: f64) -> ConsumptionEstimate {
+        let distance_along_route = distance_along_route.clamp(0.0, route.distance);
+        let used = self.consumption_between(route, 0.0, distance_along_route);
+        let remaining = self.consumption_between(route, distance_along_route, route.distance);
+        let at_arrival = self.initial_energy - used - remaining;
+
+        let consumption_per_meter = (used + remaining) / route.distance;
+        let range_at_arrival = if at_arrival <= 0.0 {
+            Some(0.0)
+        } else if consumption_per_meter > 0.0 && consumption_per_meter.is_finite() {
+            Some(at_arrival / consumption_per_meter)
+        } else {
+            None
+        };
+
+        ConsumptionEstimate {
+            used,
+            remaining,
+            at_arrival,
+            range_at_arrival,
+        }
+    }
+
+    /// Estimates the consumption between two distances along the route.
+    pub fn consumption_between(&self, route: &Route, from: f64, to: f64) -> f64 {
+        // The speed of each step, along with the distance at which it ends
+        let mut step_end = 0.0;
+        let steps: Vec<_> = route
+            .steps
+            .iter()
+            .map(|step| {
+                step_end += step.distance;
+                let speed = if step.duration > 0.0 {
+                    step.distance / step.duration
+                } else {
+                    0.0
+                };
+                (step_end, speed)
+            })
+            .collect();
+
+        // Split the route wherever the speed or grade may change
+        let mut breakpoints: Vec<f64> = steps
+            .iter()
+            .map(|&(end, _)| end)
+            .chain(
+                route
+                    .elevation_profile
+                    .iter()
+                    .flat_map(|profile| profile.samples.iter())
+                    .map(|sample| sample.distance_along_route),
+            )
+            .filter(|&distance| distance > from && distance < to)
+            .chain([from, to])
+            .collect();
+        breakpoints.sort_by(f64::total_cmp);
+        breakpoints.dedup();
+
+        breakpoints
+            .windows(2)
+            .map(|pair| {
+                let (start, end) = (pair[0], pair[1]);
+                let length = end - start;
+                if length <= 0.0 {
+                    return 0.0;
+                }
+
+                let midpoint = (start + end) / 2.0;
+                let speed = steps
+                    .iter()
+                    .find(|&&(step_end, _)| midpoint < step_end)
+                    .or(steps.last())
+                    .map_or(0.0, |&(_, speed)| speed);
+                let slope = route
+                    .elevation_profile
+                    .as_ref()
+                    .map_or(0.0, |profile| slope(profile, start, end));
+
+                self.segment_consumption(length, speed, slope)
+            })
+            .sum()
+    }
+
+    /// The consumption over a stretch of road driven at a constant speed (in meters per second).
+    fn segment_consumption(&self, length: f64, speed: f64, slope: f64) -> f64 {
+        // The force required at the wheels, in newtons
+        let force = self.mass * GRAVITY * (self.rolling_resistance + slope)
+            + 0.5 * AIR_DENSITY * self.drag_area * speed * speed;
+        let wheel_energy = force * length;
+        let auxiliary_energy = if speed > 0.0 {
+            self.auxiliary_power * 1000.0 * length / speed
+        } else {
+            0.0
+        };
+
+        match self.powertrain {
+            Powertrain::Combustion {
+                efficiency,
+                fuel_energy_density,
+            } => {
+                (wheel_energy.max(0.0) + auxiliary_energy)
+                    / efficiency
+                    / JOULES_PER_KILOWATT_HOUR
+                    / fuel_energy_density
+            }
+            Powertrain::Electric {
+                efficiency,
+                regeneration_efficiency,
+            } => {
+                let drive_energy = if wheel_energy > 0.0 {
+                    wheel_energy / efficiency
+                } else {
+                    wheel_energy * regeneration_efficiency
+                };
+                (drive_energy + auxiliary_energy) / JOULES_PER_KILOWATT_HOUR
+            }
+        }
+    }
+}
+
+/// The average slope (rise over run) between two distances along the route.
+fn slope(profile: &ElevationProfile, start: f64, end: f64) -> f64 {
+    match (profile.elevation_at(start), profile.elevation_at(end)) {
+        (Some(start_elevation), Some(end_elevation)) => {
+            (end_elevation - start_elevation) / (end - start)
+        }
+        _ => 0.0,
+    }
+}
+
+/// Estimates the fuel or energy consumption of a route before the trip starts.
+///
+/// During navigation, the estimate is included in the trip progress
+/// when [`crate::navigation_controller::models::NavigationControllerConfig::vehicle_energy_profile`] is set.
+#[uniffi::export]
+pub fn estimate_route_consumption(
+    route: &Route,
+    vehicle: &VehicleEnergyProfile,
+) -> ConsumptionEstimate {
+    vehicle.estimate(route, 0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::elevation::ElevationSample;
+    use crate::models::RouteStep;
+    use crate::navigation_controller::test_helpers::{gen_dummy_route_step, gen_route_from_steps};
+
+    const ELECTRIC_CAR: VehicleEnergyProfile = VehicleEnergyProfile {
+        mass: 2000.0,
+        drag_area: 0.6,
+        rolling_resistance: 0.01,
+        auxiliary_power: 0.0,
+        powertrain: Powertrain::Electric {
+            efficiency: 0.9,
+            regeneration_efficiency: 0.6,
+        },
+        initial_energy: 50.0,
+    };
+
+    const GASOLINE_CAR: VehicleEnergyProfile = VehicleEnergyProfile {
+        powertrain: Powertrain::Combustion {
+            efficiency: 0.25,
+            fuel_energy_density: 8.9,
+        },
+        ..ELECTRIC_CAR
+    };
+
+    /// A straight route of two steps, each 10km long, driven at 20 m/s.
+    fn route(elevations: Option<[f64; 3]>) -> Route {
+        let step = |start_lng: f64| RouteStep {
+            distance: 10_000.0,
+            duration: 500.0,
+            ..gen_dummy_route_step(start_lng, 0.0, start_lng + 0.09, 0.0)
+        };
+        let mut route = gen_route_from_steps(vec![step(0.0), step(0.09)]);
+        route.elevation_profile = elevations.map(|elevations| {
+            ElevationProfile::from_samples(
+                [0.0, 10_000.0, 20_000.0]
+                    .into_iter()
+                    .zip(elevations)
+                    .map(|(distance_along_route, elevation)| ElevationSample {
+                        distance_along_route,
+                        elevation,
+                    })
+                    .collect(),
+            )
+        });
+        route
+    }
+
+    fn flat_consumption_per_meter(vehicle: &VehicleEnergyProfile) -> f64 {
+        let force = vehicle.mass * GRAVITY * vehicle.rolling_resistance
+            + 0.5 * AIR_DENSITY * vehicle.drag_area * 20.0 * 20.0;
+        force / 0.9 / JOULES_PER_KILOWATT_HOUR
+    }
+
+    #[test]
+    fn flat_route() {
+        let route = route(None);
+        let estimate = ELECTRIC_CAR.estimate(&route, 5_000.0);
+        let per_meter = flat_consumption_per_meter(&ELECTRIC_CAR);
+
+        assert!((estimate.used - 5_000.0 * per_meter).abs() < 1e-9);
+        assert!((estimate.remaining - 15_000.0 * per_meter).abs() < 1e-9);
+        assert!((estimate.at_arrival - (50.0 - 20_000.0 * per_meter)).abs() < 1e-9);
+        assert!(
+            (estimate.range_at_arrival.unwrap() - estimate.at_arrival / per_meter).abs() < 1e-6
+        );
+        // A flat elevation profile is the same as none at all
+        assert_eq!(
+            estimate,
+            ELECTRIC_CAR.estimate(&self::route(Some([100.0, 100.0, 100.0])), 5_000.0)
+        );
+    }
+
+    #[test]
+    fn climbing_uses_more_energy() {
+        let flat = ELECTRIC_CAR.estimate(&route(None), 0.0);
+        let hill = ELECTRIC_CAR.estimate(&route(Some([0.0, 200.0, 0.0])), 0.0);
+        let climb = ELECTRIC_CAR.estimate(&route(Some([0.0, 200.0, 400.0])), 0.0);
+
+        // Regeneration recovers some, but not all, of the energy spent climbing the hill
+        assert!(hill.remaining > flat.remaining);
+        assert!(climb.remaining > hill.remaining);
+
+        // Climbing 400m takes at least the potential energy of lifting the car that high
+        let potential_energy = ELECTRIC_CAR.mass * GRAVITY * 400.0 / JOULES_PER_KILOWATT_HOUR;
+        assert!(climb.remaining - flat.remaining > potential_energy);
+    }
+
+    #[test]
+    fn combustion_engines_do_not_regenerate() {
+        // A descent steep enough that neither vehicle needs to use any energy
+        let descent = route(Some([2000.0, 1000.0, 0.0]));
+
+        let electric = ELECTRIC_CAR.estimate(&descent, 0.0);
+        assert!(electric.remaining < 0.0);
+        assert!(electric.at_arrival > ELECTRIC_CAR.initial_energy);
+        assert_eq!(electric.range_at_arrival, None);
+
+        let gasoline = GASOLINE_CAR.estimate(&descent, 0.0);
+        assert_eq!(gasoline.remaining, 0.0);
+        assert_eq!(gasoline.at_arrival, GASOLINE_CAR.initial_energy);
+    }
+
+    #[test]
+    fn fuel_consumption_and_range() {
+        let estimate = estimate_route_consumption(
+            &route(None),
+            &VehicleEnergyProfile {
+                auxiliary_power: 1.0,
+                initial_energy: 0.5,
+                ..GASOLINE_CAR
+            },
+        );
+
+        // Roughly 5 liters per 100km
+        assert!((estimate.remaining - 0.98).abs() < 0.01);
+        assert_eq!(estimate.used, 0.0);
+        assert!(estimate.at_arrival < 0.0);
+        assert_eq!(estimate.range_at_arrival, Some(0.0));
+    }
+}
@@ -1,12 +1,14 @@
-use crate::algorithms::deviation_from_line;
-use crate::models::{Route, RouteStep, UserLocation};
-use geo::Point;
+use crate::algorithms::{deviation_from_line, DistanceMeasurement};
+use crate::models::{GeographicCoordinate, Route, RouteStep, UserLocation};
+use geo::{EuclideanDistance, HaversineBearing, LineString, Point};
 use std::sync::Arc;
 
+#[cfg(feature = "wasm-js")]
+use serde::{Deserialize, Serialize};
 #[cfg(test)]
 use {
     crate::{
-        models::GeographicCoordinate,
+        models::CourseOverGround,
         navigation_controller::test_helpers::{gen_dummy_route_step, gen_route_from_steps},
     },
     proptest::prelude::*,
@@ -43,6 +45,7 @@ impl RouteDeviationTracking {
         location: UserLocation,
         route: &Route,
         current_route_step: &RouteStep,
+        measurement: DistanceMeasurement,
     ) -> RouteDeviation {
         match self {
             RouteDeviationTracking::None => RouteDeviation::NoDeviation,
@@ -56,6 +59,7 @@ impl RouteDeviationTracking {
                     deviation_from_line(
                         &Point::from(location),
                         &current_route_step.get_linestring(),
+                        measurement,
                     )
                     .map_or(RouteDeviation::NoDeviation, |deviation| {
                         if deviation > 0.0 && deviation > *max_acceptable_deviation {
@@ -80,8 +84,8 @@ impl RouteDeviationTracking {
 /// Status information that describes whether the user is proceeding according to the route or not.
 ///
 /// Note that the name is intentionally a bit generic to allow for expansion of other states.
-/// For example, we could conceivably add a "wrong way" status in the future.
 #[derive(Debug, Copy, Clone, PartialEq, uniffi::Enum)]
+#[cfg_attr(feature = "wasm-js", derive(Serialize, Deserialize))]
 pub enum RouteDeviation {
     /// The user is proceeding on course within the expected tolerances; everything is normal.
     NoDeviation,
@@ -90,6 +94,109 @@ pub enum RouteDeviation {
         /// The deviation from the route line, in meters.
         deviation_from_route_line: f64,
     },
+    /// The user is following the route line, but traveling in the opposite direction.
+    ///
+    /// Unlike [`RouteDeviation::OffRoute`], a new route is not necessarily required;
+    /// this is typically an occasion to prompt the user to make a U-turn.
+    WrongDirection {
+        /// The distance that the user has traveled in the wrong direction, in meters.
+        distance_in_wrong_direction: f64,
+    },
+}
+
+/// Determines if the user is traveling along the route in the wrong direction.
+///
+/// This is checked only when the user is not otherwise off route.
+#[derive(Debug, Copy, Clone, PartialEq, uniffi::Enum)]
+pub enum WrongDirectionDetection {
+    /// No checks will be done.
+    None,
+    /// Compares the user's course over ground with the bearing of the nearest route segment.
+    CourseOverGround {
+        /// The minimum difference between the course and the segment bearing, in degrees,
+        /// for the user to be considered heading in the wrong direction.
+        ///
+        /// Values around 135 work well in practice; 180 means exactly opposite.
+        min_bearing_difference: u16,
+        /// The maximum acceptable accuracy of the course over ground, in degrees.
+        /// Locations with less accurate courses (or with no course) reset the detection.
+        max_course_accuracy: u16,
+        /// The distance the user must travel in the wrong direction, in meters,
+        /// before [`RouteDeviation::WrongDirection`] is reported.
+        min_distance: f64,
+    },
+}
+
+/// The result of checking for travel in the wrong direction.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) enum WrongDirectionStatus {
+    /// The user is heading in the expected direction (or we can't tell).
+    CorrectDirection,
+    /// The user is heading in the wrong direction, but has not gone far enough to report it.
+    Reversing {
+        /// Where the user first started heading in the wrong direction.
+        since: GeographicCoordinate,
+    },
+    /// The user has traveled far enough in the wrong direction to report it.
+    WrongDirection {
+        /// Where the user first started heading in the wrong direction.
+        since: GeographicCoordinate,
+        /// The distance from that point, in meters.
+        distance: f64,
+    },
+}
+
+impl WrongDirectionDetection {
+    /// Checks whether the user is traveling along the line in the wrong direction.
+    ///
+    /// `since` is where the user first started heading in the wrong direction (if they are already doing so).
+    #[must_use]
+    pub(crate) fn check_wrong_direction(
+        &self,
+        location: &UserLocation,
+        line: &LineString,
+        since: Option<GeographicCoordinate>,
+        measurement: DistanceMeasurement,
+    ) -> WrongDirectionStatus {
+        match self {
+            WrongDirectionDetection::None => WrongDirectionStatus::CorrectDirection,
+            WrongDirectionDetection::CourseOverGround {
+                min_bearing_difference,
+                max_course_accuracy,
+                min_distance,
+            } => {
+                let Some(course) = location.course_over_ground else {
+                    return WrongDirectionStatus::CorrectDirection;
+                };
+                if course.accuracy.unwrap_or(0) > *max_course_accuracy {
+                    return WrongDirectionStatus::CorrectDirection;
+                }
+
+                let point = Point::from(*location);
+                let Some(segment) = line.lines().min_by(|a, b| {
+                    a.euclidean_distance(&point)
+                        .total_cmp(&b.euclidean_distance(&point))
+                }) else {
+                    return WrongDirectionStatus::CorrectDirection;
+                };
+                let segment_bearing = segment.start_point().haversine_bearing(segment.end_point());
+                let difference = (f64::from(course.degrees) - segment_bearing).rem_euclid(360.0);
+                let difference = difference.min(360.0 - difference);
+
+                if difference >= f64::from(*min_bearing_difference) {
+                    let since = since.unwrap_or(location.coordinates);
+                    let distance = measurement.distance(&point, &Point::from(since));
+                    if distance >= *min_distance {
+                        WrongDirectionStatus::WrongDirection { since, distance }
+                    } else {
+                        WrongDirectionStatus::Reversing { since }
+                    }
+                } else {
+                    WrongDirectionStatus::CorrectDirection
+                }
+            }
+        }
+    }
 }
 
 #[uniffi::export(with_foreign)]
@@ -132,10 +239,12 @@ proptest! {
             horizontal_accuracy: 0.0,
             course_over_ground: None,
             timestamp: SystemTime::now(),
-            speed: None
+            speed: None,
+            altitude: None,
+            vertical_accuracy: None,
         };
         prop_assert_eq!(
-            tracking.check_route_deviation(user_location_on_route, &route, &current_route_step),
+            tracking.check_route_deviation(user_location_on_route, &route, &current_route_step, DistanceMeasurement::Haversine),
             RouteDeviation::NoDeviation
         );
 
@@ -149,10 +258,12 @@ proptest! {
             horizontal_accuracy: 0.0,
             course_over_ground: None,
             timestamp: SystemTime::now(),
-            speed: None
+            speed: None,
+            altitude: None,
+            vertical_accuracy: None,
         };
         prop_assert_eq!(
-            tracking.check_route_deviation(user_location_random, &route, &current_route_step),
+            tracking.check_route_deviation(user_location_random, &route, &current_route_step, DistanceMeasurement::Haversine),
             RouteDeviation::NoDeviation
         );
     }
@@ -194,10 +305,12 @@ proptest! {
             horizontal_accuracy: 0.0,
             course_over_ground: None,
             timestamp: SystemTime::now(),
-            speed: None
+            speed: None,
+            altitude: None,
+            vertical_accuracy: None,
         };
         prop_assert_eq!(
-            tracking.check_route_deviation(user_location_on_route, &route, &current_route_step),
+            tracking.check_route_deviation(user_location_on_route, &route, &current_route_step, DistanceMeasurement::Haversine),
             RouteDeviation::NoDeviation
         );
 
@@ -211,10 +324,12 @@ proptest! {
             horizontal_accuracy: 0.0,
             course_over_ground: None,
             timestamp: SystemTime::now(),
-            speed: None
+            speed: None,
+            altitude: None,
+            vertical_accuracy: None,
         };
         prop_assert_eq!(
-            tracking.check_route_deviation(user_location_random, &route, &current_route_step),
+            tracking.check_route_deviation(user_location_random, &route, &current_route_step, DistanceMeasurement::Haversine),
             RouteDeviation::NoDeviation
         );
     }
@@ -257,10 +372,12 @@ proptest! {
             horizontal_accuracy: 0.0,
             course_over_ground: None,
             timestamp: SystemTime::now(),
-            speed: None
+            speed: None,
+            altitude: None,
+            vertical_accuracy: None,
         };
         prop_assert_eq!(
-            tracking.check_route_deviation(user_location_on_route, &route, &current_route_step),
+            tracking.check_route_deviation(user_location_on_route, &route, &current_route_step, DistanceMeasurement::Haversine),
             RouteDeviation::OffRoute {
                 deviation_from_route_line: 7.0
             }
@@ -276,10 +393,12 @@ proptest! {
             horizontal_accuracy: 0.0,
             course_over_ground: None,
             timestamp: SystemTime::now(),
-            speed: None
+            speed: None,
+            altitude: None,
+            vertical_accuracy: None,
         };
         prop_assert_eq!(
-            tracking.check_route_deviation(user_location_random, &route, &current_route_step),
+            tracking.check_route_deviation(user_location_random, &route, &current_route_step, DistanceMeasurement::Haversine),
             RouteDeviation::OffRoute {
                 deviation_from_route_line: 7.0
             }
@@ -314,10 +433,12 @@ proptest! {
             horizontal_accuracy,
             course_over_ground: None,
             timestamp: SystemTime::now(),
-            speed: None
+            speed: None,
+            altitude: None,
+            vertical_accuracy: None,
         };
         prop_assert_eq!(
-            tracking.check_route_deviation(user_location_on_route, &route, &current_route_step),
+            tracking.check_route_deviation(user_location_on_route, &route, &current_route_step, DistanceMeasurement::Haversine),
             RouteDeviation::NoDeviation
         );
 
@@ -333,10 +454,12 @@ proptest! {
             horizontal_accuracy: 0.0,
             course_over_ground: None,
             timestamp: SystemTime::now(),
-            speed: None
+            speed: None,
+            altitude: None,
+            vertical_accuracy: None,
         };
-        let deviation = deviation_from_line(&Point::from(coordinates), &current_route_step.get_linestring());
-        match tracking.check_route_deviation(user_location_random, &route, &current_route_step) {
+        let deviation = deviation_from_line(&Point::from(coordinates), &current_route_step.get_linestring(), DistanceMeasurement::Haversine);
+        match tracking.check_route_deviation(user_location_random, &route, &current_route_step, DistanceMeasurement::Haversine) {
             RouteDeviation::NoDeviation => {
                 if let Some(calculated) = deviation {
                     prop_assert!(calculated <= max_acceptable_deviation);
@@ -348,6 +471,9 @@ proptest! {
                     deviation.unwrap()
                 );
             }
+            RouteDeviation::WrongDirection { .. } => {
+                prop_assert!(false, "Static thresholds never report the wrong direction");
+            }
         }
     }
 
@@ -377,11 +503,90 @@ proptest! {
             horizontal_accuracy: horizontal_accuracy as f64,
             course_over_ground: None,
             timestamp: SystemTime::now(),
-            speed: None
+            speed: None,
+            altitude: None,
+            vertical_accuracy: None,
         };
         prop_assert_eq!(
-            tracking.check_route_deviation(user_location_random, &route, &current_route_step),
+            tracking.check_route_deviation(user_location_random, &route, &current_route_step, DistanceMeasurement::Haversine),
             RouteDeviation::NoDeviation
         );
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::navigation_controller::test_helpers::gen_user_location;
+
+    #[test]
+    fn wrong_direction() {
+        let detection = WrongDirectionDetection::CourseOverGround {
+            min_bearing_difference: 135,
+            max_course_accuracy: 30,
+            min_distance: 50.0,
+        };
+        // Heading due east
+        let line = gen_dummy_route_step(0.0, 0.0, 0.01, 0.0).get_linestring();
+
+        // Following the route
+        assert_eq!(
+            detection.check_wrong_direction(
+                &gen_user_location(0.005, 0.0, 0).with_course(CourseOverGround::new(95, Some(10))),
+                &line,
+                None,
+                DistanceMeasurement::Haversine
+            ),
+            WrongDirectionStatus::CorrectDirection
+        );
+        // Heading west; starts tracking
+        let since = GeographicCoordinate {
+            lat: 0.0,
+            lng: 0.005,
+        };
+        assert_eq!(
+            detection.check_wrong_direction(
+                &gen_user_location(0.005, 0.0, 0).with_course(CourseOverGround::new(270, Some(10))),
+                &line,
+                None,
+                DistanceMeasurement::Haversine
+            ),
+            WrongDirectionStatus::Reversing { since }
+        );
+        // Still heading west after ~111m
+        match detection.check_wrong_direction(
+            &gen_user_location(0.004, 0.0, 0).with_course(CourseOverGround::new(265, Some(10))),
+            &line,
+            Some(since),
+            DistanceMeasurement::Haversine,
+        ) {
+            WrongDirectionStatus::WrongDirection {
+                since: actual_since,
+                distance,
+            } => {
+                assert_eq!(actual_since, since);
+                assert!(distance > 100.0);
+            }
+            status => panic!("Expected the wrong direction; got {status:?}"),
+        }
+        // Inaccurate or missing courses are ignored
+        assert_eq!(
+            detection.check_wrong_direction(
+                &gen_user_location(0.004, 0.0, 0).with_course(CourseOverGround::new(270, Some(90))),
+                &line,
+                Some(since),
+                DistanceMeasurement::Haversine
+            ),
+            WrongDirectionStatus::CorrectDirection
+        );
+        assert_eq!(
+            detection.check_wrong_direction(
+                &gen_user_location(0.004, 0.0, 0),
+                &line,
+                Some(since),
+                DistanceMeasurement::Haversine
+            ),
+            WrongDirectionStatus::CorrectDirection
+        );
+    }
+}
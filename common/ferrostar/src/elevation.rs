@@ -0,0 +1,442 @@
+//! Elevation profiles and climb statistics.
+//!
+//! Most routing APIs do not include elevation in their turn-by-turn responses,
+//! so profiles are typically built from a secondary source
+//! (ex: the Valhalla `/height` endpoint, the elevations in a GPX track,
+//! or the 3D geometry returned by openrouteservice when `elevation` is enabled).
+
+use crate::models::GeographicCoordinate;
+use crate::routing_adapters::error::RoutingResponseParseError;
+use geo::{HaversineDistance, Point};
+use serde::{Deserialize, Serialize};
+
+/// Grades are computed over windows of at least this length (in meters),
+/// as the grade between two closely spaced samples is mostly noise.
+const MIN_GRADE_WINDOW: f64 = 25.0;
+
+/// The elevation at a point along a route.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize, uniffi::Record)]
+pub struct ElevationSample {
+    /// The distance from the start of the route, in meters.
+    pub distance_along_route: f64,
+    /// The elevation above sea level, in meters.
+    pub elevation: f64,
+}
+
+/// The elevation profile of a route along with some summary statistics.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, uniffi::Record)]
+pub struct ElevationProfile {
+    /// Elevation samples, ordered by distance along the route.
+    pub samples: Vec<ElevationSample>,
+    /// The sum of all elevation gains, in meters.
+    pub total_ascent: f64,
+    /// The sum of all elevation losses, in meters (as a positive number).
+    pub total_descent: f64,
+    /// The steepest uphill grade, in percent.
+    pub max_grade: f64,
+    /// The steepest downhill grade, in percent (as a negative number).
+    pub min_grade: f64,
+}
+
+impl ElevationProfile {
+    /// Builds a profile from samples, computing the summary statistics.
+    ///
+    /// Samples are sorted by distance, and invalid (NaN or infinite) values are discarded.
+    pub fn from_samples(mut samples: Vec<ElevationSample>) -> Self {
+        samples.retain(|sample| {
+            sample.distance_along_route.is_finite() && sample.elevation.is_finite()
+        });
+        samples.sort_by(|a, b| a.distance_along_route.total_cmp(&b.distance_along_route));
+
+        let (total_ascent, total_descent) =
+            samples
+                .windows(2)
+                .fold((0.0, 0.0), |(ascent, descent), window| {
+                    let delta = window[1].elevation - window[0].elevation;
+                    if delta > 0.0 {
+                        (ascent + delta, descent)
+                    } else {
+                        (ascent, descent - delta)
+                    }
+                });
+
+        let (max_grade, min_grade) = grades(&samples)
+            .into_iter()
+            .fold((0f64, 0f64), |(max, min), grade| {
+                (max.max(grade), min.min(grade))
+            });
+
+        Self {
+            samples,
+            total_ascent,
+            total_descent,
+            max_grade,
+            min_grade,
+        }
+    }
+
+    /// Interpolates the elevation at a distance along the route.
+    ///
+    /// Returns `None` if the profile has no samples.
+    pub fn elevation_at(&self, distance_along_route: f64) -> Option<f64> {
+        let first = self.samples.first()?;
+        let last = self.samples.last()?;
+        if distance_along_route <= first.distance_along_route {
+            return Some(first.elevation);
+        }
+        if distance_along_route >= last.distance_along_route {
+            return Some(last.elevation);
+        }
+
+        self.samples.windows(2).find_map(|window| {
+            let (a, b) = (window[0], window[1]);
+            if distance_along_route >= a.distance_along_route
+                && distance_along_route < b.distance_along_route
+            {
+                let fraction = (distance_along_route - a.distance_along_route)
+                    / (b.distance_along_route - a.distance_along_route);
+                Some(a.elevation + fraction * (b.elevation - a.elevation))
+            } else {
+                None
+            }
+        })
+    }
+}
+
+/// An optional adjustment to the estimated duration remaining which accounts for hills.
+///
+/// Most routing engines do a decent job of estimating travel times for motor vehicles,
+/// but many ignore slope for pedestrians and cyclists, where it matters the most.
+/// These models have no effect unless the route has an [`ElevationProfile`].
+#[derive(Clone, Copy, Debug, PartialEq, uniffi::Enum)]
+pub enum GradeEtaAdjustment {
+    /// The duration reported by the routing engine is used as-is.
+    None,
+    /// Scales the remaining duration using Tobler's hiking function.
+    ///
+    /// This is well suited to pedestrian navigation, as it slows the user down on steep ascents
+    /// *and* steep descents, with the fastest pace on a gentle downhill.
+    Tobler,
+    /// Adds a fixed amount of time for every meter of ascent remaining (Naismith's rule).
+    ///
+    /// The classic rule for walkers is 6 seconds per meter; cyclists typically use something lower.
+    Naismith {
+        /// The extra time required per meter of ascent, in seconds.
+        seconds_per_meter_of_ascent: f64,
+    },
+}
+
+impl GradeEtaAdjustment {
+    /// Adjusts the duration remaining given how far the user has traveled along the route.
+    pub fn adjust_duration(
+        &self,
+        profile: Option<&ElevationProfile>,
+        distance_along_route: f64,
+        duration_remaining: f64,
+    ) -> f64 {
+        let Some(profile) = profile else {
+            return duration_remaining;
+        };
+
+        match self {
+            GradeEtaAdjustment::None => duration_remaining,
+            GradeEtaAdjustment::Tobler => profile
+                .tobler_time_factor(distance_along_route)
+                .map_or(duration_remaining, |factor| duration_remaining * factor),
+            GradeEtaAdjustment::Naismith {
+                seconds_per_meter_of_ascent,
+            } => {
+                duration_remaining
+                    + profile.ascent_remaining(distance_along_route) * seconds_per_meter_of_ascent
+            }
+        }
+    }
+}
+
+/// Tobler's hiking function, which estimates walking speed (in km/h) given a slope (rise over run).
+fn tobler_speed(slope: f64) -> f64 {
+    6.0 * (-3.5 * (slope + 0.05).abs()).exp()
+}
+
+impl ElevationProfile {
+    /// The portions of the profile after `distance_along_route` as (length, slope) pairs.
+    fn remaining_segments(
+        &self,
+        distance_along_route: f64,
+    ) -> impl Iterator<Item = (f64, f64)> + '_ {
+        let start_elevation = self.elevation_at(distance_along_route);
+        self.samples.windows(2).filter_map(move |window| {
+            let (a, b) = (window[0], window[1]);
+            if b.distance_along_route <= distance_along_route {
+                return None;
+            }
+
+            // Clip the segment that the user is currently on
+            let (start_distance, start_elevation) = if a.distance_along_route < distance_along_route
+            {
+                (distance_along_route, start_elevation?)
+            } else {
+                (a.distance_along_route, a.elevation)
+            };
+
+            let length = b.distance_along_route - start_distance;
+            if length > 0.0 {
+                Some((length, (b.elevation - start_elevation) / length))
+            } else {
+                None
+            }
+        })
+    }
+
+    /// The total ascent (in meters) remaining after a distance along the route.
+    pub fn ascent_remaining(&self, distance_along_route: f64) -> f64 {
+        self.remaining_segments(distance_along_route)
+            .map(|(length, slope)| (length * slope).max(0.0))
+            .sum()
+    }
+
+    /// The ratio of the time it takes to walk the rest of the route
+    /// to the time it would take if the route were flat, according to Tobler's hiking function.
+    ///
+    /// Returns `None` if no part of the profile remains.
+    fn tobler_time_factor(&self, distance_along_route: f64) -> Option<f64> {
+        let flat_speed = tobler_speed(0.0);
+        let (total_length, weighted_length) = self.remaining_segments(distance_along_route).fold(
+            (0.0, 0.0),
+            |(total, weighted), (length, slope)| {
+                (
+                    total + length,
+                    weighted + length * flat_speed / tobler_speed(slope),
+                )
+            },
+        );
+
+        if total_length > 0.0 {
+            Some(weighted_length / total_length)
+        } else {
+            None
+        }
+    }
+}
+
+/// Computes the grades (in percent) over consecutive windows of at least [`MIN_GRADE_WINDOW`] meters.
+pub(crate) fn grades(samples: &[ElevationSample]) -> Vec<f64> {
+    let mut result = vec![];
+    let Some(mut window_start) = samples.first() else {
+        return result;
+    };
+
+    for sample in &samples[1..] {
+        let distance = sample.distance_along_route - window_start.distance_along_route;
+        if distance >= MIN_GRADE_WINDOW {
+            result.push((sample.elevation - window_start.elevation) / distance * 100.0);
+            window_start = sample;
+        }
+    }
+
+    result
+}
+
+/// Builds an elevation profile from a sequence of coordinates and their elevations.
+///
+/// This is suitable for GPX tracks and 3D geometries (ex: openrouteservice with `elevation=true`),
+/// where each coordinate has a known elevation.
+/// Distances are computed using the haversine formula.
+#[uniffi::export]
+pub fn elevation_profile_from_coordinates(
+    coordinates: Vec<GeographicCoordinate>,
+    elevations: Vec<f64>,
+) -> Result<ElevationProfile, RoutingResponseParseError> {
+    if coordinates.len() != elevations.len() {
+        return Err(RoutingResponseParseError::MismatchedElevations {
+            coordinates: u32::try_from(coordinates.len()).unwrap_or(u32::MAX),
+            elevations: u32::try_from(elevations.len()).unwrap_or(u32::MAX),
+        });
+    }
+
+    let mut distance_along_route = 0.0;
+    let mut previous: Option<Point> = None;
+    let samples = coordinates
+        .into_iter()
+        .zip(elevations)
+        .map(|(coordinate, elevation)| {
+            let point = Point::from(coordinate);
+            if let Some(previous) = previous {
+                distance_along_route += previous.haversine_distance(&point);
+            }
+            previous = Some(point);
+
+            ElevationSample {
+                distance_along_route,
+                elevation,
+            }
+        })
+        .collect();
+
+    Ok(ElevationProfile::from_samples(samples))
+}
+
+/// The subset of a Valhalla `/height` response that we care about.
+///
+/// See <https://valhalla.github.io/valhalla/api/elevation/api-reference/>.
+#[derive(Deserialize, Debug)]
+struct ValhallaHeightResponse {
+    /// Pairs of (cumulative distance, height) when the request was made with `range: true`.
+    range_height: Vec<(f64, Option<f64>)>,
+}
+
+/// Parses a response from the Valhalla `/height` endpoint into an elevation profile.
+///
+/// The request must have been made with `range: true`;
+/// the usual approach is to send the route geometry as the `encoded_polyline`.
+/// Points with no elevation data are skipped.
+#[uniffi::export]
+pub fn parse_valhalla_height_response(
+    response: &[u8],
+) -> Result<ElevationProfile, RoutingResponseParseError> {
+    let res: ValhallaHeightResponse = serde_json::from_slice(response)?;
+    let samples = res
+        .range_height
+        .into_iter()
+        .filter_map(|(distance_along_route, elevation)| {
+            elevation.map(|elevation| ElevationSample {
+                distance_along_route,
+                elevation,
+            })
+        })
+        .collect();
+    Ok(ElevationProfile::from_samples(samples))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(distance_along_route: f64, elevation: f64) -> ElevationSample {
+        ElevationSample {
+            distance_along_route,
+            elevation,
+        }
+    }
+
+    #[test]
+    fn climb_statistics() {
+        let profile = ElevationProfile::from_samples(vec![
+            sample(0.0, 100.0),
+            sample(100.0, 110.0),
+            sample(200.0, 105.0),
+            sample(300.0, 125.0),
+            sample(400.0, 125.0),
+        ]);
+
+        assert_eq!(profile.total_ascent, 30.0);
+        assert_eq!(profile.total_descent, 5.0);
+        assert_eq!(profile.max_grade, 20.0);
+        assert_eq!(profile.min_grade, -5.0);
+    }
+
+    #[test]
+    fn short_windows_are_merged() {
+        // A 1m jump over 1m would be a 100% grade if not for the window
+        let profile = ElevationProfile::from_samples(vec![
+            sample(0.0, 100.0),
+            sample(1.0, 101.0),
+            sample(50.0, 101.0),
+        ]);
+
+        assert_eq!(profile.max_grade, 2.0);
+    }
+
+    #[test]
+    fn interpolate_elevation() {
+        let profile = ElevationProfile::from_samples(vec![sample(100.0, 20.0), sample(0.0, 10.0)]);
+
+        assert_eq!(profile.elevation_at(-10.0), Some(10.0));
+        assert_eq!(profile.elevation_at(50.0), Some(15.0));
+        assert_eq!(profile.elevation_at(150.0), Some(20.0));
+        assert_eq!(
+            ElevationProfile::from_samples(vec![]).elevation_at(0.0),
+            None
+        );
+    }
+
+    #[test]
+    fn naismith_adjustment() {
+        let profile = ElevationProfile::from_samples(vec![
+            sample(0.0, 100.0),
+            sample(100.0, 120.0),
+            sample(200.0, 110.0),
+        ]);
+        let adjustment = GradeEtaAdjustment::Naismith {
+            seconds_per_meter_of_ascent: 6.0,
+        };
+
+        assert_eq!(
+            adjustment.adjust_duration(Some(&profile), 0.0, 100.0),
+            220.0
+        );
+        // Halfway up the hill
+        assert_eq!(
+            adjustment.adjust_duration(Some(&profile), 50.0, 100.0),
+            160.0
+        );
+        // Nothing but downhill left
+        assert_eq!(
+            adjustment.adjust_duration(Some(&profile), 150.0, 100.0),
+            100.0
+        );
+        // No profile; no adjustment
+        assert_eq!(adjustment.adjust_duration(None, 0.0, 100.0), 100.0);
+    }
+
+    #[test]
+    fn tobler_adjustment() {
+        let flat = ElevationProfile::from_samples(vec![sample(0.0, 100.0), sample(100.0, 100.0)]);
+        let uphill = ElevationProfile::from_samples(vec![sample(0.0, 100.0), sample(100.0, 120.0)]);
+        let gentle_downhill =
+            ElevationProfile::from_samples(vec![sample(0.0, 100.0), sample(100.0, 95.0)]);
+        let adjustment = GradeEtaAdjustment::Tobler;
+
+        assert!((adjustment.adjust_duration(Some(&flat), 0.0, 100.0) - 100.0).abs() < 1e-9);
+        assert!(adjustment.adjust_duration(Some(&uphill), 0.0, 100.0) > 150.0);
+        assert!(adjustment.adjust_duration(Some(&gentle_downhill), 0.0, 100.0) < 100.0);
+        // Past the end of the profile
+        assert_eq!(
+            adjustment.adjust_duration(Some(&uphill), 200.0, 100.0),
+            100.0
+        );
+    }
+
+    #[test]
+    fn profile_from_coordinates() {
+        let coordinates = vec![
+            GeographicCoordinate { lat: 0.0, lng: 0.0 },
+            GeographicCoordinate {
+                lat: 0.0,
+                lng: 0.001,
+            },
+        ];
+
+        let profile = elevation_profile_from_coordinates(coordinates.clone(), vec![10.0, 15.0])
+            .expect("Unable to build profile");
+        assert_eq!(profile.samples.len(), 2);
+        assert!((profile.samples[1].distance_along_route - 111.2).abs() < 0.1);
+        assert_eq!(profile.total_ascent, 5.0);
+
+        assert!(elevation_profile_from_coordinates(coordinates, vec![10.0]).is_err());
+    }
+
+    #[test]
+    fn parse_height_response() {
+        let response = r#"{"encoded_polyline":"s{cplAfiz{pCa]xBoN}s@","range_height":[[0,302],[120,null],[250,315]]}"#;
+        let profile = parse_valhalla_height_response(response.as_bytes())
+            .expect("Unable to parse height response");
+
+        assert_eq!(
+            profile.samples,
+            vec![sample(0.0, 302.0), sample(250.0, 315.0)]
+        );
+        assert_eq!(profile.total_ascent, 13.0);
+        assert_eq!(profile.max_grade, 5.2);
+    }
+}
@@ -0,0 +1,184 @@
+//! Guidance for the final approach to a destination,
+//! from the end of the route to where the user actually wants to go.
+//!
+//! Routes end at the point on the road network nearest to the destination,
+//! which is often not where the user should go (ex: the entrance of a building,
+//! or a car park when there is nowhere to stop).
+//! When a route has [`DestinationDetails`](crate::models::DestinationDetails),
+//! a [`NavigationEvent::FinalApproach`](crate::navigation_controller::models::NavigationEvent::FinalApproach)
+//! event with [`FinalApproachGuidance`] is reported as the user begins the final step of the route.
+
+use crate::algorithms::DistanceMeasurement;
+use crate::geometry_util::bearing;
+use crate::models::{GeographicCoordinate, ParkingSuggestion, Route};
+use geo::Point;
+#[cfg(feature = "wasm-js")]
+use serde::{Deserialize, Serialize};
+
+/// Targets closer than this to the end of the route (in meters) have no meaningful bearing.
+const MIN_BEARING_DISTANCE: f64 = 1.0;
+
+/// Guidance from the end of the route to the destination.
+#[derive(Debug, Clone, PartialEq, uniffi::Record)]
+#[cfg_attr(feature = "wasm-js", derive(Serialize, Deserialize))]
+pub struct FinalApproachGuidance {
+    /// Where the user should go after the end of the route.
+    ///
+    /// This is the entrance of the destination nearest to the end of the route,
+    /// or the destination waypoint if no entrances are known.
+    pub target: GeographicCoordinate,
+    /// Whether the target is an entrance rather than the destination waypoint.
+    pub target_is_entrance: bool,
+    /// The straight line distance from the end of the route to the target, in meters.
+    pub distance_from_route_end: f64,
+    /// The bearing from the end of the route to the target, in clockwise degrees from true north.
+    ///
+    /// This is `None` when the target is at the end of the route.
+    pub bearing_from_route_end: Option<f64>,
+    /// Places to park near the destination, in order of preference.
+    pub parking: Vec<ParkingSuggestion>,
+}
+
+/// Determines the guidance for the final approach to the destination of a route.
+///
+/// Returns `None` if the route has no destination details, or no geometry.
+pub(crate) fn final_approach_guidance(
+    route: &Route,
+    measurement: DistanceMeasurement,
+) -> Option<FinalApproachGuidance> {
+    let details = route.destination_details.as_ref()?;
+    let route_end = Point::from(*route.geometry.last()?);
+    let distance_from_end = |coordinate: &GeographicCoordinate| {
+        measurement.distance(&route_end, &Point::from(*coordinate))
+    };
+
+    let nearest_entrance = details
+        .entrances
+        .iter()
+        .map(|entrance| (*entrance, distance_from_end(entrance)))
+        .min_by(|(_, a), (_, b)| a.total_cmp(b));
+    let (target, distance_from_route_end, target_is_entrance) = match nearest_entrance {
+        Some((entrance, distance)) => (entrance, distance, true),
+        None => {
+            let destination = route.waypoints.last()?.coordinate;
+            (destination, distance_from_end(&destination), false)
+        }
+    };
+
+    Some(FinalApproachGuidance {
+        target,
+        target_is_entrance,
+        distance_from_route_end,
+        bearing_from_route_end: (distance_from_route_end >= MIN_BEARING_DISTANCE)
+            .then(|| bearing(route_end.into(), target)),
+        parking: details.parking.clone(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{DestinationDetails, Waypoint, WaypointKind};
+    use crate::navigation_controller::test_helpers::{gen_dummy_route_step, gen_route_from_steps};
+
+    fn route(destination_details: Option<DestinationDetails>) -> Route {
+        let mut route = gen_route_from_steps(vec![gen_dummy_route_step(0.0, 0.0, 0.001, 0.0)]);
+        route.waypoints = vec![
+            Waypoint {
+                coordinate: GeographicCoordinate { lat: 0.0, lng: 0.0 },
+                kind: WaypointKind::Break,
+            },
+            Waypoint {
+                coordinate: GeographicCoordinate {
+                    lat: 0.0005,
+                    lng: 0.001,
+                },
+                kind: WaypointKind::Break,
+            },
+        ];
+        route.destination_details = destination_details;
+        route
+    }
+
+    #[test]
+    fn no_destination_details() {
+        assert_eq!(
+            final_approach_guidance(&route(None), DistanceMeasurement::Haversine),
+            None
+        );
+    }
+
+    #[test]
+    fn guides_to_nearest_entrance() {
+        let near = GeographicCoordinate {
+            lat: 0.0002,
+            lng: 0.001,
+        };
+        let far = GeographicCoordinate {
+            lat: -0.001,
+            lng: 0.001,
+        };
+        let parking = vec![ParkingSuggestion {
+            coordinate: GeographicCoordinate {
+                lat: 0.0001,
+                lng: 0.0012,
+            },
+            name: Some("Visitor parking".to_string()),
+        }];
+        let guidance = final_approach_guidance(
+            &route(Some(DestinationDetails {
+                entrances: vec![far, near],
+                parking: parking.clone(),
+            })),
+            DistanceMeasurement::Haversine,
+        )
+        .expect("Expected guidance");
+
+        assert_eq!(guidance.target, near);
+        assert!(guidance.target_is_entrance);
+        assert!((guidance.distance_from_route_end - 22.2).abs() < 0.1);
+        // Due north of the end of the route
+        assert!(guidance.bearing_from_route_end.unwrap() < 0.01);
+        assert_eq!(guidance.parking, parking);
+    }
+
+    #[test]
+    fn falls_back_to_destination_waypoint() {
+        let guidance = final_approach_guidance(
+            &route(Some(DestinationDetails {
+                entrances: vec![],
+                parking: vec![],
+            })),
+            DistanceMeasurement::Haversine,
+        )
+        .expect("Expected guidance");
+
+        assert_eq!(
+            guidance.target,
+            GeographicCoordinate {
+                lat: 0.0005,
+                lng: 0.001
+            }
+        );
+        assert!(!guidance.target_is_entrance);
+        assert!((guidance.distance_from_route_end - 55.6).abs() < 0.1);
+    }
+
+    #[test]
+    fn no_bearing_when_target_is_at_route_end() {
+        let guidance = final_approach_guidance(
+            &route(Some(DestinationDetails {
+                entrances: vec![GeographicCoordinate {
+                    lat: 0.0,
+                    lng: 0.001,
+                }],
+                parking: vec![],
+            })),
+            DistanceMeasurement::Haversine,
+        )
+        .expect("Expected guidance");
+
+        assert_eq!(guidance.distance_from_route_end, 0.0);
+        assert_eq!(guidance.bearing_from_route_end, None);
+    }
+}
@@ -0,0 +1,237 @@
+//! Tracking the user without a route (ex: for a "passive navigation" screen).
+//!
+//! Free drive applies the same location filtering as navigation,
+//! but as there is no route line to snap to,
+//! locations are only snapped to the road when they are map matched before being passed in
+//! (see [`crate::routing_adapters::map_matching::MapMatchingAdapter`]).
+//! Once a route arrives, passing the free drive state to the
+//! [`crate::navigation_controller::NavigationController`] for the route
+//! starts navigating from the user's location.
+
+use crate::location_filtering::PositioningStatus;
+use crate::models::{GeographicCoordinate, UserLocation};
+use crate::navigation_controller::models::{NavigationControllerConfig, TripState};
+use std::sync::Arc;
+
+/// Looks up the posted speed limit at a location (ex: from an offline road database).
+///
+/// This is called synchronously during location updates, so implementations must return quickly.
+#[uniffi::export(with_foreign)]
+pub trait SpeedLimitProvider: Send + Sync {
+    /// Returns the speed limit at the coordinate in meters per second, if known.
+    fn speed_limit(&self, coordinate: GeographicCoordinate) -> Option<f64>;
+}
+
+/// Tracks the user's location while there is no route.
+///
+/// Like the [`crate::navigation_controller::NavigationController`], this is stateless:
+/// it takes the current [`TripState::FreeDrive`] and a location update and returns the new state.
+#[derive(uniffi::Object)]
+pub struct FreeDriveController {
+    config: NavigationControllerConfig,
+    speed_limit_provider: Option<Arc<dyn SpeedLimitProvider>>,
+}
+
+#[uniffi::export]
+impl FreeDriveController {
+    /// Creates a controller which filters locations according to the navigation config.
+    ///
+    /// The current road name is only reported
+    /// when the [`crate::road_name::RoadNameLookup`] includes a geocoder,
+    /// and the speed limit is only reported when a provider is given.
+    #[uniffi::constructor]
+    pub fn new(
+        config: NavigationControllerConfig,
+        speed_limit_provider: Option<Arc<dyn SpeedLimitProvider>>,
+    ) -> Self {
+        Self {
+            config,
+            speed_limit_provider,
+        }
+    }
+
+    /// Returns the initial free drive state at the user's location.
+    pub fn get_initial_state(&self, location: UserLocation) -> TripState {
+        TripState::FreeDrive {
+            user_location: location,
+            current_road_name: self
+                .config
+                .road_name_lookup
+                .road_name_at(location.coordinates),
+            speed_limit: self.speed_limit(location),
+            positioning: PositioningStatus::Accurate,
+        }
+    }
+
+    /// Updates the user's current location.
+    ///
+    /// Any state other than [`TripState::FreeDrive`] (ex: after the trip is complete)
+    /// is replaced with a new free drive state at the location.
+    pub fn update_user_location(&self, location: UserLocation, state: &TripState) -> TripState {
+        let TripState::FreeDrive {
+            user_location,
+            positioning,
+            ..
+        } = state
+        else {
+            return self.get_initial_state(location);
+        };
+
+        if self.config.stale_location_rejection.is_stale(
+            &location,
+            user_location.timestamp,
+            self.config.now(),
+        ) {
+            return state.clone();
+        }
+
+        let positioning = self.config.accuracy_gating.check(&location, *positioning);
+        if positioning != PositioningStatus::Accurate {
+            // Hold the last accurate location, but let the caller know why
+            let mut state = state.clone();
+            if let TripState::FreeDrive {
+                positioning: ref mut state_positioning,
+                ..
+            } = state
+            {
+                *state_positioning = positioning;
+            }
+            return state;
+        }
+
+        let location = self.config.motion_derivation.apply(location, user_location);
+        TripState::FreeDrive {
+            user_location: location,
+            current_road_name: self
+                .config
+                .road_name_lookup
+                .road_name_at(location.coordinates),
+            speed_limit: self.speed_limit(location),
+            positioning,
+        }
+    }
+}
+
+impl FreeDriveController {
+    fn speed_limit(&self, location: UserLocation) -> Option<f64> {
+        self.speed_limit_provider
+            .as_ref()?
+            .speed_limit(location.coordinates)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+    use crate::location_filtering::AccuracyGating;
+    use crate::navigation_controller::presets::NavigationPreset;
+    use crate::navigation_controller::test_helpers::gen_user_location;
+    use crate::road_name::{RoadNameGeocoder, RoadNameLookup};
+    use std::time::{Duration, SystemTime};
+
+    struct StaticGeocoder;
+
+    impl RoadNameGeocoder for StaticGeocoder {
+        fn road_name(&self, _coordinate: GeographicCoordinate) -> Option<String> {
+            Some("Main Street".to_string())
+        }
+    }
+
+    struct StaticSpeedLimit;
+
+    impl SpeedLimitProvider for StaticSpeedLimit {
+        fn speed_limit(&self, coordinate: GeographicCoordinate) -> Option<f64> {
+            (coordinate.lng > 0.0).then_some(13.9)
+        }
+    }
+
+    fn controller() -> FreeDriveController {
+        FreeDriveController::new(
+            NavigationControllerConfig {
+                road_name_lookup: RoadNameLookup::StepNameWithGeocoderFallback {
+                    geocoder: Arc::new(StaticGeocoder),
+                },
+                accuracy_gating: AccuracyGating::MaxHorizontalAccuracy {
+                    max_horizontal_accuracy: 50.0,
+                    degraded_after: 3,
+                },
+                // The locations are a few seconds old
+                clock: Some(Arc::new(MockClock::new(
+                    SystemTime::UNIX_EPOCH + Duration::from_secs(5),
+                ))),
+                ..NavigationControllerConfig::from_preset(NavigationPreset::Driving)
+            },
+            Some(Arc::new(StaticSpeedLimit)),
+        )
+    }
+
+    #[test]
+    fn reports_road_name_and_speed_limit() {
+        let controller = controller();
+        let state = controller.get_initial_state(gen_user_location(0.0, 0.0, 0));
+        let TripState::FreeDrive {
+            current_road_name,
+            speed_limit,
+            ..
+        } = &state
+        else {
+            panic!("Expected a free drive state");
+        };
+        assert_eq!(current_road_name.as_deref(), Some("Main Street"));
+        assert_eq!(*speed_limit, None);
+
+        let state = controller.update_user_location(gen_user_location(0.001, 0.0, 1), &state);
+        let TripState::FreeDrive {
+            user_location,
+            speed_limit,
+            ..
+        } = state
+        else {
+            panic!("Expected a free drive state");
+        };
+        assert_eq!(user_location.coordinates.lng, 0.001);
+        assert_eq!(speed_limit, Some(13.9));
+    }
+
+    #[test]
+    fn filters_locations() {
+        let controller = controller();
+        let initial_state = controller.get_initial_state(gen_user_location(0.0, 0.0, 1));
+
+        // Out of order
+        let state =
+            controller.update_user_location(gen_user_location(0.001, 0.0, 0), &initial_state);
+        assert_eq!(state, initial_state);
+
+        // Too inaccurate
+        let state = controller.update_user_location(
+            gen_user_location(0.001, 0.0, 2).with_horizontal_accuracy(100.0),
+            &initial_state,
+        );
+        let TripState::FreeDrive {
+            user_location,
+            positioning,
+            ..
+        } = state
+        else {
+            panic!("Expected a free drive state");
+        };
+        assert_eq!(user_location.coordinates.lng, 0.0);
+        assert_eq!(
+            positioning,
+            PositioningStatus::Inaccurate {
+                consecutive_rejections: 1
+            }
+        );
+    }
+
+    #[test]
+    fn other_states_are_replaced() {
+        let controller = controller();
+        assert!(matches!(
+            controller.update_user_location(gen_user_location(0.0, 0.0, 0), &TripState::Complete),
+            TripState::FreeDrive { .. }
+        ));
+    }
+}
@@ -0,0 +1,590 @@
+//! Forward and reverse geocoding (ex: searching for a destination by address).
+//!
+//! Like routing, geocoding is split into a request generator and a response parser
+//! which are combined by the [`GeocodingAdapter`],
+//! and requests are executed with the same [`HttpExecutor`] used for routing.
+//! Implementations are provided for [Nominatim](https://nominatim.org/)
+//! and [Pelias](https://pelias.io/) (including hosted Pelias services like Stadia Maps).
+
+use crate::models::{BoundingBox, GeographicCoordinate};
+use crate::routing_adapters::error::{
+    RouteFetchError, RoutingBackendError, RoutingRequestGenerationError, RoutingResponseParseError,
+};
+use crate::routing_adapters::http::{
+    spawn_blocking, url_with_query, CancellationToken, HttpExecutor,
+};
+use crate::routing_adapters::RouteRequest;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// A place found by geocoding.
+#[derive(Debug, Clone, PartialEq, uniffi::Record)]
+pub struct GeocodingResult {
+    /// A human-readable name for the place, typically including the address.
+    pub label: String,
+    pub coordinate: GeographicCoordinate,
+    /// The extent of the place (ex: for a city), if the backend provides it.
+    pub bbox: Option<BoundingBox>,
+}
+
+/// A trait describing any object capable of generating geocoding requests.
+#[uniffi::export(with_foreign)]
+pub trait GeocodingRequestGenerator: Send + Sync {
+    /// Generates a request to search for places matching a text query (forward geocoding).
+    ///
+    /// Backends which support it will prefer results near `focus` (ex: the user's location).
+    fn generate_search_request(
+        &self,
+        query: String,
+        focus: Option<GeographicCoordinate>,
+        limit: u32,
+    ) -> Result<RouteRequest, RoutingRequestGenerationError>;
+
+    /// Generates a request for the places at a coordinate (reverse geocoding).
+    fn generate_reverse_request(
+        &self,
+        coordinate: GeographicCoordinate,
+    ) -> Result<RouteRequest, RoutingRequestGenerationError>;
+}
+
+/// A trait describing any object capable of parsing geocoding responses.
+///
+/// Responses to both search and reverse requests are parsed with the same method.
+#[uniffi::export(with_foreign)]
+pub trait GeocodingResponseParser: Send + Sync {
+    /// Parses the results in the response, ordered from most to least relevant.
+    fn parse_response(
+        &self,
+        response: Vec<u8>,
+    ) -> Result<Vec<GeocodingResult>, RoutingResponseParseError>;
+}
+
+/// Joins a path onto a base URL which may have a query string (ex: with an API key).
+fn endpoint_url(base_url: &str, path: &str) -> String {
+    match base_url.split_once('?') {
+        Some((base, query)) => format!("{}/{path}?{query}", base.trim_end_matches('/')),
+        None => format!("{}/{path}", base_url.trim_end_matches('/')),
+    }
+}
+
+/// A geocoding request generator for the Nominatim API, using GeoJSON output.
+///
+/// Note that the public Nominatim instance requires an identifying `User-Agent`,
+/// which should be set by the [`crate::routing_adapters::http::HttpClient`].
+#[derive(Debug)]
+pub struct NominatimRequestGenerator {
+    /// The base URL of the Nominatim server (ex: `https://nominatim.openstreetmap.org`).
+    base_url: String,
+}
+
+impl NominatimRequestGenerator {
+    pub fn new(base_url: String) -> Self {
+        Self { base_url }
+    }
+}
+
+impl GeocodingRequestGenerator for NominatimRequestGenerator {
+    fn generate_search_request(
+        &self,
+        query: String,
+        // Nominatim can only restrict results to an area, not prefer them
+        _focus: Option<GeographicCoordinate>,
+        limit: u32,
+    ) -> Result<RouteRequest, RoutingRequestGenerationError> {
+        let url = url_with_query(
+            &endpoint_url(&self.base_url, "search"),
+            &[
+                ("q", query),
+                ("format", "geojson".to_string()),
+                ("limit", limit.to_string()),
+            ],
+        );
+        Ok(RouteRequest::HttpGet {
+            url,
+            headers: HashMap::new(),
+        })
+    }
+
+    fn generate_reverse_request(
+        &self,
+        coordinate: GeographicCoordinate,
+    ) -> Result<RouteRequest, RoutingRequestGenerationError> {
+        let url = url_with_query(
+            &endpoint_url(&self.base_url, "reverse"),
+            &[
+                ("lat", coordinate.lat.to_string()),
+                ("lon", coordinate.lng.to_string()),
+                ("format", "geojson".to_string()),
+            ],
+        );
+        Ok(RouteRequest::HttpGet {
+            url,
+            headers: HashMap::new(),
+        })
+    }
+}
+
+/// A geocoding request generator for the Pelias API.
+#[derive(Debug)]
+pub struct PeliasRequestGenerator {
+    /// The base URL of the Pelias API, including the version (ex: `https://pelias.example.com/v1`).
+    ///
+    /// Users *may* include a query string with an API key.
+    base_url: String,
+}
+
+impl PeliasRequestGenerator {
+    pub fn new(base_url: String) -> Self {
+        Self { base_url }
+    }
+}
+
+impl GeocodingRequestGenerator for PeliasRequestGenerator {
+    fn generate_search_request(
+        &self,
+        query: String,
+        focus: Option<GeographicCoordinate>,
+        limit: u32,
+    ) -> Result<RouteRequest, RoutingRequestGenerationError> {
+        let mut params = vec![("text", query), ("size", limit.to_string())];
+        if let Some(focus) = focus {
+            params.push(("focus.point.lat", focus.lat.to_string()));
+            params.push(("focus.point.lon", focus.lng.to_string()));
+        }
+        Ok(RouteRequest::HttpGet {
+            url: url_with_query(&endpoint_url(&self.base_url, "search"), &params),
+            headers: HashMap::new(),
+        })
+    }
+
+    fn generate_reverse_request(
+        &self,
+        coordinate: GeographicCoordinate,
+    ) -> Result<RouteRequest, RoutingRequestGenerationError> {
+        let url = url_with_query(
+            &endpoint_url(&self.base_url, "reverse"),
+            &[
+                ("point.lat", coordinate.lat.to_string()),
+                ("point.lon", coordinate.lng.to_string()),
+            ],
+        );
+        Ok(RouteRequest::HttpGet {
+            url,
+            headers: HashMap::new(),
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct FeatureCollection<P> {
+    #[serde(default = "Vec::new")]
+    features: Vec<Feature<P>>,
+}
+
+#[derive(Deserialize)]
+struct Feature<P> {
+    properties: P,
+    geometry: PointGeometry,
+    /// The bounding box, as `[min_lng, min_lat, max_lng, max_lat]`.
+    bbox: Option<[f64; 4]>,
+}
+
+#[derive(Deserialize)]
+struct PointGeometry {
+    /// The coordinate, as `[lng, lat]`.
+    coordinates: [f64; 2],
+}
+
+impl<P> Feature<P> {
+    fn into_result(self, label: String) -> GeocodingResult {
+        GeocodingResult {
+            label,
+            coordinate: GeographicCoordinate {
+                lng: self.geometry.coordinates[0],
+                lat: self.geometry.coordinates[1],
+            },
+            bbox: self
+                .bbox
+                .map(|[min_lng, min_lat, max_lng, max_lat]| BoundingBox {
+                    sw: GeographicCoordinate {
+                        lat: min_lat,
+                        lng: min_lng,
+                    },
+                    ne: GeographicCoordinate {
+                        lat: max_lat,
+                        lng: max_lng,
+                    },
+                }),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct NominatimProperties {
+    display_name: String,
+}
+
+#[derive(Deserialize)]
+struct NominatimError {
+    error: String,
+}
+
+/// A geocoding response parser for Nominatim GeoJSON responses.
+#[derive(Debug, Default)]
+pub struct NominatimResponseParser;
+
+impl GeocodingResponseParser for NominatimResponseParser {
+    fn parse_response(
+        &self,
+        response: Vec<u8>,
+    ) -> Result<Vec<GeocodingResult>, RoutingResponseParseError> {
+        // Errors are reported with a successful HTTP status
+        if let Ok(NominatimError { error }) = serde_json::from_slice(&response) {
+            return if error == "Unable to geocode" {
+                // Nothing at the location (ex: in the ocean)
+                Ok(vec![])
+            } else {
+                Err(RoutingResponseParseError::BackendError {
+                    error: RoutingBackendError::InvalidQuery,
+                    message: Some(error),
+                })
+            };
+        }
+
+        let res: FeatureCollection<NominatimProperties> = serde_json::from_slice(&response)?;
+        Ok(res
+            .features
+            .into_iter()
+            .map(|feature| {
+                let label = feature.properties.display_name.clone();
+                feature.into_result(label)
+            })
+            .collect())
+    }
+}
+
+#[derive(Deserialize)]
+struct PeliasResponse {
+    geocoding: Option<PeliasMetadata>,
+    #[serde(flatten)]
+    collection: FeatureCollection<PeliasProperties>,
+}
+
+#[derive(Deserialize)]
+struct PeliasMetadata {
+    #[serde(default)]
+    errors: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct PeliasProperties {
+    label: String,
+}
+
+/// A geocoding response parser for Pelias responses.
+#[derive(Debug, Default)]
+pub struct PeliasResponseParser;
+
+impl GeocodingResponseParser for PeliasResponseParser {
+    fn parse_response(
+        &self,
+        response: Vec<u8>,
+    ) -> Result<Vec<GeocodingResult>, RoutingResponseParseError> {
+        let res: PeliasResponse = serde_json::from_slice(&response)?;
+        if let Some(errors) = res
+            .geocoding
+            .map(|metadata| metadata.errors)
+            .filter(|errors| !errors.is_empty())
+        {
+            return Err(RoutingResponseParseError::BackendError {
+                error: RoutingBackendError::InvalidQuery,
+                message: Some(errors.join(" ")),
+            });
+        }
+
+        Ok(res
+            .collection
+            .features
+            .into_iter()
+            .map(|feature| {
+                let label = feature.properties.label.clone();
+                feature.into_result(label)
+            })
+            .collect())
+    }
+}
+
+/// The geocoding adapter bridges between the common core and a geocoding backend.
+///
+/// Like the [`crate::routing_adapters::RouteAdapter`], this is split into a request generator
+/// and a response parser so that either half may be implemented in foreign code.
+#[derive(uniffi::Object)]
+pub struct GeocodingAdapter {
+    request_generator: Arc<dyn GeocodingRequestGenerator>,
+    response_parser: Arc<dyn GeocodingResponseParser>,
+}
+
+#[uniffi::export]
+impl GeocodingAdapter {
+    #[uniffi::constructor]
+    pub fn new(
+        request_generator: Arc<dyn GeocodingRequestGenerator>,
+        response_parser: Arc<dyn GeocodingResponseParser>,
+    ) -> Self {
+        Self {
+            request_generator,
+            response_parser,
+        }
+    }
+
+    #[uniffi::constructor]
+    pub fn new_nominatim_http(base_url: String) -> Self {
+        Self::new(
+            Arc::new(NominatimRequestGenerator::new(base_url)),
+            Arc::new(NominatimResponseParser),
+        )
+    }
+
+    #[uniffi::constructor]
+    pub fn new_pelias_http(base_url: String) -> Self {
+        Self::new(
+            Arc::new(PeliasRequestGenerator::new(base_url)),
+            Arc::new(PeliasResponseParser),
+        )
+    }
+
+    //
+    // Proxied implementation methods.
+    //
+
+    pub fn generate_search_request(
+        &self,
+        query: String,
+        focus: Option<GeographicCoordinate>,
+        limit: u32,
+    ) -> Result<RouteRequest, RoutingRequestGenerationError> {
+        self.request_generator
+            .generate_search_request(query, focus, limit)
+    }
+
+    pub fn generate_reverse_request(
+        &self,
+        coordinate: GeographicCoordinate,
+    ) -> Result<RouteRequest, RoutingRequestGenerationError> {
+        self.request_generator.generate_reverse_request(coordinate)
+    }
+
+    pub fn parse_response(
+        &self,
+        response: Vec<u8>,
+    ) -> Result<Vec<GeocodingResult>, RoutingResponseParseError> {
+        self.response_parser.parse_response(response)
+    }
+
+    //
+    // Combined fetch pipelines.
+    //
+
+    /// Searches for places matching a text query.
+    ///
+    /// As with [`crate::routing_adapters::RouteAdapter::get_route`],
+    /// the HTTP request runs on a background thread,
+    /// and this fails with [`RouteFetchError::Cancelled`] if the token is cancelled first
+    /// (ex: because the user typed another character).
+    pub async fn search(
+        &self,
+        executor: Arc<HttpExecutor>,
+        query: String,
+        focus: Option<GeographicCoordinate>,
+        limit: u32,
+        cancellation_token: Option<Arc<CancellationToken>>,
+    ) -> Result<Vec<GeocodingResult>, RouteFetchError> {
+        if is_cancelled(cancellation_token.as_deref()) {
+            return Err(RouteFetchError::Cancelled);
+        }
+        let request = self.generate_search_request(query, focus, limit)?;
+        self.fetch(executor, request, cancellation_token).await
+    }
+
+    /// Looks up the places at a coordinate.
+    pub async fn reverse(
+        &self,
+        executor: Arc<HttpExecutor>,
+        coordinate: GeographicCoordinate,
+        cancellation_token: Option<Arc<CancellationToken>>,
+    ) -> Result<Vec<GeocodingResult>, RouteFetchError> {
+        if is_cancelled(cancellation_token.as_deref()) {
+            return Err(RouteFetchError::Cancelled);
+        }
+        let request = self.generate_reverse_request(coordinate)?;
+        self.fetch(executor, request, cancellation_token).await
+    }
+}
+
+impl GeocodingAdapter {
+    async fn fetch(
+        &self,
+        executor: Arc<HttpExecutor>,
+        request: RouteRequest,
+        cancellation_token: Option<Arc<CancellationToken>>,
+    ) -> Result<Vec<GeocodingResult>, RouteFetchError> {
+        let token = cancellation_token.clone();
+        let response = spawn_blocking(move || executor.execute(&request, token)).await??;
+        if is_cancelled(cancellation_token.as_deref()) {
+            return Err(RouteFetchError::Cancelled);
+        }
+
+        Ok(self.parse_response(response)?)
+    }
+}
+
+fn is_cancelled(token: Option<&CancellationToken>) -> bool {
+    token.is_some_and(CancellationToken::is_cancelled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::routing_adapters::http::test_helpers::{block_on, executor, MockHttpClient};
+
+    const BERLIN: GeographicCoordinate = GeographicCoordinate {
+        lat: 52.5170,
+        lng: 13.3889,
+    };
+    const NOMINATIM_RESPONSE: &str = r#"{"type":"FeatureCollection","licence":"Data © OpenStreetMap contributors, ODbL 1.0. http://osm.org/copyright","features":[{"type":"Feature","properties":{"place_id":1,"osm_type":"relation","osm_id":62422,"display_name":"Berlin, Deutschland","place_rank":8,"category":"boundary","type":"administrative","importance":0.85},"bbox":[13.088345,52.3382448,13.7611609,52.6755087],"geometry":{"type":"Point","coordinates":[13.3888599,52.5170365]}}]}"#;
+    const PELIAS_RESPONSE: &str = r#"{"geocoding":{"version":"0.2","query":{"text":"Brandenburger Tor"}},"type":"FeatureCollection","features":[{"type":"Feature","geometry":{"type":"Point","coordinates":[13.377704,52.516275]},"properties":{"id":"way/518071791","layer":"venue","name":"Brandenburger Tor","label":"Brandenburger Tor, Berlin, Germany"}}],"bbox":[13.377704,52.516275,13.377704,52.516275]}"#;
+
+    fn url(request: RouteRequest) -> String {
+        let RouteRequest::HttpGet { url, .. } = request else {
+            panic!("Expected a GET request");
+        };
+        url
+    }
+
+    #[test]
+    fn nominatim_requests() {
+        let generator =
+            NominatimRequestGenerator::new("https://nominatim.openstreetmap.org/".to_string());
+
+        assert_eq!(
+            url(generator
+                .generate_search_request("Unter den Linden".to_string(), Some(BERLIN), 5)
+                .expect("Unable to generate request")),
+            "https://nominatim.openstreetmap.org/search?q=Unter%20den%20Linden&format=geojson&limit=5"
+        );
+        assert_eq!(
+            url(generator
+                .generate_reverse_request(BERLIN)
+                .expect("Unable to generate request")),
+            "https://nominatim.openstreetmap.org/reverse?lat=52.517&lon=13.3889&format=geojson"
+        );
+    }
+
+    #[test]
+    fn pelias_requests() {
+        let generator = PeliasRequestGenerator::new(
+            "https://api.stadiamaps.com/geocoding/v1?api_key=secret".to_string(),
+        );
+
+        assert_eq!(
+            url(generator
+                .generate_search_request("Brandenburger Tor".to_string(), Some(BERLIN), 3)
+                .expect("Unable to generate request")),
+            "https://api.stadiamaps.com/geocoding/v1/search?api_key=secret&text=Brandenburger%20Tor&size=3&focus.point.lat=52.517&focus.point.lon=13.3889"
+        );
+        assert_eq!(
+            url(generator
+                .generate_reverse_request(BERLIN)
+                .expect("Unable to generate request")),
+            "https://api.stadiamaps.com/geocoding/v1/reverse?api_key=secret&point.lat=52.517&point.lon=13.3889"
+        );
+    }
+
+    #[test]
+    fn parse_nominatim_response() {
+        let results = NominatimResponseParser
+            .parse_response(NOMINATIM_RESPONSE.as_bytes().to_vec())
+            .expect("Unable to parse response");
+        assert_eq!(
+            results,
+            vec![GeocodingResult {
+                label: "Berlin, Deutschland".to_string(),
+                coordinate: GeographicCoordinate {
+                    lat: 52.5170365,
+                    lng: 13.3888599
+                },
+                bbox: Some(BoundingBox {
+                    sw: GeographicCoordinate {
+                        lat: 52.3382448,
+                        lng: 13.088345
+                    },
+                    ne: GeographicCoordinate {
+                        lat: 52.6755087,
+                        lng: 13.7611609
+                    },
+                }),
+            }]
+        );
+
+        // Nothing found
+        assert_eq!(
+            NominatimResponseParser
+                .parse_response(br#"{"error":"Unable to geocode"}"#.to_vec())
+                .expect("Unable to parse response"),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn parse_pelias_response() {
+        let results = PeliasResponseParser
+            .parse_response(PELIAS_RESPONSE.as_bytes().to_vec())
+            .expect("Unable to parse response");
+        assert_eq!(
+            results,
+            vec![GeocodingResult {
+                label: "Brandenburger Tor, Berlin, Germany".to_string(),
+                coordinate: GeographicCoordinate {
+                    lat: 52.516275,
+                    lng: 13.377704
+                },
+                bbox: None,
+            }]
+        );
+
+        assert!(matches!(
+            PeliasResponseParser.parse_response(
+                br#"{"geocoding":{"errors":["invalid param 'text': text length, must be >0"]},"type":"FeatureCollection","features":[]}"#.to_vec()
+            ),
+            Err(RoutingResponseParseError::BackendError {
+                error: RoutingBackendError::InvalidQuery,
+                message: Some(_),
+            })
+        ));
+    }
+
+    #[test]
+    fn search() {
+        let adapter =
+            GeocodingAdapter::new_pelias_http("https://pelias.example.com/v1".to_string());
+        let executor = executor(MockHttpClient::new(Ok(PELIAS_RESPONSE.as_bytes().to_vec())));
+
+        let results = block_on(adapter.search(
+            executor.clone(),
+            "Brandenburger Tor".to_string(),
+            None,
+            10,
+            None,
+        ))
+        .expect("Unable to search");
+        assert_eq!(results.len(), 1);
+
+        let token = Arc::new(CancellationToken::new());
+        token.cancel();
+        assert!(matches!(
+            block_on(adapter.reverse(executor, BERLIN, Some(token))),
+            Err(RouteFetchError::Cancelled)
+        ));
+    }
+}
@@ -0,0 +1,250 @@
+//! Great circle geometry helpers for coordinates and lines.
+//!
+//! These are the building blocks that apps typically need for drawing and annotating routes
+//! (ex: placing a marker partway along a line, or highlighting the traversed part of a route),
+//! exposed here so that every platform gets the same results.
+//!
+//! Lines are given as a sequence of coordinates, and all distances are in meters
+//! (measured on a spherical earth, like [`crate::algorithms::DistanceMeasurement::Haversine`]).
+
+use crate::algorithms::MEAN_EARTH_RADIUS;
+use crate::models::GeographicCoordinate;
+use geo::{
+    Closest, ClosestPoint, HaversineBearing, HaversineDistance, HaversineIntermediate, Line, Point,
+};
+
+/// Where a point lies along a line.
+struct LineLocation {
+    /// The index of the segment closest to the point.
+    segment_index: usize,
+    /// The closest point on that segment.
+    snapped: Point,
+}
+
+impl LineLocation {
+    /// Whether this location comes after `other` along `line`.
+    fn is_after(&self, other: &LineLocation, line: &[GeographicCoordinate]) -> bool {
+        match self.segment_index.cmp(&other.segment_index) {
+            std::cmp::Ordering::Less => false,
+            std::cmp::Ordering::Greater => true,
+            std::cmp::Ordering::Equal => {
+                let segment_start = Point::from(line[self.segment_index]);
+                segment_start.haversine_distance(&self.snapped)
+                    > segment_start.haversine_distance(&other.snapped)
+            }
+        }
+    }
+}
+
+/// Finds the closest point on a line (with at least two coordinates) to `point`.
+fn locate_point(line: &[GeographicCoordinate], point: Point) -> Option<LineLocation> {
+    line.windows(2)
+        .enumerate()
+        .filter_map(|(segment_index, segment)| {
+            let segment = Line::new(segment[0], segment[1]);
+            let snapped = match segment.closest_point(&point) {
+                Closest::Intersection(snapped) | Closest::SinglePoint(snapped) => snapped,
+                Closest::Indeterminate => return None,
+            };
+            Some((
+                snapped.haversine_distance(&point),
+                LineLocation {
+                    segment_index,
+                    snapped,
+                },
+            ))
+        })
+        .min_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(_, location)| location)
+}
+
+/// Finds the point at `distance` meters along a line, following great circles between coordinates.
+///
+/// Distances beyond either end of the line are clamped to that end.
+/// Returns `None` if the line is empty.
+#[uniffi::export]
+pub fn point_along_line(
+    line: &[GeographicCoordinate],
+    distance: f64,
+) -> Option<GeographicCoordinate> {
+    let first = *line.first()?;
+    let mut remaining = distance;
+    if remaining.is_nan() || remaining <= 0.0 {
+        return Some(first);
+    }
+
+    for segment in line.windows(2) {
+        let start = Point::from(segment[0]);
+        let end = Point::from(segment[1]);
+        let segment_length = start.haversine_distance(&end);
+        if remaining <= segment_length {
+            return Some(
+                start
+                    .haversine_intermediate(&end, remaining / segment_length)
+                    .into(),
+            );
+        }
+        remaining -= segment_length;
+    }
+
+    line.last().copied()
+}
+
+/// Extracts the part of a line between the points closest to `start` and `end`.
+///
+/// The slice begins and ends at the closest points on the line (not `start` and `end` themselves)
+/// and keeps the direction of the original line,
+/// even if `end` comes before `start` along it.
+/// Returns an empty list if the line has fewer than two coordinates.
+#[uniffi::export]
+pub fn line_slice_between_points(
+    line: &[GeographicCoordinate],
+    start: GeographicCoordinate,
+    end: GeographicCoordinate,
+) -> Vec<GeographicCoordinate> {
+    let (Some(start), Some(end)) = (
+        locate_point(line, start.into()),
+        locate_point(line, end.into()),
+    ) else {
+        return vec![];
+    };
+    let (start, end) = if start.is_after(&end, line) {
+        (end, start)
+    } else {
+        (start, end)
+    };
+
+    let mut slice = vec![start.snapped.into()];
+    slice.extend_from_slice(&line[start.segment_index + 1..=end.segment_index]);
+    slice.push(end.snapped.into());
+    slice.dedup();
+    slice
+}
+
+/// The initial bearing of the great circle path from `from` to `to`,
+/// in clockwise degrees from true north (`0..360`).
+#[uniffi::export]
+pub fn bearing(from: GeographicCoordinate, to: GeographicCoordinate) -> f64 {
+    Point::from(from)
+        .haversine_bearing(Point::from(to))
+        .rem_euclid(360.0)
+}
+
+/// The distance from `point` to the great circle through `path_start` and `path_end`, in meters.
+///
+/// The distance is positive when the point is to the right of the path (facing `path_end`),
+/// and negative when it is to the left.
+/// Note that great circles extend beyond the ends of the path,
+/// so points past either end are measured against its extension.
+#[uniffi::export]
+pub fn cross_track_distance(
+    point: GeographicCoordinate,
+    path_start: GeographicCoordinate,
+    path_end: GeographicCoordinate,
+) -> f64 {
+    let start = Point::from(path_start);
+    let point = Point::from(point);
+    let angular_distance = start.haversine_distance(&point) / MEAN_EARTH_RADIUS;
+    let bearing_to_point = bearing(path_start, point.into()).to_radians();
+    let bearing_to_end = bearing(path_start, path_end).to_radians();
+
+    (angular_distance.sin() * (bearing_to_point - bearing_to_end).sin()).asin() * MEAN_EARTH_RADIUS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn coordinate(lat: f64, lng: f64) -> GeographicCoordinate {
+        GeographicCoordinate { lat, lng }
+    }
+
+    fn assert_close(a: GeographicCoordinate, b: GeographicCoordinate) {
+        assert!(
+            (a.lat - b.lat).abs() < 1e-9 && (a.lng - b.lng).abs() < 1e-9,
+            "{a:?} != {b:?}"
+        );
+    }
+
+    #[test]
+    fn points_along_a_line() {
+        let line = vec![
+            coordinate(0.0, 0.0),
+            coordinate(0.0, 1.0),
+            coordinate(1.0, 1.0),
+        ];
+        let segment_length = Point::new(0.0, 0.0).haversine_distance(&Point::new(1.0, 0.0));
+
+        assert_eq!(point_along_line(&[], 10.0), None);
+        assert_close(point_along_line(&line, -5.0).unwrap(), line[0]);
+        assert_close(
+            point_along_line(&line, segment_length / 2.0).unwrap(),
+            coordinate(0.0, 0.5),
+        );
+        assert_close(point_along_line(&line, segment_length).unwrap(), line[1]);
+        assert_close(
+            point_along_line(&line, segment_length * 1.5).unwrap(),
+            coordinate(0.5, 1.0),
+        );
+        assert_close(point_along_line(&line, 1e9).unwrap(), line[2]);
+    }
+
+    #[test]
+    fn slices_between_points() {
+        let line = vec![
+            coordinate(0.0, 0.0),
+            coordinate(0.0, 1.0),
+            coordinate(0.0, 2.0),
+            coordinate(0.0, 3.0),
+        ];
+
+        assert_eq!(
+            line_slice_between_points(&line, coordinate(0.1, 0.5), coordinate(-0.1, 2.5)),
+            vec![
+                coordinate(0.0, 0.5),
+                coordinate(0.0, 1.0),
+                coordinate(0.0, 2.0),
+                coordinate(0.0, 2.5),
+            ]
+        );
+        // The direction of the line is preserved
+        assert_eq!(
+            line_slice_between_points(&line, coordinate(0.0, 2.5), coordinate(0.0, 0.5)),
+            line_slice_between_points(&line, coordinate(0.0, 0.5), coordinate(0.0, 2.5)),
+        );
+        // Both points on the same segment
+        assert_eq!(
+            line_slice_between_points(&line, coordinate(0.0, 1.75), coordinate(0.0, 1.25)),
+            vec![coordinate(0.0, 1.25), coordinate(0.0, 1.75)]
+        );
+        // Slices which end at a vertex don't repeat it
+        assert_eq!(
+            line_slice_between_points(&line, coordinate(0.0, 0.0), coordinate(0.0, 1.0)),
+            vec![coordinate(0.0, 0.0), coordinate(0.0, 1.0)]
+        );
+        assert!(line_slice_between_points(&[line[0]], line[0], line[0]).is_empty());
+    }
+
+    #[test]
+    fn bearings() {
+        let origin = coordinate(0.0, 0.0);
+        assert!((bearing(origin, coordinate(1.0, 0.0)) - 0.0).abs() < 1e-9);
+        assert!((bearing(origin, coordinate(0.0, 1.0)) - 90.0).abs() < 1e-9);
+        assert!((bearing(origin, coordinate(-1.0, 0.0)) - 180.0).abs() < 1e-9);
+        assert!((bearing(origin, coordinate(0.0, -1.0)) - 270.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn cross_track_distances() {
+        let start = coordinate(0.0, 0.0);
+        let end = coordinate(0.0, 1.0);
+        // One degree of latitude off a path along the equator
+        let offset = Point::new(0.5, 0.0).haversine_distance(&Point::new(0.5, 1.0));
+
+        assert!(cross_track_distance(coordinate(0.0, 0.5), start, end).abs() < 1e-6);
+        assert!((cross_track_distance(coordinate(-1.0, 0.5), start, end) - offset).abs() < 1e-6);
+        assert!((cross_track_distance(coordinate(1.0, 0.5), start, end) + offset).abs() < 1e-6);
+        // Beyond the end of the path
+        assert!((cross_track_distance(coordinate(1.0, 2.0), start, end) + offset).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,695 @@
+//! Fallback instruction generation for backends which do not provide instructions.
+//!
+//! Most commercial routing backends (and Valhalla) generate localized instructions server-side,
+//! but vanilla OSRM only describes the maneuvers.
+//! In that case, we synthesize a short instruction from the maneuver and road name
+//! in the user's language.
+//!
+//! The following languages are supported; any other locale falls back to English.
+//!
+//! - English (`en`)
+//! - German (`de`)
+//! - French (`fr`)
+//! - Spanish (`es`)
+//! - Italian (`it`)
+//! - Portuguese (`pt`)
+
+use crate::models::{ManeuverModifier, ManeuverType};
+
+/// A feature of a pedestrian path which warrants its own instruction
+/// (ex: for users who cannot take the stairs).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PedestrianFeature {
+    Crosswalk,
+    Stairs,
+    Elevator,
+}
+
+/// A phrase, with and without the name of the road that the maneuver leads onto.
+///
+/// The templates may contain `{modifier}` and `{road}` placeholders.
+struct Phrase {
+    plain: &'static str,
+    onto_road: &'static str,
+}
+
+/// The translation table for a language.
+struct Phrases {
+    /// Directions, from sharp right to sharp left (in the order of [`ManeuverModifier`]).
+    modifiers: [&'static str; 7],
+    depart: Phrase,
+    arrive: Phrase,
+    turn: Phrase,
+    keep: Phrase,
+    continue_on: Phrase,
+    u_turn: Phrase,
+    merge: Phrase,
+    on_ramp: Phrase,
+    off_ramp: Phrase,
+    enter_roundabout: Phrase,
+    exit_roundabout: Phrase,
+    take_stairs: Phrase,
+    take_elevator: Phrase,
+    cross_street: Phrase,
+}
+
+const ENGLISH: Phrases = Phrases {
+    modifiers: [
+        "sharp right",
+        "right",
+        "slight right",
+        "straight",
+        "slight left",
+        "left",
+        "sharp left",
+    ],
+    depart: Phrase {
+        plain: "Depart",
+        onto_road: "Depart on {road}",
+    },
+    arrive: Phrase {
+        plain: "You have arrived at your destination",
+        onto_road: "You have arrived at your destination",
+    },
+    turn: Phrase {
+        plain: "Turn {modifier}",
+        onto_road: "Turn {modifier} onto {road}",
+    },
+    keep: Phrase {
+        plain: "Keep {modifier}",
+        onto_road: "Keep {modifier} onto {road}",
+    },
+    continue_on: Phrase {
+        plain: "Continue",
+        onto_road: "Continue on {road}",
+    },
+    u_turn: Phrase {
+        plain: "Make a U-turn",
+        onto_road: "Make a U-turn onto {road}",
+    },
+    merge: Phrase {
+        plain: "Merge",
+        onto_road: "Merge onto {road}",
+    },
+    on_ramp: Phrase {
+        plain: "Take the ramp",
+        onto_road: "Take the ramp onto {road}",
+    },
+    off_ramp: Phrase {
+        plain: "Take the exit",
+        onto_road: "Take the exit onto {road}",
+    },
+    enter_roundabout: Phrase {
+        plain: "Enter the roundabout",
+        onto_road: "Enter the roundabout and exit onto {road}",
+    },
+    exit_roundabout: Phrase {
+        plain: "Exit the roundabout",
+        onto_road: "Exit the roundabout onto {road}",
+    },
+    take_stairs: Phrase {
+        plain: "Take the stairs",
+        onto_road: "Take the stairs to {road}",
+    },
+    take_elevator: Phrase {
+        plain: "Take the elevator",
+        onto_road: "Take the elevator to {road}",
+    },
+    cross_street: Phrase {
+        plain: "Cross the street",
+        onto_road: "Cross the street to {road}",
+    },
+};
+
+const GERMAN: Phrases = Phrases {
+    modifiers: [
+        "scharf rechts",
+        "rechts",
+        "leicht rechts",
+        "geradeaus",
+        "leicht links",
+        "links",
+        "scharf links",
+    ],
+    depart: Phrase {
+        plain: "Fahren Sie los",
+        onto_road: "Fahren Sie auf {road} los",
+    },
+    arrive: Phrase {
+        plain: "Sie haben Ihr Ziel erreicht",
+        onto_road: "Sie haben Ihr Ziel erreicht",
+    },
+    turn: Phrase {
+        plain: "Biegen Sie {modifier} ab",
+        onto_road: "Biegen Sie {modifier} auf {road} ab",
+    },
+    keep: Phrase {
+        plain: "Halten Sie sich {modifier}",
+        onto_road: "Halten Sie sich {modifier} auf {road}",
+    },
+    continue_on: Phrase {
+        plain: "Fahren Sie weiter",
+        onto_road: "Fahren Sie weiter auf {road}",
+    },
+    u_turn: Phrase {
+        plain: "Wenden Sie",
+        onto_road: "Wenden Sie auf {road}",
+    },
+    merge: Phrase {
+        plain: "Fädeln Sie sich ein",
+        onto_road: "Fädeln Sie sich auf {road} ein",
+    },
+    on_ramp: Phrase {
+        plain: "Nehmen Sie die Auffahrt",
+        onto_road: "Nehmen Sie die Auffahrt auf {road}",
+    },
+    off_ramp: Phrase {
+        plain: "Nehmen Sie die Ausfahrt",
+        onto_road: "Nehmen Sie die Ausfahrt auf {road}",
+    },
+    enter_roundabout: Phrase {
+        plain: "Fahren Sie in den Kreisverkehr",
+        onto_road: "Fahren Sie in den Kreisverkehr und verlassen Sie ihn auf {road}",
+    },
+    exit_roundabout: Phrase {
+        plain: "Verlassen Sie den Kreisverkehr",
+        onto_road: "Verlassen Sie den Kreisverkehr auf {road}",
+    },
+    take_stairs: Phrase {
+        plain: "Nehmen Sie die Treppe",
+        onto_road: "Nehmen Sie die Treppe zu {road}",
+    },
+    take_elevator: Phrase {
+        plain: "Nehmen Sie den Aufzug",
+        onto_road: "Nehmen Sie den Aufzug zu {road}",
+    },
+    cross_street: Phrase {
+        plain: "Überqueren Sie die Straße",
+        onto_road: "Überqueren Sie die Straße zu {road}",
+    },
+};
+
+const FRENCH: Phrases = Phrases {
+    modifiers: [
+        "franchement à droite",
+        "à droite",
+        "légèrement à droite",
+        "tout droit",
+        "légèrement à gauche",
+        "à gauche",
+        "franchement à gauche",
+    ],
+    depart: Phrase {
+        plain: "Partez",
+        onto_road: "Partez sur {road}",
+    },
+    arrive: Phrase {
+        plain: "Vous êtes arrivé à destination",
+        onto_road: "Vous êtes arrivé à destination",
+    },
+    turn: Phrase {
+        plain: "Tournez {modifier}",
+        onto_road: "Tournez {modifier} sur {road}",
+    },
+    keep: Phrase {
+        plain: "Restez {modifier}",
+        onto_road: "Restez {modifier} sur {road}",
+    },
+    continue_on: Phrase {
+        plain: "Continuez",
+        onto_road: "Continuez sur {road}",
+    },
+    u_turn: Phrase {
+        plain: "Faites demi-tour",
+        onto_road: "Faites demi-tour sur {road}",
+    },
+    merge: Phrase {
+        plain: "Insérez-vous",
+        onto_road: "Insérez-vous sur {road}",
+    },
+    on_ramp: Phrase {
+        plain: "Prenez la bretelle",
+        onto_road: "Prenez la bretelle vers {road}",
+    },
+    off_ramp: Phrase {
+        plain: "Prenez la sortie",
+        onto_road: "Prenez la sortie vers {road}",
+    },
+    enter_roundabout: Phrase {
+        plain: "Entrez dans le rond-point",
+        onto_road: "Entrez dans le rond-point et sortez sur {road}",
+    },
+    exit_roundabout: Phrase {
+        plain: "Sortez du rond-point",
+        onto_road: "Sortez du rond-point sur {road}",
+    },
+    take_stairs: Phrase {
+        plain: "Prenez l'escalier",
+        onto_road: "Prenez l'escalier vers {road}",
+    },
+    take_elevator: Phrase {
+        plain: "Prenez l'ascenseur",
+        onto_road: "Prenez l'ascenseur vers {road}",
+    },
+    cross_street: Phrase {
+        plain: "Traversez la rue",
+        onto_road: "Traversez la rue vers {road}",
+    },
+};
+
+const SPANISH: Phrases = Phrases {
+    modifiers: [
+        "bruscamente a la derecha",
+        "a la derecha",
+        "ligeramente a la derecha",
+        "recto",
+        "ligeramente a la izquierda",
+        "a la izquierda",
+        "bruscamente a la izquierda",
+    ],
+    depart: Phrase {
+        plain: "Salga",
+        onto_road: "Salga por {road}",
+    },
+    arrive: Phrase {
+        plain: "Ha llegado a su destino",
+        onto_road: "Ha llegado a su destino",
+    },
+    turn: Phrase {
+        plain: "Gire {modifier}",
+        onto_road: "Gire {modifier} hacia {road}",
+    },
+    keep: Phrase {
+        plain: "Manténgase {modifier}",
+        onto_road: "Manténgase {modifier} hacia {road}",
+    },
+    continue_on: Phrase {
+        plain: "Continúe",
+        onto_road: "Continúe por {road}",
+    },
+    u_turn: Phrase {
+        plain: "Cambie de sentido",
+        onto_road: "Cambie de sentido hacia {road}",
+    },
+    merge: Phrase {
+        plain: "Incorpórese",
+        onto_road: "Incorpórese a {road}",
+    },
+    on_ramp: Phrase {
+        plain: "Tome la rampa",
+        onto_road: "Tome la rampa hacia {road}",
+    },
+    off_ramp: Phrase {
+        plain: "Tome la salida",
+        onto_road: "Tome la salida hacia {road}",
+    },
+    enter_roundabout: Phrase {
+        plain: "Entre en la rotonda",
+        onto_road: "Entre en la rotonda y salga por {road}",
+    },
+    exit_roundabout: Phrase {
+        plain: "Salga de la rotonda",
+        onto_road: "Salga de la rotonda por {road}",
+    },
+    take_stairs: Phrase {
+        plain: "Tome las escaleras",
+        onto_road: "Tome las escaleras hacia {road}",
+    },
+    take_elevator: Phrase {
+        plain: "Tome el ascensor",
+        onto_road: "Tome el ascensor hacia {road}",
+    },
+    cross_street: Phrase {
+        plain: "Cruce la calle",
+        onto_road: "Cruce la calle hacia {road}",
+    },
+};
+
+const ITALIAN: Phrases = Phrases {
+    modifiers: [
+        "nettamente a destra",
+        "a destra",
+        "leggermente a destra",
+        "dritto",
+        "leggermente a sinistra",
+        "a sinistra",
+        "nettamente a sinistra",
+    ],
+    depart: Phrase {
+        plain: "Parti",
+        onto_road: "Parti su {road}",
+    },
+    arrive: Phrase {
+        plain: "Sei arrivato a destinazione",
+        onto_road: "Sei arrivato a destinazione",
+    },
+    turn: Phrase {
+        plain: "Svolta {modifier}",
+        onto_road: "Svolta {modifier} su {road}",
+    },
+    keep: Phrase {
+        plain: "Tieniti {modifier}",
+        onto_road: "Tieniti {modifier} su {road}",
+    },
+    continue_on: Phrase {
+        plain: "Prosegui",
+        onto_road: "Prosegui su {road}",
+    },
+    u_turn: Phrase {
+        plain: "Fai inversione a U",
+        onto_road: "Fai inversione a U su {road}",
+    },
+    merge: Phrase {
+        plain: "Immettiti",
+        onto_road: "Immettiti su {road}",
+    },
+    on_ramp: Phrase {
+        plain: "Prendi la rampa",
+        onto_road: "Prendi la rampa per {road}",
+    },
+    off_ramp: Phrase {
+        plain: "Prendi l'uscita",
+        onto_road: "Prendi l'uscita per {road}",
+    },
+    enter_roundabout: Phrase {
+        plain: "Entra nella rotonda",
+        onto_road: "Entra nella rotonda ed esci su {road}",
+    },
+    exit_roundabout: Phrase {
+        plain: "Esci dalla rotonda",
+        onto_road: "Esci dalla rotonda su {road}",
+    },
+    take_stairs: Phrase {
+        plain: "Prendi le scale",
+        onto_road: "Prendi le scale verso {road}",
+    },
+    take_elevator: Phrase {
+        plain: "Prendi l'ascensore",
+        onto_road: "Prendi l'ascensore verso {road}",
+    },
+    cross_street: Phrase {
+        plain: "Attraversa la strada",
+        onto_road: "Attraversa la strada verso {road}",
+    },
+};
+
+const PORTUGUESE: Phrases = Phrases {
+    modifiers: [
+        "acentuadamente à direita",
+        "à direita",
+        "levemente à direita",
+        "em frente",
+        "levemente à esquerda",
+        "à esquerda",
+        "acentuadamente à esquerda",
+    ],
+    depart: Phrase {
+        plain: "Siga",
+        onto_road: "Siga por {road}",
+    },
+    arrive: Phrase {
+        plain: "Você chegou ao seu destino",
+        onto_road: "Você chegou ao seu destino",
+    },
+    turn: Phrase {
+        plain: "Vire {modifier}",
+        onto_road: "Vire {modifier} em {road}",
+    },
+    keep: Phrase {
+        plain: "Mantenha-se {modifier}",
+        onto_road: "Mantenha-se {modifier} em {road}",
+    },
+    continue_on: Phrase {
+        plain: "Continue",
+        onto_road: "Continue em {road}",
+    },
+    u_turn: Phrase {
+        plain: "Faça o retorno",
+        onto_road: "Faça o retorno em {road}",
+    },
+    merge: Phrase {
+        plain: "Entre na via",
+        onto_road: "Entre em {road}",
+    },
+    on_ramp: Phrase {
+        plain: "Pegue a rampa",
+        onto_road: "Pegue a rampa para {road}",
+    },
+    off_ramp: Phrase {
+        plain: "Pegue a saída",
+        onto_road: "Pegue a saída para {road}",
+    },
+    enter_roundabout: Phrase {
+        plain: "Entre na rotatória",
+        onto_road: "Entre na rotatória e saia em {road}",
+    },
+    exit_roundabout: Phrase {
+        plain: "Saia da rotatória",
+        onto_road: "Saia da rotatória em {road}",
+    },
+    take_stairs: Phrase {
+        plain: "Pegue a escada",
+        onto_road: "Pegue a escada para {road}",
+    },
+    take_elevator: Phrase {
+        plain: "Pegue o elevador",
+        onto_road: "Pegue o elevador para {road}",
+    },
+    cross_street: Phrase {
+        plain: "Atravesse a rua",
+        onto_road: "Atravesse a rua para {road}",
+    },
+};
+
+impl Phrases {
+    /// Looks up the translation table for a locale (ex: `de-AT`), falling back to English.
+    fn for_locale(locale: &str) -> &'static Phrases {
+        let language = locale
+            .split(['-', '_'])
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        match language.as_str() {
+            "de" => &GERMAN,
+            "fr" => &FRENCH,
+            "es" => &SPANISH,
+            "it" => &ITALIAN,
+            "pt" => &PORTUGUESE,
+            _ => &ENGLISH,
+        }
+    }
+
+    fn modifier(&self, modifier: ManeuverModifier) -> &'static str {
+        match modifier {
+            ManeuverModifier::SharpRight => self.modifiers[0],
+            ManeuverModifier::Right => self.modifiers[1],
+            ManeuverModifier::SlightRight => self.modifiers[2],
+            ManeuverModifier::Straight | ManeuverModifier::UTurn => self.modifiers[3],
+            ManeuverModifier::SlightLeft => self.modifiers[4],
+            ManeuverModifier::Left => self.modifiers[5],
+            ManeuverModifier::SharpLeft => self.modifiers[6],
+        }
+    }
+}
+
+/// Synthesizes an instruction for a maneuver in the language of the locale (ex: `de-DE`).
+///
+/// The road name is the name of the road that the maneuver leads onto (if any).
+/// Unsupported locales fall back to English.
+pub fn synthesize_instruction(
+    locale: &str,
+    maneuver_type: Option<ManeuverType>,
+    maneuver_modifier: Option<ManeuverModifier>,
+    road_name: Option<&str>,
+) -> String {
+    let phrases = Phrases::for_locale(locale);
+    let (phrase, modifier) = match (maneuver_type, maneuver_modifier) {
+        (Some(ManeuverType::Depart), _) => (&phrases.depart, None),
+        (Some(ManeuverType::Arrive), _) => (&phrases.arrive, None),
+        (_, Some(ManeuverModifier::UTurn)) => (&phrases.u_turn, None),
+        (
+            Some(ManeuverType::Roundabout | ManeuverType::Rotary | ManeuverType::RoundaboutTurn),
+            _,
+        ) => (&phrases.enter_roundabout, None),
+        (Some(ManeuverType::ExitRoundabout | ManeuverType::ExitRotary), _) => {
+            (&phrases.exit_roundabout, None)
+        }
+        (Some(ManeuverType::Merge), _) => (&phrases.merge, None),
+        (Some(ManeuverType::OnRamp), _) => (&phrases.on_ramp, None),
+        (Some(ManeuverType::OffRamp), _) => (&phrases.off_ramp, None),
+        // Forks only indicate which side to keep to
+        (
+            Some(ManeuverType::Fork),
+            Some(
+                ManeuverModifier::SharpLeft | ManeuverModifier::Left | ManeuverModifier::SlightLeft,
+            ),
+        ) => (&phrases.keep, Some(ManeuverModifier::Left)),
+        (
+            Some(ManeuverType::Fork),
+            Some(
+                ManeuverModifier::SharpRight
+                | ManeuverModifier::Right
+                | ManeuverModifier::SlightRight,
+            ),
+        ) => (&phrases.keep, Some(ManeuverModifier::Right)),
+        (Some(ManeuverType::Turn | ManeuverType::EndOfRoad) | None, Some(modifier))
+            if modifier != ManeuverModifier::Straight =>
+        {
+            (&phrases.turn, Some(modifier))
+        }
+        _ => (&phrases.continue_on, None),
+    };
+
+    let road_name = road_name.map(str::trim).filter(|name| !name.is_empty());
+    let template = if road_name.is_some() {
+        phrase.onto_road
+    } else {
+        phrase.plain
+    };
+    template
+        .replace(
+            "{modifier}",
+            modifier.map_or("", |modifier| phrases.modifier(modifier)),
+        )
+        .replace("{road}", road_name.unwrap_or_default())
+}
+
+/// Synthesizes an instruction to take a pedestrian feature in the language of the locale (ex: `de-DE`).
+///
+/// The road name is the name of the path that the feature leads onto (if any).
+/// Unsupported locales fall back to English.
+pub fn synthesize_pedestrian_instruction(
+    locale: &str,
+    feature: PedestrianFeature,
+    road_name: Option<&str>,
+) -> String {
+    let phrases = Phrases::for_locale(locale);
+    let phrase = match feature {
+        PedestrianFeature::Crosswalk => &phrases.cross_street,
+        PedestrianFeature::Stairs => &phrases.take_stairs,
+        PedestrianFeature::Elevator => &phrases.take_elevator,
+    };
+    match road_name.map(str::trim).filter(|name| !name.is_empty()) {
+        Some(road_name) => phrase.onto_road.replace("{road}", road_name),
+        None => phrase.plain.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn english_instructions() {
+        assert_eq!(
+            synthesize_instruction(
+                "en-US",
+                Some(ManeuverType::Turn),
+                Some(ManeuverModifier::SlightLeft),
+                Some("Main Street")
+            ),
+            "Turn slight left onto Main Street"
+        );
+        assert_eq!(
+            synthesize_instruction(
+                "en",
+                Some(ManeuverType::Fork),
+                Some(ManeuverModifier::SlightRight),
+                None
+            ),
+            "Keep right"
+        );
+        assert_eq!(
+            synthesize_instruction("en", Some(ManeuverType::NewName), None, Some(" ")),
+            "Continue"
+        );
+        assert_eq!(
+            synthesize_instruction(
+                "en",
+                Some(ManeuverType::Continue),
+                Some(ManeuverModifier::UTurn),
+                None
+            ),
+            "Make a U-turn"
+        );
+        assert_eq!(
+            synthesize_instruction("en", Some(ManeuverType::Arrive), None, Some("Main Street")),
+            "You have arrived at your destination"
+        );
+    }
+
+    #[test]
+    fn localized_instructions() {
+        assert_eq!(
+            synthesize_instruction(
+                "de-DE",
+                Some(ManeuverType::Turn),
+                Some(ManeuverModifier::Right),
+                Some("Torstraße")
+            ),
+            "Biegen Sie rechts auf Torstraße ab"
+        );
+        assert_eq!(
+            synthesize_instruction(
+                "fr_CA",
+                Some(ManeuverType::EndOfRoad),
+                Some(ManeuverModifier::Left),
+                None
+            ),
+            "Tournez à gauche"
+        );
+        assert_eq!(
+            synthesize_instruction("ES", Some(ManeuverType::Roundabout), None, Some("Gran Vía")),
+            "Entre en la rotonda y salga por Gran Vía"
+        );
+        assert_eq!(
+            synthesize_instruction("it", Some(ManeuverType::Depart), None, None),
+            "Parti"
+        );
+        assert_eq!(
+            synthesize_instruction("pt-BR", Some(ManeuverType::OffRamp), None, None),
+            "Pegue a saída"
+        );
+    }
+
+    #[test]
+    fn unsupported_locales_fall_back_to_english() {
+        assert_eq!(
+            synthesize_instruction(
+                "tlh",
+                Some(ManeuverType::Turn),
+                Some(ManeuverModifier::Left),
+                None
+            ),
+            "Turn left"
+        );
+        assert_eq!(
+            synthesize_instruction("", Some(ManeuverType::Merge), None, None),
+            "Merge"
+        );
+    }
+
+    #[test]
+    fn pedestrian_instructions() {
+        assert_eq!(
+            synthesize_pedestrian_instruction("en", PedestrianFeature::Stairs, None),
+            "Take the stairs"
+        );
+        assert_eq!(
+            synthesize_pedestrian_instruction(
+                "en-GB",
+                PedestrianFeature::Elevator,
+                Some("Platform 2")
+            ),
+            "Take the elevator to Platform 2"
+        );
+        assert_eq!(
+            synthesize_pedestrian_instruction("de", PedestrianFeature::Crosswalk, Some(" ")),
+            "Überqueren Sie die Straße"
+        );
+        assert_eq!(
+            synthesize_pedestrian_instruction("tlh", PedestrianFeature::Crosswalk, None),
+            "Cross the street"
+        );
+    }
+}
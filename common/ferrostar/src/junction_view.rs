@@ -0,0 +1,70 @@
+//! Junction views (and signboards), which are images of complex junctions
+//! showing the lanes and signs to follow.
+//!
+//! Some backends (ex: Mapbox) reference junction view images in their responses,
+//! which are parsed into [`RouteStep::junction_views`](crate::models::RouteStep::junction_views).
+//! A [`NavigationEvent::JunctionViewApproaching`] event is reported when the user approaches one,
+//! and apps may supply a [`JunctionViewResolver`] to fetch the image ahead of time.
+
+use crate::models::JunctionView;
+use crate::navigation_controller::models::NavigationEvent;
+
+/// Fetches the images for junction views as the user approaches them.
+///
+/// This is called synchronously during location updates, so implementations must return quickly.
+/// The image should be fetched in the background
+/// (ex: into a cache that the junction view UI reads from).
+#[uniffi::export(with_foreign)]
+pub trait JunctionViewResolver: Send + Sync {
+    /// Starts fetching the image for a junction view that the user is approaching.
+    fn fetch_image(&self, junction_view: JunctionView);
+}
+
+/// Notifies the resolver of each junction view approached in a sequence of events.
+pub(crate) fn fetch_approached_images(
+    resolver: &dyn JunctionViewResolver,
+    events: &[NavigationEvent],
+) {
+    for event in events {
+        if let NavigationEvent::JunctionViewApproaching { junction_view } = event {
+            resolver.fetch_image(junction_view.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct RecordingResolver(Mutex<Vec<JunctionView>>);
+
+    impl JunctionViewResolver for RecordingResolver {
+        fn fetch_image(&self, junction_view: JunctionView) {
+            self.0.lock().unwrap().push(junction_view);
+        }
+    }
+
+    #[test]
+    fn fetches_approached_images() {
+        let junction_view = JunctionView {
+            image_reference: "https://example.com/jct/1".to_string(),
+            kind: Some("jct".to_string()),
+            trigger_distance_before_maneuver: 500.0,
+        };
+        let resolver = RecordingResolver::default();
+
+        fetch_approached_images(
+            &resolver,
+            &[
+                NavigationEvent::TripCompleted,
+                NavigationEvent::JunctionViewApproaching {
+                    junction_view: junction_view.clone(),
+                },
+            ],
+        );
+
+        assert_eq!(*resolver.0.lock().unwrap(), vec![junction_view]);
+    }
+}
@@ -9,16 +9,64 @@
 //! 0.1.0 (est. mid-April).
 
 pub mod algorithms;
+pub mod alternative_routes;
+pub mod announcement_timing;
+pub mod arrival_detection;
+pub mod breadcrumbs;
+pub mod camera;
+pub mod climbs;
+pub mod clock;
+pub mod closure_avoidance;
+pub mod consumption;
 pub mod deviation_detection;
+pub mod elevation;
+pub mod final_approach;
+pub mod free_drive;
+pub mod geocoding;
+pub mod geometry_util;
+pub mod instructions;
+pub mod junction_view;
+pub mod location_filtering;
+pub mod logging;
 pub mod models;
 pub mod navigation_controller;
+#[cfg(feature = "offline-bundles")]
+pub mod offline_bundle;
+pub mod projection;
+pub mod rerouting;
+pub mod road_name;
+pub mod route_scoring;
 pub mod routing_adapters;
+pub mod search_along_route;
 pub mod simulation;
+pub mod ssml;
+pub mod stale_routes;
+pub mod telemetry;
+#[cfg(feature = "test-utils")]
+pub mod test_utils;
+pub mod tile_prefetch;
+pub mod trip_optimization;
+pub mod trip_summary;
+pub mod tunnel_mode;
+pub mod units;
+#[cfg(feature = "wasm-js")]
+pub mod wasm;
 
+use crate::clock::Clock;
+use crate::navigation_controller::models::NavigationControllerConfig;
+use crate::navigation_controller::presets::NavigationPreset;
+use crate::routing_adapters::ev::{EvParameters, OsrmEvHttpRequestGenerator};
 use crate::routing_adapters::osrm::OsrmResponseParser;
-use crate::routing_adapters::valhalla::ValhallaHttpRequestGenerator;
+use crate::routing_adapters::otp::OtpResponseParser;
+use crate::routing_adapters::post_processing::{
+    ContinueStepMerger, InstructionReplacement, InstructionRewriter, RoutePostProcessor,
+};
+use crate::routing_adapters::protobuf::ProtobufResponseParser;
+use crate::routing_adapters::valhalla::{ValhallaHttpRequestGenerator, ValhallaRequestOptions};
+use crate::routing_adapters::valhalla_multimodal::ValhallaMultimodalResponseParser;
 use std::str::FromStr;
 use std::sync::Arc;
+use std::time::SystemTime;
 use uuid::Uuid;
 
 use crate::routing_adapters::error::InstantiationError;
@@ -26,6 +74,19 @@ use routing_adapters::{RouteRequestGenerator, RouteResponseParser};
 
 uniffi::setup_scaffolding!();
 
+/// The current time.
+///
+/// The system clock is not available on `wasm32-unknown-unknown`, so we ask JavaScript instead.
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+pub(crate) fn now() -> SystemTime {
+    SystemTime::UNIX_EPOCH + std::time::Duration::from_secs_f64(js_sys::Date::now() / 1000.0)
+}
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+pub(crate) fn now() -> SystemTime {
+    SystemTime::now()
+}
+
 uniffi::custom_type!(Uuid, String);
 
 impl UniffiCustomTypeConverter for Uuid {
@@ -67,6 +128,52 @@ fn create_valhalla_request_generator(
     ))
 }
 
+/// Creates a [`RouteRequestGenerator`] for Valhalla with any combination of request options
+/// (ex: the instruction language, accessibility requirements, or areas to avoid).
+///
+/// See [`ValhallaHttpRequestGenerator::with_options`] for details.
+#[uniffi::export]
+fn create_valhalla_request_generator_with_options(
+    endpoint_url: String,
+    profile: String,
+    options: ValhallaRequestOptions,
+) -> Result<Arc<dyn RouteRequestGenerator>, InstantiationError> {
+    Ok(Arc::new(ValhallaHttpRequestGenerator::with_options(
+        endpoint_url,
+        profile,
+        options,
+    )?))
+}
+
+/// Creates a [`RouteRequestGenerator`] for an OSRM-compatible directions API with EV routing extensions.
+///
+/// See [`OsrmEvHttpRequestGenerator`] for details.
+#[uniffi::export]
+fn create_osrm_ev_request_generator(
+    base_url: String,
+    ev: EvParameters,
+) -> Arc<dyn RouteRequestGenerator> {
+    Arc::new(OsrmEvHttpRequestGenerator::new(base_url, ev))
+}
+
+/// Creates a [`RoutePostProcessor`] which rewrites the text of instructions.
+///
+/// See [`InstructionRewriter`] for details.
+#[uniffi::export]
+fn create_instruction_rewriter(
+    replacements: Vec<InstructionReplacement>,
+) -> Arc<dyn RoutePostProcessor> {
+    Arc::new(InstructionRewriter::new(replacements))
+}
+
+/// Creates a [`RoutePostProcessor`] which merges short steps that only continue along the way.
+///
+/// See [`ContinueStepMerger`] for details.
+#[uniffi::export]
+fn create_continue_step_merger(max_distance: f64) -> Arc<dyn RoutePostProcessor> {
+    Arc::new(ContinueStepMerger::new(max_distance))
+}
+
 /// Creates a [`RouteResponseParser`] capable of parsing OSRM responses.
 ///
 /// This response parser is designed to be fairly flexible,
@@ -76,3 +183,81 @@ fn create_valhalla_request_generator(
 fn create_osrm_response_parser(polyline_precision: u32) -> Arc<dyn RouteResponseParser> {
     Arc::new(OsrmResponseParser::new(polyline_precision))
 }
+
+/// Creates a [`RouteResponseParser`] capable of parsing OSRM responses,
+/// which stamps [`models::Route::planned_at`] using the clock.
+///
+/// See [`OsrmResponseParser::with_clock`] for details.
+#[uniffi::export]
+fn create_osrm_response_parser_with_clock(
+    polyline_precision: u32,
+    clock: Arc<dyn Clock>,
+) -> Arc<dyn RouteResponseParser> {
+    Arc::new(OsrmResponseParser::new(polyline_precision).with_clock(clock))
+}
+
+/// Creates a [`RouteResponseParser`] capable of parsing OSRM responses,
+/// which synthesizes any missing instructions in the language of a locale (ex: `de-DE`).
+///
+/// See [`OsrmResponseParser::with_locale`] for details.
+#[uniffi::export]
+fn create_localized_osrm_response_parser(
+    polyline_precision: u32,
+    locale: String,
+) -> Arc<dyn RouteResponseParser> {
+    Arc::new(OsrmResponseParser::new(polyline_precision).with_locale(locale))
+}
+
+/// Creates a [`RouteResponseParser`] capable of parsing OSRM responses,
+/// which parses steps lazily to reduce peak memory usage.
+///
+/// This is slightly slower than [`create_osrm_response_parser`],
+/// but is recommended for very long routes with multi-megabyte responses.
+/// See [`OsrmResponseParser::with_lazy_step_parsing`] for details.
+#[uniffi::export]
+fn create_lazy_osrm_response_parser(polyline_precision: u32) -> Arc<dyn RouteResponseParser> {
+    Arc::new(OsrmResponseParser::with_lazy_step_parsing(
+        polyline_precision,
+    ))
+}
+
+/// Creates a [`RouteResponseParser`] capable of parsing [OpenTripPlanner](https://www.opentripplanner.org/) plan responses.
+///
+/// This enables multimodal (public transit) navigation.
+#[uniffi::export]
+fn create_otp_response_parser() -> Arc<dyn RouteResponseParser> {
+    Arc::new(OtpResponseParser::new())
+}
+
+/// Creates a [`RouteResponseParser`] capable of parsing multimodal (public transit) routes
+/// from the Valhalla native route API.
+///
+/// `utc_offset_seconds` is used for the stop times when the response does not include an offset
+/// (see [`ValhallaMultimodalResponseParser::with_utc_offset`]).
+#[uniffi::export]
+fn create_valhalla_multimodal_response_parser(
+    utc_offset_seconds: Option<i32>,
+) -> Arc<dyn RouteResponseParser> {
+    let parser = ValhallaMultimodalResponseParser::new();
+    Arc::new(match utc_offset_seconds {
+        Some(seconds) => parser.with_utc_offset(seconds),
+        None => parser,
+    })
+}
+
+/// Creates a [`RouteResponseParser`] capable of parsing routes in the Protocol Buffers exchange format
+/// (see [`routing_adapters::protobuf`]).
+///
+/// This is useful for routes which are computed ahead of time and shipped to devices.
+#[uniffi::export]
+fn create_protobuf_response_parser() -> Arc<dyn RouteResponseParser> {
+    Arc::new(ProtobufResponseParser::new())
+}
+
+/// Creates a [`NavigationControllerConfig`] for a common mode of travel.
+///
+/// See [`NavigationControllerConfig::from_preset`] for details.
+#[uniffi::export]
+fn create_navigation_controller_config(preset: NavigationPreset) -> NavigationControllerConfig {
+    NavigationControllerConfig::from_preset(preset)
+}
@@ -0,0 +1,384 @@
+use crate::models::{CourseOverGround, Speed, UserLocation};
+use geo::{GeodesicBearing, HaversineDistance, Point};
+#[cfg(feature = "wasm-js")]
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, SystemTime};
+
+/// Determines which location updates are discarded as stale.
+///
+/// Location providers occasionally deliver fixes out of order (ex: when replaying buffered fixes).
+/// Processing an older fix after a newer one moves the user backward along the route,
+/// which can undo step advances and trigger spurious deviation warnings.
+#[derive(Debug, Copy, Clone, PartialEq, uniffi::Enum)]
+pub enum StaleLocationRejection {
+    /// Every location update is processed.
+    None,
+    /// Location updates are discarded unless they are newer than the last one processed.
+    Monotonic {
+        /// The maximum age of a location update, in seconds.
+        ///
+        /// Older updates are discarded as well.
+        /// This is measured against the configured clock (usually the system clock)
+        /// when the update is processed.
+        max_age: Option<f64>,
+    },
+}
+
+impl StaleLocationRejection {
+    /// Checks whether a location update should be discarded.
+    ///
+    /// `last_timestamp` is the timestamp of the last location processed, and `now` is the current time.
+    pub(crate) fn is_stale(
+        &self,
+        location: &UserLocation,
+        last_timestamp: SystemTime,
+        now: SystemTime,
+    ) -> bool {
+        match self {
+            StaleLocationRejection::None => false,
+            StaleLocationRejection::Monotonic { max_age } => {
+                let is_out_of_order = location.timestamp <= last_timestamp;
+                let is_too_old = max_age
+                    .and_then(|max_age| Duration::try_from_secs_f64(max_age).ok())
+                    .is_some_and(|max_age| {
+                        // Locations from the future have no age
+                        now.duration_since(location.timestamp)
+                            .is_ok_and(|age| age > max_age)
+                    });
+                is_out_of_order || is_too_old
+            }
+        }
+    }
+}
+
+/// Determines which location updates are discarded as too inaccurate to navigate with.
+///
+/// Discarded updates leave the trip state unchanged (aside from the [`PositioningStatus`]),
+/// so a poor fix cannot cause a spurious deviation or step advance.
+#[derive(Debug, Copy, Clone, PartialEq, uniffi::Enum)]
+pub enum AccuracyGating {
+    /// Every location update is used, regardless of accuracy.
+    None,
+    /// Location updates are discarded if their horizontal accuracy exceeds a threshold.
+    MaxHorizontalAccuracy {
+        /// The maximum horizontal accuracy, in meters.
+        max_horizontal_accuracy: f64,
+        /// The number of consecutive discarded updates after which positioning is considered degraded.
+        degraded_after: u16,
+    },
+}
+
+/// Whether recent location updates have been accurate enough to navigate with.
+#[derive(Debug, Copy, Clone, PartialEq, uniffi::Enum)]
+#[cfg_attr(feature = "wasm-js", derive(Serialize, Deserialize))]
+pub enum PositioningStatus {
+    /// The last location update was used.
+    Accurate,
+    /// The last few location updates were discarded as too inaccurate.
+    Inaccurate { consecutive_rejections: u16 },
+    /// Enough consecutive location updates were discarded that navigation is effectively frozen.
+    ///
+    /// Apps should let the user know that the positioning signal is poor.
+    DegradedPositioning { consecutive_rejections: u16 },
+}
+
+impl AccuracyGating {
+    /// Checks a location update against the gating policy.
+    ///
+    /// Returns [`PositioningStatus::Accurate`] if the location should be used,
+    /// and the updated rejection status otherwise.
+    pub(crate) fn check(
+        &self,
+        location: &UserLocation,
+        previous_status: PositioningStatus,
+    ) -> PositioningStatus {
+        match self {
+            AccuracyGating::None => PositioningStatus::Accurate,
+            AccuracyGating::MaxHorizontalAccuracy {
+                max_horizontal_accuracy,
+                degraded_after,
+            } => {
+                if location.horizontal_accuracy <= *max_horizontal_accuracy {
+                    return PositioningStatus::Accurate;
+                }
+
+                let consecutive_rejections = match previous_status {
+                    PositioningStatus::Accurate => 1,
+                    PositioningStatus::Inaccurate {
+                        consecutive_rejections,
+                    }
+                    | PositioningStatus::DegradedPositioning {
+                        consecutive_rejections,
+                    } => consecutive_rejections.saturating_add(1),
+                };
+                if consecutive_rejections >= *degraded_after {
+                    PositioningStatus::DegradedPositioning {
+                        consecutive_rejections,
+                    }
+                } else {
+                    PositioningStatus::Inaccurate {
+                        consecutive_rejections,
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Determines whether missing speed and course values are derived from successive locations.
+///
+/// Some location providers (and most simulated or replayed locations) omit them,
+/// which disables features that depend on them, like [`crate::deviation_detection::WrongDirectionDetection`].
+/// Derived values are attached to the location before any other processing,
+/// so they are also reflected in the trip state.
+#[derive(Debug, Copy, Clone, PartialEq, uniffi::Enum)]
+pub enum MotionDerivation {
+    /// Locations are used as reported.
+    None,
+    /// Missing values are estimated from the distance and bearing between successive locations.
+    ///
+    /// The previous location is the last snapped location,
+    /// so estimates are most reliable while the user is following the route.
+    SuccessiveLocations {
+        /// The minimum time between locations, in seconds.
+        ///
+        /// Shorter intervals amplify positioning noise, so nothing is derived for them.
+        min_interval: f64,
+    },
+}
+
+impl MotionDerivation {
+    /// Fills in the speed and course of `location` (if missing) based on the previous location.
+    pub(crate) fn apply(
+        &self,
+        location: UserLocation,
+        previous_location: &UserLocation,
+    ) -> UserLocation {
+        let MotionDerivation::SuccessiveLocations { min_interval } = self else {
+            return location;
+        };
+        if location.speed.is_some() && location.course_over_ground.is_some() {
+            return location;
+        }
+
+        let Ok(interval) = location
+            .timestamp
+            .duration_since(previous_location.timestamp)
+        else {
+            return location;
+        };
+        let interval = interval.as_secs_f64();
+        if interval <= 0.0 || interval < *min_interval {
+            return location;
+        }
+
+        let previous_point = Point::from(*previous_location);
+        let point = Point::from(location);
+        let distance = previous_point.haversine_distance(&point);
+        let speed = location.speed.or(Some(Speed {
+            value: distance / interval,
+            accuracy: None,
+        }));
+        // The bearing is meaningless when standing still
+        let course_over_ground = location.course_over_ground.or_else(|| {
+            (distance > 0.0).then(|| {
+                let degrees = previous_point
+                    .geodesic_bearing(point)
+                    .rem_euclid(360.0)
+                    .round();
+                CourseOverGround::new(degrees as u16 % 360, None)
+            })
+        });
+
+        UserLocation {
+            speed,
+            course_over_ground,
+            ..location
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::navigation_controller::test_helpers::gen_user_location;
+
+    #[test]
+    fn no_rejection() {
+        let now = SystemTime::now();
+        let old_location =
+            gen_user_location(0.0, 0.0, 0).with_timestamp(now - Duration::from_secs(3600));
+        assert!(!StaleLocationRejection::None.is_stale(&old_location, now, now));
+    }
+
+    #[test]
+    fn monotonic_timestamps() {
+        let now = SystemTime::now();
+        let last_timestamp = now - Duration::from_secs(10);
+        let rejection = StaleLocationRejection::Monotonic { max_age: None };
+
+        assert!(rejection.is_stale(
+            &gen_user_location(0.0, 0.0, 0).with_timestamp(last_timestamp - Duration::from_secs(1)),
+            last_timestamp,
+            now
+        ));
+        // Duplicates are not newer either
+        assert!(rejection.is_stale(
+            &gen_user_location(0.0, 0.0, 0).with_timestamp(last_timestamp),
+            last_timestamp,
+            now
+        ));
+        assert!(!rejection.is_stale(
+            &gen_user_location(0.0, 0.0, 0).with_timestamp(last_timestamp + Duration::from_secs(1)),
+            last_timestamp,
+            now
+        ));
+    }
+
+    #[test]
+    fn max_age() {
+        let now = SystemTime::now();
+        let last_timestamp = now - Duration::from_secs(60);
+        let rejection = StaleLocationRejection::Monotonic { max_age: Some(5.0) };
+
+        assert!(rejection.is_stale(
+            &gen_user_location(0.0, 0.0, 0).with_timestamp(now - Duration::from_secs(10)),
+            last_timestamp,
+            now
+        ));
+        assert!(!rejection.is_stale(
+            &gen_user_location(0.0, 0.0, 0).with_timestamp(now - Duration::from_secs(2)),
+            last_timestamp,
+            now
+        ));
+        // Clocks are not perfectly synchronized, so a location may appear to come from the future
+        assert!(!rejection.is_stale(
+            &gen_user_location(0.0, 0.0, 0).with_timestamp(now + Duration::from_secs(2)),
+            last_timestamp,
+            now
+        ));
+    }
+
+    #[test]
+    fn no_accuracy_gating() {
+        assert_eq!(
+            AccuracyGating::None.check(
+                &gen_user_location(0.0, 0.0, 0).with_horizontal_accuracy(1000.0),
+                PositioningStatus::Accurate
+            ),
+            PositioningStatus::Accurate
+        );
+    }
+
+    #[test]
+    fn accuracy_gating_degrades_after_consecutive_rejections() {
+        let gating = AccuracyGating::MaxHorizontalAccuracy {
+            max_horizontal_accuracy: 20.0,
+            degraded_after: 3,
+        };
+        let inaccurate_location = gen_user_location(0.0, 0.0, 0).with_horizontal_accuracy(50.0);
+
+        let status = gating.check(&inaccurate_location, PositioningStatus::Accurate);
+        assert_eq!(
+            status,
+            PositioningStatus::Inaccurate {
+                consecutive_rejections: 1
+            }
+        );
+        let status = gating.check(&inaccurate_location, status);
+        assert_eq!(
+            status,
+            PositioningStatus::Inaccurate {
+                consecutive_rejections: 2
+            }
+        );
+        let status = gating.check(&inaccurate_location, status);
+        assert_eq!(
+            status,
+            PositioningStatus::DegradedPositioning {
+                consecutive_rejections: 3
+            }
+        );
+        let status = gating.check(&inaccurate_location, status);
+        assert_eq!(
+            status,
+            PositioningStatus::DegradedPositioning {
+                consecutive_rejections: 4
+            }
+        );
+
+        // A single accurate location restores normal operation
+        assert_eq!(
+            gating.check(
+                &gen_user_location(0.0, 0.0, 0).with_horizontal_accuracy(20.0),
+                status
+            ),
+            PositioningStatus::Accurate
+        );
+    }
+
+    #[test]
+    fn no_motion_derivation() {
+        let now = SystemTime::now();
+        let previous = gen_user_location(0.0, 0.0, 0).with_timestamp(now - Duration::from_secs(10));
+        let location = gen_user_location(0.0, 0.001, 0).with_timestamp(now);
+
+        assert_eq!(MotionDerivation::None.apply(location, &previous), location);
+    }
+
+    #[test]
+    fn derive_speed_and_course() {
+        let now = SystemTime::now();
+        let derivation = MotionDerivation::SuccessiveLocations { min_interval: 1.0 };
+        let previous = gen_user_location(0.0, 0.0, 0).with_timestamp(now - Duration::from_secs(10));
+        // ~111m due east
+        let location = gen_user_location(0.001, 0.0, 0).with_timestamp(now);
+
+        let derived = derivation.apply(location, &previous);
+        let speed = derived.speed.expect("Expected a derived speed");
+        assert!((speed.value - 11.1).abs() < 0.1, "{}", speed.value);
+        assert_eq!(
+            derived.course_over_ground,
+            Some(CourseOverGround::new(90, None))
+        );
+
+        // Reported values take precedence
+        let reported = UserLocation {
+            speed: Some(Speed {
+                value: 12.0,
+                accuracy: Some(1.0),
+            }),
+            ..location
+        };
+        let derived = derivation.apply(reported, &previous);
+        assert_eq!(derived.speed, reported.speed);
+        assert_eq!(
+            derived.course_over_ground,
+            Some(CourseOverGround::new(90, None))
+        );
+
+        // Nothing is derived over short intervals
+        let too_soon =
+            gen_user_location(0.0, 0.0, 0).with_timestamp(now - Duration::from_millis(500));
+        assert_eq!(derivation.apply(location, &too_soon), location);
+    }
+
+    #[test]
+    fn no_course_when_stationary() {
+        let now = SystemTime::now();
+        let derivation = MotionDerivation::SuccessiveLocations { min_interval: 1.0 };
+        let derived = derivation.apply(
+            gen_user_location(0.0, 0.0, 0).with_timestamp(now),
+            &gen_user_location(0.0, 0.0, 0).with_timestamp(now - Duration::from_secs(5)),
+        );
+
+        assert_eq!(
+            derived.speed,
+            Some(Speed {
+                value: 0.0,
+                accuracy: None
+            })
+        );
+        assert_eq!(derived.course_over_ground, None);
+    }
+}
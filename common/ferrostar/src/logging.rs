@@ -0,0 +1,257 @@
+//! Structured logs of the decisions made by the core (ex: why a step advanced or the user went off route).
+//!
+//! With the `tracing` feature, the route parsers and navigation controller emit
+//! [`tracing`](https://docs.rs/tracing) spans and events.
+//! Rust apps can collect them with any subscriber,
+//! and mobile apps can forward them to their own logging with [`set_log_sink`]
+//! (ex: to attach them to support tickets).
+//!
+//! Without the feature, the logging macros expand to nothing.
+
+/// Emits a `tracing` event at the given level (ex: `debug`) when the `tracing` feature is enabled.
+///
+/// The arguments are the same as those of the `tracing` macros.
+/// They are not evaluated when the feature is disabled, so they should not have side effects.
+macro_rules! log_event {
+    ($level:ident, $($arg:tt)+) => {
+        #[cfg(feature = "tracing")]
+        {
+            tracing::$level!($($arg)+);
+        }
+    };
+}
+
+/// Enters a debug level `tracing` span until the end of the enclosing block
+/// when the `tracing` feature is enabled.
+macro_rules! enter_span {
+    ($name:literal $(, $($field:tt)+)?) => {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!($name $(, $($field)+)?).entered();
+    };
+}
+
+pub(crate) use enter_span;
+pub(crate) use log_event;
+
+#[cfg(feature = "tracing")]
+pub use sink::{clear_log_sink, set_log_sink, LogLevel, LogRecord, LogSink};
+
+#[cfg(feature = "tracing")]
+mod sink {
+    use std::collections::HashMap;
+    use std::fmt::Debug;
+    use std::sync::{Arc, Once, PoisonError, RwLock};
+    use tracing::field::{Field, Visit};
+    use tracing::{Event, Level, Subscriber};
+    use tracing_subscriber::layer::{Context, SubscriberExt};
+    use tracing_subscriber::registry::LookupSpan;
+    use tracing_subscriber::{Layer, Registry};
+
+    /// The severity of a log record.
+    #[derive(Debug, Copy, Clone, PartialEq, Eq, uniffi::Enum)]
+    pub enum LogLevel {
+        Error,
+        Warn,
+        Info,
+        Debug,
+        Trace,
+    }
+
+    impl From<LogLevel> for Level {
+        fn from(level: LogLevel) -> Self {
+            match level {
+                LogLevel::Error => Level::ERROR,
+                LogLevel::Warn => Level::WARN,
+                LogLevel::Info => Level::INFO,
+                LogLevel::Debug => Level::DEBUG,
+                LogLevel::Trace => Level::TRACE,
+            }
+        }
+    }
+
+    impl From<&Level> for LogLevel {
+        fn from(level: &Level) -> Self {
+            [
+                LogLevel::Error,
+                LogLevel::Warn,
+                LogLevel::Info,
+                LogLevel::Debug,
+            ]
+            .into_iter()
+            .find(|candidate| Level::from(*candidate) == *level)
+            .unwrap_or(LogLevel::Trace)
+        }
+    }
+
+    /// A log event from the core.
+    #[derive(Debug, Clone, PartialEq, uniffi::Record)]
+    pub struct LogRecord {
+        pub level: LogLevel,
+        /// The module which emitted the event (ex: `ferrostar::navigation_controller`).
+        pub target: String,
+        pub message: String,
+        /// The structured fields of the event (ex: the distance from the route line),
+        /// formatted as strings.
+        pub fields: HashMap<String, String>,
+        /// The names of the spans which the event occurred in, from the outermost
+        /// (ex: `update_user_location`).
+        pub spans: Vec<String>,
+    }
+
+    /// Receives the log records from the core.
+    ///
+    /// This is called synchronously as events occur (possibly from any thread),
+    /// so implementations must return quickly (ex: by queueing the record).
+    #[uniffi::export(with_foreign)]
+    pub trait LogSink: Send + Sync {
+        fn log(&self, record: LogRecord);
+    }
+
+    struct SinkConfig {
+        sink: Arc<dyn LogSink>,
+        max_level: Level,
+    }
+
+    static SINK: RwLock<Option<SinkConfig>> = RwLock::new(None);
+    static INSTALL_SUBSCRIBER: Once = Once::new();
+
+    /// Forwards the core's log records at `max_level` or above to the sink,
+    /// replacing any previous sink.
+    ///
+    /// NOTE: The first call installs a global `tracing` subscriber,
+    /// so this should not be used by apps which install their own.
+    #[uniffi::export]
+    pub fn set_log_sink(sink: Arc<dyn LogSink>, max_level: LogLevel) {
+        *SINK.write().unwrap_or_else(PoisonError::into_inner) = Some(SinkConfig {
+            sink,
+            max_level: max_level.into(),
+        });
+        INSTALL_SUBSCRIBER.call_once(|| {
+            // This fails if the app installed its own subscriber, which then takes precedence
+            let _ = tracing::subscriber::set_global_default(Registry::default().with(SinkLayer));
+        });
+    }
+
+    /// Stops forwarding log records to the sink.
+    #[uniffi::export]
+    pub fn clear_log_sink() {
+        *SINK.write().unwrap_or_else(PoisonError::into_inner) = None;
+    }
+
+    /// Forwards events to the configured [`LogSink`].
+    struct SinkLayer;
+
+    impl<S: Subscriber + for<'a> LookupSpan<'a>> Layer<S> for SinkLayer {
+        fn on_event(&self, event: &Event<'_>, context: Context<'_, S>) {
+            // The lock is released before calling the sink, in case it logs anything itself
+            let Some((sink, max_level)) = SINK
+                .read()
+                .unwrap_or_else(PoisonError::into_inner)
+                .as_ref()
+                .map(|config| (Arc::clone(&config.sink), config.max_level))
+            else {
+                return;
+            };
+            // More verbose levels compare as greater
+            let level = event.metadata().level();
+            if *level > max_level {
+                return;
+            }
+
+            let mut visitor = FieldVisitor::default();
+            event.record(&mut visitor);
+            let spans = context
+                .event_scope(event)
+                .map(|scope| {
+                    scope
+                        .from_root()
+                        .map(|span| span.name().to_string())
+                        .collect()
+                })
+                .unwrap_or_default();
+
+            sink.log(LogRecord {
+                level: level.into(),
+                target: event.metadata().target().to_string(),
+                message: visitor.message,
+                fields: visitor.fields,
+                spans,
+            });
+        }
+    }
+
+    #[derive(Default)]
+    struct FieldVisitor {
+        message: String,
+        fields: HashMap<String, String>,
+    }
+
+    impl Visit for FieldVisitor {
+        fn record_str(&mut self, field: &Field, value: &str) {
+            if field.name() == "message" {
+                self.message = value.to_string();
+            } else {
+                self.fields
+                    .insert(field.name().to_string(), value.to_string());
+            }
+        }
+
+        fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+            if field.name() == "message" {
+                self.message = format!("{value:?}");
+            } else {
+                self.fields
+                    .insert(field.name().to_string(), format!("{value:?}"));
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use std::sync::Mutex;
+
+        #[derive(Default)]
+        struct RecordingSink {
+            records: Mutex<Vec<LogRecord>>,
+        }
+
+        impl LogSink for RecordingSink {
+            fn log(&self, record: LogRecord) {
+                self.records.lock().unwrap().push(record);
+            }
+        }
+
+        #[test]
+        fn forwards_events_to_the_sink() {
+            let sink = Arc::new(RecordingSink::default());
+            *SINK.write().unwrap() = Some(SinkConfig {
+                sink: sink.clone(),
+                max_level: Level::DEBUG,
+            });
+
+            // A scoped subscriber, so that tests do not depend on the global one
+            tracing::subscriber::with_default(Registry::default().with(SinkLayer), || {
+                let _span = tracing::debug_span!("update_user_location").entered();
+                tracing::info!(deviation_from_route_line = 42.5, "User is off route");
+                tracing::trace!("Too verbose to be forwarded");
+            });
+            clear_log_sink();
+
+            let records = sink.records.lock().unwrap();
+            assert_eq!(
+                *records,
+                vec![LogRecord {
+                    level: LogLevel::Info,
+                    target: module_path!().to_string(),
+                    message: "User is off route".to_string(),
+                    fields: HashMap::from([(
+                        "deviation_from_route_line".to_string(),
+                        "42.5".to_string()
+                    )]),
+                    spans: vec!["update_user_location".to_string()],
+                }]
+            );
+        }
+    }
+}
@@ -1,21 +1,29 @@
-use geo::{Coord, LineString, Point, Rect};
+use crate::elevation::ElevationProfile;
+use geo::{BoundingRect, Coord, LineString, Point, Rect};
 use polyline::encode_coordinates;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::time::SystemTime;
-
-#[cfg(test)]
-use serde::Serialize;
 use uuid::Uuid;
 
 #[derive(Debug, thiserror::Error, uniffi::Error)]
 pub enum ModelError {
     #[error("Failed to generate a polyline from route coordinates: {error}.")]
     PolylineGenerationError { error: String },
+    #[error("Failed to serialize or deserialize a route: {error}.")]
+    RouteSerializationError { error: String },
+    #[error("The serialized route has an unsupported format version ({version}).")]
+    UnsupportedRouteVersion { version: u32 },
+    #[error("The route geometry is empty.")]
+    EmptyRouteGeometry,
+    #[error("The route has no steps.")]
+    NoRouteSteps,
+    #[error("Invalid coordinate ({lat}, {lng}).")]
+    InvalidCoordinate { lat: f64, lng: f64 },
 }
 
 /// A geographic coordinate in WGS84.
-#[derive(Clone, Copy, PartialEq, PartialOrd, Debug, uniffi::Record)]
-#[cfg_attr(test, derive(Serialize))]
+#[derive(Clone, Copy, PartialEq, PartialOrd, Debug, Serialize, Deserialize, uniffi::Record)]
 pub struct GeographicCoordinate {
     pub lat: f64,
     pub lng: f64,
@@ -65,16 +73,14 @@ impl From<GeographicCoordinate> for Point {
 /// and are used for recalculating when the user deviates from the expected route.
 ///
 /// Note that support for properties beyond basic geographic coordinates varies by routing engine.
-#[derive(Clone, Copy, PartialEq, PartialOrd, Debug, uniffi::Record)]
-#[cfg_attr(test, derive(Serialize))]
+#[derive(Clone, Copy, PartialEq, PartialOrd, Debug, Serialize, Deserialize, uniffi::Record)]
 pub struct Waypoint {
     pub coordinate: GeographicCoordinate,
     pub kind: WaypointKind,
 }
 
 /// Describes characteristics of the waypoint for the routing backend.
-#[derive(Clone, Copy, PartialEq, PartialOrd, Debug, uniffi::Enum)]
-#[cfg_attr(test, derive(Serialize))]
+#[derive(Clone, Copy, PartialEq, PartialOrd, Debug, Serialize, Deserialize, uniffi::Enum)]
 pub enum WaypointKind {
     /// Starts or ends a leg of the trip.
     ///
@@ -82,10 +88,17 @@ pub enum WaypointKind {
     Break,
     /// A waypoint that is simply passed through, but will not have any arrival or departure instructions.
     Via,
+    /// The stop at which the user boards a public transit vehicle.
+    ///
+    /// These are only present in multimodal routes.
+    TransitBoard,
+    /// The stop at which the user alights from a public transit vehicle.
+    ///
+    /// These are only present in multimodal routes.
+    TransitAlight,
 }
 
-#[derive(Clone, Copy, PartialEq, PartialOrd, Debug, uniffi::Record)]
-#[cfg_attr(test, derive(Serialize))]
+#[derive(Clone, Copy, PartialEq, PartialOrd, Debug, Serialize, Deserialize, uniffi::Record)]
 pub struct BoundingBox {
     pub sw: GeographicCoordinate,
     pub ne: GeographicCoordinate,
@@ -100,6 +113,43 @@ impl From<Rect> for BoundingBox {
     }
 }
 
+impl BoundingBox {
+    /// Returns a new bounding box which is expanded by (approximately) `meters` on every side.
+    ///
+    /// The conversion from meters to degrees uses a spherical approximation,
+    /// which is plenty for framing the map camera.
+    /// Coordinates are clamped to the valid WGS84 range (so the box never crosses the antimeridian).
+    #[must_use]
+    pub fn padded(&self, meters: f64) -> Self {
+        const METERS_PER_DEGREE: f64 = 111_320.0;
+
+        let lat_padding = meters / METERS_PER_DEGREE;
+        // Use the latitude furthest from the equator so that the padding is never too small
+        let max_abs_lat = self.sw.lat.abs().max(self.ne.lat.abs()).min(89.0);
+        let lng_padding = meters / (METERS_PER_DEGREE * max_abs_lat.to_radians().cos());
+
+        Self {
+            sw: GeographicCoordinate {
+                lat: (self.sw.lat - lat_padding).max(-90.0),
+                lng: (self.sw.lng - lng_padding).max(-180.0),
+            },
+            ne: GeographicCoordinate {
+                lat: (self.ne.lat + lat_padding).min(90.0),
+                lng: (self.ne.lng + lng_padding).min(180.0),
+            },
+        }
+    }
+}
+
+/// A polygon, such as an area reachable within a certain time.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, uniffi::Record)]
+pub struct Polygon {
+    /// The outer boundary, as a closed ring (the first and last coordinates are equal).
+    pub exterior: Vec<GeographicCoordinate>,
+    /// Holes within the polygon, as closed rings.
+    pub interiors: Vec<Vec<GeographicCoordinate>>,
+}
+
 /// The heading of the user/device.
 ///
 /// Ferrostar prefers course over ground, but may use heading in some cases.
@@ -115,7 +165,8 @@ pub struct Heading {
 
 /// The direction in which the user/device is observed to be traveling.
 #[derive(Clone, Copy, PartialEq, PartialOrd, Debug, uniffi::Record)]
-#[cfg_attr(test, derive(Serialize))]
+#[cfg_attr(any(test, feature = "wasm-js"), derive(Serialize))]
+#[cfg_attr(feature = "wasm-js", derive(Deserialize))]
 pub struct CourseOverGround {
     /// The direction in which the user's device is traveling, measured in clockwise degrees from
     /// true north (N = 0, E = 90, S = 180, W = 270).
@@ -132,7 +183,8 @@ impl CourseOverGround {
 
 /// The speed of the user from the location provider.
 #[derive(Clone, Copy, PartialEq, PartialOrd, Debug, uniffi::Record)]
-#[cfg_attr(test, derive(Serialize))]
+#[cfg_attr(any(test, feature = "wasm-js"), derive(Serialize))]
+#[cfg_attr(feature = "wasm-js", derive(Deserialize))]
 pub struct Speed {
     /// The user's speed in meters per second.
     pub value: f64,
@@ -148,7 +200,8 @@ pub struct Speed {
 /// NOTE: Heading is absent on purpose.
 /// Heading updates are not related to a change in the user's location.
 #[derive(Clone, Copy, PartialEq, PartialOrd, Debug, uniffi::Record)]
-#[cfg_attr(test, derive(Serialize))]
+#[cfg_attr(any(test, feature = "wasm-js"), derive(Serialize))]
+#[cfg_attr(feature = "wasm-js", derive(Deserialize))]
 pub struct UserLocation {
     pub coordinates: GeographicCoordinate,
     /// The estimated accuracy of the coordinate (in meters)
@@ -157,6 +210,15 @@ pub struct UserLocation {
     #[cfg_attr(test, serde(skip_serializing))]
     pub timestamp: SystemTime,
     pub speed: Option<Speed>,
+    /// The altitude above mean sea level, in meters.
+    ///
+    /// Note that some platforms report altitude above the WGS84 ellipsoid instead,
+    /// which must be converted to compare it with route elevation profiles.
+    #[uniffi(default = None)]
+    pub altitude: Option<f64>,
+    /// The estimated accuracy of the altitude, in meters.
+    #[uniffi(default = None)]
+    pub vertical_accuracy: Option<f64>,
 }
 
 impl From<UserLocation> for Point {
@@ -169,18 +231,74 @@ impl From<UserLocation> for Point {
 ///
 /// NOTE: This type is unstable and is still under active development and should be
 /// considered unstable.
-#[derive(Clone, Debug, uniffi::Record)]
-#[cfg_attr(test, derive(Serialize))]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, uniffi::Record)]
 pub struct Route {
     pub geometry: Vec<GeographicCoordinate>,
     pub bbox: BoundingBox,
     /// The total route distance, in meters.
     pub distance: f64,
+    /// The estimated travel time of the route, in seconds.
+    #[serde(default)]
+    pub duration: f64,
+    /// The total weight (cost) of the route, as reported by the routing backend.
+    ///
+    /// Lower is better, but weights are only comparable between routes using the same [`Route::weight_name`].
+    #[serde(default)]
+    #[uniffi(default = None)]
+    pub weight: Option<f64>,
+    /// The name of the metric used for the [`Route::weight`] (ex: `routability` or `duration`).
+    #[serde(default)]
+    #[uniffi(default = None)]
+    pub weight_name: Option<String>,
     /// The ordered list of waypoints to visit, including the starting point.
     /// Note that this is distinct from the *geometry* which includes all points visited.
     /// A waypoint represents a start/end point for a route leg.
     pub waypoints: Vec<Waypoint>,
     pub steps: Vec<RouteStep>,
+    /// The elevation profile of the route, if available.
+    ///
+    /// Most routing APIs do not include this in the route response,
+    /// so it is usually attached separately (see [`crate::elevation`]).
+    #[uniffi(default = None)]
+    pub elevation_profile: Option<ElevationProfile>,
+    /// Traffic incidents along the route, ordered by their position along the route.
+    ///
+    /// The built-in parsers only support Mapbox incidents (in OSRM responses).
+    /// Other incident formats (ex: HERE) need a custom [`crate::routing_adapters::RouteResponseParser`].
+    pub incidents: Vec<Incident>,
+    /// Stops to charge an electric vehicle, in the order they are visited.
+    ///
+    /// These are only planned by backends which support EV routing
+    /// (see [`crate::routing_adapters::ev`]).
+    #[serde(default)]
+    pub charging_stops: Vec<ChargingStop>,
+    /// Details of the final destination, like its entrances and nearby parking (if known).
+    ///
+    /// Most routing APIs do not include these in the route response,
+    /// so apps usually supply them from their own place data
+    /// (see [`crate::final_approach`]).
+    #[serde(default)]
+    #[uniffi(default = None)]
+    pub destination_details: Option<DestinationDetails>,
+    /// Notices from the routing backend about caveats of the route
+    /// (ex: that it could not satisfy the requested accessibility options),
+    /// which apps may show before starting navigation.
+    #[serde(default)]
+    pub notices: Vec<RouteNotice>,
+    /// When the route was fetched from the routing backend, if known.
+    ///
+    /// This is set by the built-in response parsers (using their configured clock, if any),
+    /// and is kept when the route is saved (ex: with [`Route::to_bytes`]),
+    /// so that resuming a saved route can tell how old it is (see [`crate::stale_routes`]).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[uniffi(default = None)]
+    pub planned_at: Option<SystemTime>,
+    /// Fields of the backend's route which are not otherwise parsed, as JSON (by field name).
+    ///
+    /// This preserves custom backend outputs (ex: from custom Valhalla costing models).
+    /// It is only populated when the `backend-extras` feature is enabled.
+    #[serde(default)]
+    pub extras: HashMap<String, String>,
 }
 
 /// Helper function for getting the route as an encoded polyline.
@@ -192,14 +310,194 @@ fn get_route_polyline(route: &Route, precision: u32) -> Result<String, ModelErro
         .map_err(|error| ModelError::PolylineGenerationError { error })
 }
 
+/// Creates a route from its raw parts. See [`Route::from_parts`].
+///
+/// This lets apps with their own routing source use the navigation controller.
+#[uniffi::export]
+fn create_route_from_parts(
+    geometry: Vec<GeographicCoordinate>,
+    waypoints: Vec<Waypoint>,
+    steps: Vec<RouteStep>,
+) -> Result<Route, ModelError> {
+    Route::from_parts(geometry, waypoints, steps)
+}
+
+/// Creates a route step without any transit details or tunnels.
+#[uniffi::export]
+fn create_route_step(
+    geometry: Vec<GeographicCoordinate>,
+    distance: f64,
+    duration: f64,
+    road_name: Option<String>,
+    instruction: String,
+    visual_instructions: Vec<VisualInstruction>,
+    spoken_instructions: Vec<SpokenInstruction>,
+) -> RouteStep {
+    RouteStep {
+        geometry,
+        distance,
+        duration,
+        road_name,
+        instruction,
+        visual_instructions,
+        spoken_instructions,
+        transit_leg: None,
+        has_tolls: false,
+        is_ferry: false,
+        tunnels: vec![],
+        country_changes: vec![],
+        speed_limit_sign: None,
+        speed_limit_unit: None,
+        driving_side: None,
+        intersections: vec![],
+        junction_views: vec![],
+        has_crosswalk: false,
+        has_stairs: false,
+        has_elevator: false,
+        shields: vec![],
+        extras: HashMap::new(),
+    }
+}
+
+/// The version of the format written by [`Route::to_bytes`].
+///
+/// This must be incremented whenever a change to the route models
+/// would prevent previously saved routes from being read.
+const ROUTE_FORMAT_VERSION: u32 = 1;
+
+#[derive(Deserialize)]
+struct RouteFormatHeader {
+    version: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+struct VersionedRoute<R> {
+    version: u32,
+    route: R,
+}
+
+impl From<serde_json::Error> for ModelError {
+    fn from(e: serde_json::Error) -> Self {
+        ModelError::RouteSerializationError {
+            error: e.to_string(),
+        }
+    }
+}
+
+impl Route {
+    /// Builds a route from its raw parts (ex: from a routing source implemented on the platform side).
+    ///
+    /// The bounding box is computed from the geometry,
+    /// and the distance is the sum of the step distances.
+    /// If no waypoints are given, the start and end of the geometry are used.
+    pub fn from_parts(
+        geometry: Vec<GeographicCoordinate>,
+        waypoints: Vec<Waypoint>,
+        steps: Vec<RouteStep>,
+    ) -> Result<Self, ModelError> {
+        if let Some(coordinate) = geometry
+            .iter()
+            .chain(steps.iter().flat_map(|step| step.geometry.iter()))
+            .find(|coordinate| {
+                !(-90.0..=90.0).contains(&coordinate.lat)
+                    || !(-180.0..=180.0).contains(&coordinate.lng)
+            })
+        {
+            return Err(ModelError::InvalidCoordinate {
+                lat: coordinate.lat,
+                lng: coordinate.lng,
+            });
+        }
+        if steps.is_empty() {
+            return Err(ModelError::NoRouteSteps);
+        }
+        let linestring: LineString = geometry.iter().map(|c| Coord::from(*c)).collect();
+        let (Some(bbox), Some(start), Some(end)) = (
+            linestring.bounding_rect(),
+            geometry.first().copied(),
+            geometry.last().copied(),
+        ) else {
+            return Err(ModelError::EmptyRouteGeometry);
+        };
+
+        let waypoints = if waypoints.is_empty() {
+            [start, end]
+                .into_iter()
+                .map(|coordinate| Waypoint {
+                    coordinate,
+                    kind: WaypointKind::Break,
+                })
+                .collect()
+        } else {
+            waypoints
+        };
+
+        Ok(Self {
+            geometry,
+            bbox: bbox.into(),
+            distance: steps.iter().map(|step| step.distance).sum(),
+            duration: steps.iter().map(|step| step.duration).sum(),
+            weight: None,
+            weight_name: None,
+            waypoints,
+            steps,
+            elevation_profile: None,
+            incidents: vec![],
+            charging_stops: vec![],
+            destination_details: None,
+            notices: vec![],
+            planned_at: None,
+            extras: HashMap::new(),
+        })
+    }
+
+    /// Serializes the route so that it can be saved (ex: for a planned trip)
+    /// and loaded later with [`Route::from_bytes`] without contacting the routing server again.
+    ///
+    /// The format is versioned JSON.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, ModelError> {
+        Ok(serde_json::to_vec(&VersionedRoute {
+            version: ROUTE_FORMAT_VERSION,
+            route: self,
+        })?)
+    }
+
+    /// Deserializes a route saved with [`Route::to_bytes`].
+    ///
+    /// Fails with [`ModelError::UnsupportedRouteVersion`]
+    /// if the route was saved in a different format version.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ModelError> {
+        let header: RouteFormatHeader = serde_json::from_slice(bytes)?;
+        if header.version != ROUTE_FORMAT_VERSION {
+            return Err(ModelError::UnsupportedRouteVersion {
+                version: header.version,
+            });
+        }
+
+        let versioned: VersionedRoute<Route> = serde_json::from_slice(bytes)?;
+        Ok(versioned.route)
+    }
+}
+
+/// Serializes a route for storage. See [`Route::to_bytes`].
+#[uniffi::export]
+fn route_to_bytes(route: &Route) -> Result<Vec<u8>, ModelError> {
+    route.to_bytes()
+}
+
+/// Deserializes a route saved with [`route_to_bytes`]. See [`Route::from_bytes`].
+#[uniffi::export]
+fn route_from_bytes(bytes: &[u8]) -> Result<Route, ModelError> {
+    Route::from_bytes(bytes)
+}
+
 /// A maneuver (such as a turn or merge) followed by travel of a certain distance until reaching
 /// the next step.
 ///
 /// NOTE: OSRM specifies this rather precisely as "travel along a single way to the subsequent step"
 /// but we will intentionally define this somewhat looser unless/until it becomes clear something
 ///
-#[derive(Clone, Debug, PartialEq, uniffi::Record)]
-#[cfg_attr(test, derive(Serialize))]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, uniffi::Record)]
 pub struct RouteStep {
     pub geometry: Vec<GeographicCoordinate>,
     /// The distance, in meters, to travel along the route after the maneuver to reach the next step.
@@ -210,6 +508,60 @@ pub struct RouteStep {
     pub instruction: String,
     pub visual_instructions: Vec<VisualInstruction>,
     pub spoken_instructions: Vec<SpokenInstruction>,
+    /// Details of the public transit vehicle the user is riding during this step (if any).
+    #[uniffi(default = None)]
+    pub transit_leg: Option<TransitLeg>,
+    /// Whether the step includes a toll road.
+    #[serde(default)]
+    #[uniffi(default = false)]
+    pub has_tolls: bool,
+    /// Whether the step is a ferry crossing.
+    #[serde(default)]
+    #[uniffi(default = false)]
+    pub is_ferry: bool,
+    /// The portions of the step which pass through tunnels.
+    pub tunnels: Vec<Tunnel>,
+    /// The points along the step at which the route enters a different country, in order.
+    #[serde(default)]
+    pub country_changes: Vec<CountryChange>,
+    /// The style of the speed limit signs along the step (if known), which varies by region.
+    #[serde(default)]
+    #[uniffi(default = None)]
+    pub speed_limit_sign: Option<SpeedLimitSign>,
+    /// The unit of the speed limits posted along the step (if known).
+    #[serde(default)]
+    #[uniffi(default = None)]
+    pub speed_limit_unit: Option<SpeedUnit>,
+    /// The side of the road on which traffic drives along the step (if known).
+    #[serde(default)]
+    #[uniffi(default = None)]
+    pub driving_side: Option<DrivingSide>,
+    /// The intersections along the step, starting with the one at the maneuver (if known).
+    #[serde(default)]
+    pub intersections: Vec<Intersection>,
+    /// Junction view or signboard images for the upcoming maneuver (if any).
+    #[serde(default)]
+    pub junction_views: Vec<JunctionView>,
+    /// Whether the step crosses a road at a crosswalk.
+    #[serde(default)]
+    #[uniffi(default = false)]
+    pub has_crosswalk: bool,
+    /// Whether the step includes stairs.
+    #[serde(default)]
+    #[uniffi(default = false)]
+    pub has_stairs: bool,
+    /// Whether the step includes an elevator.
+    #[serde(default)]
+    #[uniffi(default = false)]
+    pub has_elevator: bool,
+    /// The route shields of the road traveled along during this step (ex: for rendering highway shields).
+    #[serde(default)]
+    pub shields: Vec<RoadShield>,
+    /// Fields of the backend's step which are not otherwise parsed, as JSON (by field name).
+    ///
+    /// This is only populated when the `backend-extras` feature is enabled.
+    #[serde(default)]
+    pub extras: HashMap<String, String>,
 }
 
 impl RouteStep {
@@ -256,8 +608,7 @@ impl RouteStep {
 /// An instruction that can be synthesized using a TTS engine to announce an upcoming maneuver.
 ///
 /// Note that these do not have any locale information attached.
-#[derive(Debug, Clone, PartialEq, uniffi::Record)]
-#[cfg_attr(test, derive(Serialize))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, uniffi::Record)]
 pub struct SpokenInstruction {
     /// Plain-text instruction which can be synthesized with a TTS engine.
     pub text: String,
@@ -274,14 +625,14 @@ pub struct SpokenInstruction {
     /// NOTE: While it is possible to deterministically create UUIDs, we do not do so at this time.
     /// This should be theoretically possible though if someone cares to write up a proposal and a PR.
     #[cfg_attr(test, serde(skip_serializing))]
+    #[serde(default = "Uuid::new_v4")]
     pub utterance_id: Uuid,
 }
 
 /// Indicates the type of maneuver to perform.
 ///
 /// Frequently used in conjunction with [`ManeuverModifier`].
-#[derive(Deserialize, Debug, Copy, Clone, Eq, PartialEq, uniffi::Enum)]
-#[cfg_attr(test, derive(Serialize))]
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, Eq, PartialEq, uniffi::Enum)]
 #[serde(rename_all = "lowercase")]
 pub enum ManeuverType {
     Turn,
@@ -310,8 +661,7 @@ pub enum ManeuverType {
 }
 
 /// Specifies additional information about a [`ManeuverType`]
-#[derive(Deserialize, Debug, Copy, Clone, Eq, PartialEq, uniffi::Enum)]
-#[cfg_attr(test, derive(Serialize))]
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, Eq, PartialEq, uniffi::Enum)]
 #[serde(rename_all = "lowercase")]
 pub enum ManeuverModifier {
     UTurn,
@@ -328,17 +678,24 @@ pub enum ManeuverModifier {
     SharpLeft,
 }
 
-#[derive(Debug, Clone, Eq, PartialEq, uniffi::Record)]
-#[cfg_attr(test, derive(Serialize))]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize, uniffi::Record)]
 pub struct VisualInstructionContent {
     pub text: String,
     pub maneuver_type: Option<ManeuverType>,
     pub maneuver_modifier: Option<ManeuverModifier>,
     pub roundabout_exit_degrees: Option<u16>,
+    /// The exit numbers on the signage for the maneuver (ex: `23`).
+    #[serde(default)]
+    pub exit_numbers: Vec<String>,
+    /// The road references on the signage for the maneuver (ex: `A 7`).
+    #[serde(default)]
+    pub destination_refs: Vec<String>,
+    /// The destinations on the signage for the maneuver (ex: `Hamburg`).
+    #[serde(default)]
+    pub destinations: Vec<String>,
 }
 
-#[derive(Debug, Clone, PartialEq, uniffi::Record)]
-#[cfg_attr(test, derive(Serialize))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, uniffi::Record)]
 pub struct VisualInstruction {
     pub primary_content: VisualInstructionContent,
     pub secondary_content: Option<VisualInstructionContent>,
@@ -346,9 +703,274 @@ pub struct VisualInstruction {
     pub trigger_distance_before_maneuver: f64,
 }
 
+/// The type of vehicle used for a public transit leg.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize, uniffi::Enum)]
+pub enum TransitMode {
+    Tram,
+    Subway,
+    Rail,
+    Bus,
+    Ferry,
+    CableCar,
+    Gondola,
+    Funicular,
+    /// Any other mode of public transport which is not known to Ferrostar.
+    Other,
+}
+
+/// A stop (or station) served by a public transit leg.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, uniffi::Record)]
+pub struct TransitStop {
+    pub name: Option<String>,
+    pub coordinate: GeographicCoordinate,
+    /// The scheduled arrival time at this stop (if known).
+    pub arrival_time: Option<SystemTime>,
+    /// The scheduled departure time from this stop (if known).
+    pub departure_time: Option<SystemTime>,
+}
+
+/// A portion of a trip spent riding a public transit vehicle.
+///
+/// The user boards at the first stop and alights at the last stop;
+/// any stops in between are passed without getting off.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, uniffi::Record)]
+pub struct TransitLeg {
+    pub mode: TransitMode,
+    /// The public-facing name of the line (ex: "M10" or "Blue Line").
+    pub line_name: Option<String>,
+    /// The destination displayed on the vehicle.
+    pub headsign: Option<String>,
+    /// The scheduled departure time from the boarding stop (if known).
+    pub departure_time: Option<SystemTime>,
+    /// The scheduled arrival time at the alighting stop (if known).
+    pub arrival_time: Option<SystemTime>,
+    /// All stops on the leg, starting with the boarding stop and ending with the alighting stop.
+    pub stops: Vec<TransitStop>,
+}
+
+/// A portion of a [`RouteStep`] which passes through a tunnel.
+///
+/// Positioning signals are often lost in tunnels.
+/// Like instruction triggers, the extent of the tunnel is measured backward from the end of the step.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, uniffi::Record)]
+pub struct Tunnel {
+    /// The name of the tunnel (if known).
+    pub name: Option<String>,
+    /// The distance from the tunnel entrance to the end of the step, in meters.
+    pub entrance_distance_before_maneuver: f64,
+    /// The distance from the tunnel exit to the end of the step, in meters.
+    pub exit_distance_before_maneuver: f64,
+}
+
+impl Tunnel {
+    /// Determines whether a position along the step is within the tunnel,
+    /// given the distance to the end of the step.
+    pub fn contains(&self, distance_to_end_of_step: f64) -> bool {
+        (self.exit_distance_before_maneuver..=self.entrance_distance_before_maneuver)
+            .contains(&distance_to_end_of_step)
+    }
+}
+
+/// A point along a [`RouteStep`] at which the route crosses into a different country.
+///
+/// Like instruction triggers, the location of the border is measured backward from the end of the step.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, uniffi::Record)]
+pub struct CountryChange {
+    /// The ISO 3166-1 alpha-2 code of the country being entered (ex: `DE`).
+    pub iso_3166: String,
+    /// The distance from the border to the end of the step, in meters.
+    pub distance_before_maneuver: f64,
+}
+
+/// An intersection along a [`RouteStep`] (ex: for rendering a junction view).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, uniffi::Record)]
+pub struct Intersection {
+    pub location: GeographicCoordinate,
+    /// The bearings of all roads at the intersection, in clockwise degrees from true north.
+    pub bearings: Vec<u16>,
+    /// Whether each of the roads (corresponding 1:1 to the bearings) may be entered on a valid route.
+    pub entry: Vec<bool>,
+    /// The index of the bearing of the road by which the route enters the intersection.
+    ///
+    /// Note that this is the bearing from the intersection, so it is opposite the direction of travel.
+    /// There is no such road at the start of the route.
+    pub bearing_in_index: Option<u32>,
+    /// The index of the bearing of the road by which the route exits the intersection.
+    ///
+    /// There is no such road at the end of the route.
+    pub bearing_out_index: Option<u32>,
+    /// The index of the intersection in the geometry of the leg (if known).
+    pub geometry_index: Option<u64>,
+    /// The additional weight of the turn at the intersection (if known),
+    /// in the units of [`Route::weight`].
+    pub turn_weight: Option<f64>,
+    /// The additional time it takes to make the turn at the intersection (if known), in seconds.
+    pub turn_duration: Option<f64>,
+}
+
+/// A reference to a junction view or signboard image,
+/// which shows the lanes and signs at a complex junction.
+///
+/// See [`crate::junction_view`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, uniffi::Record)]
+pub struct JunctionView {
+    /// A backend-specific reference to the image (ex: a URL).
+    pub image_reference: String,
+    /// The kind of image (ex: `jct` or `signboard`), if known.
+    pub kind: Option<String>,
+    /// How far (in meters) from the upcoming maneuver the image should start being displayed.
+    pub trigger_distance_before_maneuver: f64,
+}
+
+/// The side of the road on which traffic drives.
+///
+/// This determines (among other things) which way U-turns and roundabouts go.
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, Eq, PartialEq, uniffi::Enum)]
+#[serde(rename_all = "lowercase")]
+pub enum DrivingSide {
+    Left,
+    Right,
+}
+
+/// The style of speed limit signs, so that they can be rendered like the ones along the road.
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, Eq, PartialEq, uniffi::Enum)]
+#[serde(rename_all = "lowercase")]
+pub enum SpeedLimitSign {
+    /// A circular sign with a red border, as defined by the Vienna Convention
+    /// (used in most countries).
+    Vienna,
+    /// A rectangular sign, as defined by the Manual on Uniform Traffic Control Devices
+    /// (used in the United States and Canada).
+    Mutcd,
+}
+
+/// The unit in which speed limits are posted.
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, Eq, PartialEq, uniffi::Enum)]
+pub enum SpeedUnit {
+    #[serde(rename = "km/h")]
+    KilometersPerHour,
+    #[serde(rename = "mph")]
+    MilesPerHour,
+}
+
+/// A route shield, identifying a road by its network and number (ex: Interstate 95).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, uniffi::Record)]
+pub struct RoadShield {
+    /// The road network (ex: `us-interstate`), if known.
+    ///
+    /// Network names are backend-specific; OSRM only provides the number.
+    pub network: Option<String>,
+    /// The road number, as displayed on the shield (ex: `95`).
+    pub number: String,
+}
+
+/// The kind of traffic incident.
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, Eq, PartialEq, uniffi::Enum)]
+#[serde(rename_all = "snake_case")]
+pub enum IncidentKind {
+    Accident,
+    Congestion,
+    Construction,
+    DisabledVehicle,
+    LaneRestriction,
+    MassTransit,
+    PlannedEvent,
+    RoadClosure,
+    RoadHazard,
+    Weather,
+    #[serde(other)]
+    Miscellaneous,
+}
+
+/// A traffic incident affecting a portion of the route.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, uniffi::Record)]
+pub struct Incident {
+    /// An identifier assigned by the routing backend.
+    pub id: String,
+    pub kind: IncidentKind,
+    /// A human-readable description of the incident.
+    pub description: Option<String>,
+    /// Whether the road is closed.
+    pub is_closed: bool,
+    /// The distance along the route at which the incident starts, in meters.
+    pub start_distance_along_route: f64,
+    /// The distance along the route at which the incident ends, in meters.
+    pub end_distance_along_route: f64,
+}
+
+/// A type of electric vehicle charging connector (plug).
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq, uniffi::Enum)]
+#[serde(rename_all = "snake_case")]
+pub enum ConnectorType {
+    /// SAE J1772 (Type 1) AC charging.
+    J1772,
+    /// IEC 62196 Type 2 (Mennekes) AC charging.
+    Type2,
+    /// CCS Combo 1 DC fast charging.
+    Ccs1,
+    /// CCS Combo 2 DC fast charging.
+    Ccs2,
+    /// CHAdeMO DC fast charging.
+    Chademo,
+    /// SAE J3400 (NACS, formerly the Tesla connector).
+    Nacs,
+    /// Any other connector, by its backend-specific name.
+    Other { name: String },
+}
+
+/// A notice (or warning) from the routing backend about a caveat of a route
+/// (ex: that there is no access restriction data in the region).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, uniffi::Record)]
+pub struct RouteNotice {
+    /// The backend-specific code of the notice (if any).
+    pub code: Option<u32>,
+    /// The backend-specific kind of notice (ex: `maxHeight`), if any.
+    pub kind: Option<String>,
+    /// A description of the notice.
+    pub message: String,
+}
+
+/// Details of a destination which help the user reach it after arriving at the end of the route.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, uniffi::Record)]
+pub struct DestinationDetails {
+    /// The entrances of the destination (ex: the doors of a building).
+    pub entrances: Vec<GeographicCoordinate>,
+    /// Places to park near the destination, in order of preference.
+    pub parking: Vec<ParkingSuggestion>,
+}
+
+/// A place to park near a destination.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, uniffi::Record)]
+pub struct ParkingSuggestion {
+    pub coordinate: GeographicCoordinate,
+    /// The name of the parking lot or garage (if known).
+    pub name: Option<String>,
+}
+
+/// A stop along the route to charge an electric vehicle.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, uniffi::Record)]
+pub struct ChargingStop {
+    pub coordinate: GeographicCoordinate,
+    /// The name of the charging station (if known).
+    pub name: Option<String>,
+    /// An identifier for the charging station assigned by the backend.
+    pub station_id: Option<String>,
+    /// The expected battery charge on arrival, in kilowatt-hours.
+    pub charge_at_arrival: f64,
+    /// The battery charge to charge to before departing, in kilowatt-hours.
+    pub charge_to: f64,
+    /// The expected time spent charging, in seconds.
+    pub charge_time: f64,
+    /// The maximum power of the charger, in kilowatts (if known).
+    pub power: Option<f64>,
+    /// The connector to use (if known).
+    pub connector_type: Option<ConnectorType>,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::navigation_controller::test_helpers::{gen_dummy_route_step, gen_route_from_steps};
 
     #[test]
     fn test_polyline_encode() {
@@ -358,8 +980,18 @@ mod tests {
             geometry: vec![sw, ne],
             bbox: BoundingBox { sw, ne },
             distance: 0.0,
+            duration: 0.0,
+            weight: None,
+            weight_name: None,
             waypoints: vec![],
             steps: vec![],
+            elevation_profile: None,
+            incidents: vec![],
+            charging_stops: vec![],
+            destination_details: None,
+            notices: vec![],
+            planned_at: None,
+            extras: HashMap::new(),
         };
 
         let polyline5 = get_route_polyline(&route, 5).expect("Unable to encode polyline for route");
@@ -368,4 +1000,85 @@ mod tests {
         let polyline6 = get_route_polyline(&route, 6).expect("Unable to encode polyline for route");
         insta::assert_yaml_snapshot!(polyline6);
     }
+
+    #[test]
+    fn route_from_parts() {
+        let steps = vec![
+            gen_dummy_route_step(0.0, 0.0, 1.0, 1.0),
+            gen_dummy_route_step(1.0, 1.0, 2.0, 0.5),
+        ];
+        let geometry: Vec<_> = steps
+            .iter()
+            .flat_map(|step| step.geometry.clone())
+            .collect();
+
+        let route = Route::from_parts(geometry.clone(), vec![], steps.clone())
+            .expect("Unable to build route");
+        assert_eq!(
+            route.bbox,
+            BoundingBox {
+                sw: GeographicCoordinate { lat: 0.0, lng: 0.0 },
+                ne: GeographicCoordinate { lat: 1.0, lng: 2.0 },
+            }
+        );
+        assert_eq!(route.waypoints.len(), 2);
+        assert_eq!(route.waypoints[1].coordinate, geometry[geometry.len() - 1]);
+
+        assert!(matches!(
+            Route::from_parts(vec![], vec![], steps.clone()),
+            Err(ModelError::EmptyRouteGeometry)
+        ));
+        assert!(matches!(
+            Route::from_parts(geometry.clone(), vec![], vec![]),
+            Err(ModelError::NoRouteSteps)
+        ));
+        assert!(matches!(
+            Route::from_parts(
+                vec![GeographicCoordinate {
+                    lat: 100.0,
+                    lng: 0.0
+                }],
+                vec![],
+                steps
+            ),
+            Err(ModelError::InvalidCoordinate { .. })
+        ));
+    }
+
+    #[test]
+    fn route_bytes_round_trip() {
+        let mut step = gen_dummy_route_step(0.0, 0.0, 1.0, 1.0);
+        step.tunnels = vec![Tunnel {
+            name: Some("Test Tunnel".to_string()),
+            entrance_distance_before_maneuver: 100.0,
+            exit_distance_before_maneuver: 50.0,
+        }];
+        let route = gen_route_from_steps(vec![step]);
+
+        let bytes = route.to_bytes().expect("Unable to serialize route");
+        let loaded = Route::from_bytes(&bytes).expect("Unable to deserialize route");
+        assert_eq!(loaded.geometry, route.geometry);
+        assert_eq!(loaded.bbox, route.bbox);
+        assert_eq!(loaded.waypoints, route.waypoints);
+        assert_eq!(loaded.steps, route.steps);
+    }
+
+    #[test]
+    fn route_bytes_version() {
+        let route = gen_route_from_steps(vec![gen_dummy_route_step(0.0, 0.0, 1.0, 1.0)]);
+        let mut versioned: serde_json::Value =
+            serde_json::from_slice(&route.to_bytes().expect("Unable to serialize route"))
+                .expect("Serialized route is not valid JSON");
+        versioned["version"] = 99.into();
+
+        let bytes = serde_json::to_vec(&versioned).expect("Unable to serialize JSON");
+        assert!(matches!(
+            Route::from_bytes(&bytes),
+            Err(ModelError::UnsupportedRouteVersion { version: 99 })
+        ));
+        assert!(matches!(
+            Route::from_bytes(b"not a route"),
+            Err(ModelError::RouteSerializationError { .. })
+        ));
+    }
 }
@@ -0,0 +1,170 @@
+//! Delta-based trip state updates, which only include what changed since the previous state.
+//!
+//! Most location updates only move the user along the current step,
+//! so copying the full [`TripState`] across the FFI boundary for each one is wasteful.
+//! In the delta update mode, the snapped location and trip progress are sent when they change,
+//! other fields are sent as [`TripStateChange`]s only when they change,
+//! and changes to the remaining steps and waypoints are left to the [`NavigationEvent`]s.
+
+use super::models::{NavigationEvent, TripProgress, TripState, UpcomingIncident};
+use crate::climbs::UpcomingClimb;
+use crate::deviation_detection::RouteDeviation;
+use crate::location_filtering::PositioningStatus;
+use crate::models::{RouteStep, SpokenInstruction, UserLocation, VisualInstruction};
+
+/// A change to one of the less frequently updated fields of [`TripState::Navigating`].
+#[derive(Debug, Clone, PartialEq, uniffi::Enum)]
+pub enum TripStateChange {
+    Deviation {
+        deviation: RouteDeviation,
+    },
+    VisualInstruction {
+        visual_instruction: Option<VisualInstruction>,
+    },
+    ThenStep {
+        then_step: Option<RouteStep>,
+    },
+    SpokenInstruction {
+        spoken_instruction: Option<SpokenInstruction>,
+    },
+    PrefetchedSpokenInstructions {
+        prefetched_spoken_instructions: Vec<SpokenInstruction>,
+    },
+    UpcomingIncident {
+        upcoming_incident: Option<UpcomingIncident>,
+    },
+    UpcomingClimb {
+        upcoming_climb: Option<UpcomingClimb>,
+    },
+    CurrentRoadName {
+        current_road_name: Option<String>,
+    },
+    Positioning {
+        positioning: PositioningStatus,
+    },
+}
+
+/// The difference between two trip states.
+#[derive(Debug, Clone, PartialEq, uniffi::Enum)]
+pub enum TripStateDelta {
+    /// The user is still navigating the same trip.
+    ///
+    /// Changing steps and reaching waypoints are reported by the
+    /// [`NavigationEvent::StepAdvanced`], [`NavigationEvent::StepRegressed`],
+    /// and [`NavigationEvent::WaypointReached`] events which accompany the delta.
+    Navigating {
+        /// The snapped location, if it changed.
+        snapped_user_location: Option<UserLocation>,
+        /// The trip progress, if it changed.
+        progress: Option<TripProgress>,
+        /// The other fields which changed, if any.
+        changes: Vec<TripStateChange>,
+    },
+    /// The state changed in a way which can't be expressed as a delta
+    /// (ex: the trip was completed), so the full state is included.
+    Replaced { state: TripState },
+}
+
+/// The result of a location update in the delta update mode.
+#[derive(Debug, Clone, PartialEq, uniffi::Record)]
+pub struct DeltaLocationUpdate {
+    /// The changes to the trip state.
+    pub delta: TripStateDelta,
+    /// The events which occurred during the update, in order.
+    pub events: Vec<NavigationEvent>,
+}
+
+impl TripStateDelta {
+    /// Computes the changes from the previous state to the next state.
+    pub(crate) fn new(previous: &TripState, next: &TripState) -> Self {
+        let (
+            TripState::Navigating {
+                snapped_user_location: previous_location,
+                progress: previous_progress,
+                deviation: previous_deviation,
+                visual_instruction: previous_visual_instruction,
+                then_step: previous_then_step,
+                spoken_instruction: previous_spoken_instruction,
+                prefetched_spoken_instructions: previous_prefetched_spoken_instructions,
+                upcoming_incident: previous_upcoming_incident,
+                upcoming_climb: previous_upcoming_climb,
+                current_road_name: previous_road_name,
+                positioning: previous_positioning,
+                ..
+            },
+            TripState::Navigating {
+                snapped_user_location,
+                progress,
+                deviation,
+                visual_instruction,
+                then_step,
+                spoken_instruction,
+                prefetched_spoken_instructions,
+                upcoming_incident,
+                upcoming_climb,
+                current_road_name,
+                positioning,
+                ..
+            },
+        ) = (previous, next)
+        else {
+            return TripStateDelta::Replaced {
+                state: next.clone(),
+            };
+        };
+
+        let mut changes = Vec::new();
+        if deviation != previous_deviation {
+            changes.push(TripStateChange::Deviation {
+                deviation: *deviation,
+            });
+        }
+        if visual_instruction != previous_visual_instruction {
+            changes.push(TripStateChange::VisualInstruction {
+                visual_instruction: visual_instruction.clone(),
+            });
+        }
+        if then_step != previous_then_step {
+            changes.push(TripStateChange::ThenStep {
+                then_step: then_step.clone(),
+            });
+        }
+        if spoken_instruction != previous_spoken_instruction {
+            changes.push(TripStateChange::SpokenInstruction {
+                spoken_instruction: spoken_instruction.clone(),
+            });
+        }
+        if prefetched_spoken_instructions != previous_prefetched_spoken_instructions {
+            changes.push(TripStateChange::PrefetchedSpokenInstructions {
+                prefetched_spoken_instructions: prefetched_spoken_instructions.clone(),
+            });
+        }
+        if upcoming_incident != previous_upcoming_incident {
+            changes.push(TripStateChange::UpcomingIncident {
+                upcoming_incident: upcoming_incident.clone(),
+            });
+        }
+        if upcoming_climb != previous_upcoming_climb {
+            changes.push(TripStateChange::UpcomingClimb {
+                upcoming_climb: upcoming_climb.clone(),
+            });
+        }
+        if current_road_name != previous_road_name {
+            changes.push(TripStateChange::CurrentRoadName {
+                current_road_name: current_road_name.clone(),
+            });
+        }
+        if positioning != previous_positioning {
+            changes.push(TripStateChange::Positioning {
+                positioning: *positioning,
+            });
+        }
+
+        TripStateDelta::Navigating {
+            snapped_user_location: (snapped_user_location != previous_location)
+                .then_some(*snapped_user_location),
+            progress: (progress != previous_progress).then(|| progress.clone()),
+            changes,
+        }
+    }
+}
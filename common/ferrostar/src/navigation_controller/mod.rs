@@ -1,4 +1,9 @@
+pub mod delta;
 pub mod models;
+pub mod presets;
+pub mod preview;
+pub mod shared_route;
+pub mod trip_queue;
 
 #[cfg(test)]
 pub(crate) mod test_helpers;
@@ -6,12 +11,45 @@ pub(crate) mod test_helpers;
 use crate::{
     algorithms::{
         advance_step, calculate_trip_progress, should_advance_to_next_step,
-        snap_user_location_to_line,
+        should_regress_to_previous_step, snap_user_location_to_line,
+        snap_user_location_to_line_with_course, snap_user_location_to_line_with_history,
+        snap_user_location_to_stacked_line, trim_traveled_geometry,
     },
-    models::{Route, UserLocation},
+    arrival_detection::ArrivalStatus,
+    camera::{calculate_camera_lookahead, CameraLookahead, CameraLookaheadPolicy},
+    climbs::{Climb, UpcomingClimb},
+    deviation_detection::{RouteDeviation, WrongDirectionStatus},
+    final_approach::final_approach_guidance,
+    junction_view::fetch_approached_images,
+    location_filtering::PositioningStatus,
+    logging::{enter_span, log_event},
+    models::{
+        GeographicCoordinate, Route, RouteStep, Speed, SpokenInstruction, UserLocation, Waypoint,
+        WaypointKind,
+    },
+    routing_adapters::{
+        error::RouteFetchError,
+        http::{CancellationToken, HttpExecutor},
+        RouteAdapter,
+    },
+    trip_summary::{TripStatistics, TripSummary},
+};
+use delta::{DeltaLocationUpdate, TripStateDelta};
+use geo::{LineString, Point};
+use models::{
+    BatchLocationUpdate, GeometryRetention, LastStepAdvance, ManeuverPreview,
+    NavigationControllerConfig, NavigationEvent, NavigationTracking, PowerMode, RouteChange,
+    SnappingMode, StepAdvanceStatus, TripProgress, TripState, UpcomingIncident,
 };
-use geo::{HaversineDistance, Point};
-use models::{NavigationControllerConfig, StepAdvanceStatus, TripState};
+use shared_route::{SharedRoute, TripStateUpdate};
+use std::ops::Range;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError, RwLock, RwLockReadGuard};
+use std::time::{Duration, SystemTime};
+
+/// How much farther than the closest segment (in meters) a stacked road segment may be
+/// and still be considered when snapping.
+const STACKED_ROAD_TOLERANCE: f64 = 15.0;
 
 /// Manages the navigation lifecycle of a route, reacting to inputs like user location updates
 /// and returning a new state.
@@ -22,15 +60,25 @@ use models::{NavigationControllerConfig, StepAdvanceStatus, TripState};
 /// like feeding in user location updates, route recalculation behavior, etc.
 #[derive(uniffi::Object)]
 pub struct NavigationController {
-    route: Route,
+    route: Arc<Route>,
     config: NavigationControllerConfig,
+    /// The climbs along the route, if climbs are reported.
+    climbs: Vec<Climb>,
 }
 
 #[uniffi::export]
 impl NavigationController {
     #[uniffi::constructor]
     pub fn new(route: Route, config: NavigationControllerConfig) -> Self {
-        Self { route, config }
+        let climbs = match (config.climb_alert_distance, &route.elevation_profile) {
+            (Some(_), Some(profile)) => profile.climbs(),
+            _ => vec![],
+        };
+        Self {
+            route: Arc::new(route),
+            config,
+            climbs,
+        }
     }
 
     /// Returns initial trip state as if the user had just started the route with no progress.
@@ -43,24 +91,34 @@ impl NavigationController {
         };
 
         let current_step_linestring = current_route_step.get_linestring();
-        let snapped_user_location = snap_user_location_to_line(location, &current_step_linestring);
-        let progress = calculate_trip_progress(
+        let snapped_user_location =
+            self.snap_to_step(location, &current_step_linestring, &remaining_steps, None);
+        let progress = self.calculate_trip_progress(
             &snapped_user_location.into(),
             current_route_step,
             &current_step_linestring,
             &remaining_steps,
         );
-        let deviation = self.config.route_deviation_tracking.check_route_deviation(
+        let (deviation, wrong_direction_start) = self.check_route_deviation(
             location,
-            &self.route,
             current_route_step,
+            &current_step_linestring,
+            None,
         );
         let visual_instruction = current_route_step
             .get_active_visual_instruction(progress.distance_to_next_maneuver)
             .cloned();
-        let spoken_instruction = current_route_step
-            .get_current_spoken_instruction(progress.distance_to_next_maneuver)
-            .cloned();
+        let then_step = self.then_step(&remaining_steps);
+        let spoken_instruction =
+            self.spoken_instruction(&remaining_steps, &progress, location.speed);
+        let prefetched_spoken_instructions =
+            self.prefetched_spoken_instructions(&remaining_steps, &progress);
+        let upcoming_incident = self.upcoming_incident(&progress);
+        let upcoming_climb = self.upcoming_climb(&progress);
+        let current_road_name = self
+            .config
+            .road_name_lookup
+            .current_road_name(current_route_step, snapped_user_location.coordinates);
 
         TripState::Navigating {
             snapped_user_location,
@@ -70,7 +128,17 @@ impl NavigationController {
             progress,
             deviation,
             visual_instruction,
+            then_step,
             spoken_instruction,
+            prefetched_spoken_instructions,
+            upcoming_incident,
+            upcoming_climb,
+            current_road_name,
+            tracking: NavigationTracking {
+                wrong_direction_start,
+                ..NavigationTracking::default()
+            },
+            positioning: PositioningStatus::Accurate,
         }
     }
 
@@ -86,6 +154,8 @@ impl NavigationController {
                 ref remaining_steps,
                 ref remaining_waypoints,
                 deviation,
+                tracking,
+                positioning,
                 ..
             } => {
                 // FIXME: This logic is mostly duplicated below
@@ -107,7 +177,10 @@ impl NavigationController {
                             let next_waypoint: Point = waypoint.coordinate.into();
                             // TODO: This is just a hard-coded threshold for the time being.
                             // More sophisticated behavior will take some time and use cases, so punting on this for now.
-                            current_location.haversine_distance(&next_waypoint) < 100.0
+                            self.config
+                                .distance_measurement
+                                .distance(&current_location, &next_waypoint)
+                                < 100.0
                         } else {
                             false
                         };
@@ -120,7 +193,7 @@ impl NavigationController {
                             remaining_waypoints.clone()
                         };
 
-                        let progress = calculate_trip_progress(
+                        let progress = self.calculate_trip_progress(
                             &(*snapped_user_location).into(),
                             &current_step,
                             &linestring,
@@ -130,9 +203,24 @@ impl NavigationController {
                         let visual_instruction = current_step
                             .get_active_visual_instruction(progress.distance_to_next_maneuver)
                             .cloned();
-                        let spoken_instruction = current_step
-                            .get_current_spoken_instruction(progress.distance_to_next_maneuver)
-                            .cloned();
+                        let then_step = self.then_step(&remaining_steps);
+                        let spoken_instruction = self.spoken_instruction(
+                            &remaining_steps,
+                            &progress,
+                            snapped_user_location.speed,
+                        );
+                        let prefetched_spoken_instructions =
+                            self.prefetched_spoken_instructions(&remaining_steps, &progress);
+                        let upcoming_incident = self.upcoming_incident(&progress);
+                        let upcoming_climb = self.upcoming_climb(&progress);
+                        let current_road_name = self
+                            .config
+                            .road_name_lookup
+                            .current_road_name(&current_step, snapped_user_location.coordinates);
+                        let last_step_advance = LastStepAdvance {
+                            timestamp: snapped_user_location.timestamp,
+                            distance_to_next_maneuver: progress.distance_to_next_maneuver,
+                        };
 
                         TripState::Navigating {
                             snapped_user_location: *snapped_user_location,
@@ -143,42 +231,113 @@ impl NavigationController {
                             // as it requires a non-snapped user location.
                             deviation: *deviation,
                             visual_instruction,
+                            then_step,
                             spoken_instruction,
+                            prefetched_spoken_instructions,
+                            upcoming_incident,
+                            upcoming_climb,
+                            current_road_name,
+                            tracking: NavigationTracking {
+                                arrival_dwell_start: None,
+                                wrong_direction_start: tracking.wrong_direction_start,
+                                last_step_advance: Some(last_step_advance),
+                            },
+                            positioning: *positioning,
                         }
                     }
-                    StepAdvanceStatus::EndOfRoute => TripState::Complete,
+                    StepAdvanceStatus::EndOfRoute => {
+                        log_event!(info, "Reached the end of the route");
+                        TripState::Complete
+                    }
                 }
             }
             // It's tempting to throw an error here, since the caller should know better, but
             // a mistake like this is technically harmless.
-            TripState::Complete => TripState::Complete,
+            TripState::FreeDrive { .. } | TripState::Complete => state.clone(),
         }
     }
 
     /// Updates the user's current location and updates the navigation state accordingly.
+    ///
+    /// If the state is [`TripState::FreeDrive`], navigation starts from the new location.
     pub fn update_user_location(&self, location: UserLocation, state: &TripState) -> TripState {
+        enter_span!("update_user_location");
         match state {
             TripState::Navigating {
+                snapped_user_location,
                 ref remaining_steps,
                 ref remaining_waypoints,
+                progress,
                 deviation,
                 visual_instruction,
+                then_step,
                 spoken_instruction,
-                ..
+                prefetched_spoken_instructions,
+                upcoming_incident,
+                upcoming_climb,
+                current_road_name,
+                tracking,
+                positioning,
             } => {
+                if self.config.stale_location_rejection.is_stale(
+                    &location,
+                    snapped_user_location.timestamp,
+                    self.config.now(),
+                ) {
+                    log_event!(debug, "Discarded a stale location update");
+                    return state.clone();
+                }
+
+                let positioning = self.config.accuracy_gating.check(&location, *positioning);
+                if positioning != PositioningStatus::Accurate {
+                    log_event!(
+                        debug,
+                        ?positioning,
+                        "Holding the last state, as the location is not accurate enough"
+                    );
+                    // Hold the last accurate state, but let the caller know why
+                    let mut state = state.clone();
+                    if let TripState::Navigating {
+                        positioning: ref mut state_positioning,
+                        ..
+                    } = state
+                    {
+                        *state_positioning = positioning;
+                    }
+                    return state;
+                }
+
+                let location = self
+                    .config
+                    .motion_derivation
+                    .apply(location, snapped_user_location);
+
                 let Some(current_step) = remaining_steps.first() else {
                     return TripState::Complete;
                 };
 
+                // Keep making progress along the route if the signal is lost in a tunnel
+                let location = self.config.tunnel_mode.dead_reckon(
+                    location,
+                    snapped_user_location,
+                    progress.distance_to_next_maneuver,
+                    current_step,
+                    self.config.distance_measurement,
+                );
+
                 //
                 // Core navigation logic
                 //
 
                 // Find the nearest point on the route line
                 let current_step_linestring = current_step.get_linestring();
-                let snapped_user_location =
-                    snap_user_location_to_line(location, &current_step_linestring);
-                let progress = calculate_trip_progress(
+                let snapped_user_location = self.snap_to_step(
+                    location,
+                    &current_step_linestring,
+                    remaining_steps,
+                    Some((snapped_user_location, progress)),
+                );
+                let progress = self.calculate_trip_progress(
                     &snapped_user_location.into(),
                     current_step,
                     &current_step_linestring,
@@ -191,63 +350,1460 @@ impl NavigationController {
                     progress,
                     deviation: *deviation,
                     visual_instruction: visual_instruction.clone(),
+                    then_step: then_step.clone(),
                     spoken_instruction: spoken_instruction.clone(),
+                    prefetched_spoken_instructions: prefetched_spoken_instructions.clone(),
+                    upcoming_incident: upcoming_incident.clone(),
+                    upcoming_climb: upcoming_climb.clone(),
+                    current_road_name: current_road_name.clone(),
+                    tracking: *tracking,
+                    positioning,
                 };
 
-                match if should_advance_to_next_step(
-                    &current_step_linestring,
-                    remaining_steps.get(1),
+                let (should_advance, arrival_dwell_start) = self.should_advance(
                     &location,
-                    self.config.step_advance,
-                ) {
+                    &current_step_linestring,
+                    remaining_steps,
+                    tracking.arrival_dwell_start,
+                );
+
+                let next_state = if should_advance {
                     // Advance to the next step
                     self.advance_to_next_step(&intermediate_state)
+                } else if let Some(regressed_state) =
+                    self.regress_to_previous_step(location, &intermediate_state)
+                {
+                    regressed_state
                 } else {
                     // Do not advance
                     intermediate_state
-                } {
-                    TripState::Navigating {
-                        snapped_user_location,
-                        remaining_steps,
-                        remaining_waypoints,
-                        progress,
-                        deviation: _,
-                        visual_instruction: _,
-                        spoken_instruction: _,
-                    } => {
-                        // Recalculate deviation. This happens later, as the current step may have changed.
-                        // The distance to the next maneuver will be updated by advance_to_next_step if needed.
-                        let current_step = remaining_steps
-                            .first()
-                            .expect("Invalid state: navigating with zero remaining steps.");
-                        let deviation = self.config.route_deviation_tracking.check_route_deviation(
-                            location,
-                            &self.route,
-                            current_step,
+                };
+                let next_state = self.recalculate_status(location, next_state, arrival_dwell_start);
+                #[cfg(feature = "tracing")]
+                if let TripState::Navigating {
+                    deviation: next_deviation,
+                    ..
+                } = &next_state
+                {
+                    if next_deviation != deviation {
+                        tracing::info!(
+                            previous = ?deviation,
+                            next = ?next_deviation,
+                            "The route deviation changed"
                         );
-
-                        let visual_instruction = current_step
-                            .get_active_visual_instruction(progress.distance_to_next_maneuver)
-                            .cloned();
-                        let spoken_instruction = current_step
-                            .get_current_spoken_instruction(progress.distance_to_next_maneuver)
-                            .cloned();
-
-                        TripState::Navigating {
-                            snapped_user_location,
-                            remaining_steps,
-                            remaining_waypoints,
-                            progress,
-                            deviation,
-                            visual_instruction,
-                            spoken_instruction,
-                        }
                     }
-                    TripState::Complete => TripState::Complete,
                 }
+                self.config.power_mode.batch_progress(state, next_state)
             }
+            // A route has arrived, so start navigating it from the user's location
+            TripState::FreeDrive { .. } => self.get_initial_state(location),
             // Terminal state
             TripState::Complete => TripState::Complete,
         }
     }
+
+    /// Processes a batch of location updates, such as those delivered all at once
+    /// by the OS after a period of inactivity.
+    ///
+    /// Locations are processed in timestamp order.
+    /// Any which are not newer than the last location processed are discarded.
+    /// In addition to the final state, this returns the events that happened along the way,
+    /// which would otherwise be lost in the intermediate states.
+    pub fn update_user_locations(
+        &self,
+        locations: Vec<UserLocation>,
+        state: &TripState,
+    ) -> BatchLocationUpdate {
+        let mut locations = locations;
+        locations.sort_by_key(|location| location.timestamp);
+
+        let mut last_timestamp = match state {
+            TripState::Navigating {
+                snapped_user_location,
+                ..
+            }
+            | TripState::FreeDrive {
+                user_location: snapped_user_location,
+                ..
+            } => Some(snapped_user_location.timestamp),
+            TripState::Complete => None,
+        };
+        let mut state = state.clone();
+        let mut events = Vec::new();
+        for location in locations {
+            if last_timestamp.is_some_and(|timestamp| location.timestamp <= timestamp) {
+                continue;
+            }
+            last_timestamp = Some(location.timestamp);
+
+            let next_state = self.update_user_location(location, &state);
+            events.extend(self.events_between(&state, &next_state));
+            state = next_state;
+        }
+
+        BatchLocationUpdate { state, events }
+    }
+}
+
+/// Compares a new route to the remainder of the route it replaces (ex: after a reroute).
+///
+/// `previous_state` is the last state of the old route,
+/// and `new_state` is the initial state of the new route (from the same location).
+/// Returns `None` unless both trips are in progress.
+#[uniffi::export]
+pub fn route_change(previous_state: &TripState, new_state: &TripState) -> Option<RouteChange> {
+    let (
+        TripState::Navigating {
+            progress: previous_progress,
+            ..
+        },
+        TripState::Navigating {
+            progress: new_progress,
+            ..
+        },
+    ) = (previous_state, new_state)
+    else {
+        return None;
+    };
+
+    Some(RouteChange {
+        distance_delta: new_progress.distance_remaining - previous_progress.distance_remaining,
+        duration_delta: new_progress.duration_remaining - previous_progress.duration_remaining,
+    })
+}
+
+/// Inserts a stop into a list of remaining waypoints.
+///
+/// `position` is the index of the new stop (ex: 0 to stop before the next waypoint);
+/// positions past the end make the new stop the destination.
+fn insert_waypoint(
+    remaining_waypoints: &[Waypoint],
+    coordinate: GeographicCoordinate,
+    position: u32,
+) -> Vec<Waypoint> {
+    let index = usize::try_from(position)
+        .unwrap_or(usize::MAX)
+        .min(remaining_waypoints.len());
+    let mut waypoints = remaining_waypoints.to_vec();
+    waypoints.insert(
+        index,
+        Waypoint {
+            coordinate,
+            kind: WaypointKind::Break,
+        },
+    );
+    waypoints
+}
+
+/// Replaces the destination (the last of the remaining waypoints).
+fn replace_destination(
+    remaining_waypoints: &[Waypoint],
+    destination: GeographicCoordinate,
+) -> Vec<Waypoint> {
+    let mut waypoints = remaining_waypoints.to_vec();
+    waypoints.pop();
+    waypoints.push(Waypoint {
+        coordinate: destination,
+        kind: WaypointKind::Break,
+    });
+    waypoints
+}
+
+/// Lists the maneuvers along the rest of the route, in order (ex: for a scrollable list of directions).
+///
+/// The maneuver at the start of the current step has already been passed, so it is not included.
+/// The last maneuver is the arrival at the destination.
+/// Distances and durations are consistent with the trip progress,
+/// and the estimated arrival times are relative to the timestamp of the user's last location.
+#[uniffi::export]
+pub fn remaining_maneuvers(state: &TripState) -> Vec<ManeuverPreview> {
+    let TripState::Navigating {
+        snapped_user_location,
+        remaining_steps,
+        progress,
+        ..
+    } = state
+    else {
+        return vec![];
+    };
+
+    // Work backward from the end of the trip, so that adjustments to the duration remaining
+    // (ex: for hills) are reflected in the estimates.
+    let (mut distance_after, mut duration_after) = (0.0, 0.0);
+    let mut maneuvers: Vec<_> = remaining_steps
+        .windows(2)
+        .rev()
+        .map(|pair| {
+            let (previous_step, step) = (&pair[0], &pair[1]);
+            distance_after += step.distance;
+            duration_after += step.duration;
+            let duration_to_maneuver = (progress.duration_remaining - duration_after).max(0.0);
+
+            ManeuverPreview {
+                step: step.clone(),
+                visual_instruction: previous_step.visual_instructions.first().cloned(),
+                distance_to_maneuver: (progress.distance_remaining - distance_after).max(0.0),
+                duration_to_maneuver,
+                // Durations come from the routing backend, so they may be absurdly large
+                estimated_arrival: Duration::try_from_secs_f64(duration_to_maneuver)
+                    .ok()
+                    .and_then(|duration| snapped_user_location.timestamp.checked_add(duration))
+                    .unwrap_or(snapped_user_location.timestamp),
+            }
+        })
+        .collect();
+    maneuvers.reverse();
+    maneuvers
+}
+
+/// Determines the events which happened in the transition from one state to the next.
+fn transition_events(previous: &TripState, next: &TripState) -> Vec<NavigationEvent> {
+    let TripState::Navigating {
+        remaining_steps: previous_steps,
+        remaining_waypoints: previous_waypoints,
+        progress: previous_progress,
+        ..
+    } = previous
+    else {
+        return vec![];
+    };
+
+    let waypoint_reached = |waypoint: &Waypoint| NavigationEvent::WaypointReached {
+        waypoint: *waypoint,
+    };
+    let mut events = Vec::new();
+    match next {
+        TripState::Navigating {
+            remaining_steps,
+            remaining_waypoints,
+            progress,
+            ..
+        } => {
+            if let Some(step) = remaining_steps.first() {
+                if remaining_steps.len() < previous_steps.len() {
+                    events.push(NavigationEvent::StepAdvanced { step: step.clone() });
+                } else if remaining_steps.len() > previous_steps.len() {
+                    events.push(NavigationEvent::StepRegressed { step: step.clone() });
+                }
+            }
+            let previous_distance = previous_progress.distance_to_next_maneuver;
+            let distance = progress.distance_to_next_maneuver;
+            let country_changes = passed_points(
+                previous_steps,
+                previous_distance,
+                remaining_steps,
+                distance,
+                |step| step.country_changes.as_slice(),
+                |change| change.distance_before_maneuver,
+            );
+            events.extend(country_changes.into_iter().map(|change| {
+                NavigationEvent::CountryChanged {
+                    iso_3166: change.iso_3166.clone(),
+                }
+            }));
+            let junction_views = passed_points(
+                previous_steps,
+                previous_distance,
+                remaining_steps,
+                distance,
+                |step| step.junction_views.as_slice(),
+                |junction_view| junction_view.trigger_distance_before_maneuver,
+            );
+            events.extend(junction_views.into_iter().map(|junction_view| {
+                NavigationEvent::JunctionViewApproaching {
+                    junction_view: junction_view.clone(),
+                }
+            }));
+            let reached = previous_waypoints
+                .len()
+                .saturating_sub(remaining_waypoints.len());
+            events.extend(previous_waypoints[..reached].iter().map(waypoint_reached));
+        }
+        TripState::Complete => {
+            events.extend(previous_waypoints.iter().map(waypoint_reached));
+            events.push(NavigationEvent::TripCompleted);
+        }
+        TripState::FreeDrive { .. } => {}
+    }
+    events
+}
+
+/// Whether the user began the final step of the route (which ends at the destination)
+/// in the transition from one state to the next.
+fn began_final_step(previous: &TripState, next: &TripState) -> bool {
+    // Any steps after the final one (ex: an arrival step) have no length
+    let is_final_step = |steps: &[RouteStep]| {
+        steps
+            .get(1..)
+            .unwrap_or_default()
+            .iter()
+            .all(|step| step.distance == 0.0)
+    };
+    match (previous, next) {
+        (
+            TripState::Navigating {
+                remaining_steps: previous_steps,
+                ..
+            },
+            TripState::Navigating {
+                remaining_steps, ..
+            },
+        ) => !is_final_step(previous_steps) && is_final_step(remaining_steps),
+        _ => false,
+    }
+}
+
+/// The points along the steps of a route which were passed in the transition
+/// from one position along the route to the next, in order.
+///
+/// Each position is given by the remaining steps and the distance to the end of the first one,
+/// and points are located by their distance before the maneuver at the end of their step.
+fn passed_points<'a, T>(
+    previous_steps: &'a [RouteStep],
+    previous_distance_to_next_maneuver: f64,
+    remaining_steps: &'a [RouteStep],
+    distance_to_next_maneuver: f64,
+    points: impl Fn(&'a RouteStep) -> &'a [T],
+    distance_before_maneuver: impl Fn(&T) -> f64,
+) -> Vec<&'a T> {
+    let distance_before_maneuver = &distance_before_maneuver;
+    let points_within = |step: Option<&'a RouteStep>, distances: Range<f64>| {
+        step.map(&points)
+            .unwrap_or_default()
+            .iter()
+            .filter(move |point| distances.contains(&distance_before_maneuver(*point)))
+    };
+
+    if remaining_steps.len() > previous_steps.len() {
+        // Nothing is passed when moving back to a previous step
+        return vec![];
+    }
+    let advanced = previous_steps.len() - remaining_steps.len();
+    let mut passed = Vec::new();
+    let current_step_start = if advanced == 0 {
+        previous_distance_to_next_maneuver
+    } else {
+        // The rest of the previous step, and all of any steps which were skipped over
+        passed.extend(points_within(
+            previous_steps.first(),
+            f64::NEG_INFINITY..previous_distance_to_next_maneuver,
+        ));
+        passed.extend(
+            previous_steps
+                .get(1..advanced)
+                .unwrap_or_default()
+                .iter()
+                .flat_map(&points),
+        );
+        f64::INFINITY
+    };
+    passed.extend(points_within(
+        remaining_steps.first(),
+        distance_to_next_maneuver..current_step_start,
+    ));
+    passed
+}
+
+impl NavigationController {
+    /// Determines the events which happened in the transition from one state to the next,
+    /// fetching the images of any junction views being approached.
+    pub(crate) fn events_between(
+        &self,
+        previous: &TripState,
+        next: &TripState,
+    ) -> Vec<NavigationEvent> {
+        let mut events = transition_events(previous, next);
+        if began_final_step(previous, next) {
+            if let Some(guidance) =
+                final_approach_guidance(&self.route, self.config.distance_measurement)
+            {
+                events.push(NavigationEvent::FinalApproach { guidance });
+            }
+        }
+        if let (None, Some(distance)) = (
+            self.nearby_arrival_distance(previous),
+            self.nearby_arrival_distance(next),
+        ) {
+            events.push(NavigationEvent::ArrivedNearby { distance });
+        }
+        if let Some(resolver) = &self.config.junction_view_resolver {
+            fetch_approached_images(resolver.as_ref(), &events);
+        }
+        events
+    }
+
+    /// The distance from the user to the destination, if they have arrived near it
+    /// according to the [`NearbyArrivalDetection`](crate::arrival_detection::NearbyArrivalDetection).
+    fn nearby_arrival_distance(&self, state: &TripState) -> Option<f64> {
+        let TripState::Navigating {
+            snapped_user_location,
+            remaining_steps,
+            ..
+        } = state
+        else {
+            return None;
+        };
+        let destination = *remaining_steps.last()?.geometry.last()?;
+        self.config.nearby_arrival_detection.check_nearby_arrival(
+            snapped_user_location,
+            destination,
+            self.config.distance_measurement,
+        )
+    }
+
+    /// Recalculates the deviation, instructions, and alerts for a state following a location update.
+    ///
+    /// This happens after any step advance, as the current step may have changed.
+    /// The distance to the next maneuver will be updated by `advance_to_next_step` if needed.
+    fn recalculate_status(
+        &self,
+        location: UserLocation,
+        state: TripState,
+        arrival_dwell_start: Option<SystemTime>,
+    ) -> TripState {
+        match state {
+            TripState::Navigating {
+                snapped_user_location,
+                mut remaining_steps,
+                remaining_waypoints,
+                progress,
+                deviation: _,
+                visual_instruction: _,
+                then_step: _,
+                spoken_instruction: _,
+                prefetched_spoken_instructions: _,
+                upcoming_incident: _,
+                upcoming_climb: _,
+                current_road_name: _,
+                tracking,
+                positioning,
+            } => {
+                if let (GeometryRetention::TrimTraveled { rewind_distance }, Some(current_step)) =
+                    (self.config.geometry_retention, remaining_steps.first_mut())
+                {
+                    trim_traveled_geometry(
+                        current_step,
+                        &snapped_user_location.into(),
+                        rewind_distance,
+                        self.config.distance_measurement,
+                    );
+                }
+
+                let current_step = remaining_steps
+                    .first()
+                    .expect("Invalid state: navigating with zero remaining steps.");
+                let (deviation, wrong_direction_start) = self.check_route_deviation(
+                    location,
+                    current_step,
+                    &current_step.get_linestring(),
+                    tracking.wrong_direction_start,
+                );
+                let (deviation, wrong_direction_start) = if deviation != RouteDeviation::NoDeviation
+                    && self.config.step_advance_cooldown.is_active(
+                        tracking.last_step_advance.as_ref(),
+                        &location,
+                        progress.distance_to_next_maneuver,
+                    ) {
+                    log_event!(
+                        debug,
+                        ?deviation,
+                        "Ignored a route deviation during the step advance cooldown"
+                    );
+                    (RouteDeviation::NoDeviation, None)
+                } else {
+                    (deviation, wrong_direction_start)
+                };
+
+                let visual_instruction = current_step
+                    .get_active_visual_instruction(progress.distance_to_next_maneuver)
+                    .cloned();
+                let then_step = self.then_step(&remaining_steps);
+                let spoken_instruction =
+                    self.spoken_instruction(&remaining_steps, &progress, location.speed);
+                let prefetched_spoken_instructions =
+                    self.prefetched_spoken_instructions(&remaining_steps, &progress);
+                let upcoming_incident = self.upcoming_incident(&progress);
+                let upcoming_climb = self.upcoming_climb(&progress);
+                let current_road_name = self
+                    .config
+                    .road_name_lookup
+                    .current_road_name(current_step, snapped_user_location.coordinates);
+
+                TripState::Navigating {
+                    snapped_user_location,
+                    remaining_steps,
+                    remaining_waypoints,
+                    progress,
+                    deviation,
+                    visual_instruction,
+                    then_step,
+                    spoken_instruction,
+                    prefetched_spoken_instructions,
+                    upcoming_incident,
+                    upcoming_climb,
+                    current_road_name,
+                    tracking: NavigationTracking {
+                        arrival_dwell_start,
+                        wrong_direction_start,
+                        last_step_advance: tracking.last_step_advance,
+                    },
+                    positioning,
+                }
+            }
+            TripState::FreeDrive { .. } | TripState::Complete => state,
+        }
+    }
+
+    /// Snaps the user location to the current step, according to the configured [`SnappingMode`].
+    ///
+    /// `previous` is the last snapped location and the progress at that point (on the same step),
+    /// if there was one.
+    fn snap_to_step(
+        &self,
+        location: UserLocation,
+        current_step_linestring: &LineString,
+        remaining_steps: &[RouteStep],
+        previous: Option<(&UserLocation, &TripProgress)>,
+    ) -> UserLocation {
+        match self.config.snapping {
+            SnappingMode::None => return location,
+            SnappingMode::MapMatched => {
+                let Some((previous_location, previous_progress)) = previous else {
+                    return snap_user_location_to_line(location, current_step_linestring);
+                };
+                let measurement = self.config.distance_measurement;
+                let previous_distance_along_step = measurement.length(current_step_linestring)
+                    - previous_progress.distance_to_next_maneuver;
+                return snap_user_location_to_line_with_history(
+                    location,
+                    current_step_linestring,
+                    previous_location,
+                    previous_distance_along_step,
+                    measurement,
+                );
+            }
+            SnappingMode::NearestPoint | SnappingMode::NearestPointWithHeading => {}
+        }
+        if matches!(self.config.power_mode, PowerMode::Background { .. }) {
+            // Coarser snapping saves battery while nobody is looking at the map
+            return snap_user_location_to_line(location, current_step_linestring);
+        }
+
+        let Some(elevation_profile) = self
+            .route
+            .elevation_profile
+            .as_ref()
+            .filter(|_| location.altitude.is_some())
+        else {
+            return if self.config.snapping == SnappingMode::NearestPointWithHeading {
+                snap_user_location_to_line_with_course(
+                    location,
+                    current_step_linestring,
+                    self.config.distance_measurement,
+                )
+            } else {
+                snap_user_location_to_line(location, current_step_linestring)
+            };
+        };
+
+        let step_distance_along_route = self.route.distance
+            - remaining_steps
+                .iter()
+                .map(|step| step.distance)
+                .sum::<f64>();
+        snap_user_location_to_stacked_line(
+            location,
+            current_step_linestring,
+            STACKED_ROAD_TOLERANCE,
+            self.config.snapping == SnappingMode::NearestPointWithHeading,
+            |distance_along_step| {
+                elevation_profile.elevation_at(step_distance_along_route + distance_along_step)
+            },
+            self.config.distance_measurement,
+        )
+    }
+
+    /// Finds the step after the next maneuver if it follows closely enough
+    /// to be announced together with it (ex: "turn left, then turn right").
+    fn then_step(&self, remaining_steps: &[RouteStep]) -> Option<RouteStep> {
+        let max_distance = self.config.then_step_max_distance?;
+        match remaining_steps {
+            [_, next_step, then_step, ..] if next_step.distance <= max_distance => {
+                Some(then_step.clone())
+            }
+            _ => None,
+        }
+    }
+
+    /// Gets the current spoken instruction (unless maneuvers are muted).
+    fn spoken_instruction(
+        &self,
+        remaining_steps: &[RouteStep],
+        progress: &TripProgress,
+        speed: Option<Speed>,
+    ) -> Option<SpokenInstruction> {
+        if !self.config.guidance_verbosity.includes_maneuvers() {
+            return None;
+        }
+
+        self.config
+            .announcement_timing
+            .spoken_instruction_for_remaining_steps(
+                remaining_steps,
+                progress.distance_to_next_maneuver,
+                speed,
+            )
+    }
+
+    /// Gets the spoken instructions to prepare ahead of a tunnel (unless maneuvers are muted).
+    fn prefetched_spoken_instructions(
+        &self,
+        remaining_steps: &[RouteStep],
+        progress: &TripProgress,
+    ) -> Vec<SpokenInstruction> {
+        if !self.config.guidance_verbosity.includes_maneuvers() {
+            return vec![];
+        }
+
+        self.config.tunnel_mode.prefetch_spoken_instructions(
+            remaining_steps,
+            progress.distance_to_next_maneuver,
+            self.config.distance_measurement,
+        )
+    }
+
+    /// Finds the next incident along the route which is within the alert distance (if any).
+    fn upcoming_incident(&self, progress: &TripProgress) -> Option<UpcomingIncident> {
+        if !self.config.guidance_verbosity.includes_alerts() {
+            return None;
+        }
+        let alert_distance = self.config.incident_alert_distance?;
+        let distance_along_route = self.route.distance - progress.distance_remaining;
+
+        self.route
+            .incidents
+            .iter()
+            .find(|incident| incident.end_distance_along_route >= distance_along_route)
+            .map(|incident| UpcomingIncident {
+                incident: incident.clone(),
+                distance_to_incident: (incident.start_distance_along_route - distance_along_route)
+                    .max(0.0),
+            })
+            .filter(|upcoming| upcoming.distance_to_incident <= alert_distance)
+    }
+
+    /// Finds the next climb along the route which is within the alert distance (if any).
+    fn upcoming_climb(&self, progress: &TripProgress) -> Option<UpcomingClimb> {
+        if !self.config.guidance_verbosity.includes_alerts() {
+            return None;
+        }
+        let alert_distance = self.config.climb_alert_distance?;
+        let profile = self.route.elevation_profile.as_ref()?;
+        let distance_along_route = self.route.distance - progress.distance_remaining;
+
+        profile.upcoming_climb(&self.climbs, distance_along_route, alert_distance)
+    }
+
+    /// Checks whether the user has deviated from the route.
+    ///
+    /// Travel in the wrong direction is only checked when the user is otherwise on route.
+    /// Returns the deviation along with the updated wrong direction start location.
+    fn check_route_deviation(
+        &self,
+        location: UserLocation,
+        current_step: &RouteStep,
+        current_step_linestring: &LineString,
+        wrong_direction_start: Option<GeographicCoordinate>,
+    ) -> (RouteDeviation, Option<GeographicCoordinate>) {
+        let deviation = self.config.route_deviation_tracking.check_route_deviation(
+            location,
+            &self.route,
+            current_step,
+            self.config.distance_measurement,
+        );
+        if deviation != RouteDeviation::NoDeviation {
+            return (deviation, None);
+        }
+
+        match self.config.wrong_direction_detection.check_wrong_direction(
+            &location,
+            current_step_linestring,
+            wrong_direction_start,
+            self.config.distance_measurement,
+        ) {
+            WrongDirectionStatus::CorrectDirection => (deviation, None),
+            WrongDirectionStatus::Reversing { since } => (deviation, Some(since)),
+            WrongDirectionStatus::WrongDirection { since, distance } => (
+                RouteDeviation::WrongDirection {
+                    distance_in_wrong_direction: distance,
+                },
+                Some(since),
+            ),
+        }
+    }
+
+    /// Determines whether the controller should advance to the next step.
+    ///
+    /// Advancing past the final step completes the trip,
+    /// so this is subject to the arrival detection criteria.
+    /// Returns the decision along with the updated arrival dwell start time.
+    fn should_advance(
+        &self,
+        location: &UserLocation,
+        current_step_linestring: &LineString,
+        remaining_steps: &[RouteStep],
+        arrival_dwell_start: Option<SystemTime>,
+    ) -> (bool, Option<SystemTime>) {
+        let should_advance = should_advance_to_next_step(
+            current_step_linestring,
+            remaining_steps.get(1),
+            location,
+            self.config.step_advance,
+            self.config.distance_measurement,
+        );
+
+        if should_advance {
+            log_event!(
+                debug,
+                step_advance = ?self.config.step_advance,
+                "The step advance condition was met"
+            );
+        }
+
+        let [final_step] = remaining_steps else {
+            return (should_advance, None);
+        };
+
+        let destination = final_step
+            .geometry
+            .last()
+            .copied()
+            .unwrap_or(location.coordinates);
+        match self.config.arrival_detection.check_arrival(
+            location,
+            destination,
+            arrival_dwell_start,
+            should_advance,
+            self.config.distance_measurement,
+        ) {
+            ArrivalStatus::Arrived => {
+                log_event!(
+                    info,
+                    arrival_detection = ?self.config.arrival_detection,
+                    "Arrived at the destination"
+                );
+                (true, None)
+            }
+            ArrivalStatus::Dwelling { since } => {
+                log_event!(trace, "Dwelling near the destination");
+                (false, Some(since))
+            }
+            ArrivalStatus::NotArrived => (false, None),
+        }
+    }
+
+    /// Moves back to the previous step of the route if the user has fallen back behind its end
+    /// and the [`StepAdvanceCooldown`](models::StepAdvanceCooldown) allows it.
+    ///
+    /// Returns `None` if navigation should stay on the current step.
+    /// Waypoints which were reached stay reached.
+    fn regress_to_previous_step(
+        &self,
+        location: UserLocation,
+        state: &TripState,
+    ) -> Option<TripState> {
+        let TripState::Navigating {
+            remaining_steps,
+            remaining_waypoints,
+            progress,
+            deviation,
+            visual_instruction,
+            then_step,
+            spoken_instruction,
+            prefetched_spoken_instructions,
+            upcoming_incident,
+            upcoming_climb,
+            current_road_name,
+            tracking,
+            positioning,
+            ..
+        } = state
+        else {
+            return None;
+        };
+        if !self.config.step_advance_cooldown.allows_regression(
+            tracking.last_step_advance.as_ref(),
+            &location,
+            progress.distance_to_next_maneuver,
+        ) {
+            return None;
+        }
+
+        // The remaining steps are always the end of the route (with a trimmed current step)
+        let previous_step_index = self
+            .route
+            .steps
+            .len()
+            .checked_sub(remaining_steps.len())?
+            .checked_sub(1)?;
+        let previous_step = &self.route.steps[previous_step_index];
+        let previous_step_linestring = previous_step.get_linestring();
+        if !should_regress_to_previous_step(
+            &previous_step_linestring,
+            remaining_steps.first()?,
+            &location,
+            self.config.step_advance,
+            self.config.distance_measurement,
+        ) {
+            return None;
+        }
+
+        log_event!(
+            debug,
+            "The user fell back behind the end of the previous step"
+        );
+        let remaining_steps = self.route.steps[previous_step_index..].to_vec();
+        let snapped_user_location =
+            self.snap_to_step(location, &previous_step_linestring, &remaining_steps, None);
+        let progress = self.calculate_trip_progress(
+            &snapped_user_location.into(),
+            previous_step,
+            &previous_step_linestring,
+            &remaining_steps,
+        );
+        Some(TripState::Navigating {
+            snapped_user_location,
+            remaining_steps,
+            remaining_waypoints: remaining_waypoints.clone(),
+            progress,
+            deviation: *deviation,
+            visual_instruction: visual_instruction.clone(),
+            then_step: then_step.clone(),
+            spoken_instruction: spoken_instruction.clone(),
+            prefetched_spoken_instructions: prefetched_spoken_instructions.clone(),
+            upcoming_incident: upcoming_incident.clone(),
+            upcoming_climb: upcoming_climb.clone(),
+            current_road_name: current_road_name.clone(),
+            tracking: NavigationTracking {
+                last_step_advance: None,
+                ..*tracking
+            },
+            positioning: *positioning,
+        })
+    }
+
+    /// Computes the trip progress, applying any configured adjustments.
+    fn calculate_trip_progress(
+        &self,
+        snapped_location: &Point,
+        current_step: &RouteStep,
+        current_step_linestring: &LineString,
+        remaining_steps: &[RouteStep],
+    ) -> TripProgress {
+        let progress = calculate_trip_progress(
+            snapped_location,
+            current_step,
+            current_step_linestring,
+            remaining_steps,
+            self.config.distance_measurement,
+        );
+
+        let distance_along_route = self.route.distance - progress.distance_remaining;
+        TripProgress {
+            duration_remaining: self.config.grade_eta_adjustment.adjust_duration(
+                self.route.elevation_profile.as_ref(),
+                distance_along_route,
+                progress.duration_remaining,
+            ),
+            consumption: self
+                .config
+                .vehicle_energy_profile
+                .map(|vehicle| vehicle.estimate(&self.route, distance_along_route)),
+            ..progress
+        }
+    }
+}
+
+/// A [`NavigationController`] which owns the trip state, so it can be shared between threads.
+///
+/// The stateless controller leaves it to the caller to pass in the latest state,
+/// which is error prone when location updates and user actions arrive on different threads
+/// (ex: a location callback racing a manual step advance could drop an update).
+/// This wrapper serializes every transition behind an internal lock instead,
+/// and returns the new state after each one.
+#[derive(uniffi::Object)]
+pub struct SynchronizedNavigationController {
+    // NOTE: The state lock is always acquired first
+    controller: RwLock<NavigationController>,
+    state: Mutex<TripState>,
+    /// Whether navigation is paused. This is only changed while holding the state lock.
+    paused: AtomicBool,
+    /// Statistics for the [`TripSummary`]. This is only locked while holding the state lock.
+    statistics: Mutex<TripStatistics>,
+}
+
+#[uniffi::export]
+impl SynchronizedNavigationController {
+    #[uniffi::constructor]
+    pub fn new(
+        route: Route,
+        config: NavigationControllerConfig,
+        initial_location: UserLocation,
+    ) -> Self {
+        let measurement = config.distance_measurement;
+        let mut statistics = TripStatistics::new(config.breadcrumb_recording);
+        let controller = NavigationController::new(route, config);
+        let state = controller.get_initial_state(initial_location);
+        statistics.record_location(&initial_location, &state, measurement);
+        Self {
+            controller: RwLock::new(controller),
+            state: Mutex::new(state),
+            paused: AtomicBool::new(false),
+            statistics: Mutex::new(statistics),
+        }
+    }
+
+    /// Returns the current trip state.
+    pub fn get_state(&self) -> TripState {
+        self.lock_state().clone()
+    }
+
+    /// Returns the maneuvers along the rest of the route. See [`remaining_maneuvers`].
+    pub fn get_remaining_maneuvers(&self) -> Vec<ManeuverPreview> {
+        remaining_maneuvers(&self.lock_state())
+    }
+
+    /// Advances navigation to the next step. See [`NavigationController::advance_to_next_step`].
+    ///
+    /// This does nothing while navigation is paused.
+    pub fn advance_to_next_step(&self) -> TripState {
+        let mut state = self.lock_state();
+        if self.is_paused() {
+            return state.clone();
+        }
+        *state = self.read_controller().advance_to_next_step(&state);
+        state.clone()
+    }
+
+    /// Updates the user's current location. See [`NavigationController::update_user_location`].
+    pub fn update_user_location(&self, location: UserLocation) -> TripState {
+        let mut state = self.lock_state();
+        if self.is_paused() {
+            return state.clone();
+        }
+        let controller = self.read_controller();
+        *state = controller.update_user_location(location, &state);
+        self.record_location(&location, &state, &controller);
+        state.clone()
+    }
+
+    /// Updates the user's current location, returning a compact [`TripStateUpdate`]
+    /// which refers to the route from [`Self::get_shared_route`] instead of copying it.
+    ///
+    /// See [`NavigationController::update_user_location`].
+    pub fn update_user_location_compact(&self, location: UserLocation) -> TripStateUpdate {
+        let mut state = self.lock_state();
+        let controller = self.read_controller();
+        if self.is_paused() {
+            return TripStateUpdate::new(&state, &state, &controller.route);
+        }
+        let next = controller.update_user_location(location, &state);
+        let previous = std::mem::replace(&mut *state, next);
+        self.record_location(&location, &state, &controller);
+        TripStateUpdate::new(&state, &previous, &controller.route)
+    }
+
+    /// Updates the user's current location, returning only what changed in the trip state.
+    ///
+    /// See [`delta`] and [`NavigationController::update_user_location`].
+    pub fn update_user_location_delta(&self, location: UserLocation) -> DeltaLocationUpdate {
+        let mut state = self.lock_state();
+        let controller = self.read_controller();
+        if self.is_paused() {
+            return DeltaLocationUpdate {
+                delta: TripStateDelta::new(&state, &state),
+                events: vec![],
+            };
+        }
+        let next = controller.update_user_location(location, &state);
+        let previous = std::mem::replace(&mut *state, next);
+        let mut events = controller.events_between(&previous, &state);
+        events.extend(self.record_location(&location, &state, &controller));
+        DeltaLocationUpdate {
+            delta: TripStateDelta::new(&previous, &state),
+            events,
+        }
+    }
+
+    /// Returns a handle to the route being navigated, which is shared rather than copied.
+    ///
+    /// The handle refers to the route at the time it was returned,
+    /// so it should be fetched again after [`Self::replace_route`].
+    pub fn get_shared_route(&self) -> Arc<SharedRoute> {
+        Arc::new(SharedRoute::new(Arc::clone(&self.read_controller().route)))
+    }
+
+    /// Processes a batch of location updates. See [`NavigationController::update_user_locations`].
+    pub fn update_user_locations(&self, locations: Vec<UserLocation>) -> BatchLocationUpdate {
+        let mut state = self.lock_state();
+        if self.is_paused() {
+            return BatchLocationUpdate {
+                state: state.clone(),
+                events: vec![],
+            };
+        }
+        let controller = self.read_controller();
+        let mut locations = locations;
+        locations.sort_by_key(|location| location.timestamp);
+        let mut events = Vec::new();
+        // Locations are processed one at a time so that each one is included in the trip summary
+        for location in locations {
+            let update = controller.update_user_locations(vec![location], &state);
+            *state = update.state;
+            events.extend(update.events);
+            events.extend(self.record_location(&location, &state, &controller));
+        }
+        BatchLocationUpdate {
+            state: state.clone(),
+            events,
+        }
+    }
+
+    /// Summarizes the trip so far (ex: distance traveled and reroute count).
+    ///
+    /// This is also reported in a [`NavigationEvent::TripSummaryAvailable`] event when the trip is completed
+    /// by a location update.
+    pub fn get_trip_summary(&self) -> TripSummary {
+        let _state = self.lock_state();
+        self.lock_statistics().summary()
+    }
+
+    /// Returns a simplified trail of the user's locations so far (ex: for a "your track" display).
+    ///
+    /// This is empty unless enabled by [`NavigationControllerConfig::breadcrumb_recording`].
+    pub fn get_breadcrumbs(&self) -> Vec<GeographicCoordinate> {
+        let _state = self.lock_state();
+        self.lock_statistics().breadcrumbs()
+    }
+
+    /// Computes the camera zoom and lookahead distance for the current state.
+    /// See [`crate::camera::calculate_camera_lookahead`].
+    ///
+    /// Returns `None` when the trip is complete or the app is in the background
+    /// (see [`PowerMode::Background`]).
+    pub fn get_camera_lookahead(&self, policy: &CameraLookaheadPolicy) -> Option<CameraLookahead> {
+        let state = self.lock_state();
+        if matches!(
+            self.read_controller().config.power_mode,
+            PowerMode::Background { .. }
+        ) {
+            return None;
+        }
+        let TripState::Navigating {
+            snapped_user_location,
+            progress,
+            ..
+        } = &*state
+        else {
+            return None;
+        };
+
+        calculate_camera_lookahead(
+            policy,
+            progress.distance_to_next_maneuver,
+            snapped_user_location.speed.map(|speed| speed.value),
+        )
+    }
+
+    /// Changes the power mode (ex: when the app moves to the background or foreground).
+    ///
+    /// This takes effect from the next update.
+    pub fn set_power_mode(&self, power_mode: PowerMode) {
+        let _state = self.lock_state();
+        self.controller
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .config
+            .power_mode = power_mode;
+    }
+
+    /// Pauses navigation (ex: while the user is parked for a lunch stop).
+    ///
+    /// While paused, location updates and step advances are ignored and return the current state,
+    /// so the trip progress is frozen, and the user is never reported as off route.
+    /// Any current prompt and deviation are cleared from the state.
+    /// The time spent paused is not included in the [`TripSummary`].
+    pub fn pause(&self) -> TripState {
+        let mut state = self.lock_state();
+        self.paused.store(true, Ordering::Relaxed);
+        self.lock_statistics().record_pause();
+        if let TripState::Navigating {
+            deviation,
+            spoken_instruction,
+            tracking,
+            ..
+        } = &mut *state
+        {
+            *deviation = RouteDeviation::NoDeviation;
+            *spoken_instruction = None;
+            // Arrival and wrong direction tracking start over when navigation resumes
+            tracking.arrival_dwell_start = None;
+            tracking.wrong_direction_start = None;
+        }
+        state.clone()
+    }
+
+    /// Resumes navigation after [`Self::pause`].
+    ///
+    /// Navigation picks up from the next location update
+    /// (which may, for example, find that the user is now off route).
+    pub fn resume(&self) -> TripState {
+        let state = self.lock_state();
+        self.paused.store(false, Ordering::Relaxed);
+        self.lock_statistics().record_resume();
+        state.clone()
+    }
+
+    /// Whether navigation is paused. See [`Self::pause`].
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+
+    /// Replaces the route (ex: after a reroute), keeping the same config.
+    ///
+    /// Navigation restarts from the beginning of the new route at the user's location.
+    /// If both the old and new trips are in progress,
+    /// a [`NavigationEvent::RouteChanged`] event describes how the new route compares to the remainder of the old one.
+    pub fn replace_route(&self, route: Route, location: UserLocation) -> BatchLocationUpdate {
+        let mut state = self.lock_state();
+        let mut controller = self
+            .controller
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+        let config = controller.config.clone();
+        *controller = NavigationController::new(route, config);
+        let new_state = controller.get_initial_state(location);
+        let change = route_change(&state, &new_state);
+        if change.is_some() {
+            self.lock_statistics().record_reroute();
+        }
+        let events = change
+            .map(|change| NavigationEvent::RouteChanged { change })
+            .into_iter()
+            .collect();
+        state.clone_from(&new_state);
+        BatchLocationUpdate {
+            state: new_state,
+            events,
+        }
+    }
+
+    /// Inserts a stop into the remaining trip (ex: to add a gas stop), rerouting through it.
+    ///
+    /// `position` is the index of the new stop among the remaining waypoints
+    /// (ex: 0 to stop before the next waypoint); positions past the end make it the new destination.
+    /// Waypoints which have already been reached are not revisited.
+    ///
+    /// Navigation continues along the current route while the new one is fetched.
+    /// The new route is then navigated from the user's latest location, as in [`Self::replace_route`],
+    /// so no progress is lost.
+    /// Returns `None` if the trip is not in progress (or completes before the new route arrives).
+    pub async fn add_waypoint(
+        &self,
+        route_adapter: Arc<RouteAdapter>,
+        executor: Arc<HttpExecutor>,
+        coordinate: GeographicCoordinate,
+        position: u32,
+        cancellation_token: Option<Arc<CancellationToken>>,
+    ) -> Result<Option<BatchLocationUpdate>, RouteFetchError> {
+        self.reroute_to_waypoints(
+            &route_adapter,
+            executor,
+            |remaining_waypoints| insert_waypoint(remaining_waypoints, coordinate, position),
+            cancellation_token,
+        )
+        .await
+    }
+
+    /// Changes the final destination of the trip, rerouting from the user's position.
+    ///
+    /// Any intermediate stops which have not been reached yet are kept.
+    /// Unlike creating a new controller, this keeps the session going,
+    /// so anything tracking it (ex: a [`crate::telemetry::TelemetryRecorder`]) carries on as with a reroute.
+    ///
+    /// As with [`Self::add_waypoint`], navigation continues along the current route
+    /// while the new one is fetched.
+    /// Returns `None` if the trip is not in progress (or completes before the new route arrives).
+    pub async fn change_destination(
+        &self,
+        route_adapter: Arc<RouteAdapter>,
+        executor: Arc<HttpExecutor>,
+        destination: GeographicCoordinate,
+        cancellation_token: Option<Arc<CancellationToken>>,
+    ) -> Result<Option<BatchLocationUpdate>, RouteFetchError> {
+        self.reroute_to_waypoints(
+            &route_adapter,
+            executor,
+            |remaining_waypoints| replace_destination(remaining_waypoints, destination),
+            cancellation_token,
+        )
+        .await
+    }
+}
+
+impl SynchronizedNavigationController {
+    /// Fetches a route from the user's position to a new set of waypoints
+    /// (derived from the remaining waypoints), then replaces the route with it.
+    async fn reroute_to_waypoints(
+        &self,
+        route_adapter: &RouteAdapter,
+        executor: Arc<HttpExecutor>,
+        waypoints: impl FnOnce(&[Waypoint]) -> Vec<Waypoint>,
+        cancellation_token: Option<Arc<CancellationToken>>,
+    ) -> Result<Option<BatchLocationUpdate>, RouteFetchError> {
+        let TripState::Navigating {
+            snapped_user_location,
+            remaining_waypoints,
+            ..
+        } = self.get_state()
+        else {
+            return Ok(None);
+        };
+
+        let route = route_adapter
+            .get_route(
+                executor,
+                snapped_user_location,
+                waypoints(&remaining_waypoints),
+                cancellation_token,
+            )
+            .await?;
+
+        // The user has likely moved on while the route was fetched
+        let TripState::Navigating {
+            snapped_user_location,
+            ..
+        } = self.get_state()
+        else {
+            return Ok(None);
+        };
+        Ok(Some(self.replace_route(route, snapped_user_location)))
+    }
+
+    /// Records a processed location for the trip summary.
+    ///
+    /// Returns a [`NavigationEvent::TripSummaryAvailable`] event if the location completed the trip.
+    fn record_location(
+        &self,
+        location: &UserLocation,
+        state: &TripState,
+        controller: &NavigationController,
+    ) -> Option<NavigationEvent> {
+        let mut statistics = self.lock_statistics();
+        let was_complete = statistics.is_complete();
+        statistics.record_location(location, state, controller.config.distance_measurement);
+        (!was_complete && statistics.is_complete()).then(|| NavigationEvent::TripSummaryAvailable {
+            summary: statistics.summary(),
+        })
+    }
+
+    fn lock_statistics(&self) -> MutexGuard<'_, TripStatistics> {
+        self.statistics
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn lock_state(&self) -> MutexGuard<'_, TripState> {
+        // The state is only ever replaced as a whole,
+        // so it is still consistent if another thread panicked while holding the lock.
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn read_controller(&self) -> RwLockReadGuard<'_, NavigationController> {
+        self.controller
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::create_valhalla_request_generator;
+    use crate::navigation_controller::models::StepAdvanceMode;
+    use crate::navigation_controller::presets::NavigationPreset;
+    use crate::navigation_controller::test_helpers::{
+        gen_dummy_route_step, gen_route_from_steps, gen_user_location,
+    };
+    use crate::routing_adapters::error::RoutingResponseParseError;
+    use crate::routing_adapters::http::test_helpers::{block_on, executor, MockHttpClient};
+    use crate::routing_adapters::RouteResponseParser;
+
+    /// Parses every response as a route via the new stop.
+    struct DetourParser;
+
+    impl RouteResponseParser for DetourParser {
+        fn parse_response(
+            &self,
+            _response: Vec<u8>,
+        ) -> Result<Vec<Route>, RoutingResponseParseError> {
+            let mut first_step = gen_dummy_route_step(0.0, 0.0, 0.005, 0.0);
+            first_step.distance = 556.0;
+            let mut second_step = gen_dummy_route_step(0.005, 0.0, 0.01, 0.0);
+            second_step.distance = 556.0;
+            let mut route = gen_route_from_steps(vec![first_step, second_step]);
+            route.waypoints = vec![waypoint(0.0), waypoint(0.005), waypoint(0.01)];
+            Ok(vec![route])
+        }
+    }
+
+    fn waypoint(lng: f64) -> Waypoint {
+        Waypoint {
+            coordinate: GeographicCoordinate { lat: 0.0, lng },
+            kind: WaypointKind::Break,
+        }
+    }
+
+    #[test]
+    fn map_matched_snapping_follows_the_route() {
+        // East along the equator, then back west ~11m to the north
+        let mut step = gen_dummy_route_step(0.0, 0.0, 0.002, 0.0);
+        step.geometry.extend([
+            GeographicCoordinate {
+                lng: 0.002,
+                lat: 0.0001,
+            },
+            GeographicCoordinate {
+                lng: 0.0,
+                lat: 0.0001,
+            },
+        ]);
+        step.distance = 456.0;
+        let route = gen_route_from_steps(vec![step]);
+
+        let snapped_lat = |snapping| {
+            let controller = NavigationController::new(
+                route.clone(),
+                NavigationControllerConfig {
+                    snapping,
+                    ..Default::default()
+                },
+            );
+            let state = controller.get_initial_state(gen_user_location(0.0, 0.0, 0));
+            let state = controller.update_user_location(gen_user_location(0.0009, 0.0, 10), &state);
+            // Closer to the westbound part of the route, but only a second later
+            let state =
+                controller.update_user_location(gen_user_location(0.001, 0.000_06, 11), &state);
+            let TripState::Navigating {
+                snapped_user_location,
+                ..
+            } = state
+            else {
+                panic!("Expected state to be navigating");
+            };
+            snapped_user_location.coordinates.lat
+        };
+
+        assert!((snapped_lat(SnappingMode::NearestPoint) - 0.0001).abs() < 1e-9);
+        assert!(snapped_lat(SnappingMode::MapMatched).abs() < 1e-9);
+    }
+
+    #[test]
+    fn inserts_waypoints() {
+        let remaining = vec![waypoint(1.0), waypoint(2.0)];
+        let stop = GeographicCoordinate { lat: 0.0, lng: 0.5 };
+
+        assert_eq!(
+            insert_waypoint(&remaining, stop, 0),
+            vec![waypoint(0.5), waypoint(1.0), waypoint(2.0)]
+        );
+        assert_eq!(
+            insert_waypoint(&remaining, stop, 1),
+            vec![waypoint(1.0), waypoint(0.5), waypoint(2.0)]
+        );
+        // Past the end, the stop becomes the destination
+        assert_eq!(
+            insert_waypoint(&remaining, stop, 10),
+            vec![waypoint(1.0), waypoint(2.0), waypoint(0.5)]
+        );
+    }
+
+    #[test]
+    fn replaces_destinations() {
+        let destination = GeographicCoordinate { lat: 0.0, lng: 3.0 };
+
+        assert_eq!(
+            replace_destination(&[waypoint(1.0), waypoint(2.0)], destination),
+            vec![waypoint(1.0), waypoint(3.0)]
+        );
+        assert_eq!(replace_destination(&[], destination), vec![waypoint(3.0)]);
+    }
+
+    #[test]
+    fn add_waypoint_reroutes_through_the_stop() {
+        let mut step = gen_dummy_route_step(0.0, 0.0, 0.01, 0.0);
+        step.distance = 1112.0;
+        let mut route = gen_route_from_steps(vec![step]);
+        route.waypoints = vec![waypoint(0.0), waypoint(0.01)];
+        let location = UserLocation {
+            coordinates: route.geometry[0],
+            horizontal_accuracy: 5.0,
+            course_over_ground: None,
+            timestamp: SystemTime::now(),
+            speed: None,
+            altitude: None,
+            vertical_accuracy: None,
+        };
+        let controller = SynchronizedNavigationController::new(
+            route,
+            NavigationControllerConfig {
+                step_advance: StepAdvanceMode::Manual,
+                snapping: SnappingMode::NearestPoint,
+                ..NavigationControllerConfig::from_preset(NavigationPreset::Driving)
+            },
+            location,
+        );
+        let client = MockHttpClient::new(Ok(vec![]));
+        let adapter = Arc::new(RouteAdapter::new(
+            create_valhalla_request_generator(
+                "https://valhalla.example.com/route".to_string(),
+                "auto".to_string(),
+                None,
+            )
+            .expect("Unable to create request generator"),
+            Arc::new(DetourParser),
+        ));
+
+        let update = block_on(controller.add_waypoint(
+            adapter,
+            executor(client.clone()),
+            GeographicCoordinate {
+                lat: 0.0,
+                lng: 0.005,
+            },
+            0,
+            None,
+        ))
+        .expect("Unable to add waypoint")
+        .expect("Expected the route to be replaced");
+
+        // The stop comes before the destination
+        let bodies = client.request_bodies();
+        let body = String::from_utf8_lossy(bodies.first().expect("Expected a request"));
+        let stop = body.find("0.005").expect("Missing the new stop");
+        let destination = body.find("0.01").expect("Missing the destination");
+        assert!(stop < destination);
+        assert!(matches!(
+            update.events.as_slice(),
+            [NavigationEvent::RouteChanged { .. }]
+        ));
+        let TripState::Navigating {
+            remaining_waypoints,
+            ..
+        } = update.state
+        else {
+            panic!("Expected to be navigating");
+        };
+        assert_eq!(remaining_waypoints, vec![waypoint(0.005), waypoint(0.01)]);
+    }
 }
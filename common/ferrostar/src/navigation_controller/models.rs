@@ -1,9 +1,34 @@
-use crate::deviation_detection::{RouteDeviation, RouteDeviationTracking};
-use crate::models::{RouteStep, SpokenInstruction, UserLocation, VisualInstruction, Waypoint};
+use crate::algorithms::DistanceMeasurement;
+use crate::announcement_timing::AnnouncementTiming;
+use crate::arrival_detection::{ArrivalDetection, NearbyArrivalDetection};
+use crate::breadcrumbs::BreadcrumbRecording;
+use crate::climbs::UpcomingClimb;
+use crate::clock::Clock;
+use crate::consumption::{ConsumptionEstimate, VehicleEnergyProfile};
+use crate::deviation_detection::{RouteDeviation, RouteDeviationTracking, WrongDirectionDetection};
+use crate::elevation::GradeEtaAdjustment;
+use crate::final_approach::FinalApproachGuidance;
+use crate::junction_view::JunctionViewResolver;
+use crate::location_filtering::{
+    AccuracyGating, MotionDerivation, PositioningStatus, StaleLocationRejection,
+};
+use crate::models::{
+    GeographicCoordinate, Incident, JunctionView, Route, RouteStep, SpokenInstruction,
+    UserLocation, VisualInstruction, Waypoint,
+};
+use crate::now;
+use crate::road_name::RoadNameLookup;
+use crate::trip_summary::TripSummary;
+use crate::tunnel_mode::TunnelMode;
 use geo::LineString;
+#[cfg(feature = "wasm-js")]
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::SystemTime;
 
 /// A subset of state values that are used to show the user their current progress along the trip and it's components.
 #[derive(Debug, Clone, PartialEq, uniffi::Record)]
+#[cfg_attr(feature = "wasm-js", derive(Serialize, Deserialize))]
 pub struct TripProgress {
     /// The distance to the next maneuver, in meters.
     pub distance_to_next_maneuver: f64,
@@ -13,42 +38,188 @@ pub struct TripProgress {
     pub distance_remaining: f64,
     /// The total duration remaining in the trip, in seconds.
     pub duration_remaining: f64,
+    /// The estimated fuel or energy consumption.
+    ///
+    /// This is only estimated when [`NavigationControllerConfig::vehicle_energy_profile`] is set.
+    #[uniffi(default = None)]
+    pub consumption: Option<ConsumptionEstimate>,
 }
 
 /// Internal state of the navigation controller.
 #[derive(Debug, Clone, PartialEq, uniffi::Enum)]
+#[cfg_attr(feature = "wasm-js", derive(Serialize, Deserialize))]
 pub enum TripState {
     Navigating {
         snapped_user_location: UserLocation,
-        /// The ordered list of steps that remain in the trip.
-        ///
-        /// The step at the front of the list is always the current step.
-        /// We currently assume that you cannot move backward to a previous step.
+        /// The steps that remain in the trip, starting with the current step.
         remaining_steps: Vec<RouteStep>,
-        /// Remaining waypoints to visit on the route.
-        ///
-        /// The waypoint at the front of the list is always the *next* waypoint "goal."
-        /// Unlike the current step, there is no value in tracking the "current" waypoint,
-        /// as the main use of waypoints is recalculation when the user deviates from the route.
-        /// (In most use cases, a route will have only two waypoints, but more complex use cases
-        /// may have multiple intervening points that are visited along the route.)
-        /// This list is updated as the user advances through the route.
+        /// The waypoints that remain in the trip, starting with the next one.
         remaining_waypoints: Vec<Waypoint>,
-        /// The trip progress includes information that is useful for showing the
-        /// user's progress along the full navigation trip, the route and its components.
         progress: TripProgress,
-        /// The route deviation status: is the user following the route or not?
         deviation: RouteDeviation,
-        /// The visual instruction that should be displayed in the user interface.
+        /// The visual instruction to display.
         visual_instruction: Option<VisualInstruction>,
-        /// The most recent spoken instruction that should be synthesized using TTS.
+        /// The step after the next maneuver, if it should be shown with it (ex: "then turn right").
+        then_step: Option<RouteStep>,
+        /// The current spoken instruction.
         ///
-        /// Note it is the responsibility of the platform layer to ensure that utterances are not synthesized multiple times. This property simply reports the current spoken instruction.
+        /// Platforms are responsible for not synthesizing an utterance more than once.
         spoken_instruction: Option<SpokenInstruction>,
+        /// Spoken instructions for an upcoming tunnel, which may be prepared in advance.
+        prefetched_spoken_instructions: Vec<SpokenInstruction>,
+        upcoming_incident: Option<UpcomingIncident>,
+        upcoming_climb: Option<UpcomingClimb>,
+        current_road_name: Option<String>,
+        /// Bookkeeping of the navigation controller between location updates.
+        tracking: NavigationTracking,
+        positioning: PositioningStatus,
+    },
+    /// The user is traveling without a route (ex: on a "passive navigation" screen).
+    ///
+    /// See [`crate::free_drive::FreeDriveController`].
+    /// Passing this state to a [`crate::navigation_controller::NavigationController`]
+    /// starts navigating its route from the user's location.
+    FreeDrive {
+        /// The user's location, after filtering.
+        ///
+        /// This is only snapped to the road if the location was map matched before it was passed in.
+        user_location: UserLocation,
+        /// The name of the road the user is currently traveling on.
+        ///
+        /// This is only reported when the [`NavigationControllerConfig::road_name_lookup`] includes a geocoder.
+        current_road_name: Option<String>,
+        /// The posted speed limit at the user's location, in meters per second.
+        ///
+        /// See [`crate::free_drive::SpeedLimitProvider`].
+        speed_limit: Option<f64>,
+        /// Whether recent location updates were accurate enough to use.
+        positioning: PositioningStatus,
     },
     Complete,
 }
 
+/// An incident that the user is approaching (or currently passing through).
+#[derive(Debug, Clone, PartialEq, uniffi::Record)]
+#[cfg_attr(feature = "wasm-js", derive(Serialize, Deserialize))]
+pub struct UpcomingIncident {
+    pub incident: Incident,
+    /// The distance along the route to the start of the incident, in meters.
+    ///
+    /// This is zero once the user has reached the incident.
+    pub distance_to_incident: f64,
+}
+
+/// A maneuver along the rest of the route, for showing a list of directions.
+#[derive(Debug, Clone, PartialEq, uniffi::Record)]
+#[cfg_attr(feature = "wasm-js", derive(Serialize, Deserialize))]
+pub struct ManeuverPreview {
+    /// The step which begins with the maneuver.
+    ///
+    /// Its instruction and road name describe the maneuver.
+    pub step: RouteStep,
+    /// The primary visual instruction for the maneuver, if the route has visual instructions.
+    pub visual_instruction: Option<VisualInstruction>,
+    /// The distance along the route to the maneuver, in meters.
+    pub distance_to_maneuver: f64,
+    /// The estimated time until the maneuver, in seconds.
+    pub duration_to_maneuver: f64,
+    /// The estimated time of arrival at the maneuver.
+    pub estimated_arrival: SystemTime,
+}
+
+/// Something notable which happened during a location update.
+#[derive(Debug, Clone, PartialEq, uniffi::Enum)]
+#[cfg_attr(feature = "wasm-js", derive(Serialize, Deserialize))]
+pub enum NavigationEvent {
+    /// Navigation advanced to a new step.
+    StepAdvanced { step: RouteStep },
+    /// Navigation moved back to the previous step, as the user fell back behind its end.
+    ///
+    /// See [`StepAdvanceCooldown::Enabled`].
+    StepRegressed { step: RouteStep },
+    /// The user reached a waypoint (including the destination).
+    WaypointReached { waypoint: Waypoint },
+    /// The trip is complete.
+    TripCompleted,
+    /// The route was replaced (ex: after a reroute).
+    RouteChanged { change: RouteChange },
+    /// A route to the remaining waypoints is faster than the current one.
+    ///
+    /// See [`crate::alternative_routes::AlternativeRouteMonitor`].
+    FasterRouteAvailable { route: Route, change: RouteChange },
+    /// The route was planned long enough ago that it may be out of date
+    /// (ex: a saved route which is being resumed hours later).
+    ///
+    /// See [`crate::stale_routes::StaleRouteGuard`].
+    RouteStale {
+        /// The time since the route was planned, in seconds.
+        age: f64,
+    },
+    /// The previous trip is complete, and navigation of the next trip in the queue has started.
+    ///
+    /// See [`crate::navigation_controller::trip_queue::TripQueue`].
+    NextTripStarted {
+        route: Route,
+        /// The number of trips after this one.
+        upcoming_trip_count: u32,
+    },
+    /// The user crossed into a different country
+    /// (ex: so that units or the style of speed limit signs can be switched).
+    ///
+    /// See [`crate::models::RouteStep::country_changes`].
+    CountryChanged {
+        /// The ISO 3166-1 alpha-2 code of the country (ex: `DE`).
+        iso_3166: String,
+    },
+    /// The user is approaching a maneuver with a junction view image.
+    ///
+    /// See [`crate::junction_view`].
+    JunctionViewApproaching { junction_view: JunctionView },
+    /// The user began the final step of the route,
+    /// and the destination has details to guide them from the end of the route to it.
+    ///
+    /// See [`crate::final_approach`].
+    FinalApproach { guidance: FinalApproachGuidance },
+    /// The user stopped near the destination without completing the route
+    /// (ex: because the final steps cannot be completed), so the trip may be completed.
+    ///
+    /// See [`crate::arrival_detection::NearbyArrivalDetection`].
+    ArrivedNearby {
+        /// The distance from the user to the destination, in meters.
+        distance: f64,
+    },
+    /// Statistics about the trip which was just completed (reported after [`Self::TripCompleted`]).
+    ///
+    /// See [`crate::navigation_controller::SynchronizedNavigationController::get_trip_summary`].
+    TripSummaryAvailable { summary: TripSummary },
+}
+
+/// How a new route compares to the remainder of the route it replaces.
+///
+/// Both are measured from the user's location when the route was replaced.
+/// Negative values mean that the new route is shorter or faster
+/// (ex: a reroute around traffic saves time).
+#[derive(Debug, Copy, Clone, PartialEq, uniffi::Record)]
+#[cfg_attr(feature = "wasm-js", derive(Serialize, Deserialize))]
+pub struct RouteChange {
+    /// The change in distance remaining, in meters.
+    pub distance_delta: f64,
+    /// The change in duration remaining, in seconds.
+    pub duration_delta: f64,
+}
+
+/// The result of an update which may trigger several navigation events,
+/// like processing a batch of location updates.
+#[derive(Debug, Clone, PartialEq, uniffi::Record)]
+#[cfg_attr(feature = "wasm-js", derive(Serialize, Deserialize))]
+pub struct BatchLocationUpdate {
+    /// The state after the update (ex: after processing the last location in the batch).
+    pub state: TripState,
+    /// The events which occurred while processing the batch, in order.
+    pub events: Vec<NavigationEvent>,
+}
+
+#[allow(clippy::large_enum_variant)]
 pub enum StepAdvanceStatus {
     /// Navigation has advanced, and the information on the next step is embedded.
     Advanced {
@@ -81,10 +252,379 @@ pub enum StepAdvanceMode {
         /// of which `LineString` appears closer.
         automatic_advance_distance: Option<u16>,
     },
+    /// Automatically advances when the user's position passes the maneuver point.
+    ///
+    /// Step geometries are split at the geometry index of the maneuver intersection,
+    /// so the maneuver point is always the last vertex of the step.
+    /// The user's position is matched to a segment of the current and next step geometries,
+    /// and navigation advances once the matched segment is past the maneuver point.
+    /// Unlike [`StepAdvanceMode::DistanceToEndOfStep`], this cannot trigger early on switchbacks,
+    /// where the end of the step is geographically close to earlier parts of it.
+    ManeuverGeometryIndex {
+        /// The minimum required horizontal accuracy of the user location, in meters.
+        /// Values larger than this cannot trigger a step advance.
+        minimum_horizontal_accuracy: u16,
+    },
+    /// Automatically advances when the user's position passes the maneuver point
+    /// (as in [`StepAdvanceMode::ManeuverGeometryIndex`])
+    /// *and* the user's course roughly matches the initial bearing of the next step.
+    ///
+    /// This confirms that the maneuver was actually made,
+    /// so GPS overshoot at an intersection does not advance (and snap the user to the next step) early.
+    /// Locations without a course are only checked against the maneuver point.
+    ManeuverConfirmation {
+        /// The minimum required horizontal accuracy of the user location, in meters.
+        /// Values larger than this cannot trigger a step advance.
+        minimum_horizontal_accuracy: u16,
+        /// The maximum difference between the user's course and the initial bearing of the next step,
+        /// in degrees.
+        maximum_course_difference: u16,
+    },
+}
+
+/// State which the navigation controller carries from one location update to the next.
+///
+/// Apps do not need to inspect this.
+#[derive(Debug, Copy, Clone, Default, PartialEq, uniffi::Record)]
+#[cfg_attr(feature = "wasm-js", derive(Serialize, Deserialize))]
+pub struct NavigationTracking {
+    /// When the user first met the arrival criteria without leaving since.
+    ///
+    /// This is used to enforce the minimum dwell time of [`ArrivalDetection::Criteria`].
+    pub arrival_dwell_start: Option<SystemTime>,
+    /// Where the user first started traveling in the wrong direction without correcting since.
+    ///
+    /// This is used to enforce the minimum distance of [`WrongDirectionDetection::CourseOverGround`].
+    pub wrong_direction_start: Option<GeographicCoordinate>,
+    /// When navigation last advanced to a new step.
+    ///
+    /// This is used to enforce [`NavigationControllerConfig::step_advance_cooldown`].
+    pub last_step_advance: Option<LastStepAdvance>,
+}
+
+/// When and where navigation last advanced to a new step.
+#[derive(Debug, Copy, Clone, PartialEq, uniffi::Record)]
+#[cfg_attr(feature = "wasm-js", derive(Serialize, Deserialize))]
+pub struct LastStepAdvance {
+    /// The timestamp of the location at which the step advanced.
+    pub timestamp: SystemTime,
+    /// The distance to the maneuver at the end of the new step when it began, in meters.
+    pub distance_to_next_maneuver: f64,
+}
+
+/// Adds hysteresis to step advances.
+///
+/// Noisy positions around a corner (ex: while waiting to turn) can briefly look like
+/// the user fell back behind the end of the previous step, or left the new step,
+/// which would flicker between steps and the off route warning (and trigger rerouting).
+#[derive(Debug, Copy, Clone, PartialEq, uniffi::Enum)]
+pub enum StepAdvanceCooldown {
+    /// Navigation never moves back to a previous step,
+    /// and deviations are reported as soon as they are detected.
+    Disabled,
+    /// Navigation moves back to the previous step when the user falls back behind its end
+    /// (according to the [`StepAdvanceMode`]), except for a while after each step advance.
+    ///
+    /// Off route and wrong direction deviations are not reported until the cooldown ends either.
+    Enabled {
+        /// The time after the step advance at which the cooldown ends, in seconds.
+        duration: f64,
+        /// The distance along the new step at which the cooldown ends early, in meters.
+        ///
+        /// Once the user has made progress along the new step, any deviation is real.
+        distance: f64,
+    },
+}
+
+impl StepAdvanceCooldown {
+    /// Determines whether the cooldown after the last step advance is still in effect.
+    pub(crate) fn is_active(
+        self,
+        last_step_advance: Option<&LastStepAdvance>,
+        location: &UserLocation,
+        distance_to_next_maneuver: f64,
+    ) -> bool {
+        let (StepAdvanceCooldown::Enabled { duration, distance }, Some(last_step_advance)) =
+            (self, last_step_advance)
+        else {
+            return false;
+        };
+        let elapsed = location
+            .timestamp
+            .duration_since(last_step_advance.timestamp)
+            .map_or(0.0, |elapsed| elapsed.as_secs_f64());
+        let traveled = last_step_advance.distance_to_next_maneuver - distance_to_next_maneuver;
+
+        elapsed < duration && traveled < distance
+    }
+
+    /// Determines whether navigation may move back to the previous step.
+    ///
+    /// Steps only regress when the cooldown is enabled, and never while it is active.
+    pub(crate) fn allows_regression(
+        self,
+        last_step_advance: Option<&LastStepAdvance>,
+        location: &UserLocation,
+        distance_to_next_maneuver: f64,
+    ) -> bool {
+        matches!(self, StepAdvanceCooldown::Enabled { .. })
+            && !self.is_active(last_step_advance, location, distance_to_next_maneuver)
+    }
+}
+
+/// Determines which guidance the user is prompted with.
+///
+/// This is enforced when building the trip state,
+/// so that muted guidance behaves identically on every platform.
+/// Visual instructions are always reported.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, uniffi::Enum)]
+pub enum GuidanceVerbosity {
+    /// Spoken instructions and alerts (ex: upcoming incidents and climbs).
+    All,
+    /// Spoken instructions only.
+    ManeuversOnly,
+    /// Alerts only.
+    AlertsOnly,
+    /// No spoken instructions or alerts.
+    Muted,
+}
+
+impl GuidanceVerbosity {
+    /// Whether spoken instructions are reported.
+    pub(crate) fn includes_maneuvers(self) -> bool {
+        matches!(
+            self,
+            GuidanceVerbosity::All | GuidanceVerbosity::ManeuversOnly
+        )
+    }
+
+    /// Whether alerts are reported.
+    pub(crate) fn includes_alerts(self) -> bool {
+        matches!(self, GuidanceVerbosity::All | GuidanceVerbosity::AlertsOnly)
+    }
+}
+
+/// Determines how much work the controller does for each location update.
+///
+/// Apps typically receive fewer (and less frequent) location updates while backgrounded,
+/// and nobody is looking at the map,
+/// so the controller can save battery without affecting voice guidance.
+#[derive(Debug, Copy, Clone, PartialEq, uniffi::Enum)]
+pub enum PowerMode {
+    /// Every location update is fully processed.
+    Foreground,
+    /// Reduces the work done for each location update while the app is backgrounded.
+    ///
+    /// - Locations are snapped to the nearest point on the route line,
+    ///   without disambiguating parallel or stacked roads by course or altitude.
+    /// - Camera hints are suppressed.
+    /// - Trip progress is only reported when it changes by at least `progress_update_distance`
+    ///   (or navigation advances to the next step).
+    ///
+    /// Spoken instructions are still triggered using the latest location.
+    Background {
+        /// The minimum change in the distance remaining, in meters, before progress is reported.
+        progress_update_distance: f64,
+    },
+}
+
+/// Determines whether the geometry which the user has already traveled is kept in the trip state.
+#[derive(Debug, Copy, Clone, PartialEq, uniffi::Enum)]
+pub enum GeometryRetention {
+    /// The full geometry of the current step is kept until the step is completed.
+    Full,
+    /// The geometry of the current step is progressively truncated as the user travels along it,
+    /// which bounds the memory used by the trip state on long steps (ex: hundreds of kilometers of highway).
+    ///
+    /// The distance and duration of the current step are reduced to match the remaining geometry,
+    /// so trip progress is unaffected.
+    /// Note that the navigation controller still holds its own copy of the full route.
+    TrimTraveled {
+        /// How much of the traveled geometry to keep behind the user, in meters.
+        ///
+        /// This lets the user back up a short distance (ex: in a parking lot) without leaving the line.
+        rewind_distance: f64,
+    },
+}
+
+impl PowerMode {
+    /// Holds the progress of the previous state unless it changed enough to report it.
+    pub(crate) fn batch_progress(&self, previous: &TripState, mut next: TripState) -> TripState {
+        let PowerMode::Background {
+            progress_update_distance,
+        } = self
+        else {
+            return next;
+        };
+
+        if let (
+            TripState::Navigating {
+                progress: previous_progress,
+                remaining_steps: previous_steps,
+                ..
+            },
+            TripState::Navigating {
+                progress,
+                remaining_steps,
+                ..
+            },
+        ) = (previous, &mut next)
+        {
+            if remaining_steps.len() == previous_steps.len()
+                && (previous_progress.distance_remaining - progress.distance_remaining).abs()
+                    < *progress_update_distance
+            {
+                progress.clone_from(previous_progress);
+            }
+        }
+
+        next
+    }
+}
+
+/// Determines how the user's location is snapped to the route line.
+#[derive(Debug, Copy, Clone, PartialEq, uniffi::Enum)]
+pub enum SnappingMode {
+    /// Locations are used as reported.
+    ///
+    /// Progress is still measured along the route,
+    /// but the displayed position may be off the route line.
+    /// This is useful for pedestrians, who rarely follow the route line exactly.
+    None,
+    /// Locations are snapped to the nearest point on the route line.
+    ///
+    /// If the route has an elevation profile, the user's altitude is used to disambiguate
+    /// stacked roads (ex: a bridge over a road that the route also uses).
+    NearestPoint,
+    /// Like [`SnappingMode::NearestPoint`], but the user's course is used to disambiguate
+    /// parallel roads (ex: a frontage road next to a highway).
+    NearestPointWithHeading,
+    /// Locations are matched to the route line using the previous position along it,
+    /// so that the match stays continuous where the route passes close to itself
+    /// (ex: a hairpin, or a loop around a block).
+    ///
+    /// See [`crate::algorithms::snap_user_location_to_line_with_history`].
+    /// Matching is constrained to the route line;
+    /// apps which need to match locations to the wider road network (ex: while off route)
+    /// can correct them beforehand with
+    /// [`crate::routing_adapters::map_matching::MapMatchingAdapter::correct_location`].
+    MapMatched,
 }
 
 #[derive(Clone, uniffi::Record)]
 pub struct NavigationControllerConfig {
     pub step_advance: StepAdvanceMode,
+    /// Optionally lets navigation move back to the previous step,
+    /// suppressing that and route deviation for a while after each step advance.
+    pub step_advance_cooldown: StepAdvanceCooldown,
     pub route_deviation_tracking: RouteDeviationTracking,
+    /// Detects when the user is traveling along the route in the wrong direction.
+    pub wrong_direction_detection: WrongDirectionDetection,
+    /// Optionally adjusts the duration remaining to account for hills
+    /// (requires the route to have an elevation profile).
+    pub grade_eta_adjustment: GradeEtaAdjustment,
+    /// Determines when the trip is complete.
+    pub arrival_detection: ArrivalDetection,
+    /// Determines when to report that the user stopped near the destination
+    /// without completing the route.
+    pub nearby_arrival_detection: NearbyArrivalDetection,
+    /// Determines how navigation progresses in tunnels.
+    pub tunnel_mode: TunnelMode,
+    /// Determines when spoken instructions are announced, given the user's speed.
+    pub announcement_timing: AnnouncementTiming,
+    /// Determines which guidance the user is prompted with.
+    pub guidance_verbosity: GuidanceVerbosity,
+    /// The distance, in meters, within which to alert the user of incidents along the route.
+    ///
+    /// Incident alerts are disabled when this is `None`.
+    #[uniffi(default = None)]
+    pub incident_alert_distance: Option<f64>,
+    /// The distance, in meters, within which to report climbs along the route.
+    ///
+    /// Climbs are only reported when this is set and the route has an elevation profile.
+    #[uniffi(default = None)]
+    pub climb_alert_distance: Option<f64>,
+    /// The maximum distance, in meters, between two maneuvers for the second one to be
+    /// reported as a [`TripState::Navigating::then_step`].
+    ///
+    /// "Then" maneuvers are disabled when this is `None`.
+    #[uniffi(default = None)]
+    pub then_step_max_distance: Option<f64>,
+    /// Determines which location updates are discarded as stale.
+    pub stale_location_rejection: StaleLocationRejection,
+    /// Determines which location updates are discarded as too inaccurate.
+    pub accuracy_gating: AccuracyGating,
+    /// Determines whether missing speed and course values are derived from successive locations.
+    pub motion_derivation: MotionDerivation,
+    /// Determines how the user's location is snapped to the route line.
+    pub snapping: SnappingMode,
+    /// Determines how distances are measured (ex: the distance to the next maneuver).
+    pub distance_measurement: DistanceMeasurement,
+    /// Determines whether the traveled geometry of the current step is kept in the trip state.
+    pub geometry_retention: GeometryRetention,
+    /// Determines whether a trail of the user's locations is kept for the trip summary.
+    ///
+    /// This is only recorded by the [`crate::navigation_controller::SynchronizedNavigationController`].
+    pub breadcrumb_recording: BreadcrumbRecording,
+    /// Reduces the work done for each location update (ex: while the app is backgrounded).
+    pub power_mode: PowerMode,
+    /// Determines how the name of the current road is reported.
+    pub road_name_lookup: RoadNameLookup,
+    /// The vehicle used to estimate fuel or energy consumption in the trip progress.
+    ///
+    /// Consumption estimates are disabled when this is `None`.
+    #[uniffi(default = None)]
+    pub vehicle_energy_profile: Option<VehicleEnergyProfile>,
+    /// Fetches junction view images as the user approaches them (if set).
+    #[uniffi(default = None)]
+    pub junction_view_resolver: Option<Arc<dyn JunctionViewResolver>>,
+    /// The source of the current time (ex: for [`Self::stale_location_rejection`]).
+    ///
+    /// The system clock is used when this is `None`.
+    #[uniffi(default = None)]
+    pub clock: Option<Arc<dyn Clock>>,
+}
+
+/// A config with every optional behavior disabled
+/// (manual step advance, no route deviation tracking, and snapping to the nearest point).
+///
+/// This is mostly useful as a base for configs which enable one behavior at a time (ex: in tests);
+/// apps will usually want [`NavigationControllerConfig::from_preset`] instead.
+impl Default for NavigationControllerConfig {
+    fn default() -> Self {
+        Self {
+            step_advance: StepAdvanceMode::Manual,
+            step_advance_cooldown: StepAdvanceCooldown::Disabled,
+            route_deviation_tracking: RouteDeviationTracking::None,
+            wrong_direction_detection: WrongDirectionDetection::None,
+            grade_eta_adjustment: GradeEtaAdjustment::None,
+            arrival_detection: ArrivalDetection::StepCompletion,
+            nearby_arrival_detection: NearbyArrivalDetection::None,
+            tunnel_mode: TunnelMode::None,
+            announcement_timing: AnnouncementTiming::Static,
+            guidance_verbosity: GuidanceVerbosity::All,
+            incident_alert_distance: None,
+            climb_alert_distance: None,
+            then_step_max_distance: None,
+            stale_location_rejection: StaleLocationRejection::None,
+            accuracy_gating: AccuracyGating::None,
+            motion_derivation: MotionDerivation::None,
+            snapping: SnappingMode::NearestPoint,
+            distance_measurement: DistanceMeasurement::Haversine,
+            geometry_retention: GeometryRetention::Full,
+            breadcrumb_recording: BreadcrumbRecording::Disabled,
+            power_mode: PowerMode::Foreground,
+            road_name_lookup: RoadNameLookup::None,
+            vehicle_energy_profile: None,
+            junction_view_resolver: None,
+            clock: None,
+        }
+    }
+}
+
+impl NavigationControllerConfig {
+    /// The current time, according to the configured clock.
+    pub(crate) fn now(&self) -> SystemTime {
+        self.clock.as_ref().map_or_else(now, |clock| clock.now())
+    }
 }
@@ -0,0 +1,246 @@
+//! Ready-made [`NavigationControllerConfig`]s for common modes of travel.
+//!
+//! These are reasonable starting points, tuned for typical phone GPS accuracy.
+//! Apps with specific needs can adjust individual fields of the preset.
+
+use super::models::{
+    GeometryRetention, GuidanceVerbosity, NavigationControllerConfig, PowerMode, SnappingMode,
+    StepAdvanceCooldown, StepAdvanceMode,
+};
+use crate::algorithms::DistanceMeasurement;
+use crate::announcement_timing::AnnouncementTiming;
+use crate::arrival_detection::{ArrivalDetection, NearbyArrivalDetection};
+use crate::breadcrumbs::BreadcrumbRecording;
+use crate::deviation_detection::{RouteDeviationTracking, WrongDirectionDetection};
+use crate::elevation::GradeEtaAdjustment;
+use crate::location_filtering::{AccuracyGating, MotionDerivation, StaleLocationRejection};
+use crate::road_name::RoadNameLookup;
+use crate::tunnel_mode::TunnelMode;
+
+/// A mode of travel with a ready-made [`NavigationControllerConfig`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, uniffi::Enum)]
+pub enum NavigationPreset {
+    /// Cars and motorcycles.
+    Driving,
+    /// Pedestrians, who frequently cut corners and turn around,
+    /// so snapping and wrong direction detection are disabled.
+    Walking,
+    /// Bicycles (including e-bikes).
+    Cycling,
+    /// Trucks and other large vehicles, which need earlier warnings and more room to maneuver.
+    Truck,
+}
+
+impl NavigationControllerConfig {
+    /// Creates the config for a [`NavigationPreset`].
+    pub fn from_preset(preset: NavigationPreset) -> Self {
+        match preset {
+            NavigationPreset::Driving => Self {
+                step_advance: StepAdvanceMode::RelativeLineStringDistance {
+                    minimum_horizontal_accuracy: 32,
+                    automatic_advance_distance: Some(15),
+                },
+                step_advance_cooldown: StepAdvanceCooldown::Disabled,
+                route_deviation_tracking: RouteDeviationTracking::StaticThreshold {
+                    minimum_horizontal_accuracy: 25,
+                    max_acceptable_deviation: 50.0,
+                },
+                wrong_direction_detection: WrongDirectionDetection::CourseOverGround {
+                    min_bearing_difference: 135,
+                    max_course_accuracy: 30,
+                    min_distance: 50.0,
+                },
+                grade_eta_adjustment: GradeEtaAdjustment::None,
+                arrival_detection: ArrivalDetection::Criteria {
+                    max_distance_to_destination: 50.0,
+                    max_speed: Some(5.0),
+                    min_dwell_time: None,
+                },
+                nearby_arrival_detection: NearbyArrivalDetection::None,
+                tunnel_mode: TunnelMode::RouteTime {
+                    minimum_horizontal_accuracy: 32,
+                    prefetch_distance: 500.0,
+                },
+                announcement_timing: AnnouncementTiming::SpeedScaled {
+                    // 50 km/h
+                    reference_speed: 13.9,
+                    min_scale: 0.75,
+                    max_scale: 2.0,
+                },
+                guidance_verbosity: GuidanceVerbosity::All,
+                incident_alert_distance: Some(1000.0),
+                climb_alert_distance: None,
+                then_step_max_distance: Some(100.0),
+                stale_location_rejection: StaleLocationRejection::Monotonic {
+                    max_age: Some(10.0),
+                },
+                accuracy_gating: AccuracyGating::MaxHorizontalAccuracy {
+                    max_horizontal_accuracy: 65.0,
+                    degraded_after: 5,
+                },
+                motion_derivation: MotionDerivation::SuccessiveLocations { min_interval: 1.0 },
+                snapping: SnappingMode::NearestPointWithHeading,
+                distance_measurement: DistanceMeasurement::Haversine,
+                geometry_retention: GeometryRetention::Full,
+                breadcrumb_recording: BreadcrumbRecording::Disabled,
+                power_mode: PowerMode::Foreground,
+                road_name_lookup: RoadNameLookup::StepName,
+                vehicle_energy_profile: None,
+                junction_view_resolver: None,
+                clock: None,
+            },
+            NavigationPreset::Walking => Self {
+                step_advance: StepAdvanceMode::DistanceToEndOfStep {
+                    distance: 10,
+                    minimum_horizontal_accuracy: 32,
+                },
+                step_advance_cooldown: StepAdvanceCooldown::Disabled,
+                route_deviation_tracking: RouteDeviationTracking::StaticThreshold {
+                    minimum_horizontal_accuracy: 32,
+                    max_acceptable_deviation: 40.0,
+                },
+                wrong_direction_detection: WrongDirectionDetection::None,
+                grade_eta_adjustment: GradeEtaAdjustment::Tobler,
+                arrival_detection: ArrivalDetection::Criteria {
+                    max_distance_to_destination: 20.0,
+                    max_speed: None,
+                    min_dwell_time: None,
+                },
+                nearby_arrival_detection: NearbyArrivalDetection::None,
+                tunnel_mode: TunnelMode::None,
+                announcement_timing: AnnouncementTiming::Static,
+                guidance_verbosity: GuidanceVerbosity::All,
+                incident_alert_distance: None,
+                climb_alert_distance: None,
+                then_step_max_distance: Some(20.0),
+                stale_location_rejection: StaleLocationRejection::Monotonic {
+                    max_age: Some(10.0),
+                },
+                accuracy_gating: AccuracyGating::MaxHorizontalAccuracy {
+                    max_horizontal_accuracy: 50.0,
+                    degraded_after: 10,
+                },
+                motion_derivation: MotionDerivation::None,
+                snapping: SnappingMode::None,
+                distance_measurement: DistanceMeasurement::Haversine,
+                geometry_retention: GeometryRetention::Full,
+                breadcrumb_recording: BreadcrumbRecording::Disabled,
+                power_mode: PowerMode::Foreground,
+                road_name_lookup: RoadNameLookup::StepName,
+                vehicle_energy_profile: None,
+                junction_view_resolver: None,
+                clock: None,
+            },
+            NavigationPreset::Cycling => Self {
+                step_advance: StepAdvanceMode::DistanceToEndOfStep {
+                    distance: 10,
+                    minimum_horizontal_accuracy: 25,
+                },
+                step_advance_cooldown: StepAdvanceCooldown::Disabled,
+                route_deviation_tracking: RouteDeviationTracking::StaticThreshold {
+                    minimum_horizontal_accuracy: 25,
+                    max_acceptable_deviation: 35.0,
+                },
+                wrong_direction_detection: WrongDirectionDetection::CourseOverGround {
+                    min_bearing_difference: 135,
+                    max_course_accuracy: 30,
+                    min_distance: 30.0,
+                },
+                grade_eta_adjustment: GradeEtaAdjustment::Naismith {
+                    seconds_per_meter_of_ascent: 3.0,
+                },
+                arrival_detection: ArrivalDetection::Criteria {
+                    max_distance_to_destination: 25.0,
+                    max_speed: None,
+                    min_dwell_time: None,
+                },
+                nearby_arrival_detection: NearbyArrivalDetection::None,
+                tunnel_mode: TunnelMode::None,
+                announcement_timing: AnnouncementTiming::SpeedScaled {
+                    // 18 km/h
+                    reference_speed: 5.0,
+                    min_scale: 0.75,
+                    max_scale: 1.5,
+                },
+                guidance_verbosity: GuidanceVerbosity::All,
+                incident_alert_distance: Some(300.0),
+                climb_alert_distance: Some(2000.0),
+                then_step_max_distance: Some(50.0),
+                stale_location_rejection: StaleLocationRejection::Monotonic {
+                    max_age: Some(10.0),
+                },
+                accuracy_gating: AccuracyGating::MaxHorizontalAccuracy {
+                    max_horizontal_accuracy: 50.0,
+                    degraded_after: 5,
+                },
+                motion_derivation: MotionDerivation::SuccessiveLocations { min_interval: 1.0 },
+                snapping: SnappingMode::NearestPoint,
+                distance_measurement: DistanceMeasurement::Haversine,
+                geometry_retention: GeometryRetention::Full,
+                breadcrumb_recording: BreadcrumbRecording::Disabled,
+                power_mode: PowerMode::Foreground,
+                road_name_lookup: RoadNameLookup::StepName,
+                vehicle_energy_profile: None,
+                junction_view_resolver: None,
+                clock: None,
+            },
+            NavigationPreset::Truck => Self {
+                step_advance: StepAdvanceMode::RelativeLineStringDistance {
+                    minimum_horizontal_accuracy: 32,
+                    automatic_advance_distance: Some(20),
+                },
+                step_advance_cooldown: StepAdvanceCooldown::Disabled,
+                route_deviation_tracking: RouteDeviationTracking::StaticThreshold {
+                    minimum_horizontal_accuracy: 25,
+                    max_acceptable_deviation: 60.0,
+                },
+                arrival_detection: ArrivalDetection::Criteria {
+                    max_distance_to_destination: 75.0,
+                    max_speed: Some(5.0),
+                    min_dwell_time: None,
+                },
+                // Heavy vehicles need more time to slow down and change lanes
+                announcement_timing: AnnouncementTiming::SpeedScaled {
+                    reference_speed: 13.9,
+                    min_scale: 1.0,
+                    max_scale: 2.5,
+                },
+                incident_alert_distance: Some(2000.0),
+                then_step_max_distance: Some(200.0),
+                ..Self::from_preset(NavigationPreset::Driving)
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn truck_preset_extends_driving() {
+        let driving = NavigationControllerConfig::from_preset(NavigationPreset::Driving);
+        let truck = NavigationControllerConfig::from_preset(NavigationPreset::Truck);
+
+        assert_eq!(truck.snapping, driving.snapping);
+        assert_eq!(truck.accuracy_gating, driving.accuracy_gating);
+        assert!(matches!(
+            truck.route_deviation_tracking,
+            RouteDeviationTracking::StaticThreshold {
+                max_acceptable_deviation,
+                ..
+            } if max_acceptable_deviation > 50.0
+        ));
+    }
+
+    #[test]
+    fn walking_preset_does_not_snap() {
+        let walking = NavigationControllerConfig::from_preset(NavigationPreset::Walking);
+
+        assert_eq!(walking.snapping, SnappingMode::None);
+        assert!(matches!(
+            walking.wrong_direction_detection,
+            WrongDirectionDetection::None
+        ));
+    }
+}
@@ -0,0 +1,197 @@
+//! Previewing a trip before starting navigation (ex: with a scrubber along the route).
+//!
+//! The preview simulates the user traveling along the route,
+//! so the banners, spoken instructions, camera parameters, and ETA at any point
+//! are exactly what navigation would report there.
+
+use super::models::{NavigationControllerConfig, PowerMode, StepAdvanceMode, TripState};
+use super::NavigationController;
+use crate::arrival_detection::{ArrivalDetection, NearbyArrivalDetection};
+use crate::deviation_detection::{RouteDeviationTracking, WrongDirectionDetection};
+use crate::location_filtering::{AccuracyGating, MotionDerivation, StaleLocationRejection};
+use crate::models::{Route, Speed, UserLocation};
+use crate::tunnel_mode::{travel_along_step, TunnelMode};
+use std::time::Duration;
+
+/// Computes the trip state at any point along a route, without navigating it.
+///
+/// Timestamps are relative to when the state is computed,
+/// as if the user had started the route then and traveled at the expected speed,
+/// so the estimated arrival time is the same at every point of the preview.
+#[derive(uniffi::Object)]
+pub struct RoutePreviewController {
+    controller: NavigationController,
+}
+
+#[uniffi::export]
+impl RoutePreviewController {
+    /// Creates a preview of the route using the navigation config.
+    ///
+    /// Parts of the config which react to the user's actual movements
+    /// (ex: step advance, deviation detection, and location filtering) are disabled,
+    /// as the simulated user always follows the route exactly.
+    #[uniffi::constructor]
+    pub fn new(route: Route, config: NavigationControllerConfig) -> Self {
+        let config = NavigationControllerConfig {
+            step_advance: StepAdvanceMode::Manual,
+            route_deviation_tracking: RouteDeviationTracking::None,
+            wrong_direction_detection: WrongDirectionDetection::None,
+            arrival_detection: ArrivalDetection::StepCompletion,
+            nearby_arrival_detection: NearbyArrivalDetection::None,
+            tunnel_mode: TunnelMode::None,
+            stale_location_rejection: StaleLocationRejection::None,
+            accuracy_gating: AccuracyGating::None,
+            motion_derivation: MotionDerivation::None,
+            power_mode: PowerMode::Foreground,
+            ..config
+        };
+        Self {
+            controller: NavigationController::new(route, config),
+        }
+    }
+
+    /// Returns the trip state at a fraction (from 0 to 1) of the distance along the route.
+    ///
+    /// Fractions outside this range are clamped.
+    /// The trip is never complete, even at the end of the route.
+    pub fn get_state_at(&self, fraction: f64) -> TripState {
+        let route = &self.controller.route;
+        let fraction = if fraction.is_nan() {
+            0.0
+        } else {
+            fraction.clamp(0.0, 1.0)
+        };
+        let target_distance = fraction * route.distance;
+        let start_time = self.controller.config.now();
+        let measurement = self.controller.config.distance_measurement;
+
+        let Some(&start) = route.steps.first().and_then(|step| step.geometry.first()) else {
+            return TripState::Complete;
+        };
+        let mut state = self.controller.get_initial_state(UserLocation {
+            coordinates: start,
+            horizontal_accuracy: 0.0,
+            course_over_ground: None,
+            timestamp: start_time,
+            speed: None,
+            altitude: None,
+            vertical_accuracy: None,
+        });
+        let (mut distance_before_step, mut duration_before_step) = (0.0, 0.0);
+        for (index, step) in route.steps.iter().enumerate() {
+            let is_last_step = index + 1 == route.steps.len();
+            if distance_before_step + step.distance < target_distance && !is_last_step {
+                state = self.controller.advance_to_next_step(&state);
+                distance_before_step += step.distance;
+                duration_before_step += step.duration;
+                continue;
+            }
+
+            let step_fraction = if step.distance > 0.0 {
+                ((target_distance - distance_before_step) / step.distance).clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+            let elapsed = duration_before_step + step_fraction * step.duration;
+            let location = UserLocation {
+                coordinates: travel_along_step(
+                    step.geometry.first().copied().unwrap_or(start),
+                    step,
+                    step_fraction * measurement.length(&step.get_linestring()),
+                    measurement,
+                ),
+                horizontal_accuracy: 0.0,
+                course_over_ground: None,
+                timestamp: start_time + Duration::try_from_secs_f64(elapsed).unwrap_or_default(),
+                speed: (step.duration > 0.0).then(|| Speed {
+                    value: step.distance / step.duration,
+                    accuracy: None,
+                }),
+                altitude: None,
+                vertical_accuracy: None,
+            };
+            return self.controller.update_user_location(location, &state);
+        }
+
+        state
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::RouteStep;
+    use crate::navigation_controller::presets::NavigationPreset;
+    use crate::navigation_controller::test_helpers::{gen_dummy_route_step, gen_route_from_steps};
+    use crate::now;
+    use geo::HaversineLength;
+
+    fn step(start_lng: f64, end_lng: f64) -> RouteStep {
+        let step = gen_dummy_route_step(start_lng, 0.0, end_lng, 0.0);
+        RouteStep {
+            distance: step.get_linestring().haversine_length(),
+            duration: 100.0,
+            ..step
+        }
+    }
+
+    fn preview() -> RoutePreviewController {
+        RoutePreviewController::new(
+            gen_route_from_steps(vec![step(0.0, 0.01), step(0.01, 0.02)]),
+            NavigationControllerConfig::from_preset(NavigationPreset::Driving),
+        )
+    }
+
+    /// Returns the number of remaining steps, the distance remaining,
+    /// and the estimated arrival time (in seconds from now).
+    fn summary(state: &TripState) -> (usize, f64, f64) {
+        let TripState::Navigating {
+            snapped_user_location,
+            remaining_steps,
+            progress,
+            ..
+        } = state
+        else {
+            panic!("Expected state to be navigating");
+        };
+        let elapsed = snapped_user_location
+            .timestamp
+            .duration_since(now())
+            .map_or(0.0, |duration| duration.as_secs_f64());
+        (
+            remaining_steps.len(),
+            progress.distance_remaining,
+            elapsed + progress.duration_remaining,
+        )
+    }
+
+    #[test]
+    fn states_along_the_route() {
+        let preview = preview();
+        let total_distance = preview.controller.route.distance;
+
+        for (fraction, expected_steps) in [(0.0, 2), (0.25, 2), (0.75, 1), (1.0, 1)] {
+            let (steps, distance_remaining, arrival) = summary(&preview.get_state_at(fraction));
+            assert_eq!(steps, expected_steps, "at {fraction}");
+            assert!(
+                (distance_remaining - (1.0 - fraction) * total_distance).abs() < 1.0,
+                "at {fraction}"
+            );
+            // The ETA is the same everywhere along the preview
+            assert!((arrival - 200.0).abs() < 1.0, "at {fraction}");
+        }
+    }
+
+    #[test]
+    fn fractions_are_clamped() {
+        let preview = preview();
+        assert_eq!(
+            summary(&preview.get_state_at(2.0)).1,
+            summary(&preview.get_state_at(1.0)).1
+        );
+        assert_eq!(
+            summary(&preview.get_state_at(-1.0)).1,
+            summary(&preview.get_state_at(f64::NAN)).1
+        );
+    }
+}
@@ -0,0 +1,186 @@
+//! Compact trip state updates, which refer to a shared route rather than copying it.
+//!
+//! A [`TripState`] includes the remaining steps and waypoints of the route,
+//! all of which are copied across the FFI boundary on every location update.
+//! Platforms which only need the route data when it changes (ex: to redraw the route line
+//! or the maneuver banner) can fetch it once from a [`SharedRoute`]
+//! and use [`TripStateUpdate`]s for everything else.
+
+use super::models::{TripProgress, TripState, UpcomingIncident};
+use crate::climbs::UpcomingClimb;
+use crate::deviation_detection::RouteDeviation;
+use crate::location_filtering::PositioningStatus;
+use crate::models::{
+    BoundingBox, DrivingSide, GeographicCoordinate, Route, RouteStep, SpokenInstruction,
+    UserLocation, VisualInstruction, Waypoint,
+};
+use std::sync::Arc;
+
+/// A handle to a route which is shared with the navigation controller, so it is never copied
+/// until one of its accessors is called.
+#[derive(uniffi::Object)]
+pub struct SharedRoute {
+    route: Arc<Route>,
+}
+
+impl SharedRoute {
+    pub(crate) fn new(route: Arc<Route>) -> Self {
+        Self { route }
+    }
+}
+
+#[uniffi::export]
+impl SharedRoute {
+    /// Returns a copy of the full route.
+    pub fn get_route(&self) -> Route {
+        Route::clone(&self.route)
+    }
+
+    /// Returns the geometry of the full route.
+    pub fn get_geometry(&self) -> Vec<GeographicCoordinate> {
+        self.route.geometry.clone()
+    }
+
+    pub fn get_bbox(&self) -> BoundingBox {
+        self.route.bbox
+    }
+
+    /// Returns the total distance of the route, in meters.
+    pub fn get_distance(&self) -> f64 {
+        self.route.distance
+    }
+
+    pub fn get_waypoints(&self) -> Vec<Waypoint> {
+        self.route.waypoints.clone()
+    }
+
+    pub fn get_step_count(&self) -> u64 {
+        self.route.steps.len() as u64
+    }
+
+    /// Returns the step at an index (ex: [`TripStateUpdate::Navigating::current_step_index`]),
+    /// or `None` if the index is out of range.
+    pub fn get_step(&self, index: u64) -> Option<RouteStep> {
+        usize::try_from(index)
+            .ok()
+            .and_then(|index| self.route.steps.get(index))
+            .cloned()
+    }
+
+    /// Returns the steps from an index to the end of the route.
+    pub fn get_steps_from(&self, index: u64) -> Vec<RouteStep> {
+        usize::try_from(index)
+            .ok()
+            .and_then(|index| self.route.steps.get(index..))
+            .map(<[RouteStep]>::to_vec)
+            .unwrap_or_default()
+    }
+}
+
+/// A compact form of a [`TripState`], which refers to the steps and waypoints of a [`SharedRoute`]
+/// by index rather than copying them.
+#[derive(Debug, Clone, PartialEq, uniffi::Enum)]
+pub enum TripStateUpdate {
+    Navigating {
+        snapped_user_location: UserLocation,
+        /// The index of the current step in the route.
+        current_step_index: u64,
+        /// Whether the current step is different from the previous state
+        /// (so any step data held by the platform layer should be refreshed).
+        step_changed: bool,
+        /// The number of waypoints that remain to be visited.
+        ///
+        /// These are always the last waypoints of the route.
+        remaining_waypoint_count: u64,
+        progress: TripProgress,
+        deviation: RouteDeviation,
+        visual_instruction: Option<VisualInstruction>,
+        /// The index of the step following the next maneuver,
+        /// if it comes soon enough after it that both should be shown together.
+        ///
+        /// See [`TripState::Navigating::then_step`].
+        then_step_index: Option<u64>,
+        /// The side of the road on which traffic drives along the current step (if known).
+        driving_side: Option<DrivingSide>,
+        spoken_instruction: Option<SpokenInstruction>,
+        prefetched_spoken_instructions: Vec<SpokenInstruction>,
+        upcoming_incident: Option<UpcomingIncident>,
+        upcoming_climb: Option<UpcomingClimb>,
+        current_road_name: Option<String>,
+        positioning: PositioningStatus,
+    },
+    FreeDrive {
+        user_location: UserLocation,
+        current_road_name: Option<String>,
+        speed_limit: Option<f64>,
+        positioning: PositioningStatus,
+    },
+    Complete,
+}
+
+/// The index of the current step in the route, if navigating.
+fn current_step_index(state: &TripState, route: &Route) -> Option<usize> {
+    match state {
+        TripState::Navigating {
+            remaining_steps, ..
+        } => Some(route.steps.len().saturating_sub(remaining_steps.len())),
+        TripState::FreeDrive { .. } | TripState::Complete => None,
+    }
+}
+
+impl TripStateUpdate {
+    /// Summarizes a state along the route, noting whether the step changed since the previous state.
+    pub(crate) fn new(state: &TripState, previous: &TripState, route: &Route) -> Self {
+        match state {
+            TripState::Navigating {
+                snapped_user_location,
+                remaining_waypoints,
+                progress,
+                deviation,
+                visual_instruction,
+                then_step,
+                spoken_instruction,
+                prefetched_spoken_instructions,
+                upcoming_incident,
+                upcoming_climb,
+                current_road_name,
+                positioning,
+                ..
+            } => {
+                let step_index = current_step_index(state, route).unwrap_or_default();
+                TripStateUpdate::Navigating {
+                    snapped_user_location: *snapped_user_location,
+                    current_step_index: step_index as u64,
+                    step_changed: current_step_index(previous, route) != Some(step_index),
+                    remaining_waypoint_count: remaining_waypoints.len() as u64,
+                    progress: progress.clone(),
+                    deviation: *deviation,
+                    visual_instruction: visual_instruction.clone(),
+                    then_step_index: then_step.is_some().then_some(step_index as u64 + 2),
+                    driving_side: route
+                        .steps
+                        .get(step_index)
+                        .and_then(|step| step.driving_side),
+                    spoken_instruction: spoken_instruction.clone(),
+                    prefetched_spoken_instructions: prefetched_spoken_instructions.clone(),
+                    upcoming_incident: upcoming_incident.clone(),
+                    upcoming_climb: upcoming_climb.clone(),
+                    current_road_name: current_road_name.clone(),
+                    positioning: *positioning,
+                }
+            }
+            TripState::FreeDrive {
+                user_location,
+                current_road_name,
+                speed_limit,
+                positioning,
+            } => TripStateUpdate::FreeDrive {
+                user_location: *user_location,
+                current_road_name: current_road_name.clone(),
+                speed_limit: *speed_limit,
+                positioning: *positioning,
+            },
+            TripState::Complete => TripStateUpdate::Complete,
+        }
+    }
+}
@@ -1,5 +1,13 @@
-use crate::models::{BoundingBox, GeographicCoordinate, Route, RouteStep, Waypoint, WaypointKind};
+use super::models::{NavigationTracking, TripProgress, TripState};
+use crate::deviation_detection::RouteDeviation;
+use crate::location_filtering::PositioningStatus;
+use crate::models::{
+    BoundingBox, CourseOverGround, GeographicCoordinate, Route, RouteStep, Speed, UserLocation,
+    Waypoint, WaypointKind,
+};
 use geo::{BoundingRect, LineString, Point};
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime};
 
 pub fn gen_dummy_route_step(
     start_lng: f64,
@@ -24,6 +32,21 @@ pub fn gen_dummy_route_step(
         instruction: "".to_string(),
         visual_instructions: vec![],
         spoken_instructions: vec![],
+        transit_leg: None,
+        has_tolls: false,
+        is_ferry: false,
+        tunnels: vec![],
+        country_changes: vec![],
+        speed_limit_sign: None,
+        speed_limit_unit: None,
+        driving_side: None,
+        intersections: vec![],
+        junction_views: vec![],
+        has_crosswalk: false,
+        has_stairs: false,
+        has_elevator: false,
+        shields: vec![],
+        extras: HashMap::new(),
     }
 }
 
@@ -34,6 +57,7 @@ pub fn gen_route_from_steps(steps: Vec<RouteStep>) -> Route {
         .collect();
     let linestring = LineString::from_iter(geometry.iter().map(|point| Point::from(*point)));
     let distance = steps.iter().fold(0.0, |acc, step| acc + step.distance);
+    let duration = steps.iter().fold(0.0, |acc, step| acc + step.duration);
     let bbox = linestring.bounding_rect().unwrap();
 
     Route {
@@ -43,6 +67,9 @@ pub fn gen_route_from_steps(steps: Vec<RouteStep>) -> Route {
             ne: GeographicCoordinate::from(bbox.max()),
         },
         distance,
+        duration,
+        weight: None,
+        weight_name: None,
         waypoints: vec![
             // This method cannot be used outside the test configuration,
             // so unwraps are OK.
@@ -56,5 +83,87 @@ pub fn gen_route_from_steps(steps: Vec<RouteStep>) -> Route {
             },
         ],
         steps,
+        elevation_profile: None,
+        incidents: vec![],
+        charging_stops: vec![],
+        destination_details: None,
+        notices: vec![],
+        planned_at: None,
+        extras: HashMap::new(),
+    }
+}
+
+/// A navigating state with no progress, instructions, or alerts.
+///
+/// Tests which depend on other fields can set them by matching on the result.
+pub fn gen_navigating_state(
+    snapped_user_location: UserLocation,
+    remaining_steps: Vec<RouteStep>,
+    deviation: RouteDeviation,
+) -> TripState {
+    TripState::Navigating {
+        snapped_user_location,
+        remaining_steps,
+        remaining_waypoints: vec![],
+        progress: TripProgress {
+            distance_to_next_maneuver: 0.0,
+            distance_remaining: 0.0,
+            duration_remaining: 0.0,
+            consumption: None,
+        },
+        deviation,
+        visual_instruction: None,
+        then_step: None,
+        spoken_instruction: None,
+        prefetched_spoken_instructions: vec![],
+        upcoming_incident: None,
+        upcoming_climb: None,
+        current_road_name: None,
+        tracking: NavigationTracking::default(),
+        positioning: PositioningStatus::Accurate,
+    }
+}
+
+/// A location with 5m accuracy, `seconds` after the Unix epoch.
+pub fn gen_user_location(lng: f64, lat: f64, seconds: u64) -> UserLocation {
+    UserLocation {
+        coordinates: GeographicCoordinate { lng, lat },
+        horizontal_accuracy: 5.0,
+        course_over_ground: None,
+        timestamp: SystemTime::UNIX_EPOCH + Duration::from_secs(seconds),
+        speed: None,
+        altitude: None,
+        vertical_accuracy: None,
+    }
+}
+
+/// Builders for adjusting locations from [`gen_user_location`].
+impl UserLocation {
+    #[must_use]
+    pub fn with_horizontal_accuracy(mut self, horizontal_accuracy: f64) -> Self {
+        self.horizontal_accuracy = horizontal_accuracy;
+        self
+    }
+
+    #[must_use]
+    pub fn with_timestamp(mut self, timestamp: SystemTime) -> Self {
+        self.timestamp = timestamp;
+        self
+    }
+
+    /// Sets the speed, in meters per second (with an unknown accuracy).
+    #[must_use]
+    pub fn with_speed(mut self, speed: f64) -> Self {
+        self.speed = Some(Speed {
+            value: speed,
+            accuracy: None,
+        });
+        self
+    }
+
+    #[must_use]
+    pub fn with_course(mut self, course_over_ground: CourseOverGround) -> Self {
+        self.course_over_ground = Some(course_over_ground);
+        self
     }
 }
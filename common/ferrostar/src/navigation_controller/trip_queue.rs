@@ -0,0 +1,158 @@
+//! Back-to-back trips (ex: for couriers with many stops).
+//!
+//! Each trip is navigated with its own [`NavigationController`],
+//! and the next trip starts from the user's location as soon as the previous one is complete.
+
+use super::models::{BatchLocationUpdate, NavigationControllerConfig, NavigationEvent, TripState};
+use super::NavigationController;
+use crate::models::{Route, UserLocation};
+use std::collections::VecDeque;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+/// Navigates an ordered queue of routes, one after the other.
+///
+/// Like the [`super::SynchronizedNavigationController`], this owns the trip state
+/// and serializes every transition, so it can be shared between threads.
+/// When a trip is complete, the next route in the queue is loaded automatically,
+/// and a [`NavigationEvent::NextTripStarted`] event is reported.
+#[derive(uniffi::Object)]
+pub struct TripQueue {
+    config: NavigationControllerConfig,
+    inner: Mutex<TripQueueState>,
+}
+
+struct TripQueueState {
+    controller: NavigationController,
+    state: TripState,
+    /// The routes of the trips after the current one.
+    upcoming_routes: VecDeque<Route>,
+}
+
+#[uniffi::export]
+impl TripQueue {
+    /// Starts navigating the first route, followed by each of the upcoming routes in order.
+    #[uniffi::constructor]
+    pub fn new(
+        route: Route,
+        upcoming_routes: Vec<Route>,
+        config: NavigationControllerConfig,
+        initial_location: UserLocation,
+    ) -> Self {
+        let controller = NavigationController::new(route, config.clone());
+        let state = controller.get_initial_state(initial_location);
+        Self {
+            config,
+            inner: Mutex::new(TripQueueState {
+                controller,
+                state,
+                upcoming_routes: upcoming_routes.into(),
+            }),
+        }
+    }
+
+    /// Returns the state of the current trip.
+    ///
+    /// This is only [`TripState::Complete`] once the last trip in the queue is complete.
+    pub fn get_state(&self) -> TripState {
+        self.lock().state.clone()
+    }
+
+    /// Returns the number of trips after the current one.
+    pub fn get_upcoming_trip_count(&self) -> u32 {
+        u32::try_from(self.lock().upcoming_routes.len()).unwrap_or(u32::MAX)
+    }
+
+    /// Adds a route to the end of the queue.
+    ///
+    /// If every trip is already complete, the route is not started until the next location update.
+    pub fn enqueue_route(&self, route: Route) {
+        self.lock().upcoming_routes.push_back(route);
+    }
+
+    /// Advances navigation to the next step of the current trip.
+    /// See [`NavigationController::advance_to_next_step`].
+    ///
+    /// If this completes the trip, the next trip starts from the user's last location.
+    pub fn advance_to_next_step(&self) -> BatchLocationUpdate {
+        let mut inner = self.lock();
+        let TripState::Navigating {
+            snapped_user_location,
+            ..
+        } = inner.state
+        else {
+            return BatchLocationUpdate {
+                state: inner.state.clone(),
+                events: vec![],
+            };
+        };
+
+        let next_state = inner.controller.advance_to_next_step(&inner.state);
+        let mut events = inner.controller.events_between(&inner.state, &next_state);
+        inner.state = next_state;
+        self.start_next_trip_if_complete(&mut inner, snapped_user_location, &mut events);
+        BatchLocationUpdate {
+            state: inner.state.clone(),
+            events,
+        }
+    }
+
+    /// Updates the user's current location. See [`NavigationController::update_user_location`].
+    pub fn update_user_location(&self, location: UserLocation) -> BatchLocationUpdate {
+        self.update_user_locations(vec![location])
+    }
+
+    /// Processes a batch of location updates. See [`NavigationController::update_user_locations`].
+    ///
+    /// If a trip is completed partway through the batch,
+    /// the rest of the batch is processed by the next trip.
+    pub fn update_user_locations(&self, locations: Vec<UserLocation>) -> BatchLocationUpdate {
+        let mut locations = locations;
+        locations.sort_by_key(|location| location.timestamp);
+
+        let mut inner = self.lock();
+        let mut events = Vec::new();
+        for location in locations {
+            let update = inner
+                .controller
+                .update_user_locations(vec![location], &inner.state);
+            events.extend(update.events);
+            inner.state = update.state;
+            self.start_next_trip_if_complete(&mut inner, location, &mut events);
+        }
+
+        BatchLocationUpdate {
+            state: inner.state.clone(),
+            events,
+        }
+    }
+}
+
+impl TripQueue {
+    fn lock(&self) -> MutexGuard<'_, TripQueueState> {
+        // The state is only ever replaced as a whole,
+        // so it is still consistent if another thread panicked while holding the lock.
+        self.inner.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Starts the next trip from the location if the current one is complete.
+    fn start_next_trip_if_complete(
+        &self,
+        inner: &mut TripQueueState,
+        location: UserLocation,
+        events: &mut Vec<NavigationEvent>,
+    ) {
+        if inner.state != TripState::Complete {
+            return;
+        }
+        let Some(route) = inner.upcoming_routes.pop_front() else {
+            return;
+        };
+
+        inner.controller = NavigationController::new(route.clone(), self.config.clone());
+        inner.state = inner.controller.get_initial_state(location);
+        events.push(NavigationEvent::NextTripStarted {
+            route,
+            upcoming_trip_count: u32::try_from(inner.upcoming_routes.len()).unwrap_or(u32::MAX),
+        });
+    }
+}
@@ -0,0 +1,651 @@
+//! Offline navigation bundles containing the map tiles along a route.
+//!
+//! This goes a step further than [`crate::tile_prefetch`]: rather than listing the tiles
+//! to download, it extracts them from a local MBTiles or PMTiles (v3) archive
+//! (ex: a region downloaded ahead of time) into a new MBTiles file for the trip.
+//! This lets apps generate fully offline navigation packages on-device.
+//!
+//! Tile data is copied as is, so the bundle uses the same tile format and compression as the archive.
+//!
+//! This module requires the `offline-bundles` feature.
+
+use crate::models::Route;
+use crate::projection::TileCoordinate;
+use crate::tile_prefetch::tiles_along_route;
+use flate2::read::GzDecoder;
+use rusqlite::{params, Connection, OpenFlags, OptionalExtension};
+use std::fmt::Display;
+use std::fs::File;
+use std::io::{ErrorKind, Read, Seek, SeekFrom};
+
+/// The magic bytes at the start of a PMTiles archive.
+const PMTILES_MAGIC: &[u8] = b"PMTiles";
+/// The magic bytes at the start of an SQLite database (and hence an MBTiles archive).
+const SQLITE_MAGIC: &[u8] = b"SQLite format 3\0";
+/// The length of a PMTiles v3 header, in bytes.
+const PMTILES_HEADER_LENGTH: usize = 127;
+/// The maximum depth of PMTiles leaf directories.
+const PMTILES_MAX_DIRECTORY_DEPTH: usize = 3;
+
+#[derive(Debug, thiserror::Error, uniffi::Error)]
+pub enum OfflineBundleError {
+    #[error("Failed to read the tile archive: {error}.")]
+    ArchiveReadError { error: String },
+    #[error("The tile archive is not an MBTiles or PMTiles (v3) file.")]
+    UnsupportedArchive,
+    #[error("The tile archive uses an unsupported compression method ({compression}).")]
+    UnsupportedCompression { compression: u8 },
+    #[error("Failed to write the bundle: {error}.")]
+    BundleWriteError { error: String },
+}
+
+fn read_error(error: impl Display) -> OfflineBundleError {
+    OfflineBundleError::ArchiveReadError {
+        error: error.to_string(),
+    }
+}
+
+fn write_error(error: impl Display) -> OfflineBundleError {
+    OfflineBundleError::BundleWriteError {
+        error: error.to_string(),
+    }
+}
+
+/// A summary of the tiles in an offline bundle.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, uniffi::Record)]
+pub struct OfflineBundleSummary {
+    /// The number of tiles written to the bundle.
+    pub tile_count: u64,
+    /// The number of tiles along the route which are not in the archive
+    /// (ex: over the ocean, or outside the region of the archive).
+    pub missing_tile_count: u64,
+}
+
+/// Extracts the tiles covering a corridor around the route from a local MBTiles or PMTiles archive
+/// into a new MBTiles bundle.
+///
+/// The tiles are selected as in [`tiles_along_route`].
+/// The metadata of the archive is copied to the bundle,
+/// with the zoom range and bounds updated to match the bundle.
+///
+/// Any existing file at `bundle_path` is replaced.
+#[uniffi::export]
+pub fn extract_offline_bundle(
+    route: &Route,
+    archive_path: &str,
+    bundle_path: &str,
+    min_zoom: u8,
+    max_zoom: u8,
+    buffer: f64,
+) -> Result<OfflineBundleSummary, OfflineBundleError> {
+    let mut archive = TileArchive::open(archive_path)?;
+    let mut metadata = archive.metadata()?;
+    let bbox = route.bbox;
+    let tiles = tiles_along_route(route, min_zoom, max_zoom, buffer);
+
+    let mut bundle = create_bundle(bundle_path)?;
+    let transaction = bundle.transaction().map_err(write_error)?;
+    let mut summary = OfflineBundleSummary {
+        tile_count: 0,
+        missing_tile_count: 0,
+    };
+    for tile in &tiles {
+        match archive.tile(*tile)? {
+            Some(data) => {
+                transaction
+                    .execute(
+                        "INSERT INTO tiles (zoom_level, tile_column, tile_row, tile_data) VALUES (?1, ?2, ?3, ?4)",
+                        params![tile.z, tile.x, tms_row(*tile), data],
+                    )
+                    .map_err(write_error)?;
+                summary.tile_count += 1;
+            }
+            None => summary.missing_tile_count += 1,
+        }
+    }
+
+    if let (Some(first), Some(last)) = (tiles.first(), tiles.last()) {
+        metadata.retain(|(name, _)| !matches!(name.as_str(), "minzoom" | "maxzoom" | "bounds"));
+        metadata.push(("minzoom".to_string(), first.z.to_string()));
+        metadata.push(("maxzoom".to_string(), last.z.to_string()));
+        metadata.push((
+            "bounds".to_string(),
+            format!(
+                "{},{},{},{}",
+                bbox.sw.lng, bbox.sw.lat, bbox.ne.lng, bbox.ne.lat
+            ),
+        ));
+    }
+    for (name, value) in &metadata {
+        transaction
+            .execute(
+                "INSERT INTO metadata (name, value) VALUES (?1, ?2)",
+                params![name, value],
+            )
+            .map_err(write_error)?;
+    }
+    transaction.commit().map_err(write_error)?;
+
+    Ok(summary)
+}
+
+/// The row of a tile in the TMS scheme used by MBTiles.
+fn tms_row(tile: TileCoordinate) -> u32 {
+    (1_u32 << tile.z) - 1 - tile.y
+}
+
+/// Creates an empty MBTiles file, replacing any existing file.
+fn create_bundle(path: &str) -> Result<Connection, OfflineBundleError> {
+    match std::fs::remove_file(path) {
+        Err(error) if error.kind() != ErrorKind::NotFound => return Err(write_error(error)),
+        _ => {}
+    }
+    let connection = Connection::open(path).map_err(write_error)?;
+    connection
+        .execute_batch(
+            "CREATE TABLE metadata (name TEXT, value TEXT);
+            CREATE TABLE tiles (zoom_level INTEGER, tile_column INTEGER, tile_row INTEGER, tile_data BLOB);
+            CREATE UNIQUE INDEX tile_index ON tiles (zoom_level, tile_column, tile_row);",
+        )
+        .map_err(write_error)?;
+    Ok(connection)
+}
+
+/// A local archive of map tiles.
+enum TileArchive {
+    MbTiles(Connection),
+    PmTiles(PmTilesArchive),
+}
+
+impl TileArchive {
+    /// Opens an archive, determining its type from its contents.
+    fn open(path: &str) -> Result<Self, OfflineBundleError> {
+        let mut file = File::open(path).map_err(read_error)?;
+        let mut magic = Vec::with_capacity(SQLITE_MAGIC.len());
+        file.by_ref()
+            .take(SQLITE_MAGIC.len() as u64)
+            .read_to_end(&mut magic)
+            .map_err(read_error)?;
+
+        if magic.starts_with(SQLITE_MAGIC) {
+            let connection = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+                .map_err(read_error)?;
+            Ok(TileArchive::MbTiles(connection))
+        } else if magic.starts_with(PMTILES_MAGIC) {
+            PmTilesArchive::new(file).map(TileArchive::PmTiles)
+        } else {
+            Err(OfflineBundleError::UnsupportedArchive)
+        }
+    }
+
+    /// Reads the metadata of the archive as MBTiles metadata (name/value pairs).
+    fn metadata(&mut self) -> Result<Vec<(String, String)>, OfflineBundleError> {
+        match self {
+            TileArchive::MbTiles(connection) => {
+                let mut statement = connection
+                    .prepare("SELECT name, value FROM metadata")
+                    .map_err(read_error)?;
+                let rows = statement
+                    .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+                    .map_err(read_error)?;
+                let metadata = rows.collect::<Result<_, _>>().map_err(read_error);
+                metadata
+            }
+            TileArchive::PmTiles(archive) => archive.metadata(),
+        }
+    }
+
+    /// Reads the data of a tile, if it is in the archive.
+    fn tile(&mut self, tile: TileCoordinate) -> Result<Option<Vec<u8>>, OfflineBundleError> {
+        match self {
+            TileArchive::MbTiles(connection) => connection
+                .query_row(
+                    "SELECT tile_data FROM tiles WHERE zoom_level = ?1 AND tile_column = ?2 AND tile_row = ?3",
+                    params![tile.z, tile.x, tms_row(tile)],
+                    |row| row.get(0),
+                )
+                .optional()
+                .map_err(read_error),
+            TileArchive::PmTiles(archive) => archive.tile(tile),
+        }
+    }
+}
+
+/// An entry in a PMTiles directory.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct DirectoryEntry {
+    tile_id: u64,
+    offset: u64,
+    length: u64,
+    /// The number of consecutive tiles with the same data,
+    /// or zero if the entry points to a leaf directory.
+    run_length: u64,
+}
+
+/// A PMTiles (v3) archive.
+///
+/// See the [specification](https://github.com/protomaps/PMTiles/blob/main/spec/v3/spec.md).
+struct PmTilesArchive {
+    file: File,
+    metadata_offset: u64,
+    metadata_length: u64,
+    leaf_directories_offset: u64,
+    tile_data_offset: u64,
+    internal_compression: u8,
+    tile_type: u8,
+    root_directory: Vec<DirectoryEntry>,
+}
+
+impl PmTilesArchive {
+    fn new(mut file: File) -> Result<Self, OfflineBundleError> {
+        let header = read_at(&mut file, 0, PMTILES_HEADER_LENGTH as u64)?;
+        if header.len() < PMTILES_HEADER_LENGTH || header[7] != 3 {
+            return Err(OfflineBundleError::UnsupportedArchive);
+        }
+        let u64_at = |offset: usize| {
+            let mut bytes = [0; 8];
+            bytes.copy_from_slice(&header[offset..offset + 8]);
+            u64::from_le_bytes(bytes)
+        };
+
+        let mut archive = Self {
+            file,
+            metadata_offset: u64_at(24),
+            metadata_length: u64_at(32),
+            leaf_directories_offset: u64_at(40),
+            tile_data_offset: u64_at(56),
+            internal_compression: header[97],
+            tile_type: header[99],
+            root_directory: vec![],
+        };
+        archive.root_directory = archive.read_directory(u64_at(8), u64_at(16))?;
+        Ok(archive)
+    }
+
+    fn read_directory(
+        &mut self,
+        offset: u64,
+        length: u64,
+    ) -> Result<Vec<DirectoryEntry>, OfflineBundleError> {
+        let data = read_at(&mut self.file, offset, length)?;
+        let data = decompress(data, self.internal_compression)?;
+        parse_directory(&data)
+    }
+
+    fn metadata(&mut self) -> Result<Vec<(String, String)>, OfflineBundleError> {
+        let data = read_at(&mut self.file, self.metadata_offset, self.metadata_length)?;
+        let data = decompress(data, self.internal_compression)?;
+        let json: serde_json::Map<String, serde_json::Value> = if data.is_empty() {
+            serde_json::Map::new()
+        } else {
+            serde_json::from_slice(&data).map_err(read_error)?
+        };
+
+        // MBTiles keeps structured metadata (ex: vector layers) in a single JSON value
+        let (strings, structured): (Vec<_>, Vec<_>) =
+            json.into_iter().partition(|(_, value)| value.is_string());
+        let mut metadata: Vec<_> = strings
+            .into_iter()
+            .filter_map(|(name, value)| Some((name, value.as_str()?.to_string())))
+            .collect();
+        if !structured.is_empty() {
+            let structured: serde_json::Map<_, _> = structured.into_iter().collect();
+            metadata.push((
+                "json".to_string(),
+                serde_json::Value::Object(structured).to_string(),
+            ));
+        }
+        if !metadata.iter().any(|(name, _)| name == "format") {
+            let format = match self.tile_type {
+                1 => Some("pbf"),
+                2 => Some("png"),
+                3 => Some("jpg"),
+                4 => Some("webp"),
+                5 => Some("avif"),
+                _ => None,
+            };
+            if let Some(format) = format {
+                metadata.push(("format".to_string(), format.to_string()));
+            }
+        }
+        Ok(metadata)
+    }
+
+    fn tile(&mut self, tile: TileCoordinate) -> Result<Option<Vec<u8>>, OfflineBundleError> {
+        let tile_id = tile_id(tile);
+        let mut directory = self.root_directory.clone();
+        for _ in 0..=PMTILES_MAX_DIRECTORY_DEPTH {
+            let Some(entry) = find_entry(&directory, tile_id) else {
+                return Ok(None);
+            };
+            if entry.run_length == 0 {
+                directory =
+                    self.read_directory(self.leaf_directories_offset + entry.offset, entry.length)?;
+            } else if tile_id < entry.tile_id + entry.run_length {
+                return read_at(
+                    &mut self.file,
+                    self.tile_data_offset + entry.offset,
+                    entry.length,
+                )
+                .map(Some);
+            } else {
+                return Ok(None);
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Reads `length` bytes from a file, starting at `offset`.
+fn read_at(file: &mut File, offset: u64, length: u64) -> Result<Vec<u8>, OfflineBundleError> {
+    file.seek(SeekFrom::Start(offset)).map_err(read_error)?;
+    let mut data = Vec::new();
+    file.by_ref()
+        .take(length)
+        .read_to_end(&mut data)
+        .map_err(read_error)?;
+    Ok(data)
+}
+
+/// Decompresses data using a PMTiles compression method.
+fn decompress(data: Vec<u8>, compression: u8) -> Result<Vec<u8>, OfflineBundleError> {
+    match compression {
+        // Unknown or none
+        0 | 1 => Ok(data),
+        // gzip
+        2 => {
+            let mut decompressed = Vec::new();
+            GzDecoder::new(data.as_slice())
+                .read_to_end(&mut decompressed)
+                .map_err(read_error)?;
+            Ok(decompressed)
+        }
+        compression => Err(OfflineBundleError::UnsupportedCompression { compression }),
+    }
+}
+
+/// Finds the entry for a tile ID (the last entry starting at or before it).
+fn find_entry(directory: &[DirectoryEntry], tile_id: u64) -> Option<DirectoryEntry> {
+    let index = directory.partition_point(|entry| entry.tile_id <= tile_id);
+    index.checked_sub(1).map(|index| directory[index])
+}
+
+/// Reads an unsigned LEB128 varint.
+fn read_varint(data: &[u8], position: &mut usize) -> Result<u64, OfflineBundleError> {
+    let mut value = 0_u64;
+    for shift in (0..64).step_by(7) {
+        let byte = *data
+            .get(*position)
+            .ok_or_else(|| read_error("unexpected end of directory"))?;
+        *position += 1;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(read_error("invalid varint in directory"))
+}
+
+/// Parses a (decompressed) PMTiles directory.
+fn parse_directory(data: &[u8]) -> Result<Vec<DirectoryEntry>, OfflineBundleError> {
+    let mut position = 0;
+    let entry_count = usize::try_from(read_varint(data, &mut position)?).map_err(read_error)?;
+    // Each entry takes at least four bytes
+    if entry_count > data.len() {
+        return Err(read_error("invalid directory length"));
+    }
+    let mut entries = vec![
+        DirectoryEntry {
+            tile_id: 0,
+            offset: 0,
+            length: 0,
+            run_length: 0,
+        };
+        entry_count
+    ];
+
+    let mut last_tile_id = 0;
+    for entry in &mut entries {
+        last_tile_id += read_varint(data, &mut position)?;
+        entry.tile_id = last_tile_id;
+    }
+    for entry in &mut entries {
+        entry.run_length = read_varint(data, &mut position)?;
+    }
+    for entry in &mut entries {
+        entry.length = read_varint(data, &mut position)?;
+    }
+    for index in 0..entries.len() {
+        let offset = read_varint(data, &mut position)?;
+        // Zero means that the data immediately follows that of the previous entry
+        entries[index].offset = match (offset, index.checked_sub(1)) {
+            (0, Some(previous)) => entries[previous].offset + entries[previous].length,
+            (offset, _) => offset - 1,
+        };
+    }
+    Ok(entries)
+}
+
+/// Computes the PMTiles ID of a tile, which orders tiles by zoom level and then along a Hilbert curve.
+fn tile_id(tile: TileCoordinate) -> u64 {
+    // The number of tiles at all lower zoom levels
+    let base = ((1_u64 << (2 * u32::from(tile.z))) - 1) / 3;
+    let (mut x, mut y) = (u64::from(tile.x), u64::from(tile.y));
+    let mut distance = 0;
+    let mut size = (1_u64 << tile.z) / 2;
+    while size > 0 {
+        let rx = u64::from(x & size > 0);
+        let ry = u64::from(y & size > 0);
+        distance += size * size * ((3 * rx) ^ ry);
+        // Rotate the quadrant
+        if ry == 0 {
+            if rx == 1 {
+                x = size - 1 - (x & (size - 1));
+                y = size - 1 - (y & (size - 1));
+            }
+            std::mem::swap(&mut x, &mut y);
+        }
+        size /= 2;
+    }
+    base + distance
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::GeographicCoordinate;
+    use crate::navigation_controller::test_helpers::{gen_dummy_route_step, gen_route_from_steps};
+    use crate::projection::{coordinate_to_tile, TileScheme};
+    use std::io::Write;
+
+    fn write_varint(data: &mut Vec<u8>, mut value: u64) {
+        while value >= 0x80 {
+            data.push((value as u8 & 0x7f) | 0x80);
+            value >>= 7;
+        }
+        data.push(value as u8);
+    }
+
+    /// Writes an uncompressed PMTiles archive with a single root directory.
+    fn write_pmtiles(path: &std::path::Path, tiles: &[(TileCoordinate, &[u8])]) {
+        let mut tiles: Vec<_> = tiles
+            .iter()
+            .map(|(tile, data)| (tile_id(*tile), *data))
+            .collect();
+        tiles.sort_by_key(|(tile_id, _)| *tile_id);
+
+        let mut directory = vec![];
+        write_varint(&mut directory, tiles.len() as u64);
+        let mut last_tile_id = 0;
+        for (tile_id, _) in &tiles {
+            write_varint(&mut directory, tile_id - last_tile_id);
+            last_tile_id = *tile_id;
+        }
+        for _ in &tiles {
+            write_varint(&mut directory, 1);
+        }
+        for (_, data) in &tiles {
+            write_varint(&mut directory, data.len() as u64);
+        }
+        for (index, _) in tiles.iter().enumerate() {
+            write_varint(&mut directory, u64::from(index == 0));
+        }
+        let metadata = br#"{"name":"Test","vector_layers":[{"id":"roads"}]}"#;
+        let tile_data: Vec<u8> = tiles.iter().flat_map(|(_, data)| data.to_vec()).collect();
+
+        let root_offset = PMTILES_HEADER_LENGTH as u64;
+        let metadata_offset = root_offset + directory.len() as u64;
+        let tile_data_offset = metadata_offset + metadata.len() as u64;
+        let mut header = vec![0; PMTILES_HEADER_LENGTH];
+        header[..7].copy_from_slice(PMTILES_MAGIC);
+        header[7] = 3;
+        for (offset, value) in [
+            (8, root_offset),
+            (16, directory.len() as u64),
+            (24, metadata_offset),
+            (32, metadata.len() as u64),
+            (40, tile_data_offset),
+            (56, tile_data_offset),
+            (64, tile_data.len() as u64),
+        ] {
+            header[offset..offset + 8].copy_from_slice(&value.to_le_bytes());
+        }
+        header[97] = 1;
+        header[98] = 1;
+        header[99] = 1;
+
+        let mut file = File::create(path).unwrap();
+        for part in [&header[..], &directory, metadata, &tile_data] {
+            file.write_all(part).unwrap();
+        }
+    }
+
+    #[test]
+    fn hilbert_tile_ids() {
+        let id = |z, x, y| tile_id(TileCoordinate { x, y, z });
+        assert_eq!(id(0, 0, 0), 0);
+        assert_eq!(id(1, 0, 0), 1);
+        assert_eq!(id(1, 0, 1), 2);
+        assert_eq!(id(1, 1, 1), 3);
+        assert_eq!(id(1, 1, 0), 4);
+        assert_eq!(id(2, 0, 0), 5);
+        assert_eq!(id(2, 3, 0), 20);
+    }
+
+    #[test]
+    fn varint_directories() {
+        let mut data = vec![];
+        // Two entries: tile IDs 5 and 7, run lengths 1 and 2, lengths 300 and 10,
+        // the second immediately following the first
+        for value in [2, 5, 2, 1, 2, 300, 10, 1, 0] {
+            write_varint(&mut data, value);
+        }
+        let entries = parse_directory(&data).unwrap();
+        assert_eq!(
+            entries,
+            vec![
+                DirectoryEntry {
+                    tile_id: 5,
+                    offset: 0,
+                    length: 300,
+                    run_length: 1
+                },
+                DirectoryEntry {
+                    tile_id: 7,
+                    offset: 300,
+                    length: 10,
+                    run_length: 2
+                },
+            ]
+        );
+        assert_eq!(find_entry(&entries, 8).map(|entry| entry.tile_id), Some(7));
+        assert_eq!(find_entry(&entries, 4), None);
+        assert!(parse_directory(&[2, 5]).is_err());
+    }
+
+    #[test]
+    fn extracts_pmtiles_corridor() {
+        let directory = std::env::temp_dir().join(format!("ferrostar-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let archive_path = directory.join("region.pmtiles");
+        let bundle_path = directory.join("trip.mbtiles");
+
+        let route = gen_route_from_steps(vec![gen_dummy_route_step(0.0, 0.001, 0.05, 0.001)]);
+        let on_route = coordinate_to_tile(
+            GeographicCoordinate {
+                lat: 0.001,
+                lng: 0.03,
+            },
+            14,
+            TileScheme::Xyz,
+        );
+        let elsewhere = TileCoordinate {
+            x: 100,
+            y: 100,
+            z: 14,
+        };
+        write_pmtiles(&archive_path, &[(on_route, b"road"), (elsewhere, b"sea")]);
+
+        let summary = extract_offline_bundle(
+            &route,
+            &archive_path.to_string_lossy(),
+            &bundle_path.to_string_lossy(),
+            14,
+            14,
+            0.0,
+        )
+        .unwrap();
+        assert_eq!(
+            summary,
+            OfflineBundleSummary {
+                tile_count: 1,
+                missing_tile_count: 2
+            }
+        );
+
+        let bundle = Connection::open(&bundle_path).unwrap();
+        let data: Vec<u8> = bundle
+            .query_row(
+                "SELECT tile_data FROM tiles WHERE zoom_level = ?1 AND tile_column = ?2 AND tile_row = ?3",
+                params![on_route.z, on_route.x, tms_row(on_route)],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(data, b"road");
+        let metadata = |name: &str| -> String {
+            bundle
+                .query_row(
+                    "SELECT value FROM metadata WHERE name = ?1",
+                    params![name],
+                    |row| row.get(0),
+                )
+                .unwrap()
+        };
+        assert_eq!(metadata("name"), "Test");
+        assert_eq!(metadata("format"), "pbf");
+        assert_eq!(metadata("maxzoom"), "14");
+        assert_eq!(metadata("json"), r#"{"vector_layers":[{"id":"roads"}]}"#);
+
+        // The bundle is itself a valid archive
+        let mut bundle_archive = TileArchive::open(&bundle_path.to_string_lossy()).unwrap();
+        assert_eq!(
+            bundle_archive.tile(on_route).unwrap(),
+            Some(b"road".to_vec())
+        );
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn rejects_unknown_archives() {
+        let path = std::env::temp_dir().join(format!("ferrostar-{}.txt", uuid::Uuid::new_v4()));
+        std::fs::write(&path, "not a tile archive").unwrap();
+
+        assert!(matches!(
+            TileArchive::open(&path.to_string_lossy()),
+            Err(OfflineBundleError::UnsupportedArchive)
+        ));
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
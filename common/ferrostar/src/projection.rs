@@ -0,0 +1,283 @@
+//! Conversions between geographic coordinates and the Web Mercator projection.
+//!
+//! Map rendering (ex: camera framing and the vanishing of the traveled route line)
+//! works in projected coordinates rather than latitude and longitude.
+//! These helpers keep the formulas in one place, so that every platform projects the same way.
+//!
+//! Three coordinate spaces are supported:
+//!
+//! - Web Mercator (EPSG:3857), in meters from the intersection of the equator and prime meridian.
+//! - World coordinates, normalized so that the whole world spans `0..=1` on each axis,
+//!   with the origin in the north-west corner (like map tiles).
+//! - Tile coordinates at a zoom level, in either the XYZ or TMS scheme.
+
+use crate::models::GeographicCoordinate;
+#[cfg(feature = "wasm-js")]
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+
+/// The radius of the sphere used by the Web Mercator projection, in meters.
+pub const WEB_MERCATOR_RADIUS: f64 = 6_378_137.0;
+
+/// The maximum latitude which can be projected, in degrees.
+///
+/// This makes the projection of the world square.
+pub const MAX_WEB_MERCATOR_LATITUDE: f64 = 85.051_128_779_806_59;
+
+/// The maximum zoom level of tile coordinates.
+pub(crate) const MAX_ZOOM: u8 = 30;
+
+/// A location in the Web Mercator projection (EPSG:3857), in meters.
+#[derive(Debug, Copy, Clone, PartialEq, uniffi::Record)]
+#[cfg_attr(feature = "wasm-js", derive(Serialize, Deserialize))]
+pub struct WebMercatorCoordinate {
+    /// The distance east of the prime meridian.
+    pub x: f64,
+    /// The distance north of the equator.
+    pub y: f64,
+}
+
+/// A location in normalized world space.
+///
+/// The world spans `0..=1` on each axis, with `x` increasing to the east
+/// and `y` increasing to the south (from the north-west corner).
+#[derive(Debug, Copy, Clone, PartialEq, uniffi::Record)]
+#[cfg_attr(feature = "wasm-js", derive(Serialize, Deserialize))]
+pub struct WorldCoordinate {
+    pub x: f64,
+    pub y: f64,
+}
+
+/// Determines how map tile rows are numbered.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, uniffi::Enum)]
+#[cfg_attr(feature = "wasm-js", derive(Serialize, Deserialize))]
+pub enum TileScheme {
+    /// Rows are numbered from the north (as used by most web maps).
+    Xyz,
+    /// Rows are numbered from the south (as used by MBTiles).
+    Tms,
+}
+
+/// The address of a map tile.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, uniffi::Record)]
+#[cfg_attr(feature = "wasm-js", derive(Serialize, Deserialize))]
+pub struct TileCoordinate {
+    pub x: u32,
+    pub y: u32,
+    pub z: u8,
+}
+
+/// Projects a coordinate to Web Mercator.
+///
+/// Latitudes beyond [`MAX_WEB_MERCATOR_LATITUDE`] are clamped.
+#[uniffi::export]
+pub fn coordinate_to_web_mercator(coordinate: GeographicCoordinate) -> WebMercatorCoordinate {
+    let lat = coordinate
+        .lat
+        .clamp(-MAX_WEB_MERCATOR_LATITUDE, MAX_WEB_MERCATOR_LATITUDE)
+        .to_radians();
+    WebMercatorCoordinate {
+        x: WEB_MERCATOR_RADIUS * coordinate.lng.to_radians(),
+        y: WEB_MERCATOR_RADIUS * (PI / 4.0 + lat / 2.0).tan().ln(),
+    }
+}
+
+/// Converts a Web Mercator location to a coordinate.
+#[uniffi::export]
+pub fn web_mercator_to_coordinate(coordinate: WebMercatorCoordinate) -> GeographicCoordinate {
+    GeographicCoordinate {
+        lat: (2.0 * (coordinate.y / WEB_MERCATOR_RADIUS).exp().atan() - PI / 2.0).to_degrees(),
+        lng: (coordinate.x / WEB_MERCATOR_RADIUS).to_degrees(),
+    }
+}
+
+/// Projects a coordinate to normalized world space.
+///
+/// Latitudes beyond [`MAX_WEB_MERCATOR_LATITUDE`] are clamped.
+#[uniffi::export]
+pub fn coordinate_to_world(coordinate: GeographicCoordinate) -> WorldCoordinate {
+    let projected = coordinate_to_web_mercator(coordinate);
+    let circumference = 2.0 * PI * WEB_MERCATOR_RADIUS;
+    WorldCoordinate {
+        x: 0.5 + projected.x / circumference,
+        y: 0.5 - projected.y / circumference,
+    }
+}
+
+/// Converts a location in normalized world space to a coordinate.
+#[uniffi::export]
+pub fn world_to_coordinate(coordinate: WorldCoordinate) -> GeographicCoordinate {
+    let circumference = 2.0 * PI * WEB_MERCATOR_RADIUS;
+    web_mercator_to_coordinate(WebMercatorCoordinate {
+        x: (coordinate.x - 0.5) * circumference,
+        y: (0.5 - coordinate.y) * circumference,
+    })
+}
+
+/// Finds the tile containing a coordinate at a zoom level (which is clamped to 30).
+#[uniffi::export]
+pub fn coordinate_to_tile(
+    coordinate: GeographicCoordinate,
+    zoom: u8,
+    scheme: TileScheme,
+) -> TileCoordinate {
+    let z = zoom.min(MAX_ZOOM);
+    let world = coordinate_to_world(coordinate);
+    let y = tile_index(world.y, z);
+    TileCoordinate {
+        x: tile_index(world.x, z),
+        y: match scheme {
+            TileScheme::Xyz => y,
+            TileScheme::Tms => (1_u32 << z) - 1 - y,
+        },
+        z,
+    }
+}
+
+/// Finds the index of the column (or XYZ row) of tiles containing a world space position
+/// at a zoom level (which must not exceed 30).
+pub(crate) fn tile_index(value: f64, zoom: u8) -> u32 {
+    let max_index = (1_u32 << zoom) - 1;
+    // The eastern and southern edges of the world belong to the last tile
+    let index = (value * f64::from(1_u32 << zoom)).floor().max(0.0) as u32;
+    index.min(max_index)
+}
+
+/// Finds the north-west corner of a tile.
+#[uniffi::export]
+pub fn tile_to_coordinate(tile: TileCoordinate, scheme: TileScheme) -> GeographicCoordinate {
+    let tile_count = f64::from(1_u32 << tile.z.min(MAX_ZOOM));
+    let y = match scheme {
+        TileScheme::Xyz => f64::from(tile.y),
+        // Rows are numbered from the south, so convert to the XYZ row
+        TileScheme::Tms => tile_count - 1.0 - f64::from(tile.y),
+    };
+    world_to_coordinate(WorldCoordinate {
+        x: f64::from(tile.x) / tile_count,
+        y: y / tile_count,
+    })
+}
+
+/// The number of meters on the ground per Web Mercator meter at a latitude.
+///
+/// Web Mercator stretches distances away from the equator,
+/// so this is used to convert projected lengths to real ones (and vice versa).
+#[uniffi::export]
+pub fn web_mercator_scale_factor(latitude: f64) -> f64 {
+    latitude
+        .clamp(-MAX_WEB_MERCATOR_LATITUDE, MAX_WEB_MERCATOR_LATITUDE)
+        .to_radians()
+        .cos()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOLERANCE: f64 = 1e-9;
+
+    fn assert_coordinates_eq(a: GeographicCoordinate, b: GeographicCoordinate) {
+        assert!(
+            (a.lat - b.lat).abs() < TOLERANCE && (a.lng - b.lng).abs() < TOLERANCE,
+            "{a:?} != {b:?}"
+        );
+    }
+
+    #[test]
+    fn web_mercator_round_trip() {
+        let coordinate = GeographicCoordinate {
+            lat: 60.534_716,
+            lng: -149.543_469,
+        };
+        let projected = coordinate_to_web_mercator(coordinate);
+        assert!((projected.x - -16_647_102.8).abs() < 0.1);
+        assert!((projected.y - 8_519_761.0).abs() < 0.1);
+        assert_coordinates_eq(web_mercator_to_coordinate(projected), coordinate);
+    }
+
+    #[test]
+    fn world_corners() {
+        let origin = coordinate_to_world(GeographicCoordinate { lat: 0.0, lng: 0.0 });
+        assert!((origin.x - 0.5).abs() < TOLERANCE);
+        assert!((origin.y - 0.5).abs() < TOLERANCE);
+
+        // Latitudes beyond the limit are clamped
+        let north_west = coordinate_to_world(GeographicCoordinate {
+            lat: 89.0,
+            lng: -180.0,
+        });
+        assert!(north_west.x.abs() < TOLERANCE);
+        assert!(north_west.y.abs() < TOLERANCE);
+
+        assert_coordinates_eq(
+            world_to_coordinate(WorldCoordinate { x: 1.0, y: 1.0 }),
+            GeographicCoordinate {
+                lat: -MAX_WEB_MERCATOR_LATITUDE,
+                lng: 180.0,
+            },
+        );
+    }
+
+    #[test]
+    fn tiles() {
+        let berlin = GeographicCoordinate {
+            lat: 52.52,
+            lng: 13.405,
+        };
+        assert_eq!(
+            coordinate_to_tile(berlin, 10, TileScheme::Xyz),
+            TileCoordinate {
+                x: 550,
+                y: 335,
+                z: 10
+            }
+        );
+        assert_eq!(
+            coordinate_to_tile(berlin, 10, TileScheme::Tms),
+            TileCoordinate {
+                x: 550,
+                y: 688,
+                z: 10
+            }
+        );
+        assert_eq!(
+            coordinate_to_tile(
+                GeographicCoordinate {
+                    lat: -90.0,
+                    lng: 180.0
+                },
+                2,
+                TileScheme::Xyz
+            ),
+            TileCoordinate { x: 3, y: 3, z: 2 }
+        );
+
+        let corner = tile_to_coordinate(
+            TileCoordinate {
+                x: 550,
+                y: 335,
+                z: 10,
+            },
+            TileScheme::Xyz,
+        );
+        assert_coordinates_eq(
+            tile_to_coordinate(
+                TileCoordinate {
+                    x: 550,
+                    y: 688,
+                    z: 10,
+                },
+                TileScheme::Tms,
+            ),
+            corner,
+        );
+        assert!(corner.lat > berlin.lat && corner.lng < berlin.lng);
+        assert_eq!(coordinate_to_tile(corner, 10, TileScheme::Xyz).x, 550);
+    }
+
+    #[test]
+    fn scale_factor() {
+        assert!((web_mercator_scale_factor(0.0) - 1.0).abs() < TOLERANCE);
+        assert!((web_mercator_scale_factor(60.0) - 0.5).abs() < TOLERANCE);
+    }
+}
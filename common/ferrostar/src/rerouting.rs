@@ -0,0 +1,270 @@
+//! Rate limiting for reroute requests.
+//!
+//! Apps request a new route when the navigation state reports that the user is off route.
+//! With a flaky GPS signal, that can flicker on and off with every location update,
+//! so apps should ask a [`RerouteThrottle`] before each request,
+//! to avoid spamming the routing backend (and the user with route changes).
+
+use crate::algorithms::DistanceMeasurement;
+use crate::deviation_detection::RouteDeviation;
+use crate::models::{GeographicCoordinate, UserLocation};
+use crate::navigation_controller::models::TripState;
+use geo::Point;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::SystemTime;
+
+/// Determines when reroute requests may be made.
+#[derive(Debug, Copy, Clone, PartialEq, uniffi::Record)]
+pub struct ReroutePolicy {
+    /// The number of consecutive off route location updates required before rerouting.
+    pub min_consecutive_off_route_fixes: u32,
+    /// The minimum time between reroute requests, in seconds.
+    pub min_interval: f64,
+    /// The minimum distance the user must move between reroute requests, in meters.
+    ///
+    /// This keeps a user who is stopped off route (ex: in a parking lot)
+    /// from requesting the same route over and over.
+    pub min_distance: f64,
+    /// The maximum number of reroute requests in flight at once.
+    pub max_in_flight: u32,
+    /// How the distance moved between reroute requests is measured.
+    ///
+    /// This should match [`crate::navigation_controller::models::NavigationControllerConfig::distance_measurement`].
+    pub distance_measurement: DistanceMeasurement,
+}
+
+impl Default for ReroutePolicy {
+    fn default() -> Self {
+        Self {
+            min_consecutive_off_route_fixes: 2,
+            min_interval: 10.0,
+            min_distance: 20.0,
+            max_in_flight: 1,
+            distance_measurement: DistanceMeasurement::Haversine,
+        }
+    }
+}
+
+/// Creates a reroute policy with sensible defaults for driving.
+#[uniffi::export]
+pub fn create_default_reroute_policy() -> ReroutePolicy {
+    ReroutePolicy::default()
+}
+
+/// The outcome of asking whether to reroute.
+#[derive(Debug, Copy, Clone, PartialEq, uniffi::Enum)]
+pub enum RerouteDecision {
+    /// A reroute should be requested now.
+    ///
+    /// Call [`RerouteThrottle::finish_request`] once the request completes (or fails).
+    Request,
+    /// The user is not off route.
+    NotOffRoute,
+    /// The user has not been off route for enough consecutive location updates yet.
+    AwaitingConfirmation { consecutive_off_route_fixes: u32 },
+    /// Too little time has passed since the last request.
+    TooSoon,
+    /// The user has not moved far enough since the last request.
+    TooClose,
+    /// The maximum number of requests are already in flight.
+    TooManyInFlight,
+}
+
+#[derive(Debug, Default)]
+struct ThrottleState {
+    consecutive_off_route_fixes: u32,
+    last_fix_timestamp: Option<SystemTime>,
+    last_request: Option<(SystemTime, GeographicCoordinate)>,
+    in_flight: u32,
+}
+
+/// Decides when to request a new route, according to a [`ReroutePolicy`].
+///
+/// Call [`RerouteThrottle::check`] after each location update.
+/// Times are measured between location timestamps, so replayed traces behave as they did live.
+#[derive(uniffi::Object)]
+pub struct RerouteThrottle {
+    policy: ReroutePolicy,
+    state: Mutex<ThrottleState>,
+}
+
+#[uniffi::export]
+impl RerouteThrottle {
+    #[uniffi::constructor]
+    pub fn new(policy: ReroutePolicy) -> Self {
+        Self {
+            policy,
+            state: Mutex::new(ThrottleState::default()),
+        }
+    }
+
+    /// Determines whether to request a new route,
+    /// given the (raw) location which was just processed and the resulting trip state.
+    ///
+    /// A [`RerouteDecision::Request`] counts as an in-flight request.
+    /// Checking the same location more than once does not count as another off route fix.
+    pub fn check(&self, location: UserLocation, trip_state: &TripState) -> RerouteDecision {
+        let mut state = self.lock_state();
+        let is_off_route = matches!(
+            trip_state,
+            TripState::Navigating {
+                deviation: RouteDeviation::OffRoute { .. },
+                ..
+            }
+        );
+        if !is_off_route {
+            state.consecutive_off_route_fixes = 0;
+            state.last_fix_timestamp = None;
+            return RerouteDecision::NotOffRoute;
+        }
+
+        if state.last_fix_timestamp != Some(location.timestamp) {
+            state.last_fix_timestamp = Some(location.timestamp);
+            state.consecutive_off_route_fixes = state.consecutive_off_route_fixes.saturating_add(1);
+        }
+        if state.consecutive_off_route_fixes < self.policy.min_consecutive_off_route_fixes {
+            return RerouteDecision::AwaitingConfirmation {
+                consecutive_off_route_fixes: state.consecutive_off_route_fixes,
+            };
+        }
+        if state.in_flight >= self.policy.max_in_flight {
+            return RerouteDecision::TooManyInFlight;
+        }
+        if let Some((timestamp, coordinates)) = state.last_request {
+            let elapsed = location
+                .timestamp
+                .duration_since(timestamp)
+                .map_or(0.0, |elapsed| elapsed.as_secs_f64());
+            if elapsed < self.policy.min_interval {
+                return RerouteDecision::TooSoon;
+            }
+            let distance = self.policy.distance_measurement.distance(
+                &Point::from(coordinates),
+                &Point::from(location.coordinates),
+            );
+            if distance < self.policy.min_distance {
+                return RerouteDecision::TooClose;
+            }
+        }
+
+        state.last_request = Some((location.timestamp, location.coordinates));
+        state.in_flight += 1;
+        RerouteDecision::Request
+    }
+
+    /// Records that a requested reroute has completed (whether or not it succeeded).
+    pub fn finish_request(&self) {
+        let mut state = self.lock_state();
+        state.in_flight = state.in_flight.saturating_sub(1);
+    }
+
+    /// Forgets all previous requests (ex: when starting a new trip).
+    pub fn reset(&self) {
+        *self.lock_state() = ThrottleState::default();
+    }
+}
+
+impl RerouteThrottle {
+    fn lock_state(&self) -> MutexGuard<'_, ThrottleState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::navigation_controller::test_helpers::{gen_navigating_state, gen_user_location};
+
+    /// Checks a location at which the user is off route.
+    fn check_off_route(throttle: &RerouteThrottle, location: UserLocation) -> RerouteDecision {
+        throttle.check(
+            location,
+            &gen_navigating_state(
+                location,
+                vec![],
+                RouteDeviation::OffRoute {
+                    deviation_from_route_line: 50.0,
+                },
+            ),
+        )
+    }
+
+    #[test]
+    fn requires_consecutive_off_route_fixes() {
+        let throttle = RerouteThrottle::new(ReroutePolicy::default());
+        assert_eq!(
+            check_off_route(&throttle, gen_user_location(0.0, 0.0, 0)),
+            RerouteDecision::AwaitingConfirmation {
+                consecutive_off_route_fixes: 1
+            }
+        );
+        // The same fix is only counted once
+        assert_eq!(
+            check_off_route(&throttle, gen_user_location(0.0, 0.0, 0)),
+            RerouteDecision::AwaitingConfirmation {
+                consecutive_off_route_fixes: 1
+            }
+        );
+        // Back on route starts the count over
+        assert_eq!(
+            throttle.check(
+                gen_user_location(0.0, 0.0, 1),
+                &gen_navigating_state(
+                    gen_user_location(0.0, 0.0, 1),
+                    vec![],
+                    RouteDeviation::NoDeviation
+                )
+            ),
+            RerouteDecision::NotOffRoute
+        );
+        assert_eq!(
+            check_off_route(&throttle, gen_user_location(0.0, 0.0, 2)),
+            RerouteDecision::AwaitingConfirmation {
+                consecutive_off_route_fixes: 1
+            }
+        );
+        assert_eq!(
+            check_off_route(&throttle, gen_user_location(0.0, 0.0, 3)),
+            RerouteDecision::Request
+        );
+    }
+
+    #[test]
+    fn limits_request_rate() {
+        let throttle = RerouteThrottle::new(ReroutePolicy {
+            min_consecutive_off_route_fixes: 1,
+            ..ReroutePolicy::default()
+        });
+        assert_eq!(
+            check_off_route(&throttle, gen_user_location(0.0, 0.0, 0)),
+            RerouteDecision::Request
+        );
+        assert_eq!(
+            check_off_route(&throttle, gen_user_location(0.0, 0.0, 1)),
+            RerouteDecision::TooManyInFlight
+        );
+
+        throttle.finish_request();
+        assert_eq!(
+            check_off_route(&throttle, gen_user_location(0.001, 0.0, 5)),
+            RerouteDecision::TooSoon
+        );
+        // Waiting in place (about 10m away)
+        assert_eq!(
+            check_off_route(&throttle, gen_user_location(0.0001, 0.0, 20)),
+            RerouteDecision::TooClose
+        );
+        // About 110m away
+        assert_eq!(
+            check_off_route(&throttle, gen_user_location(0.001, 0.0, 21)),
+            RerouteDecision::Request
+        );
+
+        throttle.reset();
+        throttle.finish_request();
+        assert_eq!(
+            check_off_route(&throttle, gen_user_location(0.001, 0.0, 22)),
+            RerouteDecision::Request
+        );
+    }
+}
@@ -0,0 +1,140 @@
+//! The name of the road the user is currently traveling on (ex: for a "current road" label).
+//!
+//! Route steps usually include a road name, but it is often missing on unnamed roads
+//! like footpaths, parking lots, and service roads.
+//! When it is, apps may supply a [`RoadNameGeocoder`] to look the name up instead.
+
+use crate::models::{GeographicCoordinate, RouteStep};
+use std::sync::Arc;
+
+/// Determines how the current road name is reported in [`crate::navigation_controller::models::TripState::Navigating`]
+/// (and [`crate::navigation_controller::models::TripState::FreeDrive`]).
+#[derive(Clone, uniffi::Enum)]
+pub enum RoadNameLookup {
+    /// The current road name is not reported.
+    None,
+    /// Uses the road name of the current step.
+    StepName,
+    /// Uses the road name of the current step, or a name from the geocoder when it is missing.
+    StepNameWithGeocoderFallback { geocoder: Arc<dyn RoadNameGeocoder> },
+}
+
+impl RoadNameLookup {
+    /// Determines the name of the road at the user's (snapped) location.
+    pub(crate) fn current_road_name(
+        &self,
+        current_step: &RouteStep,
+        coordinate: GeographicCoordinate,
+    ) -> Option<String> {
+        let step_name = current_step
+            .road_name
+            .as_ref()
+            .filter(|name| !name.trim().is_empty())
+            .cloned();
+
+        match self {
+            RoadNameLookup::None => None,
+            RoadNameLookup::StepName => step_name,
+            RoadNameLookup::StepNameWithGeocoderFallback { geocoder } => step_name.or_else(|| {
+                geocoder
+                    .road_name(coordinate)
+                    .filter(|name| !name.trim().is_empty())
+            }),
+        }
+    }
+
+    /// Determines the name of the road at a location when there is no route (ex: in free drive).
+    ///
+    /// Only the geocoder can be used, as there is no current step.
+    pub(crate) fn road_name_at(&self, coordinate: GeographicCoordinate) -> Option<String> {
+        match self {
+            RoadNameLookup::None | RoadNameLookup::StepName => None,
+            RoadNameLookup::StepNameWithGeocoderFallback { geocoder } => geocoder
+                .road_name(coordinate)
+                .filter(|name| !name.trim().is_empty()),
+        }
+    }
+}
+
+/// Looks up the name of the road at a location (ex: by reverse geocoding).
+///
+/// This is called synchronously during location updates whenever the current step has no road name,
+/// so implementations must return quickly.
+/// Network-based implementations should return the most recent name they have (or `None`)
+/// and refresh it in the background
+/// (ex: with a [`crate::geocoding::GeocodingAdapter`] once the user has moved far enough).
+#[uniffi::export(with_foreign)]
+pub trait RoadNameGeocoder: Send + Sync {
+    /// Returns the name of the road at the coordinate, if known.
+    fn road_name(&self, coordinate: GeographicCoordinate) -> Option<String>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::navigation_controller::test_helpers::gen_dummy_route_step;
+
+    struct StaticGeocoder(Option<String>);
+
+    impl RoadNameGeocoder for StaticGeocoder {
+        fn road_name(&self, _coordinate: GeographicCoordinate) -> Option<String> {
+            self.0.clone()
+        }
+    }
+
+    fn step(road_name: Option<&str>) -> RouteStep {
+        RouteStep {
+            road_name: road_name.map(ToString::to_string),
+            ..gen_dummy_route_step(0.0, 0.0, 0.001, 0.0)
+        }
+    }
+
+    const COORDINATE: GeographicCoordinate = GeographicCoordinate { lat: 0.0, lng: 0.0 };
+
+    #[test]
+    fn step_names() {
+        let lookup = RoadNameLookup::StepName;
+        assert_eq!(
+            lookup.current_road_name(&step(Some("Main Street")), COORDINATE),
+            Some("Main Street".to_string())
+        );
+        assert_eq!(lookup.current_road_name(&step(Some("")), COORDINATE), None);
+        assert_eq!(lookup.current_road_name(&step(None), COORDINATE), None);
+        assert_eq!(
+            RoadNameLookup::None.current_road_name(&step(Some("Main Street")), COORDINATE),
+            None
+        );
+    }
+
+    #[test]
+    fn geocoder_fallback() {
+        let lookup = RoadNameLookup::StepNameWithGeocoderFallback {
+            geocoder: Arc::new(StaticGeocoder(Some("Riverside Path".to_string()))),
+        };
+        assert_eq!(
+            lookup.current_road_name(&step(Some("Main Street")), COORDINATE),
+            Some("Main Street".to_string())
+        );
+        assert_eq!(
+            lookup.current_road_name(&step(Some(" ")), COORDINATE),
+            Some("Riverside Path".to_string())
+        );
+
+        let lookup = RoadNameLookup::StepNameWithGeocoderFallback {
+            geocoder: Arc::new(StaticGeocoder(None)),
+        };
+        assert_eq!(lookup.current_road_name(&step(None), COORDINATE), None);
+    }
+
+    #[test]
+    fn road_names_without_a_step() {
+        let lookup = RoadNameLookup::StepNameWithGeocoderFallback {
+            geocoder: Arc::new(StaticGeocoder(Some("Riverside Path".to_string()))),
+        };
+        assert_eq!(
+            lookup.road_name_at(COORDINATE),
+            Some("Riverside Path".to_string())
+        );
+        assert_eq!(RoadNameLookup::StepName.road_name_at(COORDINATE), None);
+    }
+}
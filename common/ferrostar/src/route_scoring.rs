@@ -0,0 +1,290 @@
+//! Ranking of alternative routes.
+//!
+//! Most routing backends can return several routes for the same request.
+//! Rather than always taking the first one, apps can score each route
+//! against the preferences of the user (ex: avoiding tolls) and pick the lowest score.
+//!
+//! Scores are expressed in seconds, so every weight converts a property of the route
+//! into an equivalent amount of travel time.
+
+use crate::models::{GeographicCoordinate, IncidentKind, Route};
+use geo::{HaversineDistance, Point};
+
+/// The length of the windows over which [`winding_distance`] compares the path length
+/// with the straight-line distance, in meters.
+///
+/// This is short enough that long, gentle highway curves barely register,
+/// but the bends of a mountain road do.
+const WINDING_WINDOW_LENGTH: f64 = 500.0;
+
+/// Weights used to score routes; see [`score_route`].
+///
+/// A weight of zero ignores the property entirely.
+#[derive(Debug, Copy, Clone, PartialEq, uniffi::Record)]
+pub struct RouteScoringWeights {
+    /// The cost of each second of estimated travel time.
+    pub duration: f64,
+    /// The cost of each meter traveled.
+    pub distance: f64,
+    /// The additional cost of each meter traveled on toll roads.
+    pub toll_distance: f64,
+    /// The additional cost of each meter traveled by ferry.
+    pub ferry_distance: f64,
+    /// The cost of each maneuver after departing (ex: turns, merges).
+    pub turn: f64,
+    /// The additional cost of each meter traveled through congestion.
+    pub congested_distance: f64,
+    /// The cost of each meter of winding (see [`winding_distance`]).
+    ///
+    /// Use a negative weight to prefer curvy roads (ex: for motorcycle touring).
+    pub winding_distance: f64,
+}
+
+impl Default for RouteScoringWeights {
+    /// Prefers the fastest route.
+    fn default() -> Self {
+        Self {
+            duration: 1.0,
+            distance: 0.0,
+            toll_distance: 0.0,
+            ferry_distance: 0.0,
+            turn: 0.0,
+            congested_distance: 0.0,
+            winding_distance: 0.0,
+        }
+    }
+}
+
+/// Creates route scoring weights which prefer the fastest route.
+#[uniffi::export]
+pub fn create_default_route_scoring_weights() -> RouteScoringWeights {
+    RouteScoringWeights::default()
+}
+
+/// Creates route scoring weights which trade some travel time for curvier roads.
+///
+/// Each meter of winding is worth a second of travel time.
+#[uniffi::export]
+pub fn create_curvy_road_route_scoring_weights() -> RouteScoringWeights {
+    RouteScoringWeights {
+        winding_distance: -1.0,
+        ..RouteScoringWeights::default()
+    }
+}
+
+/// Measures how much a line winds, based on its sinuosity.
+///
+/// The line is split into windows of roughly 500m,
+/// and the result is the total distance traveled in excess of the straight-line distance
+/// across each window, in meters.
+/// This is close to zero for straight roads and grows with the number and tightness of the bends.
+#[must_use]
+pub fn winding_distance(geometry: &[GeographicCoordinate]) -> f64 {
+    let (Some(first), Some(last)) = (geometry.first(), geometry.last()) else {
+        return 0.0;
+    };
+
+    let mut winding = 0.0;
+    let mut window_start = Point::from(*first);
+    let mut window_length = 0.0;
+    for pair in geometry.windows(2) {
+        let (a, b) = (Point::from(pair[0]), Point::from(pair[1]));
+        window_length += a.haversine_distance(&b);
+        if window_length >= WINDING_WINDOW_LENGTH {
+            winding += window_length - window_start.haversine_distance(&b);
+            window_start = b;
+            window_length = 0.0;
+        }
+    }
+    winding += window_length - window_start.haversine_distance(&Point::from(*last));
+    winding.max(0.0)
+}
+
+/// Scores a route using the given weights. Lower scores are better.
+#[uniffi::export]
+pub fn score_route(route: &Route, weights: RouteScoringWeights) -> f64 {
+    let duration: f64 = route.steps.iter().map(|step| step.duration).sum();
+    let toll_distance: f64 = route
+        .steps
+        .iter()
+        .filter(|step| step.has_tolls)
+        .map(|step| step.distance)
+        .sum();
+    let ferry_distance: f64 = route
+        .steps
+        .iter()
+        .filter(|step| step.is_ferry)
+        .map(|step| step.distance)
+        .sum();
+    let turns = route.steps.len().saturating_sub(1);
+    let congested_distance: f64 = route
+        .incidents
+        .iter()
+        .filter(|incident| incident.kind == IncidentKind::Congestion)
+        .map(|incident| {
+            (incident.end_distance_along_route - incident.start_distance_along_route).max(0.0)
+        })
+        .sum();
+    let winding = winding_distance(&route.geometry);
+
+    weights.duration * duration
+        + weights.distance * route.distance
+        + weights.toll_distance * toll_distance
+        + weights.ferry_distance * ferry_distance
+        + weights.turn * turns as f64
+        + weights.congested_distance * congested_distance
+        + weights.winding_distance * winding
+}
+
+/// Selects the route with the lowest score (see [`score_route`]).
+///
+/// Ties are broken in favor of the earlier route,
+/// since backends typically return their preferred route first.
+/// Returns `None` if there are no routes.
+#[uniffi::export]
+pub fn select_best_route(routes: Vec<Route>, weights: RouteScoringWeights) -> Option<Route> {
+    routes
+        .into_iter()
+        .map(|route| (score_route(&route, weights), route))
+        .min_by(|(a, _), (b, _)| a.total_cmp(b))
+        .map(|(_, route)| route)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Incident, RouteStep};
+    use crate::navigation_controller::test_helpers::{gen_dummy_route_step, gen_route_from_steps};
+
+    fn step(distance: f64, duration: f64) -> RouteStep {
+        RouteStep {
+            distance,
+            duration,
+            ..gen_dummy_route_step(0.0, 0.0, 0.01, 0.0)
+        }
+    }
+
+    fn route(steps: Vec<RouteStep>) -> Route {
+        let mut route = gen_route_from_steps(steps);
+        route.distance = route.steps.iter().map(|step| step.distance).sum();
+        route
+    }
+
+    #[test]
+    fn default_weights_prefer_the_fastest_route() {
+        let slow = route(vec![step(1000.0, 120.0)]);
+        let fast = route(vec![step(1500.0, 90.0)]);
+
+        assert_eq!(
+            select_best_route(vec![slow, fast.clone()], RouteScoringWeights::default()),
+            Some(fast)
+        );
+        assert_eq!(
+            select_best_route(vec![], RouteScoringWeights::default()),
+            None
+        );
+    }
+
+    #[test]
+    fn ties_prefer_the_first_route() {
+        let first = route(vec![step(1000.0, 120.0)]);
+        let mut second = first.clone();
+        second.distance = 1.0;
+
+        assert_eq!(
+            select_best_route(vec![first.clone(), second], RouteScoringWeights::default()),
+            Some(first)
+        );
+    }
+
+    #[test]
+    fn avoid_tolls_and_ferries() {
+        let weights = RouteScoringWeights {
+            toll_distance: 1.0,
+            ferry_distance: 1.0,
+            ..RouteScoringWeights::default()
+        };
+        let toll = route(vec![RouteStep {
+            has_tolls: true,
+            ..step(1000.0, 60.0)
+        }]);
+        let ferry = route(vec![RouteStep {
+            is_ferry: true,
+            ..step(1000.0, 60.0)
+        }]);
+        let free = route(vec![step(1000.0, 300.0)]);
+
+        assert!((score_route(&toll, weights) - 1060.0).abs() < f64::EPSILON);
+        assert!((score_route(&ferry, weights) - 1060.0).abs() < f64::EPSILON);
+        assert_eq!(
+            select_best_route(vec![toll, ferry, free.clone()], weights),
+            Some(free)
+        );
+    }
+
+    #[test]
+    fn penalize_turns_and_congestion() {
+        let weights = RouteScoringWeights {
+            turn: 10.0,
+            congested_distance: 0.5,
+            ..RouteScoringWeights::default()
+        };
+        let mut congested = route(vec![step(1000.0, 60.0), step(1000.0, 60.0)]);
+        congested.incidents = vec![Incident {
+            id: "1".to_string(),
+            kind: IncidentKind::Congestion,
+            description: None,
+            is_closed: false,
+            start_distance_along_route: 500.0,
+            end_distance_along_route: 700.0,
+        }];
+
+        // 120s + 1 turn + 200m of congestion
+        assert!((score_route(&congested, weights) - 230.0).abs() < f64::EPSILON);
+    }
+
+    fn zigzag(points: u32) -> Vec<GeographicCoordinate> {
+        (0..points)
+            .map(|i| GeographicCoordinate {
+                lng: f64::from(i) * 0.001,
+                lat: f64::from(i % 2) * 0.001,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn winding_distance_of_straight_and_curvy_lines() {
+        let straight: Vec<_> = (0..20)
+            .map(|i| GeographicCoordinate {
+                lng: f64::from(i) * 0.001,
+                lat: 0.0,
+            })
+            .collect();
+        assert!(winding_distance(&straight) < 1.0);
+        assert!(winding_distance(&zigzag(20)) > 500.0);
+        assert!(winding_distance(&[]).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn prefer_curvy_roads() {
+        let straight = route(vec![step(2000.0, 120.0)]);
+        let mut curvy = straight.clone();
+        curvy.geometry = zigzag(20);
+        curvy.steps[0].duration = 180.0;
+
+        assert_eq!(
+            select_best_route(
+                vec![straight.clone(), curvy.clone()],
+                RouteScoringWeights::default()
+            ),
+            Some(straight.clone())
+        );
+        assert_eq!(
+            select_best_route(
+                vec![straight, curvy.clone()],
+                create_curvy_road_route_scoring_weights()
+            ),
+            Some(curvy)
+        );
+    }
+}
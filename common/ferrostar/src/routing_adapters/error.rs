@@ -1,3 +1,5 @@
+use crate::routing_adapters::http::HttpError;
+use std::fmt;
 use uniffi::UnexpectedUniFFICallbackError;
 
 // TODO: This implementation seems less than ideal. In particular, it hides what sort of JSON error occurred due to an apparent bug in UniFFI.
@@ -16,6 +18,8 @@ pub enum RoutingRequestGenerationError {
     NotEnoughWaypoints,
     #[error("Error generating JSON for the request.")]
     JsonError,
+    #[error("The request options are invalid or not supported by the backend.")]
+    InvalidOptions,
     #[error("An unknown error generating a request was raised in foreign code.")]
     UnknownError,
 }
@@ -38,15 +42,172 @@ impl From<serde_json::Error> for RoutingRequestGenerationError {
     }
 }
 
+/// Identifies the part of a routing response at fault when it fails validation.
+///
+/// All indices are zero-based. Step indices are relative to the leg.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, uniffi::Record)]
+pub struct ResponseIndex {
+    pub route: u32,
+    pub leg: Option<u32>,
+    pub step: Option<u32>,
+}
+
+impl ResponseIndex {
+    pub(crate) fn route(route: usize) -> Self {
+        Self {
+            route: u32::try_from(route).unwrap_or(u32::MAX),
+            leg: None,
+            step: None,
+        }
+    }
+
+    pub(crate) fn leg(self, leg: usize) -> Self {
+        Self {
+            leg: Some(u32::try_from(leg).unwrap_or(u32::MAX)),
+            ..self
+        }
+    }
+
+    pub(crate) fn step(self, step: usize) -> Self {
+        Self {
+            step: Some(u32::try_from(step).unwrap_or(u32::MAX)),
+            ..self
+        }
+    }
+}
+
+impl fmt::Display for ResponseIndex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "route {}", self.route)?;
+        if let Some(leg) = self.leg {
+            write!(f, ", leg {leg}")?;
+        }
+        if let Some(step) = self.step {
+            write!(f, ", step {step}")?;
+        }
+        Ok(())
+    }
+}
+
+/// An error reported by the routing backend (rather than a problem with the response itself).
+#[derive(Debug, Clone, PartialEq, Eq, uniffi::Enum)]
+pub enum RoutingBackendError {
+    /// No route could be found between the waypoints.
+    NoRoute,
+    /// One or more waypoints could not be matched to the routing network.
+    NoSegment,
+    /// The request was malformed or contained invalid values.
+    InvalidQuery,
+    /// The request was too large (ex: too many waypoints).
+    TooBig,
+    /// Any other error code.
+    Other { code: String },
+}
+
+impl RoutingBackendError {
+    /// Maps an [OSRM response code](http://project-osrm.org/docs/v5.5.1/api/#responses).
+    pub(crate) fn from_osrm_code(code: &str) -> Self {
+        match code {
+            "NoRoute" => RoutingBackendError::NoRoute,
+            "NoSegment" => RoutingBackendError::NoSegment,
+            "InvalidUrl" | "InvalidService" | "InvalidVersion" | "InvalidOptions"
+            | "InvalidQuery" | "InvalidValue" => RoutingBackendError::InvalidQuery,
+            "TooBig" => RoutingBackendError::TooBig,
+            _ => RoutingBackendError::Other {
+                code: code.to_string(),
+            },
+        }
+    }
+}
+
+impl fmt::Display for RoutingBackendError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RoutingBackendError::NoRoute => write!(f, "no route found"),
+            RoutingBackendError::NoSegment => {
+                write!(f, "a waypoint could not be matched to the network")
+            }
+            RoutingBackendError::InvalidQuery => write!(f, "invalid query"),
+            RoutingBackendError::TooBig => write!(f, "the request is too big"),
+            RoutingBackendError::Other { code } => write!(f, "{code}"),
+        }
+    }
+}
+
 #[derive(Debug, thiserror::Error, uniffi::Error)]
 pub enum RoutingResponseParseError {
-    // TODO: Unable to find route and other common errors
-    #[error("Failed to parse route response: {error}.")]
-    ParseError { error: String },
+    #[error("The response is not valid JSON or does not match the expected format: {error}.")]
+    InvalidJson { error: String },
+    #[error("The response is not a valid protobuf route collection: {error}.")]
+    InvalidProtobuf { error: String },
+    #[error("The routing backend returned an error: {error}.")]
+    BackendError {
+        error: RoutingBackendError,
+        /// A human-readable description of the error, if the backend provided one.
+        message: Option<String>,
+    },
+    #[error("The response does not contain any routes.")]
+    NoRoutes,
+    #[error("Failed to decode the geometry of {index}: {error}.")]
+    GeometryDecodeError { index: ResponseIndex, error: String },
+    #[error("Invalid coordinate ({lat}, {lng}) in {index}.")]
+    InvalidCoordinate {
+        index: ResponseIndex,
+        lat: f64,
+        lng: f64,
+    },
+    #[error("Empty geometry in {index}.")]
+    EmptyGeometry { index: ResponseIndex },
+    #[error("Invalid distance ({distance}) or duration ({duration}) of {index}.")]
+    InvalidStepMetrics {
+        index: ResponseIndex,
+        distance: f64,
+        duration: f64,
+    },
+    #[error("The geometry of {index} does not start where the previous step ended.")]
+    DiscontinuousSteps { index: ResponseIndex },
+    #[error("Expected one elevation per coordinate; got {coordinates} coordinates and {elevations} elevations.")]
+    MismatchedElevations { coordinates: u32, elevations: u32 },
     #[error("An unknown error parsing a response was raised in foreign code.")]
     UnknownError,
 }
 
+/// An error fetching a route with [`crate::routing_adapters::RouteAdapter::get_route`].
+#[derive(Debug, thiserror::Error, uniffi::Error)]
+pub enum RouteFetchError {
+    #[error(transparent)]
+    RequestGenerationError {
+        error: RoutingRequestGenerationError,
+    },
+    #[error(transparent)]
+    HttpError { error: HttpError },
+    #[error(transparent)]
+    ResponseParseError { error: RoutingResponseParseError },
+    #[error("The request was cancelled.")]
+    Cancelled,
+}
+
+impl From<RoutingRequestGenerationError> for RouteFetchError {
+    fn from(error: RoutingRequestGenerationError) -> Self {
+        RouteFetchError::RequestGenerationError { error }
+    }
+}
+
+impl From<HttpError> for RouteFetchError {
+    fn from(error: HttpError) -> Self {
+        match error {
+            HttpError::Cancelled => RouteFetchError::Cancelled,
+            error => RouteFetchError::HttpError { error },
+        }
+    }
+}
+
+impl From<RoutingResponseParseError> for RouteFetchError {
+    fn from(error: RoutingResponseParseError) -> Self {
+        RouteFetchError::ResponseParseError { error }
+    }
+}
+
 impl From<UnexpectedUniFFICallbackError> for RoutingResponseParseError {
     fn from(_: UnexpectedUniFFICallbackError) -> RoutingResponseParseError {
         RoutingResponseParseError::UnknownError
@@ -55,7 +216,15 @@ impl From<UnexpectedUniFFICallbackError> for RoutingResponseParseError {
 
 impl From<serde_json::Error> for RoutingResponseParseError {
     fn from(e: serde_json::Error) -> Self {
-        RoutingResponseParseError::ParseError {
+        RoutingResponseParseError::InvalidJson {
+            error: e.to_string(),
+        }
+    }
+}
+
+impl From<prost::DecodeError> for RoutingResponseParseError {
+    fn from(e: prost::DecodeError) -> Self {
+        RoutingResponseParseError::InvalidProtobuf {
             error: e.to_string(),
         }
     }
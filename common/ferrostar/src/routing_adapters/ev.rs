@@ -0,0 +1,296 @@
+//! Routing for electric vehicles, with planned charging stops.
+//!
+//! EV routing requires a description of the vehicle ([`EvParameters`])
+//! so that the backend can estimate the battery charge along the route
+//! and add charging stops where needed.
+//! Planned stops are attached to the route as [`crate::models::ChargingStop`]s.
+//!
+//! Support varies by backend.
+//! [`OsrmEvHttpRequestGenerator`] supports OSRM-compatible directions APIs with EV extensions
+//! (ex: the Mapbox Directions API with `engine=electric`),
+//! and the [`crate::routing_adapters::osrm::OsrmResponseParser`] parses the planned charging stops.
+//! Valhalla does not plan charging stops.
+
+use super::{RouteRequest, RouteRequestGenerator, RoutingRequestGenerationError};
+use crate::models::{ConnectorType, UserLocation, Waypoint};
+use crate::routing_adapters::http::url_with_query;
+use std::collections::HashMap;
+
+/// Energy consumption of an electric vehicle at a given speed.
+#[derive(Debug, Copy, Clone, PartialEq, uniffi::Record)]
+pub struct EnergyConsumption {
+    /// The speed, in kilometers per hour.
+    pub speed: f64,
+    /// The energy consumed at that speed, in watt-hours per kilometer.
+    pub consumption: f64,
+}
+
+/// A description of an electric vehicle for EV routing.
+#[derive(Debug, Clone, PartialEq, uniffi::Record)]
+pub struct EvParameters {
+    /// The usable battery capacity, in kilowatt-hours.
+    pub battery_capacity: f64,
+    /// The battery charge at the start of the route, in kilowatt-hours.
+    pub initial_charge: f64,
+    /// The minimum battery charge when arriving at a charging station, in kilowatt-hours.
+    pub min_charge_at_charging_station: Option<f64>,
+    /// The minimum battery charge when arriving at the destination, in kilowatt-hours.
+    pub min_charge_at_destination: Option<f64>,
+    /// Energy consumption by speed, in order of increasing speed.
+    ///
+    /// Backends interpolate between the points.
+    pub consumption_curve: Vec<EnergyConsumption>,
+    /// The connectors that the vehicle can charge with.
+    pub connector_types: Vec<ConnectorType>,
+}
+
+/// Converts kilowatt-hours to the whole watt-hours used by OSRM-compatible APIs.
+fn watt_hours(kilowatt_hours: f64) -> i64 {
+    (kilowatt_hours * 1000.0).round() as i64
+}
+
+/// The name of a connector type in OSRM-compatible APIs.
+pub(crate) fn osrm_connector_name(connector_type: &ConnectorType) -> &str {
+    match connector_type {
+        ConnectorType::J1772 => "type1",
+        ConnectorType::Type2 => "type2",
+        ConnectorType::Ccs1 => "ccs_combo_type1",
+        ConnectorType::Ccs2 => "ccs_combo_type2",
+        ConnectorType::Chademo => "chademo",
+        ConnectorType::Nacs => "tesla",
+        ConnectorType::Other { name } => name,
+    }
+}
+
+/// Parses the name of a connector type in OSRM-compatible APIs.
+pub(crate) fn connector_type_from_osrm_name(name: &str) -> ConnectorType {
+    match name {
+        "type1" | "j1772" => ConnectorType::J1772,
+        "type2" => ConnectorType::Type2,
+        "ccs_combo_type1" | "ccs1" => ConnectorType::Ccs1,
+        "ccs_combo_type2" | "ccs2" => ConnectorType::Ccs2,
+        "chademo" => ConnectorType::Chademo,
+        "tesla" | "nacs" => ConnectorType::Nacs,
+        name => ConnectorType::Other {
+            name: name.to_string(),
+        },
+    }
+}
+
+/// A route request generator for OSRM-compatible directions APIs with EV routing extensions.
+///
+/// Requests use the `engine=electric` query parameters popularized by the Mapbox Directions API.
+/// The backend adds charging stops as waypoints with `charging-station` metadata,
+/// which are parsed into [`crate::models::ChargingStop`]s.
+#[derive(Debug)]
+pub struct OsrmEvHttpRequestGenerator {
+    /// The base URL of the directions API, including the profile
+    /// (ex: `https://api.mapbox.com/directions/v5/mapbox/driving`).
+    ///
+    /// Users *may* include a query string with an access token.
+    base_url: String,
+    ev: EvParameters,
+}
+
+impl OsrmEvHttpRequestGenerator {
+    pub fn new(base_url: String, ev: EvParameters) -> Self {
+        Self { base_url, ev }
+    }
+}
+
+impl RouteRequestGenerator for OsrmEvHttpRequestGenerator {
+    fn generate_request(
+        &self,
+        user_location: UserLocation,
+        waypoints: Vec<Waypoint>,
+    ) -> Result<RouteRequest, RoutingRequestGenerationError> {
+        if waypoints.is_empty() {
+            return Err(RoutingRequestGenerationError::NotEnoughWaypoints);
+        }
+        if self.ev.consumption_curve.is_empty() || self.ev.initial_charge > self.ev.battery_capacity
+        {
+            return Err(RoutingRequestGenerationError::InvalidOptions);
+        }
+
+        let coordinates = std::iter::once(user_location.coordinates)
+            .chain(waypoints.iter().map(|waypoint| waypoint.coordinate))
+            .map(|coordinate| format!("{},{}", coordinate.lng, coordinate.lat))
+            .collect::<Vec<_>>()
+            .join(";");
+        let (base, query) = self
+            .base_url
+            .split_once('?')
+            .map_or((self.base_url.as_str(), None), |(base, query)| {
+                (base, Some(query))
+            });
+        let mut url = format!("{}/{coordinates}", base.trim_end_matches('/'));
+        if let Some(query) = query {
+            url = format!("{url}?{query}");
+        }
+
+        let mut params = vec![
+            ("steps", "true".to_string()),
+            ("overview", "full".to_string()),
+            ("geometries", "polyline6".to_string()),
+            ("banner_instructions", "true".to_string()),
+            ("voice_instructions", "true".to_string()),
+            ("engine", "electric".to_string()),
+            (
+                "ev_max_charge",
+                watt_hours(self.ev.battery_capacity).to_string(),
+            ),
+            (
+                "ev_initial_charge",
+                watt_hours(self.ev.initial_charge).to_string(),
+            ),
+            (
+                "energy_consumption_curve",
+                self.ev
+                    .consumption_curve
+                    .iter()
+                    .map(|point| format!("{},{}", point.speed, point.consumption))
+                    .collect::<Vec<_>>()
+                    .join(";"),
+            ),
+        ];
+        if let Some(charge) = self.ev.min_charge_at_charging_station {
+            params.push((
+                "ev_min_charge_at_charging_station",
+                watt_hours(charge).to_string(),
+            ));
+        }
+        if let Some(charge) = self.ev.min_charge_at_destination {
+            params.push((
+                "ev_min_charge_at_destination",
+                watt_hours(charge).to_string(),
+            ));
+        }
+        if !self.ev.connector_types.is_empty() {
+            params.push((
+                "ev_connector_types",
+                self.ev
+                    .connector_types
+                    .iter()
+                    .map(osrm_connector_name)
+                    .collect::<Vec<_>>()
+                    .join(","),
+            ));
+        }
+
+        Ok(RouteRequest::HttpGet {
+            url: url_with_query(&url, &params),
+            headers: HashMap::new(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{GeographicCoordinate, WaypointKind};
+    use std::time::SystemTime;
+
+    const USER_LOCATION: UserLocation = UserLocation {
+        coordinates: GeographicCoordinate {
+            lat: 52.517,
+            lng: 13.388,
+        },
+        horizontal_accuracy: 6.0,
+        course_over_ground: None,
+        timestamp: SystemTime::UNIX_EPOCH,
+        speed: None,
+        altitude: None,
+        vertical_accuracy: None,
+    };
+    const WAYPOINTS: [Waypoint; 1] = [Waypoint {
+        coordinate: GeographicCoordinate {
+            lat: 48.137,
+            lng: 11.575,
+        },
+        kind: WaypointKind::Break,
+    }];
+
+    fn ev() -> EvParameters {
+        EvParameters {
+            battery_capacity: 77.0,
+            initial_charge: 60.5,
+            min_charge_at_charging_station: Some(6.0),
+            min_charge_at_destination: None,
+            consumption_curve: vec![
+                EnergyConsumption {
+                    speed: 50.0,
+                    consumption: 130.0,
+                },
+                EnergyConsumption {
+                    speed: 120.0,
+                    consumption: 210.0,
+                },
+            ],
+            connector_types: vec![ConnectorType::Ccs2, ConnectorType::Type2],
+        }
+    }
+
+    #[test]
+    fn ev_request() {
+        let generator = OsrmEvHttpRequestGenerator::new(
+            "https://api.mapbox.com/directions/v5/mapbox/driving?access_token=secret".to_string(),
+            ev(),
+        );
+        let RouteRequest::HttpGet { url, .. } = generator
+            .generate_request(USER_LOCATION, WAYPOINTS.to_vec())
+            .expect("Unable to generate request")
+        else {
+            panic!("Expected a GET request");
+        };
+
+        assert_eq!(
+            url,
+            "https://api.mapbox.com/directions/v5/mapbox/driving/13.388,52.517;11.575,48.137\
+            ?access_token=secret&steps=true&overview=full&geometries=polyline6\
+            &banner_instructions=true&voice_instructions=true&engine=electric\
+            &ev_max_charge=77000&ev_initial_charge=60500\
+            &energy_consumption_curve=50%2C130%3B120%2C210\
+            &ev_min_charge_at_charging_station=6000\
+            &ev_connector_types=ccs_combo_type2%2Ctype2"
+        );
+    }
+
+    #[test]
+    fn invalid_ev_parameters() {
+        let generator = OsrmEvHttpRequestGenerator::new(
+            "https://osrm.example.com/route/v1/driving".to_string(),
+            EvParameters {
+                initial_charge: 80.0,
+                ..ev()
+            },
+        );
+        assert!(matches!(
+            generator.generate_request(USER_LOCATION, WAYPOINTS.to_vec()),
+            Err(RoutingRequestGenerationError::InvalidOptions)
+        ));
+        assert!(matches!(
+            generator.generate_request(USER_LOCATION, vec![]),
+            Err(RoutingRequestGenerationError::NotEnoughWaypoints)
+        ));
+    }
+
+    #[test]
+    fn connector_names_round_trip() {
+        for connector_type in [
+            ConnectorType::J1772,
+            ConnectorType::Type2,
+            ConnectorType::Ccs1,
+            ConnectorType::Ccs2,
+            ConnectorType::Chademo,
+            ConnectorType::Nacs,
+            ConnectorType::Other {
+                name: "gbt".to_string(),
+            },
+        ] {
+            assert_eq!(
+                connector_type_from_osrm_name(osrm_connector_name(&connector_type)),
+                connector_type
+            );
+        }
+    }
+}
@@ -0,0 +1,637 @@
+//! A minimal HTTP execution layer for fetching routes.
+//!
+//! The actual transport is left to an [`HttpClient`] implementation
+//! (typically the platform's native networking stack,
+//! or the built-in client when the `reqwest` feature is enabled),
+//! while the [`HttpExecutor`] takes care of the tedious parts:
+//! retries with exponential backoff, timeouts, and cancellation.
+
+use crate::routing_adapters::RouteRequest;
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::Pin;
+use std::sync::{Arc, Condvar, Mutex, PoisonError};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+use std::time::Duration;
+use uniffi::UnexpectedUniFFICallbackError;
+
+#[derive(Debug, Clone, PartialEq, thiserror::Error, uniffi::Error)]
+pub enum HttpError {
+    #[error("The request timed out.")]
+    Timeout,
+    #[error("Unable to connect to the server: {error}.")]
+    ConnectionError { error: String },
+    #[error("The server responded with HTTP status {status}.")]
+    HttpStatus { status: u16 },
+    #[error("The request was cancelled.")]
+    Cancelled,
+    #[error("An unknown error executing a request was raised in foreign code.")]
+    UnknownError,
+    #[error("An internal error occurred while executing the request.")]
+    InternalError,
+}
+
+impl HttpError {
+    /// Whether the request might succeed if it is tried again.
+    ///
+    /// Server errors and rate limiting are considered transient,
+    /// but other client errors (ex: 400 Bad Request) will never succeed.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            HttpError::Timeout | HttpError::ConnectionError { .. } => true,
+            HttpError::HttpStatus { status } => *status == 429 || *status >= 500,
+            HttpError::Cancelled | HttpError::UnknownError | HttpError::InternalError => false,
+        }
+    }
+}
+
+impl From<UnexpectedUniFFICallbackError> for HttpError {
+    fn from(_: UnexpectedUniFFICallbackError) -> HttpError {
+        HttpError::UnknownError
+    }
+}
+
+/// A trait describing any object capable of executing [`RouteRequest`]s.
+///
+/// Implementations should be blocking, as the [`HttpExecutor`] handles scheduling.
+#[uniffi::export(with_foreign)]
+pub trait HttpClient: Send + Sync {
+    /// Executes the request, returning the body of a successful (2xx) response.
+    ///
+    /// The request should be abandoned with [`HttpError::Timeout`]
+    /// if it does not complete within `timeout` seconds.
+    fn execute(&self, request: RouteRequest, timeout: f64) -> Result<Vec<u8>, HttpError>;
+}
+
+/// An [`HttpClient`] backed by [`reqwest`]'s blocking client.
+///
+/// Useful on platforms which don't have a convenient native networking stack
+/// (ex: desktop apps, CLI tools, and tests).
+#[cfg(feature = "reqwest")]
+#[derive(Debug, Default)]
+pub struct ReqwestHttpClient {
+    client: reqwest::blocking::Client,
+}
+
+#[cfg(feature = "reqwest")]
+impl ReqwestHttpClient {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[cfg(feature = "reqwest")]
+impl HttpClient for ReqwestHttpClient {
+    fn execute(&self, request: RouteRequest, timeout: f64) -> Result<Vec<u8>, HttpError> {
+        let (builder, headers) = match request {
+            RouteRequest::HttpPost { url, headers, body } => {
+                (self.client.post(url).body(body), headers)
+            }
+            RouteRequest::HttpGet { url, headers } => (self.client.get(url), headers),
+        };
+        let mut builder = headers.into_iter().fold(builder, |builder, (name, value)| {
+            builder.header(name, value)
+        });
+        // Invalid timeouts (ex: infinite) leave the request without one
+        if let Ok(timeout) = Duration::try_from_secs_f64(timeout) {
+            builder = builder.timeout(timeout);
+        }
+
+        let response = builder.send()?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(HttpError::HttpStatus {
+                status: status.as_u16(),
+            });
+        }
+        Ok(response.bytes()?.to_vec())
+    }
+}
+
+#[cfg(feature = "reqwest")]
+impl From<reqwest::Error> for HttpError {
+    fn from(error: reqwest::Error) -> HttpError {
+        if error.is_timeout() {
+            HttpError::Timeout
+        } else {
+            HttpError::ConnectionError {
+                error: error.to_string(),
+            }
+        }
+    }
+}
+
+/// Determines how failed requests are retried.
+#[derive(Debug, Copy, Clone, PartialEq, uniffi::Record)]
+pub struct RetryPolicy {
+    /// The maximum number of attempts, including the first one.
+    pub max_attempts: u32,
+    /// How long to wait before the first retry, in seconds.
+    pub initial_backoff: f64,
+    /// The factor by which the wait increases after each retry.
+    pub backoff_multiplier: f64,
+    /// The maximum time to wait between attempts, in seconds.
+    pub max_backoff: f64,
+    /// The timeout for each attempt, in seconds.
+    pub request_timeout: f64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: 0.5,
+            backoff_multiplier: 2.0,
+            max_backoff: 8.0,
+            request_timeout: 15.0,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// The time to wait before the given retry (starting at zero for the first retry).
+    ///
+    /// Waits which are too long to represent (ex: with an infinite `max_backoff`)
+    /// are saturated to [`Duration::MAX`].
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let exponent = i32::try_from(retry).unwrap_or(i32::MAX);
+        let seconds = (self.initial_backoff * self.backoff_multiplier.powi(exponent))
+            .min(self.max_backoff)
+            .max(0.0);
+        Duration::try_from_secs_f64(seconds).unwrap_or(Duration::MAX)
+    }
+}
+
+/// Creates a retry policy with sensible defaults for interactive use.
+#[uniffi::export]
+pub fn create_default_retry_policy() -> RetryPolicy {
+    RetryPolicy::default()
+}
+
+/// A token which can be used to cancel in-flight work (ex: a route request which has been superseded).
+///
+/// Cancellation takes effect between attempts;
+/// the [`HttpClient`] is not interrupted while executing a request.
+#[derive(Debug, Default, uniffi::Object)]
+pub struct CancellationToken {
+    is_cancelled: Mutex<bool>,
+    condvar: Condvar,
+}
+
+#[uniffi::export]
+impl CancellationToken {
+    #[uniffi::constructor]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancels the work associated with this token. This cannot be undone.
+    pub fn cancel(&self) {
+        if let Ok(mut is_cancelled) = self.is_cancelled.lock() {
+            *is_cancelled = true;
+            self.condvar.notify_all();
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.is_cancelled
+            .lock()
+            .map_or(true, |is_cancelled| *is_cancelled)
+    }
+}
+
+impl CancellationToken {
+    /// Waits for the given duration, returning early if the token is cancelled.
+    ///
+    /// Returns whether the token was cancelled.
+    pub(crate) fn wait(&self, duration: Duration) -> bool {
+        let Ok(is_cancelled) = self.is_cancelled.lock() else {
+            return true;
+        };
+        self.condvar
+            .wait_timeout_while(is_cancelled, duration, |is_cancelled| !*is_cancelled)
+            .map_or(true, |(is_cancelled, _)| *is_cancelled)
+    }
+}
+
+/// Executes requests using an [`HttpClient`], retrying transient failures.
+#[derive(uniffi::Object)]
+pub struct HttpExecutor {
+    client: Arc<dyn HttpClient>,
+    retry_policy: RetryPolicy,
+}
+
+#[uniffi::export]
+impl HttpExecutor {
+    #[uniffi::constructor]
+    pub fn new(client: Arc<dyn HttpClient>, retry_policy: RetryPolicy) -> Self {
+        Self {
+            client,
+            retry_policy,
+        }
+    }
+
+    /// Executes the request, returning the response body.
+    ///
+    /// This blocks until the request succeeds, fails with an error that can't be retried,
+    /// runs out of attempts, or is cancelled.
+    pub fn execute(
+        &self,
+        request: &RouteRequest,
+        cancellation_token: Option<Arc<CancellationToken>>,
+    ) -> Result<Vec<u8>, HttpError> {
+        let cancellation_token = cancellation_token.unwrap_or_default();
+        let mut retry = 0;
+        loop {
+            if cancellation_token.is_cancelled() {
+                return Err(HttpError::Cancelled);
+            }
+
+            let error = match self
+                .client
+                .execute(request.clone(), self.retry_policy.request_timeout)
+            {
+                Ok(body) => return Ok(body),
+                Err(error) => error,
+            };
+
+            if !error.is_retryable() || retry + 1 >= self.retry_policy.max_attempts {
+                return Err(error);
+            }
+            if cancellation_token.wait(self.retry_policy.backoff(retry)) {
+                return Err(HttpError::Cancelled);
+            }
+            retry += 1;
+        }
+    }
+}
+
+#[cfg(feature = "reqwest")]
+#[uniffi::export]
+impl HttpExecutor {
+    /// Creates an executor which uses the built-in [`ReqwestHttpClient`].
+    #[uniffi::constructor]
+    pub fn with_builtin_client(retry_policy: RetryPolicy) -> Self {
+        Self::new(Arc::new(ReqwestHttpClient::new()), retry_policy)
+    }
+}
+
+/// Runs a blocking function on a new thread, returning a future which resolves to its result.
+///
+/// This lets async functions use the blocking [`HttpExecutor`] without tying up the caller's executor.
+/// The future always resolves: if the function panics, it resolves to [`HttpError::InternalError`]
+/// rather than waiting forever.
+pub(crate) fn spawn_blocking<T, F>(function: F) -> BlockingTask<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    let state = Arc::new(Mutex::new(BlockingTaskState {
+        result: None,
+        waker: None,
+    }));
+    let thread_state = state.clone();
+    thread::spawn(move || {
+        let result =
+            panic::catch_unwind(AssertUnwindSafe(function)).map_err(|_| HttpError::InternalError);
+        // The lock is never held across a panic, but the result must be delivered regardless
+        let mut state = thread_state.lock().unwrap_or_else(PoisonError::into_inner);
+        state.result = Some(result);
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+    });
+
+    BlockingTask { state }
+}
+
+struct BlockingTaskState<T> {
+    result: Option<Result<T, HttpError>>,
+    waker: Option<Waker>,
+}
+
+/// A future which resolves when a function started by [`spawn_blocking`] completes.
+pub(crate) struct BlockingTask<T> {
+    state: Arc<Mutex<BlockingTaskState<T>>>,
+}
+
+impl<T> Future for BlockingTask<T> {
+    type Output = Result<T, HttpError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        match state.result.take() {
+            Some(result) => Poll::Ready(result),
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// Appends query parameters to a URL (which may already have a query string, ex: with an API key),
+/// percent-encoding the values.
+pub(crate) fn url_with_query(url: &str, params: &[(&str, String)]) -> String {
+    let mut url = url.to_string();
+    for (i, (name, value)) in params.iter().enumerate() {
+        url.push(if i == 0 && !url.contains('?') {
+            '?'
+        } else {
+            '&'
+        });
+        url.push_str(name);
+        url.push('=');
+        for byte in value.bytes() {
+            if byte.is_ascii_alphanumeric() || b"-_.~".contains(&byte) {
+                url.push(char::from(byte));
+            } else {
+                url.push_str(&format!("%{byte:02X}"));
+            }
+        }
+    }
+    url
+}
+
+#[cfg(test)]
+pub(crate) mod test_helpers {
+    use super::{HttpClient, HttpError, HttpExecutor, RetryPolicy};
+    use crate::routing_adapters::RouteRequest;
+    use std::future::Future;
+    use std::pin::pin;
+    use std::sync::{Arc, Mutex};
+    use std::task::{Context, Poll, Wake};
+    use std::thread::{self, Thread};
+
+    /// A minimal OSRM route response: 333.6m with no steps (so no duration).
+    pub(crate) const OSRM_RESPONSE: &str = r#"{"code":"Ok","routes":[{"geometry":"???o}@?o}@?o}@","legs":[{"steps":[],"summary":"","weight":40.0,"duration":40.0,"distance":333.6}],"weight_name":"routability","weight":40.0,"duration":40.0,"distance":333.6}],"waypoints":[]}"#;
+
+    /// An [`HttpClient`] which returns the configured responses in order
+    /// (repeating the last one), and records every request it receives.
+    pub(crate) struct MockHttpClient {
+        responses: Mutex<Vec<Result<Vec<u8>, HttpError>>>,
+        requests: Mutex<Vec<RouteRequest>>,
+    }
+
+    impl MockHttpClient {
+        /// Responds to every request with `response`.
+        pub(crate) fn new(response: Result<Vec<u8>, HttpError>) -> Arc<Self> {
+            Self::with_responses(vec![response])
+        }
+
+        /// Responds to every request with [`OSRM_RESPONSE`].
+        pub(crate) fn osrm() -> Arc<Self> {
+            Self::new(Ok(OSRM_RESPONSE.as_bytes().to_vec()))
+        }
+
+        pub(crate) fn with_responses(responses: Vec<Result<Vec<u8>, HttpError>>) -> Arc<Self> {
+            assert!(!responses.is_empty(), "At least one response is required");
+            Arc::new(Self {
+                responses: Mutex::new(responses),
+                requests: Mutex::new(vec![]),
+            })
+        }
+
+        pub(crate) fn requests(&self) -> Vec<RouteRequest> {
+            self.requests
+                .lock()
+                .expect("Unable to lock requests")
+                .clone()
+        }
+
+        /// The bodies of the POST requests received so far.
+        pub(crate) fn request_bodies(&self) -> Vec<Vec<u8>> {
+            self.requests()
+                .into_iter()
+                .filter_map(|request| match request {
+                    RouteRequest::HttpPost { body, .. } => Some(body),
+                    RouteRequest::HttpGet { .. } => None,
+                })
+                .collect()
+        }
+    }
+
+    impl HttpClient for MockHttpClient {
+        fn execute(&self, request: RouteRequest, _timeout: f64) -> Result<Vec<u8>, HttpError> {
+            self.requests
+                .lock()
+                .expect("Unable to lock requests")
+                .push(request);
+            let mut responses = self.responses.lock().expect("Unable to lock responses");
+            if responses.len() > 1 {
+                responses.remove(0)
+            } else {
+                responses[0].clone()
+            }
+        }
+    }
+
+    /// An executor which makes a single attempt per request using `client`.
+    pub(crate) fn executor(client: Arc<dyn HttpClient>) -> Arc<HttpExecutor> {
+        Arc::new(HttpExecutor::new(
+            client,
+            RetryPolicy {
+                max_attempts: 1,
+                ..RetryPolicy::default()
+            },
+        ))
+    }
+
+    struct ThreadWaker(Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    /// Runs a future to completion on the current thread.
+    pub(crate) fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = pin!(future);
+        let waker = Arc::new(ThreadWaker(thread::current())).into();
+        let mut cx = Context::from_waker(&waker);
+        loop {
+            match future.as_mut().poll(&mut cx) {
+                Poll::Ready(output) => return output,
+                Poll::Pending => thread::park(),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_helpers::MockHttpClient;
+    use super::*;
+    use std::collections::HashMap;
+
+    const RETRY_POLICY: RetryPolicy = RetryPolicy {
+        max_attempts: 3,
+        initial_backoff: 0.001,
+        backoff_multiplier: 2.0,
+        max_backoff: 0.01,
+        request_timeout: 1.0,
+    };
+
+    fn request() -> RouteRequest {
+        RouteRequest::HttpPost {
+            url: "https://example.com/route".to_string(),
+            headers: HashMap::new(),
+            body: vec![],
+        }
+    }
+
+    #[test]
+    fn backoff() {
+        let policy = RetryPolicy::default();
+        assert_eq!(policy.backoff(0), Duration::from_millis(500));
+        assert_eq!(policy.backoff(1), Duration::from_secs(1));
+        assert_eq!(policy.backoff(2), Duration::from_secs(2));
+        assert_eq!(policy.backoff(10), Duration::from_secs(8));
+
+        let unbounded = RetryPolicy {
+            max_backoff: f64::INFINITY,
+            ..policy
+        };
+        assert_eq!(unbounded.backoff(2000), Duration::MAX);
+    }
+
+    #[test]
+    fn retries_transient_errors() {
+        let client = MockHttpClient::with_responses(vec![
+            Err(HttpError::Timeout),
+            Err(HttpError::HttpStatus { status: 503 }),
+            Ok(b"{}".to_vec()),
+        ]);
+        let executor = HttpExecutor::new(client.clone(), RETRY_POLICY);
+
+        assert_eq!(executor.execute(&request(), None), Ok(b"{}".to_vec()));
+        assert_eq!(client.requests().len(), 3);
+    }
+
+    #[test]
+    fn gives_up_after_max_attempts() {
+        let client = MockHttpClient::new(Err(HttpError::Timeout));
+        let executor = HttpExecutor::new(client.clone(), RETRY_POLICY);
+
+        assert_eq!(executor.execute(&request(), None), Err(HttpError::Timeout));
+        assert_eq!(client.requests().len(), 3);
+    }
+
+    #[test]
+    fn does_not_retry_client_errors() {
+        let client = MockHttpClient::with_responses(vec![
+            Err(HttpError::HttpStatus { status: 400 }),
+            Ok(b"{}".to_vec()),
+        ]);
+        let executor = HttpExecutor::new(client.clone(), RETRY_POLICY);
+
+        assert_eq!(
+            executor.execute(&request(), None),
+            Err(HttpError::HttpStatus { status: 400 })
+        );
+        assert_eq!(client.requests().len(), 1);
+    }
+
+    #[test]
+    fn cancellation() {
+        let client = MockHttpClient::new(Ok(b"{}".to_vec()));
+        let executor = HttpExecutor::new(client.clone(), RETRY_POLICY);
+        let token = Arc::new(CancellationToken::new());
+        token.cancel();
+
+        assert_eq!(
+            executor.execute(&request(), Some(token.clone())),
+            Err(HttpError::Cancelled)
+        );
+        assert_eq!(client.requests().len(), 0);
+        assert!(token.wait(Duration::from_secs(60)));
+    }
+
+    #[test]
+    fn url_with_query_encodes_values() {
+        assert_eq!(
+            url_with_query(
+                "https://example.com/search",
+                &[
+                    ("q", "Straße 1, Berlin".to_string()),
+                    ("limit", "5".to_string())
+                ]
+            ),
+            "https://example.com/search?q=Stra%C3%9Fe%201%2C%20Berlin&limit=5"
+        );
+        assert_eq!(
+            url_with_query(
+                "https://example.com/search?api_key=abc",
+                &[("q", "a&b".to_string())]
+            ),
+            "https://example.com/search?api_key=abc&q=a%26b"
+        );
+    }
+
+    #[test]
+    fn blocking_task() {
+        let task = spawn_blocking(|| {
+            thread::sleep(Duration::from_millis(10));
+            42
+        });
+        assert_eq!(test_helpers::block_on(task), Ok(42));
+    }
+
+    #[test]
+    fn blocking_task_panic() {
+        let task = spawn_blocking(|| -> u32 { panic!("The client panicked") });
+        assert_eq!(test_helpers::block_on(task), Err(HttpError::InternalError));
+    }
+
+    #[test]
+    fn blocking_task_poisoned_lock() {
+        let task = spawn_blocking(|| {
+            thread::sleep(Duration::from_millis(10));
+            42
+        });
+        let state = task.state.clone();
+        let _ = thread::spawn(move || {
+            let _state = state.lock();
+            panic!("Poisoning the lock");
+        })
+        .join();
+        assert!(task.state.is_poisoned());
+
+        assert_eq!(test_helpers::block_on(task), Ok(42));
+    }
+
+    #[cfg(feature = "reqwest")]
+    #[test]
+    fn reqwest_client() {
+        use std::io::{BufRead, BufReader, Write};
+        use std::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").expect("Unable to bind");
+        let url = format!("http://{}/route", listener.local_addr().unwrap());
+        let server = thread::spawn(move || {
+            for status in ["503 Service Unavailable", "200 OK"] {
+                let (mut stream, _) = listener.accept().expect("Unable to accept");
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut line = String::new();
+                while reader.read_line(&mut line).is_ok_and(|read| read > 2) {
+                    line.clear();
+                }
+                write!(
+                    stream,
+                    "HTTP/1.1 {status}\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok"
+                )
+                .unwrap();
+            }
+        });
+
+        let executor = HttpExecutor::new(Arc::new(ReqwestHttpClient::new()), RETRY_POLICY);
+        let request = RouteRequest::HttpGet {
+            url,
+            headers: HashMap::new(),
+        };
+        assert_eq!(executor.execute(&request, None), Ok(b"ok".to_vec()));
+        server.join().unwrap();
+    }
+}
@@ -0,0 +1,27 @@
+//! Hooks for inspecting route requests and responses as they pass through a [`super::RouteAdapter`].
+//!
+//! These are mostly useful for deployments behind custom gateways,
+//! which may require auth headers, session IDs, or logging of raw responses.
+
+use crate::routing_adapters::error::RoutingRequestGenerationError;
+use crate::routing_adapters::RouteRequest;
+
+/// Inspects and optionally modifies outgoing route requests.
+#[uniffi::export(with_foreign)]
+pub trait RouteRequestInterceptor: Send + Sync {
+    /// Returns the request which should be sent in place of `request`.
+    ///
+    /// Implementations which only need to add headers (ex: for authentication)
+    /// should return a copy of the request with the headers inserted.
+    fn intercept_request(
+        &self,
+        request: RouteRequest,
+    ) -> Result<RouteRequest, RoutingRequestGenerationError>;
+}
+
+/// Observes raw route responses before they are parsed.
+#[uniffi::export(with_foreign)]
+pub trait RouteResponseInterceptor: Send + Sync {
+    /// Called with the raw response bytes, exactly as they will be passed to the parser.
+    fn intercept_response(&self, response: Vec<u8>);
+}
@@ -0,0 +1,604 @@
+//! Isochrone support for showing the area reachable from a location.
+//!
+//! An isochrone is the area which can be reached within a given travel time (or distance)
+//! along the road network, which is useful for things like delivery zones.
+//! Backends return these as GeoJSON polygons, which are parsed into [`Polygon`]s.
+
+use super::error::{
+    RouteFetchError, RoutingBackendError, RoutingRequestGenerationError, RoutingResponseParseError,
+};
+use super::http::{spawn_blocking, CancellationToken, HttpExecutor};
+use super::RouteRequest;
+use crate::models::{GeographicCoordinate, Polygon};
+use serde::Deserialize;
+use serde_json::{json, Value as JsonValue};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// The limit of an isochrone.
+#[derive(Debug, Copy, Clone, PartialEq, uniffi::Enum)]
+pub enum IsochroneContour {
+    /// The area reachable within a travel time, in seconds.
+    Time { seconds: f64 },
+    /// The area reachable within a travel distance, in meters.
+    Distance { meters: f64 },
+}
+
+/// The area reachable within a contour.
+#[derive(Debug, Clone, PartialEq, uniffi::Record)]
+pub struct Isochrone {
+    pub contour: IsochroneContour,
+    /// The reachable area, which may consist of several disjoint polygons (ex: islands).
+    pub polygons: Vec<Polygon>,
+}
+
+/// A trait describing any object capable of generating isochrone requests.
+#[uniffi::export(with_foreign)]
+pub trait IsochroneRequestGenerator: Send + Sync {
+    /// Generates a request for the areas reachable from `origin` within each contour.
+    fn generate_request(
+        &self,
+        origin: GeographicCoordinate,
+        contours: Vec<IsochroneContour>,
+    ) -> Result<RouteRequest, RoutingRequestGenerationError>;
+}
+
+/// A trait describing any object capable of parsing an isochrone response.
+#[uniffi::export(with_foreign)]
+pub trait IsochroneResponseParser: Send + Sync {
+    /// Parses the isochrones in the response, in the order returned by the backend.
+    fn parse_response(
+        &self,
+        response: Vec<u8>,
+    ) -> Result<Vec<Isochrone>, RoutingResponseParseError>;
+}
+
+/// An isochrone request generator for the Valhalla `isochrone` endpoint operating over HTTP.
+#[derive(Debug)]
+pub struct ValhallaIsochroneRequestGenerator {
+    /// The full URL of the Valhalla `isochrone` endpoint.
+    ///
+    /// Users *may* include a query string with an API key.
+    endpoint_url: String,
+    /// The Valhalla costing model to use.
+    profile: String,
+}
+
+impl ValhallaIsochroneRequestGenerator {
+    pub fn new(endpoint_url: String, profile: String) -> Self {
+        Self {
+            endpoint_url,
+            profile,
+        }
+    }
+}
+
+impl IsochroneRequestGenerator for ValhallaIsochroneRequestGenerator {
+    fn generate_request(
+        &self,
+        origin: GeographicCoordinate,
+        contours: Vec<IsochroneContour>,
+    ) -> Result<RouteRequest, RoutingRequestGenerationError> {
+        if contours.is_empty() {
+            return Err(RoutingRequestGenerationError::InvalidOptions);
+        }
+
+        let headers = HashMap::from([("Content-Type".to_string(), "application/json".to_string())]);
+        // Valhalla expects times in minutes and distances in kilometers
+        let contours: Vec<JsonValue> = contours
+            .iter()
+            .map(|contour| match contour {
+                IsochroneContour::Time { seconds } => json!({ "time": seconds / 60.0 }),
+                IsochroneContour::Distance { meters } => json!({ "distance": meters / 1000.0 }),
+            })
+            .collect();
+        let args = json!({
+            "locations": [{
+                "lat": origin.lat,
+                "lon": origin.lng,
+            }],
+            "costing": &self.profile,
+            "contours": contours,
+            "polygons": true,
+        });
+        let body = serde_json::to_vec(&args)?;
+        Ok(RouteRequest::HttpPost {
+            url: self.endpoint_url.clone(),
+            headers,
+            body,
+        })
+    }
+}
+
+/// An isochrone request generator for the [openrouteservice](https://openrouteservice.org/)
+/// isochrones endpoint operating over HTTP.
+///
+/// All contours in a request must be of the same kind (time or distance).
+#[derive(Debug)]
+pub struct OpenRouteServiceIsochroneRequestGenerator {
+    /// The full URL of the isochrones endpoint, including the profile
+    /// (ex: `https://api.openrouteservice.org/v2/isochrones/driving-car`).
+    ///
+    /// Users *may* include a query string with an API key.
+    endpoint_url: String,
+}
+
+impl OpenRouteServiceIsochroneRequestGenerator {
+    pub fn new(endpoint_url: String) -> Self {
+        Self { endpoint_url }
+    }
+}
+
+impl IsochroneRequestGenerator for OpenRouteServiceIsochroneRequestGenerator {
+    fn generate_request(
+        &self,
+        origin: GeographicCoordinate,
+        contours: Vec<IsochroneContour>,
+    ) -> Result<RouteRequest, RoutingRequestGenerationError> {
+        let range_type = match contours.first() {
+            Some(IsochroneContour::Time { .. }) => "time",
+            Some(IsochroneContour::Distance { .. }) => "distance",
+            None => return Err(RoutingRequestGenerationError::InvalidOptions),
+        };
+        let range = contours
+            .iter()
+            .map(|contour| match (contour, range_type) {
+                (IsochroneContour::Time { seconds }, "time") => Ok(*seconds),
+                (IsochroneContour::Distance { meters }, "distance") => Ok(*meters),
+                _ => Err(RoutingRequestGenerationError::InvalidOptions),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let headers = HashMap::from([("Content-Type".to_string(), "application/json".to_string())]);
+        let args = json!({
+            "locations": [[origin.lng, origin.lat]],
+            "range": range,
+            "range_type": range_type,
+            "units": "m",
+        });
+        let body = serde_json::to_vec(&args)?;
+        Ok(RouteRequest::HttpPost {
+            url: self.endpoint_url.clone(),
+            headers,
+            body,
+        })
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type")]
+enum Geometry {
+    Polygon {
+        coordinates: Vec<Vec<[f64; 2]>>,
+    },
+    MultiPolygon {
+        coordinates: Vec<Vec<Vec<[f64; 2]>>>,
+    },
+}
+
+impl Geometry {
+    fn into_polygons(self) -> Vec<Polygon> {
+        let polygons = match self {
+            Geometry::Polygon { coordinates } => vec![coordinates],
+            Geometry::MultiPolygon { coordinates } => coordinates,
+        };
+        polygons
+            .into_iter()
+            .filter_map(|rings| {
+                let mut rings = rings.into_iter().map(|ring| {
+                    ring.into_iter()
+                        .map(|[lng, lat]| GeographicCoordinate { lat, lng })
+                        .collect()
+                });
+                Some(Polygon {
+                    exterior: rings.next()?,
+                    interiors: rings.collect(),
+                })
+            })
+            .collect()
+    }
+}
+
+#[derive(Deserialize)]
+struct Feature<P> {
+    properties: P,
+    geometry: Geometry,
+}
+
+#[derive(Deserialize)]
+struct ValhallaIsochroneResponse {
+    #[serde(default)]
+    features: Vec<Feature<ValhallaIsochroneProperties>>,
+    error_code: Option<u32>,
+    error: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ValhallaIsochroneProperties {
+    /// The contour, in minutes or kilometers (depending on the metric).
+    contour: f64,
+    metric: String,
+}
+
+/// An isochrone response parser for the Valhalla `isochrone` endpoint (GeoJSON output).
+#[derive(Debug, Default)]
+pub struct ValhallaIsochroneResponseParser;
+
+impl IsochroneResponseParser for ValhallaIsochroneResponseParser {
+    fn parse_response(
+        &self,
+        response: Vec<u8>,
+    ) -> Result<Vec<Isochrone>, RoutingResponseParseError> {
+        let res: ValhallaIsochroneResponse = serde_json::from_slice(&response)?;
+        if let Some(code) = res.error_code {
+            return Err(RoutingResponseParseError::BackendError {
+                error: RoutingBackendError::Other {
+                    code: code.to_string(),
+                },
+                message: res.error,
+            });
+        }
+
+        Ok(res
+            .features
+            .into_iter()
+            .map(|feature| Isochrone {
+                contour: match feature.properties.metric.as_str() {
+                    "distance" => IsochroneContour::Distance {
+                        meters: feature.properties.contour * 1000.0,
+                    },
+                    _ => IsochroneContour::Time {
+                        seconds: feature.properties.contour * 60.0,
+                    },
+                },
+                polygons: feature.geometry.into_polygons(),
+            })
+            .collect())
+    }
+}
+
+#[derive(Deserialize)]
+struct OpenRouteServiceIsochroneResponse {
+    features: Vec<Feature<OpenRouteServiceIsochroneProperties>>,
+    metadata: Option<OpenRouteServiceMetadata>,
+}
+
+#[derive(Deserialize)]
+struct OpenRouteServiceIsochroneProperties {
+    /// The contour, in seconds or meters (depending on the range type of the query).
+    value: f64,
+}
+
+#[derive(Deserialize)]
+struct OpenRouteServiceMetadata {
+    query: OpenRouteServiceQuery,
+}
+
+#[derive(Deserialize)]
+struct OpenRouteServiceQuery {
+    range_type: Option<String>,
+}
+
+/// An isochrone response parser for the openrouteservice isochrones endpoint.
+///
+/// This assumes that distances were requested in meters (as the [`OpenRouteServiceIsochroneRequestGenerator`] does).
+#[derive(Debug, Default)]
+pub struct OpenRouteServiceIsochroneResponseParser;
+
+impl IsochroneResponseParser for OpenRouteServiceIsochroneResponseParser {
+    fn parse_response(
+        &self,
+        response: Vec<u8>,
+    ) -> Result<Vec<Isochrone>, RoutingResponseParseError> {
+        let res: OpenRouteServiceIsochroneResponse = serde_json::from_slice(&response)?;
+        let is_distance = res
+            .metadata
+            .and_then(|metadata| metadata.query.range_type)
+            .is_some_and(|range_type| range_type == "distance");
+
+        Ok(res
+            .features
+            .into_iter()
+            .map(|feature| Isochrone {
+                contour: if is_distance {
+                    IsochroneContour::Distance {
+                        meters: feature.properties.value,
+                    }
+                } else {
+                    IsochroneContour::Time {
+                        seconds: feature.properties.value,
+                    }
+                },
+                polygons: feature.geometry.into_polygons(),
+            })
+            .collect())
+    }
+}
+
+/// The isochrone adapter bridges between the common core and an isochrone backend.
+///
+/// Like the [`super::RouteAdapter`], this is split into a request generator and a response parser
+/// so that either half may be implemented in foreign code.
+#[derive(uniffi::Object)]
+pub struct IsochroneAdapter {
+    request_generator: Arc<dyn IsochroneRequestGenerator>,
+    response_parser: Arc<dyn IsochroneResponseParser>,
+}
+
+#[uniffi::export]
+impl IsochroneAdapter {
+    #[uniffi::constructor]
+    pub fn new(
+        request_generator: Arc<dyn IsochroneRequestGenerator>,
+        response_parser: Arc<dyn IsochroneResponseParser>,
+    ) -> Self {
+        Self {
+            request_generator,
+            response_parser,
+        }
+    }
+
+    #[uniffi::constructor]
+    pub fn new_valhalla_http(endpoint_url: String, profile: String) -> Self {
+        Self::new(
+            Arc::new(ValhallaIsochroneRequestGenerator::new(
+                endpoint_url,
+                profile,
+            )),
+            Arc::new(ValhallaIsochroneResponseParser),
+        )
+    }
+
+    #[uniffi::constructor]
+    pub fn new_openrouteservice_http(endpoint_url: String) -> Self {
+        Self::new(
+            Arc::new(OpenRouteServiceIsochroneRequestGenerator::new(endpoint_url)),
+            Arc::new(OpenRouteServiceIsochroneResponseParser),
+        )
+    }
+
+    //
+    // Proxied implementation methods.
+    //
+
+    pub fn generate_request(
+        &self,
+        origin: GeographicCoordinate,
+        contours: Vec<IsochroneContour>,
+    ) -> Result<RouteRequest, RoutingRequestGenerationError> {
+        self.request_generator.generate_request(origin, contours)
+    }
+
+    pub fn parse_response(
+        &self,
+        response: Vec<u8>,
+    ) -> Result<Vec<Isochrone>, RoutingResponseParseError> {
+        self.response_parser.parse_response(response)
+    }
+
+    //
+    // Combined fetch pipeline.
+    //
+
+    /// Generates a request, executes it with the given executor, and parses the isochrones.
+    ///
+    /// As with [`super::RouteAdapter::get_route`], the HTTP request runs on a background thread,
+    /// and this fails with [`RouteFetchError::Cancelled`] if the token is cancelled first.
+    pub async fn get_isochrones(
+        &self,
+        executor: Arc<HttpExecutor>,
+        origin: GeographicCoordinate,
+        contours: Vec<IsochroneContour>,
+        cancellation_token: Option<Arc<CancellationToken>>,
+    ) -> Result<Vec<Isochrone>, RouteFetchError> {
+        let is_cancelled = |token: &Option<Arc<CancellationToken>>| {
+            token.as_ref().is_some_and(|token| token.is_cancelled())
+        };
+        if is_cancelled(&cancellation_token) {
+            return Err(RouteFetchError::Cancelled);
+        }
+
+        let request = self.generate_request(origin, contours)?;
+        let token = cancellation_token.clone();
+        let response = spawn_blocking(move || executor.execute(&request, token)).await??;
+        if is_cancelled(&cancellation_token) {
+            return Err(RouteFetchError::Cancelled);
+        }
+
+        Ok(self.parse_response(response)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::http::test_helpers::{block_on, executor, MockHttpClient};
+    use super::*;
+
+    const VALHALLA_ENDPOINT_URL: &str = "https://valhalla.example.com/isochrone";
+    const ORS_ENDPOINT_URL: &str = "https://api.openrouteservice.org/v2/isochrones/driving-car";
+    const ORIGIN: GeographicCoordinate = GeographicCoordinate {
+        lat: 52.5,
+        lng: 13.4,
+    };
+    const VALHALLA_RESPONSE: &str = r#"{"type":"FeatureCollection","features":[
+        {"type":"Feature","properties":{"contour":15,"metric":"time"},"geometry":{"type":"MultiPolygon","coordinates":[
+            [[[13.3,52.4],[13.5,52.4],[13.5,52.6],[13.3,52.4]],[[13.4,52.45],[13.45,52.45],[13.45,52.5],[13.4,52.45]]],
+            [[[13.6,52.4],[13.7,52.4],[13.7,52.5],[13.6,52.4]]]
+        ]}},
+        {"type":"Feature","properties":{"contour":5,"metric":"time"},"geometry":{"type":"Polygon","coordinates":[
+            [[13.35,52.45],[13.45,52.45],[13.45,52.55],[13.35,52.45]]
+        ]}}
+    ]}"#;
+    const ORS_RESPONSE: &str = r#"{"type":"FeatureCollection","bbox":[13.3,52.4,13.5,52.6],"features":[
+        {"type":"Feature","properties":{"group_index":0,"value":2000.0,"center":[13.4,52.5]},"geometry":{"type":"Polygon","coordinates":[
+            [[13.3,52.4],[13.5,52.4],[13.5,52.6],[13.3,52.4]]
+        ]}}
+    ],"metadata":{"service":"isochrones","query":{"profile":"driving-car","locations":[[13.4,52.5]],"range":[2000.0],"range_type":"distance"}}}"#;
+
+    fn request_body(request: RouteRequest, expected_url: &str) -> JsonValue {
+        let RouteRequest::HttpPost { url, headers, body } = request else {
+            panic!("Expected a POST request");
+        };
+        assert_eq!(url, expected_url);
+        assert_eq!(headers["Content-Type"], "application/json");
+        serde_json::from_slice(&body).expect("Invalid JSON body")
+    }
+
+    #[test]
+    fn valhalla_request_body() {
+        let generator = ValhallaIsochroneRequestGenerator::new(
+            VALHALLA_ENDPOINT_URL.to_string(),
+            "auto".to_string(),
+        );
+
+        assert!(matches!(
+            generator.generate_request(ORIGIN, vec![]),
+            Err(RoutingRequestGenerationError::InvalidOptions)
+        ));
+
+        let request = generator
+            .generate_request(
+                ORIGIN,
+                vec![
+                    IsochroneContour::Time { seconds: 600.0 },
+                    IsochroneContour::Distance { meters: 5000.0 },
+                ],
+            )
+            .expect("Unable to generate request");
+        assert_eq!(
+            request_body(request, VALHALLA_ENDPOINT_URL),
+            json!({
+                "locations": [{"lat": 52.5, "lon": 13.4}],
+                "costing": "auto",
+                "contours": [{"time": 10.0}, {"distance": 5.0}],
+                "polygons": true,
+            })
+        );
+    }
+
+    #[test]
+    fn openrouteservice_request_body() {
+        let generator =
+            OpenRouteServiceIsochroneRequestGenerator::new(ORS_ENDPOINT_URL.to_string());
+
+        let request = generator
+            .generate_request(
+                ORIGIN,
+                vec![
+                    IsochroneContour::Time { seconds: 300.0 },
+                    IsochroneContour::Time { seconds: 600.0 },
+                ],
+            )
+            .expect("Unable to generate request");
+        assert_eq!(
+            request_body(request, ORS_ENDPOINT_URL),
+            json!({
+                "locations": [[13.4, 52.5]],
+                "range": [300.0, 600.0],
+                "range_type": "time",
+                "units": "m",
+            })
+        );
+
+        // Mixed contours are not supported
+        assert!(matches!(
+            generator.generate_request(
+                ORIGIN,
+                vec![
+                    IsochroneContour::Time { seconds: 300.0 },
+                    IsochroneContour::Distance { meters: 5000.0 },
+                ],
+            ),
+            Err(RoutingRequestGenerationError::InvalidOptions)
+        ));
+    }
+
+    #[test]
+    fn parse_valhalla_isochrones() {
+        let isochrones = ValhallaIsochroneResponseParser
+            .parse_response(VALHALLA_RESPONSE.as_bytes().to_vec())
+            .expect("Unable to parse response");
+
+        let [large, small] = isochrones.as_slice() else {
+            panic!("Expected two isochrones; found {isochrones:?}");
+        };
+        assert_eq!(large.contour, IsochroneContour::Time { seconds: 900.0 });
+        assert_eq!(large.polygons.len(), 2);
+        assert_eq!(large.polygons[0].exterior.len(), 4);
+        assert_eq!(large.polygons[0].interiors.len(), 1);
+        assert_eq!(
+            large.polygons[0].exterior[0],
+            GeographicCoordinate {
+                lat: 52.4,
+                lng: 13.3
+            }
+        );
+        assert_eq!(small.contour, IsochroneContour::Time { seconds: 300.0 });
+        assert_eq!(small.polygons.len(), 1);
+        assert!(small.polygons[0].interiors.is_empty());
+    }
+
+    #[test]
+    fn parse_valhalla_error() {
+        assert!(matches!(
+            ValhallaIsochroneResponseParser.parse_response(
+                br#"{"error_code":171,"error":"No suitable edges near location","status_code":400,"status":"Bad Request"}"#.to_vec()
+            ),
+            Err(RoutingResponseParseError::BackendError {
+                error: RoutingBackendError::Other { code },
+                message: Some(_),
+            }) if code == "171"
+        ));
+    }
+
+    #[test]
+    fn parse_openrouteservice_isochrones() {
+        let isochrones = OpenRouteServiceIsochroneResponseParser
+            .parse_response(ORS_RESPONSE.as_bytes().to_vec())
+            .expect("Unable to parse response");
+
+        let [isochrone] = isochrones.as_slice() else {
+            panic!("Expected one isochrone; found {isochrones:?}");
+        };
+        assert_eq!(
+            isochrone.contour,
+            IsochroneContour::Distance { meters: 2000.0 }
+        );
+        assert_eq!(isochrone.polygons.len(), 1);
+    }
+
+    #[test]
+    fn get_isochrones() {
+        let adapter = IsochroneAdapter::new_valhalla_http(
+            VALHALLA_ENDPOINT_URL.to_string(),
+            "auto".to_string(),
+        );
+        let executor = executor(MockHttpClient::new(Ok(VALHALLA_RESPONSE
+            .as_bytes()
+            .to_vec())));
+
+        let isochrones = block_on(adapter.get_isochrones(
+            executor.clone(),
+            ORIGIN,
+            vec![
+                IsochroneContour::Time { seconds: 300.0 },
+                IsochroneContour::Time { seconds: 900.0 },
+            ],
+            None,
+        ))
+        .expect("Unable to fetch isochrones");
+        assert_eq!(isochrones.len(), 2);
+
+        let token = Arc::new(CancellationToken::new());
+        token.cancel();
+        assert!(matches!(
+            block_on(adapter.get_isochrones(
+                executor,
+                ORIGIN,
+                vec![IsochroneContour::Time { seconds: 300.0 }],
+                Some(token),
+            )),
+            Err(RouteFetchError::Cancelled)
+        ));
+    }
+}
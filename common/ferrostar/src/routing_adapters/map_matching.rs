@@ -0,0 +1,454 @@
+//! Map matching support for correcting the user's position in dense road networks.
+//!
+//! Geometric snapping only considers the current fix, so in dense urban grids
+//! it can pick a parallel street which happens to be slightly closer.
+//! Map matching services consider the recent trace as a whole (including turns and timing),
+//! which usually resolves the ambiguity.
+
+use super::error::{
+    RouteFetchError, RoutingBackendError, RoutingRequestGenerationError, RoutingResponseParseError,
+};
+use super::http::{spawn_blocking, CancellationToken, HttpExecutor};
+use super::RouteRequest;
+use crate::models::{GeographicCoordinate, UserLocation};
+use serde::Deserialize;
+use serde_json::{json, Value as JsonValue};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::SystemTime;
+
+/// A trait describing any object capable of generating map matching requests from a trace of locations.
+#[uniffi::export(with_foreign)]
+pub trait MapMatchingRequestGenerator: Send + Sync {
+    /// Generates a map matching request for the trace, which is ordered from oldest to newest.
+    fn generate_request(
+        &self,
+        locations: Vec<UserLocation>,
+    ) -> Result<RouteRequest, RoutingRequestGenerationError>;
+}
+
+/// A trait describing any object capable of parsing a map matching response.
+#[uniffi::export(with_foreign)]
+pub trait MapMatchingResponseParser: Send + Sync {
+    /// Parses the matched coordinate of each location in the trace.
+    ///
+    /// The result has one entry per location, which is `None` if the location could not be matched.
+    fn parse_response(
+        &self,
+        response: Vec<u8>,
+    ) -> Result<Vec<Option<GeographicCoordinate>>, RoutingResponseParseError>;
+}
+
+/// A map matching request generator for the Valhalla `trace_route` endpoint operating over HTTP.
+///
+/// Responses are requested in the OSRM format, so they can be parsed by the [`OsrmMapMatchingResponseParser`].
+#[derive(Debug)]
+pub struct ValhallaMapMatchingRequestGenerator {
+    /// The full URL of the Valhalla `trace_route` endpoint.
+    ///
+    /// Users *may* include a query string with an API key.
+    endpoint_url: String,
+    /// The Valhalla costing model to use.
+    profile: String,
+}
+
+impl ValhallaMapMatchingRequestGenerator {
+    pub fn new(endpoint_url: String, profile: String) -> Self {
+        Self {
+            endpoint_url,
+            profile,
+        }
+    }
+}
+
+impl MapMatchingRequestGenerator for ValhallaMapMatchingRequestGenerator {
+    fn generate_request(
+        &self,
+        locations: Vec<UserLocation>,
+    ) -> Result<RouteRequest, RoutingRequestGenerationError> {
+        if locations.len() < 2 {
+            return Err(RoutingRequestGenerationError::NotEnoughWaypoints);
+        }
+
+        let headers = HashMap::from([("Content-Type".to_string(), "application/json".to_string())]);
+        let shape: Vec<JsonValue> = locations
+            .iter()
+            .map(|location| {
+                let time = location
+                    .timestamp
+                    .duration_since(SystemTime::UNIX_EPOCH)
+                    .map_or(0, |duration| duration.as_secs());
+                json!({
+                    "lat": location.coordinates.lat,
+                    "lon": location.coordinates.lng,
+                    "time": time,
+                    // Valhalla rejects very small search radii
+                    "radius": location.horizontal_accuracy.max(5.0),
+                })
+            })
+            .collect();
+        let args = json!({
+            "format": "osrm",
+            "shape": shape,
+            "costing": &self.profile,
+            "shape_match": "map_snap",
+            "use_timestamps": true,
+        });
+        let body = serde_json::to_vec(&args)?;
+        Ok(RouteRequest::HttpPost {
+            url: self.endpoint_url.clone(),
+            headers,
+            body,
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct MatchResponse {
+    code: String,
+    message: Option<String>,
+    #[serde(default)]
+    tracepoints: Vec<Option<Tracepoint>>,
+}
+
+#[derive(Deserialize)]
+struct Tracepoint {
+    /// The matched coordinate, as `[lng, lat]`.
+    location: [f64; 2],
+}
+
+/// A map matching response parser for the [OSRM match service](http://project-osrm.org/docs/v5.5.1/api/#match-service)
+/// response format (also produced by Valhalla).
+#[derive(Debug, Default)]
+pub struct OsrmMapMatchingResponseParser;
+
+impl MapMatchingResponseParser for OsrmMapMatchingResponseParser {
+    fn parse_response(
+        &self,
+        response: Vec<u8>,
+    ) -> Result<Vec<Option<GeographicCoordinate>>, RoutingResponseParseError> {
+        let res: MatchResponse = serde_json::from_slice(&response)?;
+        if res.code != "Ok" {
+            return Err(RoutingResponseParseError::BackendError {
+                error: RoutingBackendError::from_osrm_code(&res.code),
+                message: res.message,
+            });
+        }
+
+        Ok(res
+            .tracepoints
+            .into_iter()
+            .map(|tracepoint| {
+                tracepoint.map(|tracepoint| GeographicCoordinate {
+                    lng: tracepoint.location[0],
+                    lat: tracepoint.location[1],
+                })
+            })
+            .collect())
+    }
+}
+
+/// The map matching adapter keeps a trace of recent raw location fixes
+/// and uses a map matching backend to correct the position of the most recent one.
+///
+/// Feed every raw fix to [`MapMatchingAdapter::record_location`],
+/// then periodically (ex: when the geometrically snapped position looks suspicious)
+/// call [`MapMatchingAdapter::correct_location`]
+/// and pass the corrected location to the navigation controller instead of the raw one.
+///
+/// Like the [`super::RouteAdapter`], this is split into a request generator and a response parser
+/// so that either half may be implemented in foreign code.
+#[derive(uniffi::Object)]
+pub struct MapMatchingAdapter {
+    request_generator: Arc<dyn MapMatchingRequestGenerator>,
+    response_parser: Arc<dyn MapMatchingResponseParser>,
+    max_trace_length: u16,
+    trace: Mutex<VecDeque<UserLocation>>,
+}
+
+#[uniffi::export]
+impl MapMatchingAdapter {
+    /// Creates an adapter which keeps at most `max_trace_length` recent fixes.
+    #[uniffi::constructor]
+    pub fn new(
+        request_generator: Arc<dyn MapMatchingRequestGenerator>,
+        response_parser: Arc<dyn MapMatchingResponseParser>,
+        max_trace_length: u16,
+    ) -> Self {
+        Self {
+            request_generator,
+            response_parser,
+            max_trace_length,
+            trace: Mutex::new(VecDeque::with_capacity(max_trace_length.into())),
+        }
+    }
+
+    #[uniffi::constructor]
+    pub fn new_valhalla_http(endpoint_url: String, profile: String, max_trace_length: u16) -> Self {
+        Self::new(
+            Arc::new(ValhallaMapMatchingRequestGenerator::new(
+                endpoint_url,
+                profile,
+            )),
+            Arc::new(OsrmMapMatchingResponseParser),
+            max_trace_length,
+        )
+    }
+
+    /// Adds a raw location fix to the trace, discarding the oldest fix if the trace is full.
+    ///
+    /// Fixes which are not newer than the last one recorded are ignored.
+    pub fn record_location(&self, location: UserLocation) {
+        let mut trace = self.lock_trace();
+        if trace
+            .back()
+            .is_some_and(|last| location.timestamp <= last.timestamp)
+        {
+            return;
+        }
+        trace.push_back(location);
+        while trace.len() > self.max_trace_length.into() {
+            trace.pop_front();
+        }
+    }
+
+    /// Discards the trace (ex: after a reroute or a long pause).
+    pub fn clear_trace(&self) {
+        self.lock_trace().clear();
+    }
+
+    /// Returns the recorded trace, ordered from oldest to newest.
+    pub fn trace(&self) -> Vec<UserLocation> {
+        self.lock_trace().iter().copied().collect()
+    }
+
+    //
+    // Proxied implementation methods.
+    //
+
+    pub fn generate_request(
+        &self,
+        locations: Vec<UserLocation>,
+    ) -> Result<RouteRequest, RoutingRequestGenerationError> {
+        self.request_generator.generate_request(locations)
+    }
+
+    pub fn parse_response(
+        &self,
+        response: Vec<u8>,
+    ) -> Result<Vec<Option<GeographicCoordinate>>, RoutingResponseParseError> {
+        self.response_parser.parse_response(response)
+    }
+
+    //
+    // Combined correction pipeline.
+    //
+
+    /// Matches the recorded trace and returns the most recent fix, moved to its matched position.
+    ///
+    /// The fix is returned unchanged if the backend could not match it.
+    /// At least two fixes must have been recorded.
+    /// As with [`super::RouteAdapter::get_route`], the HTTP request runs on a background thread,
+    /// and this fails with [`RouteFetchError::Cancelled`] if the token is cancelled first.
+    pub async fn correct_location(
+        &self,
+        executor: Arc<HttpExecutor>,
+        cancellation_token: Option<Arc<CancellationToken>>,
+    ) -> Result<UserLocation, RouteFetchError> {
+        let is_cancelled = |token: &Option<Arc<CancellationToken>>| {
+            token.as_ref().is_some_and(|token| token.is_cancelled())
+        };
+        if is_cancelled(&cancellation_token) {
+            return Err(RouteFetchError::Cancelled);
+        }
+
+        let trace = self.trace();
+        let Some(&latest) = trace.last() else {
+            return Err(RoutingRequestGenerationError::NotEnoughWaypoints.into());
+        };
+        let request = self.generate_request(trace)?;
+        let token = cancellation_token.clone();
+        let response = spawn_blocking(move || executor.execute(&request, token)).await??;
+        if is_cancelled(&cancellation_token) {
+            return Err(RouteFetchError::Cancelled);
+        }
+
+        let matched = self.parse_response(response)?;
+        Ok(corrected_location(latest, &matched))
+    }
+}
+
+impl MapMatchingAdapter {
+    fn lock_trace(&self) -> MutexGuard<'_, VecDeque<UserLocation>> {
+        self.trace.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Moves `latest` to the matched position of the last tracepoint, if there is one.
+fn corrected_location(
+    latest: UserLocation,
+    matched: &[Option<GeographicCoordinate>],
+) -> UserLocation {
+    match matched.last() {
+        Some(Some(coordinates)) => UserLocation {
+            coordinates: *coordinates,
+            ..latest
+        },
+        _ => latest,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::http::test_helpers::{block_on, executor, MockHttpClient};
+    use super::*;
+    use crate::navigation_controller::test_helpers::gen_user_location;
+    use std::time::Duration;
+
+    const ENDPOINT_URL: &str = "https://valhalla.example.com/trace_route";
+    const MATCH_RESPONSE: &str = r#"{"code":"Ok","matchings":[],"tracepoints":[null,{"location":[13.3951,52.5175],"name":"Unter den Linden","matchings_index":0,"waypoint_index":0,"alternatives_count":1}]}"#;
+
+    fn adapter(max_trace_length: u16) -> MapMatchingAdapter {
+        MapMatchingAdapter::new_valhalla_http(
+            ENDPOINT_URL.to_string(),
+            "auto".to_string(),
+            max_trace_length,
+        )
+    }
+
+    #[test]
+    fn valhalla_request_body() {
+        let generator =
+            ValhallaMapMatchingRequestGenerator::new(ENDPOINT_URL.to_string(), "auto".to_string());
+
+        assert!(matches!(
+            generator.generate_request(vec![
+                gen_user_location(13.4, 52.5, 1).with_horizontal_accuracy(12.0)
+            ]),
+            Err(RoutingRequestGenerationError::NotEnoughWaypoints)
+        ));
+
+        let RouteRequest::HttpPost { url, headers, body } = generator
+            .generate_request(vec![
+                gen_user_location(13.4, 52.5, 1).with_horizontal_accuracy(12.0),
+                gen_user_location(13.5, 52.6, 2).with_horizontal_accuracy(12.0),
+            ])
+            .expect("Unable to generate request")
+        else {
+            panic!("Expected a POST request");
+        };
+        assert_eq!(url, ENDPOINT_URL);
+        assert_eq!(headers["Content-Type"], "application/json");
+        let body: JsonValue = serde_json::from_slice(&body).expect("Invalid JSON body");
+        assert_eq!(
+            body,
+            json!({
+                "format": "osrm",
+                "shape": [
+                    {"lat": 52.5, "lon": 13.4, "time": 1, "radius": 12.0},
+                    {"lat": 52.6, "lon": 13.5, "time": 2, "radius": 12.0},
+                ],
+                "costing": "auto",
+                "shape_match": "map_snap",
+                "use_timestamps": true,
+            })
+        );
+    }
+
+    #[test]
+    fn parse_tracepoints() {
+        let matched = OsrmMapMatchingResponseParser
+            .parse_response(MATCH_RESPONSE.as_bytes().to_vec())
+            .expect("Unable to parse response");
+        assert_eq!(
+            matched,
+            vec![
+                None,
+                Some(GeographicCoordinate {
+                    lat: 52.5175,
+                    lng: 13.3951
+                })
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_backend_error() {
+        assert!(matches!(
+            OsrmMapMatchingResponseParser.parse_response(
+                br#"{"code":"NoMatch","message":"Could not match the trace."}"#.to_vec()
+            ),
+            Err(RoutingResponseParseError::BackendError {
+                error: RoutingBackendError::Other { code },
+                message: Some(_),
+            }) if code == "NoMatch"
+        ));
+    }
+
+    #[test]
+    fn trace_is_bounded_and_monotonic() {
+        let adapter = adapter(2);
+        adapter.record_location(gen_user_location(0.0, 0.0, 1));
+        adapter.record_location(gen_user_location(0.0, 0.0, 2));
+        // Out of order
+        adapter.record_location(gen_user_location(1.0, 1.0, 1));
+        adapter.record_location(gen_user_location(0.0, 0.0, 3));
+
+        let timestamps: Vec<_> = adapter
+            .trace()
+            .iter()
+            .map(|location| location.timestamp)
+            .collect();
+        assert_eq!(
+            timestamps,
+            vec![
+                SystemTime::UNIX_EPOCH + Duration::from_secs(2),
+                SystemTime::UNIX_EPOCH + Duration::from_secs(3),
+            ]
+        );
+
+        adapter.clear_trace();
+        assert!(adapter.trace().is_empty());
+    }
+
+    #[test]
+    fn correct_location_uses_matched_position() {
+        let adapter = adapter(10);
+        adapter.record_location(gen_user_location(13.3940, 52.5170, 1));
+        let latest = gen_user_location(13.3950, 52.5178, 2);
+        adapter.record_location(latest);
+
+        let corrected = block_on(adapter.correct_location(
+            executor(MockHttpClient::new(Ok(MATCH_RESPONSE.as_bytes().to_vec()))),
+            None,
+        ))
+        .expect("Unable to correct location");
+        assert_eq!(
+            corrected,
+            UserLocation {
+                coordinates: GeographicCoordinate {
+                    lat: 52.5175,
+                    lng: 13.3951
+                },
+                ..latest
+            }
+        );
+    }
+
+    #[test]
+    fn unmatched_location_is_unchanged() {
+        let latest = gen_user_location(13.3950, 52.5178, 2);
+        assert_eq!(corrected_location(latest, &[None, None]), latest);
+    }
+
+    #[test]
+    fn correct_location_requires_a_trace() {
+        let executor = executor(MockHttpClient::new(Ok(MATCH_RESPONSE.as_bytes().to_vec())));
+        assert!(matches!(
+            block_on(adapter(10).correct_location(executor, None)),
+            Err(RouteFetchError::RequestGenerationError {
+                error: RoutingRequestGenerationError::NotEnoughWaypoints
+            })
+        ));
+    }
+}
@@ -0,0 +1,452 @@
+//! Travel time and distance matrices (the OSRM table service and the Valhalla matrix service).
+//!
+//! A matrix contains the travel time and distance from each of a set of sources
+//! to each of a set of destinations.
+//! These are the usual input for multi-stop optimization (ex: choosing the order of deliveries).
+
+use super::error::{
+    RouteFetchError, RoutingBackendError, RoutingRequestGenerationError, RoutingResponseParseError,
+};
+use super::http::{spawn_blocking, CancellationToken, HttpExecutor};
+use super::RouteRequest;
+use crate::models::GeographicCoordinate;
+use serde::Deserialize;
+use serde_json::{json, Value as JsonValue};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Travel times and distances between sources and destinations.
+///
+/// Both are indexed by source, then by destination.
+/// Entries are `None` if the destination cannot be reached from the source.
+#[derive(Debug, Clone, PartialEq, uniffi::Record)]
+pub struct Matrix {
+    /// The travel times, in seconds.
+    pub durations: Vec<Vec<Option<f64>>>,
+    /// The travel distances, in meters.
+    pub distances: Vec<Vec<Option<f64>>>,
+}
+
+/// A trait describing any object capable of generating matrix requests.
+#[uniffi::export(with_foreign)]
+pub trait MatrixRequestGenerator: Send + Sync {
+    /// Generates a request for the travel times and distances from each source to each destination.
+    fn generate_request(
+        &self,
+        sources: Vec<GeographicCoordinate>,
+        destinations: Vec<GeographicCoordinate>,
+    ) -> Result<RouteRequest, RoutingRequestGenerationError>;
+}
+
+/// A trait describing any object capable of parsing a matrix response.
+#[uniffi::export(with_foreign)]
+pub trait MatrixResponseParser: Send + Sync {
+    fn parse_response(&self, response: Vec<u8>) -> Result<Matrix, RoutingResponseParseError>;
+}
+
+/// A matrix request generator for the [OSRM table service](http://project-osrm.org/docs/v5.5.1/api/#table-service).
+#[derive(Debug)]
+pub struct OsrmMatrixRequestGenerator {
+    /// The base URL of the OSRM server, which is followed by `/table/v1/{profile}`.
+    base_url: String,
+    /// The OSRM profile to use (ex: `driving`).
+    profile: String,
+}
+
+impl OsrmMatrixRequestGenerator {
+    pub fn new(base_url: String, profile: String) -> Self {
+        Self { base_url, profile }
+    }
+}
+
+impl MatrixRequestGenerator for OsrmMatrixRequestGenerator {
+    fn generate_request(
+        &self,
+        sources: Vec<GeographicCoordinate>,
+        destinations: Vec<GeographicCoordinate>,
+    ) -> Result<RouteRequest, RoutingRequestGenerationError> {
+        if sources.is_empty() || destinations.is_empty() {
+            return Err(RoutingRequestGenerationError::NotEnoughWaypoints);
+        }
+
+        let join_indices = |indices: std::ops::Range<usize>| {
+            indices
+                .map(|index| index.to_string())
+                .collect::<Vec<_>>()
+                .join(";")
+        };
+        let coordinates = sources
+            .iter()
+            .chain(destinations.iter())
+            .map(|coordinate| format!("{},{}", coordinate.lng, coordinate.lat))
+            .collect::<Vec<_>>()
+            .join(";");
+        let url = format!(
+            "{}/table/v1/{}/{coordinates}?sources={}&destinations={}&annotations=duration,distance",
+            self.base_url.trim_end_matches('/'),
+            self.profile,
+            join_indices(0..sources.len()),
+            join_indices(sources.len()..sources.len() + destinations.len()),
+        );
+        Ok(RouteRequest::HttpGet {
+            url,
+            headers: HashMap::new(),
+        })
+    }
+}
+
+/// A matrix request generator for the Valhalla `sources_to_targets` endpoint operating over HTTP.
+#[derive(Debug)]
+pub struct ValhallaMatrixRequestGenerator {
+    /// The full URL of the Valhalla `sources_to_targets` endpoint.
+    ///
+    /// Users *may* include a query string with an API key.
+    endpoint_url: String,
+    /// The Valhalla costing model to use.
+    profile: String,
+}
+
+impl ValhallaMatrixRequestGenerator {
+    pub fn new(endpoint_url: String, profile: String) -> Self {
+        Self {
+            endpoint_url,
+            profile,
+        }
+    }
+}
+
+impl MatrixRequestGenerator for ValhallaMatrixRequestGenerator {
+    fn generate_request(
+        &self,
+        sources: Vec<GeographicCoordinate>,
+        destinations: Vec<GeographicCoordinate>,
+    ) -> Result<RouteRequest, RoutingRequestGenerationError> {
+        if sources.is_empty() || destinations.is_empty() {
+            return Err(RoutingRequestGenerationError::NotEnoughWaypoints);
+        }
+
+        let headers = HashMap::from([("Content-Type".to_string(), "application/json".to_string())]);
+        let locations = |coordinates: &[GeographicCoordinate]| -> Vec<JsonValue> {
+            coordinates
+                .iter()
+                .map(|coordinate| json!({ "lat": coordinate.lat, "lon": coordinate.lng }))
+                .collect()
+        };
+        let args = json!({
+            "sources": locations(&sources),
+            "targets": locations(&destinations),
+            "costing": &self.profile,
+            "units": "kilometers",
+        });
+        let body = serde_json::to_vec(&args)?;
+        Ok(RouteRequest::HttpPost {
+            url: self.endpoint_url.clone(),
+            headers,
+            body,
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct OsrmTableResponse {
+    code: String,
+    message: Option<String>,
+    #[serde(default)]
+    durations: Vec<Vec<Option<f64>>>,
+    #[serde(default)]
+    distances: Vec<Vec<Option<f64>>>,
+}
+
+/// A matrix response parser for the OSRM table service response format.
+#[derive(Debug, Default)]
+pub struct OsrmMatrixResponseParser;
+
+impl MatrixResponseParser for OsrmMatrixResponseParser {
+    fn parse_response(&self, response: Vec<u8>) -> Result<Matrix, RoutingResponseParseError> {
+        let res: OsrmTableResponse = serde_json::from_slice(&response)?;
+        if res.code != "Ok" {
+            return Err(RoutingResponseParseError::BackendError {
+                error: RoutingBackendError::from_osrm_code(&res.code),
+                message: res.message,
+            });
+        }
+
+        Ok(Matrix {
+            durations: res.durations,
+            distances: res.distances,
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct ValhallaMatrixResponse {
+    #[serde(default)]
+    sources_to_targets: Vec<Vec<ValhallaMatrixEntry>>,
+    error_code: Option<u32>,
+    error: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ValhallaMatrixEntry {
+    /// The travel time, in seconds.
+    time: Option<f64>,
+    /// The travel distance, in kilometers.
+    distance: Option<f64>,
+}
+
+/// A matrix response parser for the Valhalla `sources_to_targets` endpoint (JSON output).
+///
+/// This assumes that distances were requested in kilometers (as the [`ValhallaMatrixRequestGenerator`] does).
+#[derive(Debug, Default)]
+pub struct ValhallaMatrixResponseParser;
+
+impl MatrixResponseParser for ValhallaMatrixResponseParser {
+    fn parse_response(&self, response: Vec<u8>) -> Result<Matrix, RoutingResponseParseError> {
+        let res: ValhallaMatrixResponse = serde_json::from_slice(&response)?;
+        if let Some(code) = res.error_code {
+            return Err(RoutingResponseParseError::BackendError {
+                error: RoutingBackendError::Other {
+                    code: code.to_string(),
+                },
+                message: res.error,
+            });
+        }
+
+        let (durations, distances) = res
+            .sources_to_targets
+            .iter()
+            .map(|row| {
+                let durations = row.iter().map(|entry| entry.time).collect();
+                let distances = row
+                    .iter()
+                    .map(|entry| entry.distance.map(|distance| distance * 1000.0))
+                    .collect();
+                (durations, distances)
+            })
+            .unzip();
+        Ok(Matrix {
+            durations,
+            distances,
+        })
+    }
+}
+
+/// The matrix adapter bridges between the common core and a matrix backend.
+///
+/// Like the [`super::RouteAdapter`], this is split into a request generator and a response parser
+/// so that either half may be implemented in foreign code.
+#[derive(uniffi::Object)]
+pub struct MatrixAdapter {
+    request_generator: Arc<dyn MatrixRequestGenerator>,
+    response_parser: Arc<dyn MatrixResponseParser>,
+}
+
+#[uniffi::export]
+impl MatrixAdapter {
+    #[uniffi::constructor]
+    pub fn new(
+        request_generator: Arc<dyn MatrixRequestGenerator>,
+        response_parser: Arc<dyn MatrixResponseParser>,
+    ) -> Self {
+        Self {
+            request_generator,
+            response_parser,
+        }
+    }
+
+    #[uniffi::constructor]
+    pub fn new_osrm_http(base_url: String, profile: String) -> Self {
+        Self::new(
+            Arc::new(OsrmMatrixRequestGenerator::new(base_url, profile)),
+            Arc::new(OsrmMatrixResponseParser),
+        )
+    }
+
+    #[uniffi::constructor]
+    pub fn new_valhalla_http(endpoint_url: String, profile: String) -> Self {
+        Self::new(
+            Arc::new(ValhallaMatrixRequestGenerator::new(endpoint_url, profile)),
+            Arc::new(ValhallaMatrixResponseParser),
+        )
+    }
+
+    //
+    // Proxied implementation methods.
+    //
+
+    pub fn generate_request(
+        &self,
+        sources: Vec<GeographicCoordinate>,
+        destinations: Vec<GeographicCoordinate>,
+    ) -> Result<RouteRequest, RoutingRequestGenerationError> {
+        self.request_generator
+            .generate_request(sources, destinations)
+    }
+
+    pub fn parse_response(&self, response: Vec<u8>) -> Result<Matrix, RoutingResponseParseError> {
+        self.response_parser.parse_response(response)
+    }
+
+    //
+    // Combined fetch pipeline.
+    //
+
+    /// Generates a request, executes it with the given executor, and parses the matrix.
+    ///
+    /// As with [`super::RouteAdapter::get_route`], the HTTP request runs on a background thread,
+    /// and this fails with [`RouteFetchError::Cancelled`] if the token is cancelled first.
+    pub async fn get_matrix(
+        &self,
+        executor: Arc<HttpExecutor>,
+        sources: Vec<GeographicCoordinate>,
+        destinations: Vec<GeographicCoordinate>,
+        cancellation_token: Option<Arc<CancellationToken>>,
+    ) -> Result<Matrix, RouteFetchError> {
+        let is_cancelled = |token: &Option<Arc<CancellationToken>>| {
+            token.as_ref().is_some_and(|token| token.is_cancelled())
+        };
+        if is_cancelled(&cancellation_token) {
+            return Err(RouteFetchError::Cancelled);
+        }
+
+        let request = self.generate_request(sources, destinations)?;
+        let token = cancellation_token.clone();
+        let response = spawn_blocking(move || executor.execute(&request, token)).await??;
+        if is_cancelled(&cancellation_token) {
+            return Err(RouteFetchError::Cancelled);
+        }
+
+        Ok(self.parse_response(response)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::http::test_helpers::{block_on, executor, MockHttpClient};
+    use super::*;
+
+    const SOURCES: [GeographicCoordinate; 1] = [GeographicCoordinate {
+        lat: 52.5,
+        lng: 13.4,
+    }];
+    const DESTINATIONS: [GeographicCoordinate; 2] = [
+        GeographicCoordinate {
+            lat: 52.6,
+            lng: 13.5,
+        },
+        GeographicCoordinate {
+            lat: 52.7,
+            lng: 13.6,
+        },
+    ];
+    const OSRM_RESPONSE: &str = r#"{"code":"Ok","durations":[[600.5,null]],"distances":[[8000.0,null]],"sources":[],"destinations":[]}"#;
+    const VALHALLA_RESPONSE: &str = r#"{"sources_to_targets":[[{"distance":8.0,"time":600,"to_index":0,"from_index":0},{"distance":null,"time":null,"to_index":1,"from_index":0}]],"units":"kilometers"}"#;
+
+    #[test]
+    fn osrm_request_url() {
+        let generator = OsrmMatrixRequestGenerator::new(
+            "https://router.project-osrm.org/".to_string(),
+            "driving".to_string(),
+        );
+
+        assert!(matches!(
+            generator.generate_request(SOURCES.to_vec(), vec![]),
+            Err(RoutingRequestGenerationError::NotEnoughWaypoints)
+        ));
+
+        let request = generator
+            .generate_request(SOURCES.to_vec(), DESTINATIONS.to_vec())
+            .expect("Unable to generate request");
+        assert_eq!(
+            request,
+            RouteRequest::HttpGet {
+                url: "https://router.project-osrm.org/table/v1/driving/13.4,52.5;13.5,52.6;13.6,52.7?sources=0&destinations=1;2&annotations=duration,distance".to_string(),
+                headers: HashMap::new(),
+            }
+        );
+    }
+
+    #[test]
+    fn valhalla_request_body() {
+        let generator = ValhallaMatrixRequestGenerator::new(
+            "https://valhalla.example.com/sources_to_targets".to_string(),
+            "auto".to_string(),
+        );
+
+        let RouteRequest::HttpPost { body, .. } = generator
+            .generate_request(SOURCES.to_vec(), DESTINATIONS.to_vec())
+            .expect("Unable to generate request")
+        else {
+            panic!("Expected a POST request");
+        };
+        let body: JsonValue = serde_json::from_slice(&body).expect("Invalid JSON body");
+        assert_eq!(
+            body,
+            json!({
+                "sources": [{"lat": 52.5, "lon": 13.4}],
+                "targets": [{"lat": 52.6, "lon": 13.5}, {"lat": 52.7, "lon": 13.6}],
+                "costing": "auto",
+                "units": "kilometers",
+            })
+        );
+    }
+
+    #[test]
+    fn parse_osrm_and_valhalla_matrices() {
+        let expected = Matrix {
+            durations: vec![vec![Some(600.5), None]],
+            distances: vec![vec![Some(8000.0), None]],
+        };
+        assert_eq!(
+            OsrmMatrixResponseParser
+                .parse_response(OSRM_RESPONSE.as_bytes().to_vec())
+                .expect("Unable to parse response"),
+            expected
+        );
+        assert_eq!(
+            ValhallaMatrixResponseParser
+                .parse_response(VALHALLA_RESPONSE.as_bytes().to_vec())
+                .expect("Unable to parse response"),
+            Matrix {
+                durations: vec![vec![Some(600.0), None]],
+                ..expected
+            }
+        );
+    }
+
+    #[test]
+    fn parse_backend_errors() {
+        assert!(matches!(
+            OsrmMatrixResponseParser.parse_response(
+                br#"{"code":"InvalidQuery","message":"Query string malformed"}"#.to_vec()
+            ),
+            Err(RoutingResponseParseError::BackendError {
+                error: RoutingBackendError::InvalidQuery,
+                message: Some(_),
+            })
+        ));
+        assert!(matches!(
+            ValhallaMatrixResponseParser.parse_response(
+                br#"{"error_code":154,"error":"Path distance exceeds the max distance limit","status_code":400}"#.to_vec()
+            ),
+            Err(RoutingResponseParseError::BackendError {
+                error: RoutingBackendError::Other { code },
+                message: Some(_),
+            }) if code == "154"
+        ));
+    }
+
+    #[test]
+    fn get_matrix() {
+        let adapter = MatrixAdapter::new_osrm_http(
+            "https://router.project-osrm.org".to_string(),
+            "driving".to_string(),
+        );
+        let executor = executor(MockHttpClient::new(Ok(OSRM_RESPONSE.as_bytes().to_vec())));
+
+        let matrix =
+            block_on(adapter.get_matrix(executor, SOURCES.to_vec(), DESTINATIONS.to_vec(), None))
+                .expect("Unable to fetch matrix");
+        assert_eq!(matrix.durations, vec![vec![Some(600.5), None]]);
+    }
+}
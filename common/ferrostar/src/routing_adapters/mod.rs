@@ -1,26 +1,47 @@
 use crate::models::Waypoint;
 use crate::routing_adapters::error::InstantiationError;
 use crate::{
-    create_osrm_response_parser, create_valhalla_request_generator,
+    create_localized_osrm_response_parser, create_osrm_response_parser,
+    create_valhalla_request_generator, create_valhalla_request_generator_with_options,
     models::{Route, UserLocation},
 };
-use error::{RoutingRequestGenerationError, RoutingResponseParseError};
+use error::{RouteFetchError, RoutingRequestGenerationError, RoutingResponseParseError};
+use http::{spawn_blocking, CancellationToken, HttpExecutor};
+use interceptors::{RouteRequestInterceptor, RouteResponseInterceptor};
+use post_processing::{process_routes, RoutePostProcessor};
 use std::collections::HashMap;
 use std::fmt::Debug;
 use std::sync::Arc;
+use valhalla::ValhallaRequestOptions;
 
 pub mod error;
+pub mod ev;
+pub mod http;
+pub mod interceptors;
+pub mod isochrones;
+pub mod map_matching;
+pub mod matrix;
 pub mod osrm;
+pub mod otp;
+pub mod post_processing;
+pub mod protobuf;
 pub mod valhalla;
+pub mod valhalla_multimodal;
+mod validation;
 
 /// A route request generated by a [`RouteRequestGenerator`].
-#[derive(PartialEq, Debug, uniffi::Enum)]
+#[derive(Clone, PartialEq, Debug, uniffi::Enum)]
 pub enum RouteRequest {
     HttpPost {
         url: String,
         headers: HashMap<String, String>,
         body: Vec<u8>,
     },
+    /// A request with all parameters in the URL (ex: for the OSRM table service).
+    HttpGet {
+        url: String,
+        headers: HashMap<String, String>,
+    },
 }
 
 /// A trait describing any object capable of generating [`RouteRequest`]s.
@@ -57,6 +78,9 @@ pub trait RouteResponseParser: Send + Sync {
     ///
     /// We use a sequence of octets as a common interchange format.
     /// as this works for all currently conceivable formats (JSON, PBF, etc.).
+    ///
+    /// Parsers of fresh backend responses should set [`Route::planned_at`]
+    /// (ex: from a [`crate::clock::Clock`]), so that stale routes can be detected later.
     fn parse_response(&self, response: Vec<u8>) -> Result<Vec<Route>, RoutingResponseParseError>;
 }
 
@@ -84,6 +108,9 @@ pub trait RouteResponseParser: Send + Sync {
 pub struct RouteAdapter {
     request_generator: Arc<dyn RouteRequestGenerator>,
     response_parser: Arc<dyn RouteResponseParser>,
+    request_interceptor: Option<Arc<dyn RouteRequestInterceptor>>,
+    response_interceptor: Option<Arc<dyn RouteResponseInterceptor>>,
+    post_processors: Vec<Arc<dyn RoutePostProcessor>>,
 }
 
 #[uniffi::export]
@@ -92,10 +119,31 @@ impl RouteAdapter {
     pub fn new(
         request_generator: Arc<dyn RouteRequestGenerator>,
         response_parser: Arc<dyn RouteResponseParser>,
+    ) -> Self {
+        Self::new_with_processing(request_generator, response_parser, None, None, vec![])
+    }
+
+    /// Creates a route adapter which passes requests and responses through the given interceptors,
+    /// and runs each parsed route through the post-processors.
+    ///
+    /// The request interceptor runs after the request is generated,
+    /// and the response interceptor runs before the response is parsed.
+    /// The post-processors run in order after the response is parsed
+    /// (see [`post_processing`] for details).
+    #[uniffi::constructor]
+    pub fn new_with_processing(
+        request_generator: Arc<dyn RouteRequestGenerator>,
+        response_parser: Arc<dyn RouteResponseParser>,
+        request_interceptor: Option<Arc<dyn RouteRequestInterceptor>>,
+        response_interceptor: Option<Arc<dyn RouteResponseInterceptor>>,
+        post_processors: Vec<Arc<dyn RoutePostProcessor>>,
     ) -> Self {
         Self {
             request_generator,
             response_parser,
+            request_interceptor,
+            response_interceptor,
+            post_processors,
         }
     }
 
@@ -111,6 +159,32 @@ impl RouteAdapter {
         Ok(Self::new(request_generator, response_parser))
     }
 
+    /// Creates a route adapter for Valhalla with any combination of request options,
+    /// which runs each parsed route through the post-processors.
+    ///
+    /// If a language is set, the response parser synthesizes any missing instructions in it too.
+    #[uniffi::constructor]
+    pub fn new_valhalla_http_with_options(
+        endpoint_url: String,
+        profile: String,
+        options: ValhallaRequestOptions,
+        post_processors: Vec<Arc<dyn RoutePostProcessor>>,
+    ) -> Result<Self, InstantiationError> {
+        let response_parser = match options.language.clone() {
+            Some(locale) => create_localized_osrm_response_parser(6, locale),
+            None => create_osrm_response_parser(6),
+        };
+        let request_generator =
+            create_valhalla_request_generator_with_options(endpoint_url, profile, options)?;
+        Ok(Self::new_with_processing(
+            request_generator,
+            response_parser,
+            None,
+            None,
+            post_processors,
+        ))
+    }
+
     //
     // Proxied implementation methods.
     //
@@ -120,14 +194,257 @@ impl RouteAdapter {
         user_location: UserLocation,
         waypoints: Vec<Waypoint>,
     ) -> Result<RouteRequest, RoutingRequestGenerationError> {
-        self.request_generator
-            .generate_request(user_location, waypoints)
+        let request = self
+            .request_generator
+            .generate_request(user_location, waypoints)?;
+        match &self.request_interceptor {
+            Some(interceptor) => interceptor.intercept_request(request),
+            None => Ok(request),
+        }
     }
 
     pub fn parse_response(
         &self,
         response: Vec<u8>,
     ) -> Result<Vec<Route>, RoutingResponseParseError> {
-        self.response_parser.parse_response(response)
+        if let Some(interceptor) = &self.response_interceptor {
+            interceptor.intercept_response(response.clone());
+        }
+        let routes = self.response_parser.parse_response(response)?;
+        Ok(process_routes(&self.post_processors, routes))
+    }
+
+    //
+    // Combined fetch pipeline.
+    //
+
+    /// Generates a request, executes it with the given executor, and parses the first route.
+    ///
+    /// The HTTP request runs on a background thread, so this is safe to await from UI code.
+    /// If a cancellation token is supplied and is cancelled before the route is returned
+    /// (ex: because a newer reroute request has superseded this one),
+    /// this fails with [`RouteFetchError::Cancelled`].
+    pub async fn get_route(
+        &self,
+        executor: Arc<HttpExecutor>,
+        user_location: UserLocation,
+        waypoints: Vec<Waypoint>,
+        cancellation_token: Option<Arc<CancellationToken>>,
+    ) -> Result<Route, RouteFetchError> {
+        let is_cancelled = |token: &Option<Arc<CancellationToken>>| {
+            token.as_ref().is_some_and(|token| token.is_cancelled())
+        };
+        if is_cancelled(&cancellation_token) {
+            return Err(RouteFetchError::Cancelled);
+        }
+
+        let request = self.generate_request(user_location, waypoints)?;
+        let token = cancellation_token.clone();
+        let response = spawn_blocking(move || executor.execute(&request, token)).await??;
+        if is_cancelled(&cancellation_token) {
+            return Err(RouteFetchError::Cancelled);
+        }
+
+        self.parse_response(response)?.into_iter().next().ok_or(
+            RouteFetchError::ResponseParseError {
+                error: RoutingResponseParseError::NoRoutes,
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::http::test_helpers::{block_on, executor, MockHttpClient, OSRM_RESPONSE};
+    use super::http::HttpError;
+    use super::*;
+    use crate::models::{GeographicCoordinate, WaypointKind};
+    use crate::navigation_controller::test_helpers::gen_user_location;
+    use crate::{create_osrm_response_parser, create_valhalla_request_generator};
+    use std::sync::Mutex;
+
+    fn adapter() -> RouteAdapter {
+        RouteAdapter::new(
+            create_valhalla_request_generator(
+                "https://valhalla.example.com/route".to_string(),
+                "auto".to_string(),
+                None,
+            )
+            .expect("Unable to create request generator"),
+            create_osrm_response_parser(6),
+        )
+    }
+
+    fn waypoints() -> Vec<Waypoint> {
+        vec![Waypoint {
+            coordinate: GeographicCoordinate { lat: 0.0, lng: 0.0 },
+            kind: WaypointKind::Break,
+        }]
+    }
+
+    fn get_route(
+        executor: Arc<HttpExecutor>,
+        cancellation_token: Option<Arc<CancellationToken>>,
+    ) -> Result<Route, RouteFetchError> {
+        block_on(adapter().get_route(
+            executor,
+            gen_user_location(0.0, 0.0, 0),
+            waypoints(),
+            cancellation_token,
+        ))
+    }
+
+    #[test]
+    fn get_route_success() {
+        let executor = executor(MockHttpClient::new(Ok(OSRM_RESPONSE.as_bytes().to_vec())));
+        assert!(get_route(executor, None).is_ok());
+    }
+
+    #[test]
+    fn get_route_http_error() {
+        let executor = executor(MockHttpClient::new(Err(HttpError::HttpStatus {
+            status: 500,
+        })));
+        assert!(matches!(
+            get_route(executor, None),
+            Err(RouteFetchError::HttpError {
+                error: HttpError::HttpStatus { status: 500 }
+            })
+        ));
+    }
+
+    #[test]
+    fn get_route_parse_error() {
+        let executor = executor(MockHttpClient::new(Ok(b"not json".to_vec())));
+        assert!(matches!(
+            get_route(executor, None),
+            Err(RouteFetchError::ResponseParseError {
+                error: RoutingResponseParseError::InvalidJson { .. }
+            })
+        ));
+    }
+
+    #[test]
+    fn get_route_cancelled() {
+        let executor = executor(MockHttpClient::new(Ok(OSRM_RESPONSE.as_bytes().to_vec())));
+        let token = Arc::new(CancellationToken::new());
+        token.cancel();
+        assert!(matches!(
+            get_route(executor, Some(token)),
+            Err(RouteFetchError::Cancelled)
+        ));
+    }
+
+    struct AuthInterceptor;
+
+    impl RouteRequestInterceptor for AuthInterceptor {
+        fn intercept_request(
+            &self,
+            request: RouteRequest,
+        ) -> Result<RouteRequest, RoutingRequestGenerationError> {
+            let RouteRequest::HttpPost {
+                url,
+                mut headers,
+                body,
+            } = request
+            else {
+                return Ok(request);
+            };
+            headers.insert("Authorization".to_string(), "Bearer secret".to_string());
+            Ok(RouteRequest::HttpPost { url, headers, body })
+        }
+    }
+
+    #[derive(Default)]
+    struct RecordingInterceptor {
+        responses: Mutex<Vec<Vec<u8>>>,
+    }
+
+    impl RouteResponseInterceptor for RecordingInterceptor {
+        fn intercept_response(&self, response: Vec<u8>) {
+            self.responses
+                .lock()
+                .expect("Unable to lock responses")
+                .push(response);
+        }
+    }
+
+    #[test]
+    fn request_interceptor_modifies_request() {
+        let adapter = RouteAdapter::new_with_processing(
+            adapter().request_generator,
+            create_osrm_response_parser(6),
+            Some(Arc::new(AuthInterceptor)),
+            None,
+            vec![],
+        );
+        let RouteRequest::HttpPost { headers, .. } = adapter
+            .generate_request(gen_user_location(0.0, 0.0, 0), waypoints())
+            .expect("Unable to generate request")
+        else {
+            panic!("Expected a POST request");
+        };
+        assert_eq!(
+            headers.get("Authorization").map(String::as_str),
+            Some("Bearer secret")
+        );
+    }
+
+    #[test]
+    fn response_interceptor_observes_raw_response() {
+        let interceptor = Arc::new(RecordingInterceptor::default());
+        let adapter = RouteAdapter::new_with_processing(
+            adapter().request_generator,
+            create_osrm_response_parser(6),
+            None,
+            Some(interceptor.clone()),
+            vec![],
+        );
+        let response = OSRM_RESPONSE.as_bytes().to_vec();
+        adapter
+            .parse_response(response.clone())
+            .expect("Unable to parse response");
+        assert_eq!(
+            *interceptor
+                .responses
+                .lock()
+                .expect("Unable to lock responses"),
+            vec![response]
+        );
+    }
+
+    #[test]
+    fn post_processors_transform_parsed_routes() {
+        struct ClearWeight;
+
+        impl RoutePostProcessor for ClearWeight {
+            fn process_route(&self, mut route: Route) -> Route {
+                route.weight = None;
+                route
+            }
+        }
+
+        let interceptor = Arc::new(RecordingInterceptor::default());
+        let adapter = RouteAdapter::new_with_processing(
+            adapter().request_generator,
+            create_osrm_response_parser(6),
+            None,
+            Some(interceptor.clone()),
+            vec![Arc::new(ClearWeight)],
+        );
+        let routes = adapter
+            .parse_response(OSRM_RESPONSE.as_bytes().to_vec())
+            .expect("Unable to parse response");
+        assert_eq!(routes.len(), 1);
+        assert_eq!(routes[0].weight, None);
+        // Interceptors still run alongside the post-processors
+        assert_eq!(
+            interceptor
+                .responses
+                .lock()
+                .expect("Unable to lock responses")
+                .len(),
+            1
+        );
     }
 }
@@ -1,37 +1,114 @@
 pub(crate) mod models;
+pub mod streaming;
 
 use super::RouteResponseParser;
+use crate::algorithms::decode_polyline;
+use crate::clock::Clock;
+use crate::logging::{enter_span, log_event};
 use crate::models::{
-    GeographicCoordinate, RouteStep, SpokenInstruction, VisualInstruction,
+    ChargingStop, CountryChange, GeographicCoordinate, Incident, Intersection, JunctionView,
+    RoadShield, RouteNotice, RouteStep, SpokenInstruction, Tunnel, VisualInstruction,
     VisualInstructionContent, Waypoint, WaypointKind,
 };
+use crate::now;
 use crate::routing_adapters::{
-    osrm::models::{RouteResponse, RouteStep as OsrmRouteStep},
+    error::{ResponseIndex, RoutingBackendError},
+    ev::connector_type_from_osrm_name,
+    osrm::models::{
+        BannerComponent, BannerContent, BannerInstruction, IntersectionAdmin, Intersections,
+        RouteLeg, RouteResponse, RouteStep as OsrmRouteStep, StepAdmins, StepBanners, StepSignage,
+        Waypoint as OsrmWaypoint,
+    },
+    validation::{validate_geometry, validate_leg_steps},
     Route, RoutingResponseParseError,
 };
-use geo::BoundingRect;
-use polyline::decode_polyline;
-use std::collections::HashSet;
+use crate::ssml::{augment_ssml, PhonemeHint};
+use geo::{BoundingRect, HaversineDistance, LineString, Point};
+use serde_json::value::RawValue;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::SystemTime;
 use uuid::Uuid;
 
 /// A response parser for OSRM-compatible routing backends.
 ///
 /// The parser is NOT limited to only the standard OSRM format; many Valhalla/Mapbox tags are also
 /// parsed and are included in the final route.
-#[derive(Debug)]
 pub struct OsrmResponseParser {
     polyline_precision: u32,
+    lazy_step_parsing: bool,
+    /// The locale for synthesized instructions.
+    locale: String,
+    /// The clock for [`Route::planned_at`] (the system clock when this is `None`).
+    clock: Option<Arc<dyn Clock>>,
 }
 
+/// The default locale for synthesized instructions.
+const DEFAULT_LOCALE: &str = "en-US";
+
 impl OsrmResponseParser {
     pub fn new(polyline_precision: u32) -> Self {
-        Self { polyline_precision }
+        Self {
+            polyline_precision,
+            lazy_step_parsing: false,
+            locale: DEFAULT_LOCALE.to_string(),
+            clock: None,
+        }
     }
-}
 
-impl RouteResponseParser for OsrmResponseParser {
-    fn parse_response(&self, response: Vec<u8>) -> Result<Vec<Route>, RoutingResponseParseError> {
-        let res: RouteResponse = serde_json::from_slice(&response)?;
+    /// Creates a parser which defers parsing each step until it is converted.
+    ///
+    /// By default, the entire response is deserialized up front,
+    /// so the OSRM models for every step (including intersections, annotations, etc.)
+    /// are held in memory at once.
+    /// With lazy step parsing, the response is first scanned without copying the steps,
+    /// and each step is then parsed and converted on its own.
+    /// This keeps peak memory usage much lower for very long routes (ex: cross-country trucking),
+    /// at the cost of scanning the steps twice.
+    pub fn with_lazy_step_parsing(polyline_precision: u32) -> Self {
+        Self {
+            polyline_precision,
+            lazy_step_parsing: true,
+            locale: DEFAULT_LOCALE.to_string(),
+            clock: None,
+        }
+    }
+
+    /// Sets the locale (ex: `de-DE`) of the instructions which are synthesized
+    /// when the backend does not include any (ex: vanilla OSRM).
+    ///
+    /// This should match the language requested from the backend.
+    /// See [`crate::instructions`] for the supported languages.
+    #[must_use]
+    pub fn with_locale(mut self, locale: String) -> Self {
+        self.locale = locale;
+        self
+    }
+
+    /// Sets the clock used to stamp [`Route::planned_at`] on parsed routes,
+    /// which should be the same one as in the navigation controller's config.
+    #[must_use]
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = Some(clock);
+        self
+    }
+
+    /// The current time, according to the configured clock.
+    fn now(&self) -> SystemTime {
+        self.clock.as_ref().map_or_else(now, |clock| clock.now())
+    }
+
+    fn parse_routes<S: OsrmStep>(
+        &self,
+        res: RouteResponse<S>,
+    ) -> Result<Vec<Route>, RoutingResponseParseError> {
+        if res.code != "Ok" {
+            return Err(RoutingResponseParseError::BackendError {
+                error: RoutingBackendError::from_osrm_code(&res.code),
+                message: res.message,
+            });
+        }
+
         let via_waypoint_indices: HashSet<_> = res
             .routes
             .iter()
@@ -59,56 +136,282 @@ impl RouteResponseParser for OsrmResponseParser {
                 },
             })
             .collect();
+        let charging_stops: Vec<_> = res.waypoints.iter().filter_map(charging_stop).collect();
+        let warnings: Vec<_> = res
+            .warnings
+            .iter()
+            .map(|warning| RouteNotice {
+                code: warning.code,
+                kind: None,
+                message: warning.text.clone(),
+            })
+            .collect();
+
+        let planned_at = self.now();
 
         // This isn't the most functional in style, but it's a bit difficult to construct a pipeline
         // today. Stabilization of try_collect may help.
         let mut routes = vec![];
-        for route in res.routes {
-            let linestring =
-                decode_polyline(&route.geometry, self.polyline_precision).map_err(|error| {
-                    RoutingResponseParseError::ParseError {
-                        error: error.clone(),
-                    }
-                })?;
-            if let Some(bbox) = linestring.bounding_rect() {
-                let geometry = linestring
-                    .coords()
-                    .map(|coord| GeographicCoordinate::from(*coord))
-                    .collect();
-
-                let mut steps = vec![];
-                for leg in route.legs {
-                    for step in leg.steps {
-                        steps.push(RouteStep::from_osrm(&step, self.polyline_precision)?);
-                    }
-                }
+        for (route_index, route) in res.routes.into_iter().enumerate() {
+            let index = ResponseIndex::route(route_index);
+            let linestring = decode_polyline(&route.geometry, self.polyline_precision)
+                .map_err(|error| RoutingResponseParseError::GeometryDecodeError { index, error })?;
+            let geometry: Vec<_> = linestring
+                .coords()
+                .map(|coord| GeographicCoordinate::from(*coord))
+                .collect();
+            validate_geometry(&geometry, index)?;
+            let Some(bbox) = linestring.bounding_rect() else {
+                return Err(RoutingResponseParseError::EmptyGeometry { index });
+            };
 
-                routes.push(Route {
-                    geometry,
-                    bbox: bbox.into(),
-                    distance: route.distance,
-                    waypoints: waypoints.clone(),
-                    steps,
-                });
+            let mut steps = vec![];
+            let mut incidents = vec![];
+            let mut notices = warnings.clone();
+            let mut leg_start_distance = 0.0;
+            let mut country = None;
+            for (leg_index, leg) in route.legs.iter().enumerate() {
+                let leg_index = index.leg(leg_index);
+                let mut leg_steps = leg
+                    .steps
+                    .iter()
+                    .enumerate()
+                    .map(|(step_index, step)| {
+                        step.to_route_step(
+                            self.polyline_precision,
+                            &self.locale,
+                            leg_index.step(step_index),
+                        )
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                validate_leg_steps(&leg_steps, leg_index)?;
+                apply_signage(&mut leg_steps, leg)?;
+                apply_shield_networks(&mut leg_steps, leg)?;
+                apply_country_changes(&mut leg_steps, leg, &mut country)?;
+
+                incidents.extend(leg_incidents(leg, &leg_steps, leg_start_distance));
+                notices.extend(leg.notifications.iter().map(|notification| RouteNotice {
+                    code: None,
+                    kind: notification.subtype.clone(),
+                    message: notification.message(),
+                }));
+                leg_start_distance += leg.distance;
+                steps.extend(leg_steps);
             }
+            incidents.sort_by(|a, b| {
+                a.start_distance_along_route
+                    .total_cmp(&b.start_distance_along_route)
+            });
+
+            routes.push(Route {
+                geometry,
+                bbox: bbox.into(),
+                distance: route.distance,
+                duration: route.duration,
+                weight: route.weight,
+                weight_name: route.weight_name,
+                waypoints: waypoints.clone(),
+                steps,
+                elevation_profile: None,
+                incidents,
+                charging_stops: charging_stops.clone(),
+                destination_details: None,
+                notices,
+                planned_at: Some(planned_at),
+                extras: HashMap::new(),
+            });
+        }
+
+        if routes.is_empty() {
+            return Err(RoutingResponseParseError::NoRoutes);
         }
 
         Ok(routes)
     }
 }
 
+impl RouteResponseParser for OsrmResponseParser {
+    fn parse_response(&self, response: Vec<u8>) -> Result<Vec<Route>, RoutingResponseParseError> {
+        enter_span!("parse_osrm_response", bytes = response.len());
+        let routes = if self.lazy_step_parsing {
+            let res: RouteResponse<&RawValue> = serde_json::from_slice(&response)?;
+            self.parse_routes(res)?
+        } else {
+            let res: RouteResponse = serde_json::from_slice(&response)?;
+            self.parse_routes(res)?
+        };
+        #[cfg(feature = "backend-extras")]
+        let routes = add_route_extras(routes, &response)?;
+        log_event!(
+            debug,
+            route_count = routes.len(),
+            "Parsed the OSRM response"
+        );
+        Ok(routes)
+    }
+}
+
+/// The fields of OSRM routes which are parsed into [`Route`]s.
+#[cfg(feature = "backend-extras")]
+const PARSED_ROUTE_FIELDS: [&str; 6] = [
+    "geometry",
+    "legs",
+    "distance",
+    "duration",
+    "weight",
+    "weight_name",
+];
+
+/// Adds any fields of the routes in a response which are not otherwise parsed to their extras.
+///
+/// This is done in a separate pass over the response,
+/// as fields cannot be collected by flattening routes with lazily parsed steps.
+#[cfg(feature = "backend-extras")]
+fn add_route_extras(
+    mut routes: Vec<Route>,
+    response: &[u8],
+) -> Result<Vec<Route>, RoutingResponseParseError> {
+    #[derive(serde::Deserialize)]
+    struct RouteFields<'a> {
+        #[serde(borrow, default)]
+        routes: Vec<HashMap<String, &'a RawValue>>,
+    }
+
+    let fields: RouteFields = serde_json::from_slice(response)?;
+    for (route, fields) in routes.iter_mut().zip(fields.routes) {
+        route.extras = fields
+            .into_iter()
+            .filter(|(name, _)| !PARSED_ROUTE_FIELDS.contains(&name.as_str()))
+            .map(|(name, value)| (name, value.get().to_string()))
+            .collect();
+    }
+    Ok(routes)
+}
+
+/// The fields of an OSRM step which are not otherwise parsed, as JSON.
+#[cfg(feature = "backend-extras")]
+fn step_extras(step: &OsrmRouteStep) -> HashMap<String, String> {
+    step.extras
+        .iter()
+        .map(|(name, value)| (name.clone(), value.to_string()))
+        .collect()
+}
+
+#[cfg(not(feature = "backend-extras"))]
+fn step_extras(_step: &OsrmRouteStep) -> HashMap<String, String> {
+    HashMap::new()
+}
+
+/// A step in an OSRM route leg, which may not have been deserialized yet.
+trait OsrmStep {
+    fn to_route_step(
+        &self,
+        polyline_precision: u32,
+        locale: &str,
+        index: ResponseIndex,
+    ) -> Result<RouteStep, RoutingResponseParseError>;
+
+    fn signage(&self) -> Result<StepSignage, RoutingResponseParseError>;
+
+    fn shield_components(&self) -> Result<Vec<BannerComponent>, RoutingResponseParseError>;
+
+    fn intersection_admins(&self) -> Result<Vec<IntersectionAdmin>, RoutingResponseParseError>;
+}
+
+impl OsrmStep for OsrmRouteStep {
+    fn to_route_step(
+        &self,
+        polyline_precision: u32,
+        locale: &str,
+        index: ResponseIndex,
+    ) -> Result<RouteStep, RoutingResponseParseError> {
+        RouteStep::from_osrm(self, polyline_precision, locale, index)
+    }
+
+    fn signage(&self) -> Result<StepSignage, RoutingResponseParseError> {
+        Ok(StepSignage {
+            exits: self.exits.clone(),
+            destinations: self.destinations.clone(),
+            name: self.name.clone(),
+            pronunciation: self.pronunciation.clone(),
+        })
+    }
+
+    fn shield_components(&self) -> Result<Vec<BannerComponent>, RoutingResponseParseError> {
+        Ok(self
+            .banner_instructions
+            .iter()
+            .flat_map(BannerInstruction::shield_components)
+            .cloned()
+            .collect())
+    }
+
+    fn intersection_admins(&self) -> Result<Vec<IntersectionAdmin>, RoutingResponseParseError> {
+        Ok(self
+            .intersections
+            .iter()
+            .map(|intersection| IntersectionAdmin {
+                location: intersection.location,
+                admin_index: intersection.admin_index,
+            })
+            .collect())
+    }
+}
+
+impl OsrmStep for &RawValue {
+    fn to_route_step(
+        &self,
+        polyline_precision: u32,
+        locale: &str,
+        index: ResponseIndex,
+    ) -> Result<RouteStep, RoutingResponseParseError> {
+        // The OSRM step is dropped as soon as it is converted
+        let step: OsrmRouteStep = serde_json::from_str(self.get())?;
+        RouteStep::from_osrm(&step, polyline_precision, locale, index)
+    }
+
+    fn signage(&self) -> Result<StepSignage, RoutingResponseParseError> {
+        Ok(serde_json::from_str(self.get())?)
+    }
+
+    fn shield_components(&self) -> Result<Vec<BannerComponent>, RoutingResponseParseError> {
+        let banners: StepBanners = serde_json::from_str(self.get())?;
+        Ok(banners
+            .banner_instructions
+            .iter()
+            .flat_map(BannerInstruction::shield_components)
+            .cloned()
+            .collect())
+    }
+
+    fn intersection_admins(&self) -> Result<Vec<IntersectionAdmin>, RoutingResponseParseError> {
+        let admins: StepAdmins = serde_json::from_str(self.get())?;
+        Ok(admins.intersections)
+    }
+}
+
 impl RouteStep {
-    fn from_osrm(
+    pub(crate) fn from_osrm(
         value: &OsrmRouteStep,
         polyline_precision: u32,
+        locale: &str,
+        index: ResponseIndex,
     ) -> Result<Self, RoutingResponseParseError> {
         let linestring = decode_polyline(&value.geometry, polyline_precision)
-            .map_err(|error| RoutingResponseParseError::ParseError { error })?;
+            .map_err(|error| RoutingResponseParseError::GeometryDecodeError { index, error })?;
         // TODO: Trait for this common pattern?
         let geometry = linestring
             .coords()
             .map(|coord| GeographicCoordinate::from(*coord))
             .collect();
+        let tunnels = tunnels(value, &linestring);
+        let has_tolls = value.intersections.iter().any(Intersections::is_toll);
+        let is_ferry = value.mode.as_deref() == Some("ferry")
+            || value.intersections.iter().any(Intersections::is_ferry);
+        let pedestrian_feature = value
+            .intersections
+            .first()
+            .and_then(Intersections::pedestrian_feature);
 
         let visual_instructions = value
             .banner_instructions
@@ -119,6 +422,9 @@ impl RouteStep {
                     maneuver_type: banner.primary.maneuver_type,
                     maneuver_modifier: banner.primary.maneuver_modifier,
                     roundabout_exit_degrees: banner.primary.roundabout_exit_degrees,
+                    exit_numbers: component_texts(&banner.primary, "exit-number"),
+                    destination_refs: component_texts(&banner.primary, "icon"),
+                    destinations: vec![],
                 },
                 secondary_content: banner.secondary.as_ref().map(|secondary| {
                     VisualInstructionContent {
@@ -126,18 +432,27 @@ impl RouteStep {
                         maneuver_type: secondary.maneuver_type,
                         maneuver_modifier: secondary.maneuver_modifier,
                         roundabout_exit_degrees: banner.primary.roundabout_exit_degrees,
+                        exit_numbers: component_texts(secondary, "exit-number"),
+                        destination_refs: component_texts(secondary, "icon"),
+                        destinations: vec![],
                     }
                 }),
                 trigger_distance_before_maneuver: banner.distance_along_geometry,
             })
             .collect();
 
+        let hints: Vec<_> = phoneme_hint(value.name.as_deref(), value.pronunciation.as_deref())
+            .into_iter()
+            .collect();
         let spoken_instructions = value
             .voice_instructions
             .iter()
             .map(|instruction| SpokenInstruction {
                 text: instruction.announcement.clone(),
-                ssml: instruction.ssml_announcement.clone(),
+                ssml: instruction
+                    .ssml_announcement
+                    .as_deref()
+                    .map(|ssml| augment_ssml(ssml, &hints)),
                 trigger_distance_before_maneuver: instruction.distance_along_geometry,
                 utterance_id: Uuid::new_v4(),
             })
@@ -150,24 +465,375 @@ impl RouteStep {
             distance: value.distance,
             duration: value.duration,
             road_name: value.name.clone(),
-            instruction: value.maneuver.get_instruction(),
+            instruction: value.maneuver.get_instruction(
+                locale,
+                value.name.as_deref(),
+                pedestrian_feature,
+            ),
             visual_instructions,
             spoken_instructions,
+            transit_leg: None,
+            has_tolls,
+            is_ferry,
+            tunnels,
+            // Country changes are added from the admins of the leg
+            country_changes: vec![],
+            speed_limit_sign: value.speed_limit_sign(),
+            speed_limit_unit: value.speed_limit_unit(),
+            driving_side: value.driving_side(),
+            intersections: value.intersections.iter().map(intersection).collect(),
+            junction_views: junction_views(value),
+            has_crosswalk: value.intersections.iter().any(Intersections::is_crosswalk),
+            has_stairs: value.intersections.iter().any(Intersections::is_stairs),
+            has_elevator: value.intersections.iter().any(Intersections::is_elevator),
+            shields: shields(value),
+            extras: step_extras(value),
+        })
+    }
+}
+
+/// Extracts the junction view images from the `guidance-view` components of the banners.
+fn junction_views(step: &OsrmRouteStep) -> Vec<JunctionView> {
+    step.banner_instructions
+        .iter()
+        .flat_map(|banner| {
+            banner
+                .view
+                .iter()
+                .flat_map(|view| &view.components)
+                .filter(|component| component.component_type.as_deref() == Some("guidance-view"))
+                .filter_map(|component| {
+                    Some(JunctionView {
+                        image_reference: component.image_url.clone()?,
+                        kind: component.sub_type.clone(),
+                        trigger_distance_before_maneuver: banner.distance_along_geometry,
+                    })
+                })
+        })
+        .collect()
+}
+
+/// Converts an OSRM intersection to the model type.
+fn intersection(value: &Intersections) -> Intersection {
+    Intersection {
+        location: GeographicCoordinate {
+            lat: value.location.latitude(),
+            lng: value.location.longitude(),
+        },
+        bearings: value.bearings.clone(),
+        entry: value.entry.clone(),
+        bearing_in_index: value
+            .intersection_in
+            .and_then(|index| u32::try_from(index).ok()),
+        bearing_out_index: value
+            .intersection_out
+            .and_then(|index| u32::try_from(index).ok()),
+        geometry_index: value.geometry_index.map(|index| index as u64),
+        turn_weight: value.turn_weight,
+        turn_duration: value.turn_duration,
+    }
+}
+
+/// The text of the banner components of a type (ex: `exit-number`).
+fn component_texts(content: &BannerContent, component_type: &str) -> Vec<String> {
+    content
+        .components
+        .iter()
+        .filter(|component| component.component_type.as_deref() == Some(component_type))
+        .map(|component| component.text.clone())
+        .collect()
+}
+
+/// Adds the signage of each step to the visual instructions for the maneuver which leads onto it.
+///
+/// The visual instructions of a step describe the maneuver at its end,
+/// so the signage comes from the *following* step.
+/// Signage from the banner components takes precedence.
+fn apply_signage<S: OsrmStep>(
+    steps: &mut [RouteStep],
+    leg: &RouteLeg<S>,
+) -> Result<(), RoutingResponseParseError> {
+    for (step, next_step) in steps.iter_mut().zip(leg.steps.iter().skip(1)) {
+        add_signage(step, &next_step.signage()?);
+    }
+    Ok(())
+}
+
+/// Adds the signage of the following step to the visual instructions of a step.
+fn add_signage(step: &mut RouteStep, signage: &StepSignage) {
+    let exit_numbers = signage.exit_numbers();
+    let (destination_refs, destinations) = signage.destinations();
+
+    for instruction in &mut step.visual_instructions {
+        let content = &mut instruction.primary_content;
+        if content.exit_numbers.is_empty() {
+            content.exit_numbers.clone_from(&exit_numbers);
+        }
+        if content.destination_refs.is_empty() {
+            content.destination_refs.clone_from(&destination_refs);
+        }
+        content.destinations.clone_from(&destinations);
+    }
+
+    // Announcements usually name the way after the maneuver
+    if let Some(hint) = phoneme_hint(signage.name.as_deref(), signage.pronunciation.as_deref()) {
+        for instruction in &mut step.spoken_instructions {
+            if let Some(ssml) = &mut instruction.ssml {
+                *ssml = augment_ssml(ssml, std::slice::from_ref(&hint));
+            }
+        }
+    }
+}
+
+/// A pronunciation hint for the name of a way (from the IPA transcription in OSRM responses).
+fn phoneme_hint(name: Option<&str>, pronunciation: Option<&str>) -> Option<PhonemeHint> {
+    Some(PhonemeHint {
+        text: name?.to_string(),
+        phoneme: pronunciation?.to_string(),
+        alphabet: "ipa".to_string(),
+    })
+}
+
+/// The route shields of the way along a step, from its references (ex: `A 7;E 45`).
+fn shields(step: &OsrmRouteStep) -> Vec<RoadShield> {
+    step.reference
+        .iter()
+        .flat_map(|reference| reference.split(';'))
+        .map(str::trim)
+        .filter(|number| !number.is_empty())
+        .map(|number| RoadShield {
+            network: None,
+            number: number.to_string(),
         })
+        .collect()
+}
+
+/// Identifies the networks of each step's shields
+/// from the instructions for the maneuver which leads onto it.
+fn apply_shield_networks<S: OsrmStep>(
+    steps: &mut [RouteStep],
+    leg: &RouteLeg<S>,
+) -> Result<(), RoutingResponseParseError> {
+    for (step, previous_step) in steps.iter_mut().skip(1).zip(&leg.steps) {
+        add_shield_networks(step, &previous_step.shield_components()?);
+    }
+    Ok(())
+}
+
+/// Identifies the networks of a step's shields from the Mapbox shield components
+/// in the instructions for the maneuver which leads onto it.
+///
+/// Shields without a matching component (ex: from backends other than Mapbox) are left as is.
+fn add_shield_networks(step: &mut RouteStep, components: &[BannerComponent]) {
+    for shield in &mut step.shields {
+        let mapbox_shield = components.iter().find_map(|component| {
+            component.mapbox_shield.as_ref().filter(|mapbox_shield| {
+                component.text == shield.number || mapbox_shield.display_ref == shield.number
+            })
+        });
+        if let Some(mapbox_shield) = mapbox_shield {
+            *shield = RoadShield {
+                network: Some(mapbox_shield.name.clone()),
+                number: mapbox_shield.display_ref.clone(),
+            };
+        }
     }
 }
 
+/// Converts a waypoint which the backend added to charge an electric vehicle.
+fn charging_stop(waypoint: &OsrmWaypoint) -> Option<ChargingStop> {
+    let metadata = waypoint
+        .metadata
+        .as_ref()
+        .filter(|metadata| metadata.waypoint_type == "charging-station")?;
+    let kilowatt_hours = |watt_hours: Option<f64>| watt_hours.unwrap_or(0.0) / 1000.0;
+
+    Some(ChargingStop {
+        coordinate: GeographicCoordinate {
+            lat: waypoint.location.latitude(),
+            lng: waypoint.location.longitude(),
+        },
+        name: metadata.name.clone().or_else(|| waypoint.name.clone()),
+        station_id: metadata.station_id.clone(),
+        charge_at_arrival: kilowatt_hours(metadata.charge_at_arrival),
+        charge_to: kilowatt_hours(metadata.charge_to),
+        charge_time: metadata.charge_time.unwrap_or(0.0),
+        power: metadata.power_kw,
+        connector_type: metadata
+            .plug_type
+            .as_deref()
+            .map(connector_type_from_osrm_name),
+    })
+}
+
+/// Converts the incidents along a leg, locating them along the route.
+///
+/// Incidents are located using indices into the full leg geometry,
+/// which we reconstruct from the steps.
+fn leg_incidents<S>(
+    leg: &RouteLeg<S>,
+    steps: &[RouteStep],
+    leg_start_distance: f64,
+) -> Vec<Incident> {
+    // The distance along the leg at each point in the leg geometry.
+    // Adjacent steps share their start and end points, so these are not repeated.
+    let mut distances = vec![];
+    let mut previous: Option<Point> = None;
+    for step in steps {
+        for coordinate in step.geometry.iter().skip(usize::from(previous.is_some())) {
+            let point = Point::from(*coordinate);
+            let distance = previous.map_or(0.0, |previous| previous.haversine_distance(&point));
+            distances.push(distances.last().copied().unwrap_or(0.0) + distance);
+            previous = Some(point);
+        }
+    }
+    let distance_at_index = |index: usize| {
+        leg_start_distance
+            + distances
+                .get(index)
+                .or(distances.last())
+                .copied()
+                .unwrap_or(0.0)
+    };
+
+    leg.incidents
+        .iter()
+        .map(|incident| Incident {
+            id: incident.id.clone(),
+            kind: incident.kind,
+            description: incident.description.clone(),
+            is_closed: incident.is_closed,
+            start_distance_along_route: distance_at_index(incident.geometry_index_start),
+            end_distance_along_route: distance_at_index(
+                incident
+                    .geometry_index_end
+                    .unwrap_or(incident.geometry_index_start),
+            ),
+        })
+        .collect()
+}
+
+/// Records the points along the steps of a leg at which the route enters a different country,
+/// using the administrative regions of the intersections.
+///
+/// `country` is the country at the end of the previous leg (if any),
+/// and is updated to the country at the end of this leg.
+fn apply_country_changes<S: OsrmStep>(
+    steps: &mut [RouteStep],
+    leg: &RouteLeg<S>,
+    country: &mut Option<String>,
+) -> Result<(), RoutingResponseParseError> {
+    for (step, osrm_step) in steps.iter_mut().zip(&leg.steps) {
+        let linestring = step.get_linestring();
+        for intersection in osrm_step.intersection_admins()? {
+            let Some(admin) = intersection
+                .admin_index
+                .and_then(|admin_index| leg.admins.get(admin_index))
+            else {
+                continue;
+            };
+            if country.as_ref() == Some(&admin.iso_3166_1) {
+                continue;
+            }
+
+            // The country at the start of the route is not a change
+            if country.replace(admin.iso_3166_1.clone()).is_some() {
+                let point = Point::new(
+                    intersection.location.longitude(),
+                    intersection.location.latitude(),
+                );
+                step.country_changes.push(CountryChange {
+                    iso_3166: admin.iso_3166_1.clone(),
+                    distance_before_maneuver: distance_to_end_of_line(&point, &linestring),
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Extracts the tunnels along a step from the classes of the intersections.
+///
+/// Each tunnel extends from the first intersection exiting into a tunnel
+/// until the next intersection that does not (or the end of the step).
+fn tunnels(step: &OsrmRouteStep, linestring: &LineString) -> Vec<Tunnel> {
+    let mut tunnels = vec![];
+    let mut entrance: Option<(Option<String>, f64)> = None;
+    for intersection in &step.intersections {
+        let point = Point::new(
+            intersection.location.longitude(),
+            intersection.location.latitude(),
+        );
+        match (intersection.is_tunnel(), entrance.take()) {
+            (true, None) => {
+                entrance = Some((
+                    intersection.tunnel_name.clone(),
+                    distance_to_end_of_line(&point, linestring),
+                ));
+            }
+            (true, Some(open)) => entrance = Some(open),
+            (false, Some((name, entrance_distance_before_maneuver))) => tunnels.push(Tunnel {
+                name,
+                entrance_distance_before_maneuver,
+                exit_distance_before_maneuver: distance_to_end_of_line(&point, linestring),
+            }),
+            (false, None) => {}
+        }
+    }
+
+    if let Some((name, entrance_distance_before_maneuver)) = entrance {
+        tunnels.push(Tunnel {
+            name,
+            entrance_distance_before_maneuver,
+            exit_distance_before_maneuver: 0.0,
+        });
+    }
+
+    tunnels
+}
+
+/// Measures the distance along the line from the vertex nearest to the point to the end of the line.
+///
+/// Intersections are always vertices of the step geometry, so this does not interpolate.
+fn distance_to_end_of_line(point: &Point, linestring: &LineString) -> f64 {
+    let nearest_index = linestring
+        .points()
+        .enumerate()
+        .min_by(|(_, a), (_, b)| {
+            a.haversine_distance(point)
+                .total_cmp(&b.haversine_distance(point))
+        })
+        .map_or(0, |(index, _)| index);
+
+    linestring
+        .lines()
+        .skip(nearest_index)
+        .map(|line| line.start_point().haversine_distance(&line.end_point()))
+        .sum()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::clock::MockClock;
+    use crate::models::{ConnectorType, IncidentKind, SpeedLimitSign, SpeedUnit};
+    use proptest::prelude::*;
+    use std::time::Duration;
 
-    const STANDARD_OSRM_POLYLINE6_RESPONSE: &str = r#"{"code":"Ok","routes":[{"geometry":"qikdcB{~dpXmxRbaBuqAoqKyy@svFwNcfKzsAysMdr@evD`m@qrAohBi}A{OkdGjg@ajDZww@lJ}Jrs@}`CvzBq`E`PiB`~A|l@z@feA","legs":[{"steps":[],"summary":"","weight":263.1,"duration":260.2,"distance":1886.3},{"steps":[],"summary":"","weight":370.5,"duration":370.5,"distance":2845.5}],"weight_name":"routability","weight":633.6,"duration":630.7,"distance":4731.8}],"waypoints":[{"hint":"Dv8JgCp3moUXAAAABQAAAAAAAAAgAAAAIXRPQYXNK0AAAAAAcPePQQsAAAADAAAAAAAAABAAAAA6-wAA_kvMAKlYIQM8TMwArVghAwAA7wrXLH_K","distance":4.231521214,"name":"Friedrichstraße","location":[13.388798,52.517033]},{"hint":"JEvdgVmFiocGAAAACgAAAAAAAAB3AAAAppONQOodwkAAAAAA8TeEQgYAAAAKAAAAAAAAAHcAAAA6-wAAfm7MABiJIQOCbswA_4ghAwAAXwXXLH_K","distance":2.795148358,"name":"Torstraße","location":[13.39763,52.529432]},{"hint":"oSkYgP___38fAAAAUQAAACYAAAAeAAAAeosKQlNOX0IQ7CZCjsMGQh8AAABRAAAAJgAAAB4AAAA6-wAASufMAOdwIQNL58wA03AhAwQAvxDXLH_K","distance":2.226580806,"name":"Platz der Vereinten Nationen","location":[13.428554,52.523239]}]}"#;
-    const VALHALLA_OSRM_RESPONSE: &str = r#"{"code":"Ok","routes":[{"distance":2604.35,"duration":2007.289,"geometry":"e|akpBozpfn@AG~ApSAzFg@pKsFvfA]lFdDr@kAvOoAvDkC|B]~DMzAyCj^c@lFi@d@wIbHu@f@cV|PkA~@_TxQxX|eC{Az@qDrBw@b@{BnATbCNjBd@rHyAj@g@JiDrAcJxDcBjBcA^sDvAsIjDmCnD}@R`@bHgHnBsRvGkDhCsDpTpF~dEPfMfAft@H~FNrEdAt}@f@pY@rA?`@@rBJhRCdAIbD]nFa@bDaAbIiAdImB~MKt@wGrd@qBnOoDbUwAxJVfH\\jMHpEGzAiAjDqMbf@gBnFkC~HeDbKs@vBkCtF}CpGuIzNU`@oGzH{FhGqi@hc@ud@t_@wIpI{JfNqLfTwJjVgDdJ_HvYaEpUgHxa@aFhd@mErt@q@~FmFrd@oJdw@kFmDsCyAyArJgAdAJhDm@`G_@fCMrAmAfFiKf|@{Fxh@oCdSi@dGaBrQcBbNwCd\\kGlh@uA~PuEzr@_@bHa@dC}@\\KbEOvCk@FoQbw@uNno@Gv@SxCo@hEiA`@i@nBf@pCQtDk@xC{B|KgTraAuA\\i@o@mFzY}GiGqBoC","legs":[{"admins":[{"iso_3166_1":"EE","iso_3166_1_alpha3":"EST"}],"annotation":{"distance":[0.2,19.4,7.1,11.6,66.4,6.9,9.4,15.7,6.9,8.6,5.7,2.7,29.7,7.0,2.6,20.9,3.2,44.3,4.6,41.1,130.5,5.4,10.4,3.3,7.3,3.9,3.2,9.0,5.2,2.3,9.8,20.5,6.4,3.9,10.3,19.5,9.3,3.5,8.5,16.8,35.8,10.3,21.9,179.8,12.9,48.4,7.3,6.1,56.9,24.2,2.4,1.0,3.3,17.5,2.0,4.7,7.0,5.0,9.9,10.1,14.9,1.7,37.5,16.2,22.3,11.8,8.5,13.1,6.0,2.6,6.4,43.9,8.9,11.9,14.3,4.5,10.4,11.7,23.9,1.6,17.6,15.9,82.6,73.4,21.4,25.3,30.9,29.8,13.8,29.0,23.1,35.6,36.0,49.9,7.8,36.5,54.8,14.0,8.6,11.7,4.5,4.9,7.7,4.2,2.5,7.9,59.6,40.4,20.0,7.8,17.7,14.8,27.7,40.4,17.0,48.4,8.5,4.2,3.6,5.6,4.4,2.5,60.6,52.0,1.6,4.5,6.3,4.2,3.9,4.7,5.2,5.0,13.6,71.2,4.9,2.7,27.7,17.6,7.5],"duration":[0.184,15.315,5.639,9.818,51.539,4.898,6.604,12.227,5.127,6.412,4.012,1.919,20.947,4.96,1.815,14.72,2.267,33.128,3.248,29.012,101.367,3.808,8.841,2.592,5.742,2.774,2.247,6.331,3.643,1.589,6.887,14.472,4.482,2.747,7.288,13.793,6.594,2.469,5.983,13.027,27.83,8.028,15.49,126.908,12.15,34.152,5.129,4.281,42.571,18.073,1.781,0.681,2.318,17.664,2.012,4.717,7.059,5.058,7.669,7.844,10.516,1.177,26.445,11.458,15.741,8.304,5.987,13.246,4.213,1.864,4.77,32.852,6.677,8.938,10.737,3.339,7.818,11.006,22.394,1.32,14.893,13.483,69.994,51.784,15.108,19.969,24.415,23.531,10.899,32.187,16.31,25.104,25.445,35.213,5.477,25.799,38.709,9.902,6.086,8.228,3.156,3.427,5.46,2.993,1.871,5.888,44.617,30.206,15.496,5.487,12.51,10.434,19.585,31.347,13.188,37.62,6.681,3.349,2.809,3.942,3.1,1.737,45.312,40.41,1.278,3.174,4.898,3.284,3.057,3.644,4.072,3.527,13.723,50.263,3.432,1.908,20.727,17.401,7.403],"maxspeed":[{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"speed":30,"unit":"km/h"},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true}],"speed":[1.3,1.3,1.3,1.2,1.3,1.4,1.4,1.3,1.3,1.3,1.4,1.4,1.4,1.4,1.4,1.4,1.4,1.3,1.4,1.4,1.3,1.4,1.2,1.3,1.3,1.4,1.4,1.4,1.4,1.4,1.4,1.4,1.4,1.4,1.4,1.4,1.4,1.4,1.4,1.3,1.3,1.3,1.4,1.4,1.1,1.4,1.4,1.4,1.3,1.3,1.3,1.4,1.4,1.0,1.0,1.0,1.0,1.0,1.3,1.3,1.4,1.4,1.4,1.4,1.4,1.4,1.4,1.0,1.4,1.4,1.3,1.3,1.3,1.3,1.3,1.3,1.3,1.1,1.1,1.2,1.2,1.2,1.2,1.4,1.4,1.3,1.3,1.3,1.3,0.9,1.4,1.4,1.4,1.4,1.4,1.4,1.4,1.4,1.4,1.4,1.4,1.4,1.4,1.4,1.3,1.3,1.3,1.3,1.3,1.4,1.4,1.4,1.4,1.3,1.3,1.3,1.3,1.3,1.3,1.4,1.4,1.4,1.3,1.3,1.3,1.4,1.3,1.3,1.3,1.3,1.3,1.4,1.0,1.4,1.4,1.4,1.3,1.0,1.0]},"distance":2604.35,"duration":2007.289,"steps":[{"bannerInstructions":[{"distanceAlongGeometry":111.251,"primary":{"components":[{"text":"Turn left onto the walkway.","type":"text"}],"modifier":"left","text":"Turn left onto the walkway.","type":"turn"}}],"distance":111.251,"driving_side":"right","duration":90.107,"geometry":"e|akpBozpfn@AG~ApSAzFg@pKsFvfA]lF","intersections":[{"admin_index":0,"bearings":[254],"duration":20.754,"entry":[true],"geometry_index":0,"location":[24.765368,59.442643],"out":0,"weight":21.791},{"admin_index":0,"bearings":[7,82,189,281],"duration":11.165,"entry":[true,false,true,true],"geometry_index":3,"in":1,"location":[24.764917,59.442597],"out":3,"turn_duration":1.0,"turn_weight":1.0,"weight":12.181},{"admin_index":0,"bearings":[13,101,191,282],"duration":52.247,"entry":[true,false,true,true],"geometry_index":4,"in":1,"location":[24.764716,59.442617],"out":3,"turn_duration":1.0,"turn_weight":1.0,"weight":52.247},{"admin_index":0,"bearings":[49,102,191,284],"entry":[true,false,true,true],"geometry_index":5,"in":1,"location":[24.763568,59.442739],"out":3,"turn_duration":1.0,"turn_weight":1.0}],"maneuver":{"bearing_after":254,"bearing_before":0,"instruction":"Walk west on the walkway.","location":[24.765368,59.442643],"type":"depart"},"mode":"walking","name":"","speedLimitSign":"vienna","speedLimitUnit":"km/h","voiceInstructions":[{"announcement":"Walk west on the walkway.","distanceAlongGeometry":111.251,"ssmlAnnouncement":"<speak>Walk west on the walkway.</speak>"},{"announcement":"In 200 feet, Turn left onto the walkway.","distanceAlongGeometry":60.0,"ssmlAnnouncement":"<speak>In 200 feet, Turn left onto the walkway.</speak>"}],"weight":92.161},{"bannerInstructions":[{"distanceAlongGeometry":9.0,"primary":{"components":[{"text":"Laeva","type":"text"}],"modifier":"right","text":"Laeva","type":"turn"}}],"distance":9.0,"driving_side":"right","duration":6.353,"geometry":"ccbkpBqbmfn@dDr@","intersections":[{"admin_index":0,"bearings":[14,104,189],"entry":[true,false,true],"geometry_index":6,"in":1,"location":[24.763449,59.442754],"out":2}],"maneuver":{"bearing_after":189,"bearing_before":284,"instruction":"Turn left onto the walkway.","location":[24.763449,59.442754],"modifier":"left","type":"turn"},"mode":"walking","name":"","speedLimitSign":"vienna","speedLimitUnit":"km/h","voiceInstructions":[{"announcement":"In 14 feet, Turn right onto Laeva.","distanceAlongGeometry":4.5,"ssmlAnnouncement":"<speak>In 14 feet, Turn right onto Laeva.</speak>"}],"weight":6.353},{"bannerInstructions":[{"distanceAlongGeometry":16.0,"primary":{"components":[{"text":"Bear right.","type":"text"}],"modifier":"slight right","text":"Bear right.","type":"turn"}}],"distance":16.0,"driving_side":"right","duration":12.424,"geometry":"}}akpB}`mfn@kAvO","intersections":[{"admin_index":0,"bearings":[9,101,200,286],"entry":[false,true,true,true],"geometry_index":7,"in":0,"location":[24.763423,59.442671],"out":3,"turn_weight":5.0}],"maneuver":{"bearing_after":286,"bearing_before":189,"instruction":"Turn right onto Laeva.","location":[24.763423,59.442671],"modifier":"right","type":"turn"},"mode":"walking","name":"Laeva","speedLimitSign":"vienna","speedLimitUnit":"km/h","voiceInstructions":[{"announcement":"In 26 feet, Bear right.","distanceAlongGeometry":8.0,"ssmlAnnouncement":"<speak>In 26 feet, Bear right.</speak>"}],"weight":17.424},{"bannerInstructions":[{"distanceAlongGeometry":15.0,"primary":{"components":[{"text":"Bear left onto the walkway.","type":"text"}],"modifier":"slight left","text":"Bear left onto the walkway.","type":"turn"}}],"distance":15.0,"driving_side":"right","duration":11.224,"geometry":"i`bkpBeplfn@oAvDkC|B","intersections":[{"admin_index":0,"bearings":[106,191,324],"entry":[false,true,true],"geometry_index":8,"in":0,"location":[24.763155,59.442709],"out":2,"turn_weight":5.0}],"maneuver":{"bearing_after":324,"bearing_before":286,"instruction":"Bear right.","location":[24.763155,59.442709],"modifier":"slight right","type":"turn"},"mode":"walking","name":"","speedLimitSign":"vienna","speedLimitUnit":"km/h","voiceInstructions":[{"announcement":"In 24 feet, Bear left onto the walkway.","distanceAlongGeometry":7.5,"ssmlAnnouncement":"<speak>In 24 feet, Bear left onto the walkway.</speak>"}],"weight":16.224},{"bannerInstructions":[{"distanceAlongGeometry":38.0,"primary":{"components":[{"text":"Continue.","type":"text"}],"modifier":"straight","text":"Continue.","type":"new name"}}],"distance":38.0,"driving_side":"right","duration":26.824,"geometry":"egbkpBoflfn@]~DMzAyCj^","intersections":[{"admin_index":0,"bearings":[1,70,145,287],"duration":5.647,"entry":[true,true,false,true],"geometry_index":10,"in":2,"location":[24.763,59.442819],"out":3,"weight":5.647},{"admin_index":0,"bearings":[107,158,287],"entry":[false,true,true],"geometry_index":12,"in":0,"location":[24.762858,59.442841],"out":2}],"maneuver":{"bearing_after":287,"bearing_before":325,"instruction":"Bear left onto the walkway.","location":[24.763,59.442819],"modifier":"slight left","type":"turn"},"mode":"walking","name":"","speedLimitSign":"vienna","speedLimitUnit":"km/h","voiceInstructions":[{"announcement":"In 62 feet, Continue.","distanceAlongGeometry":19.0,"ssmlAnnouncement":"<speak>In 62 feet, Continue.</speak>"}],"weight":26.824},{"bannerInstructions":[{"distanceAlongGeometry":7.0,"primary":{"components":[{"text":"Admiralisild; Admiral Bridge","type":"text"}],"modifier":"right","text":"Admiralisild; Admiral Bridge","type":"turn"}}],"distance":7.0,"driving_side":"right","duration":4.941,"geometry":"kmbkpBg~jfn@c@lF","intersections":[{"admin_index":0,"bearings":[107,155,287],"entry":[false,false,true],"geometry_index":13,"in":0,"location":[24.762356,59.442918],"out":2}],"maneuver":{"bearing_after":287,"bearing_before":287,"instruction":"Continue.","location":[24.762356,59.442918],"modifier":"straight","type":"new name"},"mode":"walking","name":"","speedLimitSign":"vienna","speedLimitUnit":"km/h","voiceInstructions":[{"announcement":"In 11 feet, Turn right onto Admiralisild/Admiral Bridge.","distanceAlongGeometry":3.5,"ssmlAnnouncement":"<speak>In 11 feet, Turn right onto Admiralisild/Admiral Bridge.</speak>"}],"weight":4.941},{"bannerInstructions":[{"distanceAlongGeometry":70.0,"primary":{"components":[{"text":"Continue on the walkway.","type":"text"}],"modifier":"straight","text":"Continue on the walkway.","type":"new name"}}],"distance":70.0,"driving_side":"right","duration":52.275,"geometry":"onbkpByvjfn@i@d@wIbHu@f@cV|P","intersections":[{"admin_index":0,"bearings":[107,168,336],"duration":16.235,"entry":[false,true,true],"geometry_index":14,"in":0,"location":[24.762237,59.442936],"out":2,"weight":16.235},{"admin_index":0,"bearings":[62,157,186,249,339],"duration":3.118,"entry":[true,false,true,true,true],"geometry_index":16,"in":1,"location":[24.762072,59.443129],"out":4,"turn_duration":1.0,"turn_weight":1.0,"weight":3.118},{"admin_index":0,"bearings":[159,338],"entry":[false,true],"geometry_index":17,"in":0,"location":[24.762052,59.443156],"out":1,"turn_weight":5.0}],"maneuver":{"bearing_after":336,"bearing_before":287,"instruction":"Turn right onto Admiralisild/Admiral Bridge.","location":[24.762237,59.442936],"modifier":"right","type":"turn"},"mode":"walking","name":"Admiralisild; Admiral Bridge","speedLimitSign":"vienna","speedLimitUnit":"km/h","voiceInstructions":[{"announcement":"In 200 feet, Continue on the walkway.","distanceAlongGeometry":60.0,"ssmlAnnouncement":"<speak>In 200 feet, Continue on the walkway.</speak>"}],"weight":57.275},{"bannerInstructions":[{"distanceAlongGeometry":46.0,"primary":{"components":[{"text":"Turn left onto the walkway.","type":"text"}],"modifier":"left","text":"Turn left onto the walkway.","type":"turn"}}],"distance":46.0,"driving_side":"right","duration":33.471,"geometry":"ksckpBiyifn@kA~@_TxQ","intersections":[{"admin_index":0,"bearings":[158,337],"duration":3.529,"entry":[false,true],"geometry_index":18,"in":0,"location":[24.761765,59.443526],"out":1,"turn_weight":5.0,"weight":8.529},{"admin_index":0,"bearings":[70,157,246,336],"entry":[true,false,true,true],"geometry_index":19,"in":1,"location":[24.761733,59.443564],"out":3,"turn_duration":1.0,"turn_weight":1.0}],"maneuver":{"bearing_after":337,"bearing_before":338,"instruction":"Continue on the walkway.","location":[24.761765,59.443526],"modifier":"straight","type":"new name"},"mode":"walking","name":"","speedLimitSign":"vienna","speedLimitUnit":"km/h","voiceInstructions":[{"announcement":"In 75 feet, Turn left onto the walkway.","distanceAlongGeometry":23.0,"ssmlAnnouncement":"<speak>In 75 feet, Turn left onto the walkway.</speak>"}],"weight":38.471},{"bannerInstructions":[{"distanceAlongGeometry":131.0,"primary":{"components":[{"text":"Turn right onto the walkway.","type":"text"}],"modifier":"right","text":"Turn right onto the walkway.","type":"turn"}}],"distance":131.0,"driving_side":"right","duration":101.718,"geometry":"wjdkpBodifn@xX|eC","intersections":[{"admin_index":0,"bearings":[55,156,249,336],"entry":[true,false,true,true],"geometry_index":20,"in":1,"location":[24.761432,59.4439],"out":2}],"maneuver":{"bearing_after":249,"bearing_before":336,"instruction":"Turn left onto the walkway.","location":[24.761432,59.4439],"modifier":"left","type":"turn"},"mode":"walking","name":"","speedLimitSign":"vienna","speedLimitUnit":"km/h","voiceInstructions":[{"announcement":"In 200 feet, Turn right onto the walkway.","distanceAlongGeometry":60.0,"ssmlAnnouncement":"<speak>In 200 feet, Turn right onto the walkway.</speak>"}],"weight":101.718},{"bannerInstructions":[{"distanceAlongGeometry":25.0,"primary":{"components":[{"text":"Turn left onto the walkway.","type":"text"}],"modifier":"left","text":"Turn left onto the walkway.","type":"turn"}}],"distance":25.0,"driving_side":"right","duration":21.906,"geometry":"}pckpBq}dfn@{Az@qDrBw@b@{BnA","intersections":[{"admin_index":0,"bearings":[69,251,342],"duration":3.529,"entry":[false,true,true],"geometry_index":21,"in":0,"location":[24.759273,59.443487],"out":2,"weight":3.529},{"admin_index":0,"bearings":[70,162,258,342],"duration":9.471,"entry":[false,false,false,true],"geometry_index":22,"in":1,"location":[24.759243,59.443533],"out":3,"turn_duration":1.0,"turn_weight":1.0,"weight":10.318},{"admin_index":0,"bearings":[70,162,244,342],"entry":[true,false,true,true],"geometry_index":23,"in":1,"location":[24.759185,59.443622],"out":3,"turn_duration":1.0,"turn_weight":1.0}],"maneuver":{"bearing_after":342,"bearing_before":249,"instruction":"Turn right onto the walkway.","location":[24.759273,59.443487],"modifier":"right","type":"turn"},"mode":"walking","name":"","speedLimitSign":"vienna","speedLimitUnit":"km/h","voiceInstructions":[{"announcement":"In 41 feet, Turn left onto the walkway.","distanceAlongGeometry":12.5,"ssmlAnnouncement":"<speak>In 41 feet, Turn left onto the walkway.</speak>"}],"weight":23.148},{"bannerInstructions":[{"distanceAlongGeometry":16.0,"primary":{"components":[{"text":"Logi","type":"text"}],"modifier":"right","text":"Logi","type":"turn"}}],"distance":16.0,"driving_side":"right","duration":12.294,"geometry":"__dkpBmtdfn@TbCNjBd@rH","intersections":[{"admin_index":0,"bearings":[77,162,253,348],"duration":4.941,"entry":[true,false,true,true],"geometry_index":25,"in":1,"location":[24.759127,59.443712],"out":2,"weight":4.941},{"admin_index":0,"bearings":[73,168,256,335],"entry":[false,true,true,true],"geometry_index":27,"in":0,"location":[24.759007,59.443693],"out":2,"turn_duration":1.0,"turn_weight":1.0}],"maneuver":{"bearing_after":253,"bearing_before":342,"instruction":"Turn left onto the walkway.","location":[24.759127,59.443712],"modifier":"left","type":"turn"},"mode":"walking","name":"","speedLimitSign":"vienna","speedLimitUnit":"km/h","voiceInstructions":[{"announcement":"In 26 feet, Turn right onto Logi.","distanceAlongGeometry":8.0,"ssmlAnnouncement":"<speak>In 26 feet, Turn right onto Logi.</speak>"}],"weight":12.294},{"bannerInstructions":[{"distanceAlongGeometry":91.0,"primary":{"components":[{"text":"Turn left onto the walkway.","type":"text"}],"modifier":"left","text":"Turn left onto the walkway.","type":"turn"}}],"distance":91.0,"driving_side":"right","duration":72.235,"geometry":"s|ckpBicdfn@yAj@g@JiDrAcJxDcBjBcA^sDvAsIjDmCnD}@R","intersections":[{"admin_index":0,"bearings":[76,163,258,348],"duration":4.941,"entry":[false,true,true,true],"geometry_index":28,"in":0,"location":[24.758853,59.443674],"out":3,"weight":4.941},{"admin_index":0,"bearings":[61,168,248,345],"duration":28.118,"entry":[true,false,true,true],"geometry_index":30,"in":1,"location":[24.758825,59.443739],"out":3,"turn_duration":2.0,"turn_weight":7.0,"weight":33.118},{"admin_index":0,"bearings":[161,347],"duration":2.824,"entry":[false,true],"geometry_index":33,"in":0,"location":[24.758636,59.444052],"out":1,"weight":2.824},{"admin_index":0,"bearings":[82,167,258,346],"duration":8.059,"entry":[true,false,true,true],"geometry_index":34,"in":1,"location":[24.75862,59.444086],"out":3,"turn_duration":1.0,"turn_weight":1.0,"weight":8.059},{"admin_index":0,"bearings":[18,166,246,346],"duration":19.118,"entry":[true,false,true,true],"geometry_index":35,"in":1,"location":[24.758576,59.444176],"out":3,"turn_duration":5.0,"turn_weight":5.0,"weight":19.118},{"admin_index":0,"bearings":[166,253,328],"duration":6.353,"entry":[false,true,true],"geometry_index":36,"in":0,"location":[24.75849,59.444346],"out":2,"weight":6.353},{"admin_index":0,"bearings":[148,213,351],"entry":[false,true,true],"geometry_index":37,"in":0,"location":[24.758402,59.444417],"out":2}],"maneuver":{"bearing_after":348,"bearing_before":256,"instruction":"Turn right onto Logi.","location":[24.758853,59.443674],"modifier":"right","type":"turn"},"mode":"walking","name":"Logi","speedLimitSign":"vienna","speedLimitUnit":"km/h","voiceInstructions":[{"announcement":"In 200 feet, Turn left onto the walkway.","distanceAlongGeometry":60.0,"ssmlAnnouncement":"<speak>In 200 feet, Turn left onto the walkway.</speak>"}],"weight":77.235},{"bannerInstructions":[{"distanceAlongGeometry":8.0,"primary":{"components":[{"text":"Turn right onto the walkway.","type":"text"}],"modifier":"right","text":"Turn right onto the walkway.","type":"turn"}}],"distance":8.0,"driving_side":"right","duration":5.647,"geometry":"_mekpBofcfn@`@bH","intersections":[{"admin_index":0,"bearings":[77,171,257,346],"entry":[true,false,true,true],"geometry_index":38,"in":1,"location":[24.758392,59.444448],"out":2,"turn_weight":5.0}],"maneuver":{"bearing_after":257,"bearing_before":351,"instruction":"Turn left onto the walkway.","location":[24.758392,59.444448],"modifier":"left","type":"turn"},"mode":"walking","name":"","speedLimitSign":"vienna","speedLimitUnit":"km/h","voiceInstructions":[{"announcement":"In 13 feet, Turn right onto the walkway.","distanceAlongGeometry":4.0,"ssmlAnnouncement":"<speak>In 13 feet, Turn right onto the walkway.</speak>"}],"weight":10.647},{"bannerInstructions":[{"distanceAlongGeometry":85.0,"primary":{"components":[{"text":"Kultuurikilomeeter","type":"text"}],"modifier":"slight left","text":"Kultuurikilomeeter","type":"turn"}}],"distance":85.0,"driving_side":"right","duration":64.447,"geometry":"}kekpBk}bfn@gHnBsRvGkDhCsDpT","intersections":[{"admin_index":0,"bearings":[77,213,349],"duration":48.918,"entry":[false,true,true],"geometry_index":39,"in":0,"location":[24.758246,59.444431],"out":2,"weight":48.918},{"admin_index":0,"bearings":[75,158,297],"entry":[true,false,true],"geometry_index":42,"in":1,"location":[24.757981,59.444979],"out":2}],"maneuver":{"bearing_after":349,"bearing_before":257,"instruction":"Turn right onto the walkway.","location":[24.758246,59.444431],"modifier":"right","type":"turn"},"mode":"walking","name":"","speedLimitSign":"vienna","speedLimitUnit":"km/h","voiceInstructions":[{"announcement":"In 200 feet, Bear left onto Kultuurikilomeeter.","distanceAlongGeometry":60.0,"ssmlAnnouncement":"<speak>In 200 feet, Bear left onto Kultuurikilomeeter.</speak>"}],"weight":64.447},{"bannerInstructions":[{"distanceAlongGeometry":1254.0,"primary":{"components":[{"text":"Turn right onto the walkway.","type":"text"}],"modifier":"right","text":"Turn right onto the walkway.","type":"turn"}}],"distance":1254.0,"driving_side":"right","duration":966.424,"geometry":"ysfkpBgwafn@pF~dEPfMfAft@H~FNrEdAt}@f@pY@rA?`@@rBJhRCdAIbD]nFa@bDaAbIiAdImB~MKt@wGrd@qBnOoDbUwAxJVfH\\jMHpEGzAiAjDqMbf@gBnFkC~HeDbKs@vBkCtF}CpGuIzNU`@oGzH{FhGqi@hc@ud@t_@wIpI{JfNqLfTwJjVgDdJ_HvYaEpUgHxa@aFhd@mErt@q@~FmFrd@oJdw@","intersections":[{"admin_index":0,"bearings":[117,266,329],"duration":127.059,"entry":[false,true,true],"geometry_index":43,"in":0,"location":[24.757636,59.445069],"out":1,"turn_weight":5.0,"weight":132.059},{"admin_index":0,"bearings":[86,175,266,355],"duration":13.205,"entry":[false,true,true,true],"geometry_index":44,"in":0,"location":[24.754468,59.444948],"out":2,"turn_duration":1.0,"turn_weight":1.0,"weight":15.035},{"admin_index":0,"bearings":[86,265],"duration":39.529,"entry":[false,true],"geometry_index":45,"in":0,"location":[24.75424,59.444939],"out":1,"weight":39.529},{"admin_index":0,"bearings":[86,262],"duration":4.235,"entry":[false,true],"geometry_index":47,"in":0,"location":[24.75326,59.444898],"out":1,"weight":4.235},{"admin_index":0,"bearings":[82,176,266],"duration":62.104,"entry":[false,true,true],"geometry_index":48,"in":0,"location":[24.753154,59.44489],"out":2,"weight":62.104},{"admin_index":0,"bearings":[86,176,268,358],"duration":3.824,"entry":[false,true,true,true],"geometry_index":51,"in":0,"location":[24.751684,59.444834],"out":2,"turn_duration":1.0,"turn_weight":6.0,"weight":8.824},{"admin_index":0,"bearings":[88,176,268,358],"duration":37.339,"entry":[false,true,true,true],"geometry_index":53,"in":0,"location":[24.751609,59.444833],"out":2,"turn_duration":1.0,"turn_weight":1.0,"weight":44.607},{"admin_index":0,"bearings":[109,134,292],"duration":15.529,"entry":[false,true,true],"geometry_index":58,"in":0,"location":[24.750981,59.444866],"out":2,"weight":15.529},{"admin_index":0,"bearings":[114,156,294],"duration":10.588,"entry":[false,true,true],"geometry_index":60,"in":0,"location":[24.750656,59.444936],"out":2,"weight":10.588},{"admin_index":0,"bearings":[114,169,294,359],"duration":39.824,"entry":[false,true,true,true],"geometry_index":61,"in":0,"location":[24.750416,59.444991],"out":2,"turn_duration":1.0,"turn_weight":1.0,"weight":39.824},{"admin_index":0,"bearings":[34,113,296],"duration":15.529,"entry":[true,false,true],"geometry_index":64,"in":1,"location":[24.749523,59.445194],"out":2,"weight":15.529},{"admin_index":0,"bearings":[116,295],"duration":14.118,"entry":[false,true],"geometry_index":65,"in":0,"location":[24.749169,59.445282],"out":1,"weight":14.118},{"admin_index":0,"bearings":[81,263,345],"duration":13.122,"entry":[false,true,true],"geometry_index":67,"in":0,"location":[24.748832,59.445314],"out":1,"weight":15.747},{"admin_index":0,"bearings":[83,183,268,351],"duration":8.353,"entry":[false,true,true,true],"geometry_index":68,"in":0,"location":[24.748602,59.445299],"out":2,"turn_duration":2.0,"turn_weight":7.0,"weight":13.353},{"admin_index":0,"bearings":[90,194,310],"duration":53.125,"entry":[false,true,true],"geometry_index":70,"in":0,"location":[24.748451,59.445298],"out":2,"weight":53.125},{"admin_index":0,"bearings":[131,222,310],"duration":21.699,"entry":[false,true,true],"geometry_index":74,"in":0,"location":[24.747459,59.44569],"out":2,"weight":21.699},{"admin_index":0,"bearings":[45,138,319],"duration":33.798,"entry":[true,false,true],"geometry_index":77,"in":1,"location":[24.747082,59.445869],"out":2,"weight":38.867},{"admin_index":0,"bearings":[56,143,237,328],"duration":100.953,"entry":[true,false,true,true],"geometry_index":79,"in":1,"location":[24.746691,59.446119],"out":3,"turn_duration":1.0,"turn_weight":1.0,"weight":105.951},{"admin_index":0,"bearings":[65,157,249,336],"duration":68.059,"entry":[true,false,true,true],"geometry_index":83,"in":1,"location":[24.745802,59.447073],"out":3,"turn_duration":1.0,"turn_weight":1.0,"weight":68.059},{"admin_index":0,"bearings":[61,153,244,327],"duration":80.059,"entry":[true,false,true,true],"geometry_index":85,"in":1,"location":[24.74511,59.447848],"out":3,"turn_duration":1.0,"turn_weight":1.0,"weight":84.012},{"admin_index":0,"bearings":[38,133,304],"duration":32.139,"entry":[true,false,true],"geometry_index":89,"in":1,"location":[24.743973,59.448527],"out":2,"weight":40.174},{"admin_index":0,"bearings":[124,215,298],"duration":102.353,"entry":[false,true,true],"geometry_index":90,"in":0,"location":[24.743545,59.448671],"out":2,"weight":102.353},{"admin_index":0,"bearings":[103,121,291],"duration":5.647,"entry":[false,true,true],"geometry_index":94,"in":0,"location":[24.741172,59.449132],"out":2,"weight":5.647},{"admin_index":0,"bearings":[20,111,291],"entry":[true,false,true],"geometry_index":95,"in":1,"location":[24.741044,59.449157],"out":2}],"maneuver":{"bearing_after":266,"bearing_before":297,"instruction":"Bear left onto Kultuurikilomeeter.","location":[24.757636,59.445069],"modifier":"slight left","type":"turn"},"mode":"walking","name":"Kultuurikilomeeter","speedLimitSign":"vienna","speedLimitUnit":"km/h","voiceInstructions":[{"announcement":"In 200 feet, Turn right onto the walkway.","distanceAlongGeometry":60.0,"ssmlAnnouncement":"<speak>In 200 feet, Turn right onto the walkway.</speak>"}],"weight":1015.202},{"bannerInstructions":[{"distanceAlongGeometry":23.0,"primary":{"components":[{"text":"Turn left onto the walkway.","type":"text"}],"modifier":"left","text":"Turn left onto the walkway.","type":"turn"}}],"distance":23.0,"driving_side":"right","duration":18.235,"geometry":"gfokpBml~dn@kFmDsCyA","intersections":[{"admin_index":0,"bearings":[21,112,291],"duration":9.882,"entry":[true,false,true],"geometry_index":97,"in":1,"location":[24.739543,59.44946],"out":0,"turn_weight":5.0,"weight":14.882},{"admin_index":0,"bearings":[17,115,201,291],"entry":[true,true,false,true],"geometry_index":98,"in":2,"location":[24.73963,59.449578],"out":0,"turn_duration":2.0,"turn_weight":2.0}],"maneuver":{"bearing_after":21,"bearing_before":292,"instruction":"Turn right onto the walkway.","location":[24.739543,59.44946],"modifier":"right","type":"turn"},"mode":"walking","name":"","speedLimitSign":"vienna","speedLimitUnit":"km/h","voiceInstructions":[{"announcement":"In 37 feet, Turn left onto the walkway.","distanceAlongGeometry":11.5,"ssmlAnnouncement":"<speak>In 37 feet, Turn left onto the walkway.</speak>"}],"weight":23.235},{"bannerInstructions":[{"distanceAlongGeometry":16.0,"primary":{"components":[{"text":"Turn left onto the crosswalk.","type":"text"}],"modifier":"left","text":"Turn left onto the crosswalk.","type":"turn"}}],"distance":16.0,"driving_side":"right","duration":11.294,"geometry":"grokpBut~dn@yArJgAdA","intersections":[{"admin_index":0,"bearings":[111,197,304],"entry":[true,false,true],"geometry_index":99,"in":1,"location":[24.739675,59.449652],"out":2}],"maneuver":{"bearing_after":304,"bearing_before":17,"instruction":"Turn left onto the walkway.","location":[24.739675,59.449652],"modifier":"left","type":"turn"},"mode":"walking","name":"","speedLimitSign":"vienna","speedLimitUnit":"km/h","voiceInstructions":[{"announcement":"In 26 feet, Turn left onto the crosswalk.","distanceAlongGeometry":8.0,"ssmlAnnouncement":"<speak>In 26 feet, Turn left onto the crosswalk.</speak>"}],"weight":11.294},{"bannerInstructions":[{"distanceAlongGeometry":347.0,"primary":{"components":[{"text":"Turn right onto the walkway.","type":"text"}],"modifier":"right","text":"Turn right onto the walkway.","type":"turn"}}],"distance":347.0,"driving_side":"right","duration":263.849,"geometry":"iwokpB{f~dn@JhDm@`G_@fCMrAmAfFiKf|@{Fxh@oCdSi@dGaBrQcBbNwCd\\kGlh@uA~PuEzr@_@bHa@dC}@\\KbEOvC","intersections":[{"admin_index":0,"bearings":[8,127,262],"duration":3.529,"entry":[true,false,true],"geometry_index":101,"in":1,"location":[24.739454,59.449733],"out":2,"weight":3.529},{"admin_index":0,"bearings":[82,156,289,358],"duration":6.647,"entry":[false,true,true,true],"geometry_index":102,"in":0,"location":[24.739369,59.449727],"out":2,"turn_duration":1.0,"turn_weight":1.0,"weight":6.647},{"admin_index":0,"bearings":[44,109,255,295],"duration":3.823,"entry":[true,false,true,true],"geometry_index":103,"in":1,"location":[24.73924,59.44975],"out":3,"turn_duration":1.0,"turn_weight":1.0,"weight":3.823},{"admin_index":0,"bearings":[15,115,297],"duration":82.306,"entry":[true,false,true],"geometry_index":104,"in":1,"location":[24.739172,59.449766],"out":2,"weight":82.306},{"admin_index":0,"bearings":[110,201,294],"duration":15.529,"entry":[false,true,true],"geometry_index":108,"in":0,"location":[24.737365,59.450135],"out":2,"weight":15.529},{"admin_index":0,"bearings":[20,114,207,288],"duration":6.647,"entry":[true,false,true,true],"geometry_index":109,"in":1,"location":[24.737042,59.450207],"out":3,"turn_duration":1.0,"turn_weight":1.0,"weight":6.647},{"admin_index":0,"bearings":[32,108,288],"duration":42.353,"entry":[true,false,true],"geometry_index":110,"in":1,"location":[24.736911,59.450228],"out":2,"weight":42.353},{"admin_index":0,"bearings":[34,108,292],"duration":82.306,"entry":[true,false,true],"geometry_index":113,"in":1,"location":[24.735904,59.450403],"out":2,"weight":82.306},{"admin_index":0,"bearings":[104,191,295],"duration":12.649,"entry":[false,true,true],"geometry_index":116,"in":0,"location":[24.734123,59.450687],"out":2,"weight":13.282},{"admin_index":0,"bearings":[13,120,277],"duration":4.235,"entry":[true,false,true],"geometry_index":119,"in":1,"location":[24.733895,59.450751],"out":2,"weight":4.235},{"admin_index":0,"bearings":[14,97,193,282],"entry":[true,false,true,true],"geometry_index":120,"in":1,"location":[24.733797,59.450757],"out":3,"turn_duration":1.0,"turn_weight":1.0}],"maneuver":{"bearing_after":262,"bearing_before":307,"instruction":"Turn left onto the crosswalk.","location":[24.739454,59.449733],"modifier":"left","type":"turn"},"mode":"walking","name":"","speedLimitSign":"vienna","speedLimitUnit":"km/h","voiceInstructions":[{"announcement":"In 200 feet, Turn right onto the walkway.","distanceAlongGeometry":60.0,"ssmlAnnouncement":"<speak>In 200 feet, Turn right onto the walkway.</speak>"}],"weight":264.482},{"bannerInstructions":[{"distanceAlongGeometry":2.0,"primary":{"components":[{"text":"Turn left onto the walkway.","type":"text"}],"modifier":"left","text":"Turn left onto the walkway.","type":"turn"}}],"distance":2.0,"driving_side":"right","duration":1.412,"geometry":"ywqkpBq`sdn@k@F","intersections":[{"admin_index":0,"bearings":[102,269,355],"entry":[false,true,true],"geometry_index":121,"in":0,"location":[24.733721,59.450765],"out":2}],"maneuver":{"bearing_after":355,"bearing_before":282,"instruction":"Turn right onto the walkway.","location":[24.733721,59.450765],"modifier":"right","type":"turn"},"mode":"walking","name":"","speedLimitSign":"vienna","speedLimitUnit":"km/h","voiceInstructions":[{"announcement":"In 3 feet, Turn left onto the walkway.","distanceAlongGeometry":1.0,"ssmlAnnouncement":"<speak>In 3 feet, Turn left onto the walkway.</speak>"}],"weight":1.412},{"bannerInstructions":[{"distanceAlongGeometry":241.0,"primary":{"components":[{"text":"Allveelaeva","type":"text"}],"modifier":"slight left","text":"Allveelaeva","type":"turn"}}],"distance":241.0,"driving_side":"right","duration":184.456,"geometry":"eyqkpBi`sdn@oQbw@uNno@Gv@SxCo@hEiA`@i@nBf@pCQtDk@xC{B|KgTraAuA\\i@o@","intersections":[{"admin_index":0,"bearings":[14,175,303],"duration":45.642,"entry":[true,false,true],"geometry_index":122,"in":1,"location":[24.733717,59.450787],"out":2,"weight":45.642},{"admin_index":0,"bearings":[100,123,302],"duration":41.929,"entry":[true,false,true],"geometry_index":123,"in":1,"location":[24.732819,59.451083],"out":2,"weight":41.929},{"admin_index":0,"bearings":[119,213,284],"duration":2.823,"entry":[false,true,true],"geometry_index":125,"in":0,"location":[24.732015,59.451338],"out":2,"weight":2.823},{"admin_index":0,"bearings":[31,104,214,303],"duration":19.635,"entry":[true,false,true,true],"geometry_index":126,"in":1,"location":[24.731938,59.451348],"out":3,"turn_duration":1.0,"turn_weight":1.0,"weight":19.635},{"admin_index":0,"bearings":[25,89,193,299],"duration":4.529,"entry":[true,false,true,true],"geometry_index":131,"in":1,"location":[24.7316,59.451419],"out":3,"turn_duration":1.0,"turn_weight":1.0,"weight":4.529},{"admin_index":0,"bearings":[119,194,301],"duration":14.132,"entry":[false,true,true],"geometry_index":132,"in":0,"location":[24.731523,59.451441],"out":2,"weight":16.958},{"admin_index":0,"bearings":[38,121,302],"entry":[true,false,true],"geometry_index":133,"in":1,"location":[24.731316,59.451503],"out":2}],"maneuver":{"bearing_after":303,"bearing_before":355,"instruction":"Turn left onto the walkway.","location":[24.733717,59.450787],"modifier":"left","type":"turn"},"mode":"walking","name":"","speedLimitSign":"vienna","speedLimitUnit":"km/h","voiceInstructions":[{"announcement":"In 200 feet, Bear left onto Allveelaeva.","distanceAlongGeometry":60.0,"ssmlAnnouncement":"<speak>In 200 feet, Bear left onto Allveelaeva.</speak>"}],"weight":187.283},{"bannerInstructions":[{"distanceAlongGeometry":28.0,"primary":{"components":[{"text":"Peetri","type":"text"}],"modifier":"right","text":"Peetri","type":"turn"}}],"distance":28.0,"driving_side":"right","duration":20.951,"geometry":"e_tkpBehldn@mFzY","intersections":[{"admin_index":0,"bearings":[32,120,152,299],"entry":[true,true,false,true],"geometry_index":136,"in":2,"location":[24.730259,59.451907],"out":3,"turn_weight":5.0}],"maneuver":{"bearing_after":299,"bearing_before":332,"instruction":"Bear left onto Allveelaeva.","location":[24.730259,59.451907],"modifier":"slight left","type":"turn"},"mode":"walking","name":"Allveelaeva","speedLimitSign":"vienna","speedLimitUnit":"km/h","voiceInstructions":[{"announcement":"In 45 feet, Turn right onto Peetri.","distanceAlongGeometry":14.0,"ssmlAnnouncement":"<speak>In 45 feet, Turn right onto Peetri.</speak>"}],"weight":25.951},{"bannerInstructions":[{"distanceAlongGeometry":25.099,"primary":{"components":[{"text":"You have arrived at your destination.","type":"text"}],"text":"You have arrived at your destination.","type":"arrive"}}],"distance":25.099,"driving_side":"right","duration":24.804,"geometry":"sftkpBimkdn@}GiGqBoC","intersections":[{"admin_index":0,"bearings":[1,25,119,208],"entry":[true,true,false,true],"geometry_index":137,"in":2,"location":[24.729829,59.452026],"out":1,"turn_weight":5.0}],"maneuver":{"bearing_after":25,"bearing_before":299,"instruction":"Turn right onto Peetri.","location":[24.729829,59.452026],"modifier":"right","type":"turn"},"mode":"walking","name":"Peetri","speedLimitSign":"vienna","speedLimitUnit":"km/h","voiceInstructions":[{"announcement":"In 41 feet, You have arrived at your destination.","distanceAlongGeometry":12.5495,"ssmlAnnouncement":"<speak>In 41 feet, You have arrived at your destination.</speak>"}],"weight":54.607},{"bannerInstructions":[{"distanceAlongGeometry":0.0,"primary":{"components":[{"text":"You have arrived at your destination.","type":"text"}],"text":"You have arrived at your destination.","type":"arrive"}}],"distance":0.0,"driving_side":"right","duration":0.0,"geometry":"cstkpBczkdn@??","intersections":[{"admin_index":0,"bearings":[213],"entry":[true],"geometry_index":139,"in":0,"location":[24.730034,59.452226]}],"maneuver":{"bearing_after":0,"bearing_before":33,"instruction":"You have arrived at your destination.","location":[24.730034,59.452226],"type":"arrive"},"mode":"walking","name":"Peetri","speedLimitSign":"vienna","speedLimitUnit":"km/h","voiceInstructions":[],"weight":0.0}],"summary":"Logi, Kultuurikilomeeter","via_waypoints":[],"weight":2132.626}],"weight":2132.626,"weight_name":"pedestrian"}],"waypoints":[{"distance":0.546,"location":[24.765368,59.442643],"name":""},{"distance":0.134,"location":[24.730034,59.452226],"name":"Peetri"}]}"#;
-    const VALHALLA_OSRM_RESPONSE_VIA_WAYS: &str = r#"{"code":"Ok","routes":[{"distance":2089.442,"duration":301.262,"geometry":"oop|u@ntan{Cn@gArJ{HvKuCpVsHxC_AhEiBpCkA|AN|Bn@fC}@dI_IjGmHfH_EpBwBxI{PfIsOzGgMpGyQnDmIvDmHf@gCT{AdByTtByKVoCJwBBqA`@oPe@uQe@eNMaTK{Lu@aO}EoWsDgL{KaTiDwHoC{K_AkAu@_BcC{DsHgKuDoGcE_KwB}JsAqPIeJ`@}IvAeKd@mEd@wCDmBGkLAoGv@qHKaGYqFw@kI{@}NToI~@yIjBaJtAkF`D{JxGkQ~J}O`KuJtSmOjKgLtJ}IhB}EvBcIfAiJ`CgJb@gHp@iLnAmQzBcNnCkLhGaUpFmMzHiMdKqMhIoLjJcQjEgKzAgFtAqGvAgNjC}NbAkGTeMp@aG`CiKzCwJdI}QvKcOfG{MtEmPdBsP","legs":[{"admins":[{"iso_3166_1":"US","iso_3166_1_alpha3":"USA"}],"annotation":{"distance":[4.4,25.8,23.9,44.6,9.1,12.4,8.9,5.3,7.4,8.2,23.9,21.0,19.0,8.6,33.9,31.7,27.3,33.1,19.0,17.9,7.0,4.7,34.5,21.0,7.2,5.9,4.0,27.4,29.2,23.8,32.9,21.7,25.3,40.2,23.0,40.1,17.9,21.6,5.1,5.6,11.8,25.7,16.7,21.7,19.8,27.8,17.5,17.2,19.6,10.3,7.7,5.4,20.9,13.3,15.2,12.6,11.9,16.5,25.1,16.4,17.2,18.3,12.5,20.6,32.7,34.0,28.2,44.9,30.2,26.9,12.3,17.2,18.1,19.0,14.6,21.0,29.1,24.6,22.4,37.5,26.3,28.4,31.4,28.0,34.8,22.2,12.4,14.2,24.3,26.1,13.6,22.2,12.9,20.5,20.3,34.7,33.9,27.5,29.7,28.1],"duration":[0.635,3.717,3.436,6.419,1.314,1.782,1.286,0.762,1.065,1.174,3.447,3.019,2.729,1.243,4.882,4.569,3.929,4.765,2.738,2.584,1.008,0.67,4.971,3.031,1.03,0.848,0.577,3.943,4.211,3.427,4.736,3.12,3.636,5.787,3.309,5.772,2.581,3.116,0.74,0.801,1.692,3.698,2.404,3.121,2.85,4.005,2.516,2.473,2.829,1.479,1.11,0.774,3.007,1.911,2.196,1.815,1.713,2.375,3.614,2.367,2.484,2.633,1.795,2.968,4.708,4.896,4.059,6.466,4.352,3.877,1.776,2.471,2.607,2.735,2.099,3.019,4.194,3.542,3.22,5.398,3.781,4.094,4.526,4.025,5.011,3.202,1.789,2.044,3.5,3.754,1.96,3.194,1.856,2.957,2.922,4.995,4.882,3.957,4.278,4.048],"maxspeed":[{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true}],"speed":[6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9]},"distance":2089.442,"duration":301.262,"steps":[{"bannerInstructions":[{"distanceAlongGeometry":2089.442,"primary":{"components":[{"text":"You have arrived at your destination.","type":"text"}],"text":"You have arrived at your destination.","type":"arrive"}}],"distance":2089.442,"driving_side":"right","duration":301.262,"geometry":"oop|u@ntan{Cn@gArJ{HvKuCpVsHxC_AhEiBpCkA|AN|Bn@fC}@dI_IjGmHfH_EpBwBxI{PfIsOzGgMpGyQnDmIvDmHf@gCT{AdByTtByKVoCJwBBqA`@oPe@uQe@eNMaTK{Lu@aO}EoWsDgL{KaTiDwHoC{K_AkAu@_BcC{DsHgKuDoGcE_KwB}JsAqPIeJ`@}IvAeKd@mEd@wCDmBGkLAoGv@qHKaGYqFw@kI{@}NToI~@yIjBaJtAkF`D{JxGkQ~J}O`KuJtSmOjKgLtJ}IhB}EvBcIfAiJ`CgJb@gHp@iLnAmQzBcNnCkLhGaUpFmMzHiMdKqMhIoLjJcQjEgKzAgFtAqGvAgNjC}NbAkGTeMp@aG`CiKzCwJdI}QvKcOfG{MtEmPdBsP","intersections":[{"admin_index":0,"bearings":[134],"duration":4.352,"entry":[true],"geometry_index":0,"location":[-82.036056,28.795656],"out":0,"weight":8.922},{"admin_index":0,"bearings":[160,162,323],"duration":9.823,"entry":[false,true,false],"geometry_index":2,"in":2,"location":[-82.035862,28.795446],"out":1,"weight":20.138},{"admin_index":0,"bearings":[160,340],"duration":3.024,"entry":[true,false],"geometry_index":4,"in":1,"location":[-82.035633,28.794865],"out":0,"turn_weight":1.5,"weight":7.699},{"admin_index":0,"bearings":[155,335],"duration":4.32,"entry":[true,false],"geometry_index":6,"in":1,"location":[-82.035548,28.794687],"out":0,"turn_weight":1.5,"weight":10.356},{"admin_index":0,"bearings":[59,139,239,338],"duration":10.445,"entry":[true,true,true,false],"geometry_index":10,"in":3,"location":[-82.035511,28.794436],"out":1,"turn_duration":0.077,"turn_weight":6.0,"weight":27.254},{"admin_index":0,"bearings":[52,125,239,317],"duration":24.525,"entry":[true,true,true,false],"geometry_index":14,"in":3,"location":[-82.035044,28.793934],"out":1,"turn_duration":0.045,"turn_weight":6.0,"weight":56.184},{"admin_index":0,"bearings":[18,103,199,290],"duration":10.542,"entry":[true,true,true,false],"geometry_index":21,"in":3,"location":[-82.033577,28.793118],"out":1,"turn_duration":0.03,"turn_weight":6.0,"weight":27.55},{"admin_index":0,"bearings":[7,94,184,277],"duration":45.814,"entry":[true,true,true,false],"geometry_index":26,"in":3,"location":[-82.032845,28.792979],"out":1,"turn_duration":0.022,"turn_weight":6.0,"weight":99.874},{"admin_index":0,"bearings":[51,148,234,324],"duration":28.246,"entry":[true,true,false,true],"geometry_index":40,"in":2,"location":[-82.029777,28.793661],"out":0,"turn_duration":0.022,"turn_weight":6.0,"weight":63.859},{"admin_index":0,"bearings":[8,92,186,286],"duration":9.7,"entry":[true,true,true,false],"geometry_index":51,"in":3,"location":[-82.027959,28.794078],"out":1,"turn_duration":0.052,"turn_weight":6.6,"weight":26.378},{"admin_index":0,"bearings":[83,182,267,356],"duration":17.016,"entry":[true,true,false,true],"geometry_index":56,"in":2,"location":[-82.027272,28.794058],"out":0,"turn_duration":0.024,"turn_weight":6.6,"weight":41.434},{"admin_index":0,"bearings":[116,293],"duration":33.12,"entry":[true,false],"geometry_index":63,"in":1,"location":[-82.026094,28.793989],"out":0,"turn_weight":1.65,"weight":69.546},{"admin_index":0,"bearings":[30,113,208,299],"duration":9.962,"entry":[true,true,true,false],"geometry_index":71,"in":3,"location":[-82.024391,28.792613],"out":1,"turn_duration":0.026,"turn_weight":6.6,"weight":26.969},{"admin_index":0,"bearings":[13,98,191,278],"duration":10.808,"entry":[true,true,true,false],"geometry_index":75,"in":3,"location":[-82.02372,28.792434],"out":1,"turn_duration":0.008,"turn_weight":6.6,"weight":28.74},{"admin_index":0,"bearings":[18,111,199,286],"duration":35.002,"entry":[true,true,true,false],"geometry_index":78,"in":3,"location":[-82.02297,28.792307],"out":1,"turn_duration":0.01,"turn_weight":6.6,"weight":78.334},{"admin_index":0,"bearings":[25,110,191,294],"duration":9.384,"entry":[true,true,true,false],"geometry_index":87,"in":3,"location":[-82.020892,28.791133],"out":1,"turn_duration":0.024,"turn_weight":8.8,"weight":27.988},{"admin_index":0,"bearings":[106,204,287],"duration":5.203,"entry":[true,false,false],"geometry_index":90,"in":2,"location":[-82.020256,28.790976],"out":0,"turn_duration":0.019,"turn_weight":1.65,"weight":12.277},{"admin_index":0,"bearings":[7,102,184,273],"duration":7.788,"entry":[true,true,true,false],"geometry_index":92,"in":3,"location":[-82.019895,28.790931],"out":1,"turn_duration":0.012,"turn_weight":6.6,"weight":22.541},{"admin_index":0,"bearings":[122,213,295],"entry":[true,false,false],"geometry_index":95,"in":2,"location":[-82.019381,28.790763],"out":0,"turn_duration":0.01,"turn_weight":1.65}],"maneuver":{"bearing_after":134,"bearing_before":0,"instruction":"Drive southeast.","location":[-82.036056,28.795656],"type":"depart"},"mode":"driving","name":"","speedLimitSign":"mutcd","speedLimitUnit":"mph","voiceInstructions":[{"announcement":"Drive southeast.","distanceAlongGeometry":2089.442,"ssmlAnnouncement":"<speak>Drive southeast.</speak>"},{"announcement":"In 200 feet, You have arrived at your destination.","distanceAlongGeometry":60.0,"ssmlAnnouncement":"<speak>In 200 feet, You have arrived at your destination.</speak>"}],"weight":703.153},{"bannerInstructions":[{"distanceAlongGeometry":0.0,"primary":{"components":[{"text":"You have arrived at your destination.","type":"text"}],"text":"You have arrived at your destination.","type":"arrive"}}],"distance":0.0,"driving_side":"right","duration":0.0,"geometry":"ste|u@hm~l{C??","intersections":[{"admin_index":0,"bearings":[282],"entry":[true],"geometry_index":100,"in":0,"location":[-82.018021,28.790106]}],"maneuver":{"bearing_after":0,"bearing_before":102,"instruction":"You have arrived at your destination.","location":[-82.018021,28.790106],"type":"arrive"},"mode":"driving","name":"","speedLimitSign":"mutcd","speedLimitUnit":"mph","voiceInstructions":[],"weight":0.0}],"summary":"","via_waypoints":[{"distance_from_start":30.223,"geometry_index":2,"waypoint_index":1}],"weight":703.153}],"weight":703.153,"weight_name":"golf_cart"}],"waypoints":[{"distance":0.095,"location":[-82.036056,28.795656],"name":""},{"distance":0.0,"location":[-82.035862,28.795446],"name":""},{"distance":4.898,"location":[-82.018021,28.790106],"name":""}]}"#;
+    pub(super) const STANDARD_OSRM_POLYLINE6_RESPONSE: &str = r#"{"code":"Ok","routes":[{"geometry":"qikdcB{~dpXmxRbaBuqAoqKyy@svFwNcfKzsAysMdr@evD`m@qrAohBi}A{OkdGjg@ajDZww@lJ}Jrs@}`CvzBq`E`PiB`~A|l@z@feA","legs":[{"steps":[],"summary":"","weight":263.1,"duration":260.2,"distance":1886.3},{"steps":[],"summary":"","weight":370.5,"duration":370.5,"distance":2845.5}],"weight_name":"routability","weight":633.6,"duration":630.7,"distance":4731.8}],"waypoints":[{"hint":"Dv8JgCp3moUXAAAABQAAAAAAAAAgAAAAIXRPQYXNK0AAAAAAcPePQQsAAAADAAAAAAAAABAAAAA6-wAA_kvMAKlYIQM8TMwArVghAwAA7wrXLH_K","distance":4.231521214,"name":"Friedrichstraße","location":[13.388798,52.517033]},{"hint":"JEvdgVmFiocGAAAACgAAAAAAAAB3AAAAppONQOodwkAAAAAA8TeEQgYAAAAKAAAAAAAAAHcAAAA6-wAAfm7MABiJIQOCbswA_4ghAwAAXwXXLH_K","distance":2.795148358,"name":"Torstraße","location":[13.39763,52.529432]},{"hint":"oSkYgP___38fAAAAUQAAACYAAAAeAAAAeosKQlNOX0IQ7CZCjsMGQh8AAABRAAAAJgAAAB4AAAA6-wAASufMAOdwIQNL58wA03AhAwQAvxDXLH_K","distance":2.226580806,"name":"Platz der Vereinten Nationen","location":[13.428554,52.523239]}]}"#;
+    pub(super) const VALHALLA_OSRM_RESPONSE: &str = r#"{"code":"Ok","routes":[{"distance":2604.35,"duration":2007.289,"geometry":"e|akpBozpfn@AG~ApSAzFg@pKsFvfA]lFdDr@kAvOoAvDkC|B]~DMzAyCj^c@lFi@d@wIbHu@f@cV|PkA~@_TxQxX|eC{Az@qDrBw@b@{BnATbCNjBd@rHyAj@g@JiDrAcJxDcBjBcA^sDvAsIjDmCnD}@R`@bHgHnBsRvGkDhCsDpTpF~dEPfMfAft@H~FNrEdAt}@f@pY@rA?`@@rBJhRCdAIbD]nFa@bDaAbIiAdImB~MKt@wGrd@qBnOoDbUwAxJVfH\\jMHpEGzAiAjDqMbf@gBnFkC~HeDbKs@vBkCtF}CpGuIzNU`@oGzH{FhGqi@hc@ud@t_@wIpI{JfNqLfTwJjVgDdJ_HvYaEpUgHxa@aFhd@mErt@q@~FmFrd@oJdw@kFmDsCyAyArJgAdAJhDm@`G_@fCMrAmAfFiKf|@{Fxh@oCdSi@dGaBrQcBbNwCd\\kGlh@uA~PuEzr@_@bHa@dC}@\\KbEOvCk@FoQbw@uNno@Gv@SxCo@hEiA`@i@nBf@pCQtDk@xC{B|KgTraAuA\\i@o@mFzY}GiGqBoC","legs":[{"admins":[{"iso_3166_1":"EE","iso_3166_1_alpha3":"EST"}],"annotation":{"distance":[0.2,19.4,7.1,11.6,66.4,6.9,9.4,15.7,6.9,8.6,5.7,2.7,29.7,7.0,2.6,20.9,3.2,44.3,4.6,41.1,130.5,5.4,10.4,3.3,7.3,3.9,3.2,9.0,5.2,2.3,9.8,20.5,6.4,3.9,10.3,19.5,9.3,3.5,8.5,16.8,35.8,10.3,21.9,179.8,12.9,48.4,7.3,6.1,56.9,24.2,2.4,1.0,3.3,17.5,2.0,4.7,7.0,5.0,9.9,10.1,14.9,1.7,37.5,16.2,22.3,11.8,8.5,13.1,6.0,2.6,6.4,43.9,8.9,11.9,14.3,4.5,10.4,11.7,23.9,1.6,17.6,15.9,82.6,73.4,21.4,25.3,30.9,29.8,13.8,29.0,23.1,35.6,36.0,49.9,7.8,36.5,54.8,14.0,8.6,11.7,4.5,4.9,7.7,4.2,2.5,7.9,59.6,40.4,20.0,7.8,17.7,14.8,27.7,40.4,17.0,48.4,8.5,4.2,3.6,5.6,4.4,2.5,60.6,52.0,1.6,4.5,6.3,4.2,3.9,4.7,5.2,5.0,13.6,71.2,4.9,2.7,27.7,17.6,7.5],"duration":[0.184,15.315,5.639,9.818,51.539,4.898,6.604,12.227,5.127,6.412,4.012,1.919,20.947,4.96,1.815,14.72,2.267,33.128,3.248,29.012,101.367,3.808,8.841,2.592,5.742,2.774,2.247,6.331,3.643,1.589,6.887,14.472,4.482,2.747,7.288,13.793,6.594,2.469,5.983,13.027,27.83,8.028,15.49,126.908,12.15,34.152,5.129,4.281,42.571,18.073,1.781,0.681,2.318,17.664,2.012,4.717,7.059,5.058,7.669,7.844,10.516,1.177,26.445,11.458,15.741,8.304,5.987,13.246,4.213,1.864,4.77,32.852,6.677,8.938,10.737,3.339,7.818,11.006,22.394,1.32,14.893,13.483,69.994,51.784,15.108,19.969,24.415,23.531,10.899,32.187,16.31,25.104,25.445,35.213,5.477,25.799,38.709,9.902,6.086,8.228,3.156,3.427,5.46,2.993,1.871,5.888,44.617,30.206,15.496,5.487,12.51,10.434,19.585,31.347,13.188,37.62,6.681,3.349,2.809,3.942,3.1,1.737,45.312,40.41,1.278,3.174,4.898,3.284,3.057,3.644,4.072,3.527,13.723,50.263,3.432,1.908,20.727,17.401,7.403],"maxspeed":[{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"speed":30,"unit":"km/h"},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true}],"speed":[1.3,1.3,1.3,1.2,1.3,1.4,1.4,1.3,1.3,1.3,1.4,1.4,1.4,1.4,1.4,1.4,1.4,1.3,1.4,1.4,1.3,1.4,1.2,1.3,1.3,1.4,1.4,1.4,1.4,1.4,1.4,1.4,1.4,1.4,1.4,1.4,1.4,1.4,1.4,1.3,1.3,1.3,1.4,1.4,1.1,1.4,1.4,1.4,1.3,1.3,1.3,1.4,1.4,1.0,1.0,1.0,1.0,1.0,1.3,1.3,1.4,1.4,1.4,1.4,1.4,1.4,1.4,1.0,1.4,1.4,1.3,1.3,1.3,1.3,1.3,1.3,1.3,1.1,1.1,1.2,1.2,1.2,1.2,1.4,1.4,1.3,1.3,1.3,1.3,0.9,1.4,1.4,1.4,1.4,1.4,1.4,1.4,1.4,1.4,1.4,1.4,1.4,1.4,1.4,1.3,1.3,1.3,1.3,1.3,1.4,1.4,1.4,1.4,1.3,1.3,1.3,1.3,1.3,1.3,1.4,1.4,1.4,1.3,1.3,1.3,1.4,1.3,1.3,1.3,1.3,1.3,1.4,1.0,1.4,1.4,1.4,1.3,1.0,1.0]},"distance":2604.35,"duration":2007.289,"steps":[{"bannerInstructions":[{"distanceAlongGeometry":111.251,"primary":{"components":[{"text":"Turn left onto the walkway.","type":"text"}],"modifier":"left","text":"Turn left onto the walkway.","type":"turn"}}],"distance":111.251,"driving_side":"right","duration":90.107,"geometry":"e|akpBozpfn@AG~ApSAzFg@pKsFvfA]lF","intersections":[{"admin_index":0,"bearings":[254],"duration":20.754,"entry":[true],"geometry_index":0,"location":[24.765368,59.442643],"out":0,"weight":21.791},{"admin_index":0,"bearings":[7,82,189,281],"duration":11.165,"entry":[true,false,true,true],"geometry_index":3,"in":1,"location":[24.764917,59.442597],"out":3,"turn_duration":1.0,"turn_weight":1.0,"weight":12.181},{"admin_index":0,"bearings":[13,101,191,282],"duration":52.247,"entry":[true,false,true,true],"geometry_index":4,"in":1,"location":[24.764716,59.442617],"out":3,"turn_duration":1.0,"turn_weight":1.0,"weight":52.247},{"admin_index":0,"bearings":[49,102,191,284],"entry":[true,false,true,true],"geometry_index":5,"in":1,"location":[24.763568,59.442739],"out":3,"turn_duration":1.0,"turn_weight":1.0}],"maneuver":{"bearing_after":254,"bearing_before":0,"instruction":"Walk west on the walkway.","location":[24.765368,59.442643],"type":"depart"},"mode":"walking","name":"","speedLimitSign":"vienna","speedLimitUnit":"km/h","voiceInstructions":[{"announcement":"Walk west on the walkway.","distanceAlongGeometry":111.251,"ssmlAnnouncement":"<speak>Walk west on the walkway.</speak>"},{"announcement":"In 200 feet, Turn left onto the walkway.","distanceAlongGeometry":60.0,"ssmlAnnouncement":"<speak>In 200 feet, Turn left onto the walkway.</speak>"}],"weight":92.161},{"bannerInstructions":[{"distanceAlongGeometry":9.0,"primary":{"components":[{"text":"Laeva","type":"text"}],"modifier":"right","text":"Laeva","type":"turn"}}],"distance":9.0,"driving_side":"right","duration":6.353,"geometry":"ccbkpBqbmfn@dDr@","intersections":[{"admin_index":0,"bearings":[14,104,189],"entry":[true,false,true],"geometry_index":6,"in":1,"location":[24.763449,59.442754],"out":2}],"maneuver":{"bearing_after":189,"bearing_before":284,"instruction":"Turn left onto the walkway.","location":[24.763449,59.442754],"modifier":"left","type":"turn"},"mode":"walking","name":"","speedLimitSign":"vienna","speedLimitUnit":"km/h","voiceInstructions":[{"announcement":"In 14 feet, Turn right onto Laeva.","distanceAlongGeometry":4.5,"ssmlAnnouncement":"<speak>In 14 feet, Turn right onto Laeva.</speak>"}],"weight":6.353},{"bannerInstructions":[{"distanceAlongGeometry":16.0,"primary":{"components":[{"text":"Bear right.","type":"text"}],"modifier":"slight right","text":"Bear right.","type":"turn"}}],"distance":16.0,"driving_side":"right","duration":12.424,"geometry":"}}akpB}`mfn@kAvO","intersections":[{"admin_index":0,"bearings":[9,101,200,286],"entry":[false,true,true,true],"geometry_index":7,"in":0,"location":[24.763423,59.442671],"out":3,"turn_weight":5.0}],"maneuver":{"bearing_after":286,"bearing_before":189,"instruction":"Turn right onto Laeva.","location":[24.763423,59.442671],"modifier":"right","type":"turn"},"mode":"walking","name":"Laeva","speedLimitSign":"vienna","speedLimitUnit":"km/h","voiceInstructions":[{"announcement":"In 26 feet, Bear right.","distanceAlongGeometry":8.0,"ssmlAnnouncement":"<speak>In 26 feet, Bear right.</speak>"}],"weight":17.424},{"bannerInstructions":[{"distanceAlongGeometry":15.0,"primary":{"components":[{"text":"Bear left onto the walkway.","type":"text"}],"modifier":"slight left","text":"Bear left onto the walkway.","type":"turn"}}],"distance":15.0,"driving_side":"right","duration":11.224,"geometry":"i`bkpBeplfn@oAvDkC|B","intersections":[{"admin_index":0,"bearings":[106,191,324],"entry":[false,true,true],"geometry_index":8,"in":0,"location":[24.763155,59.442709],"out":2,"turn_weight":5.0}],"maneuver":{"bearing_after":324,"bearing_before":286,"instruction":"Bear right.","location":[24.763155,59.442709],"modifier":"slight right","type":"turn"},"mode":"walking","name":"","speedLimitSign":"vienna","speedLimitUnit":"km/h","voiceInstructions":[{"announcement":"In 24 feet, Bear left onto the walkway.","distanceAlongGeometry":7.5,"ssmlAnnouncement":"<speak>In 24 feet, Bear left onto the walkway.</speak>"}],"weight":16.224},{"bannerInstructions":[{"distanceAlongGeometry":38.0,"primary":{"components":[{"text":"Continue.","type":"text"}],"modifier":"straight","text":"Continue.","type":"new name"}}],"distance":38.0,"driving_side":"right","duration":26.824,"geometry":"egbkpBoflfn@]~DMzAyCj^","intersections":[{"admin_index":0,"bearings":[1,70,145,287],"duration":5.647,"entry":[true,true,false,true],"geometry_index":10,"in":2,"location":[24.763,59.442819],"out":3,"weight":5.647},{"admin_index":0,"bearings":[107,158,287],"entry":[false,true,true],"geometry_index":12,"in":0,"location":[24.762858,59.442841],"out":2}],"maneuver":{"bearing_after":287,"bearing_before":325,"instruction":"Bear left onto the walkway.","location":[24.763,59.442819],"modifier":"slight left","type":"turn"},"mode":"walking","name":"","speedLimitSign":"vienna","speedLimitUnit":"km/h","voiceInstructions":[{"announcement":"In 62 feet, Continue.","distanceAlongGeometry":19.0,"ssmlAnnouncement":"<speak>In 62 feet, Continue.</speak>"}],"weight":26.824},{"bannerInstructions":[{"distanceAlongGeometry":7.0,"primary":{"components":[{"text":"Admiralisild; Admiral Bridge","type":"text"}],"modifier":"right","text":"Admiralisild; Admiral Bridge","type":"turn"}}],"distance":7.0,"driving_side":"right","duration":4.941,"geometry":"kmbkpBg~jfn@c@lF","intersections":[{"admin_index":0,"bearings":[107,155,287],"entry":[false,false,true],"geometry_index":13,"in":0,"location":[24.762356,59.442918],"out":2}],"maneuver":{"bearing_after":287,"bearing_before":287,"instruction":"Continue.","location":[24.762356,59.442918],"modifier":"straight","type":"new name"},"mode":"walking","name":"","speedLimitSign":"vienna","speedLimitUnit":"km/h","voiceInstructions":[{"announcement":"In 11 feet, Turn right onto Admiralisild/Admiral Bridge.","distanceAlongGeometry":3.5,"ssmlAnnouncement":"<speak>In 11 feet, Turn right onto Admiralisild/Admiral Bridge.</speak>"}],"weight":4.941},{"bannerInstructions":[{"distanceAlongGeometry":70.0,"primary":{"components":[{"text":"Continue on the walkway.","type":"text"}],"modifier":"straight","text":"Continue on the walkway.","type":"new name"}}],"distance":70.0,"driving_side":"right","duration":52.275,"geometry":"onbkpByvjfn@i@d@wIbHu@f@cV|P","intersections":[{"admin_index":0,"bearings":[107,168,336],"duration":16.235,"entry":[false,true,true],"geometry_index":14,"in":0,"location":[24.762237,59.442936],"out":2,"weight":16.235},{"admin_index":0,"bearings":[62,157,186,249,339],"duration":3.118,"entry":[true,false,true,true,true],"geometry_index":16,"in":1,"location":[24.762072,59.443129],"out":4,"turn_duration":1.0,"turn_weight":1.0,"weight":3.118},{"admin_index":0,"bearings":[159,338],"entry":[false,true],"geometry_index":17,"in":0,"location":[24.762052,59.443156],"out":1,"turn_weight":5.0}],"maneuver":{"bearing_after":336,"bearing_before":287,"instruction":"Turn right onto Admiralisild/Admiral Bridge.","location":[24.762237,59.442936],"modifier":"right","type":"turn"},"mode":"walking","name":"Admiralisild; Admiral Bridge","speedLimitSign":"vienna","speedLimitUnit":"km/h","voiceInstructions":[{"announcement":"In 200 feet, Continue on the walkway.","distanceAlongGeometry":60.0,"ssmlAnnouncement":"<speak>In 200 feet, Continue on the walkway.</speak>"}],"weight":57.275},{"bannerInstructions":[{"distanceAlongGeometry":46.0,"primary":{"components":[{"text":"Turn left onto the walkway.","type":"text"}],"modifier":"left","text":"Turn left onto the walkway.","type":"turn"}}],"distance":46.0,"driving_side":"right","duration":33.471,"geometry":"ksckpBiyifn@kA~@_TxQ","intersections":[{"admin_index":0,"bearings":[158,337],"duration":3.529,"entry":[false,true],"geometry_index":18,"in":0,"location":[24.761765,59.443526],"out":1,"turn_weight":5.0,"weight":8.529},{"admin_index":0,"bearings":[70,157,246,336],"entry":[true,false,true,true],"geometry_index":19,"in":1,"location":[24.761733,59.443564],"out":3,"turn_duration":1.0,"turn_weight":1.0}],"maneuver":{"bearing_after":337,"bearing_before":338,"instruction":"Continue on the walkway.","location":[24.761765,59.443526],"modifier":"straight","type":"new name"},"mode":"walking","name":"","speedLimitSign":"vienna","speedLimitUnit":"km/h","voiceInstructions":[{"announcement":"In 75 feet, Turn left onto the walkway.","distanceAlongGeometry":23.0,"ssmlAnnouncement":"<speak>In 75 feet, Turn left onto the walkway.</speak>"}],"weight":38.471},{"bannerInstructions":[{"distanceAlongGeometry":131.0,"primary":{"components":[{"text":"Turn right onto the walkway.","type":"text"}],"modifier":"right","text":"Turn right onto the walkway.","type":"turn"}}],"distance":131.0,"driving_side":"right","duration":101.718,"geometry":"wjdkpBodifn@xX|eC","intersections":[{"admin_index":0,"bearings":[55,156,249,336],"entry":[true,false,true,true],"geometry_index":20,"in":1,"location":[24.761432,59.4439],"out":2}],"maneuver":{"bearing_after":249,"bearing_before":336,"instruction":"Turn left onto the walkway.","location":[24.761432,59.4439],"modifier":"left","type":"turn"},"mode":"walking","name":"","speedLimitSign":"vienna","speedLimitUnit":"km/h","voiceInstructions":[{"announcement":"In 200 feet, Turn right onto the walkway.","distanceAlongGeometry":60.0,"ssmlAnnouncement":"<speak>In 200 feet, Turn right onto the walkway.</speak>"}],"weight":101.718},{"bannerInstructions":[{"distanceAlongGeometry":25.0,"primary":{"components":[{"text":"Turn left onto the walkway.","type":"text"}],"modifier":"left","text":"Turn left onto the walkway.","type":"turn"}}],"distance":25.0,"driving_side":"right","duration":21.906,"geometry":"}pckpBq}dfn@{Az@qDrBw@b@{BnA","intersections":[{"admin_index":0,"bearings":[69,251,342],"duration":3.529,"entry":[false,true,true],"geometry_index":21,"in":0,"location":[24.759273,59.443487],"out":2,"weight":3.529},{"admin_index":0,"bearings":[70,162,258,342],"duration":9.471,"entry":[false,false,false,true],"geometry_index":22,"in":1,"location":[24.759243,59.443533],"out":3,"turn_duration":1.0,"turn_weight":1.0,"weight":10.318},{"admin_index":0,"bearings":[70,162,244,342],"entry":[true,false,true,true],"geometry_index":23,"in":1,"location":[24.759185,59.443622],"out":3,"turn_duration":1.0,"turn_weight":1.0}],"maneuver":{"bearing_after":342,"bearing_before":249,"instruction":"Turn right onto the walkway.","location":[24.759273,59.443487],"modifier":"right","type":"turn"},"mode":"walking","name":"","speedLimitSign":"vienna","speedLimitUnit":"km/h","voiceInstructions":[{"announcement":"In 41 feet, Turn left onto the walkway.","distanceAlongGeometry":12.5,"ssmlAnnouncement":"<speak>In 41 feet, Turn left onto the walkway.</speak>"}],"weight":23.148},{"bannerInstructions":[{"distanceAlongGeometry":16.0,"primary":{"components":[{"text":"Logi","type":"text"}],"modifier":"right","text":"Logi","type":"turn"}}],"distance":16.0,"driving_side":"right","duration":12.294,"geometry":"__dkpBmtdfn@TbCNjBd@rH","intersections":[{"admin_index":0,"bearings":[77,162,253,348],"duration":4.941,"entry":[true,false,true,true],"geometry_index":25,"in":1,"location":[24.759127,59.443712],"out":2,"weight":4.941},{"admin_index":0,"bearings":[73,168,256,335],"entry":[false,true,true,true],"geometry_index":27,"in":0,"location":[24.759007,59.443693],"out":2,"turn_duration":1.0,"turn_weight":1.0}],"maneuver":{"bearing_after":253,"bearing_before":342,"instruction":"Turn left onto the walkway.","location":[24.759127,59.443712],"modifier":"left","type":"turn"},"mode":"walking","name":"","speedLimitSign":"vienna","speedLimitUnit":"km/h","voiceInstructions":[{"announcement":"In 26 feet, Turn right onto Logi.","distanceAlongGeometry":8.0,"ssmlAnnouncement":"<speak>In 26 feet, Turn right onto Logi.</speak>"}],"weight":12.294},{"bannerInstructions":[{"distanceAlongGeometry":91.0,"primary":{"components":[{"text":"Turn left onto the walkway.","type":"text"}],"modifier":"left","text":"Turn left onto the walkway.","type":"turn"}}],"distance":91.0,"driving_side":"right","duration":72.235,"geometry":"s|ckpBicdfn@yAj@g@JiDrAcJxDcBjBcA^sDvAsIjDmCnD}@R","intersections":[{"admin_index":0,"bearings":[76,163,258,348],"duration":4.941,"entry":[false,true,true,true],"geometry_index":28,"in":0,"location":[24.758853,59.443674],"out":3,"weight":4.941},{"admin_index":0,"bearings":[61,168,248,345],"duration":28.118,"entry":[true,false,true,true],"geometry_index":30,"in":1,"location":[24.758825,59.443739],"out":3,"turn_duration":2.0,"turn_weight":7.0,"weight":33.118},{"admin_index":0,"bearings":[161,347],"duration":2.824,"entry":[false,true],"geometry_index":33,"in":0,"location":[24.758636,59.444052],"out":1,"weight":2.824},{"admin_index":0,"bearings":[82,167,258,346],"duration":8.059,"entry":[true,false,true,true],"geometry_index":34,"in":1,"location":[24.75862,59.444086],"out":3,"turn_duration":1.0,"turn_weight":1.0,"weight":8.059},{"admin_index":0,"bearings":[18,166,246,346],"duration":19.118,"entry":[true,false,true,true],"geometry_index":35,"in":1,"location":[24.758576,59.444176],"out":3,"turn_duration":5.0,"turn_weight":5.0,"weight":19.118},{"admin_index":0,"bearings":[166,253,328],"duration":6.353,"entry":[false,true,true],"geometry_index":36,"in":0,"location":[24.75849,59.444346],"out":2,"weight":6.353},{"admin_index":0,"bearings":[148,213,351],"entry":[false,true,true],"geometry_index":37,"in":0,"location":[24.758402,59.444417],"out":2}],"maneuver":{"bearing_after":348,"bearing_before":256,"instruction":"Turn right onto Logi.","location":[24.758853,59.443674],"modifier":"right","type":"turn"},"mode":"walking","name":"Logi","speedLimitSign":"vienna","speedLimitUnit":"km/h","voiceInstructions":[{"announcement":"In 200 feet, Turn left onto the walkway.","distanceAlongGeometry":60.0,"ssmlAnnouncement":"<speak>In 200 feet, Turn left onto the walkway.</speak>"}],"weight":77.235},{"bannerInstructions":[{"distanceAlongGeometry":8.0,"primary":{"components":[{"text":"Turn right onto the walkway.","type":"text"}],"modifier":"right","text":"Turn right onto the walkway.","type":"turn"}}],"distance":8.0,"driving_side":"right","duration":5.647,"geometry":"_mekpBofcfn@`@bH","intersections":[{"admin_index":0,"bearings":[77,171,257,346],"entry":[true,false,true,true],"geometry_index":38,"in":1,"location":[24.758392,59.444448],"out":2,"turn_weight":5.0}],"maneuver":{"bearing_after":257,"bearing_before":351,"instruction":"Turn left onto the walkway.","location":[24.758392,59.444448],"modifier":"left","type":"turn"},"mode":"walking","name":"","speedLimitSign":"vienna","speedLimitUnit":"km/h","voiceInstructions":[{"announcement":"In 13 feet, Turn right onto the walkway.","distanceAlongGeometry":4.0,"ssmlAnnouncement":"<speak>In 13 feet, Turn right onto the walkway.</speak>"}],"weight":10.647},{"bannerInstructions":[{"distanceAlongGeometry":85.0,"primary":{"components":[{"text":"Kultuurikilomeeter","type":"text"}],"modifier":"slight left","text":"Kultuurikilomeeter","type":"turn"}}],"distance":85.0,"driving_side":"right","duration":64.447,"geometry":"}kekpBk}bfn@gHnBsRvGkDhCsDpT","intersections":[{"admin_index":0,"bearings":[77,213,349],"duration":48.918,"entry":[false,true,true],"geometry_index":39,"in":0,"location":[24.758246,59.444431],"out":2,"weight":48.918},{"admin_index":0,"bearings":[75,158,297],"entry":[true,false,true],"geometry_index":42,"in":1,"location":[24.757981,59.444979],"out":2}],"maneuver":{"bearing_after":349,"bearing_before":257,"instruction":"Turn right onto the walkway.","location":[24.758246,59.444431],"modifier":"right","type":"turn"},"mode":"walking","name":"","speedLimitSign":"vienna","speedLimitUnit":"km/h","voiceInstructions":[{"announcement":"In 200 feet, Bear left onto Kultuurikilomeeter.","distanceAlongGeometry":60.0,"ssmlAnnouncement":"<speak>In 200 feet, Bear left onto Kultuurikilomeeter.</speak>"}],"weight":64.447},{"bannerInstructions":[{"distanceAlongGeometry":1254.0,"primary":{"components":[{"text":"Turn right onto the walkway.","type":"text"}],"modifier":"right","text":"Turn right onto the walkway.","type":"turn"}}],"distance":1254.0,"driving_side":"right","duration":966.424,"geometry":"ysfkpBgwafn@pF~dEPfMfAft@H~FNrEdAt}@f@pY@rA?`@@rBJhRCdAIbD]nFa@bDaAbIiAdImB~MKt@wGrd@qBnOoDbUwAxJVfH\\jMHpEGzAiAjDqMbf@gBnFkC~HeDbKs@vBkCtF}CpGuIzNU`@oGzH{FhGqi@hc@ud@t_@wIpI{JfNqLfTwJjVgDdJ_HvYaEpUgHxa@aFhd@mErt@q@~FmFrd@oJdw@","intersections":[{"admin_index":0,"bearings":[117,266,329],"duration":127.059,"entry":[false,true,true],"geometry_index":43,"in":0,"location":[24.757636,59.445069],"out":1,"turn_weight":5.0,"weight":132.059},{"admin_index":0,"bearings":[86,175,266,355],"duration":13.205,"entry":[false,true,true,true],"geometry_index":44,"in":0,"location":[24.754468,59.444948],"out":2,"turn_duration":1.0,"turn_weight":1.0,"weight":15.035},{"admin_index":0,"bearings":[86,265],"duration":39.529,"entry":[false,true],"geometry_index":45,"in":0,"location":[24.75424,59.444939],"out":1,"weight":39.529},{"admin_index":0,"bearings":[86,262],"duration":4.235,"entry":[false,true],"geometry_index":47,"in":0,"location":[24.75326,59.444898],"out":1,"weight":4.235},{"admin_index":0,"bearings":[82,176,266],"duration":62.104,"entry":[false,true,true],"geometry_index":48,"in":0,"location":[24.753154,59.44489],"out":2,"weight":62.104},{"admin_index":0,"bearings":[86,176,268,358],"duration":3.824,"entry":[false,true,true,true],"geometry_index":51,"in":0,"location":[24.751684,59.444834],"out":2,"turn_duration":1.0,"turn_weight":6.0,"weight":8.824},{"admin_index":0,"bearings":[88,176,268,358],"duration":37.339,"entry":[false,true,true,true],"geometry_index":53,"in":0,"location":[24.751609,59.444833],"out":2,"turn_duration":1.0,"turn_weight":1.0,"weight":44.607},{"admin_index":0,"bearings":[109,134,292],"duration":15.529,"entry":[false,true,true],"geometry_index":58,"in":0,"location":[24.750981,59.444866],"out":2,"weight":15.529},{"admin_index":0,"bearings":[114,156,294],"duration":10.588,"entry":[false,true,true],"geometry_index":60,"in":0,"location":[24.750656,59.444936],"out":2,"weight":10.588},{"admin_index":0,"bearings":[114,169,294,359],"duration":39.824,"entry":[false,true,true,true],"geometry_index":61,"in":0,"location":[24.750416,59.444991],"out":2,"turn_duration":1.0,"turn_weight":1.0,"weight":39.824},{"admin_index":0,"bearings":[34,113,296],"duration":15.529,"entry":[true,false,true],"geometry_index":64,"in":1,"location":[24.749523,59.445194],"out":2,"weight":15.529},{"admin_index":0,"bearings":[116,295],"duration":14.118,"entry":[false,true],"geometry_index":65,"in":0,"location":[24.749169,59.445282],"out":1,"weight":14.118},{"admin_index":0,"bearings":[81,263,345],"duration":13.122,"entry":[false,true,true],"geometry_index":67,"in":0,"location":[24.748832,59.445314],"out":1,"weight":15.747},{"admin_index":0,"bearings":[83,183,268,351],"duration":8.353,"entry":[false,true,true,true],"geometry_index":68,"in":0,"location":[24.748602,59.445299],"out":2,"turn_duration":2.0,"turn_weight":7.0,"weight":13.353},{"admin_index":0,"bearings":[90,194,310],"duration":53.125,"entry":[false,true,true],"geometry_index":70,"in":0,"location":[24.748451,59.445298],"out":2,"weight":53.125},{"admin_index":0,"bearings":[131,222,310],"duration":21.699,"entry":[false,true,true],"geometry_index":74,"in":0,"location":[24.747459,59.44569],"out":2,"weight":21.699},{"admin_index":0,"bearings":[45,138,319],"duration":33.798,"entry":[true,false,true],"geometry_index":77,"in":1,"location":[24.747082,59.445869],"out":2,"weight":38.867},{"admin_index":0,"bearings":[56,143,237,328],"duration":100.953,"entry":[true,false,true,true],"geometry_index":79,"in":1,"location":[24.746691,59.446119],"out":3,"turn_duration":1.0,"turn_weight":1.0,"weight":105.951},{"admin_index":0,"bearings":[65,157,249,336],"duration":68.059,"entry":[true,false,true,true],"geometry_index":83,"in":1,"location":[24.745802,59.447073],"out":3,"turn_duration":1.0,"turn_weight":1.0,"weight":68.059},{"admin_index":0,"bearings":[61,153,244,327],"duration":80.059,"entry":[true,false,true,true],"geometry_index":85,"in":1,"location":[24.74511,59.447848],"out":3,"turn_duration":1.0,"turn_weight":1.0,"weight":84.012},{"admin_index":0,"bearings":[38,133,304],"duration":32.139,"entry":[true,false,true],"geometry_index":89,"in":1,"location":[24.743973,59.448527],"out":2,"weight":40.174},{"admin_index":0,"bearings":[124,215,298],"duration":102.353,"entry":[false,true,true],"geometry_index":90,"in":0,"location":[24.743545,59.448671],"out":2,"weight":102.353},{"admin_index":0,"bearings":[103,121,291],"duration":5.647,"entry":[false,true,true],"geometry_index":94,"in":0,"location":[24.741172,59.449132],"out":2,"weight":5.647},{"admin_index":0,"bearings":[20,111,291],"entry":[true,false,true],"geometry_index":95,"in":1,"location":[24.741044,59.449157],"out":2}],"maneuver":{"bearing_after":266,"bearing_before":297,"instruction":"Bear left onto Kultuurikilomeeter.","location":[24.757636,59.445069],"modifier":"slight left","type":"turn"},"mode":"walking","name":"Kultuurikilomeeter","speedLimitSign":"vienna","speedLimitUnit":"km/h","voiceInstructions":[{"announcement":"In 200 feet, Turn right onto the walkway.","distanceAlongGeometry":60.0,"ssmlAnnouncement":"<speak>In 200 feet, Turn right onto the walkway.</speak>"}],"weight":1015.202},{"bannerInstructions":[{"distanceAlongGeometry":23.0,"primary":{"components":[{"text":"Turn left onto the walkway.","type":"text"}],"modifier":"left","text":"Turn left onto the walkway.","type":"turn"}}],"distance":23.0,"driving_side":"right","duration":18.235,"geometry":"gfokpBml~dn@kFmDsCyA","intersections":[{"admin_index":0,"bearings":[21,112,291],"duration":9.882,"entry":[true,false,true],"geometry_index":97,"in":1,"location":[24.739543,59.44946],"out":0,"turn_weight":5.0,"weight":14.882},{"admin_index":0,"bearings":[17,115,201,291],"entry":[true,true,false,true],"geometry_index":98,"in":2,"location":[24.73963,59.449578],"out":0,"turn_duration":2.0,"turn_weight":2.0}],"maneuver":{"bearing_after":21,"bearing_before":292,"instruction":"Turn right onto the walkway.","location":[24.739543,59.44946],"modifier":"right","type":"turn"},"mode":"walking","name":"","speedLimitSign":"vienna","speedLimitUnit":"km/h","voiceInstructions":[{"announcement":"In 37 feet, Turn left onto the walkway.","distanceAlongGeometry":11.5,"ssmlAnnouncement":"<speak>In 37 feet, Turn left onto the walkway.</speak>"}],"weight":23.235},{"bannerInstructions":[{"distanceAlongGeometry":16.0,"primary":{"components":[{"text":"Turn left onto the crosswalk.","type":"text"}],"modifier":"left","text":"Turn left onto the crosswalk.","type":"turn"}}],"distance":16.0,"driving_side":"right","duration":11.294,"geometry":"grokpBut~dn@yArJgAdA","intersections":[{"admin_index":0,"bearings":[111,197,304],"entry":[true,false,true],"geometry_index":99,"in":1,"location":[24.739675,59.449652],"out":2}],"maneuver":{"bearing_after":304,"bearing_before":17,"instruction":"Turn left onto the walkway.","location":[24.739675,59.449652],"modifier":"left","type":"turn"},"mode":"walking","name":"","speedLimitSign":"vienna","speedLimitUnit":"km/h","voiceInstructions":[{"announcement":"In 26 feet, Turn left onto the crosswalk.","distanceAlongGeometry":8.0,"ssmlAnnouncement":"<speak>In 26 feet, Turn left onto the crosswalk.</speak>"}],"weight":11.294},{"bannerInstructions":[{"distanceAlongGeometry":347.0,"primary":{"components":[{"text":"Turn right onto the walkway.","type":"text"}],"modifier":"right","text":"Turn right onto the walkway.","type":"turn"}}],"distance":347.0,"driving_side":"right","duration":263.849,"geometry":"iwokpB{f~dn@JhDm@`G_@fCMrAmAfFiKf|@{Fxh@oCdSi@dGaBrQcBbNwCd\\kGlh@uA~PuEzr@_@bHa@dC}@\\KbEOvC","intersections":[{"admin_index":0,"bearings":[8,127,262],"duration":3.529,"entry":[true,false,true],"geometry_index":101,"in":1,"location":[24.739454,59.449733],"out":2,"weight":3.529},{"admin_index":0,"bearings":[82,156,289,358],"duration":6.647,"entry":[false,true,true,true],"geometry_index":102,"in":0,"location":[24.739369,59.449727],"out":2,"turn_duration":1.0,"turn_weight":1.0,"weight":6.647},{"admin_index":0,"bearings":[44,109,255,295],"duration":3.823,"entry":[true,false,true,true],"geometry_index":103,"in":1,"location":[24.73924,59.44975],"out":3,"turn_duration":1.0,"turn_weight":1.0,"weight":3.823},{"admin_index":0,"bearings":[15,115,297],"duration":82.306,"entry":[true,false,true],"geometry_index":104,"in":1,"location":[24.739172,59.449766],"out":2,"weight":82.306},{"admin_index":0,"bearings":[110,201,294],"duration":15.529,"entry":[false,true,true],"geometry_index":108,"in":0,"location":[24.737365,59.450135],"out":2,"weight":15.529},{"admin_index":0,"bearings":[20,114,207,288],"duration":6.647,"entry":[true,false,true,true],"geometry_index":109,"in":1,"location":[24.737042,59.450207],"out":3,"turn_duration":1.0,"turn_weight":1.0,"weight":6.647},{"admin_index":0,"bearings":[32,108,288],"duration":42.353,"entry":[true,false,true],"geometry_index":110,"in":1,"location":[24.736911,59.450228],"out":2,"weight":42.353},{"admin_index":0,"bearings":[34,108,292],"duration":82.306,"entry":[true,false,true],"geometry_index":113,"in":1,"location":[24.735904,59.450403],"out":2,"weight":82.306},{"admin_index":0,"bearings":[104,191,295],"duration":12.649,"entry":[false,true,true],"geometry_index":116,"in":0,"location":[24.734123,59.450687],"out":2,"weight":13.282},{"admin_index":0,"bearings":[13,120,277],"duration":4.235,"entry":[true,false,true],"geometry_index":119,"in":1,"location":[24.733895,59.450751],"out":2,"weight":4.235},{"admin_index":0,"bearings":[14,97,193,282],"entry":[true,false,true,true],"geometry_index":120,"in":1,"location":[24.733797,59.450757],"out":3,"turn_duration":1.0,"turn_weight":1.0}],"maneuver":{"bearing_after":262,"bearing_before":307,"instruction":"Turn left onto the crosswalk.","location":[24.739454,59.449733],"modifier":"left","type":"turn"},"mode":"walking","name":"","speedLimitSign":"vienna","speedLimitUnit":"km/h","voiceInstructions":[{"announcement":"In 200 feet, Turn right onto the walkway.","distanceAlongGeometry":60.0,"ssmlAnnouncement":"<speak>In 200 feet, Turn right onto the walkway.</speak>"}],"weight":264.482},{"bannerInstructions":[{"distanceAlongGeometry":2.0,"primary":{"components":[{"text":"Turn left onto the walkway.","type":"text"}],"modifier":"left","text":"Turn left onto the walkway.","type":"turn"}}],"distance":2.0,"driving_side":"right","duration":1.412,"geometry":"ywqkpBq`sdn@k@F","intersections":[{"admin_index":0,"bearings":[102,269,355],"entry":[false,true,true],"geometry_index":121,"in":0,"location":[24.733721,59.450765],"out":2}],"maneuver":{"bearing_after":355,"bearing_before":282,"instruction":"Turn right onto the walkway.","location":[24.733721,59.450765],"modifier":"right","type":"turn"},"mode":"walking","name":"","speedLimitSign":"vienna","speedLimitUnit":"km/h","voiceInstructions":[{"announcement":"In 3 feet, Turn left onto the walkway.","distanceAlongGeometry":1.0,"ssmlAnnouncement":"<speak>In 3 feet, Turn left onto the walkway.</speak>"}],"weight":1.412},{"bannerInstructions":[{"distanceAlongGeometry":241.0,"primary":{"components":[{"text":"Allveelaeva","type":"text"}],"modifier":"slight left","text":"Allveelaeva","type":"turn"}}],"distance":241.0,"driving_side":"right","duration":184.456,"geometry":"eyqkpBi`sdn@oQbw@uNno@Gv@SxCo@hEiA`@i@nBf@pCQtDk@xC{B|KgTraAuA\\i@o@","intersections":[{"admin_index":0,"bearings":[14,175,303],"duration":45.642,"entry":[true,false,true],"geometry_index":122,"in":1,"location":[24.733717,59.450787],"out":2,"weight":45.642},{"admin_index":0,"bearings":[100,123,302],"duration":41.929,"entry":[true,false,true],"geometry_index":123,"in":1,"location":[24.732819,59.451083],"out":2,"weight":41.929},{"admin_index":0,"bearings":[119,213,284],"duration":2.823,"entry":[false,true,true],"geometry_index":125,"in":0,"location":[24.732015,59.451338],"out":2,"weight":2.823},{"admin_index":0,"bearings":[31,104,214,303],"duration":19.635,"entry":[true,false,true,true],"geometry_index":126,"in":1,"location":[24.731938,59.451348],"out":3,"turn_duration":1.0,"turn_weight":1.0,"weight":19.635},{"admin_index":0,"bearings":[25,89,193,299],"duration":4.529,"entry":[true,false,true,true],"geometry_index":131,"in":1,"location":[24.7316,59.451419],"out":3,"turn_duration":1.0,"turn_weight":1.0,"weight":4.529},{"admin_index":0,"bearings":[119,194,301],"duration":14.132,"entry":[false,true,true],"geometry_index":132,"in":0,"location":[24.731523,59.451441],"out":2,"weight":16.958},{"admin_index":0,"bearings":[38,121,302],"entry":[true,false,true],"geometry_index":133,"in":1,"location":[24.731316,59.451503],"out":2}],"maneuver":{"bearing_after":303,"bearing_before":355,"instruction":"Turn left onto the walkway.","location":[24.733717,59.450787],"modifier":"left","type":"turn"},"mode":"walking","name":"","speedLimitSign":"vienna","speedLimitUnit":"km/h","voiceInstructions":[{"announcement":"In 200 feet, Bear left onto Allveelaeva.","distanceAlongGeometry":60.0,"ssmlAnnouncement":"<speak>In 200 feet, Bear left onto Allveelaeva.</speak>"}],"weight":187.283},{"bannerInstructions":[{"distanceAlongGeometry":28.0,"primary":{"components":[{"text":"Peetri","type":"text"}],"modifier":"right","text":"Peetri","type":"turn"}}],"distance":28.0,"driving_side":"right","duration":20.951,"geometry":"e_tkpBehldn@mFzY","intersections":[{"admin_index":0,"bearings":[32,120,152,299],"entry":[true,true,false,true],"geometry_index":136,"in":2,"location":[24.730259,59.451907],"out":3,"turn_weight":5.0}],"maneuver":{"bearing_after":299,"bearing_before":332,"instruction":"Bear left onto Allveelaeva.","location":[24.730259,59.451907],"modifier":"slight left","type":"turn"},"mode":"walking","name":"Allveelaeva","speedLimitSign":"vienna","speedLimitUnit":"km/h","voiceInstructions":[{"announcement":"In 45 feet, Turn right onto Peetri.","distanceAlongGeometry":14.0,"ssmlAnnouncement":"<speak>In 45 feet, Turn right onto Peetri.</speak>"}],"weight":25.951},{"bannerInstructions":[{"distanceAlongGeometry":25.099,"primary":{"components":[{"text":"You have arrived at your destination.","type":"text"}],"text":"You have arrived at your destination.","type":"arrive"}}],"distance":25.099,"driving_side":"right","duration":24.804,"geometry":"sftkpBimkdn@}GiGqBoC","intersections":[{"admin_index":0,"bearings":[1,25,119,208],"entry":[true,true,false,true],"geometry_index":137,"in":2,"location":[24.729829,59.452026],"out":1,"turn_weight":5.0}],"maneuver":{"bearing_after":25,"bearing_before":299,"instruction":"Turn right onto Peetri.","location":[24.729829,59.452026],"modifier":"right","type":"turn"},"mode":"walking","name":"Peetri","speedLimitSign":"vienna","speedLimitUnit":"km/h","voiceInstructions":[{"announcement":"In 41 feet, You have arrived at your destination.","distanceAlongGeometry":12.5495,"ssmlAnnouncement":"<speak>In 41 feet, You have arrived at your destination.</speak>"}],"weight":54.607},{"bannerInstructions":[{"distanceAlongGeometry":0.0,"primary":{"components":[{"text":"You have arrived at your destination.","type":"text"}],"text":"You have arrived at your destination.","type":"arrive"}}],"distance":0.0,"driving_side":"right","duration":0.0,"geometry":"cstkpBczkdn@??","intersections":[{"admin_index":0,"bearings":[213],"entry":[true],"geometry_index":139,"in":0,"location":[24.730034,59.452226]}],"maneuver":{"bearing_after":0,"bearing_before":33,"instruction":"You have arrived at your destination.","location":[24.730034,59.452226],"type":"arrive"},"mode":"walking","name":"Peetri","speedLimitSign":"vienna","speedLimitUnit":"km/h","voiceInstructions":[],"weight":0.0}],"summary":"Logi, Kultuurikilomeeter","via_waypoints":[],"weight":2132.626}],"weight":2132.626,"weight_name":"pedestrian"}],"waypoints":[{"distance":0.546,"location":[24.765368,59.442643],"name":""},{"distance":0.134,"location":[24.730034,59.452226],"name":"Peetri"}]}"#;
+    pub(super) const VALHALLA_OSRM_RESPONSE_VIA_WAYS: &str = r#"{"code":"Ok","routes":[{"distance":2089.442,"duration":301.262,"geometry":"oop|u@ntan{Cn@gArJ{HvKuCpVsHxC_AhEiBpCkA|AN|Bn@fC}@dI_IjGmHfH_EpBwBxI{PfIsOzGgMpGyQnDmIvDmHf@gCT{AdByTtByKVoCJwBBqA`@oPe@uQe@eNMaTK{Lu@aO}EoWsDgL{KaTiDwHoC{K_AkAu@_BcC{DsHgKuDoGcE_KwB}JsAqPIeJ`@}IvAeKd@mEd@wCDmBGkLAoGv@qHKaGYqFw@kI{@}NToI~@yIjBaJtAkF`D{JxGkQ~J}O`KuJtSmOjKgLtJ}IhB}EvBcIfAiJ`CgJb@gHp@iLnAmQzBcNnCkLhGaUpFmMzHiMdKqMhIoLjJcQjEgKzAgFtAqGvAgNjC}NbAkGTeMp@aG`CiKzCwJdI}QvKcOfG{MtEmPdBsP","legs":[{"admins":[{"iso_3166_1":"US","iso_3166_1_alpha3":"USA"}],"annotation":{"distance":[4.4,25.8,23.9,44.6,9.1,12.4,8.9,5.3,7.4,8.2,23.9,21.0,19.0,8.6,33.9,31.7,27.3,33.1,19.0,17.9,7.0,4.7,34.5,21.0,7.2,5.9,4.0,27.4,29.2,23.8,32.9,21.7,25.3,40.2,23.0,40.1,17.9,21.6,5.1,5.6,11.8,25.7,16.7,21.7,19.8,27.8,17.5,17.2,19.6,10.3,7.7,5.4,20.9,13.3,15.2,12.6,11.9,16.5,25.1,16.4,17.2,18.3,12.5,20.6,32.7,34.0,28.2,44.9,30.2,26.9,12.3,17.2,18.1,19.0,14.6,21.0,29.1,24.6,22.4,37.5,26.3,28.4,31.4,28.0,34.8,22.2,12.4,14.2,24.3,26.1,13.6,22.2,12.9,20.5,20.3,34.7,33.9,27.5,29.7,28.1],"duration":[0.635,3.717,3.436,6.419,1.314,1.782,1.286,0.762,1.065,1.174,3.447,3.019,2.729,1.243,4.882,4.569,3.929,4.765,2.738,2.584,1.008,0.67,4.971,3.031,1.03,0.848,0.577,3.943,4.211,3.427,4.736,3.12,3.636,5.787,3.309,5.772,2.581,3.116,0.74,0.801,1.692,3.698,2.404,3.121,2.85,4.005,2.516,2.473,2.829,1.479,1.11,0.774,3.007,1.911,2.196,1.815,1.713,2.375,3.614,2.367,2.484,2.633,1.795,2.968,4.708,4.896,4.059,6.466,4.352,3.877,1.776,2.471,2.607,2.735,2.099,3.019,4.194,3.542,3.22,5.398,3.781,4.094,4.526,4.025,5.011,3.202,1.789,2.044,3.5,3.754,1.96,3.194,1.856,2.957,2.922,4.995,4.882,3.957,4.278,4.048],"maxspeed":[{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true},{"unknown":true}],"speed":[6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9,6.9]},"distance":2089.442,"duration":301.262,"steps":[{"bannerInstructions":[{"distanceAlongGeometry":2089.442,"primary":{"components":[{"text":"You have arrived at your destination.","type":"text"}],"text":"You have arrived at your destination.","type":"arrive"}}],"distance":2089.442,"driving_side":"right","duration":301.262,"geometry":"oop|u@ntan{Cn@gArJ{HvKuCpVsHxC_AhEiBpCkA|AN|Bn@fC}@dI_IjGmHfH_EpBwBxI{PfIsOzGgMpGyQnDmIvDmHf@gCT{AdByTtByKVoCJwBBqA`@oPe@uQe@eNMaTK{Lu@aO}EoWsDgL{KaTiDwHoC{K_AkAu@_BcC{DsHgKuDoGcE_KwB}JsAqPIeJ`@}IvAeKd@mEd@wCDmBGkLAoGv@qHKaGYqFw@kI{@}NToI~@yIjBaJtAkF`D{JxGkQ~J}O`KuJtSmOjKgLtJ}IhB}EvBcIfAiJ`CgJb@gHp@iLnAmQzBcNnCkLhGaUpFmMzHiMdKqMhIoLjJcQjEgKzAgFtAqGvAgNjC}NbAkGTeMp@aG`CiKzCwJdI}QvKcOfG{MtEmPdBsP","intersections":[{"admin_index":0,"bearings":[134],"duration":4.352,"entry":[true],"geometry_index":0,"location":[-82.036056,28.795656],"out":0,"weight":8.922},{"admin_index":0,"bearings":[160,162,323],"duration":9.823,"entry":[false,true,false],"geometry_index":2,"in":2,"location":[-82.035862,28.795446],"out":1,"weight":20.138},{"admin_index":0,"bearings":[160,340],"duration":3.024,"entry":[true,false],"geometry_index":4,"in":1,"location":[-82.035633,28.794865],"out":0,"turn_weight":1.5,"weight":7.699},{"admin_index":0,"bearings":[155,335],"duration":4.32,"entry":[true,false],"geometry_index":6,"in":1,"location":[-82.035548,28.794687],"out":0,"turn_weight":1.5,"weight":10.356},{"admin_index":0,"bearings":[59,139,239,338],"duration":10.445,"entry":[true,true,true,false],"geometry_index":10,"in":3,"location":[-82.035511,28.794436],"out":1,"turn_duration":0.077,"turn_weight":6.0,"weight":27.254},{"admin_index":0,"bearings":[52,125,239,317],"duration":24.525,"entry":[true,true,true,false],"geometry_index":14,"in":3,"location":[-82.035044,28.793934],"out":1,"turn_duration":0.045,"turn_weight":6.0,"weight":56.184},{"admin_index":0,"bearings":[18,103,199,290],"duration":10.542,"entry":[true,true,true,false],"geometry_index":21,"in":3,"location":[-82.033577,28.793118],"out":1,"turn_duration":0.03,"turn_weight":6.0,"weight":27.55},{"admin_index":0,"bearings":[7,94,184,277],"duration":45.814,"entry":[true,true,true,false],"geometry_index":26,"in":3,"location":[-82.032845,28.792979],"out":1,"turn_duration":0.022,"turn_weight":6.0,"weight":99.874},{"admin_index":0,"bearings":[51,148,234,324],"duration":28.246,"entry":[true,true,false,true],"geometry_index":40,"in":2,"location":[-82.029777,28.793661],"out":0,"turn_duration":0.022,"turn_weight":6.0,"weight":63.859},{"admin_index":0,"bearings":[8,92,186,286],"duration":9.7,"entry":[true,true,true,false],"geometry_index":51,"in":3,"location":[-82.027959,28.794078],"out":1,"turn_duration":0.052,"turn_weight":6.6,"weight":26.378},{"admin_index":0,"bearings":[83,182,267,356],"duration":17.016,"entry":[true,true,false,true],"geometry_index":56,"in":2,"location":[-82.027272,28.794058],"out":0,"turn_duration":0.024,"turn_weight":6.6,"weight":41.434},{"admin_index":0,"bearings":[116,293],"duration":33.12,"entry":[true,false],"geometry_index":63,"in":1,"location":[-82.026094,28.793989],"out":0,"turn_weight":1.65,"weight":69.546},{"admin_index":0,"bearings":[30,113,208,299],"duration":9.962,"entry":[true,true,true,false],"geometry_index":71,"in":3,"location":[-82.024391,28.792613],"out":1,"turn_duration":0.026,"turn_weight":6.6,"weight":26.969},{"admin_index":0,"bearings":[13,98,191,278],"duration":10.808,"entry":[true,true,true,false],"geometry_index":75,"in":3,"location":[-82.02372,28.792434],"out":1,"turn_duration":0.008,"turn_weight":6.6,"weight":28.74},{"admin_index":0,"bearings":[18,111,199,286],"duration":35.002,"entry":[true,true,true,false],"geometry_index":78,"in":3,"location":[-82.02297,28.792307],"out":1,"turn_duration":0.01,"turn_weight":6.6,"weight":78.334},{"admin_index":0,"bearings":[25,110,191,294],"duration":9.384,"entry":[true,true,true,false],"geometry_index":87,"in":3,"location":[-82.020892,28.791133],"out":1,"turn_duration":0.024,"turn_weight":8.8,"weight":27.988},{"admin_index":0,"bearings":[106,204,287],"duration":5.203,"entry":[true,false,false],"geometry_index":90,"in":2,"location":[-82.020256,28.790976],"out":0,"turn_duration":0.019,"turn_weight":1.65,"weight":12.277},{"admin_index":0,"bearings":[7,102,184,273],"duration":7.788,"entry":[true,true,true,false],"geometry_index":92,"in":3,"location":[-82.019895,28.790931],"out":1,"turn_duration":0.012,"turn_weight":6.6,"weight":22.541},{"admin_index":0,"bearings":[122,213,295],"entry":[true,false,false],"geometry_index":95,"in":2,"location":[-82.019381,28.790763],"out":0,"turn_duration":0.01,"turn_weight":1.65}],"maneuver":{"bearing_after":134,"bearing_before":0,"instruction":"Drive southeast.","location":[-82.036056,28.795656],"type":"depart"},"mode":"driving","name":"","speedLimitSign":"mutcd","speedLimitUnit":"mph","voiceInstructions":[{"announcement":"Drive southeast.","distanceAlongGeometry":2089.442,"ssmlAnnouncement":"<speak>Drive southeast.</speak>"},{"announcement":"In 200 feet, You have arrived at your destination.","distanceAlongGeometry":60.0,"ssmlAnnouncement":"<speak>In 200 feet, You have arrived at your destination.</speak>"}],"weight":703.153},{"bannerInstructions":[{"distanceAlongGeometry":0.0,"primary":{"components":[{"text":"You have arrived at your destination.","type":"text"}],"text":"You have arrived at your destination.","type":"arrive"}}],"distance":0.0,"driving_side":"right","duration":0.0,"geometry":"ste|u@hm~l{C??","intersections":[{"admin_index":0,"bearings":[282],"entry":[true],"geometry_index":100,"in":0,"location":[-82.018021,28.790106]}],"maneuver":{"bearing_after":0,"bearing_before":102,"instruction":"You have arrived at your destination.","location":[-82.018021,28.790106],"type":"arrive"},"mode":"driving","name":"","speedLimitSign":"mutcd","speedLimitUnit":"mph","voiceInstructions":[],"weight":0.0}],"summary":"","via_waypoints":[{"distance_from_start":30.223,"geometry_index":2,"waypoint_index":1}],"weight":703.153}],"weight":703.153,"weight_name":"golf_cart"}],"waypoints":[{"distance":0.095,"location":[-82.036056,28.795656],"name":""},{"distance":0.0,"location":[-82.035862,28.795446],"name":""},{"distance":4.898,"location":[-82.018021,28.790106],"name":""}]}"#;
+
+    /// A clock for deterministic [`Route::planned_at`] timestamps.
+    pub(super) fn test_clock() -> Arc<dyn Clock> {
+        Arc::new(MockClock::new(
+            SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000),
+        ))
+    }
 
     #[test]
     fn parse_standard_osrm() {
-        let parser = OsrmResponseParser::new(6);
+        let parser = OsrmResponseParser::new(6).with_clock(test_clock());
         let routes = parser
             .parse_response(STANDARD_OSRM_POLYLINE6_RESPONSE.into())
             .expect("Unable to parse OSRM response");
@@ -176,7 +842,7 @@ mod tests {
 
     #[test]
     fn parse_valhalla_osrm() {
-        let parser = OsrmResponseParser::new(6);
+        let parser = OsrmResponseParser::new(6).with_clock(test_clock());
         let routes = parser
             .parse_response(VALHALLA_OSRM_RESPONSE.into())
             .expect("Unable to parse Valhalla OSRM response");
@@ -185,10 +851,800 @@ mod tests {
 
     #[test]
     fn parse_valhalla_osrm_with_via_ways() {
-        let parser = OsrmResponseParser::new(6);
+        let parser = OsrmResponseParser::new(6).with_clock(test_clock());
         let routes = parser
             .parse_response(VALHALLA_OSRM_RESPONSE_VIA_WAYS.into())
             .expect("Unable to parse Valhalla OSRM response");
         insta::assert_yaml_snapshot!(routes);
     }
+
+    #[test]
+    fn parse_lazy_steps() {
+        for response in [
+            STANDARD_OSRM_POLYLINE6_RESPONSE,
+            VALHALLA_OSRM_RESPONSE,
+            VALHALLA_OSRM_RESPONSE_VIA_WAYS,
+        ] {
+            let eager = OsrmResponseParser::new(6)
+                .with_clock(test_clock())
+                .parse_response(response.into())
+                .expect("Unable to parse OSRM response");
+            let lazy = OsrmResponseParser::with_lazy_step_parsing(6)
+                .with_clock(test_clock())
+                .parse_response(response.into())
+                .expect("Unable to parse OSRM response");
+
+            // Utterance IDs are random, so compare the serialized forms (which omit them)
+            assert_eq!(
+                serde_json::to_value(&lazy).expect("Unable to serialize routes"),
+                serde_json::to_value(&eager).expect("Unable to serialize routes")
+            );
+        }
+    }
+
+    #[test]
+    fn parse_lazy_step_errors() {
+        let parser = OsrmResponseParser::with_lazy_step_parsing(6);
+        assert!(matches!(
+            parser.parse_response(
+                r#"{"code":"Ok","routes":[{"distance":0,"duration":0,"geometry":"??","legs":[{"distance":0,"duration":0,"steps":[{"distance":0}]}]}],"waypoints":[]}"#.into()
+            ),
+            Err(RoutingResponseParseError::InvalidJson { .. })
+        ));
+        assert!(matches!(
+            parser.parse_response(r#"{"code":"NoRoute"}"#.into()),
+            Err(RoutingResponseParseError::BackendError {
+                error: RoutingBackendError::NoRoute,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn parse_tunnels() {
+        // Three segments of ~111m each heading east; the middle one is a tunnel
+        let step = r#"{
+            "distance": 333.6,
+            "duration": 30,
+            "geometry": "???o}@?o}@?o}@",
+            "maneuver": {"location": [0, 0], "bearing_before": 0, "bearing_after": 90, "type": "depart"},
+            "intersections": [
+                {"location": [0, 0], "bearings": [90], "entry": [true]},
+                {"location": [0.001, 0], "bearings": [90, 270], "entry": [true, false], "classes": ["tunnel"], "tunnel_name": "Test Tunnel"},
+                {"location": [0.002, 0], "bearings": [90, 270], "entry": [true, false]}
+            ]
+        }"#;
+        let step: OsrmRouteStep = serde_json::from_str(step).expect("Unable to parse step");
+        let step = RouteStep::from_osrm(&step, 6, "en", ResponseIndex::route(0).leg(0).step(0))
+            .expect("Unable to convert step");
+
+        assert_eq!(step.geometry.len(), 4);
+        let [tunnel] = step.tunnels.as_slice() else {
+            panic!("Expected exactly one tunnel; found {:?}", step.tunnels);
+        };
+        assert_eq!(tunnel.name.as_deref(), Some("Test Tunnel"));
+        assert!((tunnel.entrance_distance_before_maneuver - 222.4).abs() < 0.1);
+        assert!((tunnel.exit_distance_before_maneuver - 111.2).abs() < 0.1);
+    }
+
+    #[test]
+    fn parse_country_changes() {
+        // Three segments of ~111m each heading east; the last one is across the border
+        let leg = r#"{
+            "distance": 333.6,
+            "duration": 30,
+            "admins": [
+                {"iso_3166_1": "DE", "iso_3166_1_alpha3": "DEU"},
+                {"iso_3166_1": "AT", "iso_3166_1_alpha3": "AUT"}
+            ],
+            "steps": [{
+                "distance": 333.6,
+                "duration": 30,
+                "geometry": "???o}@?o}@?o}@",
+                "maneuver": {"location": [0, 0], "bearing_before": 0, "bearing_after": 90, "type": "depart"},
+                "intersections": [
+                    {"location": [0, 0], "bearings": [90], "entry": [true], "admin_index": 0},
+                    {"location": [0.001, 0], "bearings": [90, 270], "entry": [true, false], "admin_index": 0},
+                    {"location": [0.002, 0], "bearings": [90, 270], "entry": [true, false], "admin_index": 1}
+                ]
+            }]
+        }"#;
+        let leg: RouteLeg = serde_json::from_str(leg).expect("Unable to parse leg");
+        let step = RouteStep::from_osrm(
+            &leg.steps[0],
+            6,
+            "en",
+            ResponseIndex::route(0).leg(0).step(0),
+        )
+        .expect("Unable to convert step");
+
+        // The country at the start of the route is not a change
+        let mut steps = vec![step.clone()];
+        let mut country = None;
+        apply_country_changes(&mut steps, &leg, &mut country).expect("Unable to apply admins");
+        let [change] = steps[0].country_changes.as_slice() else {
+            panic!(
+                "Expected exactly one country change; found {:?}",
+                steps[0].country_changes
+            );
+        };
+        assert_eq!(change.iso_3166, "AT");
+        assert!((change.distance_before_maneuver - 111.2).abs() < 0.1);
+        assert_eq!(country.as_deref(), Some("AT"));
+
+        // A leg which starts in a different country than the previous one ended in
+        let mut steps = vec![step];
+        apply_country_changes(&mut steps, &leg, &mut country).expect("Unable to apply admins");
+        let iso_codes: Vec<_> = steps[0]
+            .country_changes
+            .iter()
+            .map(|change| change.iso_3166.as_str())
+            .collect();
+        assert_eq!(iso_codes, ["DE", "AT"]);
+        assert!((steps[0].country_changes[0].distance_before_maneuver - 333.6).abs() < 0.1);
+    }
+
+    #[test]
+    fn parse_speed_limit_signs() {
+        let step = |sign: &str, unit: &str| {
+            let step = format!(
+                r#"{{
+                    "distance": 111.2,
+                    "duration": 10,
+                    "geometry": "???o}}@",
+                    "maneuver": {{"location": [0, 0], "bearing_before": 0, "bearing_after": 90, "type": "depart"}},
+                    "intersections": [],
+                    "speedLimitSign": "{sign}",
+                    "speedLimitUnit": "{unit}"
+                }}"#
+            );
+            let step: OsrmRouteStep = serde_json::from_str(&step).expect("Unable to parse step");
+            RouteStep::from_osrm(&step, 6, "en", ResponseIndex::route(0).leg(0).step(0))
+                .expect("Unable to convert step")
+        };
+
+        let mutcd = step("mutcd", "mph");
+        assert_eq!(mutcd.speed_limit_sign, Some(SpeedLimitSign::Mutcd));
+        assert_eq!(mutcd.speed_limit_unit, Some(SpeedUnit::MilesPerHour));
+        let vienna = step("vienna", "km/h");
+        assert_eq!(vienna.speed_limit_sign, Some(SpeedLimitSign::Vienna));
+        assert_eq!(vienna.speed_limit_unit, Some(SpeedUnit::KilometersPerHour));
+        // Unknown values are ignored
+        let unknown = step("japan", "knots");
+        assert_eq!(unknown.speed_limit_sign, None);
+        assert_eq!(unknown.speed_limit_unit, None);
+    }
+
+    #[test]
+    fn parse_junction_views() {
+        let step = r#"{
+            "distance": 111.2,
+            "duration": 10,
+            "geometry": "???o}@",
+            "maneuver": {"location": [0, 0], "bearing_before": 0, "bearing_after": 90, "type": "fork", "modifier": "slight right"},
+            "intersections": [],
+            "bannerInstructions": [{
+                "distanceAlongGeometry": 100,
+                "primary": {"text": "A 7", "type": "fork", "modifier": "slight right", "components": []},
+                "view": {
+                    "text": "CA01610_1_E",
+                    "components": [
+                        {"text": "CA01610_1_E", "type": "guidance-view", "subType": "jct", "imageURL": "https://example.com/jct/CA01610_1_E"},
+                        {"text": "No image", "type": "guidance-view"}
+                    ]
+                }
+            }]
+        }"#;
+        let step: OsrmRouteStep = serde_json::from_str(step).expect("Unable to parse step");
+        let step = RouteStep::from_osrm(&step, 6, "en", ResponseIndex::route(0).leg(0).step(0))
+            .expect("Unable to convert step");
+
+        assert_eq!(
+            step.junction_views,
+            vec![JunctionView {
+                image_reference: "https://example.com/jct/CA01610_1_E".to_string(),
+                kind: Some("jct".to_string()),
+                trigger_distance_before_maneuver: 100.0,
+            }]
+        );
+    }
+
+    #[test]
+    fn synthesized_instructions() {
+        let step = r#"{
+            "distance": 111.2,
+            "duration": 10,
+            "geometry": "???o}@",
+            "name": "Torstraße",
+            "maneuver": {"location": [0, 0], "bearing_before": 0, "bearing_after": 270, "type": "turn", "modifier": "left"},
+            "intersections": []
+        }"#;
+        let step: OsrmRouteStep = serde_json::from_str(step).expect("Unable to parse step");
+        let index = ResponseIndex::route(0).leg(0).step(0);
+
+        let english =
+            RouteStep::from_osrm(&step, 6, "en-US", index).expect("Unable to convert step");
+        assert_eq!(english.instruction, "Turn left onto Torstraße");
+        let german =
+            RouteStep::from_osrm(&step, 6, "de-DE", index).expect("Unable to convert step");
+        assert_eq!(german.instruction, "Biegen Sie links auf Torstraße ab");
+
+        // Instructions from the backend are used as is
+        let step = r#"{
+            "distance": 111.2,
+            "duration": 10,
+            "geometry": "???o}@",
+            "maneuver": {"location": [0, 0], "bearing_before": 0, "bearing_after": 270, "type": "turn", "modifier": "left", "instruction": "Links abbiegen"},
+            "intersections": []
+        }"#;
+        let step: OsrmRouteStep = serde_json::from_str(step).expect("Unable to parse step");
+        let step = RouteStep::from_osrm(&step, 6, "fr", index).expect("Unable to convert step");
+        assert_eq!(step.instruction, "Links abbiegen");
+    }
+
+    #[test]
+    fn parse_tolls_and_ferries() {
+        let step = r#"{
+            "distance": 333.6,
+            "duration": 30,
+            "geometry": "???o}@?o}@?o}@",
+            "mode": "ferry",
+            "maneuver": {"location": [0, 0], "bearing_before": 0, "bearing_after": 90, "type": "depart"},
+            "intersections": [
+                {"location": [0, 0], "bearings": [90], "entry": [true]},
+                {"location": [0.001, 0], "bearings": [90, 270], "entry": [true, false], "classes": ["toll"]}
+            ]
+        }"#;
+        let step: OsrmRouteStep = serde_json::from_str(step).expect("Unable to parse step");
+        let step = RouteStep::from_osrm(&step, 6, "en", ResponseIndex::route(0).leg(0).step(0))
+            .expect("Unable to convert step");
+
+        assert!(step.has_tolls);
+        assert!(step.is_ferry);
+    }
+
+    #[test]
+    fn parse_pedestrian_features() {
+        let step = r#"{
+            "distance": 333.6,
+            "duration": 240,
+            "geometry": "???o}@?o}@?o}@",
+            "mode": "walking",
+            "name": "Station Underpass",
+            "maneuver": {"location": [0, 0], "bearing_before": 0, "bearing_after": 90, "type": "turn", "modifier": "left"},
+            "intersections": [
+                {"location": [0, 0], "bearings": [90, 180], "entry": [true, false], "classes": ["steps"]},
+                {"location": [0.001, 0], "bearings": [90, 270], "entry": [true, false], "classes": ["crossing"]}
+            ]
+        }"#;
+        let step: OsrmRouteStep = serde_json::from_str(step).expect("Unable to parse step");
+        let step = RouteStep::from_osrm(&step, 6, "en", ResponseIndex::route(0).leg(0).step(0))
+            .expect("Unable to convert step");
+
+        assert!(step.has_stairs);
+        assert!(step.has_crosswalk);
+        assert!(!step.has_elevator);
+        // The stairs are at the maneuver, so they are described instead of the turn
+        assert_eq!(step.instruction, "Take the stairs to Station Underpass");
+    }
+
+    #[test]
+    fn parse_charging_stops() {
+        let response = r#"{
+            "code": "Ok",
+            "routes": [{"geometry": "???o}@?o}@?o}@", "legs": [], "weight_name": "auto", "weight": 40.0, "duration": 40.0, "distance": 333.6}],
+            "waypoints": [
+                {"name": "", "location": [0, 0]},
+                {
+                    "name": "Main Street",
+                    "location": [0.001, 0],
+                    "metadata": {
+                        "type": "charging-station",
+                        "name": "Supercharger",
+                        "station_id": "ocm-42",
+                        "charge_at_arrival": 12300,
+                        "charge_to": 51720,
+                        "charge_time": 1230,
+                        "power_kw": 150,
+                        "plug_type": "ccs_combo_type1"
+                    }
+                },
+                {"name": "", "location": [0.003, 0], "metadata": {"type": "user-provided"}}
+            ]
+        }"#;
+        let routes = OsrmResponseParser::new(6)
+            .parse_response(response.into())
+            .expect("Unable to parse OSRM response");
+
+        assert_eq!(routes[0].waypoints.len(), 3);
+        assert_eq!(
+            routes[0].charging_stops,
+            vec![ChargingStop {
+                coordinate: GeographicCoordinate {
+                    lat: 0.0,
+                    lng: 0.001
+                },
+                name: Some("Supercharger".to_string()),
+                station_id: Some("ocm-42".to_string()),
+                charge_at_arrival: 12.3,
+                charge_to: 51.72,
+                charge_time: 1230.0,
+                power: Some(150.0),
+                connector_type: Some(ConnectorType::Ccs1),
+            }]
+        );
+    }
+
+    #[cfg(feature = "backend-extras")]
+    #[test]
+    fn parse_backend_extras() {
+        let response = r#"{
+            "code": "Ok",
+            "routes": [{
+                "geometry": "???o}@?o}@?o}@",
+                "legs": [{
+                    "distance": 333.6,
+                    "duration": 40.0,
+                    "steps": [{
+                        "distance": 333.6,
+                        "duration": 40.0,
+                        "geometry": "???o}@?o}@?o}@",
+                        "maneuver": {"location": [0, 0], "bearing_before": 0, "bearing_after": 90, "type": "depart"},
+                        "intersections": [{"location": [0, 0], "bearings": [90], "entry": [true]}],
+                        "shade_fraction": 0.75
+                    }]
+                }],
+                "weight_name": "auto",
+                "weight": 40.0,
+                "duration": 40.0,
+                "distance": 333.6,
+                "custom_cost": {"scenic": 12.5}
+            }],
+            "waypoints": [{"name": "", "location": [0, 0]}, {"name": "", "location": [0.003, 0]}]
+        }"#;
+
+        for parser in [
+            OsrmResponseParser::new(6),
+            OsrmResponseParser::with_lazy_step_parsing(6),
+        ] {
+            let routes = parser
+                .parse_response(response.into())
+                .expect("Unable to parse OSRM response");
+
+            assert_eq!(
+                routes[0].extras,
+                HashMap::from([("custom_cost".to_string(), r#"{"scenic": 12.5}"#.to_string())])
+            );
+            assert_eq!(
+                routes[0].steps[0].extras,
+                HashMap::from([("shade_fraction".to_string(), "0.75".to_string())])
+            );
+        }
+    }
+
+    #[test]
+    fn parse_notices() {
+        let response = r#"{
+            "code": "Ok",
+            "routes": [{
+                "geometry": "???o}@?o}@?o}@",
+                "legs": [{
+                    "distance": 333.6,
+                    "duration": 40.0,
+                    "steps": [],
+                    "notifications": [
+                        {"type": "violation", "subtype": "maxHeight", "details": {"message": "The route passes under a low bridge"}},
+                        {"type": "alert", "subtype": "evInsufficientCharge"}
+                    ]
+                }],
+                "weight_name": "auto",
+                "weight": 40.0,
+                "duration": 40.0,
+                "distance": 333.6
+            }],
+            "waypoints": [{"name": "", "location": [0, 0]}, {"name": "", "location": [0.003, 0]}],
+            "warnings": [{"code": 402, "text": "No access restriction data in this region"}]
+        }"#;
+        let routes = OsrmResponseParser::new(6)
+            .parse_response(response.into())
+            .expect("Unable to parse OSRM response");
+
+        assert_eq!(
+            routes[0].notices,
+            vec![
+                RouteNotice {
+                    code: Some(402),
+                    kind: None,
+                    message: "No access restriction data in this region".to_string(),
+                },
+                RouteNotice {
+                    code: None,
+                    kind: Some("maxHeight".to_string()),
+                    message: "The route passes under a low bridge".to_string(),
+                },
+                RouteNotice {
+                    code: None,
+                    kind: Some("evInsufficientCharge".to_string()),
+                    message: "evInsufficientCharge".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_incidents() {
+        let leg = r#"{
+            "distance": 333.6,
+            "duration": 30,
+            "steps": [
+                {
+                    "distance": 333.6,
+                    "duration": 30,
+                    "geometry": "???o}@?o}@?o}@",
+                    "maneuver": {"location": [0, 0], "bearing_before": 0, "bearing_after": 90, "type": "depart"},
+                    "intersections": []
+                },
+                {
+                    "distance": 0,
+                    "duration": 0,
+                    "geometry": "?ozD??",
+                    "maneuver": {"location": [0.003, 0], "bearing_before": 90, "bearing_after": 0, "type": "arrive"},
+                    "intersections": []
+                }
+            ],
+            "incidents": [
+                {
+                    "id": "1",
+                    "type": "construction",
+                    "description": "Lane closed for resurfacing",
+                    "geometry_index_start": 1,
+                    "geometry_index_end": 2
+                },
+                {
+                    "id": "2",
+                    "type": "road_closure",
+                    "closed": true,
+                    "geometry_index_start": 3
+                },
+                {
+                    "id": "3",
+                    "type": "something_new",
+                    "geometry_index_start": 99
+                }
+            ]
+        }"#;
+        let leg: RouteLeg = serde_json::from_str(leg).expect("Unable to parse leg");
+        let steps = leg
+            .steps
+            .iter()
+            .enumerate()
+            .map(|(step_index, step)| {
+                RouteStep::from_osrm(
+                    step,
+                    6,
+                    "en",
+                    ResponseIndex::route(0).leg(0).step(step_index),
+                )
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .expect("Unable to convert steps");
+
+        let incidents = leg_incidents(&leg, &steps, 100.0);
+        assert_eq!(incidents.len(), 3);
+
+        assert_eq!(incidents[0].kind, IncidentKind::Construction);
+        assert!(!incidents[0].is_closed);
+        assert!((incidents[0].start_distance_along_route - 211.2).abs() < 0.1);
+        assert!((incidents[0].end_distance_along_route - 322.4).abs() < 0.1);
+
+        assert_eq!(incidents[1].kind, IncidentKind::RoadClosure);
+        assert!(incidents[1].is_closed);
+        assert!((incidents[1].start_distance_along_route - 433.6).abs() < 0.1);
+        assert!((incidents[1].end_distance_along_route - 433.6).abs() < 0.1);
+
+        // Unknown kinds and out of range indices are tolerated
+        assert_eq!(incidents[2].kind, IncidentKind::Miscellaneous);
+        assert!((incidents[2].start_distance_along_route - 433.6).abs() < 0.1);
+    }
+
+    #[test]
+    fn parse_exit_signage() {
+        let leg = r#"{
+            "distance": 333.6,
+            "duration": 30,
+            "steps": [
+                {
+                    "distance": 222.4,
+                    "duration": 20,
+                    "geometry": "???o}@?o}@",
+                    "maneuver": {"location": [0, 0], "bearing_before": 0, "bearing_after": 90, "type": "depart"},
+                    "intersections": [],
+                    "bannerInstructions": [
+                        {
+                            "distanceAlongGeometry": 222.4,
+                            "primary": {
+                                "text": "Exit 23 A 7",
+                                "type": "off ramp",
+                                "modifier": "right",
+                                "components": [
+                                    {"text": "Exit", "type": "text"},
+                                    {"text": "23", "type": "exit-number"},
+                                    {"text": "A 7", "type": "icon"}
+                                ]
+                            }
+                        }
+                    ]
+                },
+                {
+                    "distance": 111.2,
+                    "duration": 10,
+                    "geometry": "?o}@?o}@",
+                    "maneuver": {"location": [0.002, 0], "bearing_before": 90, "bearing_after": 90, "type": "off ramp", "modifier": "right"},
+                    "intersections": [],
+                    "exits": "23;23a",
+                    "destinations": "A 7, A 1: Hamburg, Bremen"
+                }
+            ]
+        }"#;
+        let leg: RouteLeg = serde_json::from_str(leg).expect("Unable to parse leg");
+        let mut steps = leg
+            .steps
+            .iter()
+            .enumerate()
+            .map(|(step_index, step)| {
+                RouteStep::from_osrm(
+                    step,
+                    6,
+                    "en",
+                    ResponseIndex::route(0).leg(0).step(step_index),
+                )
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .expect("Unable to convert steps");
+        apply_signage(&mut steps, &leg).expect("Unable to apply signage");
+
+        // Banner components take precedence over the step signage
+        let content = &steps[0].visual_instructions[0].primary_content;
+        assert_eq!(content.exit_numbers, vec!["23"]);
+        assert_eq!(content.destination_refs, vec!["A 7"]);
+        assert_eq!(content.destinations, vec!["Hamburg", "Bremen"]);
+    }
+
+    #[test]
+    fn parse_pronunciation_hints() {
+        let leg = r#"{
+            "distance": 222.4,
+            "duration": 20,
+            "steps": [
+                {
+                    "distance": 111.2,
+                    "duration": 10,
+                    "geometry": "???o}@",
+                    "name": "Laeva",
+                    "maneuver": {"location": [0, 0], "bearing_before": 0, "bearing_after": 90, "type": "depart"},
+                    "intersections": [],
+                    "voiceInstructions": [
+                        {
+                            "distanceAlongGeometry": 111.2,
+                            "announcement": "Turn right onto Peetri St",
+                            "ssmlAnnouncement": "<speak>Turn right onto Peetri St</speak>"
+                        }
+                    ]
+                },
+                {
+                    "distance": 111.2,
+                    "duration": 10,
+                    "geometry": "?o}@?o}@",
+                    "name": "Peetri",
+                    "pronunciation": "ˈpeːtri",
+                    "maneuver": {"location": [0.001, 0], "bearing_before": 90, "bearing_after": 180, "type": "turn", "modifier": "right"},
+                    "intersections": []
+                }
+            ]
+        }"#;
+        let leg: RouteLeg = serde_json::from_str(leg).expect("Unable to parse leg");
+        let mut steps = leg
+            .steps
+            .iter()
+            .enumerate()
+            .map(|(step_index, step)| {
+                RouteStep::from_osrm(
+                    step,
+                    6,
+                    "en",
+                    ResponseIndex::route(0).leg(0).step(step_index),
+                )
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .expect("Unable to convert steps");
+        apply_signage(&mut steps, &leg).expect("Unable to apply signage");
+
+        // The announcement names the way after the maneuver
+        assert_eq!(
+            steps[0].spoken_instructions[0].ssml.as_deref(),
+            Some(
+                r#"<speak>Turn right onto <phoneme alphabet="ipa" ph="ˈpeːtri">Peetri</phoneme> <sub alias="Street">St</sub></speak>"#
+            )
+        );
+        assert_eq!(
+            steps[0].spoken_instructions[0].text,
+            "Turn right onto Peetri St"
+        );
+    }
+
+    #[test]
+    fn parse_road_shields() {
+        let leg = r#"{
+            "distance": 333.6,
+            "duration": 30,
+            "steps": [
+                {
+                    "distance": 222.4,
+                    "duration": 20,
+                    "geometry": "???o}@?o}@",
+                    "maneuver": {"location": [0, 0], "bearing_before": 0, "bearing_after": 90, "type": "depart"},
+                    "intersections": [],
+                    "bannerInstructions": [
+                        {
+                            "distanceAlongGeometry": 222.4,
+                            "primary": {
+                                "text": "I 95",
+                                "type": "on ramp",
+                                "modifier": "right",
+                                "components": [
+                                    {
+                                        "text": "I 95",
+                                        "type": "icon",
+                                        "mapbox_shield": {
+                                            "base_url": "https://api.mapbox.com/styles/v1/",
+                                            "name": "us-interstate",
+                                            "display_ref": "95",
+                                            "text_color": "white"
+                                        }
+                                    }
+                                ]
+                            }
+                        }
+                    ]
+                },
+                {
+                    "distance": 111.2,
+                    "duration": 10,
+                    "geometry": "?o}@?o}@",
+                    "maneuver": {"location": [0.002, 0], "bearing_before": 90, "bearing_after": 90, "type": "on ramp", "modifier": "right"},
+                    "intersections": [],
+                    "ref": "I 95; US 1"
+                }
+            ]
+        }"#;
+        let leg: RouteLeg = serde_json::from_str(leg).expect("Unable to parse leg");
+        let mut steps = leg
+            .steps
+            .iter()
+            .enumerate()
+            .map(|(step_index, step)| {
+                RouteStep::from_osrm(
+                    step,
+                    6,
+                    "en",
+                    ResponseIndex::route(0).leg(0).step(step_index),
+                )
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .expect("Unable to convert steps");
+        apply_shield_networks(&mut steps, &leg).expect("Unable to apply shield networks");
+
+        assert!(steps[0].shields.is_empty());
+        // Only the first reference has a shield component
+        assert_eq!(
+            steps[1].shields,
+            vec![
+                RoadShield {
+                    network: Some("us-interstate".to_string()),
+                    number: "95".to_string(),
+                },
+                RoadShield {
+                    network: None,
+                    number: "US 1".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_step_signage() {
+        let signage = StepSignage {
+            exits: Some("23;23a".to_string()),
+            destinations: Some("A 7, A 1: Hamburg, Bremen".to_string()),
+            ..StepSignage::default()
+        };
+        assert_eq!(signage.exit_numbers(), vec!["23", "23a"]);
+        assert_eq!(
+            signage.destinations(),
+            (
+                vec!["A 7".to_string(), "A 1".to_string()],
+                vec!["Hamburg".to_string(), "Bremen".to_string()]
+            )
+        );
+
+        // Destinations without road references
+        let signage = StepSignage {
+            destinations: Some("Airport".to_string()),
+            ..StepSignage::default()
+        };
+        assert!(signage.exit_numbers().is_empty());
+        assert_eq!(
+            signage.destinations(),
+            (vec![], vec!["Airport".to_string()])
+        );
+        assert_eq!(StepSignage::default().destinations(), (vec![], vec![]));
+    }
+
+    #[test]
+    fn parse_errors() {
+        let parser = OsrmResponseParser::new(6);
+        assert!(matches!(
+            parser.parse_response(r#"{"code":"Ok","routes":[],"waypoints":[]}"#.into()),
+            Err(RoutingResponseParseError::NoRoutes)
+        ));
+        assert!(matches!(
+            parser.parse_response(r#"{"code":"Ok","routes":"#.into()),
+            Err(RoutingResponseParseError::InvalidJson { .. })
+        ));
+        assert!(matches!(
+            parser.parse_response(
+                r#"{"code":"NoRoute","message":"Impossible route between points"}"#.into()
+            ),
+            Err(RoutingResponseParseError::BackendError {
+                error: RoutingBackendError::NoRoute,
+                message: Some(message),
+            }) if message == "Impossible route between points"
+        ));
+        assert!(matches!(
+            parser.parse_response(
+                r#"{"code":"InvalidValue","message":"Invalid coordinate value."}"#.into()
+            ),
+            Err(RoutingResponseParseError::BackendError {
+                error: RoutingBackendError::InvalidQuery,
+                ..
+            })
+        ));
+        assert!(matches!(
+            parser.parse_response(r#"{"code":"NoTrips"}"#.into()),
+            Err(RoutingResponseParseError::BackendError {
+                error: RoutingBackendError::Other { code },
+                message: None,
+            }) if code == "NoTrips"
+        ));
+    }
+
+    /// Parses a response with both the eager and lazy parsers.
+    fn parse_both_ways(response: &[u8]) {
+        let _ = OsrmResponseParser::new(6).parse_response(response.to_vec());
+        let _ = OsrmResponseParser::with_lazy_step_parsing(6).parse_response(response.to_vec());
+    }
+
+    proptest! {
+        #[test]
+        fn arbitrary_responses_do_not_panic(response: Vec<u8>) {
+            parse_both_ways(&response);
+        }
+
+        #[test]
+        fn corrupted_responses_do_not_panic(
+            position: prop::sample::Index,
+            byte: u8,
+            truncate: bool,
+        ) {
+            // Corrupting a valid response reaches much deeper into the parser than random bytes
+            let mut response = VALHALLA_OSRM_RESPONSE.as_bytes().to_vec();
+            let position = position.index(response.len());
+            if truncate {
+                response.truncate(position);
+            } else {
+                response[position] = byte;
+            }
+            parse_both_ways(&response);
+        }
+    }
 }
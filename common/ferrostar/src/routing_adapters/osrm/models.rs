@@ -4,10 +4,18 @@
 //! by others which are now pseudo-standardized (ex: Mapbox). We omit some fields which are not
 //! needed for navigation.
 
-use crate::models::{ManeuverModifier, ManeuverType};
+use crate::instructions::{
+    synthesize_instruction, synthesize_pedestrian_instruction, PedestrianFeature,
+};
+use crate::models::{
+    DrivingSide, IncidentKind, ManeuverModifier, ManeuverType, SpeedLimitSign, SpeedUnit,
+};
+use serde::de::{value::StrDeserializer, IntoDeserializer};
 use serde::Deserialize;
+#[cfg(feature = "backend-extras")]
+use std::collections::HashMap;
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, Copy, Clone)]
 #[serde(transparent)]
 pub struct Coordinate {
     tuple: (f64, f64),
@@ -23,23 +31,53 @@ impl Coordinate {
     }
 }
 
+/// A route response.
+///
+/// The step type is generic so that steps can be deserialized lazily
+/// (see [`crate::routing_adapters::osrm::OsrmResponseParser::with_lazy_step_parsing`]).
 #[derive(Deserialize, Debug)]
-pub struct RouteResponse {
+#[serde(bound(deserialize = "S: Deserialize<'de>"))]
+pub struct RouteResponse<S = RouteStep> {
     /// The response code.
     ///
-    /// Ok indicates success. TODO: enumerate others?
+    /// Ok indicates success; anything else is an error
+    /// (see [`crate::routing_adapters::error::RoutingBackendError`]).
     pub code: String,
-    pub routes: Vec<Route>,
+    /// A description of the error (if any).
+    pub message: Option<String>,
+    /// The routes; absent when an error occurred.
+    #[serde(default)]
+    pub routes: Vec<Route<S>>,
+    #[serde(default)]
     pub waypoints: Vec<Waypoint>,
+    /// A Valhalla extension listing warnings about the routes
+    /// (ex: that there is no access restriction data in the region).
+    #[serde(default)]
+    pub warnings: Vec<Warning>,
+}
+
+/// A warning about the routes in a response.
+///
+/// NOTE: This is a Valhalla extension.
+#[derive(Deserialize, Debug)]
+pub struct Warning {
+    /// The Valhalla warning code.
+    pub code: Option<u32>,
+    /// A description of the warning.
+    pub text: String,
 }
 
 /// A route between two or more waypoints.
 #[derive(Deserialize, Debug)]
-pub struct Route {
+pub struct Route<S = RouteStep> {
     /// The estimated travel time, in seconds.
     pub duration: f64,
     /// The distance traveled by the route, in meters.
     pub distance: f64,
+    /// The weight of the route, in units of the `weight_name` metric.
+    pub weight: Option<f64>,
+    /// The name of the metric used to weigh routes (ex: `routability`).
+    pub weight_name: Option<String>,
     /// The geometry of the route.
     ///
     /// NOTE: This library assumes that 1) an overview geometry will always be requested, and
@@ -47,22 +85,97 @@ pub struct Route {
     /// by the [`crate::routing_adapters::RouteResponseParser`]).
     pub geometry: String,
     /// The legs between the given waypoints.
-    pub legs: Vec<RouteLeg>,
+    pub legs: Vec<RouteLeg<S>>,
 }
 
 /// A route between exactly two waypoints.
 #[derive(Deserialize, Debug)]
-pub struct RouteLeg {
+pub struct RouteLeg<S = RouteStep> {
     pub annotation: Option<Annotation>,
     /// The estimated travel time, in seconds.
     pub duration: f64,
     /// The distance traveled this leg, in meters.
     pub distance: f64,
     /// A sequence of steps with turn-by-turn instructions.
-    pub steps: Vec<RouteStep>,
+    pub steps: Vec<S>,
     /// A Mapbox and Valhalla extension which indicates which waypoints are passed through rather than creating a new leg.
     #[serde(default)]
     pub via_waypoints: Vec<ViaWaypoint>,
+    /// A Mapbox extension listing traffic incidents along the leg.
+    #[serde(default)]
+    pub incidents: Vec<Incident>,
+    /// A Mapbox and Valhalla extension listing the administrative regions (countries)
+    /// which the leg passes through.
+    ///
+    /// These are referenced by [`Intersections::admin_index`].
+    #[serde(default)]
+    pub admins: Vec<Admin>,
+    /// A Mapbox extension listing notifications about the leg
+    /// (ex: that a vehicle restriction could not be satisfied).
+    #[serde(default)]
+    pub notifications: Vec<Notification>,
+}
+
+/// A notification about a route leg.
+///
+/// NOTE: This is a Mapbox extension. We omit the fields which are not needed for navigation.
+#[derive(Deserialize, Debug)]
+pub struct Notification {
+    /// The kind of notification (ex: `violation`).
+    #[serde(rename = "type")]
+    pub notification_type: String,
+    /// The specific kind of notification (ex: `maxHeight`).
+    pub subtype: Option<String>,
+    pub details: Option<NotificationDetails>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct NotificationDetails {
+    /// A description of the notification.
+    pub message: Option<String>,
+}
+
+impl Notification {
+    /// A description of the notification, falling back to its (sub)type.
+    pub fn message(&self) -> String {
+        self.details
+            .as_ref()
+            .and_then(|details| details.message.clone())
+            .or_else(|| self.subtype.clone())
+            .unwrap_or_else(|| self.notification_type.clone())
+    }
+}
+
+/// An administrative region along a route leg.
+///
+/// NOTE: This is a Mapbox and Valhalla extension.
+#[derive(Deserialize, Debug)]
+pub struct Admin {
+    /// The ISO 3166-1 alpha-2 code of the country (ex: `DE`).
+    pub iso_3166_1: String,
+    /// The ISO 3166-1 alpha-3 code of the country (ex: `DEU`).
+    pub iso_3166_1_alpha3: Option<String>,
+}
+
+/// A traffic incident along a route leg.
+///
+/// NOTE: This is a Mapbox extension. We omit the fields which are not needed for navigation.
+/// HERE incidents are not supported, as HERE routing responses are not OSRM-compatible.
+#[derive(Deserialize, Debug)]
+pub struct Incident {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub kind: IncidentKind,
+    pub description: Option<String>,
+    /// Whether the road is closed.
+    #[serde(default, rename = "closed")]
+    pub is_closed: bool,
+    /// The index of the leg geometry point at which the incident starts.
+    pub geometry_index_start: usize,
+    /// The index of the leg geometry point at which the incident ends.
+    ///
+    /// Incidents without an end affect only a single point.
+    pub geometry_index_end: Option<usize>,
 }
 
 /// An annotation of a route leg with fine-grained information about segments or nodes.
@@ -125,11 +238,31 @@ pub struct RouteStep {
     /// and Valhalla.
     pub exits: Option<String>,
 
+    /// The destinations of the way, as `refs: names` (ex: `A 7: Hamburg, Bremen`).
+    ///
+    /// NOTE: This annotation is not in the official spec, but is a common extension used by Mapbox
+    /// and Valhalla.
+    pub destinations: Option<String>,
+
     /// The side of the way on which traffic proceeds.
     ///
     /// NOTE: This annotation is not in the official spec, but is a common extension used by Mapbox
     /// and Valhalla.
     pub driving_side: Option<String>,
+
+    /// The style of the speed limit signs along the step (ex: `vienna` or `mutcd`).
+    ///
+    /// NOTE: This annotation is not in the official spec, but is a common extension used by Mapbox
+    /// and Valhalla.
+    #[serde(rename = "speedLimitSign")]
+    pub speed_limit_sign: Option<String>,
+
+    /// The unit of the speed limits along the step (ex: `km/h` or `mph`).
+    ///
+    /// NOTE: This annotation is not in the official spec, but is a common extension used by Mapbox
+    /// and Valhalla.
+    #[serde(rename = "speedLimitUnit")]
+    pub speed_limit_unit: Option<String>,
     // Mapbox and Valhalla extensions that might be useful later
     // pub rotary_name: Option<String>,
     // pub rotary_pronunciation: Option<String>,
@@ -139,6 +272,27 @@ pub struct RouteStep {
     /// Textual instructions that are displayed as a banner; supported by Mapbox and Stadia Maps
     #[serde(default, rename = "voiceInstructions")]
     pub voice_instructions: Vec<VoiceInstruction>,
+    /// Any other fields of the step (ex: from custom Valhalla costing models).
+    #[cfg(feature = "backend-extras")]
+    #[serde(flatten)]
+    pub extras: HashMap<String, serde_json::Value>,
+}
+
+impl RouteStep {
+    /// The style of the speed limit signs along the step, if it is known.
+    pub fn speed_limit_sign(&self) -> Option<SpeedLimitSign> {
+        self.speed_limit_sign.as_deref().and_then(parse_enum)
+    }
+
+    /// The unit of the speed limits along the step, if it is known.
+    pub fn speed_limit_unit(&self) -> Option<SpeedUnit> {
+        self.speed_limit_unit.as_deref().and_then(parse_enum)
+    }
+
+    /// The side of the road on which traffic drives along the step, if it is known.
+    pub fn driving_side(&self) -> Option<DrivingSide> {
+        self.driving_side.as_deref().and_then(parse_enum)
+    }
 }
 
 #[derive(Deserialize, Debug)]
@@ -148,6 +302,17 @@ pub struct BannerInstruction {
     pub distance_along_geometry: f64,
     pub primary: BannerContent,
     pub secondary: Option<BannerContent>,
+    /// A junction view or signboard image for the maneuver.
+    ///
+    /// NOTE: This is a Mapbox extension.
+    pub view: Option<BannerView>,
+}
+
+/// The image components of a banner (ex: a junction view).
+#[derive(Deserialize, Debug)]
+pub struct BannerView {
+    #[serde(default)]
+    pub components: Vec<BannerComponent>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -171,6 +336,124 @@ pub struct BannerContent {
     /// the original travel.
     #[serde(rename = "degrees")]
     pub roundabout_exit_degrees: Option<u16>,
+    /// The parts of the instruction text, with their types; supported by Mapbox and Valhalla
+    #[serde(default)]
+    pub components: Vec<BannerComponent>,
+}
+
+impl BannerInstruction {
+    /// The components of the primary and secondary content which describe Mapbox shields.
+    pub fn shield_components(&self) -> impl Iterator<Item = &BannerComponent> {
+        std::iter::once(&self.primary)
+            .chain(&self.secondary)
+            .flat_map(|content| &content.components)
+            .filter(|component| component.mapbox_shield.is_some())
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct BannerComponent {
+    pub text: String,
+    /// The type of component (ex: `text`, `icon` for road shields, or `exit-number`).
+    #[serde(rename = "type")]
+    pub component_type: Option<String>,
+    /// The shield to display for an `icon` component.
+    ///
+    /// NOTE: This annotation is not in the official spec; it is specific to Mapbox.
+    pub mapbox_shield: Option<MapboxShield>,
+    /// The URL of the image for a `guidance-view` component.
+    ///
+    /// NOTE: This annotation is not in the official spec; it is specific to Mapbox.
+    #[serde(rename = "imageURL")]
+    pub image_url: Option<String>,
+    /// The kind of image for a `guidance-view` component (ex: `jct` or `signboard`).
+    ///
+    /// NOTE: This annotation is not in the official spec; it is specific to Mapbox.
+    #[serde(rename = "subType")]
+    pub sub_type: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct MapboxShield {
+    /// The name of the shield design, which identifies the road network (ex: `us-interstate`).
+    pub name: String,
+    /// The road number to display on the shield (ex: `95`).
+    pub display_ref: String,
+}
+
+/// The banner instructions of a step.
+///
+/// This is deserialized separately from the rest of the step
+/// so that it is available when steps are parsed lazily.
+#[derive(Deserialize, Debug)]
+pub struct StepBanners {
+    #[serde(default, rename = "bannerInstructions")]
+    pub banner_instructions: Vec<BannerInstruction>,
+}
+
+/// The locations and administrative regions of the intersections along a step.
+///
+/// This is deserialized separately from the rest of the step
+/// so that it is available when steps are parsed lazily.
+#[derive(Deserialize, Debug)]
+pub struct StepAdmins {
+    #[serde(default)]
+    pub intersections: Vec<IntersectionAdmin>,
+}
+
+/// The location and administrative region of an intersection.
+#[derive(Deserialize, Debug)]
+pub struct IntersectionAdmin {
+    pub location: Coordinate,
+    /// An index into the [`RouteLeg::admins`].
+    pub admin_index: Option<usize>,
+}
+
+/// The signage (and name) of the way that a step begins on.
+///
+/// This is deserialized separately from the rest of the step
+/// so that it is available when steps are parsed lazily.
+#[derive(Deserialize, Debug, Default)]
+pub struct StepSignage {
+    /// A list of exits (name or number), separated by semicolons.
+    pub exits: Option<String>,
+    /// The destinations of the way, as `refs: names` (ex: `A 7: Hamburg, Bremen`).
+    pub destinations: Option<String>,
+    /// The name of the way.
+    pub name: Option<String>,
+    /// A pronunciation hint for the name of the way.
+    pub pronunciation: Option<String>,
+}
+
+impl StepSignage {
+    pub fn exit_numbers(&self) -> Vec<String> {
+        split_list(self.exits.as_deref(), ';')
+    }
+
+    /// Splits the destinations into road references and names.
+    pub fn destinations(&self) -> (Vec<String>, Vec<String>) {
+        match self
+            .destinations
+            .as_deref()
+            .map(|value| value.split_once(':'))
+        {
+            Some(Some((refs, names))) => {
+                (split_list(Some(refs), ','), split_list(Some(names), ','))
+            }
+            Some(None) => (vec![], split_list(self.destinations.as_deref(), ',')),
+            None => (vec![], vec![]),
+        }
+    }
+}
+
+fn split_list(value: Option<&str>, separator: char) -> Vec<String> {
+    value
+        .into_iter()
+        .flat_map(|value| value.split(separator))
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(ToString::to_string)
+        .collect()
 }
 
 #[derive(Deserialize, Debug)]
@@ -199,21 +482,48 @@ pub struct StepManeuver {
 }
 
 impl StepManeuver {
-    // TODO: This is a placeholder implementation.
-    // Most commercial offerings offer server-side synthesis of voice instructions.
-    // However, we might consider synthesizing these locally too.
-    // This will be rather cumbersome with localization though.
-    fn synthesize_instruction(&self, _locale: &str) -> String {
-        String::from("TODO: OSRM instruction synthesis")
+    /// Synthesizes an instruction for backends which do not generate them (ex: vanilla OSRM).
+    ///
+    /// The pedestrian feature (if any) is that of the path which the maneuver leads onto.
+    fn synthesize_instruction(
+        &self,
+        locale: &str,
+        road_name: Option<&str>,
+        pedestrian_feature: Option<PedestrianFeature>,
+    ) -> String {
+        let maneuver_type = parse_enum(&self.maneuver_type);
+        match (maneuver_type, pedestrian_feature) {
+            (Some(ManeuverType::Depart | ManeuverType::Arrive), _) | (_, None) => {
+                synthesize_instruction(
+                    locale,
+                    maneuver_type,
+                    self.modifier.as_deref().and_then(parse_enum),
+                    road_name,
+                )
+            }
+            (_, Some(feature)) => synthesize_pedestrian_instruction(locale, feature, road_name),
+        }
     }
 
-    pub fn get_instruction(&self) -> String {
+    /// Gets the instruction from the backend, or synthesizes one in the language of the locale.
+    pub fn get_instruction(
+        &self,
+        locale: &str,
+        road_name: Option<&str>,
+        pedestrian_feature: Option<PedestrianFeature>,
+    ) -> String {
         self.instruction
             .clone()
-            .unwrap_or_else(|| self.synthesize_instruction("en-US"))
+            .unwrap_or_else(|| self.synthesize_instruction(locale, road_name, pedestrian_feature))
     }
 }
 
+/// Parses a string enum value (ex: a maneuver type), if it is known.
+fn parse_enum<'a, T: Deserialize<'a>>(value: &'a str) -> Option<T> {
+    let deserializer: StrDeserializer<'a, serde::de::value::Error> = value.into_deserializer();
+    T::deserialize(deserializer).ok()
+}
+
 #[derive(Deserialize, Debug)]
 pub struct Intersections {
     /// The location of the intersection
@@ -228,6 +538,15 @@ pub struct Intersections {
     /// Note that Valhalla servers do not return this property.
     #[serde(default)]
     pub classes: Vec<String>,
+    /// The name of the tunnel that the road exiting the intersection passes through.
+    ///
+    /// NOTE: This annotation is not in the official spec, but is a Mapbox extension.
+    pub tunnel_name: Option<String>,
+    /// An index into the [`RouteLeg::admins`] for the region of the intersection.
+    ///
+    /// NOTE: This annotation is not in the official spec, but is a common extension used by Mapbox
+    /// and Valhalla.
+    pub admin_index: Option<usize>,
     /// A list of entry flags, corresponding 1:1 to the list of bearings.
     ///
     /// This value indicates whether the respective road could be entered on a valid route (not
@@ -239,16 +558,29 @@ pub struct Intersections {
     /// maneuver/passing the intersection.
     /// To get the bearing in the direction of driving, the bearing has to be rotated by
     /// 180 degrees. Not supplied for `depart` maneuvers.
-    #[serde(default)]
     #[serde(rename = "in")]
-    pub intersection_in: usize,
-    /// An index into the bearings/entry array used to calculate the bearing just before the turn.
+    pub intersection_in: Option<usize>,
+    /// An index into the bearings/entry array used to calculate the bearing just after the turn.
     ///
     /// The clockwise angle from true north to the direction of travel immediately after the
     /// maneuver/passing the intersection.  Not supplied for `arrive` maneuvers.
-    #[serde(default)]
     #[serde(rename = "out")]
-    pub intersection_out: usize,
+    pub intersection_out: Option<usize>,
+    /// The index of the intersection in the geometry of the leg.
+    ///
+    /// NOTE: This annotation is not in the official spec, but is a common extension used by Mapbox
+    /// and Valhalla.
+    pub geometry_index: Option<usize>,
+    /// The additional weight of the turn at the intersection.
+    ///
+    /// NOTE: This annotation is not in the official spec, but is a common extension used by Mapbox
+    /// and Valhalla.
+    pub turn_weight: Option<f64>,
+    /// The additional time it takes to make the turn at the intersection, in seconds.
+    ///
+    /// NOTE: This annotation is not in the official spec, but is a common extension used by Mapbox
+    /// and Valhalla.
+    pub turn_duration: Option<f64>,
     /// A list of turn [Lane]s available at the intersection (if info is available).
     ///
     /// Lanes are listed in left-to-right order.
@@ -256,6 +588,58 @@ pub struct Intersections {
     pub lanes: Vec<Lane>,
 }
 
+impl Intersections {
+    /// Whether the road exiting the intersection passes through a tunnel.
+    pub fn is_tunnel(&self) -> bool {
+        self.tunnel_name.is_some() || self.classes.iter().any(|class| class == "tunnel")
+    }
+
+    /// Whether the road exiting the intersection is a toll road.
+    pub fn is_toll(&self) -> bool {
+        self.classes.iter().any(|class| class == "toll")
+    }
+
+    /// Whether the road exiting the intersection is a ferry route.
+    pub fn is_ferry(&self) -> bool {
+        self.classes.iter().any(|class| class == "ferry")
+    }
+
+    /// Whether the path exiting the intersection crosses a road at a crosswalk.
+    pub fn is_crosswalk(&self) -> bool {
+        self.classes
+            .iter()
+            .any(|class| class == "crossing" || class == "crosswalk")
+    }
+
+    /// Whether the path exiting the intersection is a flight of stairs.
+    pub fn is_stairs(&self) -> bool {
+        self.classes
+            .iter()
+            .any(|class| class == "steps" || class == "stairs")
+    }
+
+    /// Whether the path exiting the intersection is an elevator.
+    pub fn is_elevator(&self) -> bool {
+        self.classes.iter().any(|class| class == "elevator")
+    }
+
+    /// The pedestrian feature of the path exiting the intersection (if any).
+    ///
+    /// Elevators and stairs take precedence over crosswalks,
+    /// since they are more important to describe for accessibility.
+    pub fn pedestrian_feature(&self) -> Option<PedestrianFeature> {
+        if self.is_elevator() {
+            Some(PedestrianFeature::Elevator)
+        } else if self.is_stairs() {
+            Some(PedestrianFeature::Stairs)
+        } else if self.is_crosswalk() {
+            Some(PedestrianFeature::Crosswalk)
+        } else {
+            None
+        }
+    }
+}
+
 #[derive(Deserialize, Debug)]
 pub struct Lane {
     /// An indication (ex: marking on the road, sign, etc.) for a turn lane.
@@ -276,6 +660,30 @@ pub struct Waypoint {
     pub distance: Option<f64>,
     /// The waypoint's location on the road network.
     pub location: Coordinate,
+    /// Additional information about the waypoint (a Mapbox extension).
+    pub metadata: Option<WaypointMetadata>,
+}
+
+/// Describes a waypoint which was added by the backend (ex: a charging stop for EV routes).
+#[derive(Deserialize, Debug)]
+pub struct WaypointMetadata {
+    /// The kind of waypoint (ex: `charging-station`).
+    #[serde(rename = "type")]
+    pub waypoint_type: String,
+    /// The name of the charging station.
+    pub name: Option<String>,
+    /// An identifier for the charging station.
+    pub station_id: Option<String>,
+    /// The expected battery charge on arrival, in watt-hours.
+    pub charge_at_arrival: Option<f64>,
+    /// The battery charge to charge to, in watt-hours.
+    pub charge_to: Option<f64>,
+    /// The expected time spent charging, in seconds.
+    pub charge_time: Option<f64>,
+    /// The maximum power of the charger, in kilowatts.
+    pub power_kw: Option<f64>,
+    /// The connector to use (ex: `ccs_combo_type1`).
+    pub plug_type: Option<String>,
 }
 
 #[derive(Deserialize, Debug)]
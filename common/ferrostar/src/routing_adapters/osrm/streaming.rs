@@ -0,0 +1,483 @@
+//! Incremental parsing of OSRM responses while they are still downloading.
+//!
+//! OSRM responses list the overview geometry and the steps of each route
+//! well before the (comparatively small) waypoints at the end.
+//! By scanning the response as it arrives, we can build a provisional route
+//! from the first route's geometry and whatever steps are complete so far,
+//! so that navigation can start before the full response is available.
+
+use super::models::BannerComponent;
+use super::models::RouteStep as OsrmRouteStep;
+use super::{add_shield_networks, add_signage, OsrmResponseParser, OsrmStep};
+use crate::algorithms::decode_polyline;
+use crate::clock::Clock;
+use crate::models::{GeographicCoordinate, Route, RouteStep, Waypoint, WaypointKind};
+use crate::routing_adapters::{
+    error::{ResponseIndex, RoutingResponseParseError},
+    validation::{validate_geometry, validate_step},
+    RouteResponseParser,
+};
+use geo::{BoundingRect, Coord, HaversineLength, LineString};
+use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::{Arc, Mutex};
+
+/// A parser which accepts an OSRM response in chunks as it is downloaded.
+///
+/// Push each chunk with [`StreamingOsrmResponseParser::push`].
+/// As soon as the first route's geometry and first step have arrived,
+/// [`StreamingOsrmResponseParser::provisional_route`] returns a route which can be used to start navigating.
+/// Once the download completes, [`StreamingOsrmResponseParser::finish`] parses the full response
+/// (exactly like [`OsrmResponseParser`]), and the provisional route should be replaced.
+///
+/// Only the first route is available provisionally.
+#[derive(uniffi::Object)]
+pub struct StreamingOsrmResponseParser {
+    parser: OsrmResponseParser,
+    state: Mutex<StreamingState>,
+}
+
+#[derive(Default)]
+struct StreamingState {
+    buffer: Vec<u8>,
+    scanner: Scanner,
+    geometry: Option<Vec<GeographicCoordinate>>,
+    steps: Vec<RouteStep>,
+    /// The shield components in the instructions of the last step.
+    shield_components: Vec<BannerComponent>,
+}
+
+#[uniffi::export]
+impl StreamingOsrmResponseParser {
+    #[uniffi::constructor]
+    pub fn new(polyline_precision: u32) -> Self {
+        Self {
+            parser: OsrmResponseParser::new(polyline_precision),
+            state: Mutex::new(StreamingState::default()),
+        }
+    }
+
+    /// Creates a parser which stamps [`Route::planned_at`] on routes using the clock
+    /// (the system clock when this is `None`),
+    /// which should be the same one as in the navigation controller's config.
+    #[uniffi::constructor]
+    pub fn new_with_clock(polyline_precision: u32, clock: Option<Arc<dyn Clock>>) -> Self {
+        let parser = OsrmResponseParser::new(polyline_precision);
+        Self {
+            parser: match clock {
+                Some(clock) => parser.with_clock(clock),
+                None => parser,
+            },
+            state: Mutex::new(StreamingState::default()),
+        }
+    }
+
+    /// Appends the next chunk of the response body.
+    ///
+    /// Fails if a complete geometry or step in the chunk is invalid.
+    pub fn push(&self, chunk: &[u8]) -> Result<(), RoutingResponseParseError> {
+        let mut state = self
+            .state
+            .lock()
+            .map_err(|_| RoutingResponseParseError::UnknownError)?;
+        let StreamingState {
+            buffer,
+            scanner,
+            geometry,
+            steps,
+            shield_components,
+        } = &mut *state;
+
+        buffer.extend_from_slice(chunk);
+        for (value, range) in scanner.scan(buffer) {
+            let json = &buffer[range];
+            match value {
+                Value::Geometry => {
+                    let index = ResponseIndex::route(0);
+                    let polyline: String = serde_json::from_slice(json)?;
+                    let linestring =
+                        decode_polyline(&polyline, self.parser.polyline_precision).map_err(
+                            |error| RoutingResponseParseError::GeometryDecodeError { index, error },
+                        )?;
+                    let coordinates: Vec<_> = linestring
+                        .coords()
+                        .map(|coord| GeographicCoordinate::from(*coord))
+                        .collect();
+                    validate_geometry(&coordinates, index)?;
+                    *geometry = Some(coordinates);
+                }
+                Value::Step { leg, step } => {
+                    let index = ResponseIndex::route(0).leg(leg).step(step);
+                    let osrm_step: OsrmRouteStep = serde_json::from_slice(json)?;
+                    let mut route_step = RouteStep::from_osrm(
+                        &osrm_step,
+                        self.parser.polyline_precision,
+                        &self.parser.locale,
+                        index,
+                    )?;
+                    validate_step(&route_step, index)?;
+                    // The signage of this step belongs to the previous step's instructions,
+                    // which in turn identify the shields of this step
+                    if step > 0 {
+                        if let Some(previous_step) = steps.last_mut() {
+                            add_signage(previous_step, &osrm_step.signage()?);
+                        }
+                        add_shield_networks(&mut route_step, shield_components);
+                    }
+                    *shield_components = osrm_step.shield_components()?;
+                    steps.push(route_step);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Builds a route from the first route's geometry and the steps received so far.
+    ///
+    /// Returns `None` until the geometry and at least one step are available.
+    /// The waypoints are the start and end of the geometry,
+    /// the distance is measured from the geometry,
+    /// and the duration is the total of the steps received so far,
+    /// as the actual values are not available until later in the response.
+    pub fn provisional_route(&self) -> Option<Route> {
+        let state = self.state.lock().ok()?;
+        let geometry = state.geometry.clone()?;
+        if state.steps.is_empty() {
+            return None;
+        }
+
+        let linestring: LineString = geometry.iter().map(|c| Coord::from(*c)).collect();
+        let waypoints = [geometry.first()?, geometry.last()?]
+            .into_iter()
+            .map(|coordinate| Waypoint {
+                coordinate: *coordinate,
+                kind: WaypointKind::Break,
+            })
+            .collect();
+        Some(Route {
+            bbox: linestring.bounding_rect()?.into(),
+            distance: linestring.haversine_length(),
+            duration: state.steps.iter().map(|step| step.duration).sum(),
+            weight: None,
+            weight_name: None,
+            waypoints,
+            geometry,
+            steps: state.steps.clone(),
+            elevation_profile: None,
+            incidents: vec![],
+            charging_stops: vec![],
+            destination_details: None,
+            notices: vec![],
+            planned_at: Some(self.parser.now()),
+            extras: HashMap::new(),
+        })
+    }
+
+    /// Parses the complete response once it has been fully received.
+    ///
+    /// This resets the parser, so it can be reused for another response.
+    pub fn finish(&self) -> Result<Vec<Route>, RoutingResponseParseError> {
+        let mut state = self
+            .state
+            .lock()
+            .map_err(|_| RoutingResponseParseError::UnknownError)?;
+        let StreamingState { buffer, .. } = std::mem::take(&mut *state);
+        self.parser.parse_response(buffer)
+    }
+}
+
+//
+// Scanning
+//
+
+/// A value in the response which can be used before the response is complete.
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Value {
+    /// The overview geometry of the first route.
+    Geometry,
+    /// A step of the first route.
+    Step { leg: usize, step: usize },
+}
+
+#[derive(Debug)]
+enum Frame {
+    Object {
+        key: Option<String>,
+        expecting_key: bool,
+    },
+    Array {
+        index: usize,
+    },
+}
+
+/// Identifies the value starting at the current position, if it is one we're interested in.
+fn value_at(stack: &[Frame]) -> Option<Value> {
+    let key = |frame: &Frame| match frame {
+        Frame::Object { key, .. } => key.clone(),
+        Frame::Array { .. } => None,
+    };
+    let index = |frame: &Frame| match frame {
+        Frame::Object { .. } => None,
+        Frame::Array { index } => Some(*index),
+    };
+
+    match stack {
+        [routes, route, geometry]
+            if key(routes).as_deref() == Some("routes")
+                && index(route) == Some(0)
+                && key(geometry).as_deref() == Some("geometry") =>
+        {
+            Some(Value::Geometry)
+        }
+        [routes, route, legs, leg, steps, step]
+            if key(routes).as_deref() == Some("routes")
+                && index(route) == Some(0)
+                && key(legs).as_deref() == Some("legs")
+                && key(steps).as_deref() == Some("steps") =>
+        {
+            Some(Value::Step {
+                leg: index(leg)?,
+                step: index(step)?,
+            })
+        }
+        _ => None,
+    }
+}
+
+#[derive(Debug)]
+struct Capture {
+    value: Value,
+    start: usize,
+    depth: usize,
+}
+
+/// A minimal JSON scanner which tracks the path to the current position
+/// so that we can pick out values as soon as they are complete.
+///
+/// This does not validate the JSON; that is left to serde when parsing the values
+/// (and the full response).
+#[derive(Debug, Default)]
+struct Scanner {
+    position: usize,
+    stack: Vec<Frame>,
+    in_string: bool,
+    is_escaped: bool,
+    is_key: bool,
+    string_start: usize,
+    capture: Option<Capture>,
+}
+
+impl Scanner {
+    /// Scans any bytes which have been appended to the buffer since the last call,
+    /// returning the byte ranges of the values which were completed.
+    fn scan(&mut self, buffer: &[u8]) -> Vec<(Value, Range<usize>)> {
+        let mut completed = vec![];
+        while let Some(&byte) = buffer.get(self.position) {
+            let position = self.position;
+            self.position += 1;
+
+            if self.in_string {
+                if self.is_escaped {
+                    self.is_escaped = false;
+                } else if byte == b'\\' {
+                    self.is_escaped = true;
+                } else if byte == b'"' {
+                    self.in_string = false;
+                    if self.is_key {
+                        if let Some(Frame::Object { key, .. }) = self.stack.last_mut() {
+                            *key = Some(
+                                String::from_utf8_lossy(&buffer[self.string_start..position])
+                                    .into_owned(),
+                            );
+                        }
+                    } else {
+                        self.end_value(position + 1, &mut completed);
+                    }
+                }
+                continue;
+            }
+
+            match byte {
+                b'{' => {
+                    self.start_value(position);
+                    self.stack.push(Frame::Object {
+                        key: None,
+                        expecting_key: true,
+                    });
+                }
+                b'[' => {
+                    self.start_value(position);
+                    self.stack.push(Frame::Array { index: 0 });
+                }
+                b'}' | b']' => {
+                    self.stack.pop();
+                    self.end_value(position + 1, &mut completed);
+                }
+                b',' => match self.stack.last_mut() {
+                    Some(Frame::Object { key, expecting_key }) => {
+                        *key = None;
+                        *expecting_key = true;
+                    }
+                    Some(Frame::Array { index }) => *index += 1,
+                    None => {}
+                },
+                b':' => {
+                    if let Some(Frame::Object { expecting_key, .. }) = self.stack.last_mut() {
+                        *expecting_key = false;
+                    }
+                }
+                b'"' => {
+                    self.in_string = true;
+                    self.string_start = position + 1;
+                    self.is_key = matches!(
+                        self.stack.last(),
+                        Some(Frame::Object {
+                            expecting_key: true,
+                            ..
+                        })
+                    );
+                    if !self.is_key {
+                        self.start_value(position);
+                    }
+                }
+                // Whitespace and scalars don't affect the structure
+                _ => {}
+            }
+        }
+
+        completed
+    }
+
+    fn start_value(&mut self, start: usize) {
+        if self.capture.is_some() {
+            return;
+        }
+        if let Some(value) = value_at(&self.stack) {
+            self.capture = Some(Capture {
+                value,
+                start,
+                depth: self.stack.len(),
+            });
+        }
+    }
+
+    fn end_value(&mut self, end: usize, completed: &mut Vec<(Value, Range<usize>)>) {
+        match &self.capture {
+            Some(capture) if capture.depth == self.stack.len() => {
+                completed.push((capture.value, capture.start..end));
+                self.capture = None;
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::routing_adapters::osrm::tests::{
+        test_clock, STANDARD_OSRM_POLYLINE6_RESPONSE, VALHALLA_OSRM_RESPONSE,
+    };
+
+    #[test]
+    fn scan_in_chunks() {
+        let response = br#"{"code":"Ok","routes":[{"geometry":"a\"b","legs":[{"steps":[{"geometry":"x"},{"name":"}"}]},{"steps":[{}]}]},{"geometry":"c","legs":[{"steps":[{}]}]}]}"#;
+        let mut scanner = Scanner::default();
+        let mut buffer = vec![];
+        let mut completed = vec![];
+        for chunk in response.chunks(7) {
+            buffer.extend_from_slice(chunk);
+            completed.extend(scanner.scan(&buffer));
+        }
+
+        let values: Vec<_> = completed
+            .into_iter()
+            .map(|(value, range)| {
+                (
+                    value,
+                    String::from_utf8_lossy(&response[range]).into_owned(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            values,
+            vec![
+                (Value::Geometry, r#""a\"b""#.to_string()),
+                (
+                    Value::Step { leg: 0, step: 0 },
+                    r#"{"geometry":"x"}"#.to_string()
+                ),
+                (
+                    Value::Step { leg: 0, step: 1 },
+                    r#"{"name":"}"}"#.to_string()
+                ),
+                (Value::Step { leg: 1, step: 0 }, "{}".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn provisional_route() {
+        let clock = test_clock();
+        let parser = StreamingOsrmResponseParser::new_with_clock(6, Some(clock.clone()));
+        let response = VALHALLA_OSRM_RESPONSE.as_bytes();
+        let chunk_size = 1024;
+        let mut first_available_at = None;
+        for (chunk_index, chunk) in response.chunks(chunk_size).enumerate() {
+            parser.push(chunk).expect("Unable to push chunk");
+            if first_available_at.is_none() && parser.provisional_route().is_some() {
+                first_available_at = Some((chunk_index + 1) * chunk_size);
+            }
+        }
+
+        let first_available_at = first_available_at.expect("No provisional route was available");
+        assert!(first_available_at < response.len() / 2);
+
+        let provisional = parser
+            .provisional_route()
+            .expect("No provisional route was available");
+        let routes = parser.finish().expect("Unable to parse the full response");
+        assert_eq!(provisional.planned_at, Some(clock.now()));
+        assert_eq!(provisional.geometry, routes[0].geometry);
+        // Utterance IDs are random, so compare the serialized forms (which omit them)
+        assert_eq!(
+            serde_json::to_value(&provisional.steps).expect("Unable to serialize steps"),
+            serde_json::to_value(&routes[0].steps).expect("Unable to serialize steps")
+        );
+
+        // The parser is reset after finishing
+        assert!(parser.provisional_route().is_none());
+    }
+
+    #[test]
+    fn finish_matches_full_parse() {
+        let clock = test_clock();
+        let parser = StreamingOsrmResponseParser::new_with_clock(6, Some(clock.clone()));
+        parser
+            .push(STANDARD_OSRM_POLYLINE6_RESPONSE.as_bytes())
+            .expect("Unable to push response");
+        let routes = parser.finish().expect("Unable to parse response");
+        let expected = OsrmResponseParser::new(6)
+            .with_clock(clock)
+            .parse_response(STANDARD_OSRM_POLYLINE6_RESPONSE.into())
+            .expect("Unable to parse response");
+        assert_eq!(
+            serde_json::to_value(&routes).expect("Unable to serialize routes"),
+            serde_json::to_value(&expected).expect("Unable to serialize routes")
+        );
+    }
+
+    #[test]
+    fn invalid_step() {
+        let parser = StreamingOsrmResponseParser::new(6);
+        assert!(matches!(
+            parser.push(br#"{"code":"Ok","routes":[{"legs":[{"steps":[{"distance":"#),
+            Ok(())
+        ));
+        assert!(matches!(
+            parser.push(br#"true}]}]}]}"#),
+            Err(RoutingResponseParseError::InvalidJson { .. })
+        ));
+    }
+}
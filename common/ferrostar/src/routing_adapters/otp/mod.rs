@@ -0,0 +1,431 @@
+pub(crate) mod models;
+
+use super::RouteResponseParser;
+use crate::algorithms::decode_polyline;
+use crate::clock::Clock;
+use crate::models::{
+    GeographicCoordinate, RouteStep, TransitLeg, TransitMode, TransitStop, Waypoint, WaypointKind,
+};
+use crate::now;
+use crate::routing_adapters::{
+    error::{ResponseIndex, RoutingBackendError},
+    otp::models::{Leg, Place, PlanResponse, WalkStep},
+    validation::{validate_geometry, validate_leg_steps},
+    Route, RoutingResponseParseError,
+};
+use geo::{BoundingRect, HaversineDistance, LineString, Point};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+/// OTP always encodes leg geometry as polyline5.
+const OTP_POLYLINE_PRECISION: u32 = 5;
+
+/// A response parser for the [OpenTripPlanner](https://www.opentripplanner.org/) REST plan API.
+///
+/// Each itinerary is converted into a [Route].
+/// Street legs (ex: walking to the stop) are split into steps using the OTP walk steps,
+/// and every transit leg becomes a single step with a [`TransitLeg`] attached.
+/// The stops where the user boards and alights are included in the route waypoints
+/// as [`WaypointKind::TransitBoard`] and [`WaypointKind::TransitAlight`].
+///
+/// For Valhalla multimodal routes, see
+/// [`crate::routing_adapters::valhalla_multimodal::ValhallaMultimodalResponseParser`].
+#[derive(Default)]
+pub struct OtpResponseParser {
+    /// The clock for [`Route::planned_at`] (the system clock when this is `None`).
+    clock: Option<Arc<dyn Clock>>,
+}
+
+impl OtpResponseParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the clock used to stamp [`Route::planned_at`] on parsed routes,
+    /// which should be the same one as in the navigation controller's config.
+    #[must_use]
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = Some(clock);
+        self
+    }
+}
+
+impl RouteResponseParser for OtpResponseParser {
+    fn parse_response(&self, response: Vec<u8>) -> Result<Vec<Route>, RoutingResponseParseError> {
+        let res: PlanResponse = serde_json::from_slice(&response)?;
+
+        let Some(plan) = res.plan else {
+            return Err(match res.error {
+                Some(error) => RoutingResponseParseError::BackendError {
+                    error: backend_error(error.message.as_deref()),
+                    message: error.msg,
+                },
+                None => RoutingResponseParseError::NoRoutes,
+            });
+        };
+
+        let planned_at = self.clock.as_ref().map_or_else(now, |clock| clock.now());
+        let mut routes = vec![];
+        for (route_index, itinerary) in plan.itineraries.iter().enumerate() {
+            let index = ResponseIndex::route(route_index);
+            let (Some(first_leg), Some(last_leg)) = (itinerary.legs.first(), itinerary.legs.last())
+            else {
+                continue;
+            };
+
+            let mut waypoints = vec![Waypoint {
+                coordinate: place_coordinate(&first_leg.from),
+                kind: WaypointKind::Break,
+            }];
+            let mut geometry: Vec<GeographicCoordinate> = vec![];
+            let mut steps = vec![];
+            let mut distance = 0.0;
+
+            for (leg_index, leg) in itinerary.legs.iter().enumerate() {
+                let leg_geometry = decode_leg_geometry(leg, index.leg(leg_index))?;
+
+                // Consecutive legs share an endpoint; avoid duplicating it in the overall geometry
+                let skip = usize::from(geometry.last() == leg_geometry.first());
+                geometry.extend(leg_geometry.iter().skip(skip));
+                distance += leg.distance;
+
+                if leg.is_transit {
+                    waypoints.push(Waypoint {
+                        coordinate: place_coordinate(&leg.from),
+                        kind: WaypointKind::TransitBoard,
+                    });
+                    waypoints.push(Waypoint {
+                        coordinate: place_coordinate(&leg.to),
+                        kind: WaypointKind::TransitAlight,
+                    });
+                    let step = transit_step(leg, leg_geometry);
+                    validate_leg_steps(std::slice::from_ref(&step), index.leg(leg_index))?;
+                    steps.push(step);
+                } else {
+                    let leg_steps = street_steps(leg, &leg_geometry);
+                    validate_leg_steps(&leg_steps, index.leg(leg_index))?;
+                    steps.extend(leg_steps);
+                }
+            }
+
+            waypoints.push(Waypoint {
+                coordinate: place_coordinate(&last_leg.to),
+                kind: WaypointKind::Break,
+            });
+
+            validate_geometry(&geometry, index)?;
+            let linestring: LineString = geometry.iter().map(|coord| Point::from(*coord)).collect();
+            let Some(bbox) = linestring.bounding_rect() else {
+                return Err(RoutingResponseParseError::EmptyGeometry { index });
+            };
+            routes.push(Route {
+                geometry,
+                bbox: bbox.into(),
+                distance,
+                duration: itinerary
+                    .duration
+                    .unwrap_or_else(|| steps.iter().map(|step| step.duration).sum()),
+                weight: None,
+                weight_name: None,
+                waypoints,
+                steps,
+                elevation_profile: None,
+                incidents: vec![],
+                charging_stops: vec![],
+                destination_details: None,
+                notices: vec![],
+                planned_at: Some(planned_at),
+                extras: HashMap::new(),
+            });
+        }
+
+        if routes.is_empty() {
+            return Err(RoutingResponseParseError::NoRoutes);
+        }
+
+        Ok(routes)
+    }
+}
+
+/// Maps an OTP planner error code.
+fn backend_error(code: Option<&str>) -> RoutingBackendError {
+    match code {
+        Some("PATH_NOT_FOUND" | "NO_TRANSIT_TIMES") => RoutingBackendError::NoRoute,
+        Some("LOCATION_NOT_ACCESSIBLE" | "OUTSIDE_BOUNDS") => RoutingBackendError::NoSegment,
+        Some(code) => RoutingBackendError::Other {
+            code: code.to_string(),
+        },
+        None => RoutingBackendError::Other {
+            code: "UNKNOWN".to_string(),
+        },
+    }
+}
+
+fn place_coordinate(place: &Place) -> GeographicCoordinate {
+    GeographicCoordinate {
+        lat: place.lat,
+        lng: place.lon,
+    }
+}
+
+fn timestamp(millis: Option<u64>) -> Option<SystemTime> {
+    // Timestamps beyond what the platform can represent are discarded
+    millis.and_then(|millis| SystemTime::UNIX_EPOCH.checked_add(Duration::from_millis(millis)))
+}
+
+fn decode_leg_geometry(
+    leg: &Leg,
+    index: ResponseIndex,
+) -> Result<Vec<GeographicCoordinate>, RoutingResponseParseError> {
+    let linestring = decode_polyline(&leg.geometry.points, OTP_POLYLINE_PRECISION)
+        .map_err(|error| RoutingResponseParseError::GeometryDecodeError { index, error })?;
+    Ok(linestring
+        .coords()
+        .map(|coord| GeographicCoordinate::from(*coord))
+        .collect())
+}
+
+fn transit_mode(mode: &str) -> TransitMode {
+    match mode {
+        "TRAM" => TransitMode::Tram,
+        "SUBWAY" => TransitMode::Subway,
+        "RAIL" => TransitMode::Rail,
+        "BUS" => TransitMode::Bus,
+        "FERRY" => TransitMode::Ferry,
+        "CABLE_CAR" => TransitMode::CableCar,
+        "GONDOLA" => TransitMode::Gondola,
+        "FUNICULAR" => TransitMode::Funicular,
+        _ => TransitMode::Other,
+    }
+}
+
+fn transit_stop(place: &Place) -> TransitStop {
+    TransitStop {
+        name: place.name.clone(),
+        coordinate: place_coordinate(place),
+        arrival_time: timestamp(place.arrival),
+        departure_time: timestamp(place.departure),
+    }
+}
+
+fn transit_step(leg: &Leg, geometry: Vec<GeographicCoordinate>) -> RouteStep {
+    let line_name = leg
+        .route_short_name
+        .clone()
+        .or_else(|| leg.route_long_name.clone());
+    let stops = std::iter::once(&leg.from)
+        .chain(leg.intermediate_stops.iter())
+        .chain(std::iter::once(&leg.to))
+        .map(transit_stop)
+        .collect();
+
+    let line = line_name.as_deref().unwrap_or(&leg.mode);
+    let towards = leg
+        .headsign
+        .as_ref()
+        .map(|headsign| format!(" towards {headsign}"))
+        .unwrap_or_default();
+    let get_off_at = leg
+        .to
+        .name
+        .as_ref()
+        .map(|stop_name| format!(" and get off at {stop_name}"))
+        .unwrap_or_default();
+    let instruction = format!("Take the {line}{towards}{get_off_at}");
+
+    let mode = transit_mode(&leg.mode);
+    RouteStep {
+        geometry,
+        distance: leg.distance,
+        duration: leg.get_duration(),
+        road_name: None,
+        instruction,
+        visual_instructions: vec![],
+        spoken_instructions: vec![],
+        transit_leg: Some(TransitLeg {
+            mode,
+            line_name,
+            headsign: leg.headsign.clone(),
+            departure_time: timestamp(leg.start_time),
+            arrival_time: timestamp(leg.end_time),
+            stops,
+        }),
+        has_tolls: false,
+        is_ferry: mode == TransitMode::Ferry,
+        tunnels: vec![],
+        country_changes: vec![],
+        speed_limit_sign: None,
+        speed_limit_unit: None,
+        driving_side: None,
+        intersections: vec![],
+        junction_views: vec![],
+        has_crosswalk: false,
+        has_stairs: false,
+        has_elevator: false,
+        shields: vec![],
+        extras: HashMap::new(),
+    }
+}
+
+/// Synthesizes an instruction for an OTP walk step.
+fn walk_step_instruction(step: &WalkStep) -> String {
+    let street_name = step.street_name.as_deref().filter(|_| !step.bogus_name);
+    let action = match step.relative_direction.as_deref() {
+        Some("DEPART") => "Head",
+        Some("HARD_LEFT") => "Turn sharp left",
+        Some("LEFT") => "Turn left",
+        Some("SLIGHTLY_LEFT") => "Bear left",
+        Some("HARD_RIGHT") => "Turn sharp right",
+        Some("RIGHT") => "Turn right",
+        Some("SLIGHTLY_RIGHT") => "Bear right",
+        Some("UTURN_LEFT" | "UTURN_RIGHT") => "Make a U-turn",
+        Some("CIRCLE_CLOCKWISE" | "CIRCLE_COUNTERCLOCKWISE") => "Enter the roundabout",
+        Some("ELEVATOR") => return "Take the elevator".to_string(),
+        Some("ENTER_STATION") => return "Enter the station".to_string(),
+        Some("EXIT_STATION") => return "Exit the station".to_string(),
+        _ => "Continue",
+    };
+
+    match street_name {
+        Some(name) if action == "Head" => format!("Head along {name}"),
+        Some(name) => format!("{action} onto {name}"),
+        None => action.to_string(),
+    }
+}
+
+/// Splits a street leg into steps at the starting point of each OTP walk step.
+fn street_steps(leg: &Leg, geometry: &[GeographicCoordinate]) -> Vec<RouteStep> {
+    if leg.steps.is_empty() {
+        return vec![RouteStep {
+            geometry: geometry.to_vec(),
+            distance: leg.distance,
+            duration: leg.get_duration(),
+            road_name: None,
+            instruction: leg.to.name.as_ref().map_or_else(
+                || "Continue".to_string(),
+                |name| format!("Continue to {name}"),
+            ),
+            visual_instructions: vec![],
+            spoken_instructions: vec![],
+            transit_leg: None,
+            has_tolls: false,
+            is_ferry: false,
+            tunnels: vec![],
+            country_changes: vec![],
+            speed_limit_sign: None,
+            speed_limit_unit: None,
+            driving_side: None,
+            intersections: vec![],
+            junction_views: vec![],
+            has_crosswalk: false,
+            has_stairs: false,
+            has_elevator: false,
+            shields: vec![],
+            extras: HashMap::new(),
+        }];
+    }
+
+    // Find where each step starts in the leg geometry.
+    // The search only moves forward, since steps are ordered.
+    let mut start_indices = vec![];
+    let mut search_from = 0;
+    for step in &leg.steps {
+        let step_start = Point::new(step.lon, step.lat);
+        let index = geometry
+            .iter()
+            .enumerate()
+            .skip(search_from)
+            .min_by(|(_, a), (_, b)| {
+                Point::from(**a)
+                    .haversine_distance(&step_start)
+                    .total_cmp(&Point::from(**b).haversine_distance(&step_start))
+            })
+            .map_or(search_from, |(index, _)| index);
+        start_indices.push(index);
+        search_from = index;
+    }
+
+    let leg_duration = leg.get_duration();
+    leg.steps
+        .iter()
+        .enumerate()
+        .map(|(i, step)| {
+            let last_index = geometry.len().saturating_sub(1);
+            let start = start_indices[i];
+            // A step needs at least two points to form a line
+            let end = start_indices
+                .get(i + 1)
+                .copied()
+                .unwrap_or(last_index)
+                .max(start + 1)
+                .min(last_index);
+            let step_geometry = geometry
+                .get(start..=end)
+                .map_or_else(Vec::new, <[GeographicCoordinate]>::to_vec);
+
+            RouteStep {
+                geometry: step_geometry,
+                distance: step.distance,
+                // OTP does not report durations for individual steps, so we apportion by distance
+                duration: if leg.distance > 0.0 {
+                    leg_duration * step.distance / leg.distance
+                } else {
+                    0.0
+                },
+                road_name: step.street_name.clone().filter(|_| !step.bogus_name),
+                instruction: walk_step_instruction(step),
+                visual_instructions: vec![],
+                spoken_instructions: vec![],
+                transit_leg: None,
+                has_tolls: false,
+                is_ferry: false,
+                tunnels: vec![],
+                country_changes: vec![],
+                speed_limit_sign: None,
+                speed_limit_unit: None,
+                driving_side: None,
+                intersections: vec![],
+                junction_views: vec![],
+                has_crosswalk: false,
+                has_stairs: false,
+                has_elevator: false,
+                shields: vec![],
+                extras: HashMap::new(),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+
+    const OTP_RESPONSE: &str = r#"{"plan":{"date":1700000000000,"from":{"name":"Origin","lat":45.52,"lon":-122.68},"to":{"name":"Destination","lat":45.5262,"lon":-122.6765},"itineraries":[{"duration":900,"startTime":1700000000000,"endTime":1700000900000,"legs":[{"mode":"WALK","transitLeg":false,"distance":134.0,"duration":120,"startTime":1700000000000,"endTime":1700000120000,"from":{"name":"Origin","lat":45.52,"lon":-122.68},"to":{"name":"SW 5th & Oak","lat":45.5205,"lon":-122.679,"arrival":1700000120000,"departure":1700000180000},"legGeometry":{"points":"_sytG~|wkVcB??gE","length":3},"steps":[{"distance":56.0,"relativeDirection":"DEPART","streetName":"SW 5th Ave","bogusName":false,"lat":45.52,"lon":-122.68},{"distance":78.0,"relativeDirection":"RIGHT","streetName":"path","bogusName":true,"lat":45.5205,"lon":-122.68}]},{"mode":"BUS","transitLeg":true,"distance":690.0,"duration":600,"startTime":1700000180000,"endTime":1700000780000,"routeShortName":"20","routeLongName":"Burnside/Stark","headsign":"Gresham TC","from":{"name":"SW 5th & Oak","lat":45.5205,"lon":-122.679,"departure":1700000180000},"to":{"name":"NE Sandy & 12th","lat":45.526,"lon":-122.677,"arrival":1700000780000},"intermediateStops":[{"name":"W Burnside & 4th","lat":45.523,"lon":-122.679,"arrival":1700000400000,"departure":1700000400000}],"legGeometry":{"points":"cvytGvvwkVsN?wQoK","length":3},"steps":[]},{"mode":"WALK","transitLeg":false,"distance":44.0,"duration":60,"startTime":1700000780000,"endTime":1700000840000,"from":{"name":"NE Sandy & 12th","lat":45.526,"lon":-122.677},"to":{"name":"Destination","lat":45.5262,"lon":-122.6765},"legGeometry":{"points":"oxztGfjwkVg@cB","length":2},"steps":[]}]}]}}"#;
+
+    #[test]
+    fn parse_multimodal_itinerary() {
+        let clock = MockClock::new(SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000));
+        let parser = OtpResponseParser::new().with_clock(Arc::new(clock));
+        let routes = parser
+            .parse_response(OTP_RESPONSE.into())
+            .expect("Unable to parse OTP response");
+        insta::assert_yaml_snapshot!(routes);
+    }
+
+    #[test]
+    fn parse_planner_error() {
+        let parser = OtpResponseParser::new();
+        let result = parser.parse_response(
+            r#"{"error":{"id":404,"msg":"No trip found.","message":"PATH_NOT_FOUND"}}"#.into(),
+        );
+        assert!(matches!(
+            result,
+            Err(RoutingResponseParseError::BackendError {
+                error: RoutingBackendError::NoRoute,
+                message: Some(message),
+            }) if message == "No trip found."
+        ));
+    }
+}
@@ -0,0 +1,153 @@
+//! [OpenTripPlanner](https://www.opentripplanner.org/) models from the REST plan API: <http://dev.opentripplanner.org/apidoc/1.5.0/json_Response.html>
+//!
+//! OTP 2.x continues to serve the same structure from its legacy REST API.
+//! We omit most fields which are not needed for navigation.
+
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug)]
+pub struct PlanResponse {
+    /// The trip plan; absent when an error occurred.
+    pub plan: Option<TripPlan>,
+    pub error: Option<PlannerError>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct PlannerError {
+    /// A machine-readable error code (ex: `PATH_NOT_FOUND`).
+    pub message: Option<String>,
+    /// A human-readable error message.
+    pub msg: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct TripPlan {
+    #[serde(default)]
+    pub itineraries: Vec<Itinerary>,
+}
+
+/// A single way of getting from the origin to the destination.
+#[derive(Deserialize, Debug)]
+pub struct Itinerary {
+    /// The duration of the itinerary (including any waiting time), in seconds.
+    pub duration: Option<f64>,
+    pub legs: Vec<Leg>,
+}
+
+/// A portion of an itinerary using a single mode of travel.
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Leg {
+    /// The mode of travel (ex: `WALK`, `BUS`, or `SUBWAY`).
+    pub mode: String,
+    /// Whether this leg uses public transit.
+    #[serde(default, rename = "transitLeg")]
+    pub is_transit: bool,
+    /// The distance traveled, in meters.
+    pub distance: f64,
+    /// The duration of the leg, in seconds.
+    pub duration: Option<f64>,
+    /// The scheduled departure time, in milliseconds since the Unix epoch.
+    pub start_time: Option<u64>,
+    /// The scheduled arrival time, in milliseconds since the Unix epoch.
+    pub end_time: Option<u64>,
+    pub route_short_name: Option<String>,
+    pub route_long_name: Option<String>,
+    pub headsign: Option<String>,
+    pub from: Place,
+    pub to: Place,
+    #[serde(default)]
+    pub intermediate_stops: Vec<Place>,
+    #[serde(rename = "legGeometry")]
+    pub geometry: EncodedPolyline,
+    /// Turn-by-turn directions (only present for street legs).
+    #[serde(default)]
+    pub steps: Vec<WalkStep>,
+}
+
+impl Leg {
+    /// The duration of the leg, in seconds.
+    ///
+    /// Older versions of OTP omit the duration, in which case it is computed from the timestamps.
+    pub fn get_duration(&self) -> f64 {
+        self.duration
+            .unwrap_or_else(|| match (self.start_time, self.end_time) {
+                (Some(start), Some(end)) => end.saturating_sub(start) as f64 / 1000.0,
+                _ => 0.0,
+            })
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Place {
+    pub name: Option<String>,
+    pub lat: f64,
+    pub lon: f64,
+    /// The arrival time, in milliseconds since the Unix epoch.
+    pub arrival: Option<u64>,
+    /// The departure time, in milliseconds since the Unix epoch.
+    pub departure: Option<u64>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct EncodedPolyline {
+    /// A polyline with a precision of 5 digits.
+    pub points: String,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct WalkStep {
+    /// The distance of this step, in meters.
+    pub distance: f64,
+    /// The direction relative to the previous step (ex: `DEPART`, `LEFT`, or `CONTINUE`).
+    pub relative_direction: Option<String>,
+    pub street_name: Option<String>,
+    /// Whether the street name is generated (ex: "path" or "sidewalk")
+    /// rather than from the map data.
+    #[serde(default)]
+    pub bogus_name: bool,
+    /// The latitude at which the step starts.
+    pub lat: f64,
+    /// The longitude at which the step starts.
+    pub lon: f64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deserialize_error() {
+        let data = r#"{
+            "requestParameters": {},
+            "error": {
+                "id": 404,
+                "msg": "No trip found. There may be no transit service within the maximum specified distance or at the specified time, or your start or end point might not be safely accessible.",
+                "message": "PATH_NOT_FOUND",
+                "noPath": true
+            }
+        }"#;
+
+        let response: PlanResponse = serde_json::from_str(data).expect("Failed to parse response");
+        assert!(response.plan.is_none());
+        let error = response.error.expect("Expected an error");
+        assert_eq!(error.message.as_deref(), Some("PATH_NOT_FOUND"));
+    }
+
+    #[test]
+    fn leg_duration_fallback() {
+        let data = r#"{
+            "mode": "BUS",
+            "distance": 1000,
+            "startTime": 1700000000000,
+            "endTime": 1700000300000,
+            "from": {"lat": 0, "lon": 0},
+            "to": {"lat": 0, "lon": 0},
+            "legGeometry": {"points": ""}
+        }"#;
+
+        let leg: Leg = serde_json::from_str(data).expect("Failed to parse leg");
+        assert_eq!(leg.get_duration(), 300.0);
+    }
+}
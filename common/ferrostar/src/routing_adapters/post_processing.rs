@@ -0,0 +1,341 @@
+//! Processors which transform routes after they are parsed
+//! (ex: rewriting instructions, merging steps, or filtering out private roads).
+//!
+//! Processors are configured on a [`super::RouteAdapter`] (see [`super::RouteAdapter::new_with_processing`]),
+//! so that every platform gets the same processed routes.
+
+use crate::models::{ManeuverType, Route, RouteStep};
+use std::sync::Arc;
+
+/// Transforms a route after it has been parsed.
+#[uniffi::export(with_foreign)]
+pub trait RoutePostProcessor: Send + Sync {
+    /// Returns the route which should be used in place of `route`.
+    fn process_route(&self, route: Route) -> Route;
+}
+
+/// Runs each route through the processors, in order.
+pub(crate) fn process_routes(
+    processors: &[Arc<dyn RoutePostProcessor>],
+    routes: Vec<Route>,
+) -> Vec<Route> {
+    routes
+        .into_iter()
+        .map(|route| {
+            processors
+                .iter()
+                .fold(route, |route, processor| processor.process_route(route))
+        })
+        .collect()
+}
+
+/// A replacement of text in instructions.
+#[derive(Debug, Clone, PartialEq, uniffi::Record)]
+pub struct InstructionReplacement {
+    /// The text to replace (case-sensitive).
+    pub text: String,
+    /// The text to replace it with.
+    pub replacement: String,
+}
+
+/// Rewrites the text of the instructions of each step
+/// (ex: to expand abbreviations like `St` for speech).
+///
+/// Replacements are applied in order to the step instructions,
+/// the text of visual instructions, and the text (and SSML) of spoken instructions.
+/// Take care that replacements in SSML do not alter its markup.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InstructionRewriter {
+    replacements: Vec<InstructionReplacement>,
+}
+
+impl InstructionRewriter {
+    pub fn new(replacements: Vec<InstructionReplacement>) -> Self {
+        Self { replacements }
+    }
+
+    fn rewrite(&self, text: &mut String) {
+        for InstructionReplacement {
+            text: from,
+            replacement,
+        } in &self.replacements
+        {
+            if !from.is_empty() && text.contains(from.as_str()) {
+                *text = text.replace(from.as_str(), replacement);
+            }
+        }
+    }
+}
+
+impl RoutePostProcessor for InstructionRewriter {
+    fn process_route(&self, mut route: Route) -> Route {
+        for step in &mut route.steps {
+            self.rewrite(&mut step.instruction);
+            for visual in &mut step.visual_instructions {
+                self.rewrite(&mut visual.primary_content.text);
+                if let Some(secondary) = &mut visual.secondary_content {
+                    self.rewrite(&mut secondary.text);
+                }
+            }
+            for spoken in &mut step.spoken_instructions {
+                self.rewrite(&mut spoken.text);
+                if let Some(ssml) = &mut spoken.ssml {
+                    self.rewrite(ssml);
+                }
+            }
+        }
+        route
+    }
+}
+
+/// Merges short steps which only continue along the way (or onto a way with a new name)
+/// into the preceding step, to avoid a flurry of trivial prompts
+/// (ex: on pedestrian routes along many short walkways).
+///
+/// The maneuver of each step is determined from the visual instructions of the preceding step,
+/// so steps are only merged when the route has visual instructions.
+/// The first and last steps and transit steps are never merged.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContinueStepMerger {
+    /// The maximum length of a step which will be merged, in meters.
+    max_distance: f64,
+}
+
+impl ContinueStepMerger {
+    pub fn new(max_distance: f64) -> Self {
+        Self { max_distance }
+    }
+
+    /// Whether a step should be merged into the preceding one.
+    fn should_merge(&self, previous: &RouteStep, step: &RouteStep) -> bool {
+        let maneuver_type = previous
+            .visual_instructions
+            .first()
+            .and_then(|instruction| instruction.primary_content.maneuver_type);
+        step.distance < self.max_distance
+            && matches!(
+                maneuver_type,
+                Some(ManeuverType::Continue | ManeuverType::NewName)
+            )
+            && previous.transit_leg.is_none()
+            && step.transit_leg.is_none()
+    }
+}
+
+/// Merges a step into the preceding one, removing the maneuver between them.
+///
+/// The merged step begins with the maneuver of the preceding step,
+/// and ends with the instructions for the maneuver following the step.
+fn merge_steps(previous: &mut RouteStep, step: RouteStep) {
+    // Anything located relative to the end of the preceding step is now further from the end
+    for tunnel in &mut previous.tunnels {
+        tunnel.entrance_distance_before_maneuver += step.distance;
+        tunnel.exit_distance_before_maneuver += step.distance;
+    }
+    for change in &mut previous.country_changes {
+        change.distance_before_maneuver += step.distance;
+    }
+
+    previous.geometry.extend(step.geometry.into_iter().skip(1));
+    previous.distance += step.distance;
+    previous.duration += step.duration;
+    // The instructions and junction views of the preceding step were for the removed maneuver
+    previous.visual_instructions = step.visual_instructions;
+    if let Some(first) = previous.visual_instructions.first_mut() {
+        // Show the next maneuver for the whole merged step
+        first.trigger_distance_before_maneuver = first
+            .trigger_distance_before_maneuver
+            .max(previous.distance);
+    }
+    previous.spoken_instructions = step.spoken_instructions;
+    previous.junction_views = step.junction_views;
+    previous.has_tolls |= step.has_tolls;
+    previous.is_ferry |= step.is_ferry;
+    previous.has_crosswalk |= step.has_crosswalk;
+    previous.has_stairs |= step.has_stairs;
+    previous.has_elevator |= step.has_elevator;
+    previous.tunnels.extend(step.tunnels);
+    previous.country_changes.extend(step.country_changes);
+    previous.intersections.extend(step.intersections);
+    for shield in step.shields {
+        if !previous.shields.contains(&shield) {
+            previous.shields.push(shield);
+        }
+    }
+}
+
+impl RoutePostProcessor for ContinueStepMerger {
+    fn process_route(&self, mut route: Route) -> Route {
+        let last_index = route.steps.len().saturating_sub(1);
+        let mut steps: Vec<RouteStep> = Vec::with_capacity(route.steps.len());
+        for (index, step) in route.steps.into_iter().enumerate() {
+            match steps.last_mut() {
+                Some(previous) if index != last_index && self.should_merge(previous, &step) => {
+                    merge_steps(previous, step);
+                }
+                _ => steps.push(step),
+            }
+        }
+        route.steps = steps;
+        route
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{SpokenInstruction, VisualInstruction, VisualInstructionContent};
+    use crate::navigation_controller::test_helpers::{gen_dummy_route_step, gen_route_from_steps};
+    use uuid::Uuid;
+
+    fn route() -> Route {
+        let mut step = gen_dummy_route_step(0.0, 0.0, 0.001, 0.0);
+        step.instruction = "Turn left onto Main St".to_string();
+        step.visual_instructions = vec![VisualInstruction {
+            primary_content: VisualInstructionContent {
+                text: "Main St".to_string(),
+                maneuver_type: None,
+                maneuver_modifier: None,
+                roundabout_exit_degrees: None,
+                exit_numbers: vec![],
+                destination_refs: vec![],
+                destinations: vec![],
+            },
+            secondary_content: None,
+            trigger_distance_before_maneuver: 100.0,
+        }];
+        step.spoken_instructions = vec![SpokenInstruction {
+            text: "Turn left onto Main St".to_string(),
+            ssml: Some("<speak>Turn left onto Main St</speak>".to_string()),
+            trigger_distance_before_maneuver: 100.0,
+            utterance_id: Uuid::nil(),
+        }];
+        gen_route_from_steps(vec![step])
+    }
+
+    struct StepCounter;
+
+    impl RoutePostProcessor for StepCounter {
+        fn process_route(&self, mut route: Route) -> Route {
+            let count = route.steps.len();
+            for step in &mut route.steps {
+                step.instruction = format!("{} ({count})", step.instruction);
+            }
+            route
+        }
+    }
+
+    #[test]
+    fn rewrites_instructions() {
+        let rewriter = InstructionRewriter::new(vec![InstructionReplacement {
+            text: " St".to_string(),
+            replacement: " Street".to_string(),
+        }]);
+
+        let route = rewriter.process_route(route());
+
+        let step = &route.steps[0];
+        assert_eq!(step.instruction, "Turn left onto Main Street");
+        assert_eq!(
+            step.visual_instructions[0].primary_content.text,
+            "Main Street"
+        );
+        assert_eq!(
+            step.spoken_instructions[0].ssml.as_deref(),
+            Some("<speak>Turn left onto Main Street</speak>")
+        );
+    }
+
+    fn step_with_banner(
+        distance: f64,
+        instruction: &str,
+        next_maneuver_type: ManeuverType,
+        next_instruction: &str,
+    ) -> RouteStep {
+        let mut step = gen_dummy_route_step(0.0, 0.0, 0.001, 0.0);
+        step.distance = distance;
+        step.duration = distance;
+        step.instruction = instruction.to_string();
+        step.visual_instructions = vec![VisualInstruction {
+            primary_content: VisualInstructionContent {
+                text: next_instruction.to_string(),
+                maneuver_type: Some(next_maneuver_type),
+                maneuver_modifier: None,
+                roundabout_exit_degrees: None,
+                exit_numbers: vec![],
+                destination_refs: vec![],
+                destinations: vec![],
+            },
+            secondary_content: None,
+            trigger_distance_before_maneuver: distance,
+        }];
+        step
+    }
+
+    #[test]
+    fn merges_short_continue_steps() {
+        let route = gen_route_from_steps(vec![
+            step_with_banner(100.0, "Walk west", ManeuverType::NewName, "Continue"),
+            step_with_banner(7.0, "Continue", ManeuverType::Continue, "Continue"),
+            step_with_banner(5.0, "Continue", ManeuverType::Turn, "Turn right"),
+            step_with_banner(50.0, "Turn right", ManeuverType::NewName, "Continue"),
+            step_with_banner(70.0, "Continue", ManeuverType::Arrive, "Arrive"),
+            step_with_banner(0.0, "Arrive", ManeuverType::Arrive, "Arrive"),
+        ]);
+
+        let route = ContinueStepMerger::new(20.0).process_route(route);
+
+        let instructions: Vec<_> = route
+            .steps
+            .iter()
+            .map(|step| step.instruction.as_str())
+            .collect();
+        // The long continue step is kept, as is the final step
+        assert_eq!(
+            instructions,
+            vec!["Walk west", "Turn right", "Continue", "Arrive"]
+        );
+        let merged = &route.steps[0];
+        assert!((merged.distance - 112.0).abs() < f64::EPSILON);
+        assert_eq!(merged.geometry.len(), 4);
+        assert_eq!(
+            merged.visual_instructions[0].primary_content.text,
+            "Turn right"
+        );
+        assert!(
+            (merged.visual_instructions[0].trigger_distance_before_maneuver - 112.0).abs()
+                < f64::EPSILON
+        );
+    }
+
+    #[test]
+    fn keeps_steps_without_visual_instructions() {
+        let mut first = gen_dummy_route_step(0.0, 0.0, 0.001, 0.0);
+        first.distance = 100.0;
+        let mut second = gen_dummy_route_step(0.001, 0.0, 0.002, 0.0);
+        second.distance = 5.0;
+        let route = gen_route_from_steps(vec![first, second.clone(), second]);
+
+        let route = ContinueStepMerger::new(20.0).process_route(route);
+
+        assert_eq!(route.steps.len(), 3);
+    }
+
+    #[test]
+    fn processors_run_in_order() {
+        let processors: Vec<Arc<dyn RoutePostProcessor>> = vec![
+            Arc::new(InstructionRewriter::new(vec![InstructionReplacement {
+                text: "Main St".to_string(),
+                replacement: "Main Street".to_string(),
+            }])),
+            Arc::new(StepCounter),
+        ];
+
+        let routes = process_routes(&processors, vec![route()]);
+
+        assert_eq!(
+            routes[0].steps[0].instruction,
+            "Turn left onto Main Street (1)"
+        );
+    }
+}
@@ -0,0 +1,1019 @@
+//! A compact Protocol Buffers exchange format for routes.
+//!
+//! This is intended for routes which are computed ahead of time (ex: by a fleet backend)
+//! and shipped to devices. The schema is in `route.proto` alongside this module.
+//! Compared to OSRM JSON, geometries are only included once (as polyline6 strings),
+//! and enums and numbers use a binary encoding.
+
+pub(crate) mod models;
+
+use super::RouteResponseParser;
+use crate::algorithms::decode_polyline;
+use crate::elevation::{ElevationProfile, ElevationSample};
+use crate::models::{
+    BoundingBox, ChargingStop, ConnectorType, CountryChange, DestinationDetails, DrivingSide,
+    GeographicCoordinate, Incident, IncidentKind, Intersection, JunctionView, ManeuverModifier,
+    ManeuverType, ModelError, ParkingSuggestion, RoadShield, RouteNotice, RouteStep,
+    SpeedLimitSign, SpeedUnit, SpokenInstruction, TransitLeg, TransitMode, TransitStop, Tunnel,
+    VisualInstruction, VisualInstructionContent, Waypoint, WaypointKind,
+};
+use crate::routing_adapters::{
+    error::ResponseIndex, validation::validate_geometry, Route, RoutingResponseParseError,
+};
+use geo::Coord;
+use polyline::encode_coordinates;
+use prost::Message;
+use std::time::{Duration, SystemTime};
+use uuid::Uuid;
+
+/// Geometries are always encoded as polyline6.
+const POLYLINE_PRECISION: u32 = 6;
+
+/// A response parser for routes encoded with [`encode_routes_protobuf`].
+#[derive(Debug, Default)]
+pub struct ProtobufResponseParser;
+
+impl ProtobufResponseParser {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl RouteResponseParser for ProtobufResponseParser {
+    fn parse_response(&self, response: Vec<u8>) -> Result<Vec<Route>, RoutingResponseParseError> {
+        let collection = models::RouteCollection::decode(response.as_slice())?;
+        if collection.routes.is_empty() {
+            return Err(RoutingResponseParseError::NoRoutes);
+        }
+
+        collection
+            .routes
+            .into_iter()
+            .enumerate()
+            .map(|(route_index, route)| decode_route(route, ResponseIndex::route(route_index)))
+            .collect()
+    }
+}
+
+/// Encodes routes in the Protocol Buffers exchange format.
+///
+/// The result can be parsed with a [`ProtobufResponseParser`].
+/// Note that coordinates are rounded to six decimal places.
+#[uniffi::export]
+pub fn encode_routes_protobuf(routes: &[Route]) -> Result<Vec<u8>, ModelError> {
+    let collection = models::RouteCollection {
+        routes: routes.iter().map(encode_route).collect::<Result<_, _>>()?,
+    };
+    Ok(collection.encode_to_vec())
+}
+
+//
+// Encoding
+//
+
+fn encode_route(route: &Route) -> Result<models::Route, ModelError> {
+    Ok(models::Route {
+        geometry: encode_geometry(&route.geometry)?,
+        bbox: Some(models::BoundingBox {
+            sw: Some(encode_coordinate(route.bbox.sw)),
+            ne: Some(encode_coordinate(route.bbox.ne)),
+        }),
+        distance: route.distance,
+        duration: route.duration,
+        weight: route.weight,
+        weight_name: route.weight_name.clone(),
+        waypoints: route
+            .waypoints
+            .iter()
+            .map(|waypoint| models::Waypoint {
+                coordinate: Some(encode_coordinate(waypoint.coordinate)),
+                kind: encode_waypoint_kind(waypoint.kind),
+            })
+            .collect(),
+        steps: route
+            .steps
+            .iter()
+            .map(encode_step)
+            .collect::<Result<_, _>>()?,
+        elevation_profile: route.elevation_profile.as_ref().map(|profile| {
+            models::ElevationProfile {
+                samples: profile
+                    .samples
+                    .iter()
+                    .map(|sample| models::ElevationSample {
+                        distance_along_route: sample.distance_along_route,
+                        elevation: sample.elevation,
+                    })
+                    .collect(),
+            }
+        }),
+        incidents: route
+            .incidents
+            .iter()
+            .map(|incident| models::Incident {
+                id: incident.id.clone(),
+                kind: encode_incident_kind(incident.kind),
+                description: incident.description.clone(),
+                is_closed: incident.is_closed,
+                start_distance_along_route: incident.start_distance_along_route,
+                end_distance_along_route: incident.end_distance_along_route,
+            })
+            .collect(),
+        charging_stops: route
+            .charging_stops
+            .iter()
+            .map(|stop| {
+                let (connector_type, connector_name) =
+                    stop.connector_type
+                        .as_ref()
+                        .map_or((None, None), |connector| {
+                            let (value, name) = encode_connector_type(connector);
+                            (Some(value), name)
+                        });
+                models::ChargingStop {
+                    coordinate: Some(encode_coordinate(stop.coordinate)),
+                    name: stop.name.clone(),
+                    station_id: stop.station_id.clone(),
+                    charge_at_arrival: stop.charge_at_arrival,
+                    charge_to: stop.charge_to,
+                    charge_time: stop.charge_time,
+                    power: stop.power,
+                    connector_type,
+                    connector_name,
+                }
+            })
+            .collect(),
+        destination_details: route.destination_details.as_ref().map(|details| {
+            models::DestinationDetails {
+                entrances: details
+                    .entrances
+                    .iter()
+                    .map(|entrance| encode_coordinate(*entrance))
+                    .collect(),
+                parking: details
+                    .parking
+                    .iter()
+                    .map(|parking| models::ParkingSuggestion {
+                        coordinate: Some(encode_coordinate(parking.coordinate)),
+                        name: parking.name.clone(),
+                    })
+                    .collect(),
+            }
+        }),
+        notices: route
+            .notices
+            .iter()
+            .map(|notice| models::RouteNotice {
+                code: notice.code,
+                message: notice.message.clone(),
+                kind: notice.kind.clone(),
+            })
+            .collect(),
+        extras: route.extras.clone(),
+        planned_at: route.planned_at.and_then(encode_timestamp),
+    })
+}
+
+fn encode_step(step: &RouteStep) -> Result<models::RouteStep, ModelError> {
+    Ok(models::RouteStep {
+        geometry: encode_geometry(&step.geometry)?,
+        distance: step.distance,
+        duration: step.duration,
+        road_name: step.road_name.clone(),
+        instruction: step.instruction.clone(),
+        visual_instructions: step
+            .visual_instructions
+            .iter()
+            .map(|instruction| models::VisualInstruction {
+                primary_content: Some(encode_visual_content(&instruction.primary_content)),
+                secondary_content: instruction
+                    .secondary_content
+                    .as_ref()
+                    .map(encode_visual_content),
+                trigger_distance_before_maneuver: instruction.trigger_distance_before_maneuver,
+            })
+            .collect(),
+        spoken_instructions: step
+            .spoken_instructions
+            .iter()
+            .map(|instruction| models::SpokenInstruction {
+                text: instruction.text.clone(),
+                ssml: instruction.ssml.clone(),
+                trigger_distance_before_maneuver: instruction.trigger_distance_before_maneuver,
+                utterance_id: instruction.utterance_id.as_bytes().to_vec(),
+            })
+            .collect(),
+        transit_leg: step.transit_leg.as_ref().map(|leg| models::TransitLeg {
+            mode: encode_transit_mode(leg.mode),
+            line_name: leg.line_name.clone(),
+            headsign: leg.headsign.clone(),
+            departure_time: leg.departure_time.and_then(encode_timestamp),
+            arrival_time: leg.arrival_time.and_then(encode_timestamp),
+            stops: leg
+                .stops
+                .iter()
+                .map(|stop| models::TransitStop {
+                    name: stop.name.clone(),
+                    coordinate: Some(encode_coordinate(stop.coordinate)),
+                    arrival_time: stop.arrival_time.and_then(encode_timestamp),
+                    departure_time: stop.departure_time.and_then(encode_timestamp),
+                })
+                .collect(),
+        }),
+        has_tolls: step.has_tolls,
+        is_ferry: step.is_ferry,
+        tunnels: step
+            .tunnels
+            .iter()
+            .map(|tunnel| models::Tunnel {
+                name: tunnel.name.clone(),
+                entrance_distance_before_maneuver: tunnel.entrance_distance_before_maneuver,
+                exit_distance_before_maneuver: tunnel.exit_distance_before_maneuver,
+            })
+            .collect(),
+        shields: step
+            .shields
+            .iter()
+            .map(|shield| models::RoadShield {
+                network: shield.network.clone(),
+                number: shield.number.clone(),
+            })
+            .collect(),
+        country_changes: step
+            .country_changes
+            .iter()
+            .map(|change| models::CountryChange {
+                iso_3166: change.iso_3166.clone(),
+                distance_before_maneuver: change.distance_before_maneuver,
+            })
+            .collect(),
+        speed_limit_sign: step.speed_limit_sign.map_or(0, encode_speed_limit_sign),
+        speed_limit_unit: step.speed_limit_unit.map_or(0, encode_speed_unit),
+        driving_side: step.driving_side.map_or(0, encode_driving_side),
+        intersections: step
+            .intersections
+            .iter()
+            .map(|intersection| models::Intersection {
+                location: Some(encode_coordinate(intersection.location)),
+                bearings: intersection
+                    .bearings
+                    .iter()
+                    .map(|&bearing| bearing.into())
+                    .collect(),
+                entry: intersection.entry.clone(),
+                bearing_in_index: intersection.bearing_in_index,
+                bearing_out_index: intersection.bearing_out_index,
+                geometry_index: intersection.geometry_index,
+                turn_weight: intersection.turn_weight,
+                turn_duration: intersection.turn_duration,
+            })
+            .collect(),
+        has_crosswalk: step.has_crosswalk,
+        has_stairs: step.has_stairs,
+        has_elevator: step.has_elevator,
+        extras: step.extras.clone(),
+        junction_views: step
+            .junction_views
+            .iter()
+            .map(|junction_view| models::JunctionView {
+                image_reference: junction_view.image_reference.clone(),
+                kind: junction_view.kind.clone(),
+                trigger_distance_before_maneuver: junction_view.trigger_distance_before_maneuver,
+            })
+            .collect(),
+    })
+}
+
+fn encode_geometry(geometry: &[GeographicCoordinate]) -> Result<String, ModelError> {
+    encode_coordinates(geometry.iter().map(|c| Coord::from(*c)), POLYLINE_PRECISION)
+        .map_err(|error| ModelError::PolylineGenerationError { error })
+}
+
+fn encode_coordinate(coordinate: GeographicCoordinate) -> models::Coordinate {
+    models::Coordinate {
+        lat: coordinate.lat,
+        lng: coordinate.lng,
+    }
+}
+
+fn encode_visual_content(content: &VisualInstructionContent) -> models::VisualInstructionContent {
+    models::VisualInstructionContent {
+        text: content.text.clone(),
+        maneuver_type: content.maneuver_type.map_or(0, encode_maneuver_type),
+        maneuver_modifier: content
+            .maneuver_modifier
+            .map_or(0, encode_maneuver_modifier),
+        roundabout_exit_degrees: content.roundabout_exit_degrees.map(u32::from),
+        exit_numbers: content.exit_numbers.clone(),
+        destination_refs: content.destination_refs.clone(),
+        destinations: content.destinations.clone(),
+    }
+}
+
+fn encode_timestamp(time: SystemTime) -> Option<u64> {
+    let millis = time
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .ok()?
+        .as_millis();
+    u64::try_from(millis).ok()
+}
+
+//
+// Decoding
+//
+
+fn decode_route(
+    route: models::Route,
+    index: ResponseIndex,
+) -> Result<Route, RoutingResponseParseError> {
+    let geometry = decode_geometry(&route.geometry, index)?;
+    let bbox = route.bbox.ok_or_else(|| missing_field("bbox", index))?;
+
+    Ok(Route {
+        geometry,
+        bbox: BoundingBox {
+            sw: decode_coordinate(bbox.sw, index)?,
+            ne: decode_coordinate(bbox.ne, index)?,
+        },
+        distance: route.distance,
+        duration: route.duration,
+        weight: route.weight,
+        weight_name: route.weight_name,
+        waypoints: route
+            .waypoints
+            .into_iter()
+            .map(|waypoint| {
+                Ok::<_, RoutingResponseParseError>(Waypoint {
+                    coordinate: decode_coordinate(waypoint.coordinate, index)?,
+                    kind: decode_waypoint_kind(waypoint.kind),
+                })
+            })
+            .collect::<Result<_, _>>()?,
+        steps: route
+            .steps
+            .into_iter()
+            .enumerate()
+            .map(|(step_index, step)| decode_step(step, index.step(step_index)))
+            .collect::<Result<_, _>>()?,
+        elevation_profile: route.elevation_profile.map(|profile| {
+            ElevationProfile::from_samples(
+                profile
+                    .samples
+                    .into_iter()
+                    .map(|sample| ElevationSample {
+                        distance_along_route: sample.distance_along_route,
+                        elevation: sample.elevation,
+                    })
+                    .collect(),
+            )
+        }),
+        incidents: route
+            .incidents
+            .into_iter()
+            .map(|incident| Incident {
+                id: incident.id,
+                kind: decode_incident_kind(incident.kind),
+                description: incident.description,
+                is_closed: incident.is_closed,
+                start_distance_along_route: incident.start_distance_along_route,
+                end_distance_along_route: incident.end_distance_along_route,
+            })
+            .collect(),
+        charging_stops: route
+            .charging_stops
+            .into_iter()
+            .map(|stop| {
+                Ok::<_, RoutingResponseParseError>(ChargingStop {
+                    coordinate: decode_coordinate(stop.coordinate, index)?,
+                    name: stop.name,
+                    station_id: stop.station_id,
+                    charge_at_arrival: stop.charge_at_arrival,
+                    charge_to: stop.charge_to,
+                    charge_time: stop.charge_time,
+                    power: stop.power,
+                    connector_type: stop
+                        .connector_type
+                        .map(|value| decode_connector_type(value, stop.connector_name)),
+                })
+            })
+            .collect::<Result<_, _>>()?,
+        destination_details: route
+            .destination_details
+            .map(|details| {
+                Ok::<_, RoutingResponseParseError>(DestinationDetails {
+                    entrances: details
+                        .entrances
+                        .into_iter()
+                        .map(|entrance| decode_coordinate(Some(entrance), index))
+                        .collect::<Result<_, _>>()?,
+                    parking: details
+                        .parking
+                        .into_iter()
+                        .map(|parking| {
+                            Ok::<_, RoutingResponseParseError>(ParkingSuggestion {
+                                coordinate: decode_coordinate(parking.coordinate, index)?,
+                                name: parking.name,
+                            })
+                        })
+                        .collect::<Result<_, _>>()?,
+                })
+            })
+            .transpose()?,
+        notices: route
+            .notices
+            .into_iter()
+            .map(|notice| RouteNotice {
+                code: notice.code,
+                kind: notice.kind,
+                message: notice.message,
+            })
+            .collect(),
+        planned_at: route.planned_at.and_then(decode_timestamp),
+        extras: route.extras,
+    })
+}
+
+fn decode_step(
+    step: models::RouteStep,
+    index: ResponseIndex,
+) -> Result<RouteStep, RoutingResponseParseError> {
+    Ok(RouteStep {
+        geometry: decode_geometry(&step.geometry, index)?,
+        distance: step.distance,
+        duration: step.duration,
+        road_name: step.road_name,
+        instruction: step.instruction,
+        visual_instructions: step
+            .visual_instructions
+            .into_iter()
+            .map(|instruction| {
+                let primary_content = instruction
+                    .primary_content
+                    .ok_or_else(|| missing_field("primary_content", index))?;
+                Ok::<_, RoutingResponseParseError>(VisualInstruction {
+                    primary_content: decode_visual_content(primary_content),
+                    secondary_content: instruction.secondary_content.map(decode_visual_content),
+                    trigger_distance_before_maneuver: instruction.trigger_distance_before_maneuver,
+                })
+            })
+            .collect::<Result<_, _>>()?,
+        spoken_instructions: step
+            .spoken_instructions
+            .into_iter()
+            .map(|instruction| SpokenInstruction {
+                text: instruction.text,
+                ssml: instruction.ssml,
+                trigger_distance_before_maneuver: instruction.trigger_distance_before_maneuver,
+                // Generate a fresh ID if the sender didn't include one
+                utterance_id: Uuid::from_slice(&instruction.utterance_id)
+                    .unwrap_or_else(|_| Uuid::new_v4()),
+            })
+            .collect(),
+        transit_leg: step
+            .transit_leg
+            .map(|leg| {
+                Ok::<_, RoutingResponseParseError>(TransitLeg {
+                    mode: decode_transit_mode(leg.mode),
+                    line_name: leg.line_name,
+                    headsign: leg.headsign,
+                    departure_time: leg.departure_time.and_then(decode_timestamp),
+                    arrival_time: leg.arrival_time.and_then(decode_timestamp),
+                    stops: leg
+                        .stops
+                        .into_iter()
+                        .map(|stop| {
+                            Ok::<_, RoutingResponseParseError>(TransitStop {
+                                name: stop.name,
+                                coordinate: decode_coordinate(stop.coordinate, index)?,
+                                arrival_time: stop.arrival_time.and_then(decode_timestamp),
+                                departure_time: stop.departure_time.and_then(decode_timestamp),
+                            })
+                        })
+                        .collect::<Result<_, _>>()?,
+                })
+            })
+            .transpose()?,
+        has_tolls: step.has_tolls,
+        is_ferry: step.is_ferry,
+        tunnels: step
+            .tunnels
+            .into_iter()
+            .map(|tunnel| Tunnel {
+                name: tunnel.name,
+                entrance_distance_before_maneuver: tunnel.entrance_distance_before_maneuver,
+                exit_distance_before_maneuver: tunnel.exit_distance_before_maneuver,
+            })
+            .collect(),
+        shields: step
+            .shields
+            .into_iter()
+            .map(|shield| RoadShield {
+                network: shield.network,
+                number: shield.number,
+            })
+            .collect(),
+        country_changes: step
+            .country_changes
+            .into_iter()
+            .map(|change| CountryChange {
+                iso_3166: change.iso_3166,
+                distance_before_maneuver: change.distance_before_maneuver,
+            })
+            .collect(),
+        speed_limit_sign: decode_speed_limit_sign(step.speed_limit_sign),
+        speed_limit_unit: decode_speed_unit(step.speed_limit_unit),
+        driving_side: decode_driving_side(step.driving_side),
+        intersections: step
+            .intersections
+            .into_iter()
+            .map(|intersection| {
+                Ok::<_, RoutingResponseParseError>(Intersection {
+                    location: decode_coordinate(intersection.location, index)?,
+                    bearings: intersection
+                        .bearings
+                        .into_iter()
+                        .map(|bearing| {
+                            u16::try_from(bearing)
+                                .map_err(|_| invalid_field("intersection bearing", index))
+                        })
+                        .collect::<Result<_, _>>()?,
+                    entry: intersection.entry,
+                    bearing_in_index: intersection.bearing_in_index,
+                    bearing_out_index: intersection.bearing_out_index,
+                    geometry_index: intersection.geometry_index,
+                    turn_weight: intersection.turn_weight,
+                    turn_duration: intersection.turn_duration,
+                })
+            })
+            .collect::<Result<_, _>>()?,
+        has_crosswalk: step.has_crosswalk,
+        has_stairs: step.has_stairs,
+        has_elevator: step.has_elevator,
+        extras: step.extras,
+        junction_views: step
+            .junction_views
+            .into_iter()
+            .map(|junction_view| JunctionView {
+                image_reference: junction_view.image_reference,
+                kind: junction_view.kind,
+                trigger_distance_before_maneuver: junction_view.trigger_distance_before_maneuver,
+            })
+            .collect(),
+    })
+}
+
+fn decode_geometry(
+    polyline: &str,
+    index: ResponseIndex,
+) -> Result<Vec<GeographicCoordinate>, RoutingResponseParseError> {
+    let linestring = decode_polyline(polyline, POLYLINE_PRECISION)
+        .map_err(|error| RoutingResponseParseError::GeometryDecodeError { index, error })?;
+    let geometry: Vec<_> = linestring.coords().map(|coord| (*coord).into()).collect();
+    validate_geometry(&geometry, index)?;
+    Ok(geometry)
+}
+
+fn decode_coordinate(
+    coordinate: Option<models::Coordinate>,
+    index: ResponseIndex,
+) -> Result<GeographicCoordinate, RoutingResponseParseError> {
+    let coordinate = coordinate.ok_or_else(|| missing_field("coordinate", index))?;
+    Ok(GeographicCoordinate {
+        lat: coordinate.lat,
+        lng: coordinate.lng,
+    })
+}
+
+fn decode_visual_content(content: models::VisualInstructionContent) -> VisualInstructionContent {
+    VisualInstructionContent {
+        text: content.text,
+        maneuver_type: decode_maneuver_type(content.maneuver_type),
+        maneuver_modifier: decode_maneuver_modifier(content.maneuver_modifier),
+        roundabout_exit_degrees: content
+            .roundabout_exit_degrees
+            .and_then(|degrees| u16::try_from(degrees).ok()),
+        exit_numbers: content.exit_numbers,
+        destination_refs: content.destination_refs,
+        destinations: content.destinations,
+    }
+}
+
+/// Decodes a timestamp, discarding any which are beyond what the platform can represent.
+fn decode_timestamp(millis: u64) -> Option<SystemTime> {
+    SystemTime::UNIX_EPOCH.checked_add(Duration::from_millis(millis))
+}
+
+fn missing_field(field: &str, index: ResponseIndex) -> RoutingResponseParseError {
+    RoutingResponseParseError::InvalidProtobuf {
+        error: format!("{index} is missing the required field {field}"),
+    }
+}
+
+fn invalid_field(field: &str, index: ResponseIndex) -> RoutingResponseParseError {
+    RoutingResponseParseError::InvalidProtobuf {
+        error: format!("{index} has an invalid value for the field {field}"),
+    }
+}
+
+//
+// Enum mappings (these must be kept in sync with route.proto)
+//
+
+fn encode_waypoint_kind(kind: WaypointKind) -> i32 {
+    match kind {
+        WaypointKind::Break => 0,
+        WaypointKind::Via => 1,
+        WaypointKind::TransitBoard => 2,
+        WaypointKind::TransitAlight => 3,
+    }
+}
+
+fn decode_waypoint_kind(value: i32) -> WaypointKind {
+    match value {
+        1 => WaypointKind::Via,
+        2 => WaypointKind::TransitBoard,
+        3 => WaypointKind::TransitAlight,
+        _ => WaypointKind::Break,
+    }
+}
+
+fn encode_maneuver_type(maneuver_type: ManeuverType) -> i32 {
+    match maneuver_type {
+        ManeuverType::Turn => 1,
+        ManeuverType::NewName => 2,
+        ManeuverType::Depart => 3,
+        ManeuverType::Arrive => 4,
+        ManeuverType::Merge => 5,
+        ManeuverType::OnRamp => 6,
+        ManeuverType::OffRamp => 7,
+        ManeuverType::Fork => 8,
+        ManeuverType::EndOfRoad => 9,
+        ManeuverType::Continue => 10,
+        ManeuverType::Roundabout => 11,
+        ManeuverType::Rotary => 12,
+        ManeuverType::RoundaboutTurn => 13,
+        ManeuverType::Notification => 14,
+        ManeuverType::ExitRoundabout => 15,
+        ManeuverType::ExitRotary => 16,
+    }
+}
+
+fn decode_maneuver_type(value: i32) -> Option<ManeuverType> {
+    match value {
+        1 => Some(ManeuverType::Turn),
+        2 => Some(ManeuverType::NewName),
+        3 => Some(ManeuverType::Depart),
+        4 => Some(ManeuverType::Arrive),
+        5 => Some(ManeuverType::Merge),
+        6 => Some(ManeuverType::OnRamp),
+        7 => Some(ManeuverType::OffRamp),
+        8 => Some(ManeuverType::Fork),
+        9 => Some(ManeuverType::EndOfRoad),
+        10 => Some(ManeuverType::Continue),
+        11 => Some(ManeuverType::Roundabout),
+        12 => Some(ManeuverType::Rotary),
+        13 => Some(ManeuverType::RoundaboutTurn),
+        14 => Some(ManeuverType::Notification),
+        15 => Some(ManeuverType::ExitRoundabout),
+        16 => Some(ManeuverType::ExitRotary),
+        _ => None,
+    }
+}
+
+fn encode_maneuver_modifier(modifier: ManeuverModifier) -> i32 {
+    match modifier {
+        ManeuverModifier::UTurn => 1,
+        ManeuverModifier::SharpRight => 2,
+        ManeuverModifier::Right => 3,
+        ManeuverModifier::SlightRight => 4,
+        ManeuverModifier::Straight => 5,
+        ManeuverModifier::SlightLeft => 6,
+        ManeuverModifier::Left => 7,
+        ManeuverModifier::SharpLeft => 8,
+    }
+}
+
+fn decode_maneuver_modifier(value: i32) -> Option<ManeuverModifier> {
+    match value {
+        1 => Some(ManeuverModifier::UTurn),
+        2 => Some(ManeuverModifier::SharpRight),
+        3 => Some(ManeuverModifier::Right),
+        4 => Some(ManeuverModifier::SlightRight),
+        5 => Some(ManeuverModifier::Straight),
+        6 => Some(ManeuverModifier::SlightLeft),
+        7 => Some(ManeuverModifier::Left),
+        8 => Some(ManeuverModifier::SharpLeft),
+        _ => None,
+    }
+}
+
+fn encode_driving_side(side: DrivingSide) -> i32 {
+    match side {
+        DrivingSide::Left => 1,
+        DrivingSide::Right => 2,
+    }
+}
+
+fn decode_driving_side(value: i32) -> Option<DrivingSide> {
+    match value {
+        1 => Some(DrivingSide::Left),
+        2 => Some(DrivingSide::Right),
+        _ => None,
+    }
+}
+
+fn encode_speed_limit_sign(sign: SpeedLimitSign) -> i32 {
+    match sign {
+        SpeedLimitSign::Vienna => 1,
+        SpeedLimitSign::Mutcd => 2,
+    }
+}
+
+fn decode_speed_limit_sign(value: i32) -> Option<SpeedLimitSign> {
+    match value {
+        1 => Some(SpeedLimitSign::Vienna),
+        2 => Some(SpeedLimitSign::Mutcd),
+        _ => None,
+    }
+}
+
+fn encode_speed_unit(unit: SpeedUnit) -> i32 {
+    match unit {
+        SpeedUnit::KilometersPerHour => 1,
+        SpeedUnit::MilesPerHour => 2,
+    }
+}
+
+fn decode_speed_unit(value: i32) -> Option<SpeedUnit> {
+    match value {
+        1 => Some(SpeedUnit::KilometersPerHour),
+        2 => Some(SpeedUnit::MilesPerHour),
+        _ => None,
+    }
+}
+
+fn encode_transit_mode(mode: TransitMode) -> i32 {
+    match mode {
+        TransitMode::Other => 0,
+        TransitMode::Tram => 1,
+        TransitMode::Subway => 2,
+        TransitMode::Rail => 3,
+        TransitMode::Bus => 4,
+        TransitMode::Ferry => 5,
+        TransitMode::CableCar => 6,
+        TransitMode::Gondola => 7,
+        TransitMode::Funicular => 8,
+    }
+}
+
+fn decode_transit_mode(value: i32) -> TransitMode {
+    match value {
+        1 => TransitMode::Tram,
+        2 => TransitMode::Subway,
+        3 => TransitMode::Rail,
+        4 => TransitMode::Bus,
+        5 => TransitMode::Ferry,
+        6 => TransitMode::CableCar,
+        7 => TransitMode::Gondola,
+        8 => TransitMode::Funicular,
+        _ => TransitMode::Other,
+    }
+}
+
+fn encode_incident_kind(kind: IncidentKind) -> i32 {
+    match kind {
+        IncidentKind::Miscellaneous => 0,
+        IncidentKind::Accident => 1,
+        IncidentKind::Congestion => 2,
+        IncidentKind::Construction => 3,
+        IncidentKind::DisabledVehicle => 4,
+        IncidentKind::LaneRestriction => 5,
+        IncidentKind::MassTransit => 6,
+        IncidentKind::PlannedEvent => 7,
+        IncidentKind::RoadClosure => 8,
+        IncidentKind::RoadHazard => 9,
+        IncidentKind::Weather => 10,
+    }
+}
+
+fn decode_incident_kind(value: i32) -> IncidentKind {
+    match value {
+        1 => IncidentKind::Accident,
+        2 => IncidentKind::Congestion,
+        3 => IncidentKind::Construction,
+        4 => IncidentKind::DisabledVehicle,
+        5 => IncidentKind::LaneRestriction,
+        6 => IncidentKind::MassTransit,
+        7 => IncidentKind::PlannedEvent,
+        8 => IncidentKind::RoadClosure,
+        9 => IncidentKind::RoadHazard,
+        10 => IncidentKind::Weather,
+        _ => IncidentKind::Miscellaneous,
+    }
+}
+
+/// Returns the enum value, and the name for [`ConnectorType::Other`].
+fn encode_connector_type(connector_type: &ConnectorType) -> (i32, Option<String>) {
+    match connector_type {
+        ConnectorType::Other { name } => (0, Some(name.clone())),
+        ConnectorType::J1772 => (1, None),
+        ConnectorType::Type2 => (2, None),
+        ConnectorType::Ccs1 => (3, None),
+        ConnectorType::Ccs2 => (4, None),
+        ConnectorType::Chademo => (5, None),
+        ConnectorType::Nacs => (6, None),
+    }
+}
+
+fn decode_connector_type(value: i32, name: Option<String>) -> ConnectorType {
+    match value {
+        1 => ConnectorType::J1772,
+        2 => ConnectorType::Type2,
+        3 => ConnectorType::Ccs1,
+        4 => ConnectorType::Ccs2,
+        5 => ConnectorType::Chademo,
+        6 => ConnectorType::Nacs,
+        _ => ConnectorType::Other {
+            name: name.unwrap_or_default(),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::navigation_controller::test_helpers::{gen_dummy_route_step, gen_route_from_steps};
+
+    fn route() -> Route {
+        let mut first_step = gen_dummy_route_step(13.388798, 52.517033, 13.39763, 52.529432);
+        first_step.distance = 1886.3;
+        first_step.road_name = Some("Friedrichstraße".to_string());
+        first_step.instruction = "Turn right onto Torstraße.".to_string();
+        first_step.visual_instructions = vec![VisualInstruction {
+            primary_content: VisualInstructionContent {
+                text: "Torstraße".to_string(),
+                maneuver_type: Some(ManeuverType::Turn),
+                maneuver_modifier: Some(ManeuverModifier::Right),
+                roundabout_exit_degrees: None,
+                exit_numbers: vec![],
+                destination_refs: vec!["B 96a".to_string()],
+                destinations: vec!["Mitte".to_string()],
+            },
+            secondary_content: None,
+            trigger_distance_before_maneuver: 200.0,
+        }];
+        first_step.spoken_instructions = vec![SpokenInstruction {
+            text: "Turn right onto Torstraße.".to_string(),
+            ssml: None,
+            trigger_distance_before_maneuver: 100.0,
+            utterance_id: Uuid::new_v4(),
+        }];
+        first_step.tunnels = vec![Tunnel {
+            name: Some("Tunnel Tiergarten".to_string()),
+            entrance_distance_before_maneuver: 900.0,
+            exit_distance_before_maneuver: 400.0,
+        }];
+        first_step.has_tolls = true;
+        first_step.has_crosswalk = true;
+        first_step.has_elevator = true;
+        first_step.shields = vec![RoadShield {
+            network: Some("de-bundesstrasse".to_string()),
+            number: "96a".to_string(),
+        }];
+
+        let mut second_step = gen_dummy_route_step(13.39763, 52.529432, 13.428554, 52.523239);
+        second_step.distance = 2845.5;
+        second_step.is_ferry = true;
+        second_step.has_stairs = true;
+        second_step.transit_leg = Some(TransitLeg {
+            mode: TransitMode::Tram,
+            line_name: Some("M8".to_string()),
+            headsign: None,
+            departure_time: decode_timestamp(1_700_000_000_000),
+            arrival_time: decode_timestamp(1_700_000_600_000),
+            stops: vec![TransitStop {
+                name: Some("Rosenthaler Platz".to_string()),
+                coordinate: second_step.geometry[0],
+                arrival_time: None,
+                departure_time: decode_timestamp(1_700_000_000_000),
+            }],
+        });
+
+        let mut route = gen_route_from_steps(vec![first_step, second_step]);
+        route.elevation_profile = Some(ElevationProfile::from_samples(vec![
+            ElevationSample {
+                distance_along_route: 0.0,
+                elevation: 34.0,
+            },
+            ElevationSample {
+                distance_along_route: 4731.8,
+                elevation: 41.0,
+            },
+        ]));
+        route.incidents = vec![Incident {
+            id: "1".to_string(),
+            kind: IncidentKind::Construction,
+            description: None,
+            is_closed: false,
+            start_distance_along_route: 100.0,
+            end_distance_along_route: 300.0,
+        }];
+        route.charging_stops = vec![
+            ChargingStop {
+                coordinate: route.steps[1].geometry[0],
+                name: Some("Ladepark Torstraße".to_string()),
+                station_id: Some("42".to_string()),
+                charge_at_arrival: 12.5,
+                charge_to: 60.0,
+                charge_time: 1500.0,
+                power: Some(150.0),
+                connector_type: Some(ConnectorType::Ccs2),
+            },
+            ChargingStop {
+                coordinate: route.steps[1].geometry[1],
+                name: None,
+                station_id: None,
+                charge_at_arrival: 40.0,
+                charge_to: 45.0,
+                charge_time: 600.0,
+                power: None,
+                connector_type: Some(ConnectorType::Other {
+                    name: "gbt".to_string(),
+                }),
+            },
+        ];
+        route.destination_details = Some(DestinationDetails {
+            entrances: vec![GeographicCoordinate {
+                lat: 52.5301,
+                lng: 13.4022,
+            }],
+            parking: vec![ParkingSuggestion {
+                coordinate: GeographicCoordinate {
+                    lat: 52.5297,
+                    lng: 13.4019,
+                },
+                name: Some("Parkhaus Mitte".to_string()),
+            }],
+        });
+        route.notices = vec![RouteNotice {
+            code: Some(402),
+            kind: None,
+            message: "Steps could not be avoided".to_string(),
+        }];
+        route.planned_at = Some(SystemTime::UNIX_EPOCH + Duration::from_millis(1_700_000_000_123));
+        route
+    }
+
+    #[test]
+    fn round_trip() {
+        let original = route();
+        let encoded = encode_routes_protobuf(&[original.clone()]).expect("Unable to encode routes");
+        let decoded = ProtobufResponseParser::new()
+            .parse_response(encoded)
+            .expect("Unable to decode routes");
+
+        assert_eq!(decoded.len(), 1);
+        let decoded = &decoded[0];
+        assert_eq!(decoded.geometry, original.geometry);
+        assert_eq!(decoded.bbox, original.bbox);
+        assert_eq!(decoded.distance, original.distance);
+        assert_eq!(decoded.waypoints, original.waypoints);
+        assert_eq!(decoded.steps, original.steps);
+        assert_eq!(decoded.elevation_profile, original.elevation_profile);
+        assert_eq!(decoded.charging_stops, original.charging_stops);
+        assert_eq!(decoded.incidents, original.incidents);
+        assert_eq!(decoded.destination_details, original.destination_details);
+        assert_eq!(decoded.notices, original.notices);
+        assert_eq!(decoded.planned_at, original.planned_at);
+    }
+
+    #[test]
+    fn invalid_protobuf() {
+        assert!(matches!(
+            ProtobufResponseParser::new().parse_response(b"not a protobuf".to_vec()),
+            Err(RoutingResponseParseError::InvalidProtobuf { .. })
+        ));
+    }
+
+    #[test]
+    fn empty_collection() {
+        assert!(matches!(
+            ProtobufResponseParser::new().parse_response(vec![]),
+            Err(RoutingResponseParseError::NoRoutes)
+        ));
+    }
+
+    #[test]
+    fn missing_bbox() {
+        let mut route = encode_route(&route()).expect("Unable to encode route");
+        route.bbox = None;
+        let encoded = models::RouteCollection {
+            routes: vec![route],
+        }
+        .encode_to_vec();
+        assert!(matches!(
+            ProtobufResponseParser::new().parse_response(encoded),
+            Err(RoutingResponseParseError::InvalidProtobuf { .. })
+        ));
+    }
+}
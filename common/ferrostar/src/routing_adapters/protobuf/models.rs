@@ -0,0 +1,323 @@
+//! Message definitions matching `route.proto`.
+//!
+//! These are maintained by hand rather than generated so that building the crate
+//! does not require `protoc`. Enums are represented by their raw `i32` values
+//! (which is how they are encoded on the wire); see the parent module for the mappings.
+
+use std::collections::HashMap;
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct RouteCollection {
+    #[prost(message, repeated, tag = "1")]
+    pub routes: Vec<Route>,
+}
+
+#[derive(Clone, Copy, PartialEq, prost::Message)]
+pub struct Coordinate {
+    #[prost(double, tag = "1")]
+    pub lat: f64,
+    #[prost(double, tag = "2")]
+    pub lng: f64,
+}
+
+#[derive(Clone, Copy, PartialEq, prost::Message)]
+pub struct BoundingBox {
+    #[prost(message, optional, tag = "1")]
+    pub sw: Option<Coordinate>,
+    #[prost(message, optional, tag = "2")]
+    pub ne: Option<Coordinate>,
+}
+
+#[derive(Clone, Copy, PartialEq, prost::Message)]
+pub struct Waypoint {
+    #[prost(message, optional, tag = "1")]
+    pub coordinate: Option<Coordinate>,
+    #[prost(int32, tag = "2")]
+    pub kind: i32,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Route {
+    /// Polyline6-encoded geometry.
+    #[prost(string, tag = "1")]
+    pub geometry: String,
+    #[prost(message, optional, tag = "2")]
+    pub bbox: Option<BoundingBox>,
+    #[prost(double, tag = "3")]
+    pub distance: f64,
+    #[prost(message, repeated, tag = "4")]
+    pub waypoints: Vec<Waypoint>,
+    #[prost(message, repeated, tag = "5")]
+    pub steps: Vec<RouteStep>,
+    #[prost(message, optional, tag = "6")]
+    pub elevation_profile: Option<ElevationProfile>,
+    #[prost(message, repeated, tag = "7")]
+    pub incidents: Vec<Incident>,
+    #[prost(message, repeated, tag = "8")]
+    pub charging_stops: Vec<ChargingStop>,
+    #[prost(double, tag = "9")]
+    pub duration: f64,
+    #[prost(double, optional, tag = "10")]
+    pub weight: Option<f64>,
+    #[prost(string, optional, tag = "11")]
+    pub weight_name: Option<String>,
+    #[prost(message, optional, tag = "12")]
+    pub destination_details: Option<DestinationDetails>,
+    #[prost(message, repeated, tag = "13")]
+    pub notices: Vec<RouteNotice>,
+    #[prost(map = "string, string", tag = "14")]
+    pub extras: HashMap<String, String>,
+    #[prost(uint64, optional, tag = "15")]
+    pub planned_at: Option<u64>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct RouteStep {
+    /// Polyline6-encoded geometry.
+    #[prost(string, tag = "1")]
+    pub geometry: String,
+    #[prost(double, tag = "2")]
+    pub distance: f64,
+    #[prost(double, tag = "3")]
+    pub duration: f64,
+    #[prost(string, optional, tag = "4")]
+    pub road_name: Option<String>,
+    #[prost(string, tag = "5")]
+    pub instruction: String,
+    #[prost(message, repeated, tag = "6")]
+    pub visual_instructions: Vec<VisualInstruction>,
+    #[prost(message, repeated, tag = "7")]
+    pub spoken_instructions: Vec<SpokenInstruction>,
+    #[prost(message, optional, tag = "8")]
+    pub transit_leg: Option<TransitLeg>,
+    #[prost(message, repeated, tag = "9")]
+    pub tunnels: Vec<Tunnel>,
+    #[prost(bool, tag = "10")]
+    pub has_tolls: bool,
+    #[prost(bool, tag = "11")]
+    pub is_ferry: bool,
+    #[prost(message, repeated, tag = "12")]
+    pub shields: Vec<RoadShield>,
+    #[prost(message, repeated, tag = "13")]
+    pub country_changes: Vec<CountryChange>,
+    #[prost(int32, tag = "14")]
+    pub speed_limit_sign: i32,
+    #[prost(int32, tag = "15")]
+    pub speed_limit_unit: i32,
+    #[prost(int32, tag = "16")]
+    pub driving_side: i32,
+    #[prost(message, repeated, tag = "17")]
+    pub intersections: Vec<Intersection>,
+    #[prost(message, repeated, tag = "18")]
+    pub junction_views: Vec<JunctionView>,
+    #[prost(bool, tag = "19")]
+    pub has_crosswalk: bool,
+    #[prost(bool, tag = "20")]
+    pub has_stairs: bool,
+    #[prost(bool, tag = "21")]
+    pub has_elevator: bool,
+    #[prost(map = "string, string", tag = "22")]
+    pub extras: HashMap<String, String>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct VisualInstructionContent {
+    #[prost(string, tag = "1")]
+    pub text: String,
+    #[prost(int32, tag = "2")]
+    pub maneuver_type: i32,
+    #[prost(int32, tag = "3")]
+    pub maneuver_modifier: i32,
+    #[prost(uint32, optional, tag = "4")]
+    pub roundabout_exit_degrees: Option<u32>,
+    #[prost(string, repeated, tag = "5")]
+    pub exit_numbers: Vec<String>,
+    #[prost(string, repeated, tag = "6")]
+    pub destination_refs: Vec<String>,
+    #[prost(string, repeated, tag = "7")]
+    pub destinations: Vec<String>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct VisualInstruction {
+    #[prost(message, optional, tag = "1")]
+    pub primary_content: Option<VisualInstructionContent>,
+    #[prost(message, optional, tag = "2")]
+    pub secondary_content: Option<VisualInstructionContent>,
+    #[prost(double, tag = "3")]
+    pub trigger_distance_before_maneuver: f64,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct SpokenInstruction {
+    #[prost(string, tag = "1")]
+    pub text: String,
+    #[prost(string, optional, tag = "2")]
+    pub ssml: Option<String>,
+    #[prost(double, tag = "3")]
+    pub trigger_distance_before_maneuver: f64,
+    #[prost(bytes = "vec", tag = "4")]
+    pub utterance_id: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct TransitStop {
+    #[prost(string, optional, tag = "1")]
+    pub name: Option<String>,
+    #[prost(message, optional, tag = "2")]
+    pub coordinate: Option<Coordinate>,
+    #[prost(uint64, optional, tag = "3")]
+    pub arrival_time: Option<u64>,
+    #[prost(uint64, optional, tag = "4")]
+    pub departure_time: Option<u64>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct TransitLeg {
+    #[prost(int32, tag = "1")]
+    pub mode: i32,
+    #[prost(string, optional, tag = "2")]
+    pub line_name: Option<String>,
+    #[prost(string, optional, tag = "3")]
+    pub headsign: Option<String>,
+    #[prost(uint64, optional, tag = "4")]
+    pub departure_time: Option<u64>,
+    #[prost(uint64, optional, tag = "5")]
+    pub arrival_time: Option<u64>,
+    #[prost(message, repeated, tag = "6")]
+    pub stops: Vec<TransitStop>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Tunnel {
+    #[prost(string, optional, tag = "1")]
+    pub name: Option<String>,
+    #[prost(double, tag = "2")]
+    pub entrance_distance_before_maneuver: f64,
+    #[prost(double, tag = "3")]
+    pub exit_distance_before_maneuver: f64,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct JunctionView {
+    #[prost(string, tag = "1")]
+    pub image_reference: String,
+    #[prost(string, optional, tag = "2")]
+    pub kind: Option<String>,
+    #[prost(double, tag = "3")]
+    pub trigger_distance_before_maneuver: f64,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Intersection {
+    #[prost(message, optional, tag = "1")]
+    pub location: Option<Coordinate>,
+    #[prost(uint32, repeated, tag = "2")]
+    pub bearings: Vec<u32>,
+    #[prost(bool, repeated, tag = "3")]
+    pub entry: Vec<bool>,
+    #[prost(uint32, optional, tag = "4")]
+    pub bearing_in_index: Option<u32>,
+    #[prost(uint32, optional, tag = "5")]
+    pub bearing_out_index: Option<u32>,
+    #[prost(uint64, optional, tag = "6")]
+    pub geometry_index: Option<u64>,
+    #[prost(double, optional, tag = "7")]
+    pub turn_weight: Option<f64>,
+    #[prost(double, optional, tag = "8")]
+    pub turn_duration: Option<f64>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct CountryChange {
+    #[prost(string, tag = "1")]
+    pub iso_3166: String,
+    #[prost(double, tag = "2")]
+    pub distance_before_maneuver: f64,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct RoadShield {
+    #[prost(string, optional, tag = "1")]
+    pub network: Option<String>,
+    #[prost(string, tag = "2")]
+    pub number: String,
+}
+
+#[derive(Clone, Copy, PartialEq, prost::Message)]
+pub struct ElevationSample {
+    #[prost(double, tag = "1")]
+    pub distance_along_route: f64,
+    #[prost(double, tag = "2")]
+    pub elevation: f64,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ElevationProfile {
+    #[prost(message, repeated, tag = "1")]
+    pub samples: Vec<ElevationSample>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Incident {
+    #[prost(string, tag = "1")]
+    pub id: String,
+    #[prost(int32, tag = "2")]
+    pub kind: i32,
+    #[prost(string, optional, tag = "3")]
+    pub description: Option<String>,
+    #[prost(bool, tag = "4")]
+    pub is_closed: bool,
+    #[prost(double, tag = "5")]
+    pub start_distance_along_route: f64,
+    #[prost(double, tag = "6")]
+    pub end_distance_along_route: f64,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct RouteNotice {
+    #[prost(uint32, optional, tag = "1")]
+    pub code: Option<u32>,
+    #[prost(string, tag = "2")]
+    pub message: String,
+    #[prost(string, optional, tag = "3")]
+    pub kind: Option<String>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct DestinationDetails {
+    #[prost(message, repeated, tag = "1")]
+    pub entrances: Vec<Coordinate>,
+    #[prost(message, repeated, tag = "2")]
+    pub parking: Vec<ParkingSuggestion>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ParkingSuggestion {
+    #[prost(message, optional, tag = "1")]
+    pub coordinate: Option<Coordinate>,
+    #[prost(string, optional, tag = "2")]
+    pub name: Option<String>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ChargingStop {
+    #[prost(message, optional, tag = "1")]
+    pub coordinate: Option<Coordinate>,
+    #[prost(string, optional, tag = "2")]
+    pub name: Option<String>,
+    #[prost(string, optional, tag = "3")]
+    pub station_id: Option<String>,
+    #[prost(double, tag = "4")]
+    pub charge_at_arrival: f64,
+    #[prost(double, tag = "5")]
+    pub charge_to: f64,
+    #[prost(double, tag = "6")]
+    pub charge_time: f64,
+    #[prost(double, optional, tag = "7")]
+    pub power: Option<f64>,
+    #[prost(int32, optional, tag = "8")]
+    pub connector_type: Option<i32>,
+    #[prost(string, optional, tag = "9")]
+    pub connector_name: Option<String>,
+}
@@ -1,8 +1,64 @@
 use super::{RouteRequest, RoutingRequestGenerationError};
+use crate::closure_avoidance::{AvoidArea, AvoidedAreas};
 use crate::models::{UserLocation, Waypoint, WaypointKind};
 use crate::routing_adapters::RouteRequestGenerator;
-use serde_json::{json, Value as JsonValue};
+use serde_json::{json, Map, Value as JsonValue};
 use std::collections::HashMap;
+use std::sync::Arc;
+
+/// The step penalty (in seconds) used to avoid stairs.
+///
+/// This is large enough that stairs are only used when there is no reasonable alternative.
+const AVOID_STAIRS_STEP_PENALTY: u32 = 3600;
+
+/// Accessibility requirements for pedestrian (ex: wheelchair) routes.
+#[derive(Debug, Clone, PartialEq, uniffi::Record)]
+pub struct AccessibilityOptions {
+    /// Whether to avoid stairs wherever possible.
+    pub avoid_stairs: bool,
+    /// The maximum incline of the route, as a percentage grade (ex: 6 for a 6% ramp).
+    pub max_incline: Option<u8>,
+    /// Whether to only use paths with surfaces suitable for wheelchairs
+    /// (ex: no gravel or cobblestones).
+    pub require_smooth_surface: bool,
+}
+
+/// Optional settings for Valhalla route requests.
+///
+/// Every option may be combined with the others.
+/// See [`ValhallaHttpRequestGenerator::with_options`].
+#[derive(Debug, Clone, Default, uniffi::Record)]
+pub struct ValhallaRequestOptions {
+    /// JSON costing options to pass through.
+    #[uniffi(default = None)]
+    pub costing_options_json: Option<String>,
+    /// The language of the instructions (ex: `de-DE`), if not the server default.
+    ///
+    /// See [`ValhallaHttpRequestGenerator::with_language`].
+    #[uniffi(default = None)]
+    pub language: Option<String>,
+    /// How strongly to prefer smaller, curvier roads over highways,
+    /// from 0 (no preference) to 1 (avoid highways wherever possible).
+    ///
+    /// See [`ValhallaHttpRequestGenerator::preferring_curvy_roads`].
+    #[uniffi(default = None)]
+    pub curvy_road_preference: Option<f64>,
+    /// Accessibility requirements for pedestrian routes.
+    ///
+    /// See [`ValhallaHttpRequestGenerator::with_accessibility_options`].
+    #[uniffi(default = None)]
+    pub accessibility: Option<AccessibilityOptions>,
+    /// Areas to exclude from routes (ex: road closures).
+    ///
+    /// See [`ValhallaHttpRequestGenerator::with_avoided_areas`].
+    #[uniffi(default = None)]
+    pub avoided_areas: Option<Arc<AvoidedAreas>>,
+    /// The local departure time (`YYYY-MM-DDThh:mm`) for multimodal routes.
+    ///
+    /// See [`ValhallaHttpRequestGenerator::departing_at`].
+    #[uniffi(default = None)]
+    pub departure_time: Option<String>,
+}
 
 /// A route request generator for Valhalla backends operating over HTTP.
 ///
@@ -18,9 +74,15 @@ pub struct ValhallaHttpRequestGenerator {
     endpoint_url: String,
     /// The Valhalla costing model to use.
     profile: String,
-    // TODO: Language, units, and other top-level parameters
+    // TODO: Units and other top-level parameters
+    /// The language of the instructions (ex: `de-DE`), if not the server default.
+    language: Option<String>,
     /// JSON costing options to pass through.
     costing_options: JsonValue,
+    /// Areas to exclude from routes, which may change between requests.
+    avoided_areas: Option<Arc<AvoidedAreas>>,
+    /// The local departure time (`YYYY-MM-DDThh:mm`) for multimodal routes.
+    departure_time: Option<String>,
 }
 
 impl ValhallaHttpRequestGenerator {
@@ -28,7 +90,10 @@ impl ValhallaHttpRequestGenerator {
         Self {
             endpoint_url,
             profile,
+            language: None,
             costing_options: costing_options.unwrap_or(json!({})),
+            avoided_areas: None,
+            departure_time: None,
         }
     }
 
@@ -44,9 +109,133 @@ impl ValhallaHttpRequestGenerator {
         Ok(Self {
             endpoint_url,
             profile,
+            language: None,
             costing_options: parsed_costing_options,
+            avoided_areas: None,
+            departure_time: None,
         })
     }
+
+    /// Creates a request generator with any combination of options.
+    ///
+    /// Fails if the costing options are not valid JSON.
+    pub fn with_options(
+        endpoint_url: String,
+        profile: String,
+        options: ValhallaRequestOptions,
+    ) -> Result<Self, serde_json::Error> {
+        let mut generator =
+            Self::with_costing_options_json(endpoint_url, profile, options.costing_options_json)?;
+        if let Some(language) = options.language {
+            generator = generator.with_language(language);
+        }
+        if let Some(preference) = options.curvy_road_preference {
+            generator = generator.preferring_curvy_roads(preference);
+        }
+        if let Some(accessibility) = &options.accessibility {
+            generator = generator.with_accessibility_options(accessibility);
+        }
+        if let Some(avoided_areas) = options.avoided_areas {
+            generator = generator.with_avoided_areas(avoided_areas);
+        }
+        if let Some(departure_time) = options.departure_time {
+            generator = generator.departing_at(departure_time);
+        }
+        Ok(generator)
+    }
+
+    /// Requests instructions in the language of a locale (ex: `de-DE`).
+    ///
+    /// Valhalla supports [many languages](https://valhalla.github.io/valhalla/api/turn-by-turn/api-reference/#supported-language-tags).
+    /// Use the same locale for the response parser
+    /// (see [`crate::routing_adapters::osrm::OsrmResponseParser::with_locale`])
+    /// so that any instructions synthesized on the device match.
+    #[must_use]
+    pub fn with_language(mut self, language: String) -> Self {
+        self.language = Some(language);
+        self
+    }
+
+    /// Steers the route away from highways and toward smaller (and typically curvier) roads,
+    /// which is popular for motorcycle touring.
+    ///
+    /// `preference` ranges from 0 (no preference) to 1 (avoid highways wherever possible).
+    /// This sets the `use_highways` costing option for the profile,
+    /// unless it was already set explicitly in the costing options.
+    #[must_use]
+    pub fn preferring_curvy_roads(mut self, preference: f64) -> Self {
+        if let Some(profile_options) = self.profile_options_mut() {
+            profile_options
+                .entry("use_highways")
+                .or_insert_with(|| (1.0 - preference.clamp(0.0, 1.0)).into());
+        }
+        self
+    }
+
+    /// Requests routes which meet accessibility requirements.
+    ///
+    /// This is intended for the `pedestrian` costing model.
+    /// Stairs are avoided with a large `step_penalty`, the incline is limited with `max_grade`,
+    /// and smooth surfaces are required with the `wheelchair` pedestrian `type`.
+    /// Costing options which were already set explicitly are kept.
+    /// Valhalla includes a notice in the route (see [`crate::models::Route::notices`])
+    /// if the options could not be satisfied.
+    #[must_use]
+    pub fn with_accessibility_options(mut self, accessibility: &AccessibilityOptions) -> Self {
+        if let Some(profile_options) = self.profile_options_mut() {
+            if accessibility.avoid_stairs {
+                profile_options
+                    .entry("step_penalty")
+                    .or_insert_with(|| AVOID_STAIRS_STEP_PENALTY.into());
+            }
+            if let Some(max_incline) = accessibility.max_incline {
+                profile_options
+                    .entry("max_grade")
+                    .or_insert_with(|| max_incline.into());
+            }
+            if accessibility.require_smooth_surface {
+                profile_options
+                    .entry("type")
+                    .or_insert_with(|| "wheelchair".into());
+            }
+        }
+        self
+    }
+
+    /// Excludes areas from all subsequent routes (ex: road closures reported by the user).
+    ///
+    /// The areas are read when each request is generated,
+    /// so areas which are added later (ex: by a [`crate::closure_avoidance::ClosureRerouter`])
+    /// apply to the following requests.
+    /// Polygons are sent as `exclude_polygons`, and locations as `exclude_locations`.
+    #[must_use]
+    pub fn with_avoided_areas(mut self, avoided_areas: Arc<AvoidedAreas>) -> Self {
+        self.avoided_areas = Some(avoided_areas);
+        self
+    }
+
+    /// Requests a multimodal (public transit) route departing at a local time (`YYYY-MM-DDThh:mm`).
+    ///
+    /// The time is local to the origin, as Valhalla does not accept an offset from UTC.
+    /// This switches to Valhalla's native response format, as the OSRM format does not include any transit details,
+    /// so responses must be parsed with a
+    /// [`crate::routing_adapters::valhalla_multimodal::ValhallaMultimodalResponseParser`].
+    #[must_use]
+    pub fn departing_at(mut self, local_date_time: String) -> Self {
+        self.departure_time = Some(local_date_time);
+        self
+    }
+
+    /// The costing options for the profile, which are created if necessary.
+    ///
+    /// Returns `None` if the costing options are not a JSON object.
+    fn profile_options_mut(&mut self) -> Option<&mut Map<String, JsonValue>> {
+        self.costing_options
+            .as_object_mut()?
+            .entry(self.profile.clone())
+            .or_insert_with(|| json!({}))
+            .as_object_mut()
+    }
 }
 
 impl RouteRequestGenerator for ValhallaHttpRequestGenerator {
@@ -77,7 +266,11 @@ impl RouteRequestGenerator for ValhallaHttpRequestGenerator {
                         "lat": waypoint.coordinate.lat,
                         "lon": waypoint.coordinate.lng,
                         "type": match waypoint.kind {
-                            WaypointKind::Break => "break",
+                            // Transit stops only appear in multimodal routes,
+                            // and the user needs to stop at them, so they are treated as breaks.
+                            WaypointKind::Break
+                            | WaypointKind::TransitBoard
+                            | WaypointKind::TransitAlight => "break",
                             WaypointKind::Via => "via",
                         },
                     })
@@ -88,7 +281,7 @@ impl RouteRequestGenerator for ValhallaHttpRequestGenerator {
             // Though it would be nice to use PBF if we can get the required data.
             // However, certain info (like banners) are only available in the OSRM format.
             // TODO: Trace attributes as we go rather than pulling a fat payload upfront that we might ditch later?
-            let args = json!({
+            let mut args = json!({
                 "format": "osrm",
                 "filters": {
                     "action": "include",
@@ -105,6 +298,37 @@ impl RouteRequestGenerator for ValhallaHttpRequestGenerator {
                 "locations": locations,
                 "costing_options": &self.costing_options,
             });
+            if let Some(language) = &self.language {
+                args["language"] = language.as_str().into();
+            }
+            if let Some(departure_time) = &self.departure_time {
+                args["format"] = "json".into();
+                // Type 1 is a departure at the given time
+                args["date_time"] = json!({ "type": 1, "value": departure_time });
+            }
+            if let Some(avoided_areas) = &self.avoided_areas {
+                let mut polygons = vec![];
+                let mut locations = vec![];
+                for area in avoided_areas.get_areas() {
+                    match area {
+                        AvoidArea::Polygon { ring } => polygons.push(
+                            ring.iter()
+                                .map(|coordinate| [coordinate.lng, coordinate.lat])
+                                .collect::<Vec<_>>(),
+                        ),
+                        AvoidArea::Location { coordinate } => locations.push(json!({
+                            "lat": coordinate.lat,
+                            "lon": coordinate.lng,
+                        })),
+                    }
+                }
+                if !polygons.is_empty() {
+                    args["exclude_polygons"] = json!(polygons);
+                }
+                if !locations.is_empty() {
+                    args["exclude_locations"] = json!(locations);
+                }
+            }
             let body = serde_json::to_vec(&args)?;
             Ok(RouteRequest::HttpPost {
                 url: self.endpoint_url.clone(),
@@ -119,7 +343,7 @@ impl RouteRequestGenerator for ValhallaHttpRequestGenerator {
 mod tests {
     use super::*;
     use crate::models::{CourseOverGround, GeographicCoordinate};
-    use assert_json_diff::assert_json_include;
+    use assert_json_diff::{assert_json_eq, assert_json_include};
     use serde_json::{from_slice, json};
     use std::time::SystemTime;
 
@@ -131,6 +355,8 @@ mod tests {
         course_over_ground: None,
         timestamp: SystemTime::UNIX_EPOCH,
         speed: None,
+        altitude: None,
+        vertical_accuracy: None,
     };
     const USER_LOCATION_WITH_COURSE: UserLocation = UserLocation {
         coordinates: GeographicCoordinate { lat: 0.0, lng: 0.0 },
@@ -141,6 +367,8 @@ mod tests {
         }),
         timestamp: SystemTime::UNIX_EPOCH,
         speed: None,
+        altitude: None,
+        vertical_accuracy: None,
     };
     const WAYPOINTS: [Waypoint; 2] = [
         Waypoint {
@@ -187,6 +415,7 @@ mod tests {
                 assert_eq!(headers["Content-Type"], "application/json".to_string());
                 from_slice(&body).expect("Failed to parse request body as JSON")
             }
+            Ok(request) => panic!("Expected a POST request; got {request:?}"),
             Err(e) => {
                 println!("Failed to generate request: {:?}", e);
                 json!(null)
@@ -281,6 +510,247 @@ mod tests {
         );
     }
 
+    #[test]
+    fn request_body_preferring_curvy_roads() {
+        let generator =
+            ValhallaHttpRequestGenerator::new(ENDPOINT_URL.to_string(), COSTING.to_string(), None)
+                .preferring_curvy_roads(0.75);
+        let RouteRequest::HttpPost { body, .. } = generator
+            .generate_request(USER_LOCATION, WAYPOINTS.to_vec())
+            .expect("Unable to generate request")
+        else {
+            panic!("Expected a POST request");
+        };
+        let body_json: JsonValue = from_slice(&body).expect("Failed to parse request body as JSON");
+
+        assert_json_include!(
+            actual: body_json,
+            expected: json!({
+                "costing_options": {
+                    "bicycle": {
+                        "use_highways": 0.25,
+                    },
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn preferring_curvy_roads_keeps_explicit_options() {
+        let generator = ValhallaHttpRequestGenerator::with_costing_options_json(
+            ENDPOINT_URL.to_string(),
+            COSTING.to_string(),
+            Some(r#"{"bicycle": {"bicycle_type": "Road", "use_highways": 0.9}}"#.to_string()),
+        )
+        .expect("Unable to create request generator")
+        .preferring_curvy_roads(1.0);
+        let RouteRequest::HttpPost { body, .. } = generator
+            .generate_request(USER_LOCATION, WAYPOINTS.to_vec())
+            .expect("Unable to generate request")
+        else {
+            panic!("Expected a POST request");
+        };
+        let body_json: JsonValue = from_slice(&body).expect("Failed to parse request body as JSON");
+
+        assert_json_include!(
+            actual: body_json,
+            expected: json!({
+                "costing_options": {
+                    "bicycle": {
+                        "bicycle_type": "Road",
+                        "use_highways": 0.9,
+                    },
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn request_body_with_accessibility_options() {
+        let generator = ValhallaHttpRequestGenerator::with_costing_options_json(
+            ENDPOINT_URL.to_string(),
+            "pedestrian".to_string(),
+            Some(r#"{"pedestrian": {"max_grade": 8}}"#.to_string()),
+        )
+        .expect("Unable to create request generator")
+        .with_accessibility_options(&AccessibilityOptions {
+            avoid_stairs: true,
+            max_incline: Some(6),
+            require_smooth_surface: true,
+        });
+        let RouteRequest::HttpPost { body, .. } = generator
+            .generate_request(USER_LOCATION, WAYPOINTS.to_vec())
+            .expect("Unable to generate request")
+        else {
+            panic!("Expected a POST request");
+        };
+        let body_json: JsonValue = from_slice(&body).expect("Failed to parse request body as JSON");
+
+        assert_json_eq!(
+            body_json["costing_options"],
+            json!({
+                "pedestrian": {
+                    "step_penalty": 3600,
+                    "max_grade": 8,
+                    "type": "wheelchair",
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn request_body_with_language() {
+        let generator =
+            ValhallaHttpRequestGenerator::new(ENDPOINT_URL.to_string(), COSTING.to_string(), None)
+                .with_language("de-DE".to_string());
+        let RouteRequest::HttpPost { body, .. } = generator
+            .generate_request(USER_LOCATION, WAYPOINTS.to_vec())
+            .expect("Unable to generate request")
+        else {
+            panic!("Expected a POST request");
+        };
+        let body_json: JsonValue = from_slice(&body).expect("Failed to parse request body as JSON");
+
+        assert_json_include!(
+            actual: body_json,
+            expected: json!({
+                "language": "de-DE",
+            })
+        );
+
+        // The server default is used otherwise
+        let body_json = generate_body(USER_LOCATION, WAYPOINTS.to_vec(), None);
+        assert!(body_json.get("language").is_none());
+    }
+
+    #[test]
+    fn request_body_with_avoided_areas() {
+        let avoided_areas = Arc::new(AvoidedAreas::new());
+        let generator =
+            ValhallaHttpRequestGenerator::new(ENDPOINT_URL.to_string(), COSTING.to_string(), None)
+                .with_avoided_areas(avoided_areas.clone());
+        let generate_body = || {
+            let RouteRequest::HttpPost { body, .. } = generator
+                .generate_request(USER_LOCATION, WAYPOINTS.to_vec())
+                .expect("Unable to generate request")
+            else {
+                panic!("Expected a POST request");
+            };
+            from_slice::<JsonValue>(&body).expect("Failed to parse request body as JSON")
+        };
+
+        // Nothing is excluded until an area is added
+        let body_json = generate_body();
+        assert!(body_json.get("exclude_polygons").is_none());
+        assert!(body_json.get("exclude_locations").is_none());
+
+        avoided_areas.add(AvoidArea::Polygon {
+            ring: vec![
+                GeographicCoordinate { lat: 0.0, lng: 1.0 },
+                GeographicCoordinate { lat: 0.1, lng: 1.0 },
+                GeographicCoordinate { lat: 0.1, lng: 1.1 },
+            ],
+        });
+        avoided_areas.add(AvoidArea::Location {
+            coordinate: GeographicCoordinate { lat: 1.0, lng: 2.0 },
+        });
+        let body_json = generate_body();
+        assert_json_eq!(
+            body_json["exclude_polygons"],
+            json!([[[1.0, 0.0], [1.0, 0.1], [1.1, 0.1]]])
+        );
+        assert_json_eq!(
+            body_json["exclude_locations"],
+            json!([{ "lat": 1.0, "lon": 2.0 }])
+        );
+    }
+
+    #[test]
+    fn request_body_with_combined_options() {
+        let avoided_areas = Arc::new(AvoidedAreas::new());
+        avoided_areas.add(AvoidArea::Location {
+            coordinate: GeographicCoordinate { lat: 1.0, lng: 2.0 },
+        });
+        let generator = ValhallaHttpRequestGenerator::with_options(
+            ENDPOINT_URL.to_string(),
+            "pedestrian".to_string(),
+            ValhallaRequestOptions {
+                costing_options_json: Some(r#"{"pedestrian": {"walking_speed": 4}}"#.to_string()),
+                language: Some("de-DE".to_string()),
+                curvy_road_preference: Some(0.5),
+                accessibility: Some(AccessibilityOptions {
+                    avoid_stairs: true,
+                    max_incline: None,
+                    require_smooth_surface: false,
+                }),
+                avoided_areas: Some(avoided_areas),
+                departure_time: None,
+            },
+        )
+        .expect("Unable to create request generator");
+        let RouteRequest::HttpPost { body, .. } = generator
+            .generate_request(USER_LOCATION, WAYPOINTS.to_vec())
+            .expect("Unable to generate request")
+        else {
+            panic!("Expected a POST request");
+        };
+        let body_json: JsonValue = from_slice(&body).expect("Failed to parse request body as JSON");
+
+        assert_json_include!(
+            actual: body_json,
+            expected: json!({
+                "language": "de-DE",
+                "costing_options": {
+                    "pedestrian": {
+                        "walking_speed": 4,
+                        "use_highways": 0.5,
+                        "step_penalty": 3600,
+                    },
+                },
+                "exclude_locations": [{ "lat": 1.0, "lon": 2.0 }],
+            })
+        );
+    }
+
+    #[test]
+    fn request_body_with_departure_time() {
+        let generator = ValhallaHttpRequestGenerator::new(
+            ENDPOINT_URL.to_string(),
+            "multimodal".to_string(),
+            None,
+        )
+        .departing_at("2024-05-06T08:00".to_string());
+        let RouteRequest::HttpPost { body, .. } = generator
+            .generate_request(USER_LOCATION, WAYPOINTS.to_vec())
+            .expect("Unable to generate request")
+        else {
+            panic!("Expected a POST request");
+        };
+        let body_json: JsonValue = from_slice(&body).expect("Failed to parse request body as JSON");
+
+        assert_json_include!(
+            actual: body_json,
+            expected: json!({
+                "format": "json",
+                "costing": "multimodal",
+                "date_time": { "type": 1, "value": "2024-05-06T08:00" },
+            })
+        );
+    }
+
+    #[test]
+    fn invalid_costing_options_are_rejected() {
+        assert!(ValhallaHttpRequestGenerator::with_options(
+            ENDPOINT_URL.to_string(),
+            COSTING.to_string(),
+            ValhallaRequestOptions {
+                costing_options_json: Some("{".to_string()),
+                ..ValhallaRequestOptions::default()
+            },
+        )
+        .is_err());
+    }
+
     #[test]
     fn request_body_with_invalid_horizontal_accuracy() {
         let generator =
@@ -291,6 +761,8 @@ mod tests {
             course_over_ground: None,
             timestamp: SystemTime::now(),
             speed: None,
+            altitude: None,
+            vertical_accuracy: None,
         };
 
         let RouteRequest::HttpPost {
@@ -299,7 +771,10 @@ mod tests {
             body,
         } = generator
             .generate_request(location, WAYPOINTS.to_vec())
-            .unwrap();
+            .unwrap()
+        else {
+            panic!("Expected a POST request");
+        };
 
         assert_eq!(ENDPOINT_URL, request_url);
         assert_eq!(headers["Content-Type"], "application/json".to_string());
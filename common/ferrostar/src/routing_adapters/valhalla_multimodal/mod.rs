@@ -0,0 +1,439 @@
+pub(crate) mod models;
+
+use super::RouteResponseParser;
+use crate::algorithms::decode_polyline;
+use crate::clock::Clock;
+use crate::models::{
+    GeographicCoordinate, RouteStep, TransitLeg, TransitMode, TransitStop, Waypoint, WaypointKind,
+};
+use crate::now;
+use crate::routing_adapters::{
+    error::{ResponseIndex, RoutingBackendError},
+    valhalla_multimodal::models::{Maneuver, RouteResponse, TransitInfo},
+    validation::{validate_geometry, validate_leg_steps},
+    Route, RoutingResponseParseError,
+};
+use geo::{BoundingRect, LineString, Point};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+/// Valhalla always encodes native shapes as polyline6.
+const VALHALLA_POLYLINE_PRECISION: u32 = 6;
+
+/// A response parser for multimodal (public transit) routes
+/// from the [Valhalla](https://valhalla.github.io/valhalla/) native route API.
+///
+/// The OSRM response format does not include any transit details,
+/// so the request must use `"format": "json"`
+/// (see [`crate::routing_adapters::valhalla::ValhallaHttpRequestGenerator::departing_at`]).
+///
+/// Each maneuver becomes a step, and every transit maneuver has a [`TransitLeg`] attached.
+/// The stops where the user boards and alights are included in the route waypoints
+/// as [`WaypointKind::TransitBoard`] and [`WaypointKind::TransitAlight`].
+///
+/// Valhalla reports stop times in local time, without an offset from UTC.
+/// The offset of the origin is used, which Valhalla includes in the response
+/// when the request has a `date_time`.
+/// Otherwise, the offset must be set with [`ValhallaMultimodalResponseParser::with_utc_offset`]
+/// (usually from the time zone used for the request),
+/// or the stop times are omitted.
+#[derive(Default)]
+pub struct ValhallaMultimodalResponseParser {
+    /// The offset of the local time from UTC, in seconds,
+    /// for responses which do not include one.
+    utc_offset: Option<i32>,
+    /// The clock for [`Route::planned_at`] (the system clock when this is `None`).
+    clock: Option<Arc<dyn Clock>>,
+}
+
+impl ValhallaMultimodalResponseParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the offset of the local time from UTC (in seconds, ex: `-14400` for UTC-4),
+    /// which is used for the stop times when the response does not include an offset.
+    #[must_use]
+    pub fn with_utc_offset(mut self, seconds: i32) -> Self {
+        self.utc_offset = Some(seconds);
+        self
+    }
+
+    /// Sets the clock used to stamp [`Route::planned_at`] on parsed routes,
+    /// which should be the same one as in the navigation controller's config.
+    #[must_use]
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = Some(clock);
+        self
+    }
+}
+
+impl RouteResponseParser for ValhallaMultimodalResponseParser {
+    fn parse_response(&self, response: Vec<u8>) -> Result<Vec<Route>, RoutingResponseParseError> {
+        let res: RouteResponse = serde_json::from_slice(&response)?;
+
+        let Some(trip) = res.trip else {
+            return Err(match res.error_code {
+                Some(code) => RoutingResponseParseError::BackendError {
+                    error: backend_error(code),
+                    message: res.error,
+                },
+                None => RoutingResponseParseError::NoRoutes,
+            });
+        };
+
+        let index = ResponseIndex::route(0);
+        let utc_offset = trip
+            .locations
+            .first()
+            .and_then(|location| location.time_zone_offset.as_deref())
+            .and_then(parse_utc_offset)
+            .or(self.utc_offset.map(i64::from));
+        let meters_per_unit = trip.meters_per_unit();
+
+        let mut waypoints = vec![];
+        let mut geometry: Vec<GeographicCoordinate> = vec![];
+        let mut steps = vec![];
+        for (leg_index, leg) in trip.legs.iter().enumerate() {
+            let leg_geometry = decode_polyline(&leg.shape, VALHALLA_POLYLINE_PRECISION)
+                .map_err(|error| RoutingResponseParseError::GeometryDecodeError {
+                    index: index.leg(leg_index),
+                    error,
+                })?
+                .coords()
+                .map(|coord| GeographicCoordinate::from(*coord))
+                .collect::<Vec<_>>();
+            let (Some(leg_start), Some(leg_end)) = (leg_geometry.first(), leg_geometry.last())
+            else {
+                return Err(RoutingResponseParseError::EmptyGeometry {
+                    index: index.leg(leg_index),
+                });
+            };
+
+            // Consecutive legs share an endpoint; avoid duplicating it in the overall geometry
+            let skip = usize::from(geometry.last() == Some(leg_start));
+            geometry.extend(leg_geometry.iter().skip(skip));
+            if waypoints.is_empty() {
+                waypoints.push(Waypoint {
+                    coordinate: *leg_start,
+                    kind: WaypointKind::Break,
+                });
+            }
+
+            let mut leg_steps = vec![];
+            for maneuver in &leg.maneuvers {
+                let transit_leg = maneuver
+                    .transit_info
+                    .as_ref()
+                    .filter(|_| maneuver.travel_mode.as_deref() == Some("transit"))
+                    .map(|transit_info| transit_leg(maneuver, transit_info, utc_offset));
+                if let Some(transit_leg) = &transit_leg {
+                    if let (Some(board), Some(alight)) =
+                        (transit_leg.stops.first(), transit_leg.stops.last())
+                    {
+                        waypoints.push(Waypoint {
+                            coordinate: board.coordinate,
+                            kind: WaypointKind::TransitBoard,
+                        });
+                        waypoints.push(Waypoint {
+                            coordinate: alight.coordinate,
+                            kind: WaypointKind::TransitAlight,
+                        });
+                    }
+                }
+                leg_steps.push(maneuver_step(
+                    maneuver,
+                    maneuver_geometry(&leg_geometry, maneuver),
+                    meters_per_unit,
+                    transit_leg,
+                ));
+            }
+            validate_leg_steps(&leg_steps, index.leg(leg_index))?;
+            steps.extend(leg_steps);
+
+            waypoints.push(Waypoint {
+                coordinate: *leg_end,
+                kind: WaypointKind::Break,
+            });
+        }
+
+        validate_geometry(&geometry, index)?;
+        let linestring: LineString = geometry.iter().map(|coord| Point::from(*coord)).collect();
+        let Some(bbox) = linestring.bounding_rect() else {
+            return Err(RoutingResponseParseError::EmptyGeometry { index });
+        };
+        Ok(vec![Route {
+            geometry,
+            bbox: bbox.into(),
+            distance: trip.summary.length * meters_per_unit,
+            duration: trip.summary.time,
+            weight: None,
+            weight_name: None,
+            waypoints,
+            steps,
+            elevation_profile: None,
+            incidents: vec![],
+            charging_stops: vec![],
+            destination_details: None,
+            notices: vec![],
+            planned_at: Some(self.clock.as_ref().map_or_else(now, |clock| clock.now())),
+            extras: HashMap::new(),
+        }])
+    }
+}
+
+/// Maps a [Valhalla error code](https://valhalla.github.io/valhalla/api/turn-by-turn/api-reference/#http-status-codes-and-conditions).
+fn backend_error(code: u32) -> RoutingBackendError {
+    match code {
+        442 => RoutingBackendError::NoRoute,
+        171 => RoutingBackendError::NoSegment,
+        150 | 154 => RoutingBackendError::TooBig,
+        code => RoutingBackendError::Other {
+            code: code.to_string(),
+        },
+    }
+}
+
+/// Parses an offset from UTC (ex: `-04:00`), in seconds.
+fn parse_utc_offset(offset: &str) -> Option<i64> {
+    let (sign, offset) = if let Some(offset) = offset.strip_prefix('-') {
+        (-1, offset)
+    } else {
+        (1, offset.strip_prefix('+').unwrap_or(offset))
+    };
+    let (hours, minutes) = offset.split_once(':').unwrap_or((offset, "0"));
+    let hours: i64 = hours.parse().ok()?;
+    let minutes: i64 = minutes.parse().ok()?;
+    Some(sign * (hours * 3_600 + minutes * 60))
+}
+
+/// Parses a local date and time (ex: `2024-05-06T08:15`) with an offset from UTC (in seconds).
+fn parse_local_time(date_time: &str, utc_offset: i64) -> Option<SystemTime> {
+    let (date, time) = date_time.split_once('T')?;
+    let mut date = date.splitn(3, '-').map(str::parse::<i64>);
+    let (year, month, day) = (date.next()?.ok()?, date.next()?.ok()?, date.next()?.ok()?);
+    let mut time = time.splitn(3, ':').map(str::parse::<i64>);
+    let (hour, minute) = (time.next()?.ok()?, time.next()?.ok()?);
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) || hour > 23 || minute > 59 {
+        return None;
+    }
+
+    let seconds = days_from_civil(year, month, day) * 86_400 + hour * 3_600 + minute * 60;
+    // Timestamps beyond what the platform can represent are discarded
+    u64::try_from(seconds - utc_offset)
+        .ok()
+        .and_then(|seconds| SystemTime::UNIX_EPOCH.checked_add(Duration::from_secs(seconds)))
+}
+
+/// The number of days from the Unix epoch to a date in the proleptic Gregorian calendar.
+///
+/// See <http://howardhinnant.github.io/date_algorithms.html#days_from_civil>.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    // Days since March 1st, so that the leap day is at the end of the year
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// The geometry of a maneuver within its leg.
+fn maneuver_geometry(
+    leg_geometry: &[GeographicCoordinate],
+    maneuver: &Maneuver,
+) -> Vec<GeographicCoordinate> {
+    let end = maneuver
+        .end_shape_index
+        .min(leg_geometry.len().saturating_sub(1));
+    let mut geometry = leg_geometry
+        .get(maneuver.begin_shape_index..=end)
+        .map_or_else(Vec::new, <[GeographicCoordinate]>::to_vec);
+    // The arrival maneuver is a single point, but a step needs at least two points to form a line
+    if let [point] = geometry[..] {
+        geometry.push(point);
+    }
+    geometry
+}
+
+fn transit_mode(travel_type: Option<&str>) -> TransitMode {
+    match travel_type {
+        Some("tram") => TransitMode::Tram,
+        Some("metro") => TransitMode::Subway,
+        Some("rail") => TransitMode::Rail,
+        Some("bus") => TransitMode::Bus,
+        Some("ferry") => TransitMode::Ferry,
+        Some("cable_car") => TransitMode::CableCar,
+        Some("gondola") => TransitMode::Gondola,
+        Some("funicular") => TransitMode::Funicular,
+        _ => TransitMode::Other,
+    }
+}
+
+fn transit_leg(
+    maneuver: &Maneuver,
+    transit_info: &TransitInfo,
+    utc_offset: Option<i64>,
+) -> TransitLeg {
+    let local_time = |date_time: &Option<String>| {
+        date_time
+            .as_deref()
+            .zip(utc_offset)
+            .and_then(|(date_time, utc_offset)| parse_local_time(date_time, utc_offset))
+    };
+    let stops: Vec<TransitStop> = transit_info
+        .transit_stops
+        .iter()
+        .map(|stop| TransitStop {
+            name: stop.name.clone(),
+            coordinate: GeographicCoordinate {
+                lat: stop.lat,
+                lng: stop.lon,
+            },
+            arrival_time: local_time(&stop.arrival_date_time),
+            departure_time: local_time(&stop.departure_date_time),
+        })
+        .collect();
+
+    TransitLeg {
+        mode: transit_mode(maneuver.travel_type.as_deref()),
+        line_name: transit_info
+            .short_name
+            .clone()
+            .or_else(|| transit_info.long_name.clone()),
+        headsign: transit_info.headsign.clone(),
+        departure_time: stops.first().and_then(|stop| stop.departure_time),
+        arrival_time: stops.last().and_then(|stop| stop.arrival_time),
+        stops,
+    }
+}
+
+fn maneuver_step(
+    maneuver: &Maneuver,
+    geometry: Vec<GeographicCoordinate>,
+    meters_per_unit: f64,
+    transit_leg: Option<TransitLeg>,
+) -> RouteStep {
+    let is_ferry = transit_leg
+        .as_ref()
+        .is_some_and(|transit_leg| transit_leg.mode == TransitMode::Ferry);
+    RouteStep {
+        geometry,
+        distance: maneuver.length * meters_per_unit,
+        duration: maneuver.time,
+        road_name: maneuver
+            .street_names
+            .first()
+            .cloned()
+            .filter(|_| transit_leg.is_none()),
+        instruction: maneuver.instruction.clone(),
+        visual_instructions: vec![],
+        spoken_instructions: vec![],
+        transit_leg,
+        has_tolls: false,
+        is_ferry,
+        tunnels: vec![],
+        country_changes: vec![],
+        speed_limit_sign: None,
+        speed_limit_unit: None,
+        driving_side: None,
+        intersections: vec![],
+        junction_views: vec![],
+        has_crosswalk: false,
+        has_stairs: false,
+        has_elevator: false,
+        shields: vec![],
+        extras: HashMap::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+
+    /// A walk to a bus stop, a bus ride, and a walk to the destination, in Portland (UTC-8 in winter).
+    const VALHALLA_MULTIMODAL_RESPONSE: &str = r#"{"trip":{"locations":[{"type":"break","lat":45.52,"lon":-122.68,"date_time":"2023-11-14T14:13","time_zone_offset":"-08:00","time_zone_name":"America/Los_Angeles","original_index":0},{"type":"break","lat":45.5262,"lon":-122.6765,"original_index":1}],"legs":[{"maneuvers":[{"type":1,"instruction":"Walk north on SW 5th Avenue.","street_names":["SW 5th Avenue"],"time":120.0,"length":0.134,"begin_shape_index":0,"end_shape_index":2,"travel_mode":"pedestrian","travel_type":"foot"},{"type":30,"instruction":"Take the 20 toward Gresham TC.","time":600.0,"length":0.69,"begin_shape_index":2,"end_shape_index":5,"travel_mode":"transit","travel_type":"bus","transit_info":{"short_name":"20","long_name":"Burnside/Stark","headsign":"Gresham TC","transit_stops":[{"type":"stop","name":"SW 5th & Oak","lat":45.5205,"lon":-122.679,"departure_date_time":"2023-11-14T14:16"},{"type":"stop","name":"W Burnside & 4th","lat":45.523,"lon":-122.679,"arrival_date_time":"2023-11-14T14:20","departure_date_time":"2023-11-14T14:20"},{"type":"stop","name":"NE Sandy & 12th","lat":45.526,"lon":-122.677,"arrival_date_time":"2023-11-14T14:26"}]}},{"type":1,"instruction":"Walk east on NE Sandy Boulevard.","street_names":["NE Sandy Boulevard"],"time":60.0,"length":0.044,"begin_shape_index":5,"end_shape_index":6,"travel_mode":"pedestrian","travel_type":"foot"},{"type":4,"instruction":"You have arrived at your destination.","time":0.0,"length":0.0,"begin_shape_index":6,"end_shape_index":6,"travel_mode":"pedestrian","travel_type":"foot"}],"summary":{"length":0.868,"time":900.0},"shape":"_giyuA~jx~hFg^??o}@g{C?ozD??_|BoKg^"}],"summary":{"length":0.868,"time":900.0},"status_message":"Found route between points","status":0,"units":"kilometers","language":"en-US"}}"#;
+
+    fn parser() -> ValhallaMultimodalResponseParser {
+        let clock = MockClock::new(SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000));
+        ValhallaMultimodalResponseParser::new().with_clock(Arc::new(clock))
+    }
+
+    #[test]
+    fn parse_multimodal_trip() {
+        let routes = parser()
+            .parse_response(VALHALLA_MULTIMODAL_RESPONSE.into())
+            .expect("Unable to parse Valhalla response");
+        insta::assert_yaml_snapshot!(routes);
+    }
+
+    #[test]
+    fn stop_times_use_the_local_offset() {
+        let routes = parser()
+            .parse_response(VALHALLA_MULTIMODAL_RESPONSE.into())
+            .expect("Unable to parse Valhalla response");
+        let transit_leg = routes[0].steps[1]
+            .transit_leg
+            .as_ref()
+            .expect("Expected a transit leg");
+        // 14:16 in UTC-8 is 22:16 UTC
+        assert_eq!(
+            transit_leg.departure_time,
+            Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_160))
+        );
+        assert_eq!(
+            transit_leg.arrival_time,
+            Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_760))
+        );
+    }
+
+    #[test]
+    fn stop_times_fall_back_to_the_configured_offset() {
+        let response = VALHALLA_MULTIMODAL_RESPONSE.replace(r#""time_zone_offset":"-08:00","#, "");
+        let departure_time = |parser: ValhallaMultimodalResponseParser| {
+            let routes = parser
+                .parse_response(response.clone().into())
+                .expect("Unable to parse Valhalla response");
+            routes[0].steps[1]
+                .transit_leg
+                .as_ref()
+                .and_then(|transit_leg| transit_leg.departure_time)
+        };
+
+        assert_eq!(departure_time(parser()), None);
+        assert_eq!(
+            departure_time(parser().with_utc_offset(-8 * 3_600)),
+            Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_160))
+        );
+    }
+
+    #[test]
+    fn parse_local_times() {
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(days_from_civil(2000, 3, 1), 11_017);
+        assert_eq!(parse_utc_offset("+05:30"), Some(19_800));
+        assert_eq!(parse_utc_offset("-04:00"), Some(-14_400));
+        assert_eq!(
+            parse_local_time("2023-11-14T22:13", 0),
+            Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1_699_999_980))
+        );
+        assert_eq!(parse_local_time("2023-13-14T22:13", 0), None);
+        assert_eq!(parse_local_time("yesterday", 0), None);
+    }
+
+    #[test]
+    fn parse_backend_error() {
+        let result = parser().parse_response(
+            r#"{"error_code":442,"error":"No path could be found for input","status_code":400,"status":"Bad Request"}"#.into(),
+        );
+        assert!(matches!(
+            result,
+            Err(RoutingResponseParseError::BackendError {
+                error: RoutingBackendError::NoRoute,
+                ..
+            })
+        ));
+    }
+}
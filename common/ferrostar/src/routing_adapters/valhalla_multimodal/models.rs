@@ -0,0 +1,121 @@
+//! Valhalla models from the native (`"format": "json"`) route API: <https://valhalla.github.io/valhalla/api/turn-by-turn/api-reference/#outputs-of-a-route>
+//!
+//! We omit most fields which are not needed for navigation.
+
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug)]
+pub struct RouteResponse {
+    /// The trip; absent when an error occurred.
+    pub trip: Option<Trip>,
+    /// A machine-readable error code (ex: `442`).
+    pub error_code: Option<u32>,
+    /// A human-readable error message.
+    pub error: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Trip {
+    /// The locations of the request, with any details added by Valhalla.
+    #[serde(default)]
+    pub locations: Vec<Location>,
+    /// One leg between each pair of break locations.
+    pub legs: Vec<Leg>,
+    pub summary: Summary,
+    /// The units of all lengths (`kilometers` or `miles`).
+    pub units: Option<String>,
+}
+
+impl Trip {
+    /// The number of meters in one unit of length.
+    pub fn meters_per_unit(&self) -> f64 {
+        match self.units.as_deref() {
+            Some("miles") => 1_609.344,
+            _ => 1_000.0,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Location {
+    /// The offset of the local time zone from UTC (ex: `-04:00`).
+    ///
+    /// Valhalla only includes this when the request has a `date_time`.
+    pub time_zone_offset: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Leg {
+    pub maneuvers: Vec<Maneuver>,
+    /// The leg geometry, as a polyline with a precision of 6 digits.
+    pub shape: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Summary {
+    /// The total length, in [`Trip::units`].
+    pub length: f64,
+    /// The total duration (including any waiting time), in seconds.
+    pub time: f64,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Maneuver {
+    pub instruction: String,
+    #[serde(default)]
+    pub street_names: Vec<String>,
+    /// The duration of the maneuver, in seconds.
+    pub time: f64,
+    /// The length of the maneuver, in [`Trip::units`].
+    pub length: f64,
+    /// The index of the leg geometry point at which the maneuver starts.
+    pub begin_shape_index: usize,
+    /// The index of the leg geometry point at which the maneuver ends.
+    pub end_shape_index: usize,
+    /// The mode of travel (ex: `pedestrian` or `transit`).
+    pub travel_mode: Option<String>,
+    /// The type of vehicle (ex: `foot`, `bus`, or `metro`).
+    pub travel_type: Option<String>,
+    /// Details of the line, which are only present for transit maneuvers.
+    pub transit_info: Option<TransitInfo>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct TransitInfo {
+    pub short_name: Option<String>,
+    pub long_name: Option<String>,
+    pub headsign: Option<String>,
+    /// All stops on the maneuver, starting with the boarding stop and ending with the alighting stop.
+    #[serde(default)]
+    pub transit_stops: Vec<TransitStop>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct TransitStop {
+    pub name: Option<String>,
+    pub lat: f64,
+    pub lon: f64,
+    /// The scheduled arrival time, in local time without an offset (ex: `2024-05-06T08:15`).
+    pub arrival_date_time: Option<String>,
+    /// The scheduled departure time, in local time without an offset (ex: `2024-05-06T08:16`).
+    pub departure_date_time: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deserialize_error() {
+        let data = r#"{
+            "error_code": 442,
+            "error": "No path could be found for input",
+            "status_code": 400,
+            "status": "Bad Request"
+        }"#;
+
+        let response: RouteResponse = serde_json::from_str(data).expect("Failed to parse response");
+        assert!(response.trip.is_none());
+        assert_eq!(response.error_code, Some(442));
+    }
+}
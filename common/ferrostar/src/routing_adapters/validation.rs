@@ -0,0 +1,149 @@
+//! Sanity checks for parsed routes.
+//!
+//! Routing backends occasionally return garbage (ex: swapped coordinates or mangled polylines),
+//! and it is far better to reject these up front than to navigate along them.
+
+use crate::models::{GeographicCoordinate, RouteStep};
+use crate::routing_adapters::error::{ResponseIndex, RoutingResponseParseError};
+use geo::{HaversineDistance, Point};
+
+/// The maximum gap between the end of a step and the start of the next, in meters.
+///
+/// Steps normally share their endpoint exactly, but some backends round the two differently.
+const MAX_STEP_GAP: f64 = 1.0;
+
+/// Validates that a geometry is non-empty and that every coordinate is within range.
+pub(crate) fn validate_geometry(
+    geometry: &[GeographicCoordinate],
+    index: ResponseIndex,
+) -> Result<(), RoutingResponseParseError> {
+    if geometry.is_empty() {
+        return Err(RoutingResponseParseError::EmptyGeometry { index });
+    }
+
+    match geometry.iter().find(|coordinate| !is_valid(coordinate)) {
+        Some(coordinate) => Err(RoutingResponseParseError::InvalidCoordinate {
+            index,
+            lat: coordinate.lat,
+            lng: coordinate.lng,
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Validates the geometry of a step, and that its distance and duration are not negative.
+pub(crate) fn validate_step(
+    step: &RouteStep,
+    index: ResponseIndex,
+) -> Result<(), RoutingResponseParseError> {
+    validate_geometry(&step.geometry, index)?;
+    // NaN fails these comparisons too
+    if !(step.distance >= 0.0 && step.duration >= 0.0) {
+        return Err(RoutingResponseParseError::InvalidStepMetrics {
+            index,
+            distance: step.distance,
+            duration: step.duration,
+        });
+    }
+    Ok(())
+}
+
+/// Validates the steps of a leg.
+///
+/// In addition to validating each step,
+/// this ensures that the steps cover the leg without gaps.
+pub(crate) fn validate_leg_steps(
+    steps: &[RouteStep],
+    index: ResponseIndex,
+) -> Result<(), RoutingResponseParseError> {
+    let mut previous_end: Option<Point> = None;
+    for (step_index, step) in steps.iter().enumerate() {
+        let index = index.step(step_index);
+        validate_step(step, index)?;
+
+        let (Some(start), Some(end)) = (step.geometry.first(), step.geometry.last()) else {
+            return Err(RoutingResponseParseError::EmptyGeometry { index });
+        };
+        if let Some(previous_end) = previous_end {
+            if previous_end.haversine_distance(&Point::from(*start)) > MAX_STEP_GAP {
+                return Err(RoutingResponseParseError::DiscontinuousSteps { index });
+            }
+        }
+        previous_end = Some(Point::from(*end));
+    }
+
+    Ok(())
+}
+
+fn is_valid(coordinate: &GeographicCoordinate) -> bool {
+    (-90.0..=90.0).contains(&coordinate.lat) && (-180.0..=180.0).contains(&coordinate.lng)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::navigation_controller::test_helpers::gen_dummy_route_step;
+
+    #[test]
+    fn geometry() {
+        let index = ResponseIndex::route(1).leg(0).step(2);
+        let valid = GeographicCoordinate {
+            lat: 60.5,
+            lng: -149.5,
+        };
+        // Swapped lat/lng are a common mistake
+        let swapped = GeographicCoordinate {
+            lat: -149.5,
+            lng: 60.5,
+        };
+
+        assert!(validate_geometry(&[valid], index).is_ok());
+        assert!(matches!(
+            validate_geometry(&[], index),
+            Err(RoutingResponseParseError::EmptyGeometry { index: actual }) if actual == index
+        ));
+        let error = validate_geometry(&[valid, swapped], index).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Invalid coordinate (-149.5, 60.5) in route 1, leg 0, step 2."
+        );
+    }
+
+    #[test]
+    fn leg_steps() {
+        let index = ResponseIndex::route(0).leg(1);
+        let first = gen_dummy_route_step(0.0, 0.0, 0.001, 0.0);
+        let second = gen_dummy_route_step(0.001, 0.0, 0.002, 0.0);
+        let detached = gen_dummy_route_step(0.003, 0.0, 0.004, 0.0);
+        let invalid = gen_dummy_route_step(0.002, 0.0, 200.0, 0.0);
+
+        assert!(validate_leg_steps(&[first.clone(), second.clone()], index).is_ok());
+        assert!(matches!(
+            validate_leg_steps(&[first.clone(), second.clone(), detached], index),
+            Err(RoutingResponseParseError::DiscontinuousSteps { index: actual }) if actual == index.step(2)
+        ));
+        assert!(matches!(
+            validate_leg_steps(&[first, second, invalid], index),
+            Err(RoutingResponseParseError::InvalidCoordinate { index: actual, .. }) if actual == index.step(2)
+        ));
+    }
+
+    #[test]
+    fn step_metrics() {
+        let index = ResponseIndex::route(0).leg(0).step(0);
+        let step = gen_dummy_route_step(0.0, 0.0, 0.001, 0.0);
+        assert!(validate_step(&step, index).is_ok());
+
+        for (distance, duration) in [(-1.0, 10.0), (10.0, -1.0), (f64::NAN, 10.0)] {
+            let invalid = RouteStep {
+                distance,
+                duration,
+                ..step.clone()
+            };
+            assert!(matches!(
+                validate_step(&invalid, index),
+                Err(RoutingResponseParseError::InvalidStepMetrics { .. })
+            ));
+        }
+    }
+}
@@ -0,0 +1,524 @@
+//! Searching for places along the remainder of the route (ex: the next gas station).
+//!
+//! The remaining route geometry is buffered into a corridor polygon,
+//! which is passed to a [`PoiProvider`] along with the category to search for.
+//! Each result is then routed to (via the [`RouteAdapter`]) to estimate how much time
+//! stopping there would add to the trip.
+
+use crate::models::{GeographicCoordinate, Polygon, Route, Waypoint, WaypointKind};
+use crate::navigation_controller::models::{TripProgress, TripState};
+use crate::routing_adapters::error::RouteFetchError;
+use crate::routing_adapters::http::{spawn_blocking, CancellationToken, HttpExecutor};
+use crate::routing_adapters::RouteAdapter;
+use geo::{
+    HaversineBearing, HaversineDestination, HaversineDistance, HaversineIntermediate, LineString,
+    Point, Simplify,
+};
+use std::cmp::Ordering;
+use std::sync::Arc;
+use uniffi::UnexpectedUniFFICallbackError;
+
+/// The longest distance that the corridor may be offset from the route line at a sharp turn,
+/// as a multiple of the buffer distance.
+const MAX_MITER_RATIO: f64 = 2.0;
+
+/// The approximate length of a degree of latitude, in meters.
+const METERS_PER_DEGREE: f64 = 111_320.0;
+
+/// A category of points of interest.
+#[derive(Debug, Clone, PartialEq, Eq, uniffi::Enum)]
+pub enum PoiCategory {
+    /// Gas stations.
+    Fuel,
+    /// Electric vehicle charging stations.
+    EvCharging,
+    /// Restaurants, cafes, and other places to eat.
+    Food,
+    /// Any other category supported by the provider.
+    Other { name: String },
+}
+
+/// A place found by a [`PoiProvider`].
+#[derive(Debug, Clone, PartialEq, uniffi::Record)]
+pub struct PointOfInterest {
+    /// An identifier for the place which is unique within the provider.
+    pub id: String,
+    pub name: String,
+    pub coordinate: GeographicCoordinate,
+    pub category: PoiCategory,
+}
+
+/// Options for [`SearchAlongRoute::search`].
+#[derive(Debug, Clone, PartialEq, uniffi::Record)]
+pub struct SearchAlongRouteOptions {
+    pub category: PoiCategory,
+    /// The maximum distance from the route line, in meters.
+    pub max_distance_from_route: f64,
+    /// How far ahead along the route to search, in meters.
+    ///
+    /// The entire remainder of the route is searched when this is `None`.
+    pub max_distance_ahead: Option<f64>,
+    /// The maximum number of results.
+    ///
+    /// A route is requested for every result, so this should be kept small.
+    pub limit: u32,
+}
+
+/// A point of interest along the route.
+#[derive(Debug, Clone, PartialEq, uniffi::Record)]
+pub struct SearchAlongRouteResult {
+    pub poi: PointOfInterest,
+    /// The additional travel time to stop at the point of interest, in seconds.
+    ///
+    /// This is `None` if no route could be found via the point of interest.
+    pub detour_duration: Option<f64>,
+    /// The additional distance to stop at the point of interest, in meters.
+    pub detour_distance: Option<f64>,
+}
+
+#[derive(Debug, thiserror::Error, uniffi::Error)]
+pub enum PoiSearchError {
+    #[error("The point of interest search failed: {error}.")]
+    SearchFailed { error: String },
+    #[error("The search was cancelled.")]
+    Cancelled,
+    #[error("An unknown error searching for points of interest was raised in foreign code.")]
+    UnknownError,
+}
+
+impl From<UnexpectedUniFFICallbackError> for PoiSearchError {
+    fn from(_: UnexpectedUniFFICallbackError) -> PoiSearchError {
+        PoiSearchError::UnknownError
+    }
+}
+
+/// A source of points of interest (ex: a places API or an offline database).
+///
+/// This is called from a background thread, so implementations may block on network requests.
+#[uniffi::export(with_foreign)]
+pub trait PoiProvider: Send + Sync {
+    /// Finds up to `limit` places of the category within the area.
+    ///
+    /// Results should be ordered from most to least relevant.
+    fn search(
+        &self,
+        area: Polygon,
+        category: PoiCategory,
+        limit: u32,
+    ) -> Result<Vec<PointOfInterest>, PoiSearchError>;
+}
+
+/// Buffers a line into a polygon covering everything within `buffer_distance` meters of it.
+///
+/// The line is simplified first, so the polygon has a manageable number of vertices
+/// (many POI APIs limit the size of the search area).
+/// The buffer is approximate; it may slightly overshoot on the outside of turns,
+/// and the ends are squared off.
+/// Returns `None` if the line has fewer than two distinct coordinates.
+pub fn route_corridor(geometry: &[GeographicCoordinate], buffer_distance: f64) -> Option<Polygon> {
+    // NOTE: Simplification requires at least two points
+    if geometry.len() < 2 {
+        return None;
+    }
+    let line: LineString = geometry.iter().map(|coord| Point::from(*coord)).collect();
+    let mut points: Vec<Point> = line
+        .simplify(&(buffer_distance / 4.0 / METERS_PER_DEGREE))
+        .points()
+        .collect();
+    points.dedup();
+    let (&first, &last) = (points.first()?, points.last()?);
+    if points.len() < 2 {
+        return None;
+    }
+
+    let bearings: Vec<f64> = points
+        .windows(2)
+        .map(|pair| pair[0].haversine_bearing(pair[1]))
+        .collect();
+    let mut left = Vec::with_capacity(points.len());
+    let mut right = Vec::with_capacity(points.len());
+    for (i, point) in points.iter().enumerate() {
+        let incoming = bearings[i.saturating_sub(1)];
+        let outgoing = bearings[i.min(bearings.len() - 1)];
+        let half_turn = ((outgoing - incoming + 540.0) % 360.0 - 180.0) / 2.0;
+        let bearing = incoming + half_turn;
+        // Offset the corners farther so that the sides stay parallel to the route
+        let offset =
+            (buffer_distance / half_turn.to_radians().cos()).min(buffer_distance * MAX_MITER_RATIO);
+        left.push(point.haversine_destination(bearing - 90.0, offset));
+        right.push(point.haversine_destination(bearing + 90.0, offset));
+    }
+
+    let square_cap = |point: Point, bearing: f64| {
+        [bearing - 45.0, bearing + 45.0].map(|bearing| {
+            point.haversine_destination(bearing, buffer_distance * std::f64::consts::SQRT_2)
+        })
+    };
+    let [start_right, start_left] = square_cap(first, bearings[0] + 180.0);
+    let [end_left, end_right] = square_cap(last, bearings[bearings.len() - 1]);
+
+    let mut exterior = vec![start_left];
+    exterior.extend(left);
+    exterior.extend([end_left, end_right]);
+    exterior.extend(right.into_iter().rev());
+    exterior.extend([start_right, start_left]);
+
+    Some(Polygon {
+        exterior: exterior
+            .into_iter()
+            .map(GeographicCoordinate::from)
+            .collect(),
+        interiors: vec![],
+    })
+}
+
+/// Truncates a line after the given distance, in meters.
+fn truncate_line(
+    geometry: &[GeographicCoordinate],
+    max_distance: f64,
+) -> Vec<GeographicCoordinate> {
+    let Some(&first) = geometry.first() else {
+        return vec![];
+    };
+
+    let mut result = vec![first];
+    let mut distance = 0.0;
+    for pair in geometry.windows(2) {
+        let (a, b) = (Point::from(pair[0]), Point::from(pair[1]));
+        let segment_length = a.haversine_distance(&b);
+        if segment_length > 0.0 && distance + segment_length >= max_distance {
+            let fraction = (max_distance - distance) / segment_length;
+            result.push(a.haversine_intermediate(&b, fraction).into());
+            break;
+        }
+        distance += segment_length;
+        result.push(pair[1]);
+    }
+    result
+}
+
+/// Computes the detour of a route via a point of interest compared to the current route.
+fn calculate_detour(route: &Route, progress: &TripProgress) -> (f64, f64) {
+    let duration: f64 = route.steps.iter().map(|step| step.duration).sum();
+    (
+        duration - progress.duration_remaining,
+        route.distance - progress.distance_remaining,
+    )
+}
+
+/// Searches for points of interest along the remainder of the route.
+#[derive(uniffi::Object)]
+pub struct SearchAlongRoute {
+    poi_provider: Arc<dyn PoiProvider>,
+    route_adapter: Arc<RouteAdapter>,
+    executor: Arc<HttpExecutor>,
+}
+
+#[uniffi::export]
+impl SearchAlongRoute {
+    #[uniffi::constructor]
+    pub fn new(
+        poi_provider: Arc<dyn PoiProvider>,
+        route_adapter: Arc<RouteAdapter>,
+        executor: Arc<HttpExecutor>,
+    ) -> Self {
+        Self {
+            poi_provider,
+            route_adapter,
+            executor,
+        }
+    }
+
+    /// Searches for points of interest within a corridor around the rest of the route,
+    /// and estimates the detour to each one.
+    ///
+    /// A route from the user's location to the remaining waypoints via each point of interest
+    /// is requested, and compared with the trip progress.
+    /// Results are ordered by detour duration, with any that could not be routed to last.
+    /// Returns no results if the trip is complete.
+    pub async fn search(
+        &self,
+        state: TripState,
+        options: SearchAlongRouteOptions,
+        cancellation_token: Option<Arc<CancellationToken>>,
+    ) -> Result<Vec<SearchAlongRouteResult>, PoiSearchError> {
+        let TripState::Navigating {
+            snapped_user_location,
+            remaining_steps,
+            remaining_waypoints,
+            progress,
+            ..
+        } = state
+        else {
+            return Ok(vec![]);
+        };
+
+        // The current step starts behind the user, so only the part remaining is included
+        let mut geometry = remaining_steps
+            .first()
+            .map(|step| {
+                let reversed: Vec<_> = step.geometry.iter().rev().copied().collect();
+                let mut remaining = truncate_line(&reversed, progress.distance_to_next_maneuver);
+                remaining.reverse();
+                remaining
+            })
+            .unwrap_or_default();
+        geometry.extend(
+            remaining_steps
+                .iter()
+                .skip(1)
+                .flat_map(|step| step.geometry.iter().copied()),
+        );
+        if let Some(max_distance_ahead) = options.max_distance_ahead {
+            geometry = truncate_line(&geometry, max_distance_ahead);
+        }
+        let Some(corridor) = route_corridor(&geometry, options.max_distance_from_route) else {
+            return Ok(vec![]);
+        };
+
+        let provider = self.poi_provider.clone();
+        let category = options.category;
+        let limit = options.limit;
+        let pois = spawn_blocking(move || provider.search(corridor, category, limit))
+            .await
+            .map_err(|error| PoiSearchError::SearchFailed {
+                error: error.to_string(),
+            })??;
+        if cancellation_token
+            .as_ref()
+            .is_some_and(|token| token.is_cancelled())
+        {
+            return Err(PoiSearchError::Cancelled);
+        }
+
+        let mut results = Vec::with_capacity(pois.len());
+        for poi in pois.into_iter().take(options.limit as usize) {
+            let waypoints = std::iter::once(Waypoint {
+                coordinate: poi.coordinate,
+                kind: WaypointKind::Break,
+            })
+            .chain(remaining_waypoints.iter().copied())
+            .collect();
+            let detour = match self
+                .route_adapter
+                .get_route(
+                    self.executor.clone(),
+                    snapped_user_location,
+                    waypoints,
+                    cancellation_token.clone(),
+                )
+                .await
+            {
+                Ok(route) => Some(calculate_detour(&route, &progress)),
+                Err(RouteFetchError::Cancelled) => return Err(PoiSearchError::Cancelled),
+                Err(_) => None,
+            };
+            results.push(SearchAlongRouteResult {
+                poi,
+                detour_duration: detour.map(|(duration, _)| duration),
+                detour_distance: detour.map(|(_, distance)| distance),
+            });
+        }
+
+        results.sort_by(|a, b| match (a.detour_duration, b.detour_duration) {
+            (Some(a), Some(b)) => a.total_cmp(&b),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        });
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::UserLocation;
+    use crate::navigation_controller::models::NavigationControllerConfig;
+    use crate::navigation_controller::presets::NavigationPreset;
+    use crate::navigation_controller::test_helpers::{
+        gen_dummy_route_step, gen_route_from_steps, gen_user_location,
+    };
+    use crate::navigation_controller::NavigationController;
+    use crate::routing_adapters::http::test_helpers::{
+        block_on, executor, MockHttpClient, OSRM_RESPONSE,
+    };
+    use crate::routing_adapters::http::HttpError;
+    use crate::{create_osrm_response_parser, create_valhalla_request_generator};
+    use geo::Contains;
+    use std::sync::Mutex;
+    use std::time::SystemTime;
+
+    #[derive(Default)]
+    struct RecordingProvider {
+        areas: Mutex<Vec<Polygon>>,
+    }
+
+    impl PoiProvider for RecordingProvider {
+        fn search(
+            &self,
+            area: Polygon,
+            category: PoiCategory,
+            limit: u32,
+        ) -> Result<Vec<PointOfInterest>, PoiSearchError> {
+            self.areas.lock().expect("Unable to lock areas").push(area);
+            Ok((0..limit)
+                .map(|i| PointOfInterest {
+                    id: i.to_string(),
+                    name: format!("Station {i}"),
+                    coordinate: GeographicCoordinate {
+                        lat: 0.0001,
+                        lng: 0.005,
+                    },
+                    category: category.clone(),
+                })
+                .collect())
+        }
+    }
+
+    fn to_geo(polygon: &Polygon) -> geo::Polygon {
+        geo::Polygon::new(
+            polygon
+                .exterior
+                .iter()
+                .map(|coord| Point::from(*coord))
+                .collect(),
+            vec![],
+        )
+    }
+
+    fn search_along_route(
+        provider: Arc<RecordingProvider>,
+        client: Arc<MockHttpClient>,
+    ) -> SearchAlongRoute {
+        SearchAlongRoute::new(
+            provider,
+            Arc::new(RouteAdapter::new(
+                create_valhalla_request_generator(
+                    "https://valhalla.example.com/route".to_string(),
+                    "auto".to_string(),
+                    None,
+                )
+                .expect("Unable to create request generator"),
+                create_osrm_response_parser(6),
+            )),
+            executor(client),
+        )
+    }
+
+    fn navigating_state() -> TripState {
+        let route = gen_route_from_steps(vec![gen_dummy_route_step(0.0, 0.0, 0.01, 0.0)]);
+        let location = UserLocation {
+            timestamp: SystemTime::now(),
+            ..gen_user_location(0.002, 0.0, 0)
+        };
+        NavigationController::new(
+            route,
+            NavigationControllerConfig::from_preset(NavigationPreset::Cycling),
+        )
+        .get_initial_state(location)
+    }
+
+    fn options(limit: u32) -> SearchAlongRouteOptions {
+        SearchAlongRouteOptions {
+            category: PoiCategory::Fuel,
+            max_distance_from_route: 50.0,
+            max_distance_ahead: None,
+            limit,
+        }
+    }
+
+    #[test]
+    fn corridor_covers_the_route() {
+        let geometry = [
+            GeographicCoordinate { lat: 0.0, lng: 0.0 },
+            GeographicCoordinate {
+                lat: 0.0,
+                lng: 0.01,
+            },
+            GeographicCoordinate {
+                lat: 0.01,
+                lng: 0.01,
+            },
+        ];
+        let corridor = to_geo(&route_corridor(&geometry, 50.0).expect("Expected a corridor"));
+
+        let contains = |lat: f64, lng: f64| corridor.contains(&Point::new(lng, lat));
+        // On the route, including the corner
+        assert!(contains(0.0, 0.005));
+        assert!(contains(0.0, 0.01));
+        assert!(contains(0.005, 0.01));
+        // 30m from the route
+        assert!(contains(0.00027, 0.005));
+        assert!(contains(-0.00027, 0.005));
+        // 100m from the route, or beyond the ends
+        assert!(!contains(0.0009, 0.005));
+        assert!(!contains(0.0, -0.001));
+        assert!(!contains(0.011, 0.01));
+
+        assert_eq!(route_corridor(&geometry[..1], 50.0), None);
+    }
+
+    #[test]
+    fn truncates_lines() {
+        let geometry = [
+            GeographicCoordinate { lat: 0.0, lng: 0.0 },
+            GeographicCoordinate {
+                lat: 0.0,
+                lng: 0.01,
+            },
+        ];
+        let truncated = truncate_line(&geometry, 556.0);
+        assert_eq!(truncated.len(), 2);
+        assert!((truncated[1].lng - 0.005).abs() < 1e-5);
+
+        assert_eq!(truncate_line(&geometry, 5000.0), geometry.to_vec());
+    }
+
+    #[test]
+    fn search_with_detours() {
+        let provider = Arc::new(RecordingProvider::default());
+        let search = search_along_route(
+            provider.clone(),
+            MockHttpClient::with_responses(vec![
+                Err(HttpError::HttpStatus { status: 400 }),
+                Ok(OSRM_RESPONSE.as_bytes().to_vec()),
+            ]),
+        );
+
+        let results = block_on(search.search(navigating_state(), options(2), None))
+            .expect("Unable to search along the route");
+        assert_eq!(results.len(), 2);
+        // The result which could not be routed to comes last
+        assert_eq!(results[0].poi.id, "1");
+        assert!(results[0].detour_distance.is_some());
+        assert_eq!(results[1].poi.id, "0");
+        assert_eq!(results[1].detour_duration, None);
+
+        // The corridor starts at the user's location
+        let areas = provider.areas.lock().expect("Unable to lock areas");
+        let corridor = to_geo(&areas[0]);
+        assert!(corridor.contains(&Point::new(0.005, 0.0)));
+        assert!(!corridor.contains(&Point::new(0.001, 0.0)));
+    }
+
+    #[test]
+    fn search_when_complete_or_cancelled() {
+        let search = search_along_route(
+            Arc::new(RecordingProvider::default()),
+            MockHttpClient::osrm(),
+        );
+        assert_eq!(
+            block_on(search.search(TripState::Complete, options(1), None))
+                .expect("Unable to search along the route"),
+            vec![]
+        );
+
+        let token = Arc::new(CancellationToken::new());
+        token.cancel();
+        assert!(matches!(
+            block_on(search.search(navigating_state(), options(1), Some(token))),
+            Err(PoiSearchError::Cancelled)
+        ));
+    }
+}
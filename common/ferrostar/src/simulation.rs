@@ -1,10 +1,12 @@
-use crate::algorithms::trunc_float;
+use crate::algorithms::{decode_polyline, trunc_float};
 use crate::models::{CourseOverGround, GeographicCoordinate, Route, UserLocation};
+use crate::now;
 use geo::{coord, DensifyHaversine, GeodesicBearing, LineString, Point};
-use polyline::decode_polyline;
-use std::time::SystemTime;
+use std::f64::consts::PI;
 
-#[cfg(test)]
+#[cfg(feature = "wasm-js")]
+use serde::Deserialize;
+#[cfg(any(test, feature = "wasm-js"))]
 use serde::Serialize;
 
 #[derive(Debug, thiserror::Error, uniffi::Error)]
@@ -16,10 +18,112 @@ pub enum SimulationError {
 }
 
 #[derive(uniffi::Record, Clone, PartialEq)]
-#[cfg_attr(test, derive(Serialize))]
+#[cfg_attr(any(test, feature = "wasm-js"), derive(Serialize))]
+#[cfg_attr(feature = "wasm-js", derive(Deserialize))]
 pub struct LocationSimulationState {
     pub current_location: UserLocation,
     remaining_locations: Vec<GeographicCoordinate>,
+    #[cfg_attr(
+        any(test, feature = "wasm-js"),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    #[cfg_attr(feature = "wasm-js", serde(default))]
+    noise: Option<SimulationNoise>,
+}
+
+/// The approximate number of meters per degree of latitude.
+const METERS_PER_DEGREE: f64 = 111_320.0;
+
+/// A model of GPS errors, which are added to simulated locations
+/// (ex: to exercise location filtering and off route detection).
+///
+/// The errors are pseudo-random, but entirely determined by the seed,
+/// so a simulation with the same seed always produces the same locations on every platform.
+#[derive(uniffi::Record, Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(any(test, feature = "wasm-js"), derive(Serialize))]
+#[cfg_attr(feature = "wasm-js", derive(Deserialize))]
+pub struct GpsNoiseModel {
+    /// The standard deviation of the position error in each direction (north and east), in meters.
+    pub position_error: f64,
+    /// The horizontal accuracy reported with each location, in meters.
+    ///
+    /// Outliers report the same accuracy, as real receivers rarely know when they are wrong.
+    pub horizontal_accuracy: f64,
+    /// The probability (from 0 to 1) that a location is an outlier (ex: from multipath reflections).
+    pub outlier_probability: f64,
+    /// How far outliers are from the true position, in meters.
+    pub outlier_distance: f64,
+    /// The probability (from 0 to 1) that no location is received (ex: under a bridge).
+    pub gap_probability: f64,
+    pub seed: u64,
+}
+
+/// The state of the GPS noise in a simulation.
+#[derive(uniffi::Record, Clone, PartialEq)]
+#[cfg_attr(any(test, feature = "wasm-js"), derive(Serialize))]
+#[cfg_attr(feature = "wasm-js", derive(Deserialize))]
+pub struct SimulationNoise {
+    model: GpsNoiseModel,
+    /// The state of the pseudo-random number generator.
+    random_state: u64,
+    /// The true location (without noise) which the simulation follows.
+    true_coordinates: GeographicCoordinate,
+}
+
+impl SimulationNoise {
+    /// Adds noise to the next simulated location.
+    ///
+    /// Returns the previous location during a gap, as no new location is received.
+    fn apply(&mut self, location: UserLocation, previous: UserLocation) -> UserLocation {
+        self.true_coordinates = location.coordinates;
+        if self.next_f64() < self.model.gap_probability {
+            return previous;
+        }
+
+        let (north, east) = if self.next_f64() < self.model.outlier_probability {
+            let direction = 2.0 * PI * self.next_f64();
+            (
+                self.model.outlier_distance * direction.cos(),
+                self.model.outlier_distance * direction.sin(),
+            )
+        } else {
+            (
+                self.model.position_error * self.next_gaussian(),
+                self.model.position_error * self.next_gaussian(),
+            )
+        };
+        let lat = location.coordinates.lat;
+        UserLocation {
+            coordinates: GeographicCoordinate {
+                lat: lat + north / METERS_PER_DEGREE,
+                lng: location.coordinates.lng + east / (METERS_PER_DEGREE * lat.to_radians().cos()),
+            },
+            horizontal_accuracy: self.model.horizontal_accuracy,
+            ..location
+        }
+    }
+
+    /// Generates the next pseudo-random number (using SplitMix64).
+    fn next_u64(&mut self) -> u64 {
+        self.random_state = self.random_state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.random_state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Generates a uniformly distributed number in `0..1`.
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1_u64 << 53) as f64
+    }
+
+    /// Generates a normally distributed number with a standard deviation of 1 (using the Box-Muller transform).
+    fn next_gaussian(&mut self) -> f64 {
+        // Avoid the logarithm of zero
+        let u1 = 1.0 - self.next_f64();
+        let u2 = self.next_f64();
+        (-2.0 * u1.ln()).sqrt() * (2.0 * PI * u2).cos()
+    }
 }
 
 /// Creates a location simulation from a set of coordinates.
@@ -42,8 +146,10 @@ pub fn location_simulation_from_coordinates(
                     degrees: bearing.round() as u16,
                     accuracy: None,
                 }),
-                timestamp: SystemTime::now(),
+                timestamp: now(),
                 speed: None,
+                altitude: None,
+                vertical_accuracy: None,
             };
 
             let remaining_locations = if let Some(distance) = resample_distance {
@@ -77,6 +183,7 @@ pub fn location_simulation_from_coordinates(
             Ok(LocationSimulationState {
                 current_location,
                 remaining_locations,
+                noise: None,
             })
         } else {
             Err(SimulationError::NotEnoughPoints)
@@ -119,6 +226,26 @@ pub fn location_simulation_from_polyline(
     location_simulation_from_coordinates(coordinates, resample_distance)
 }
 
+/// Adds GPS noise to the locations of a simulation from the next location onward.
+///
+/// During a gap in the signal, the current location (including its timestamp)
+/// is not updated when the simulation advances, as though no location was received.
+#[uniffi::export]
+pub fn location_simulation_with_noise(
+    state: LocationSimulationState,
+    model: GpsNoiseModel,
+) -> LocationSimulationState {
+    let true_coordinates = state.current_location.coordinates;
+    LocationSimulationState {
+        noise: Some(SimulationNoise {
+            model,
+            random_state: model.seed,
+            true_coordinates,
+        }),
+        ..state
+    }
+}
+
 /// Returns the next simulation state based on the desired strategy.
 /// Results of this can be thought of like a stream from a generator function.
 ///
@@ -130,7 +257,15 @@ pub fn location_simulation_from_polyline(
 #[uniffi::export]
 pub fn advance_location_simulation(state: &LocationSimulationState) -> LocationSimulationState {
     if let Some((next_coordinate, rest)) = state.remaining_locations.split_first() {
-        let current_point = Point::from(state.current_location.coordinates);
+        // The simulation follows the true location, regardless of any noise
+        let current_point = Point::from(
+            state
+                .noise
+                .as_ref()
+                .map_or(state.current_location.coordinates, |noise| {
+                    noise.true_coordinates
+                }),
+        );
         let next_point = Point::from(*next_coordinate);
         let mut bearing = current_point.geodesic_bearing(next_point);
         if bearing < 0.0 {
@@ -144,13 +279,22 @@ pub fn advance_location_simulation(state: &LocationSimulationState) -> LocationS
                 degrees: bearing.round() as u16,
                 accuracy: None,
             }),
-            timestamp: SystemTime::now(),
+            timestamp: now(),
             speed: None,
+            altitude: None,
+            vertical_accuracy: None,
+        };
+
+        let mut noise = state.noise.clone();
+        let current_location = match &mut noise {
+            Some(noise) => noise.apply(next_location, state.current_location),
+            None => next_location,
         };
 
         LocationSimulationState {
-            current_location: next_location,
+            current_location,
             remaining_locations: Vec::from(rest),
+            noise,
         }
     } else {
         state.clone()
@@ -264,4 +408,125 @@ mod tests {
         );
         insta::assert_yaml_snapshot!(states);
     }
+
+    fn noise_model(seed: u64) -> GpsNoiseModel {
+        GpsNoiseModel {
+            position_error: 5.0,
+            horizontal_accuracy: 8.0,
+            outlier_probability: 0.05,
+            outlier_distance: 200.0,
+            gap_probability: 0.1,
+            seed,
+        }
+    }
+
+    /// Simulates about 2km of travel along the equator, returning the (true, simulated) locations.
+    fn simulate_with_noise(model: GpsNoiseModel) -> Vec<(GeographicCoordinate, UserLocation)> {
+        let state = location_simulation_from_coordinates(
+            // Only the segments after the first point are resampled
+            vec![
+                GeographicCoordinate { lng: 0.0, lat: 0.0 },
+                GeographicCoordinate {
+                    lng: 0.00001,
+                    lat: 0.0,
+                },
+                GeographicCoordinate {
+                    lng: 0.02,
+                    lat: 0.0,
+                },
+            ],
+            Some(5.0),
+        )
+        .expect("Unable to initialize simulation");
+        let mut state = location_simulation_with_noise(state, model);
+
+        let mut locations = vec![];
+        while !state.remaining_locations.is_empty() {
+            state = advance_location_simulation(&state);
+            let true_coordinates = state.noise.as_ref().unwrap().true_coordinates;
+            locations.push((true_coordinates, state.current_location));
+        }
+        locations
+    }
+
+    fn coordinates(
+        locations: &[(GeographicCoordinate, UserLocation)],
+    ) -> Vec<GeographicCoordinate> {
+        locations
+            .iter()
+            .map(|(_, location)| location.coordinates)
+            .collect()
+    }
+
+    #[test]
+    fn noise_is_determined_by_the_seed() {
+        let first = simulate_with_noise(noise_model(42));
+        assert_eq!(
+            coordinates(&first),
+            coordinates(&simulate_with_noise(noise_model(42)))
+        );
+        assert_ne!(
+            coordinates(&first),
+            coordinates(&simulate_with_noise(noise_model(7)))
+        );
+    }
+
+    #[test]
+    fn noise_follows_the_model() {
+        let locations = simulate_with_noise(noise_model(42));
+        let mut gaps = 0;
+        let mut outliers = 0;
+        let mut squared_errors = vec![];
+        let mut previous_coordinates = None;
+        for (true_coordinates, location) in &locations {
+            // The location is not updated during gaps
+            if previous_coordinates == Some(location.coordinates) {
+                gaps += 1;
+                continue;
+            }
+            previous_coordinates = Some(location.coordinates);
+            assert!((location.horizontal_accuracy - 8.0).abs() < f64::EPSILON);
+
+            let error = Point::from(*true_coordinates)
+                .haversine_distance(&Point::from(location.coordinates));
+            if (error - 200.0).abs() < 1.0 {
+                outliers += 1;
+            } else {
+                // Gaussian errors beyond 6 standard deviations (30m) should never happen
+                assert!(error < 30.0, "Unexpected error of {error}m");
+                squared_errors.push(error * error);
+            }
+        }
+
+        // About 445 locations, with 10% gaps and 5% outliers
+        let count = locations.len() as f64;
+        assert!(count > 400.0);
+        assert!((f64::from(gaps) / count - 0.1).abs() < 0.04, "{gaps} gaps");
+        assert!(
+            (f64::from(outliers) / count - 0.05).abs() < 0.03,
+            "{outliers} outliers"
+        );
+        // The expected squared error is twice the variance in each direction
+        let mean_squared_error = squared_errors.iter().sum::<f64>() / squared_errors.len() as f64;
+        assert!(
+            (mean_squared_error.sqrt() - 5.0 * 2_f64.sqrt()).abs() < 1.0,
+            "RMS error of {}m",
+            mean_squared_error.sqrt()
+        );
+    }
+
+    #[test]
+    fn noiseless_model() {
+        let model = GpsNoiseModel {
+            position_error: 0.0,
+            horizontal_accuracy: 0.0,
+            outlier_probability: 0.0,
+            outlier_distance: 0.0,
+            gap_probability: 0.0,
+            seed: 0,
+        };
+        for (true_coordinates, location) in simulate_with_noise(model) {
+            assert_eq!(location.coordinates, true_coordinates);
+        }
+    }
 }
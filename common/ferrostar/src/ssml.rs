@@ -0,0 +1,346 @@
+//! Post-processing of SSML announcements for text-to-speech engines.
+//!
+//! Routing backends generate SSML of varying quality,
+//! and TTS engines on each platform read the same markup differently.
+//! Processing announcements in the core makes them consistent:
+//!
+//! - Markup is sanitized (wrapped in a `<speak>` element, with stray `&`, `<`, and `>` escaped).
+//! - Common English road abbreviations (ex: `Rd`) are expanded with `<sub>` elements,
+//!   so that they are not spelled out.
+//! - Pronunciation hints (ex: for street names) are added with `<phoneme>` elements.
+//!
+//! Text which is already inside `<sub>`, `<phoneme>`, or `<say-as>` elements is left as is,
+//! so processing an announcement more than once has no further effect.
+
+/// A pronunciation hint for a word or phrase in announcements (ex: a street name).
+#[derive(Debug, Clone, PartialEq, Eq, uniffi::Record)]
+pub struct PhonemeHint {
+    /// The text as it appears in announcements (ex: `Peetri`).
+    pub text: String,
+    /// The pronunciation of the text, in the phonetic alphabet.
+    pub phoneme: String,
+    /// The phonetic alphabet (ex: `ipa` or `x-sampa`).
+    pub alphabet: String,
+}
+
+/// Elements whose content must not be augmented.
+const PROTECTED_ELEMENTS: [&str; 3] = ["phoneme", "say-as", "sub"];
+
+/// Common English road abbreviations, and their expansions.
+const ABBREVIATIONS: [(&str, &str); 11] = [
+    ("Ave", "Avenue"),
+    ("Blvd", "Boulevard"),
+    ("Ct", "Court"),
+    ("Expy", "Expressway"),
+    ("Fwy", "Freeway"),
+    ("Hwy", "Highway"),
+    ("Ln", "Lane"),
+    ("Pkwy", "Parkway"),
+    ("Pl", "Place"),
+    ("Rd", "Road"),
+    ("Sq", "Square"),
+];
+
+/// Abbreviations which mean something else when they introduce a name (ex: `St. Mary Rd`),
+/// with their expansions as a road type and as a title.
+const TITLE_ABBREVIATIONS: [(&str, &str, &str); 2] =
+    [("Dr", "Drive", "Doctor"), ("St", "Street", "Saint")];
+
+/// A piece of an announcement, which is either text or finished markup.
+enum Piece {
+    Text(String),
+    Markup(String),
+}
+
+/// Sanitizes and augments an SSML announcement.
+///
+/// Plain text is accepted too, as long as it is already escaped.
+/// Use [`synthesize_ssml`] for unescaped plain text.
+#[uniffi::export]
+pub fn process_ssml(ssml: &str, hints: &[PhonemeHint]) -> String {
+    augment_ssml(ssml, hints)
+}
+
+/// Synthesizes SSML from a plain text announcement (for backends which only provide text).
+#[uniffi::export]
+pub fn synthesize_ssml(text: &str, hints: &[PhonemeHint]) -> String {
+    augment_ssml(&format!("<speak>{}</speak>", escape(text.trim())), hints)
+}
+
+pub(crate) fn augment_ssml(ssml: &str, hints: &[PhonemeHint]) -> String {
+    let ssml = ssml.trim();
+    let is_wrapped = ssml.starts_with("<speak");
+    let mut output = String::with_capacity(ssml.len());
+    if !is_wrapped {
+        output.push_str("<speak>");
+    }
+
+    let mut protected_depth = 0_usize;
+    let mut rest = ssml;
+    while !rest.is_empty() {
+        // Stray brackets and unterminated tags are treated as text
+        let (text, tag) = tag_start(rest)
+            .and_then(|start| {
+                rest[start..]
+                    .find('>')
+                    .map(|length| (&rest[..start], &rest[start..=start + length]))
+            })
+            .unwrap_or((rest, ""));
+        rest = &rest[text.len() + tag.len()..];
+
+        let text = escape_bare(text);
+        if protected_depth == 0 {
+            output.push_str(&augment(&text, hints));
+        } else {
+            output.push_str(&text);
+        }
+
+        let name = tag
+            .trim_start_matches('<')
+            .trim_start_matches('/')
+            .split(|c: char| c.is_whitespace() || c == '/' || c == '>')
+            .next()
+            .unwrap_or_default();
+        if PROTECTED_ELEMENTS.contains(&name) && !tag.ends_with("/>") {
+            if tag.starts_with("</") {
+                protected_depth = protected_depth.saturating_sub(1);
+            } else {
+                protected_depth += 1;
+            }
+        }
+        output.push_str(tag);
+    }
+
+    if !is_wrapped {
+        output.push_str("</speak>");
+    }
+    output
+}
+
+/// Finds the start of the next tag (ex: `<break`, `</sub`, or `<?xml`).
+fn tag_start(ssml: &str) -> Option<usize> {
+    ssml.match_indices('<')
+        .map(|(index, _)| index)
+        .find(|index| {
+            ssml[index + 1..]
+                .starts_with(|c: char| c.is_ascii_alphabetic() || matches!(c, '/' | '?' | '!'))
+        })
+}
+
+/// Escapes text for use in SSML.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Escapes stray characters in (already escaped) text, leaving entities intact.
+fn escape_bare(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    for (index, c) in text.char_indices() {
+        match c {
+            '&' if !is_entity(&text[index..]) => output.push_str("&amp;"),
+            '<' => output.push_str("&lt;"),
+            '>' => output.push_str("&gt;"),
+            c => output.push(c),
+        }
+    }
+    output
+}
+
+fn is_entity(text: &str) -> bool {
+    text.find(';').is_some_and(|end| {
+        let name = &text[1..end];
+        !name.is_empty()
+            && (name.chars().all(char::is_alphanumeric)
+                || name.strip_prefix('#').is_some_and(|code| {
+                    !code.is_empty() && code.chars().all(|c| c.is_ascii_hexdigit() || c == 'x')
+                }))
+    })
+}
+
+/// Adds phoneme hints and expands abbreviations in (escaped) text.
+fn augment(text: &str, hints: &[PhonemeHint]) -> String {
+    let mut pieces = vec![Piece::Text(text.to_string())];
+    for hint in hints {
+        let hint_text = escape(hint.text.trim());
+        if hint_text.is_empty() || hint.phoneme.trim().is_empty() {
+            continue;
+        }
+        pieces = pieces
+            .into_iter()
+            .flat_map(|piece| match piece {
+                Piece::Text(text) => add_phonemes(&text, &hint_text, hint),
+                markup @ Piece::Markup(_) => vec![markup],
+            })
+            .collect();
+    }
+
+    pieces
+        .into_iter()
+        .map(|piece| match piece {
+            Piece::Text(text) => expand_abbreviations(&text),
+            Piece::Markup(markup) => markup,
+        })
+        .collect()
+}
+
+/// Wraps whole-word occurrences of the hint text in `<phoneme>` elements.
+fn add_phonemes(text: &str, hint_text: &str, hint: &PhonemeHint) -> Vec<Piece> {
+    let mut pieces = vec![];
+    let mut last = 0;
+    for (start, _) in text.match_indices(hint_text) {
+        let end = start + hint_text.len();
+        if start < last || !is_word_boundary(text, start) || !is_word_boundary(text, end) {
+            continue;
+        }
+        pieces.push(Piece::Text(text[last..start].to_string()));
+        pieces.push(Piece::Markup(format!(
+            r#"<phoneme alphabet="{}" ph="{}">{hint_text}</phoneme>"#,
+            escape(&hint.alphabet),
+            escape(&hint.phoneme),
+        )));
+        last = end;
+    }
+    pieces.push(Piece::Text(text[last..].to_string()));
+    pieces
+}
+
+fn is_capitalized(word: Option<&str>) -> bool {
+    word.and_then(|word| word.chars().next())
+        .is_some_and(char::is_uppercase)
+}
+
+fn is_word_boundary(text: &str, index: usize) -> bool {
+    let before = text[..index].chars().next_back();
+    let after = text[index..].chars().next();
+    !(before.is_some_and(char::is_alphanumeric) && after.is_some_and(char::is_alphanumeric))
+}
+
+/// Expands road abbreviations in (escaped) text with `<sub>` elements.
+fn expand_abbreviations(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+    let mut previous_word: Option<&str> = None;
+    while let Some(start) = rest.find(char::is_alphanumeric) {
+        let end = rest[start..]
+            .find(|c: char| !c.is_alphanumeric())
+            .map_or(rest.len(), |length| start + length);
+        let word = &rest[start..end];
+        output.push_str(&rest[..start]);
+        rest = &rest[end..];
+
+        // Entities (ex: `&amp;`) are not words
+        if output.ends_with('&') || output.ends_with("&#") {
+            output.push_str(word);
+            continue;
+        }
+
+        let next_word = rest
+            .trim_start_matches('.')
+            .strip_prefix(' ')
+            .and_then(|next| next.split(|c: char| !c.is_alphanumeric()).next());
+        let introduces_name = is_capitalized(next_word) && !is_capitalized(previous_word);
+
+        if let Some((_, road, title)) = TITLE_ABBREVIATIONS
+            .iter()
+            .find(|(abbreviation, _, _)| *abbreviation == word)
+        {
+            if introduces_name {
+                // The period belongs to the abbreviation, rather than ending a sentence
+                let period = if rest.starts_with('.') { "." } else { "" };
+                rest = &rest[period.len()..];
+                output.push_str(&format!(r#"<sub alias="{title}">{word}{period}</sub>"#));
+            } else {
+                output.push_str(&format!(r#"<sub alias="{road}">{word}</sub>"#));
+            }
+        } else if let Some((_, expansion)) = ABBREVIATIONS
+            .iter()
+            .find(|(abbreviation, _)| *abbreviation == word)
+        {
+            output.push_str(&format!(r#"<sub alias="{expansion}">{word}</sub>"#));
+        } else {
+            output.push_str(word);
+        }
+        previous_word = Some(word);
+    }
+    output.push_str(rest);
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hint(text: &str, phoneme: &str) -> PhonemeHint {
+        PhonemeHint {
+            text: text.to_string(),
+            phoneme: phoneme.to_string(),
+            alphabet: "ipa".to_string(),
+        }
+    }
+
+    #[test]
+    fn unchanged_ssml() {
+        let ssml = "<speak>In 200 feet, Turn left onto the walkway.</speak>";
+        assert_eq!(process_ssml(ssml, &[]), ssml);
+    }
+
+    #[test]
+    fn sanitize() {
+        assert_eq!(
+            process_ssml("Turn left onto A & B Lane", &[]),
+            "<speak>Turn left onto A &amp; B Lane</speak>"
+        );
+        assert_eq!(
+            process_ssml("<speak>Exit 2 &amp; 3 &gt; 1 < 4</speak>", &[]),
+            "<speak>Exit 2 &amp; 3 &gt; 1 &lt; 4</speak>"
+        );
+    }
+
+    #[test]
+    fn abbreviations() {
+        assert_eq!(
+            synthesize_ssml("Turn right onto Main St.", &[]),
+            r#"<speak>Turn right onto Main <sub alias="Street">St</sub>.</speak>"#
+        );
+        assert_eq!(
+            synthesize_ssml("Turn left onto St. Mary Rd, then Dr King Blvd", &[]),
+            r#"<speak>Turn left onto <sub alias="Saint">St.</sub> Mary <sub alias="Road">Rd</sub>, then <sub alias="Doctor">Dr</sub> King <sub alias="Boulevard">Blvd</sub></speak>"#
+        );
+        // Only whole words are expanded
+        assert_eq!(
+            synthesize_ssml("Continue on Rdgway", &[]),
+            "<speak>Continue on Rdgway</speak>"
+        );
+    }
+
+    #[test]
+    fn phoneme_hints() {
+        let hints = vec![hint("Peetri", "ˈpeːtri"), hint("Logi", "ˈloɡi")];
+        assert_eq!(
+            process_ssml(
+                "<speak>In 45 feet, Turn right onto Peetri, then Logistics Rd.</speak>",
+                &hints
+            ),
+            r#"<speak>In 45 feet, Turn right onto <phoneme alphabet="ipa" ph="ˈpeːtri">Peetri</phoneme>, then Logistics <sub alias="Road">Rd</sub>.</speak>"#
+        );
+    }
+
+    #[test]
+    fn processing_is_idempotent() {
+        let hints = vec![hint("Ave Maria", "ˈɑːveɪ məˈriːə")];
+        let once = synthesize_ssml("Turn left onto Ave Maria Ave", &hints);
+        assert_eq!(
+            once,
+            r#"<speak>Turn left onto <phoneme alphabet="ipa" ph="ˈɑːveɪ məˈriːə">Ave Maria</phoneme> <sub alias="Avenue">Ave</sub></speak>"#
+        );
+        assert_eq!(process_ssml(&once, &hints), once);
+
+        // Existing markup is preserved
+        let ssml =
+            r#"<speak><say-as interpret-as="characters">St</say-as> <break time="1s"/></speak>"#;
+        assert_eq!(process_ssml(ssml, &[]), ssml);
+    }
+}
@@ -0,0 +1,237 @@
+//! Detection of stale routes.
+//!
+//! Apps may save a route and resume navigating it later (ex: the next morning).
+//! Traffic, closures, and the user's position may all have changed in the meantime,
+//! so a [`StaleRouteGuard`] checks how long ago the route was planned before navigation starts,
+//! reporting a [`NavigationEvent::RouteStale`] advisory
+//! and optionally fetching a fresh route to replace it.
+
+use crate::clock::Clock;
+use crate::models::{Route, UserLocation};
+use crate::navigation_controller::models::NavigationEvent;
+use crate::now;
+use crate::routing_adapters::error::RouteFetchError;
+use crate::routing_adapters::http::{CancellationToken, HttpExecutor};
+use crate::routing_adapters::RouteAdapter;
+use std::sync::Arc;
+
+/// A route which is ready to navigate.
+#[derive(Debug, Clone, PartialEq, uniffi::Record)]
+pub struct PreparedRoute {
+    /// The route to navigate.
+    ///
+    /// This is a freshly fetched route if the original was stale and could be refreshed,
+    /// and the original route otherwise.
+    pub route: Route,
+    /// Advisories about the original route (ex: [`NavigationEvent::RouteStale`]).
+    pub events: Vec<NavigationEvent>,
+    /// Whether the route was replaced with a freshly fetched one.
+    pub refreshed: bool,
+}
+
+/// Checks the age of routes before navigation starts.
+///
+/// Routes without a known planning time (ex: routes which were saved by an older version)
+/// are never considered stale.
+#[derive(uniffi::Object)]
+pub struct StaleRouteGuard {
+    max_age: f64,
+    refresh: Option<(Arc<RouteAdapter>, Arc<HttpExecutor>)>,
+    clock: Option<Arc<dyn Clock>>,
+}
+
+#[uniffi::export]
+impl StaleRouteGuard {
+    /// Creates a guard which reports routes planned more than `max_age` seconds ago.
+    ///
+    /// The age is measured with the clock (the system clock when this is `None`),
+    /// which should be the same one as in the navigation controller's config.
+    #[uniffi::constructor]
+    pub fn new(max_age: f64, clock: Option<Arc<dyn Clock>>) -> Self {
+        Self {
+            max_age,
+            refresh: None,
+            clock,
+        }
+    }
+
+    /// Creates a guard which reports routes planned more than `max_age` seconds ago,
+    /// and refreshes them using the route adapter in [`StaleRouteGuard::prepare`].
+    #[uniffi::constructor]
+    pub fn new_with_refresh(
+        max_age: f64,
+        route_adapter: Arc<RouteAdapter>,
+        executor: Arc<HttpExecutor>,
+        clock: Option<Arc<dyn Clock>>,
+    ) -> Self {
+        Self {
+            max_age,
+            refresh: Some((route_adapter, executor)),
+            clock,
+        }
+    }
+
+    /// Returns a [`NavigationEvent::RouteStale`] event if the route is older than the max age.
+    pub fn check(&self, route: &Route) -> Option<NavigationEvent> {
+        let now = self.clock.as_ref().map_or_else(now, |clock| clock.now());
+        let age = now
+            .duration_since(route.planned_at?)
+            .map_or(0.0, |age| age.as_secs_f64());
+        (age > self.max_age).then_some(NavigationEvent::RouteStale { age })
+    }
+
+    /// Checks the route before navigation starts,
+    /// fetching a new route from the user's location to the remaining waypoints if it is stale
+    /// (and the guard was created with [`StaleRouteGuard::new_with_refresh`]).
+    ///
+    /// The [`NavigationEvent::RouteStale`] advisory is included even if the route was refreshed,
+    /// so apps can let the user know why the route changed.
+    pub async fn prepare(
+        &self,
+        route: Route,
+        user_location: UserLocation,
+        cancellation_token: Option<Arc<CancellationToken>>,
+    ) -> Result<PreparedRoute, RouteFetchError> {
+        let Some(event) = self.check(&route) else {
+            return Ok(PreparedRoute {
+                route,
+                events: vec![],
+                refreshed: false,
+            });
+        };
+        let Some((route_adapter, executor)) = &self.refresh else {
+            return Ok(PreparedRoute {
+                route,
+                events: vec![event],
+                refreshed: false,
+            });
+        };
+
+        let fresh_route = route_adapter
+            .get_route(
+                executor.clone(),
+                user_location,
+                route.waypoints.iter().skip(1).copied().collect(),
+                cancellation_token,
+            )
+            .await?;
+        Ok(PreparedRoute {
+            route: fresh_route,
+            events: vec![event],
+            refreshed: true,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+    use crate::navigation_controller::test_helpers::{
+        gen_dummy_route_step, gen_route_from_steps, gen_user_location,
+    };
+    use crate::routing_adapters::http::test_helpers::{block_on, executor, MockHttpClient};
+    use crate::{create_osrm_response_parser, create_valhalla_request_generator};
+    use std::time::{Duration, SystemTime};
+
+    fn route_planned_at(planned_at: Option<SystemTime>) -> Route {
+        Route {
+            planned_at,
+            ..gen_route_from_steps(vec![gen_dummy_route_step(0.0, 0.0, 0.01, 0.0)])
+        }
+    }
+
+    fn guard_with_refresh() -> StaleRouteGuard {
+        StaleRouteGuard::new_with_refresh(
+            3600.0,
+            Arc::new(RouteAdapter::new(
+                create_valhalla_request_generator(
+                    "https://valhalla.example.com/route".to_string(),
+                    "auto".to_string(),
+                    None,
+                )
+                .expect("Unable to create request generator"),
+                create_osrm_response_parser(6),
+            )),
+            executor(MockHttpClient::osrm()),
+            None,
+        )
+    }
+
+    #[test]
+    fn detects_stale_routes() {
+        let planned_at = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000);
+        let clock = Arc::new(MockClock::new(planned_at + Duration::from_secs(600)));
+        let guard = StaleRouteGuard::new(3600.0, Some(clock.clone()));
+        let route = route_planned_at(Some(planned_at));
+
+        assert_eq!(guard.check(&route), None);
+
+        clock.set(planned_at + Duration::from_secs(7200));
+        assert_eq!(
+            guard.check(&route),
+            Some(NavigationEvent::RouteStale { age: 7200.0 })
+        );
+        // Routes without a planning time are never stale
+        assert_eq!(guard.check(&route_planned_at(None)), None);
+
+        // A clock which went backwards does not make the route stale
+        clock.set(planned_at - Duration::from_secs(7200));
+        assert_eq!(guard.check(&route), None);
+    }
+
+    #[test]
+    fn prepare_keeps_fresh_routes() {
+        let route = route_planned_at(Some(SystemTime::now()));
+        let prepared = block_on(guard_with_refresh().prepare(
+            route.clone(),
+            gen_user_location(0.0, 0.0, 0),
+            None,
+        ))
+        .expect("Unable to prepare route");
+        assert_eq!(
+            prepared,
+            PreparedRoute {
+                route,
+                events: vec![],
+                refreshed: false,
+            }
+        );
+    }
+
+    #[test]
+    fn prepare_reports_stale_routes_without_refresh() {
+        let route = route_planned_at(Some(SystemTime::now() - Duration::from_secs(7200)));
+        let prepared = block_on(StaleRouteGuard::new(3600.0, None).prepare(
+            route.clone(),
+            gen_user_location(0.0, 0.0, 0),
+            None,
+        ))
+        .expect("Unable to prepare route");
+        assert_eq!(prepared.route, route);
+        assert!(!prepared.refreshed);
+        assert!(matches!(
+            prepared.events.as_slice(),
+            [NavigationEvent::RouteStale { .. }]
+        ));
+    }
+
+    #[test]
+    fn prepare_refreshes_stale_routes() {
+        let route = route_planned_at(Some(SystemTime::now() - Duration::from_secs(7200)));
+        let prepared = block_on(guard_with_refresh().prepare(
+            route.clone(),
+            gen_user_location(0.0, 0.0, 0),
+            None,
+        ))
+        .expect("Unable to prepare route");
+        assert!(prepared.refreshed);
+        assert_ne!(prepared.route, route);
+        assert!((prepared.route.distance - 333.6).abs() < f64::EPSILON);
+        assert!(prepared.route.planned_at.is_some());
+        assert!(matches!(
+            prepared.events.as_slice(),
+            [NavigationEvent::RouteStale { .. }]
+        ));
+    }
+}
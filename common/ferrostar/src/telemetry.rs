@@ -0,0 +1,375 @@
+//! Opt-in aggregation of guidance quality metrics for a navigation session.
+//!
+//! Nothing is recorded unless the app creates a [`TelemetryRecorder`]
+//! and feeds it the result of each update.
+//! The recorder only keeps aggregates (plus one entry per spoken instruction),
+//! and never sends anything anywhere; it is up to the app to export the summary.
+
+use crate::breadcrumbs::{BreadcrumbRecording, BreadcrumbTrail};
+use crate::deviation_detection::RouteDeviation;
+use crate::models::{GeographicCoordinate, UserLocation};
+use crate::navigation_controller::models::{NavigationEvent, TripState};
+use geo::{HaversineDistance, Point};
+use serde::Serialize;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::SystemTime;
+use uuid::Uuid;
+
+/// When a spoken instruction was triggered, compared to when the route intended it to be.
+#[derive(Debug, Clone, PartialEq, Serialize, uniffi::Record)]
+pub struct InstructionTiming {
+    pub text: String,
+    /// The distance before the maneuver at which the route intended the instruction to be spoken, in meters.
+    pub trigger_distance_before_maneuver: f64,
+    /// The distance before the maneuver at which the instruction was actually triggered, in meters.
+    ///
+    /// This is usually a bit less than the intended distance, as locations are only updated periodically.
+    pub distance_to_maneuver: f64,
+}
+
+/// A summary of guidance quality over a navigation session.
+#[derive(Debug, Clone, PartialEq, Serialize, uniffi::Record)]
+pub struct TelemetrySummary {
+    /// The time between the first and last location updates, in seconds.
+    pub duration: f64,
+    /// The number of location updates recorded.
+    pub location_update_count: u32,
+    /// The number of times the route was replaced (ex: after going off route).
+    pub reroute_count: u32,
+    /// The total time spent off route, in seconds.
+    pub off_route_duration: f64,
+    /// The average distance from the route line while off route, in meters.
+    ///
+    /// This is `None` if the user never went off route.
+    pub average_deviation: Option<f64>,
+    /// The distance from the user's location to the destination on arrival, in meters.
+    ///
+    /// This is `None` until the trip is complete.
+    pub arrival_accuracy: Option<f64>,
+    /// The spoken instructions which were triggered, in order.
+    pub instruction_timings: Vec<InstructionTiming>,
+    /// A simplified polyline of the recorded locations.
+    ///
+    /// This is empty unless the recorder was created with [`TelemetryRecorder::with_breadcrumbs`].
+    pub breadcrumbs: Vec<GeographicCoordinate>,
+}
+
+#[derive(Default)]
+struct TelemetrySession {
+    first_timestamp: Option<SystemTime>,
+    last_timestamp: Option<SystemTime>,
+    location_update_count: u32,
+    reroute_count: u32,
+    was_off_route: bool,
+    off_route_duration: f64,
+    deviation_sum: f64,
+    deviation_count: u32,
+    arrival_accuracy: Option<f64>,
+    last_utterance_id: Option<Uuid>,
+    instruction_timings: Vec<InstructionTiming>,
+    breadcrumbs: BreadcrumbTrail,
+}
+
+impl TelemetrySession {
+    fn new(breadcrumb_recording: BreadcrumbRecording) -> Self {
+        Self {
+            breadcrumbs: BreadcrumbTrail::new(breadcrumb_recording),
+            ..Self::default()
+        }
+    }
+
+    fn record(&mut self, location: UserLocation, state: &TripState, events: &[NavigationEvent]) {
+        let elapsed = self.last_timestamp.map_or(0.0, |last| {
+            location
+                .timestamp
+                .duration_since(last)
+                .map_or(0.0, |duration| duration.as_secs_f64())
+        });
+        if self.was_off_route {
+            self.off_route_duration += elapsed;
+        }
+        if !self
+            .last_timestamp
+            .is_some_and(|last| location.timestamp <= last)
+        {
+            self.breadcrumbs.push(location.coordinates);
+        }
+        self.first_timestamp.get_or_insert(location.timestamp);
+        self.last_timestamp = Some(
+            self.last_timestamp
+                .map_or(location.timestamp, |last| last.max(location.timestamp)),
+        );
+        self.location_update_count = self.location_update_count.saturating_add(1);
+
+        let mut destination = None;
+        for event in events {
+            match event {
+                NavigationEvent::RouteChanged { .. } => {
+                    self.reroute_count = self.reroute_count.saturating_add(1);
+                }
+                NavigationEvent::WaypointReached { waypoint } => {
+                    destination = Some(waypoint.coordinate);
+                }
+                NavigationEvent::TripCompleted => {
+                    self.arrival_accuracy = destination.map(|destination| {
+                        Point::from(location.coordinates)
+                            .haversine_distance(&Point::from(destination))
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        self.was_off_route = false;
+        let TripState::Navigating {
+            deviation,
+            progress,
+            spoken_instruction,
+            ..
+        } = state
+        else {
+            return;
+        };
+
+        if let RouteDeviation::OffRoute {
+            deviation_from_route_line,
+        } = deviation
+        {
+            self.was_off_route = true;
+            self.deviation_sum += *deviation_from_route_line;
+            self.deviation_count += 1;
+        }
+
+        if let Some(instruction) = spoken_instruction {
+            if self.last_utterance_id != Some(instruction.utterance_id) {
+                self.last_utterance_id = Some(instruction.utterance_id);
+                self.instruction_timings.push(InstructionTiming {
+                    text: instruction.text.clone(),
+                    trigger_distance_before_maneuver: instruction.trigger_distance_before_maneuver,
+                    distance_to_maneuver: progress.distance_to_next_maneuver,
+                });
+            }
+        }
+    }
+
+    fn summary(&self) -> TelemetrySummary {
+        let duration = match (self.first_timestamp, self.last_timestamp) {
+            (Some(first), Some(last)) => last
+                .duration_since(first)
+                .map_or(0.0, |duration| duration.as_secs_f64()),
+            _ => 0.0,
+        };
+
+        TelemetrySummary {
+            duration,
+            location_update_count: self.location_update_count,
+            reroute_count: self.reroute_count,
+            off_route_duration: self.off_route_duration,
+            average_deviation: (self.deviation_count > 0)
+                .then(|| self.deviation_sum / f64::from(self.deviation_count)),
+            arrival_accuracy: self.arrival_accuracy,
+            instruction_timings: self.instruction_timings.clone(),
+            breadcrumbs: self.breadcrumbs.coordinates(),
+        }
+    }
+}
+
+/// Aggregates guidance quality metrics over a navigation session.
+///
+/// After each location update, pass the location along with the resulting state and events
+/// (ex: from [`crate::navigation_controller::models::BatchLocationUpdate`])
+/// to [`TelemetryRecorder::record_update`].
+/// Route replacements should be recorded too, so that reroutes are counted.
+#[derive(uniffi::Object)]
+pub struct TelemetryRecorder {
+    breadcrumb_recording: BreadcrumbRecording,
+    session: Mutex<TelemetrySession>,
+}
+
+#[uniffi::export]
+impl TelemetryRecorder {
+    #[uniffi::constructor]
+    pub fn new() -> Self {
+        Self::with_breadcrumbs(BreadcrumbRecording::Disabled)
+    }
+
+    /// Creates a recorder which also keeps a trail of the recorded locations
+    /// (ex: to attach the user's actual track to the summary).
+    #[uniffi::constructor]
+    pub fn with_breadcrumbs(breadcrumb_recording: BreadcrumbRecording) -> Self {
+        Self {
+            breadcrumb_recording,
+            session: Mutex::new(TelemetrySession::new(breadcrumb_recording)),
+        }
+    }
+
+    /// Records the result of a location update.
+    pub fn record_update(
+        &self,
+        location: UserLocation,
+        state: &TripState,
+        events: &[NavigationEvent],
+    ) {
+        self.lock_session().record(location, state, events);
+    }
+
+    /// Summarizes the session so far.
+    pub fn summary(&self) -> TelemetrySummary {
+        self.lock_session().summary()
+    }
+
+    /// Summarizes the session so far as JSON (ex: for uploading to an analytics service).
+    pub fn export_summary_json(&self) -> String {
+        // Serializing plain numbers and strings cannot fail
+        serde_json::to_string(&self.summary()).unwrap_or_default()
+    }
+
+    /// Discards everything recorded so far (ex: when starting a new session).
+    pub fn reset(&self) {
+        *self.lock_session() = TelemetrySession::new(self.breadcrumb_recording);
+    }
+}
+
+impl Default for TelemetryRecorder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TelemetryRecorder {
+    fn lock_session(&self) -> MutexGuard<'_, TelemetrySession> {
+        self.session.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{GeographicCoordinate, SpokenInstruction, Waypoint, WaypointKind};
+    use crate::navigation_controller::models::RouteChange;
+    use crate::navigation_controller::test_helpers::{gen_navigating_state, gen_user_location};
+
+    fn state(
+        deviation: RouteDeviation,
+        spoken_instruction: Option<SpokenInstruction>,
+        distance_to_next_maneuver: f64,
+    ) -> TripState {
+        let mut state = gen_navigating_state(gen_user_location(0.0, 0.0, 0), vec![], deviation);
+        if let TripState::Navigating {
+            progress,
+            spoken_instruction: state_spoken_instruction,
+            ..
+        } = &mut state
+        {
+            progress.distance_to_next_maneuver = distance_to_next_maneuver;
+            progress.distance_remaining = distance_to_next_maneuver;
+            *state_spoken_instruction = spoken_instruction;
+        }
+        state
+    }
+
+    #[test]
+    fn summarizes_session() {
+        let recorder =
+            TelemetryRecorder::with_breadcrumbs(BreadcrumbRecording::Simplified { tolerance: 5.0 });
+        let instruction = SpokenInstruction {
+            text: "Turn left".to_string(),
+            ssml: None,
+            trigger_distance_before_maneuver: 200.0,
+            utterance_id: Uuid::new_v4(),
+        };
+        let off_route = RouteDeviation::OffRoute {
+            deviation_from_route_line: 60.0,
+        };
+
+        recorder.record_update(
+            gen_user_location(0.0, 0.0, 0),
+            &state(RouteDeviation::NoDeviation, None, 500.0),
+            &[],
+        );
+        recorder.record_update(
+            gen_user_location(0.0, 0.0, 10),
+            &state(
+                RouteDeviation::NoDeviation,
+                Some(instruction.clone()),
+                190.0,
+            ),
+            &[],
+        );
+        // The same instruction is only recorded once
+        recorder.record_update(
+            gen_user_location(0.0, 0.0, 20),
+            &state(off_route, Some(instruction.clone()), 150.0),
+            &[],
+        );
+        recorder.record_update(
+            gen_user_location(0.0, 0.0, 25),
+            &state(
+                RouteDeviation::OffRoute {
+                    deviation_from_route_line: 100.0,
+                },
+                None,
+                150.0,
+            ),
+            &[],
+        );
+        recorder.record_update(
+            gen_user_location(0.0, 0.0, 30),
+            &state(RouteDeviation::NoDeviation, None, 400.0),
+            &[NavigationEvent::RouteChanged {
+                change: RouteChange {
+                    distance_delta: 100.0,
+                    duration_delta: 10.0,
+                },
+            }],
+        );
+        recorder.record_update(
+            gen_user_location(0.0001, 0.0, 60),
+            &TripState::Complete,
+            &[
+                NavigationEvent::WaypointReached {
+                    waypoint: Waypoint {
+                        coordinate: GeographicCoordinate { lat: 0.0, lng: 0.0 },
+                        kind: WaypointKind::Break,
+                    },
+                },
+                NavigationEvent::TripCompleted,
+            ],
+        );
+
+        let summary = recorder.summary();
+        assert_eq!(summary.duration, 60.0);
+        assert_eq!(summary.location_update_count, 6);
+        assert_eq!(summary.reroute_count, 1);
+        assert_eq!(summary.off_route_duration, 10.0);
+        assert_eq!(summary.average_deviation, Some(80.0));
+        let arrival_accuracy = summary.arrival_accuracy.expect("Expected arrival accuracy");
+        assert!((arrival_accuracy - 11.1).abs() < 0.1);
+        assert_eq!(
+            summary.instruction_timings,
+            vec![InstructionTiming {
+                text: "Turn left".to_string(),
+                trigger_distance_before_maneuver: 200.0,
+                distance_to_maneuver: 190.0,
+            }]
+        );
+        // The user stayed put until moving to the destination
+        assert_eq!(
+            summary.breadcrumbs,
+            vec![
+                GeographicCoordinate { lat: 0.0, lng: 0.0 },
+                GeographicCoordinate {
+                    lat: 0.0,
+                    lng: 0.0001
+                }
+            ]
+        );
+        assert!(recorder
+            .export_summary_json()
+            .contains("\"reroute_count\":1"));
+
+        recorder.reset();
+        assert_eq!(recorder.summary().location_update_count, 0);
+        assert!(recorder.summary().breadcrumbs.is_empty());
+    }
+}
@@ -0,0 +1,609 @@
+//! Support for testing apps and backends against Ferrostar.
+//!
+//! This module is only available with the `test-utils` feature.
+//!
+//! # Scenarios
+//!
+//! Deterministic scenario tests replay a GPS trace along a route
+//! and check the events emitted by the navigation controller.
+//! They are intended for regression tests of tricky situations (ex: closely spaced intersections),
+//! which can be contributed as a single JSON fixture file:
+//!
+//! ```json
+//! {
+//!   "polyline_precision": 6,
+//!   "route_response": { "routes": [ ... ], "waypoints": [ ... ], "code": "Ok" },
+//!   "trace": [
+//!     { "lat": 60.534716, "lng": -149.543469, "time": 0.0, "horizontal_accuracy": 5.0 }
+//!   ],
+//!   "expected_events": [
+//!     { "type": "StepAdvanced", "instruction": "You have arrived at your destination." },
+//!     { "type": "WaypointReached" }
+//!   ]
+//! }
+//! ```
+//!
+//! The route response is in the OSRM format (see [`OsrmResponseParser`]),
+//! and the first route is navigated.
+//! Trace times are in seconds, relative to the start of the scenario.
+//!
+//! ## Scripts
+//!
+//! Rather than recording a trace, a scenario can describe the drive with a script,
+//! which generates a fix every `fix_interval` seconds (1 by default) along the route:
+//!
+//! ```json
+//! {
+//!   "route_response": { ... },
+//!   "script": [
+//!     { "type": "Speed", "kilometers_per_hour": 90 },
+//!     { "type": "Drive", "seconds": 10 },
+//!     { "type": "SignalLoss", "seconds": 30 },
+//!     { "type": "Teleport", "lat": 60.5, "lng": -149.5 },
+//!     { "type": "DriveToEnd" }
+//!   ],
+//!   "expected_events": [ ... ]
+//! }
+//! ```
+//!
+//! See [`ScriptStep`] for the available steps.
+//! When the emitted events change, the failed assertion prints them in the fixture format,
+//! so that the change can be reviewed as a diff of the fixture.
+//!
+//! # Fuzzing
+//!
+//! The `fuzz_*` functions feed arbitrary input to the response parsers
+//! (ex: from a `cargo fuzz` target or a proptest strategy).
+//! Routing responses come from untrusted networks,
+//! so the parsers must reject malformed input with an error rather than panic.
+
+use crate::algorithms::decode_polyline;
+use crate::clock::{Clock, ReplayClock};
+use crate::geometry_util::point_along_line;
+use crate::models::{
+    CourseOverGround, GeographicCoordinate, Route, RouteStep, Speed, UserLocation,
+};
+use crate::navigation_controller::models::{NavigationControllerConfig, NavigationEvent};
+use crate::navigation_controller::NavigationController;
+use crate::now;
+use crate::routing_adapters::error::{ResponseIndex, RoutingResponseParseError};
+use crate::routing_adapters::osrm::models::RouteStep as OsrmRouteStep;
+use crate::routing_adapters::osrm::streaming::StreamingOsrmResponseParser;
+use crate::routing_adapters::osrm::OsrmResponseParser;
+use crate::routing_adapters::RouteResponseParser;
+use geo::{HaversineBearing, HaversineDistance, Point};
+use serde::{Deserialize, Serialize};
+use serde_json::value::RawValue;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ScenarioError {
+    #[error("The scenario fixture could not be read: {0}.")]
+    Io(#[from] std::io::Error),
+    #[error("The scenario fixture is not valid JSON or does not match the expected format: {0}.")]
+    InvalidFixture(#[from] serde_json::Error),
+    #[error("The route response could not be parsed: {0}")]
+    InvalidRouteResponse(#[from] RoutingResponseParseError),
+    #[error("The scenario trace is empty.")]
+    EmptyTrace,
+    #[error("The scenario has both a trace and a script.")]
+    TraceAndScript,
+    #[error("The scenario script is invalid: {0}.")]
+    InvalidScript(String),
+}
+
+/// A location fix in a scenario trace.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct TraceFix {
+    pub lat: f64,
+    pub lng: f64,
+    /// The time of the fix, in seconds after the start of the scenario.
+    pub time: f64,
+    /// The horizontal accuracy, in meters.
+    #[serde(default)]
+    pub horizontal_accuracy: f64,
+    /// The course over ground, in clockwise degrees from true north.
+    #[serde(default)]
+    pub course: Option<u16>,
+    /// The speed, in meters per second.
+    #[serde(default)]
+    pub speed: Option<f64>,
+}
+
+/// A step of a scenario script, which generates the trace by driving along the route.
+///
+/// The drive starts at the beginning of the route at 50 km/h,
+/// with fixes reporting a horizontal accuracy of 5 meters.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "type")]
+pub enum ScriptStep {
+    /// Sets the speed of the following steps.
+    Speed { kilometers_per_hour: f64 },
+    /// Sets the horizontal accuracy reported by the following fixes, in meters.
+    Accuracy { meters: f64 },
+    /// Drives along the route for this many seconds (stopping at the end of the route).
+    Drive { seconds: f64 },
+    /// Drives along the route until its end.
+    DriveToEnd,
+    /// Stands still for this many seconds.
+    Stop { seconds: f64 },
+    /// Keeps driving for this many seconds without any fixes (ex: in a tunnel).
+    SignalLoss { seconds: f64 },
+    /// Reports a single fix at this position (ex: a GPS glitch),
+    /// without moving along the route.
+    Teleport { lat: f64, lng: f64 },
+}
+
+/// Generates a trace by following a script along a route.
+struct ScriptDriver {
+    geometry: Vec<GeographicCoordinate>,
+    route_length: f64,
+    fix_interval: f64,
+    /// The distance driven along the route, in meters.
+    distance: f64,
+    time: f64,
+    /// The speed, in meters per second.
+    speed: f64,
+    horizontal_accuracy: f64,
+    trace: Vec<TraceFix>,
+}
+
+impl ScriptDriver {
+    fn new(geometry: Vec<GeographicCoordinate>, fix_interval: f64) -> Self {
+        let route_length = geometry
+            .windows(2)
+            .map(|segment| Point::from(segment[0]).haversine_distance(&Point::from(segment[1])))
+            .sum();
+        let mut driver = Self {
+            geometry,
+            route_length,
+            fix_interval,
+            distance: 0.0,
+            time: 0.0,
+            speed: 50.0 / 3.6,
+            horizontal_accuracy: 5.0,
+            trace: vec![],
+        };
+        driver.push_fix(false);
+        driver
+    }
+
+    fn run(mut self, script: &[ScriptStep]) -> Result<Vec<TraceFix>, ScenarioError> {
+        if self.fix_interval.is_nan() || self.fix_interval <= 0.0 {
+            return Err(ScenarioError::InvalidScript(
+                "the fix interval must be positive".to_string(),
+            ));
+        }
+
+        for step in script {
+            match *step {
+                ScriptStep::Speed {
+                    kilometers_per_hour,
+                } => {
+                    if kilometers_per_hour.is_nan() || kilometers_per_hour < 0.0 {
+                        return Err(ScenarioError::InvalidScript(format!(
+                            "invalid speed {kilometers_per_hour} km/h"
+                        )));
+                    }
+                    self.speed = kilometers_per_hour / 3.6;
+                }
+                ScriptStep::Accuracy { meters } => self.horizontal_accuracy = meters,
+                ScriptStep::Drive { seconds } => self.advance(seconds, true, true),
+                ScriptStep::DriveToEnd => {
+                    if self.speed <= 0.0 {
+                        return Err(ScenarioError::InvalidScript(
+                            "cannot drive to the end at a speed of zero".to_string(),
+                        ));
+                    }
+                    while self.distance < self.route_length {
+                        self.tick(true, true);
+                    }
+                }
+                ScriptStep::Stop { seconds } => self.advance(seconds, false, true),
+                ScriptStep::SignalLoss { seconds } => self.advance(seconds, true, false),
+                ScriptStep::Teleport { lat, lng } => {
+                    self.time += self.fix_interval;
+                    self.trace.push(TraceFix {
+                        lat,
+                        lng,
+                        time: self.time,
+                        horizontal_accuracy: self.horizontal_accuracy,
+                        course: None,
+                        speed: None,
+                    });
+                }
+            }
+        }
+        Ok(self.trace)
+    }
+
+    /// Advances by `seconds`, with a fix every fix interval if `receive_fixes` is set.
+    fn advance(&mut self, seconds: f64, moving: bool, receive_fixes: bool) {
+        let end = self.time + seconds.max(0.0);
+        while self.time + self.fix_interval <= end {
+            self.tick(moving, receive_fixes);
+        }
+        // Any remaining time passes without a fix
+        let remaining = end - self.time;
+        if moving {
+            self.distance = (self.distance + self.speed * remaining).min(self.route_length);
+        }
+        self.time = end;
+    }
+
+    fn tick(&mut self, moving: bool, receive_fix: bool) {
+        self.time += self.fix_interval;
+        if moving {
+            self.distance = (self.distance + self.speed * self.fix_interval).min(self.route_length);
+        }
+        if receive_fix {
+            self.push_fix(moving);
+        }
+    }
+
+    fn push_fix(&mut self, moving: bool) {
+        let position = self.position_at(self.distance);
+        // The course is the direction of the route at the current position
+        let (from, to) = if self.distance + 1.0 <= self.route_length {
+            (position, self.position_at(self.distance + 1.0))
+        } else {
+            (self.position_at(self.distance - 1.0), position)
+        };
+        let bearing = Point::from(from).haversine_bearing(Point::from(to));
+        let is_moving = moving && self.speed > 0.0;
+        self.trace.push(TraceFix {
+            lat: position.lat,
+            lng: position.lng,
+            time: self.time,
+            horizontal_accuracy: self.horizontal_accuracy,
+            course: (is_moving && from != to)
+                .then(|| bearing.rem_euclid(360.0).round() as u16 % 360),
+            speed: Some(if is_moving { self.speed } else { 0.0 }),
+        });
+    }
+
+    fn position_at(&self, distance: f64) -> GeographicCoordinate {
+        if distance >= self.route_length {
+            // Avoid interpolating along any repeated coordinates at the end of the route
+            if let Some(&last) = self.geometry.last() {
+                return last;
+            }
+        }
+        point_along_line(&self.geometry, distance)
+            .unwrap_or(GeographicCoordinate { lat: 0.0, lng: 0.0 })
+    }
+}
+
+/// A [`NavigationEvent`], reduced to the details which are stable enough to write by hand.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum ScenarioEvent {
+    /// Navigation advanced to the step with this instruction.
+    StepAdvanced {
+        instruction: String,
+    },
+    /// Navigation moved back to the step with this instruction.
+    StepRegressed {
+        instruction: String,
+    },
+    WaypointReached,
+    TripCompleted,
+    RouteChanged,
+    FasterRouteAvailable,
+    RouteStale,
+    NextTripStarted,
+    CountryChanged {
+        iso_3166: String,
+    },
+    JunctionViewApproaching,
+    FinalApproach,
+    ArrivedNearby,
+    TripSummaryAvailable,
+}
+
+impl From<&NavigationEvent> for ScenarioEvent {
+    fn from(event: &NavigationEvent) -> Self {
+        match event {
+            NavigationEvent::StepAdvanced { step } => ScenarioEvent::StepAdvanced {
+                instruction: step.instruction.clone(),
+            },
+            NavigationEvent::StepRegressed { step } => ScenarioEvent::StepRegressed {
+                instruction: step.instruction.clone(),
+            },
+            NavigationEvent::WaypointReached { .. } => ScenarioEvent::WaypointReached,
+            NavigationEvent::TripCompleted => ScenarioEvent::TripCompleted,
+            NavigationEvent::RouteChanged { .. } => ScenarioEvent::RouteChanged,
+            NavigationEvent::FasterRouteAvailable { .. } => ScenarioEvent::FasterRouteAvailable,
+            NavigationEvent::RouteStale { .. } => ScenarioEvent::RouteStale,
+            NavigationEvent::NextTripStarted { .. } => ScenarioEvent::NextTripStarted,
+            NavigationEvent::CountryChanged { iso_3166 } => ScenarioEvent::CountryChanged {
+                iso_3166: iso_3166.clone(),
+            },
+            NavigationEvent::JunctionViewApproaching { .. } => {
+                ScenarioEvent::JunctionViewApproaching
+            }
+            NavigationEvent::FinalApproach { .. } => ScenarioEvent::FinalApproach,
+            NavigationEvent::ArrivedNearby { .. } => ScenarioEvent::ArrivedNearby,
+            NavigationEvent::TripSummaryAvailable { .. } => ScenarioEvent::TripSummaryAvailable,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ScenarioFixture<'a> {
+    #[serde(default = "default_polyline_precision")]
+    polyline_precision: u32,
+    #[serde(borrow)]
+    route_response: &'a RawValue,
+    #[serde(default)]
+    trace: Vec<TraceFix>,
+    #[serde(default)]
+    script: Vec<ScriptStep>,
+    /// The time between the fixes generated by the script, in seconds.
+    #[serde(default = "default_fix_interval")]
+    fix_interval: f64,
+    expected_events: Vec<ScenarioEvent>,
+}
+
+fn default_polyline_precision() -> u32 {
+    6
+}
+
+fn default_fix_interval() -> f64 {
+    1.0
+}
+
+/// A route, a GPS trace along it, and the events which navigating the trace should emit.
+///
+/// Scripted scenarios are converted to a trace when they are loaded.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Scenario {
+    pub route: Route,
+    pub trace: Vec<TraceFix>,
+    pub expected_events: Vec<ScenarioEvent>,
+}
+
+impl Scenario {
+    /// Parses a scenario from the contents of a fixture file.
+    pub fn from_json(json: &str) -> Result<Self, ScenarioError> {
+        let fixture: ScenarioFixture = serde_json::from_str(json)?;
+        let route = OsrmResponseParser::new(fixture.polyline_precision)
+            .parse_response(fixture.route_response.get().as_bytes().to_vec())?
+            .into_iter()
+            .next()
+            .ok_or(RoutingResponseParseError::NoRoutes)?;
+        let trace = match (fixture.trace.is_empty(), fixture.script.is_empty()) {
+            (false, false) => return Err(ScenarioError::TraceAndScript),
+            (true, true) => return Err(ScenarioError::EmptyTrace),
+            (false, true) => fixture.trace,
+            (true, false) => ScriptDriver::new(route.geometry.clone(), fixture.fix_interval)
+                .run(&fixture.script)?,
+        };
+
+        Ok(Self {
+            route,
+            trace,
+            expected_events: fixture.expected_events,
+        })
+    }
+
+    /// Loads a scenario from a fixture file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ScenarioError> {
+        Self::from_json(&std::fs::read_to_string(path)?)
+    }
+
+    /// Navigates the trace with the config and returns the events emitted along the way.
+    ///
+    /// Navigation starts at the first fix in the trace.
+    /// The trace is replayed with a [`ReplayClock`] (unless the config has its own clock),
+    /// so that configs which reject old locations behave as they would in the field.
+    pub fn run(&self, config: NavigationControllerConfig) -> Vec<ScenarioEvent> {
+        let start_time = now();
+        let mut locations: Vec<_> = self
+            .trace
+            .iter()
+            .map(|fix| UserLocation {
+                coordinates: GeographicCoordinate {
+                    lat: fix.lat,
+                    lng: fix.lng,
+                },
+                horizontal_accuracy: fix.horizontal_accuracy,
+                course_over_ground: fix
+                    .course
+                    .map(|degrees| CourseOverGround::new(degrees, None)),
+                timestamp: start_time + Duration::try_from_secs_f64(fix.time).unwrap_or_default(),
+                speed: fix.speed.map(|value| Speed {
+                    value,
+                    accuracy: None,
+                }),
+                altitude: None,
+                vertical_accuracy: None,
+            })
+            .collect();
+        locations.sort_by_key(|location| location.timestamp);
+        let Some((&first, rest)) = locations.split_first() else {
+            return vec![];
+        };
+
+        let replay_clock = Arc::new(ReplayClock::new(first.timestamp));
+        let clock: Arc<dyn Clock> = replay_clock.clone();
+        let controller = NavigationController::new(
+            self.route.clone(),
+            NavigationControllerConfig {
+                clock: Some(config.clock.clone().unwrap_or(clock)),
+                ..config
+            },
+        );
+        let mut state = controller.get_initial_state(first);
+        let mut events = Vec::new();
+        // Each location is processed at the time it was recorded
+        for &location in rest {
+            replay_clock.observe(location.timestamp);
+            let update = controller.update_user_locations(vec![location], &state);
+            state = update.state;
+            events.extend(update.events.iter().map(ScenarioEvent::from));
+        }
+        events
+    }
+
+    /// Navigates the trace with the config and asserts that the expected events are emitted, in order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the emitted events differ from the expected events.
+    /// The message includes the emitted events in the fixture format,
+    /// so that the fixture can be updated if the change is intended.
+    pub fn assert_events(&self, config: NavigationControllerConfig) {
+        let events = self.run(config);
+        assert!(
+            events == self.expected_events,
+            "The emitted events do not match the scenario.\nExpected: {:?}\nEmitted (as \"expected_events\"):\n{}",
+            self.expected_events,
+            serde_json::to_string_pretty(&events).unwrap_or_default()
+        );
+    }
+}
+
+/// Parses arbitrary bytes as an OSRM response with every parsing mode
+/// (eager, lazy, and streaming in chunks of `chunk_size` bytes).
+///
+/// Any result is acceptable, so long as parsing does not panic.
+pub fn fuzz_osrm_response(data: &[u8], polyline_precision: u32, chunk_size: usize) {
+    let response = data.to_vec();
+    let _ = OsrmResponseParser::new(polyline_precision).parse_response(response.clone());
+    let _ = OsrmResponseParser::with_lazy_step_parsing(polyline_precision).parse_response(response);
+
+    let streaming = StreamingOsrmResponseParser::new(polyline_precision);
+    for chunk in data.chunks(chunk_size.max(1)) {
+        if streaming.push(chunk).is_err() {
+            break;
+        }
+        let _ = streaming.provisional_route();
+    }
+    let _ = streaming.finish();
+}
+
+/// Parses arbitrary bytes as a single OSRM route step (ex: to focus on the step conversion,
+/// which is where most of the backend-specific extensions are handled).
+///
+/// Any result is acceptable, so long as parsing does not panic.
+pub fn fuzz_osrm_step(data: &[u8], polyline_precision: u32) {
+    if let Ok(step) = serde_json::from_slice::<OsrmRouteStep>(data) {
+        let _ = RouteStep::from_osrm(&step, polyline_precision, "en-US", ResponseIndex::route(0));
+    }
+}
+
+/// Decodes an arbitrary string as a polyline, the way route geometries are decoded.
+///
+/// Any result is acceptable, so long as decoding does not panic.
+pub fn fuzz_polyline(polyline: &str, precision: u32) {
+    let _ = decode_polyline(polyline, precision);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_invalid_fixtures() {
+        assert!(matches!(
+            Scenario::from_json("{}"),
+            Err(ScenarioError::InvalidFixture(_))
+        ));
+        assert!(matches!(
+            Scenario::from_json(
+                r#"{"route_response": {"routes": [], "waypoints": [], "code": "Ok"}, "trace": [], "expected_events": []}"#
+            ),
+            Err(ScenarioError::InvalidRouteResponse(_))
+        ));
+    }
+
+    #[test]
+    fn parses_expected_events() {
+        let events: Vec<ScenarioEvent> = serde_json::from_str(
+            r#"[{"type": "StepAdvanced", "instruction": "Turn left"}, {"type": "TripCompleted"}]"#,
+        )
+        .expect("Unable to parse events");
+        assert_eq!(
+            events,
+            vec![
+                ScenarioEvent::StepAdvanced {
+                    instruction: "Turn left".to_string()
+                },
+                ScenarioEvent::TripCompleted,
+            ]
+        );
+    }
+
+    #[test]
+    fn scripts_generate_traces() {
+        // About 111m east along the equator
+        let geometry = vec![
+            GeographicCoordinate { lat: 0.0, lng: 0.0 },
+            GeographicCoordinate {
+                lat: 0.0,
+                lng: 0.001,
+            },
+        ];
+        let script: Vec<ScriptStep> = serde_json::from_str(
+            r#"[
+                {"type": "Speed", "kilometers_per_hour": 36},
+                {"type": "Drive", "seconds": 3},
+                {"type": "SignalLoss", "seconds": 2.5},
+                {"type": "Teleport", "lat": 0.01, "lng": 0.0},
+                {"type": "DriveToEnd"}
+            ]"#,
+        )
+        .expect("Unable to parse script");
+        let trace = ScriptDriver::new(geometry.clone(), 1.0)
+            .run(&script)
+            .expect("Unable to run script");
+
+        let times: Vec<f64> = trace.iter().map(|fix| fix.time).collect();
+        assert_eq!(
+            times,
+            vec![0.0, 1.0, 2.0, 3.0, 6.5, 7.5, 8.5, 9.5, 10.5, 11.5, 12.5]
+        );
+        let distance = |fix: &TraceFix| {
+            Point::from(geometry[0]).haversine_distance(&Point::new(fix.lng, fix.lat))
+        };
+        // 10 m/s, continuing through the signal loss (to 55m)
+        assert!((distance(&trace[3]) - 30.0).abs() < 0.01);
+        assert!((distance(&trace[5]) - 65.0).abs() < 0.01);
+        assert_eq!((trace[4].lat, trace[4].course), (0.01, None));
+        assert_eq!(trace[5].course, Some(90));
+        // The drive ends at the end of the route
+        let last = trace.last().unwrap();
+        assert_eq!((last.lat, last.lng), (0.0, 0.001));
+
+        assert!(matches!(
+            ScriptDriver::new(geometry, 1.0).run(&[
+                ScriptStep::Speed {
+                    kilometers_per_hour: 0.0
+                },
+                ScriptStep::DriveToEnd
+            ]),
+            Err(ScenarioError::InvalidScript(_))
+        ));
+    }
+
+    #[test]
+    fn fuzz_entry_points_accept_anything() {
+        let fixture = include_str!("../tests/fixtures/scenarios/two_step_arrival.json");
+        let scenario: serde_json::Value =
+            serde_json::from_str(fixture).expect("Unable to parse fixture");
+        let response = scenario["route_response"].to_string();
+        let step = scenario["route_response"]["routes"][0]["legs"][0]["steps"][0].to_string();
+
+        for chunk_size in [0, 1, 7, response.len()] {
+            fuzz_osrm_response(response.as_bytes(), 6, chunk_size);
+            fuzz_osrm_response(&response.as_bytes()[..response.len() / 2], 6, chunk_size);
+        }
+        fuzz_osrm_response(b"\xff{\"code\"", 6, 2);
+        fuzz_osrm_step(step.as_bytes(), 6);
+        fuzz_osrm_step(step.as_bytes(), u32::MAX);
+        fuzz_polyline("~~~~~~~~~~~~~~~~~~~~~~~~~~~~~", 6);
+    }
+}
@@ -0,0 +1,142 @@
+//! Map tiles to prefetch for offline navigation along a route.
+//!
+//! Apps which navigate through areas without connectivity can download the tiles
+//! covering a corridor around the route before the trip starts.
+
+use crate::models::Route;
+use crate::projection::{
+    coordinate_to_world, tile_index, web_mercator_scale_factor, TileCoordinate, WorldCoordinate,
+    MAX_ZOOM, WEB_MERCATOR_RADIUS,
+};
+use std::collections::BTreeSet;
+use std::f64::consts::PI;
+
+/// Finds the XYZ tiles covering a corridor around the route geometry at each zoom level in
+/// `min_zoom..=max_zoom` (clamped to 30).
+///
+/// The corridor extends `buffer` meters on either side of the route
+/// (or somewhat further, as the corridor is made of rectangles).
+/// Tiles are returned in order of zoom level, then column, then row.
+///
+/// NOTE: The number of tiles grows quickly with the zoom level (roughly doubling with each level),
+/// so take care to limit the zoom range for long routes.
+#[uniffi::export]
+pub fn tiles_along_route(
+    route: &Route,
+    min_zoom: u8,
+    max_zoom: u8,
+    buffer: f64,
+) -> Vec<TileCoordinate> {
+    let points: Vec<_> = route
+        .geometry
+        .iter()
+        .map(|coordinate| (coordinate_to_world(*coordinate), coordinate.lat))
+        .collect();
+    let segments: Vec<_> = match points.as_slice() {
+        [] => vec![],
+        [point] => vec![(*point, *point)],
+        _ => points.windows(2).map(|pair| (pair[0], pair[1])).collect(),
+    };
+
+    let circumference = 2.0 * PI * WEB_MERCATOR_RADIUS;
+    let mut tiles = BTreeSet::new();
+    for zoom in min_zoom.min(MAX_ZOOM)..=max_zoom.min(MAX_ZOOM) {
+        // Segments are split into pieces no longer than a quarter of a tile,
+        // so that their bounding boxes hug the route
+        let max_piece_length = 0.25 / f64::from(1_u32 << zoom);
+        for ((start, start_lat), (end, end_lat)) in &segments {
+            // The buffer is widest (in world space) at the latitude furthest from the equator
+            let scale_factor = web_mercator_scale_factor(start_lat.abs().max(end_lat.abs()));
+            let world_buffer = buffer.max(0.0) / (circumference * scale_factor);
+
+            let length = (end.x - start.x).hypot(end.y - start.y);
+            let piece_count = ((length / max_piece_length).ceil() as u32).max(1);
+            let at = |fraction: f64| WorldCoordinate {
+                x: start.x + (end.x - start.x) * fraction,
+                y: start.y + (end.y - start.y) * fraction,
+            };
+            for piece in 0..piece_count {
+                let a = at(f64::from(piece) / f64::from(piece_count));
+                let b = at(f64::from(piece + 1) / f64::from(piece_count));
+                let columns = tile_index(a.x.min(b.x) - world_buffer, zoom)
+                    ..=tile_index(a.x.max(b.x) + world_buffer, zoom);
+                let rows = tile_index(a.y.min(b.y) - world_buffer, zoom)
+                    ..=tile_index(a.y.max(b.y) + world_buffer, zoom);
+                for x in columns {
+                    for y in rows.clone() {
+                        tiles.insert((zoom, x, y));
+                    }
+                }
+            }
+        }
+    }
+
+    tiles
+        .into_iter()
+        .map(|(z, x, y)| TileCoordinate { x, y, z })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::GeographicCoordinate;
+    use crate::navigation_controller::test_helpers::{gen_dummy_route_step, gen_route_from_steps};
+    use crate::projection::{coordinate_to_tile, TileScheme};
+
+    #[test]
+    fn covers_route_corridor() {
+        // About 5.6km due east, just north of the equator
+        let route = gen_route_from_steps(vec![gen_dummy_route_step(0.0, 0.001, 0.05, 0.001)]);
+
+        let tiles = tiles_along_route(&route, 14, 15, 0.0);
+        for zoom in [14, 15] {
+            for lng in [0.0, 0.0125, 0.025, 0.0375, 0.05] {
+                let tile = coordinate_to_tile(
+                    GeographicCoordinate { lat: 0.001, lng },
+                    zoom,
+                    TileScheme::Xyz,
+                );
+                assert!(tiles.contains(&tile), "Missing {tile:?}");
+            }
+        }
+        // Tiles at zoom 14 are about 2.4km wide
+        assert_eq!(
+            tiles
+                .iter()
+                .filter(|tile| tile.z == 14)
+                .copied()
+                .collect::<Vec<_>>(),
+            vec![
+                TileCoordinate {
+                    x: 8192,
+                    y: 8191,
+                    z: 14
+                },
+                TileCoordinate {
+                    x: 8193,
+                    y: 8191,
+                    z: 14
+                },
+                TileCoordinate {
+                    x: 8194,
+                    y: 8191,
+                    z: 14
+                },
+            ]
+        );
+        assert!(tiles.windows(2).all(|pair| pair[0].z <= pair[1].z));
+
+        // A wide buffer includes the neighboring tiles on every side
+        let buffered = tiles_along_route(&route, 14, 14, 3000.0);
+        for (x, y) in [(8191, 8190), (8195, 8192)] {
+            assert!(buffered.contains(&TileCoordinate { x, y, z: 14 }));
+        }
+    }
+
+    #[test]
+    fn empty_zoom_range() {
+        let route = gen_route_from_steps(vec![gen_dummy_route_step(0.0, 0.0, 0.02, 0.0)]);
+        assert!(tiles_along_route(&route, 10, 9, 100.0).is_empty());
+    }
+}
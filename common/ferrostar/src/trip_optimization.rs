@@ -0,0 +1,301 @@
+//! Ordering of waypoints to minimize the total travel time (the traveling salesman problem).
+//!
+//! Couriers and other multi-stop trips often have a set of stops which can be visited in any order.
+//! Given a duration matrix (ex: from [`crate::routing_adapters::matrix`], or supplied by the app),
+//! [`optimize_waypoints`] finds a fast order, which can then be used to request the route.
+//!
+//! Small trips are solved exactly. Larger ones use a nearest neighbor tour improved with 2-opt,
+//! which is usually within a few percent of the optimum.
+
+use crate::models::Waypoint;
+
+/// The largest number of reorderable stops which is solved exactly.
+///
+/// The exact solution takes O(2^n * n^2) time, which is a few milliseconds at this size.
+const MAX_EXACT_STOPS: usize = 12;
+
+#[derive(Debug, thiserror::Error, uniffi::Error)]
+pub enum TripOptimizationError {
+    #[error("The duration matrix must have one row and column for the start plus each waypoint.")]
+    InvalidMatrix,
+    #[error("There is no order in which every waypoint can be reached.")]
+    Unreachable,
+}
+
+/// Reorders waypoints to minimize the total travel time, starting from the user's location.
+///
+/// `durations` is a square matrix of travel times (in seconds) between the start (index 0)
+/// and each waypoint (index 1 onward), indexed by origin, then by destination.
+/// Missing entries mean that the destination cannot be reached from the origin.
+///
+/// If `keep_destination` is true, the last waypoint stays at the end of the trip
+/// and only the intermediate waypoints are reordered.
+#[uniffi::export]
+pub fn optimize_waypoints(
+    durations: &[Vec<Option<f64>>],
+    waypoints: &[Waypoint],
+    keep_destination: bool,
+) -> Result<Vec<Waypoint>, TripOptimizationError> {
+    if durations.len() != waypoints.len() + 1 {
+        return Err(TripOptimizationError::InvalidMatrix);
+    }
+
+    Ok(optimize_waypoint_order(durations, keep_destination)?
+        .into_iter()
+        .map(|index| waypoints[index])
+        .collect())
+}
+
+/// Finds the order in which to visit the waypoints (see [`optimize_waypoints`]).
+///
+/// Returns the indices of the waypoints in the order they should be visited.
+pub fn optimize_waypoint_order(
+    durations: &[Vec<Option<f64>>],
+    keep_destination: bool,
+) -> Result<Vec<usize>, TripOptimizationError> {
+    if durations.is_empty() || durations.iter().any(|row| row.len() != durations.len()) {
+        return Err(TripOptimizationError::InvalidMatrix);
+    }
+
+    let waypoint_count = durations.len() - 1;
+    let (stops, end) = if keep_destination && waypoint_count > 0 {
+        (
+            (1..waypoint_count).collect::<Vec<_>>(),
+            Some(waypoint_count),
+        )
+    } else {
+        ((1..=waypoint_count).collect(), None)
+    };
+
+    let order = if stops.len() <= MAX_EXACT_STOPS {
+        exact_order(durations, &stops, end)
+    } else {
+        two_opt(durations, nearest_neighbor_order(durations, &stops), end)
+    };
+    if !path_duration(durations, &order, end).is_finite() {
+        return Err(TripOptimizationError::Unreachable);
+    }
+
+    // Convert from matrix indices to waypoint indices
+    Ok(order
+        .into_iter()
+        .chain(end)
+        .map(|index| index - 1)
+        .collect())
+}
+
+fn duration(durations: &[Vec<Option<f64>>], from: usize, to: usize) -> f64 {
+    durations[from][to].unwrap_or(f64::INFINITY)
+}
+
+/// The total duration of visiting the stops in order, starting from index 0.
+fn path_duration(durations: &[Vec<Option<f64>>], order: &[usize], end: Option<usize>) -> f64 {
+    std::iter::once(0)
+        .chain(order.iter().copied())
+        .chain(end)
+        .collect::<Vec<_>>()
+        .windows(2)
+        .map(|pair| duration(durations, pair[0], pair[1]))
+        .sum()
+}
+
+/// Solves the problem exactly using the Held-Karp dynamic programming algorithm.
+fn exact_order(durations: &[Vec<Option<f64>>], stops: &[usize], end: Option<usize>) -> Vec<usize> {
+    let n = stops.len();
+    if n == 0 {
+        return vec![];
+    }
+
+    // best[mask * n + last] is the shortest path from the start which visits the stops in `mask`,
+    // ending at `last`.
+    let mut best = vec![f64::INFINITY; (1 << n) * n];
+    let mut previous = vec![usize::MAX; (1 << n) * n];
+    for (i, &stop) in stops.iter().enumerate() {
+        best[(1 << i) * n + i] = duration(durations, 0, stop);
+    }
+    for mask in 1..(1usize << n) {
+        for last in (0..n).filter(|last| mask & (1 << last) != 0) {
+            let so_far = best[mask * n + last];
+            if !so_far.is_finite() {
+                continue;
+            }
+            for next in (0..n).filter(|next| mask & (1 << next) == 0) {
+                let candidate = so_far + duration(durations, stops[last], stops[next]);
+                let index = (mask | (1 << next)) * n + next;
+                if candidate < best[index] {
+                    best[index] = candidate;
+                    previous[index] = last;
+                }
+            }
+        }
+    }
+
+    let full = (1 << n) - 1;
+    let total = |last: usize| {
+        best[full * n + last] + end.map_or(0.0, |end| duration(durations, stops[last], end))
+    };
+    let Some(mut last) = (0..n)
+        .filter(|&last| total(last).is_finite())
+        .min_by(|&a, &b| total(a).total_cmp(&total(b)))
+    else {
+        // Unreachable; any order will do
+        return stops.to_vec();
+    };
+
+    let mut order = Vec::with_capacity(n);
+    let mut mask = full;
+    while last != usize::MAX {
+        order.push(stops[last]);
+        let before = previous[mask * n + last];
+        mask &= !(1 << last);
+        last = before;
+    }
+    order.reverse();
+    order
+}
+
+/// Builds a tour by repeatedly visiting the nearest unvisited stop.
+fn nearest_neighbor_order(durations: &[Vec<Option<f64>>], stops: &[usize]) -> Vec<usize> {
+    let mut remaining = stops.to_vec();
+    let mut order = Vec::with_capacity(stops.len());
+    let mut current = 0;
+    while !remaining.is_empty() {
+        let (index, _) = remaining
+            .iter()
+            .enumerate()
+            .min_by(|&(_, &a), &(_, &b)| {
+                duration(durations, current, a).total_cmp(&duration(durations, current, b))
+            })
+            .expect("There is at least one remaining stop");
+        current = remaining.swap_remove(index);
+        order.push(current);
+    }
+    order
+}
+
+/// Improves a tour by reversing segments until no reversal makes it faster.
+///
+/// Travel times are not necessarily symmetric (ex: one-way streets),
+/// so every candidate is evaluated in full.
+fn two_opt(
+    durations: &[Vec<Option<f64>>],
+    mut order: Vec<usize>,
+    end: Option<usize>,
+) -> Vec<usize> {
+    let mut current = path_duration(durations, &order, end);
+    let mut improved = true;
+    while improved {
+        improved = false;
+        for i in 0..order.len() {
+            for j in i + 1..order.len() {
+                order[i..=j].reverse();
+                let candidate = path_duration(durations, &order, end);
+                if candidate < current {
+                    current = candidate;
+                    improved = true;
+                } else {
+                    order[i..=j].reverse();
+                }
+            }
+        }
+    }
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{GeographicCoordinate, WaypointKind};
+
+    /// A matrix for points along a line, where travel time is the distance between them.
+    fn line_matrix(positions: &[f64]) -> Vec<Vec<Option<f64>>> {
+        positions
+            .iter()
+            .map(|a| positions.iter().map(|b| Some((a - b).abs())).collect())
+            .collect()
+    }
+
+    #[test]
+    fn visits_stops_along_a_line_in_order() {
+        // Start at 0, with stops at 3, 1 and 2
+        let durations = line_matrix(&[0.0, 3.0, 1.0, 2.0]);
+        assert_eq!(
+            optimize_waypoint_order(&durations, false).expect("Unable to optimize"),
+            vec![1, 2, 0]
+        );
+    }
+
+    #[test]
+    fn keeps_the_destination() {
+        // The destination (at 2) is between the intermediate stops (at 4 and 1)
+        let durations = line_matrix(&[0.0, 4.0, 1.0, 2.0]);
+        assert_eq!(
+            optimize_waypoint_order(&durations, true).expect("Unable to optimize"),
+            vec![1, 0, 2]
+        );
+        assert_eq!(
+            optimize_waypoint_order(&durations, false).expect("Unable to optimize"),
+            vec![1, 2, 0]
+        );
+    }
+
+    #[test]
+    fn large_trips_use_the_heuristic() {
+        let positions: Vec<f64> = std::iter::once(0.0)
+            .chain((1..=20).rev().map(f64::from))
+            .collect();
+        let order =
+            optimize_waypoint_order(&line_matrix(&positions), false).expect("Unable to optimize");
+        assert_eq!(order, (0..20).rev().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn heuristic_is_improved_by_two_opt() {
+        // Nearest neighbor goes to 1 first, then has to double back past the start to -1.5
+        let durations = line_matrix(&[0.0, 1.0, -1.5, 5.0]);
+        let nearest = nearest_neighbor_order(&durations, &[1, 2, 3]);
+        assert_eq!(nearest, vec![1, 2, 3]);
+
+        let improved = two_opt(&durations, nearest, None);
+        assert_eq!(improved, exact_order(&durations, &[1, 2, 3], None));
+        assert!(
+            path_duration(&durations, &improved, None)
+                < path_duration(&durations, &[1, 2, 3], None)
+        );
+    }
+
+    #[test]
+    fn unreachable_waypoints() {
+        let mut durations = line_matrix(&[0.0, 1.0, 2.0]);
+        for row in &mut durations {
+            row[2] = None;
+        }
+        assert!(matches!(
+            optimize_waypoint_order(&durations, false),
+            Err(TripOptimizationError::Unreachable)
+        ));
+    }
+
+    #[test]
+    fn invalid_matrix() {
+        assert!(matches!(
+            optimize_waypoint_order(&[vec![Some(0.0), Some(1.0)]], false),
+            Err(TripOptimizationError::InvalidMatrix)
+        ));
+    }
+
+    #[test]
+    fn reorders_waypoints() {
+        let waypoint = |lng: f64| Waypoint {
+            coordinate: GeographicCoordinate { lat: 0.0, lng },
+            kind: WaypointKind::Break,
+        };
+        let waypoints = vec![waypoint(3.0), waypoint(1.0), waypoint(2.0)];
+
+        assert_eq!(
+            optimize_waypoints(&line_matrix(&[0.0, 3.0, 1.0, 2.0]), &waypoints, false)
+                .expect("Unable to optimize"),
+            vec![waypoint(1.0), waypoint(2.0), waypoint(3.0)]
+        );
+    }
+}
@@ -0,0 +1,299 @@
+//! Post-trip statistics, computed by the core so that every platform (and backend) agrees on them.
+//!
+//! The [`crate::navigation_controller::SynchronizedNavigationController`] keeps these statistics
+//! for the session, and reports a [`TripSummary`] when the trip is complete.
+
+use crate::algorithms::DistanceMeasurement;
+use crate::breadcrumbs::{BreadcrumbRecording, BreadcrumbTrail};
+use crate::deviation_detection::RouteDeviation;
+use crate::models::{GeographicCoordinate, UserLocation};
+use crate::navigation_controller::models::TripState;
+use geo::Point;
+#[cfg(feature = "wasm-js")]
+use serde::{Deserialize, Serialize};
+use std::time::SystemTime;
+
+/// Statistics about a trip, from the start of navigation to the last location update.
+#[derive(Debug, Clone, PartialEq, uniffi::Record)]
+#[cfg_attr(feature = "wasm-js", derive(Serialize, Deserialize))]
+pub struct TripSummary {
+    /// The distance traveled by the user (rather than the length of the route), in meters.
+    pub distance_traveled: f64,
+    /// The time between the first and last locations, in seconds.
+    ///
+    /// Time spent paused (see [`crate::navigation_controller::SynchronizedNavigationController::pause`])
+    /// is not included.
+    pub elapsed_time: f64,
+    /// The average speed over the trip, in meters per second.
+    ///
+    /// This is `None` if no time has elapsed.
+    pub average_speed: Option<f64>,
+    /// The number of times the route was replaced (ex: after going off route).
+    pub reroute_count: u32,
+    /// The furthest the user was from the route line while off route, in meters.
+    ///
+    /// This is zero if the user never went off route.
+    pub max_deviation: f64,
+    /// A simplified polyline of the user's locations over the trip.
+    ///
+    /// This is empty unless enabled by [`crate::navigation_controller::models::NavigationControllerConfig::breadcrumb_recording`].
+    pub breadcrumbs: Vec<GeographicCoordinate>,
+}
+
+/// Accumulates the statistics for a [`TripSummary`].
+#[derive(Debug, Default)]
+pub(crate) struct TripStatistics {
+    first_timestamp: Option<SystemTime>,
+    last_timestamp: Option<SystemTime>,
+    last_coordinate: Option<GeographicCoordinate>,
+    distance_traveled: f64,
+    reroute_count: u32,
+    max_deviation: f64,
+    complete: bool,
+    breadcrumbs: BreadcrumbTrail,
+    paused: bool,
+    /// Whether navigation was resumed, and no location has been recorded since.
+    resumed: bool,
+    paused_time: f64,
+}
+
+impl TripStatistics {
+    pub(crate) fn new(breadcrumb_recording: BreadcrumbRecording) -> Self {
+        Self {
+            breadcrumbs: BreadcrumbTrail::new(breadcrumb_recording),
+            ..Self::default()
+        }
+    }
+
+    /// Records a location, along with the state after it was processed.
+    ///
+    /// Locations which are older than the last one, or which navigation rejected
+    /// (ex: stale or inaccurate ones which did not update the user's position), are ignored,
+    /// as are locations after the trip is complete.
+    pub(crate) fn record_location(
+        &mut self,
+        location: &UserLocation,
+        state: &TripState,
+        measurement: DistanceMeasurement,
+    ) {
+        let accepted = match state {
+            TripState::Navigating {
+                snapped_user_location,
+                ..
+            } => snapped_user_location.timestamp == location.timestamp,
+            // The location which completed the trip
+            TripState::Complete => self.last_timestamp.is_some() && !self.complete,
+            TripState::FreeDrive { .. } => false,
+        };
+        if !accepted
+            || self
+                .last_timestamp
+                .is_some_and(|last| location.timestamp <= last)
+        {
+            return;
+        }
+
+        if self.resumed {
+            // The time between the last location before the pause and the first one after it
+            if let Some(last_timestamp) = self.last_timestamp {
+                self.paused_time += location
+                    .timestamp
+                    .duration_since(last_timestamp)
+                    .map_or(0.0, |duration| duration.as_secs_f64());
+            }
+            self.resumed = false;
+        }
+        if let Some(last_coordinate) = self.last_coordinate {
+            self.distance_traveled += measurement.distance(
+                &Point::from(last_coordinate),
+                &Point::from(location.coordinates),
+            );
+        }
+        self.first_timestamp.get_or_insert(location.timestamp);
+        self.last_timestamp = Some(location.timestamp);
+        self.last_coordinate = Some(location.coordinates);
+        self.breadcrumbs.push(location.coordinates);
+        self.complete = matches!(state, TripState::Complete);
+
+        if let TripState::Navigating {
+            deviation:
+                RouteDeviation::OffRoute {
+                    deviation_from_route_line,
+                },
+            ..
+        } = state
+        {
+            self.max_deviation = self.max_deviation.max(*deviation_from_route_line);
+        }
+    }
+
+    /// Whether the location which completed the trip has been recorded.
+    pub(crate) fn is_complete(&self) -> bool {
+        self.complete
+    }
+
+    /// The simplified trail of the user's locations so far.
+    pub(crate) fn breadcrumbs(&self) -> Vec<GeographicCoordinate> {
+        self.breadcrumbs.coordinates()
+    }
+
+    /// Records that navigation was paused.
+    pub(crate) fn record_pause(&mut self) {
+        self.paused = true;
+    }
+
+    /// Records that navigation was resumed.
+    ///
+    /// The pause is measured using location timestamps (rather than the wall clock),
+    /// from the last location before the pause to the first one after it,
+    /// and the distance between them is not counted (ex: the car may have been towed).
+    pub(crate) fn record_resume(&mut self) {
+        if std::mem::take(&mut self.paused) {
+            self.resumed = true;
+            self.last_coordinate = None;
+        }
+    }
+
+    /// Records that the route was replaced.
+    pub(crate) fn record_reroute(&mut self) {
+        self.reroute_count = self.reroute_count.saturating_add(1);
+    }
+
+    pub(crate) fn summary(&self) -> TripSummary {
+        let elapsed_time = match (self.first_timestamp, self.last_timestamp) {
+            (Some(first), Some(last)) => (last
+                .duration_since(first)
+                .map_or(0.0, |duration| duration.as_secs_f64())
+                - self.paused_time)
+                .max(0.0),
+            _ => 0.0,
+        };
+
+        TripSummary {
+            distance_traveled: self.distance_traveled,
+            elapsed_time,
+            average_speed: (elapsed_time > 0.0).then(|| self.distance_traveled / elapsed_time),
+            reroute_count: self.reroute_count,
+            max_deviation: self.max_deviation,
+            breadcrumbs: self.breadcrumbs(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::navigation_controller::test_helpers::{gen_navigating_state, gen_user_location};
+
+    #[test]
+    fn summarizes_trip() {
+        let mut statistics =
+            TripStatistics::new(BreadcrumbRecording::Simplified { tolerance: 5.0 });
+        let measurement = DistanceMeasurement::Haversine;
+        let off_route = RouteDeviation::OffRoute {
+            deviation_from_route_line: 40.0,
+        };
+
+        statistics.record_location(
+            &gen_user_location(0.0, 0.0, 0),
+            &gen_navigating_state(
+                gen_user_location(0.0, 0.0, 0),
+                vec![],
+                RouteDeviation::NoDeviation,
+            ),
+            measurement,
+        );
+        statistics.record_location(
+            &gen_user_location(0.005, 0.0, 50),
+            &gen_navigating_state(gen_user_location(0.005, 0.0, 50), vec![], off_route),
+            measurement,
+        );
+        // Rejected by navigation (the state still has the previous location)
+        statistics.record_location(
+            &gen_user_location(1.0, 0.0, 60),
+            &gen_navigating_state(gen_user_location(0.005, 0.0, 50), vec![], off_route),
+            measurement,
+        );
+        statistics.record_location(
+            &gen_user_location(0.008, 0.0, 70),
+            &gen_navigating_state(
+                gen_user_location(0.008, 0.0, 70),
+                vec![],
+                RouteDeviation::OffRoute {
+                    deviation_from_route_line: 25.0,
+                },
+            ),
+            measurement,
+        );
+        statistics.record_reroute();
+        statistics.record_location(
+            &gen_user_location(0.01, 0.0, 100),
+            &TripState::Complete,
+            measurement,
+        );
+        // Nothing more is recorded once the trip is complete
+        statistics.record_location(
+            &gen_user_location(0.1, 0.0, 110),
+            &TripState::Complete,
+            measurement,
+        );
+
+        let summary = statistics.summary();
+        // 0.01 degrees of longitude at the equator is about 1.1km
+        assert!((summary.distance_traveled - 1_111.95).abs() < 0.1);
+        assert!((summary.elapsed_time - 100.0).abs() < f64::EPSILON);
+        assert!((summary.average_speed.unwrap() - 11.1195).abs() < 0.001);
+        assert_eq!(summary.reroute_count, 1);
+        assert!((summary.max_deviation - 40.0).abs() < f64::EPSILON);
+        // The trail is a straight line (the rejected location is not included)
+        assert_eq!(
+            summary.breadcrumbs,
+            vec![
+                GeographicCoordinate { lat: 0.0, lng: 0.0 },
+                GeographicCoordinate {
+                    lat: 0.0,
+                    lng: 0.01
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn excludes_paused_time() {
+        let mut statistics = TripStatistics::new(BreadcrumbRecording::Disabled);
+        let measurement = DistanceMeasurement::Haversine;
+        let record = |statistics: &mut TripStatistics, seconds, lng| {
+            statistics.record_location(
+                &gen_user_location(lng, 0.0, seconds),
+                &gen_navigating_state(
+                    gen_user_location(lng, 0.0, seconds),
+                    vec![],
+                    RouteDeviation::NoDeviation,
+                ),
+                measurement,
+            );
+        };
+
+        record(&mut statistics, 0, 0.0);
+        record(&mut statistics, 100, 0.005);
+        // A lunch stop, after which the car is parked ~111m further along
+        statistics.record_pause();
+        statistics.record_pause();
+        statistics.record_resume();
+        record(&mut statistics, 3700, 0.006);
+        record(&mut statistics, 3800, 0.01);
+
+        let summary = statistics.summary();
+        assert!((summary.elapsed_time - 200.0).abs() < f64::EPSILON);
+        assert!((summary.distance_traveled - 1000.75).abs() < 0.1);
+        assert!((summary.average_speed.unwrap() - 5.003_76).abs() < 0.001);
+    }
+
+    #[test]
+    fn empty_trip() {
+        let summary = TripStatistics::default().summary();
+        assert_eq!(summary.average_speed, None);
+        assert!(summary.breadcrumbs.is_empty());
+        assert!(summary.distance_traveled.abs() < f64::EPSILON);
+    }
+}
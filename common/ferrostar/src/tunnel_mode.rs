@@ -0,0 +1,304 @@
+//! Navigation through tunnels.
+//!
+//! The positioning signal is usually degraded or lost entirely in tunnels,
+//! so the controller can keep progressing along the route based on the expected travel time
+//! and make upcoming spoken instructions available before the signal is lost.
+
+use crate::algorithms::DistanceMeasurement;
+use crate::models::{GeographicCoordinate, RouteStep, Speed, SpokenInstruction, UserLocation};
+use geo::{EuclideanDistance, HaversineIntermediate, Point};
+
+/// Determines how the navigation controller behaves in tunnels,
+/// where the positioning signal is usually degraded or lost entirely.
+///
+/// Tunnels are detected using [`RouteStep::tunnels`].
+#[derive(Debug, Copy, Clone, PartialEq, uniffi::Enum)]
+pub enum TunnelMode {
+    /// Tunnels receive no special treatment.
+    None,
+    /// Progresses along the route based on the expected travel time while in a tunnel.
+    ///
+    /// Location updates which are inaccurate are replaced with a position along the route,
+    /// estimated from the time elapsed since the last update and the expected speed of the step.
+    /// Spoken instructions which will be triggered in an upcoming tunnel are also made available
+    /// ahead of time so that they can be prepared (ex: synthesized by a network TTS service)
+    /// before the signal is lost.
+    RouteTime {
+        /// The minimum required horizontal accuracy of the user location, in meters.
+        /// While in a tunnel, less accurate values will be replaced with the estimated position.
+        minimum_horizontal_accuracy: u16,
+        /// The distance before a tunnel entrance at which to start pre-fetching
+        /// spoken instructions, in meters.
+        prefetch_distance: f64,
+    },
+}
+
+impl TunnelMode {
+    /// Replaces an inaccurate location with a position estimated from the route
+    /// when the user was last known to be in a tunnel.
+    ///
+    /// `previous_location` is the last snapped user location,
+    /// and `distance_to_end_of_step` is its distance from the end of the current step.
+    pub(crate) fn dead_reckon(
+        &self,
+        location: UserLocation,
+        previous_location: &UserLocation,
+        distance_to_end_of_step: f64,
+        current_step: &RouteStep,
+        measurement: DistanceMeasurement,
+    ) -> UserLocation {
+        let TunnelMode::RouteTime {
+            minimum_horizontal_accuracy,
+            ..
+        } = self
+        else {
+            return location;
+        };
+
+        let is_in_tunnel = current_step
+            .tunnels
+            .iter()
+            .any(|tunnel| tunnel.contains(distance_to_end_of_step));
+        if !is_in_tunnel
+            || location.horizontal_accuracy <= f64::from(*minimum_horizontal_accuracy)
+            || current_step.duration <= 0.0
+        {
+            return location;
+        }
+
+        let speed = current_step.distance / current_step.duration;
+        let elapsed = location
+            .timestamp
+            .duration_since(previous_location.timestamp)
+            .map_or(0.0, |duration| duration.as_secs_f64());
+
+        UserLocation {
+            coordinates: travel_along_step(
+                previous_location.coordinates,
+                current_step,
+                speed * elapsed,
+                measurement,
+            ),
+            horizontal_accuracy: 0.0,
+            course_over_ground: None,
+            timestamp: location.timestamp,
+            speed: Some(Speed {
+                value: speed,
+                accuracy: None,
+            }),
+            altitude: None,
+            vertical_accuracy: None,
+        }
+    }
+
+    /// Gets the spoken instructions that will be triggered in an upcoming tunnel.
+    ///
+    /// Tunnels on the current and next steps are considered.
+    pub(crate) fn prefetch_spoken_instructions(
+        &self,
+        remaining_steps: &[RouteStep],
+        distance_to_next_maneuver: f64,
+        measurement: DistanceMeasurement,
+    ) -> Vec<SpokenInstruction> {
+        let TunnelMode::RouteTime {
+            prefetch_distance, ..
+        } = self
+        else {
+            return vec![];
+        };
+
+        let mut instructions = vec![];
+        for (index, step) in remaining_steps.iter().take(2).enumerate() {
+            // Positions on the next step are further away by the length of that step
+            let distance_to_end_of_step = if index == 0 {
+                distance_to_next_maneuver
+            } else {
+                distance_to_next_maneuver + measurement.length(&step.get_linestring())
+            };
+
+            for tunnel in &step.tunnels {
+                let is_approaching = distance_to_end_of_step
+                    >= tunnel.exit_distance_before_maneuver
+                    && distance_to_end_of_step - tunnel.entrance_distance_before_maneuver
+                        <= *prefetch_distance;
+                if !is_approaching {
+                    continue;
+                }
+
+                // Only instructions which have not been triggered yet are of interest
+                let last_trigger = tunnel
+                    .entrance_distance_before_maneuver
+                    .min(distance_to_end_of_step);
+                instructions.extend(
+                    step.spoken_instructions
+                        .iter()
+                        .filter(|instruction| {
+                            (tunnel.exit_distance_before_maneuver..=last_trigger)
+                                .contains(&instruction.trigger_distance_before_maneuver)
+                        })
+                        .cloned(),
+                );
+            }
+        }
+
+        instructions
+    }
+}
+
+/// Travels the given distance along the step geometry, starting from a point on the step.
+///
+/// The result will never go past the end of the step.
+pub(crate) fn travel_along_step(
+    start: GeographicCoordinate,
+    step: &RouteStep,
+    distance: f64,
+    measurement: DistanceMeasurement,
+) -> GeographicCoordinate {
+    let linestring = step.get_linestring();
+    let start = Point::from(start);
+    let Some((segment_index, _)) = linestring.lines().enumerate().min_by(|(_, a), (_, b)| {
+        a.euclidean_distance(&start)
+            .total_cmp(&b.euclidean_distance(&start))
+    }) else {
+        return start.into();
+    };
+
+    let mut position = start;
+    let mut remaining = distance;
+    for vertex in linestring.points().skip(segment_index + 1) {
+        let segment_length = measurement.distance(&position, &vertex);
+        if segment_length >= remaining {
+            if segment_length > 0.0 {
+                position = position.haversine_intermediate(&vertex, remaining / segment_length);
+            }
+            return position.into();
+        }
+        remaining -= segment_length;
+        position = vertex;
+    }
+
+    position.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Tunnel;
+    use crate::navigation_controller::test_helpers::{gen_dummy_route_step, gen_user_location};
+    use geo::HaversineDistance;
+    use uuid::Uuid;
+
+    const TUNNEL_MODE: TunnelMode = TunnelMode::RouteTime {
+        minimum_horizontal_accuracy: 20,
+        prefetch_distance: 200.0,
+    };
+    const MEASUREMENT: DistanceMeasurement = DistanceMeasurement::Haversine;
+
+    fn spoken_instruction(trigger_distance_before_maneuver: f64) -> SpokenInstruction {
+        SpokenInstruction {
+            text: format!("In {trigger_distance_before_maneuver} meters, turn left"),
+            ssml: None,
+            trigger_distance_before_maneuver,
+            utterance_id: Uuid::new_v4(),
+        }
+    }
+
+    /// A step heading east for ~1,113m at 10m/s with a tunnel for most of it.
+    fn step_with_tunnel() -> RouteStep {
+        RouteStep {
+            distance: 1113.0,
+            duration: 111.3,
+            spoken_instructions: vec![
+                spoken_instruction(1050.0),
+                spoken_instruction(500.0),
+                spoken_instruction(100.0),
+            ],
+            tunnels: vec![Tunnel {
+                name: Some("Test Tunnel".to_string()),
+                entrance_distance_before_maneuver: 1000.0,
+                exit_distance_before_maneuver: 200.0,
+            }],
+            ..gen_dummy_route_step(0.0, 0.0, 0.01, 0.0)
+        }
+    }
+
+    #[test]
+    fn dead_reckoning_in_tunnel() {
+        let step = step_with_tunnel();
+        // ~556m from the end of the step, in the tunnel
+        let previous = gen_user_location(0.005, 0.0, 0);
+        let inaccurate = gen_user_location(0.002, 0.0, 10).with_horizontal_accuracy(500.0);
+
+        let estimated = TUNNEL_MODE.dead_reckon(inaccurate, &previous, 556.0, &step, MEASUREMENT);
+        let traveled = Point::from(previous).haversine_distance(&Point::from(estimated));
+        assert!((traveled - 100.0).abs() < 0.1, "Traveled {traveled}m");
+        assert!(estimated.coordinates.lng > previous.coordinates.lng);
+        assert_eq!(estimated.timestamp, inaccurate.timestamp);
+
+        // Accurate locations are used as-is
+        let accurate = gen_user_location(0.002, 0.0, 10);
+        assert_eq!(
+            TUNNEL_MODE.dead_reckon(accurate, &previous, 556.0, &step, MEASUREMENT),
+            accurate
+        );
+        // As are inaccurate locations outside the tunnel
+        assert_eq!(
+            TUNNEL_MODE.dead_reckon(inaccurate, &previous, 1100.0, &step, MEASUREMENT),
+            inaccurate
+        );
+        // Or when the mode is disabled
+        assert_eq!(
+            TunnelMode::None.dead_reckon(inaccurate, &previous, 556.0, &step, MEASUREMENT),
+            inaccurate
+        );
+    }
+
+    #[test]
+    fn dead_reckoning_stops_at_end_of_step() {
+        let step = step_with_tunnel();
+        let previous = gen_user_location(0.009, 0.0, 0);
+        let estimated = TUNNEL_MODE.dead_reckon(
+            gen_user_location(0.0, 0.0, 600).with_horizontal_accuracy(500.0),
+            &previous,
+            300.0,
+            &step,
+            MEASUREMENT,
+        );
+        assert_eq!(
+            estimated.coordinates,
+            GeographicCoordinate {
+                lat: 0.0,
+                lng: 0.01
+            }
+        );
+    }
+
+    #[test]
+    fn prefetch_spoken_instructions() {
+        let step = step_with_tunnel();
+        let trigger_distances = |distance_to_next_maneuver: f64, steps: &[RouteStep]| {
+            TUNNEL_MODE
+                .prefetch_spoken_instructions(steps, distance_to_next_maneuver, MEASUREMENT)
+                .iter()
+                .map(|instruction| instruction.trigger_distance_before_maneuver)
+                .collect::<Vec<_>>()
+        };
+
+        // Approaching the tunnel
+        assert_eq!(trigger_distances(1100.0, &[step.clone()]), vec![500.0]);
+        // In the tunnel, but the instruction has already been triggered
+        assert!(trigger_distances(400.0, &[step.clone()]).is_empty());
+        // Tunnels on the next step are considered too
+        let previous_step = gen_dummy_route_step(-0.01, 0.0, 0.0, 0.0);
+        assert_eq!(
+            trigger_distances(50.0, &[previous_step.clone(), step.clone()]),
+            vec![500.0]
+        );
+        // Too far from the tunnel
+        assert!(trigger_distances(1000.0, &[previous_step, step]).is_empty());
+        // Nothing is pre-fetched when the mode is disabled
+        assert!(TunnelMode::None
+            .prefetch_spoken_instructions(&[step_with_tunnel()], 1100.0, MEASUREMENT)
+            .is_empty());
+    }
+}
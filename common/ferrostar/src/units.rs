@@ -0,0 +1,349 @@
+//! Typed distances, speeds, and durations, with conversion and formatting for display.
+//!
+//! Values in the core (ex: [`crate::navigation_controller::models::TripProgress`])
+//! are always measured in meters and seconds.
+//! These wrappers make the unit explicit, and keep unit conversion and rounding in one place,
+//! so that every platform displays the same values.
+//!
+//! NOTE: The wrappers are named `*Value` to avoid clashing with [`crate::models::Speed`]
+//! (a location provider's speed) and the platform duration types.
+
+use crate::models::SpeedUnit;
+use crate::navigation_controller::models::TripProgress;
+#[cfg(feature = "wasm-js")]
+use serde::{Deserialize, Serialize};
+
+const METERS_PER_KILOMETER: f64 = 1_000.0;
+const METERS_PER_MILE: f64 = 1_609.344;
+const METERS_PER_FOOT: f64 = 0.3048;
+const SECONDS_PER_HOUR: f64 = 3_600.0;
+/// Distances shorter than this are displayed in feet rather than miles.
+const MIN_DISPLAYED_MILES: f64 = 0.1;
+
+/// The system of units used to display measurements.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, uniffi::Enum)]
+#[cfg_attr(feature = "wasm-js", derive(Serialize, Deserialize))]
+pub enum UnitSystem {
+    /// Meters, kilometers, and kilometers per hour.
+    Metric,
+    /// Feet, miles, and miles per hour.
+    Imperial,
+}
+
+/// A distance, in meters.
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd, uniffi::Record)]
+#[cfg_attr(feature = "wasm-js", derive(Serialize, Deserialize))]
+pub struct DistanceValue {
+    pub meters: f64,
+}
+
+impl DistanceValue {
+    pub fn from_kilometers(kilometers: f64) -> Self {
+        Self {
+            meters: kilometers * METERS_PER_KILOMETER,
+        }
+    }
+
+    pub fn from_miles(miles: f64) -> Self {
+        Self {
+            meters: miles * METERS_PER_MILE,
+        }
+    }
+
+    pub fn from_feet(feet: f64) -> Self {
+        Self {
+            meters: feet * METERS_PER_FOOT,
+        }
+    }
+
+    pub fn kilometers(self) -> f64 {
+        self.meters / METERS_PER_KILOMETER
+    }
+
+    pub fn miles(self) -> f64 {
+        self.meters / METERS_PER_MILE
+    }
+
+    pub fn feet(self) -> f64 {
+        self.meters / METERS_PER_FOOT
+    }
+}
+
+/// A speed, in meters per second.
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd, uniffi::Record)]
+#[cfg_attr(feature = "wasm-js", derive(Serialize, Deserialize))]
+pub struct SpeedValue {
+    pub meters_per_second: f64,
+}
+
+impl SpeedValue {
+    /// Converts a speed in the given unit (ex: a posted speed limit).
+    pub fn from_unit(value: f64, unit: SpeedUnit) -> Self {
+        Self {
+            meters_per_second: value / Self::meters_per_second_in(unit),
+        }
+    }
+
+    /// The speed in the given unit (ex: to compare with a posted speed limit).
+    pub fn in_unit(self, unit: SpeedUnit) -> f64 {
+        self.meters_per_second * Self::meters_per_second_in(unit)
+    }
+
+    /// The number of `unit`s in one meter per second.
+    fn meters_per_second_in(unit: SpeedUnit) -> f64 {
+        match unit {
+            SpeedUnit::KilometersPerHour => SECONDS_PER_HOUR / METERS_PER_KILOMETER,
+            SpeedUnit::MilesPerHour => SECONDS_PER_HOUR / METERS_PER_MILE,
+        }
+    }
+}
+
+/// A duration, in seconds.
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd, uniffi::Record)]
+#[cfg_attr(feature = "wasm-js", derive(Serialize, Deserialize))]
+pub struct DurationValue {
+    pub seconds: f64,
+}
+
+impl DurationValue {
+    pub fn minutes(self) -> f64 {
+        self.seconds / 60.0
+    }
+
+    pub fn hours(self) -> f64 {
+        self.seconds / SECONDS_PER_HOUR
+    }
+}
+
+/// A measurement rounded for display, split into the number and the unit
+/// so that platforms can style (and localize) them separately.
+#[derive(Debug, Clone, PartialEq, Eq, uniffi::Record)]
+#[cfg_attr(feature = "wasm-js", derive(Serialize, Deserialize))]
+pub struct FormattedMeasurement {
+    /// The rounded number (ex: `1.2`).
+    pub value: String,
+    /// The abbreviated unit (ex: `km`).
+    pub unit: String,
+}
+
+impl FormattedMeasurement {
+    fn new(value: String, unit: &str) -> Self {
+        Self {
+            value,
+            unit: unit.to_string(),
+        }
+    }
+}
+
+/// The trip progress, formatted for display.
+#[derive(Debug, Clone, PartialEq, Eq, uniffi::Record)]
+#[cfg_attr(feature = "wasm-js", derive(Serialize, Deserialize))]
+pub struct FormattedTripProgress {
+    pub distance_to_next_maneuver: FormattedMeasurement,
+    pub distance_remaining: FormattedMeasurement,
+    pub duration_remaining: FormattedMeasurement,
+}
+
+/// The trip progress, as typed values.
+///
+/// This is the platform equivalent of the `TripProgress::*_value` accessors,
+/// as methods cannot be exported on records.
+#[derive(Debug, Copy, Clone, PartialEq, uniffi::Record)]
+#[cfg_attr(feature = "wasm-js", derive(Serialize, Deserialize))]
+pub struct TripProgressValues {
+    pub distance_to_next_maneuver: DistanceValue,
+    pub distance_remaining: DistanceValue,
+    pub duration_remaining: DurationValue,
+}
+
+impl TripProgress {
+    pub fn distance_to_next_maneuver_value(&self) -> DistanceValue {
+        DistanceValue {
+            meters: self.distance_to_next_maneuver,
+        }
+    }
+
+    pub fn distance_remaining_value(&self) -> DistanceValue {
+        DistanceValue {
+            meters: self.distance_remaining,
+        }
+    }
+
+    pub fn duration_remaining_value(&self) -> DurationValue {
+        DurationValue {
+            seconds: self.duration_remaining,
+        }
+    }
+}
+
+/// The trip progress as typed values, so that platforms can convert units (ex: for custom formatting).
+#[uniffi::export]
+pub fn trip_progress_values(progress: &TripProgress) -> TripProgressValues {
+    TripProgressValues {
+        distance_to_next_maneuver: progress.distance_to_next_maneuver_value(),
+        distance_remaining: progress.distance_remaining_value(),
+        duration_remaining: progress.duration_remaining_value(),
+    }
+}
+
+/// Formats a distance for display.
+///
+/// Short distances are rounded to the nearest 10 (or 50 from 100 up) meters or feet,
+/// and longer ones are shown in kilometers or miles,
+/// with one decimal place below 10.
+#[uniffi::export]
+pub fn format_distance(distance: DistanceValue, unit_system: UnitSystem) -> FormattedMeasurement {
+    let (short, long, short_unit, long_unit) = match unit_system {
+        UnitSystem::Metric => (distance.meters, distance.kilometers(), "m", "km"),
+        UnitSystem::Imperial => (distance.feet(), distance.miles(), "ft", "mi"),
+    };
+    let short = short.max(0.0);
+    let increment = if short < 100.0 { 10.0 } else { 50.0 };
+    let rounded_short = (short / increment).round() * increment;
+    let is_short = match unit_system {
+        // Avoid showing 1000 m
+        UnitSystem::Metric => rounded_short < METERS_PER_KILOMETER,
+        UnitSystem::Imperial => long < MIN_DISPLAYED_MILES,
+    };
+    if is_short {
+        return FormattedMeasurement::new(format!("{rounded_short:.0}"), short_unit);
+    }
+
+    let rounded_long = (long * 10.0).round() / 10.0;
+    if rounded_long < 10.0 {
+        FormattedMeasurement::new(format!("{rounded_long:.1}"), long_unit)
+    } else {
+        FormattedMeasurement::new(format!("{long:.0}"), long_unit)
+    }
+}
+
+/// Formats a speed for display, rounded to a whole number of kilometers or miles per hour.
+#[uniffi::export]
+pub fn format_speed(speed: SpeedValue, unit_system: UnitSystem) -> FormattedMeasurement {
+    let (unit, label) = match unit_system {
+        UnitSystem::Metric => (SpeedUnit::KilometersPerHour, "km/h"),
+        UnitSystem::Imperial => (SpeedUnit::MilesPerHour, "mph"),
+    };
+    FormattedMeasurement::new(format!("{:.0}", speed.in_unit(unit).max(0.0)), label)
+}
+
+/// Formats a duration for display, rounded to the nearest minute.
+///
+/// Durations under an hour are shown in minutes (ex: `12 min`),
+/// and longer ones in hours and minutes (ex: `1:05 h`).
+#[uniffi::export]
+pub fn format_duration(duration: DurationValue) -> FormattedMeasurement {
+    let minutes = duration.minutes().max(0.0).round() as u64;
+    if minutes < 60 {
+        FormattedMeasurement::new(minutes.to_string(), "min")
+    } else {
+        FormattedMeasurement::new(format!("{}:{:02}", minutes / 60, minutes % 60), "h")
+    }
+}
+
+/// Formats the trip progress for display. See [`format_distance`] and [`format_duration`].
+#[uniffi::export]
+pub fn format_trip_progress(
+    progress: &TripProgress,
+    unit_system: UnitSystem,
+) -> FormattedTripProgress {
+    FormattedTripProgress {
+        distance_to_next_maneuver: format_distance(
+            progress.distance_to_next_maneuver_value(),
+            unit_system,
+        ),
+        distance_remaining: format_distance(progress.distance_remaining_value(), unit_system),
+        duration_remaining: format_duration(progress.duration_remaining_value()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn formatted(value: &str, unit: &str) -> FormattedMeasurement {
+        FormattedMeasurement::new(value.to_string(), unit)
+    }
+
+    #[test]
+    fn converts_units() {
+        let mile = DistanceValue::from_miles(1.0);
+        assert!((mile.meters - 1_609.344).abs() < 1e-9);
+        assert!((mile.feet() - 5_280.0).abs() < 1e-9);
+        assert!((DistanceValue::from_kilometers(2.5).meters - 2_500.0).abs() < 1e-9);
+
+        let speed = SpeedValue::from_unit(100.0, SpeedUnit::KilometersPerHour);
+        assert!((speed.meters_per_second - 27.777_777).abs() < 1e-6);
+        assert!((speed.in_unit(SpeedUnit::MilesPerHour) - 62.137_119).abs() < 1e-6);
+    }
+
+    #[test]
+    fn formats_distances() {
+        let metric = |meters| format_distance(DistanceValue { meters }, UnitSystem::Metric);
+        assert_eq!(metric(44.0), formatted("40", "m"));
+        assert_eq!(metric(180.0), formatted("200", "m"));
+        assert_eq!(metric(990.0), formatted("1.0", "km"));
+        assert_eq!(metric(1_234.0), formatted("1.2", "km"));
+        assert_eq!(metric(9_980.0), formatted("10", "km"));
+        assert_eq!(metric(123_456.0), formatted("123", "km"));
+
+        let imperial = |feet| format_distance(DistanceValue::from_feet(feet), UnitSystem::Imperial);
+        assert_eq!(imperial(84.0), formatted("80", "ft"));
+        assert_eq!(imperial(510.0), formatted("500", "ft"));
+        assert_eq!(imperial(7_920.0), formatted("1.5", "mi"));
+    }
+
+    #[test]
+    fn formats_speeds_and_durations() {
+        let speed = SpeedValue {
+            meters_per_second: 13.9,
+        };
+        assert_eq!(
+            format_speed(speed, UnitSystem::Metric),
+            formatted("50", "km/h")
+        );
+        assert_eq!(
+            format_speed(speed, UnitSystem::Imperial),
+            formatted("31", "mph")
+        );
+
+        assert_eq!(
+            format_duration(DurationValue { seconds: 29.0 }),
+            formatted("0", "min")
+        );
+        assert_eq!(
+            format_duration(DurationValue { seconds: 750.0 }),
+            formatted("13", "min")
+        );
+        assert_eq!(
+            format_duration(DurationValue { seconds: 3_900.0 }),
+            formatted("1:05", "h")
+        );
+    }
+
+    #[test]
+    fn formats_trip_progress() {
+        let progress = TripProgress {
+            distance_to_next_maneuver: 420.0,
+            distance_remaining: 15_300.0,
+            duration_remaining: 1_140.0,
+            consumption: None,
+        };
+        assert_eq!(
+            format_trip_progress(&progress, UnitSystem::Metric),
+            FormattedTripProgress {
+                distance_to_next_maneuver: formatted("400", "m"),
+                distance_remaining: formatted("15", "km"),
+                duration_remaining: formatted("19", "min"),
+            }
+        );
+        assert_eq!(
+            trip_progress_values(&progress),
+            TripProgressValues {
+                distance_to_next_maneuver: DistanceValue { meters: 420.0 },
+                distance_remaining: DistanceValue { meters: 15_300.0 },
+                duration_remaining: DurationValue { seconds: 1_140.0 },
+            }
+        );
+    }
+}
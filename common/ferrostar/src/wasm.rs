@@ -0,0 +1,133 @@
+//! JavaScript bindings for web apps (and anything else which can load WebAssembly).
+//!
+//! These are enabled with the `wasm-js` feature and are intended for the `wasm32-unknown-unknown` target
+//! (ex: via `wasm-pack build --features wasm-js`).
+//! Values are exchanged with JavaScript as plain objects (using `serde-wasm-bindgen`),
+//! which mirror the Rust models.
+//!
+//! NOTE: The HTTP pipeline in [`crate::routing_adapters::http`] requires threads,
+//! so it is not available here; fetch routes with the Fetch API and parse the response instead.
+
+use crate::deviation_detection::RouteDeviationTracking;
+use crate::models::{Route, UserLocation};
+use crate::navigation_controller::models::{
+    NavigationControllerConfig, SnappingMode, StepAdvanceMode, TripState,
+};
+use crate::navigation_controller::NavigationController;
+use crate::road_name::RoadNameLookup;
+use crate::routing_adapters::osrm::OsrmResponseParser;
+use crate::routing_adapters::RouteResponseParser;
+use crate::simulation::{
+    advance_location_simulation, location_simulation_from_route, LocationSimulationState,
+};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+fn to_js<T: Serialize>(value: &T) -> Result<JsValue, JsError> {
+    Ok(serde_wasm_bindgen::to_value(value)?)
+}
+
+fn from_js<T: for<'de> Deserialize<'de>>(value: JsValue) -> Result<T, JsError> {
+    Ok(serde_wasm_bindgen::from_value(value)?)
+}
+
+/// Parses an OSRM (or Valhalla OSRM-compatible) response into an array of routes.
+#[wasm_bindgen(js_name = parseOsrmResponse)]
+pub fn parse_osrm_response(response: &[u8], polyline_precision: u32) -> Result<JsValue, JsError> {
+    let routes = OsrmResponseParser::new(polyline_precision).parse_response(response.to_vec())?;
+    to_js(&routes)
+}
+
+/// The subset of [`NavigationControllerConfig`] which is configurable from JavaScript.
+///
+/// Features which need callbacks (ex: custom deviation detection) are not available.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JsNavigationControllerConfig {
+    /// See [`StepAdvanceMode::DistanceToEndOfStep`].
+    step_advance_distance: u16,
+    /// The minimum required horizontal accuracy for step advance and deviation detection, in meters.
+    minimum_horizontal_accuracy: u16,
+    /// See [`RouteDeviationTracking::StaticThreshold`].
+    max_acceptable_deviation: f64,
+}
+
+impl From<JsNavigationControllerConfig> for NavigationControllerConfig {
+    fn from(config: JsNavigationControllerConfig) -> Self {
+        Self {
+            step_advance: StepAdvanceMode::DistanceToEndOfStep {
+                distance: config.step_advance_distance,
+                minimum_horizontal_accuracy: config.minimum_horizontal_accuracy,
+            },
+            route_deviation_tracking: RouteDeviationTracking::StaticThreshold {
+                minimum_horizontal_accuracy: config.minimum_horizontal_accuracy,
+                max_acceptable_deviation: config.max_acceptable_deviation,
+            },
+            snapping: SnappingMode::NearestPointWithHeading,
+            road_name_lookup: RoadNameLookup::StepName,
+            ..Default::default()
+        }
+    }
+}
+
+/// A JavaScript wrapper around the [`NavigationController`].
+///
+/// Trip states are plain objects, which should be passed back in unmodified.
+#[wasm_bindgen(js_name = NavigationController)]
+pub struct JsNavigationController {
+    controller: NavigationController,
+}
+
+#[wasm_bindgen(js_class = NavigationController)]
+impl JsNavigationController {
+    /// Creates a controller for a route (as returned by [`parse_osrm_response`])
+    /// with a config object (ex: `{stepAdvanceDistance: 10, minimumHorizontalAccuracy: 25, maxAcceptableDeviation: 50}`).
+    #[wasm_bindgen(constructor)]
+    pub fn new(route: JsValue, config: JsValue) -> Result<JsNavigationController, JsError> {
+        let route: Route = from_js(route)?;
+        let config: JsNavigationControllerConfig = from_js(config)?;
+        Ok(Self {
+            controller: NavigationController::new(route, config.into()),
+        })
+    }
+
+    #[wasm_bindgen(js_name = getInitialState)]
+    pub fn get_initial_state(&self, location: JsValue) -> Result<JsValue, JsError> {
+        let location: UserLocation = from_js(location)?;
+        to_js(&self.controller.get_initial_state(location))
+    }
+
+    #[wasm_bindgen(js_name = advanceToNextStep)]
+    pub fn advance_to_next_step(&self, state: JsValue) -> Result<JsValue, JsError> {
+        let state: TripState = from_js(state)?;
+        to_js(&self.controller.advance_to_next_step(&state))
+    }
+
+    #[wasm_bindgen(js_name = updateUserLocation)]
+    pub fn update_user_location(
+        &self,
+        location: JsValue,
+        state: JsValue,
+    ) -> Result<JsValue, JsError> {
+        let location: UserLocation = from_js(location)?;
+        let state: TripState = from_js(state)?;
+        to_js(&self.controller.update_user_location(location, &state))
+    }
+}
+
+/// Creates a location simulation along a route. See [`location_simulation_from_route`].
+#[wasm_bindgen(js_name = locationSimulationFromRoute)]
+pub fn js_location_simulation_from_route(
+    route: JsValue,
+    resample_distance: Option<f64>,
+) -> Result<JsValue, JsError> {
+    let route: Route = from_js(route)?;
+    to_js(&location_simulation_from_route(&route, resample_distance)?)
+}
+
+/// Advances a location simulation. See [`advance_location_simulation`].
+#[wasm_bindgen(js_name = advanceLocationSimulation)]
+pub fn js_advance_location_simulation(state: JsValue) -> Result<JsValue, JsError> {
+    let state: LocationSimulationState = from_js(state)?;
+    to_js(&advance_location_simulation(&state))
+}
@@ -1,14 +1,40 @@
 extern crate ferrostar;
 
-use ferrostar::deviation_detection::RouteDeviationTracking;
-use ferrostar::models::{Route, UserLocation};
+use ferrostar::algorithms::DistanceMeasurement;
+use ferrostar::arrival_detection::{ArrivalDetection, NearbyArrivalDetection};
+use ferrostar::breadcrumbs::BreadcrumbRecording;
+use ferrostar::camera::create_default_camera_lookahead_policy;
+use ferrostar::clock::MockClock;
+use ferrostar::consumption::{estimate_route_consumption, Powertrain, VehicleEnergyProfile};
+use ferrostar::deviation_detection::{
+    RouteDeviation, RouteDeviationTracking, WrongDirectionDetection,
+};
+use ferrostar::free_drive::FreeDriveController;
+use ferrostar::junction_view::JunctionViewResolver;
+use ferrostar::location_filtering::{AccuracyGating, PositioningStatus, StaleLocationRejection};
+use ferrostar::models::{
+    CountryChange, CourseOverGround, DestinationDetails, DrivingSide, GeographicCoordinate,
+    Incident, IncidentKind, JunctionView, ParkingSuggestion, Route, RouteStep, Speed,
+    SpokenInstruction, UserLocation,
+};
+use ferrostar::navigation_controller::delta::TripStateDelta;
 use ferrostar::navigation_controller::models::{
-    NavigationControllerConfig, StepAdvanceMode, TripState,
+    GeometryRetention, GuidanceVerbosity, NavigationControllerConfig, NavigationEvent, PowerMode,
+    SnappingMode, StepAdvanceCooldown, StepAdvanceMode, TripState,
 };
-use ferrostar::navigation_controller::NavigationController;
+use ferrostar::navigation_controller::presets::NavigationPreset;
+use ferrostar::navigation_controller::shared_route::TripStateUpdate;
+use ferrostar::navigation_controller::trip_queue::TripQueue;
+use ferrostar::navigation_controller::{
+    remaining_maneuvers, NavigationController, SynchronizedNavigationController,
+};
+use ferrostar::road_name::{RoadNameGeocoder, RoadNameLookup};
 use ferrostar::routing_adapters::osrm::OsrmResponseParser;
 use ferrostar::routing_adapters::RouteResponseParser;
-use std::time::SystemTime;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, SystemTime};
+use uuid::Uuid;
 
 // A route with two steps
 const TWO_STEP_RESPONSE: &str = r#"{"routes":[{"weight_name":"auto","weight":56.002,"duration":11.488,"distance":284,"legs":[{"via_waypoints":[],"annotation":{"maxspeed":[{"speed":89,"unit":"km/h"},{"speed":89,"unit":"km/h"},{"speed":89,"unit":"km/h"},{"speed":89,"unit":"km/h"},{"speed":89,"unit":"km/h"},{"speed":89,"unit":"km/h"},{"speed":89,"unit":"km/h"},{"speed":89,"unit":"km/h"},{"speed":89,"unit":"km/h"}],"speed":[24.7,24.7,24.7,24.7,24.7,24.7,24.7,24.7,24.7],"distance":[23.6,14.9,9.6,13.2,25,28.1,38.1,41.6,90],"duration":[0.956,0.603,0.387,0.535,1.011,1.135,1.539,1.683,3.641]},"admins":[{"iso_3166_1_alpha3":"USA","iso_3166_1":"US"}],"weight":56.002,"duration":11.488,"steps":[{"intersections":[{"bearings":[288],"entry":[true],"admin_index":0,"out":0,"geometry_index":0,"location":[-149.543469,60.534716]}],"speedLimitUnit":"mph","maneuver":{"type":"depart","instruction":"Drive west on AK 1/Seward Highway.","bearing_after":288,"bearing_before":0,"location":[-149.543469,60.534716]},"speedLimitSign":"mutcd","name":"Seward Highway","duration":11.488,"distance":284,"driving_side":"right","weight":56.002,"mode":"driving","ref":"AK 1","geometry":"wzvmrBxalf|GcCrX}A|Nu@jI}@pMkBtZ{@x^_Afj@Inn@`@veB"},{"intersections":[{"bearings":[89],"entry":[true],"in":0,"admin_index":0,"geometry_index":9,"location":[-149.548581,60.534991]}],"speedLimitUnit":"mph","maneuver":{"type":"arrive","instruction":"You have arrived at your destination.","bearing_after":0,"bearing_before":269,"location":[-149.548581,60.534991]},"speedLimitSign":"mutcd","name":"Seward Highway","duration":0,"distance":0,"driving_side":"right","weight":0,"mode":"driving","ref":"AK 1","geometry":"}kwmrBhavf|G??"}],"distance":284,"summary":"AK 1"}],"geometry":"wzvmrBxalf|GcCrX}A|Nu@jI}@pMkBtZ{@x^_Afj@Inn@`@veB"}],"waypoints":[{"distance":0,"name":"AK 1","location":[-149.543469,60.534715]},{"distance":0,"name":"AK 1","location":[-149.548581,60.534991]}],"code":"Ok"}"#;
@@ -35,6 +61,8 @@ fn same_location_results_in_identical_state() {
         course_over_ground: None,
         timestamp: SystemTime::now(),
         speed: None,
+        altitude: None,
+        vertical_accuracy: None,
     };
 
     let controller = NavigationController::new(
@@ -42,6 +70,7 @@ fn same_location_results_in_identical_state() {
         NavigationControllerConfig {
             step_advance: StepAdvanceMode::Manual,
             route_deviation_tracking: RouteDeviationTracking::None,
+            ..Default::default()
         },
     );
 
@@ -64,6 +93,8 @@ fn simple_route_state_machine_manual_advance() {
         course_over_ground: None,
         timestamp: SystemTime::now(),
         speed: None,
+        altitude: None,
+        vertical_accuracy: None,
     };
     let user_location_end_of_first_step = UserLocation {
         coordinates: *route.steps[0].geometry.last().unwrap(),
@@ -71,6 +102,8 @@ fn simple_route_state_machine_manual_advance() {
         course_over_ground: None,
         timestamp: SystemTime::now(),
         speed: None,
+        altitude: None,
+        vertical_accuracy: None,
     };
 
     let controller = NavigationController::new(
@@ -78,6 +111,7 @@ fn simple_route_state_machine_manual_advance() {
         NavigationControllerConfig {
             step_advance: StepAdvanceMode::Manual,
             route_deviation_tracking: RouteDeviationTracking::None,
+            ..Default::default()
         },
     );
 
@@ -134,6 +168,8 @@ fn simple_route_state_machine_advances_with_location_change() {
         course_over_ground: None,
         timestamp: SystemTime::now(),
         speed: None,
+        altitude: None,
+        vertical_accuracy: None,
     };
     let user_location_end_of_first_step = UserLocation {
         coordinates: *route.steps[0].geometry.last().unwrap(),
@@ -141,6 +177,8 @@ fn simple_route_state_machine_advances_with_location_change() {
         course_over_ground: None,
         timestamp: SystemTime::now(),
         speed: None,
+        altitude: None,
+        vertical_accuracy: None,
     };
 
     let controller = NavigationController::new(
@@ -153,6 +191,7 @@ fn simple_route_state_machine_advances_with_location_change() {
                 minimum_horizontal_accuracy: 0,
             },
             route_deviation_tracking: RouteDeviationTracking::None,
+            ..Default::default()
         },
     );
 
@@ -182,3 +221,1801 @@ fn simple_route_state_machine_advances_with_location_change() {
     // In this case, the final step is the arrival point
     assert_eq!(remaining_waypoints.len(), 0);
 }
+
+#[test]
+fn arrival_requires_stopping_near_destination() {
+    let route = get_route_with_two_steps();
+    let destination = *route.steps[1].geometry.last().unwrap();
+    let initial_user_location = UserLocation {
+        coordinates: route.steps[0].geometry[0],
+        horizontal_accuracy: 0.0,
+        course_over_ground: None,
+        timestamp: SystemTime::now(),
+        speed: None,
+        altitude: None,
+        vertical_accuracy: None,
+    };
+    let driving_past_destination = UserLocation {
+        coordinates: destination,
+        horizontal_accuracy: 0.0,
+        course_over_ground: None,
+        timestamp: SystemTime::now(),
+        speed: Some(Speed {
+            value: 20.0,
+            accuracy: None,
+        }),
+        altitude: None,
+        vertical_accuracy: None,
+    };
+    let stopped_at_destination = UserLocation {
+        speed: Some(Speed {
+            value: 0.5,
+            accuracy: None,
+        }),
+        ..driving_past_destination
+    };
+
+    let controller = NavigationController::new(
+        route,
+        NavigationControllerConfig {
+            step_advance: StepAdvanceMode::DistanceToEndOfStep {
+                distance: 0,
+                minimum_horizontal_accuracy: 0,
+            },
+            route_deviation_tracking: RouteDeviationTracking::None,
+            arrival_detection: ArrivalDetection::Criteria {
+                max_distance_to_destination: 10.0,
+                max_speed: Some(1.0),
+                min_dwell_time: None,
+            },
+            ..Default::default()
+        },
+    );
+
+    let initial_state = controller.get_initial_state(initial_user_location);
+
+    // Reaching the end of the first step advances to the final (arrival) step
+    let state = controller.update_user_location(driving_past_destination, &initial_state);
+    let TripState::Navigating {
+        ref remaining_steps,
+        ..
+    } = state
+    else {
+        panic!("Expected state to be navigating");
+    };
+    assert_eq!(remaining_steps.len(), 1);
+
+    // Passing by the destination at speed does not complete the trip
+    let state = controller.update_user_location(driving_past_destination, &state);
+    assert!(matches!(state, TripState::Navigating { .. }));
+
+    // Stopping does
+    assert!(matches!(
+        controller.update_user_location(stopped_at_destination, &state),
+        TripState::Complete
+    ));
+}
+
+#[test]
+fn traveling_backward_along_route_reports_wrong_direction() {
+    let route = get_route_with_two_steps();
+    // The first step heads west-northwest
+    let reversing = UserLocation {
+        coordinates: route.steps[0].geometry[5],
+        horizontal_accuracy: 0.0,
+        course_over_ground: Some(CourseOverGround::new(108, Some(10))),
+        timestamp: SystemTime::now(),
+        speed: None,
+        altitude: None,
+        vertical_accuracy: None,
+    };
+    let reversed = UserLocation {
+        coordinates: route.steps[0].geometry[1],
+        ..reversing
+    };
+    let turned_around = UserLocation {
+        course_over_ground: Some(CourseOverGround::new(288, Some(10))),
+        ..reversed
+    };
+
+    let controller = NavigationController::new(
+        route,
+        NavigationControllerConfig {
+            step_advance: StepAdvanceMode::Manual,
+            route_deviation_tracking: RouteDeviationTracking::None,
+            wrong_direction_detection: WrongDirectionDetection::CourseOverGround {
+                min_bearing_difference: 135,
+                max_course_accuracy: 30,
+                min_distance: 30.0,
+            },
+            ..Default::default()
+        },
+    );
+
+    // Heading the wrong way, but not far enough to report it yet
+    let state = controller.get_initial_state(reversing);
+    let TripState::Navigating { deviation, .. } = state else {
+        panic!("Expected state to be navigating");
+    };
+    assert_eq!(deviation, RouteDeviation::NoDeviation);
+
+    let state = controller.update_user_location(reversed, &state);
+    let TripState::Navigating { deviation, .. } = state else {
+        panic!("Expected state to be navigating");
+    };
+    assert!(matches!(
+        deviation,
+        RouteDeviation::WrongDirection {
+            distance_in_wrong_direction
+        } if distance_in_wrong_direction > 30.0
+    ));
+
+    // Making a U-turn clears the deviation
+    let state = controller.update_user_location(turned_around, &state);
+    let TripState::Navigating { deviation, .. } = state else {
+        panic!("Expected state to be navigating");
+    };
+    assert_eq!(deviation, RouteDeviation::NoDeviation);
+}
+
+#[test]
+fn approaching_incident_is_surfaced() {
+    let mut route = get_route_with_two_steps();
+    route.incidents.push(Incident {
+        id: "1".to_string(),
+        kind: IncidentKind::Accident,
+        description: Some("Multi-vehicle collision".to_string()),
+        is_closed: false,
+        start_distance_along_route: 50.0,
+        end_distance_along_route: 100.0,
+    });
+    let location_at = |index: usize| UserLocation {
+        coordinates: route.steps[0].geometry[index],
+        horizontal_accuracy: 0.0,
+        course_over_ground: None,
+        timestamp: SystemTime::now(),
+        speed: None,
+        altitude: None,
+        vertical_accuracy: None,
+    };
+    let (start, in_incident, past_incident) = (location_at(0), location_at(5), location_at(6));
+
+    let controller = NavigationController::new(
+        route,
+        NavigationControllerConfig {
+            step_advance: StepAdvanceMode::Manual,
+            route_deviation_tracking: RouteDeviationTracking::None,
+            incident_alert_distance: Some(100.0),
+            ..Default::default()
+        },
+    );
+
+    let upcoming_incident = |state: &TripState| match state {
+        TripState::Navigating {
+            upcoming_incident, ..
+        } => upcoming_incident.clone(),
+        _ => panic!("Expected state to be navigating"),
+    };
+
+    let state = controller.get_initial_state(start);
+    let incident = upcoming_incident(&state).expect("Expected an upcoming incident");
+    assert_eq!(incident.incident.kind, IncidentKind::Accident);
+    assert!((incident.distance_to_incident - 50.0).abs() < 5.0);
+
+    let state = controller.update_user_location(in_incident, &state);
+    let incident = upcoming_incident(&state).expect("Expected an upcoming incident");
+    assert!(incident.distance_to_incident.abs() < f64::EPSILON);
+
+    let state = controller.update_user_location(past_incident, &state);
+    assert_eq!(upcoming_incident(&state), None);
+}
+
+#[test]
+fn synchronized_controller_is_shareable_across_threads() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<SynchronizedNavigationController>();
+
+    let route = get_route_with_two_steps();
+    let initial_user_location = UserLocation {
+        coordinates: route.steps[0].geometry[0],
+        horizontal_accuracy: 0.0,
+        course_over_ground: None,
+        timestamp: SystemTime::now(),
+        speed: None,
+        altitude: None,
+        vertical_accuracy: None,
+    };
+
+    let controller = Arc::new(SynchronizedNavigationController::new(
+        route,
+        NavigationControllerConfig {
+            step_advance: StepAdvanceMode::Manual,
+            route_deviation_tracking: RouteDeviationTracking::None,
+            ..Default::default()
+        },
+        initial_user_location,
+    ));
+    assert!(matches!(
+        controller.get_state(),
+        TripState::Navigating { .. }
+    ));
+
+    // Location updates and step advances arrive from different threads;
+    // neither of the advances may be lost.
+    let handles: Vec<_> = (0..2)
+        .map(|_| {
+            let controller = Arc::clone(&controller);
+            thread::spawn(move || {
+                controller.update_user_location(initial_user_location);
+                controller.advance_to_next_step();
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().expect("Thread panicked");
+    }
+
+    // There are only two steps, so advancing twice completes the trip
+    assert_eq!(controller.get_state(), TripState::Complete);
+    assert_eq!(
+        controller.update_user_location(initial_user_location),
+        TripState::Complete
+    );
+}
+
+#[test]
+fn batch_location_updates_report_events_in_order() {
+    let route = get_route_with_two_steps();
+    let now = SystemTime::now();
+    let initial_user_location = UserLocation {
+        coordinates: route.steps[0].geometry[0],
+        horizontal_accuracy: 0.0,
+        course_over_ground: None,
+        timestamp: now,
+        speed: None,
+        altitude: None,
+        vertical_accuracy: None,
+    };
+    let user_location_end_of_first_step = UserLocation {
+        coordinates: *route.steps[0].geometry.last().unwrap(),
+        timestamp: now + Duration::from_secs(2),
+        ..initial_user_location
+    };
+    let second_step = route.steps[1].clone();
+    let destination = route.waypoints[1];
+
+    let controller = NavigationController::new(
+        route,
+        NavigationControllerConfig {
+            step_advance: StepAdvanceMode::DistanceToEndOfStep {
+                distance: 0,
+                minimum_horizontal_accuracy: 0,
+            },
+            route_deviation_tracking: RouteDeviationTracking::None,
+            ..Default::default()
+        },
+    );
+    let initial_state = controller.get_initial_state(initial_user_location);
+
+    // Batched fixes may arrive out of order, and may overlap with locations already processed
+    let update = controller.update_user_locations(
+        vec![
+            user_location_end_of_first_step,
+            UserLocation {
+                timestamp: now + Duration::from_secs(1),
+                ..initial_user_location
+            },
+            UserLocation {
+                coordinates: destination.coordinate,
+                timestamp: now - Duration::from_secs(1),
+                ..initial_user_location
+            },
+        ],
+        &initial_state,
+    );
+
+    assert_eq!(
+        update.events,
+        vec![
+            NavigationEvent::StepAdvanced { step: second_step },
+            NavigationEvent::WaypointReached {
+                waypoint: destination
+            },
+        ]
+    );
+    assert_eq!(
+        update.state,
+        controller.update_user_location(user_location_end_of_first_step, &initial_state)
+    );
+
+    // Nothing happens for an empty batch
+    let empty_update = controller.update_user_locations(vec![], &update.state);
+    assert_eq!(empty_update.state, update.state);
+    assert!(empty_update.events.is_empty());
+}
+
+#[test]
+fn out_of_order_locations_are_discarded() {
+    let route = get_route_with_two_steps();
+    let now = SystemTime::now();
+    let initial_user_location = UserLocation {
+        coordinates: route.steps[0].geometry[0],
+        horizontal_accuracy: 0.0,
+        course_over_ground: None,
+        timestamp: now,
+        speed: None,
+        altitude: None,
+        vertical_accuracy: None,
+    };
+    let user_location_end_of_first_step = UserLocation {
+        coordinates: *route.steps[0].geometry.last().unwrap(),
+        timestamp: now + Duration::from_secs(2),
+        ..initial_user_location
+    };
+    let delayed_user_location = UserLocation {
+        timestamp: now + Duration::from_secs(1),
+        ..initial_user_location
+    };
+
+    let controller = NavigationController::new(
+        route,
+        NavigationControllerConfig {
+            step_advance: StepAdvanceMode::Manual,
+            route_deviation_tracking: RouteDeviationTracking::None,
+            stale_location_rejection: StaleLocationRejection::Monotonic { max_age: None },
+            ..Default::default()
+        },
+    );
+
+    let initial_state = controller.get_initial_state(initial_user_location);
+    let state = controller.update_user_location(user_location_end_of_first_step, &initial_state);
+    let TripState::Navigating {
+        snapped_user_location,
+        ..
+    } = state.clone()
+    else {
+        panic!("Expected state to be navigating");
+    };
+    assert_eq!(snapped_user_location, user_location_end_of_first_step);
+
+    // The delayed fix would move the user backward along the route
+    assert_eq!(
+        controller.update_user_location(delayed_user_location, &state),
+        state
+    );
+}
+
+#[test]
+fn old_locations_are_measured_against_the_configured_clock() {
+    let route = get_route_with_two_steps();
+    let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+    let location_at = |index: usize, seconds: u64| UserLocation {
+        coordinates: route.steps[0].geometry[index],
+        horizontal_accuracy: 0.0,
+        course_over_ground: None,
+        timestamp: start + Duration::from_secs(seconds),
+        speed: None,
+        altitude: None,
+        vertical_accuracy: None,
+    };
+    let clock = Arc::new(MockClock::new(start + Duration::from_secs(60)));
+    let controller = NavigationController::new(
+        route.clone(),
+        NavigationControllerConfig {
+            step_advance: StepAdvanceMode::Manual,
+            stale_location_rejection: StaleLocationRejection::Monotonic { max_age: Some(5.0) },
+            clock: Some(clock.clone()),
+            ..NavigationControllerConfig::from_preset(NavigationPreset::Driving)
+        },
+    );
+    let initial_state = controller.get_initial_state(location_at(0, 0));
+
+    // A minute old by the clock
+    let location = location_at(3, 10);
+    assert_eq!(
+        controller.update_user_location(location, &initial_state),
+        initial_state
+    );
+
+    // Fresh once the clock is back at the time of the fix
+    clock.set(location.timestamp);
+    let TripState::Navigating {
+        snapped_user_location,
+        ..
+    } = controller.update_user_location(location, &initial_state)
+    else {
+        panic!("Expected state to be navigating");
+    };
+    assert_eq!(snapped_user_location.timestamp, location.timestamp);
+}
+
+#[test]
+fn inaccurate_locations_are_gated() {
+    let route = get_route_with_two_steps();
+    let initial_user_location = UserLocation {
+        coordinates: route.steps[0].geometry[0],
+        horizontal_accuracy: 5.0,
+        course_over_ground: None,
+        timestamp: SystemTime::now(),
+        speed: None,
+        altitude: None,
+        vertical_accuracy: None,
+    };
+    let inaccurate_location = UserLocation {
+        coordinates: *route.steps[0].geometry.last().unwrap(),
+        horizontal_accuracy: 100.0,
+        ..initial_user_location
+    };
+
+    let controller = NavigationController::new(
+        route,
+        NavigationControllerConfig {
+            step_advance: StepAdvanceMode::Manual,
+            route_deviation_tracking: RouteDeviationTracking::None,
+            accuracy_gating: AccuracyGating::MaxHorizontalAccuracy {
+                max_horizontal_accuracy: 20.0,
+                degraded_after: 2,
+            },
+            ..Default::default()
+        },
+    );
+
+    let initial_state = controller.get_initial_state(initial_user_location);
+    let first_rejection = controller.update_user_location(inaccurate_location, &initial_state);
+    let second_rejection = controller.update_user_location(inaccurate_location, &first_rejection);
+    let recovered = controller.update_user_location(initial_user_location, &second_rejection);
+
+    for (state, expected_positioning) in [
+        (
+            &first_rejection,
+            PositioningStatus::Inaccurate {
+                consecutive_rejections: 1,
+            },
+        ),
+        (
+            &second_rejection,
+            PositioningStatus::DegradedPositioning {
+                consecutive_rejections: 2,
+            },
+        ),
+        (&recovered, PositioningStatus::Accurate),
+    ] {
+        let TripState::Navigating {
+            snapped_user_location,
+            positioning,
+            ..
+        } = state
+        else {
+            panic!("Expected state to be navigating");
+        };
+        // The inaccurate locations are never used
+        assert_eq!(*snapped_user_location, initial_user_location);
+        assert_eq!(*positioning, expected_positioning);
+    }
+}
+
+#[test]
+fn snapping_can_be_disabled() {
+    let route = get_route_with_two_steps();
+    let start = route.steps[0].geometry[0];
+    // A few meters away from the route line
+    let user_location = UserLocation {
+        coordinates: GeographicCoordinate {
+            lat: start.lat + 0.000_05,
+            lng: start.lng + 0.000_05,
+        },
+        horizontal_accuracy: 5.0,
+        course_over_ground: None,
+        timestamp: SystemTime::now(),
+        speed: None,
+        altitude: None,
+        vertical_accuracy: None,
+    };
+
+    let snapped_user_location = |snapping| {
+        let controller = NavigationController::new(
+            route.clone(),
+            NavigationControllerConfig {
+                step_advance: StepAdvanceMode::Manual,
+                route_deviation_tracking: RouteDeviationTracking::None,
+                snapping,
+                ..Default::default()
+            },
+        );
+        let TripState::Navigating {
+            snapped_user_location,
+            ..
+        } = controller.get_initial_state(user_location)
+        else {
+            panic!("Expected state to be navigating");
+        };
+        snapped_user_location
+    };
+
+    assert_eq!(snapped_user_location(SnappingMode::None), user_location);
+    assert_ne!(
+        snapped_user_location(SnappingMode::NearestPoint),
+        user_location
+    );
+    assert_eq!(
+        snapped_user_location(SnappingMode::MapMatched),
+        snapped_user_location(SnappingMode::NearestPoint)
+    );
+}
+
+#[test]
+fn replacing_the_route_reports_the_change() {
+    let route = get_route_with_two_steps();
+    let initial_user_location = UserLocation {
+        coordinates: route.steps[0].geometry[0],
+        horizontal_accuracy: 0.0,
+        course_over_ground: None,
+        timestamp: SystemTime::now(),
+        speed: None,
+        altitude: None,
+        vertical_accuracy: None,
+    };
+    // The same route, but twice as slow (ex: due to traffic)
+    let slower_route = Route {
+        steps: route
+            .steps
+            .iter()
+            .map(|step| RouteStep {
+                duration: step.duration * 2.0,
+                ..step.clone()
+            })
+            .collect(),
+        duration: route.duration * 2.0,
+        ..route.clone()
+    };
+
+    let controller = SynchronizedNavigationController::new(
+        route.clone(),
+        NavigationControllerConfig {
+            step_advance: StepAdvanceMode::Manual,
+            route_deviation_tracking: RouteDeviationTracking::None,
+            ..Default::default()
+        },
+        initial_user_location,
+    );
+    let TripState::Navigating { progress, .. } = controller.get_state() else {
+        panic!("Expected state to be navigating");
+    };
+
+    let update = controller.replace_route(slower_route, initial_user_location);
+    assert_eq!(controller.get_state(), update.state);
+    let [NavigationEvent::RouteChanged { change }] = update.events.as_slice() else {
+        panic!("Expected a route change event, got {:?}", update.events);
+    };
+    assert!(change.distance_delta.abs() < 1e-6, "{change:?}");
+    assert!(
+        (change.duration_delta - progress.duration_remaining).abs() < 1e-6,
+        "{change:?}"
+    );
+
+    // Switching back saves the same amount of time
+    let update = controller.replace_route(route, initial_user_location);
+    let [NavigationEvent::RouteChanged { change }] = update.events.as_slice() else {
+        panic!("Expected a route change event, got {:?}", update.events);
+    };
+    assert!(
+        (change.duration_delta + progress.duration_remaining).abs() < 1e-6,
+        "{change:?}"
+    );
+}
+
+#[test]
+fn current_road_name_falls_back_to_geocoder() {
+    struct StaticGeocoder;
+
+    impl RoadNameGeocoder for StaticGeocoder {
+        fn road_name(&self, _coordinate: GeographicCoordinate) -> Option<String> {
+            Some("Parking Lot".to_string())
+        }
+    }
+
+    let mut route = get_route_with_two_steps();
+    route.steps[0].road_name = Some(String::new());
+    let initial_user_location = UserLocation {
+        coordinates: route.steps[0].geometry[0],
+        horizontal_accuracy: 0.0,
+        course_over_ground: None,
+        timestamp: SystemTime::now(),
+        speed: None,
+        altitude: None,
+        vertical_accuracy: None,
+    };
+
+    let controller = NavigationController::new(
+        route,
+        NavigationControllerConfig {
+            step_advance: StepAdvanceMode::Manual,
+            distance_measurement: DistanceMeasurement::Haversine,
+            geometry_retention: GeometryRetention::Full,
+            breadcrumb_recording: BreadcrumbRecording::Disabled,
+            power_mode: PowerMode::Foreground,
+            road_name_lookup: RoadNameLookup::StepNameWithGeocoderFallback {
+                geocoder: Arc::new(StaticGeocoder),
+            },
+            ..NavigationControllerConfig::from_preset(NavigationPreset::Driving)
+        },
+    );
+
+    let initial_state = controller.get_initial_state(initial_user_location);
+    let TripState::Navigating {
+        current_road_name, ..
+    } = &initial_state
+    else {
+        panic!("Expected state to be navigating");
+    };
+    assert_eq!(current_road_name.as_deref(), Some("Parking Lot"));
+
+    // The second step is named
+    let TripState::Navigating {
+        current_road_name, ..
+    } = controller.advance_to_next_step(&initial_state)
+    else {
+        panic!("Expected state to be navigating");
+    };
+    assert_eq!(current_road_name.as_deref(), Some("Seward Highway"));
+}
+
+#[test]
+fn trip_progress_includes_consumption_estimate() {
+    let route = get_route_with_two_steps();
+    let initial_user_location = UserLocation {
+        coordinates: route.steps[0].geometry[0],
+        horizontal_accuracy: 0.0,
+        course_over_ground: None,
+        timestamp: SystemTime::now(),
+        speed: None,
+        altitude: None,
+        vertical_accuracy: None,
+    };
+    let vehicle = VehicleEnergyProfile {
+        mass: 1800.0,
+        drag_area: 0.6,
+        rolling_resistance: 0.01,
+        auxiliary_power: 0.5,
+        powertrain: Powertrain::Electric {
+            efficiency: 0.9,
+            regeneration_efficiency: 0.6,
+        },
+        initial_energy: 40.0,
+    };
+
+    let controller = NavigationController::new(
+        route.clone(),
+        NavigationControllerConfig {
+            step_advance: StepAdvanceMode::Manual,
+            vehicle_energy_profile: Some(vehicle),
+            ..NavigationControllerConfig::from_preset(NavigationPreset::Driving)
+        },
+    );
+
+    let TripState::Navigating { progress, .. } =
+        controller.get_initial_state(initial_user_location)
+    else {
+        panic!("Expected state to be navigating");
+    };
+    let consumption = progress
+        .consumption
+        .expect("Expected a consumption estimate");
+    // The route distance from the backend is rounded, so a tiny distance may appear traveled
+    assert!(consumption.used.abs() < 1e-3);
+    assert!(consumption.remaining > 0.0);
+    let route_estimate = estimate_route_consumption(&route, &vehicle);
+    assert!((consumption.remaining - route_estimate.remaining).abs() < 1e-3);
+    assert!((consumption.at_arrival - route_estimate.at_arrival).abs() < 1e-9);
+    assert!(
+        (consumption.at_arrival - (40.0 - consumption.used - consumption.remaining)).abs() < 1e-9
+    );
+
+    // No estimate unless a vehicle is configured
+    let controller = NavigationController::new(
+        get_route_with_two_steps(),
+        NavigationControllerConfig {
+            step_advance: StepAdvanceMode::Manual,
+            ..NavigationControllerConfig::from_preset(NavigationPreset::Driving)
+        },
+    );
+    let TripState::Navigating { progress, .. } =
+        controller.get_initial_state(initial_user_location)
+    else {
+        panic!("Expected state to be navigating");
+    };
+    assert_eq!(progress.consumption, None);
+}
+
+#[test]
+fn remaining_maneuvers_follow_progress() {
+    let route = get_route_with_two_steps();
+    let initial_user_location = UserLocation {
+        coordinates: route.steps[0].geometry[0],
+        horizontal_accuracy: 0.0,
+        course_over_ground: None,
+        timestamp: SystemTime::now(),
+        speed: None,
+        altitude: None,
+        vertical_accuracy: None,
+    };
+
+    let controller = SynchronizedNavigationController::new(
+        route.clone(),
+        NavigationControllerConfig {
+            step_advance: StepAdvanceMode::Manual,
+            ..NavigationControllerConfig::from_preset(NavigationPreset::Driving)
+        },
+        initial_user_location,
+    );
+    let TripState::Navigating { progress, .. } = controller.get_state() else {
+        panic!("Expected state to be navigating");
+    };
+
+    // Only the arrival remains after the first step
+    let maneuvers = controller.get_remaining_maneuvers();
+    assert_eq!(maneuvers.len(), 1);
+    assert_eq!(maneuvers[0].step, route.steps[1]);
+    assert_eq!(
+        maneuvers[0].visual_instruction.as_ref(),
+        route.steps[0].visual_instructions.first()
+    );
+    assert_eq!(
+        maneuvers[0].distance_to_maneuver,
+        progress.distance_remaining
+    );
+    assert_eq!(
+        maneuvers[0].duration_to_maneuver,
+        progress.duration_remaining
+    );
+    assert_eq!(
+        maneuvers[0].estimated_arrival,
+        initial_user_location.timestamp + Duration::from_secs_f64(progress.duration_remaining)
+    );
+
+    // Halfway along the first step, the maneuver is closer
+    let location = UserLocation {
+        coordinates: route.steps[0].geometry[5],
+        timestamp: initial_user_location.timestamp + Duration::from_secs(5),
+        ..initial_user_location
+    };
+    let state = controller.update_user_location(location);
+    let TripState::Navigating { progress, .. } = &state else {
+        panic!("Expected state to be navigating");
+    };
+    let maneuvers = remaining_maneuvers(&state);
+    assert_eq!(
+        maneuvers[0].distance_to_maneuver,
+        progress.distance_remaining
+    );
+    assert!(maneuvers[0].distance_to_maneuver < route.steps[0].distance);
+
+    // Nothing remains once the user is on the last step
+    controller.advance_to_next_step();
+    assert!(controller.get_remaining_maneuvers().is_empty());
+    assert!(remaining_maneuvers(&TripState::Complete).is_empty());
+}
+
+#[test]
+fn closely_spaced_maneuvers_are_grouped() {
+    let mut route = get_route_with_two_steps();
+    let end_of_first_step = *route.steps[0].geometry.last().unwrap();
+    // A short step between the first maneuver and the arrival
+    route.steps.insert(
+        1,
+        RouteStep {
+            geometry: vec![end_of_first_step, end_of_first_step],
+            distance: 40.0,
+            duration: 5.0,
+            instruction: "Turn left.".to_string(),
+            ..route.steps[0].clone()
+        },
+    );
+    let initial_user_location = UserLocation {
+        coordinates: route.steps[0].geometry[0],
+        horizontal_accuracy: 0.0,
+        course_over_ground: None,
+        timestamp: SystemTime::now(),
+        speed: None,
+        altitude: None,
+        vertical_accuracy: None,
+    };
+    let controller = |max_distance| {
+        NavigationController::new(
+            route.clone(),
+            NavigationControllerConfig {
+                step_advance: StepAdvanceMode::Manual,
+                then_step_max_distance: max_distance,
+                ..NavigationControllerConfig::from_preset(NavigationPreset::Driving)
+            },
+        )
+    };
+    let then_step = |state: &TripState| match state {
+        TripState::Navigating { then_step, .. } => then_step.clone(),
+        _ => panic!("Expected state to be navigating"),
+    };
+
+    // "Turn left, then arrive"
+    let controller_with_then_steps = controller(Some(50.0));
+    let state = controller_with_then_steps.get_initial_state(initial_user_location);
+    assert_eq!(then_step(&state), Some(route.steps[2].clone()));
+
+    // There is no maneuver after the arrival
+    let state = controller_with_then_steps.advance_to_next_step(&state);
+    assert_eq!(then_step(&state), None);
+
+    // The maneuvers are too far apart
+    let state = controller(Some(30.0)).get_initial_state(initial_user_location);
+    assert_eq!(then_step(&state), None);
+    let state = controller(None).get_initial_state(initial_user_location);
+    assert_eq!(then_step(&state), None);
+}
+
+#[test]
+fn guidance_verbosity_filters_prompts() {
+    let mut route = get_route_with_two_steps();
+    route.steps[0].spoken_instructions.push(SpokenInstruction {
+        text: "Drive west on AK 1.".to_string(),
+        ssml: None,
+        trigger_distance_before_maneuver: 284.0,
+        utterance_id: Uuid::new_v4(),
+    });
+    route.incidents.push(Incident {
+        id: "1".to_string(),
+        kind: IncidentKind::Construction,
+        description: None,
+        is_closed: false,
+        start_distance_along_route: 50.0,
+        end_distance_along_route: 100.0,
+    });
+    let initial_user_location = UserLocation {
+        coordinates: route.steps[0].geometry[0],
+        horizontal_accuracy: 0.0,
+        course_over_ground: None,
+        timestamp: SystemTime::now(),
+        speed: None,
+        altitude: None,
+        vertical_accuracy: None,
+    };
+    let prompts = |guidance_verbosity| {
+        let controller = NavigationController::new(
+            route.clone(),
+            NavigationControllerConfig {
+                step_advance: StepAdvanceMode::Manual,
+                guidance_verbosity,
+                ..NavigationControllerConfig::from_preset(NavigationPreset::Driving)
+            },
+        );
+        match controller.get_initial_state(initial_user_location) {
+            TripState::Navigating {
+                spoken_instruction,
+                upcoming_incident,
+                ..
+            } => (spoken_instruction.is_some(), upcoming_incident.is_some()),
+            _ => panic!("Expected state to be navigating"),
+        }
+    };
+
+    assert_eq!(prompts(GuidanceVerbosity::All), (true, true));
+    assert_eq!(prompts(GuidanceVerbosity::ManeuversOnly), (true, false));
+    assert_eq!(prompts(GuidanceVerbosity::AlertsOnly), (false, true));
+    assert_eq!(prompts(GuidanceVerbosity::Muted), (false, false));
+}
+
+#[test]
+fn background_mode_batches_progress() {
+    let route = get_route_with_two_steps();
+    let now = SystemTime::now();
+    let location_at = |index: usize| UserLocation {
+        coordinates: route.steps[0].geometry[index],
+        horizontal_accuracy: 0.0,
+        course_over_ground: None,
+        timestamp: now + Duration::from_secs(index as u64),
+        speed: None,
+        altitude: None,
+        vertical_accuracy: None,
+    };
+    let controller = SynchronizedNavigationController::new(
+        route.clone(),
+        NavigationControllerConfig {
+            step_advance: StepAdvanceMode::Manual,
+            ..NavigationControllerConfig::from_preset(NavigationPreset::Driving)
+        },
+        location_at(0),
+    );
+    let progress = |state: &TripState| match state {
+        TripState::Navigating { progress, .. } => progress.clone(),
+        _ => panic!("Expected state to be navigating"),
+    };
+    let policy = create_default_camera_lookahead_policy();
+    let initial_progress = progress(&controller.get_state());
+    assert!(controller.get_camera_lookahead(&policy).is_some());
+
+    controller.set_power_mode(PowerMode::Background {
+        progress_update_distance: 30.0,
+    });
+    assert_eq!(controller.get_camera_lookahead(&policy), None);
+
+    // About 24m along the route, so the progress is held
+    let state = controller.update_user_location(location_at(1));
+    assert_eq!(progress(&state), initial_progress);
+
+    // About 48m along the route
+    let state = controller.update_user_location(location_at(3));
+    assert!(progress(&state).distance_remaining < initial_progress.distance_remaining - 30.0);
+
+    controller.set_power_mode(PowerMode::Foreground);
+    assert!(controller.get_camera_lookahead(&policy).is_some());
+}
+
+#[test]
+fn paused_navigation_ignores_location_updates() {
+    let route = get_route_with_two_steps();
+    let now = SystemTime::now();
+    let clock = Arc::new(MockClock::new(now));
+    let location_at = |coordinates: GeographicCoordinate, seconds: u64| UserLocation {
+        coordinates,
+        horizontal_accuracy: 0.0,
+        course_over_ground: None,
+        timestamp: now + Duration::from_secs(seconds),
+        speed: None,
+        altitude: None,
+        vertical_accuracy: None,
+    };
+    let controller = SynchronizedNavigationController::new(
+        route.clone(),
+        NavigationControllerConfig {
+            step_advance: StepAdvanceMode::Manual,
+            clock: Some(clock.clone()),
+            ..NavigationControllerConfig::from_preset(NavigationPreset::Driving)
+        },
+        location_at(route.steps[0].geometry[0], 0),
+    );
+    let progress = |state: &TripState| match state {
+        TripState::Navigating { progress, .. } => progress.clone(),
+        _ => panic!("Expected state to be navigating"),
+    };
+
+    clock.set(now + Duration::from_secs(60));
+    controller.update_user_location(location_at(route.steps[0].geometry[0], 60));
+    let paused_state = controller.pause();
+    assert!(controller.is_paused());
+    let initial_progress = progress(&paused_state);
+    assert_eq!(controller.advance_to_next_step(), paused_state);
+
+    // Wandering off to a restaurant several minutes later
+    let restaurant = GeographicCoordinate {
+        lat: 60.537,
+        lng: -149.545,
+    };
+    clock.set(now + Duration::from_secs(600));
+    let update = controller.update_user_location_delta(location_at(restaurant, 600));
+    assert!(update.events.is_empty());
+    let state = controller.get_state();
+    assert_eq!(state, paused_state);
+    let TripState::Navigating {
+        deviation,
+        spoken_instruction,
+        ..
+    } = state
+    else {
+        panic!("Expected state to be navigating");
+    };
+    assert_eq!(deviation, RouteDeviation::NoDeviation);
+    assert_eq!(spoken_instruction, None);
+
+    // Back on the road
+    clock.set(now + Duration::from_secs(1140));
+    let state = controller.resume();
+    assert!(!controller.is_paused());
+    assert_eq!(progress(&state), initial_progress);
+    // Still parked when the first location arrives
+    controller.update_user_location(location_at(route.steps[0].geometry[0], 1140));
+    clock.set(now + Duration::from_secs(1200));
+    let state = controller.update_user_location(location_at(route.steps[0].geometry[3], 1200));
+    assert!(progress(&state).distance_remaining < initial_progress.distance_remaining - 40.0);
+    assert!(matches!(
+        state,
+        TripState::Navigating {
+            deviation: RouteDeviation::NoDeviation,
+            ..
+        }
+    ));
+    // Only the time spent driving (measured with location timestamps) counts towards the trip
+    let summary = controller.get_trip_summary();
+    assert!((summary.elapsed_time - 120.0).abs() < 1e-6);
+}
+
+#[test]
+fn traveled_geometry_can_be_trimmed() {
+    let route = get_route_with_two_steps();
+    let now = SystemTime::now();
+    let location_at = |index: usize| UserLocation {
+        coordinates: route.steps[0].geometry[index],
+        horizontal_accuracy: 0.0,
+        course_over_ground: None,
+        timestamp: now + Duration::from_secs(index as u64),
+        speed: None,
+        altitude: None,
+        vertical_accuracy: None,
+    };
+    let config = |geometry_retention| NavigationControllerConfig {
+        step_advance: StepAdvanceMode::Manual,
+        geometry_retention,
+        ..NavigationControllerConfig::from_preset(NavigationPreset::Driving)
+    };
+    let full = NavigationController::new(route.clone(), config(GeometryRetention::Full));
+    let trimmed = NavigationController::new(
+        route.clone(),
+        config(GeometryRetention::TrimTraveled {
+            rewind_distance: 20.0,
+        }),
+    );
+
+    // About 86m along the route
+    let full_state =
+        full.update_user_location(location_at(5), &full.get_initial_state(location_at(0)));
+    let trimmed_state =
+        trimmed.update_user_location(location_at(5), &trimmed.get_initial_state(location_at(0)));
+    let (
+        TripState::Navigating {
+            remaining_steps: full_steps,
+            progress: full_progress,
+            ..
+        },
+        TripState::Navigating {
+            remaining_steps: trimmed_steps,
+            progress: trimmed_progress,
+            ..
+        },
+    ) = (&full_state, &trimmed_state)
+    else {
+        panic!("Expected both states to be navigating");
+    };
+
+    assert_eq!(full_steps[0].geometry, route.steps[0].geometry);
+    // The vertices more than 20m behind the user are dropped
+    assert_eq!(trimmed_steps[0].geometry, route.steps[0].geometry[4..]);
+    assert_eq!(trimmed_steps[1], route.steps[1]);
+
+    assert!(
+        (full_progress.distance_to_next_maneuver - trimmed_progress.distance_to_next_maneuver)
+            .abs()
+            < 1e-6
+    );
+    assert!((full_progress.distance_remaining - trimmed_progress.distance_remaining).abs() < 1e-6);
+    assert!((full_progress.duration_remaining - trimmed_progress.duration_remaining).abs() < 1e-6);
+}
+
+#[test]
+fn compact_updates_refer_to_the_shared_route() {
+    let route = get_route_with_two_steps();
+    let now = SystemTime::now();
+    let location_at = |index: usize| UserLocation {
+        coordinates: route.steps[0].geometry[index],
+        horizontal_accuracy: 0.0,
+        course_over_ground: None,
+        timestamp: now + Duration::from_secs(index as u64),
+        speed: None,
+        altitude: None,
+        vertical_accuracy: None,
+    };
+    let controller = SynchronizedNavigationController::new(
+        route.clone(),
+        NavigationControllerConfig {
+            step_advance: StepAdvanceMode::DistanceToEndOfStep {
+                distance: 10,
+                minimum_horizontal_accuracy: 16,
+            },
+            arrival_detection: ArrivalDetection::Criteria {
+                max_distance_to_destination: 50.0,
+                max_speed: None,
+                min_dwell_time: Some(60.0),
+            },
+            ..NavigationControllerConfig::from_preset(NavigationPreset::Driving)
+        },
+        location_at(0),
+    );
+
+    let shared_route = controller.get_shared_route();
+    assert_eq!(shared_route.get_route(), route);
+    assert_eq!(shared_route.get_step_count(), 2);
+    assert_eq!(shared_route.get_step(0), Some(route.steps[0].clone()));
+    assert_eq!(shared_route.get_step(2), None);
+    assert_eq!(shared_route.get_steps_from(1), route.steps[1..]);
+    assert!(shared_route.get_steps_from(3).is_empty());
+
+    let TripStateUpdate::Navigating {
+        current_step_index,
+        step_changed,
+        remaining_waypoint_count,
+        progress,
+        driving_side,
+        ..
+    } = controller.update_user_location_compact(location_at(3))
+    else {
+        panic!("Expected the update to be navigating");
+    };
+    assert_eq!(current_step_index, 0);
+    assert!(!step_changed);
+    assert_eq!(remaining_waypoint_count, 1);
+    assert_eq!(driving_side, Some(DrivingSide::Right));
+    // The compact update is consistent with the full state
+    let TripState::Navigating {
+        progress: full_progress,
+        ..
+    } = controller.get_state()
+    else {
+        panic!("Expected state to be navigating");
+    };
+    assert_eq!(progress, full_progress);
+
+    // Reaching the end of the first step
+    assert!(matches!(
+        controller.update_user_location_compact(location_at(9)),
+        TripStateUpdate::Navigating {
+            current_step_index: 1,
+            step_changed: true,
+            ..
+        }
+    ));
+}
+
+#[test]
+fn delta_updates_only_include_changes() {
+    let route = get_route_with_two_steps();
+    let now = SystemTime::now();
+    let location_at = |index: usize| UserLocation {
+        coordinates: route.steps[0].geometry[index],
+        horizontal_accuracy: 0.0,
+        course_over_ground: None,
+        timestamp: now + Duration::from_secs(index as u64),
+        speed: None,
+        altitude: None,
+        vertical_accuracy: None,
+    };
+    let controller = SynchronizedNavigationController::new(
+        route.clone(),
+        NavigationControllerConfig {
+            step_advance: StepAdvanceMode::DistanceToEndOfStep {
+                distance: 10,
+                minimum_horizontal_accuracy: 16,
+            },
+            arrival_detection: ArrivalDetection::StepCompletion,
+            ..NavigationControllerConfig::from_preset(NavigationPreset::Driving)
+        },
+        location_at(0),
+    );
+
+    // Nothing changes for the same location
+    let update = controller.update_user_location_delta(location_at(0));
+    assert_eq!(
+        update.delta,
+        TripStateDelta::Navigating {
+            snapped_user_location: None,
+            progress: None,
+            changes: vec![],
+        }
+    );
+    assert!(update.events.is_empty());
+
+    // Moving along the step changes the location and progress
+    let update = controller.update_user_location_delta(location_at(3));
+    let TripStateDelta::Navigating {
+        snapped_user_location,
+        progress,
+        ..
+    } = update.delta
+    else {
+        panic!("Expected a navigating delta");
+    };
+    let TripState::Navigating {
+        snapped_user_location: expected_location,
+        progress: expected_progress,
+        ..
+    } = controller.get_state()
+    else {
+        panic!("Expected state to be navigating");
+    };
+    assert_eq!(snapped_user_location, Some(expected_location));
+    assert_eq!(progress, Some(expected_progress));
+    assert!(update.events.is_empty());
+
+    // Advancing to the next step is reported by events rather than in the delta
+    let update = controller.update_user_location_delta(location_at(9));
+    assert!(matches!(
+        update.delta,
+        TripStateDelta::Navigating {
+            progress: Some(_),
+            ..
+        }
+    ));
+    assert!(matches!(
+        update.events.as_slice(),
+        [
+            NavigationEvent::StepAdvanced { step },
+            NavigationEvent::WaypointReached { .. },
+        ] if *step == route.steps[1]
+    ));
+
+    // Completing the trip replaces the state
+    let update = controller.update_user_location_delta(UserLocation {
+        timestamp: now + Duration::from_secs(10),
+        ..location_at(9)
+    });
+    assert_eq!(
+        update.delta,
+        TripStateDelta::Replaced {
+            state: TripState::Complete
+        }
+    );
+    assert!(matches!(
+        update.events.as_slice(),
+        [
+            NavigationEvent::TripCompleted,
+            NavigationEvent::TripSummaryAvailable { .. },
+        ]
+    ));
+}
+
+#[test]
+fn completing_a_trip_reports_a_summary() {
+    let route = get_route_with_two_steps();
+    let now = SystemTime::now();
+    let location_at = |index: usize, seconds: u64| UserLocation {
+        coordinates: route.steps[0].geometry[index],
+        horizontal_accuracy: 0.0,
+        course_over_ground: None,
+        timestamp: now + Duration::from_secs(seconds),
+        speed: None,
+        altitude: None,
+        vertical_accuracy: None,
+    };
+    let controller = SynchronizedNavigationController::new(
+        route.clone(),
+        NavigationControllerConfig {
+            step_advance: StepAdvanceMode::DistanceToEndOfStep {
+                distance: 10,
+                minimum_horizontal_accuracy: 16,
+            },
+            arrival_detection: ArrivalDetection::StepCompletion,
+            breadcrumb_recording: BreadcrumbRecording::Simplified { tolerance: 5.0 },
+            ..NavigationControllerConfig::from_preset(NavigationPreset::Driving)
+        },
+        location_at(0, 0),
+    );
+
+    let update =
+        controller.update_user_locations((1..=3).map(|i| location_at(i, i as u64)).collect());
+    assert!(update.events.is_empty());
+    let update = controller.replace_route(route.clone(), location_at(3, 3));
+    assert!(matches!(
+        update.events.as_slice(),
+        [NavigationEvent::RouteChanged { .. }]
+    ));
+
+    // The summary is reported as soon as the trip is complete
+    let update = controller.update_user_locations(
+        (4..=9)
+            .map(|i| location_at(i, i as u64))
+            .chain([location_at(9, 10)])
+            .collect(),
+    );
+    assert_eq!(update.state, TripState::Complete);
+    let Some(NavigationEvent::TripSummaryAvailable { summary }) = update.events.last() else {
+        panic!("Expected a trip summary");
+    };
+    assert_eq!(
+        update.events[update.events.len() - 2],
+        NavigationEvent::TripCompleted
+    );
+    // The user followed the whole first step
+    assert!((summary.distance_traveled - 284.0).abs() < 1.0);
+    assert!((summary.elapsed_time - 10.0).abs() < 0.001);
+    assert!((summary.average_speed.unwrap() - 28.4).abs() < 0.1);
+    assert_eq!(summary.reroute_count, 1);
+    assert!(summary.max_deviation.abs() < f64::EPSILON);
+    assert_eq!(controller.get_trip_summary(), *summary);
+
+    // The breadcrumbs follow the road, with nearly straight stretches simplified
+    let geometry = &route.steps[0].geometry;
+    assert_eq!(summary.breadcrumbs.first(), geometry.first());
+    assert_eq!(summary.breadcrumbs.last(), geometry.last());
+    assert!(summary.breadcrumbs.len() < geometry.len());
+    assert_eq!(controller.get_breadcrumbs(), summary.breadcrumbs);
+}
+
+#[test]
+fn crossing_a_border_reports_the_country_change() {
+    let mut route = get_route_with_two_steps();
+    // Between the third and fourth points of the first step
+    route.steps[0].country_changes = vec![CountryChange {
+        iso_3166: "CA".to_string(),
+        distance_before_maneuver: 240.0,
+    }];
+    // At the start of the last step
+    route.steps[1].country_changes = vec![CountryChange {
+        iso_3166: "US".to_string(),
+        distance_before_maneuver: 0.0,
+    }];
+    let now = SystemTime::now();
+    let location_at = |index: usize| UserLocation {
+        coordinates: route.steps[0].geometry[index],
+        horizontal_accuracy: 0.0,
+        course_over_ground: None,
+        timestamp: now + Duration::from_secs(index as u64),
+        speed: None,
+        altitude: None,
+        vertical_accuracy: None,
+    };
+    let controller = SynchronizedNavigationController::new(
+        route.clone(),
+        NavigationControllerConfig {
+            step_advance: StepAdvanceMode::DistanceToEndOfStep {
+                distance: 10,
+                minimum_horizontal_accuracy: 16,
+            },
+            arrival_detection: ArrivalDetection::StepCompletion,
+            ..NavigationControllerConfig::from_preset(NavigationPreset::Driving)
+        },
+        location_at(0),
+    );
+    let country_changes = |location: UserLocation| -> Vec<String> {
+        controller
+            .update_user_location_delta(location)
+            .events
+            .into_iter()
+            .filter_map(|event| match event {
+                NavigationEvent::CountryChanged { iso_3166 } => Some(iso_3166),
+                _ => None,
+            })
+            .collect()
+    };
+
+    assert!(country_changes(location_at(2)).is_empty());
+    assert_eq!(country_changes(location_at(3)), ["CA"]);
+    // Each border is only reported once
+    assert!(country_changes(location_at(5)).is_empty());
+    // Borders at the start of a step are reported on advancing to it
+    assert_eq!(country_changes(location_at(9)), ["US"]);
+}
+
+#[test]
+fn approaching_a_junction_view_fetches_its_image() {
+    #[derive(Default)]
+    struct RecordingResolver(Mutex<Vec<JunctionView>>);
+
+    impl JunctionViewResolver for RecordingResolver {
+        fn fetch_image(&self, junction_view: JunctionView) {
+            self.0.lock().unwrap().push(junction_view);
+        }
+    }
+
+    let junction_view = JunctionView {
+        image_reference: "https://example.com/jct/1".to_string(),
+        kind: Some("jct".to_string()),
+        trigger_distance_before_maneuver: 100.0,
+    };
+    let mut route = get_route_with_two_steps();
+    route.steps[0].junction_views = vec![junction_view.clone()];
+    let now = SystemTime::now();
+    let location_at = |index: usize| UserLocation {
+        coordinates: route.steps[0].geometry[index],
+        horizontal_accuracy: 0.0,
+        course_over_ground: None,
+        timestamp: now + Duration::from_secs(index as u64),
+        speed: None,
+        altitude: None,
+        vertical_accuracy: None,
+    };
+    let resolver = Arc::new(RecordingResolver::default());
+    let controller = SynchronizedNavigationController::new(
+        route.clone(),
+        NavigationControllerConfig {
+            step_advance: StepAdvanceMode::DistanceToEndOfStep {
+                distance: 10,
+                minimum_horizontal_accuracy: 16,
+            },
+            junction_view_resolver: Some(resolver.clone()),
+            ..NavigationControllerConfig::from_preset(NavigationPreset::Driving)
+        },
+        location_at(0),
+    );
+
+    let update = controller.update_user_location_delta(location_at(3));
+    assert!(update.events.is_empty());
+    assert!(resolver.0.lock().unwrap().is_empty());
+
+    // The last point before the maneuver is ~90m away
+    let update = controller.update_user_location_delta(location_at(8));
+    assert_eq!(
+        update.events,
+        vec![NavigationEvent::JunctionViewApproaching {
+            junction_view: junction_view.clone()
+        }]
+    );
+    assert_eq!(*resolver.0.lock().unwrap(), vec![junction_view]);
+}
+
+#[test]
+fn beginning_the_final_step_reports_the_final_approach() {
+    let mut route = get_route_with_two_steps();
+    // Split the first step so that there is a step before the final one
+    let first_step = route.steps[0].clone();
+    route.steps.insert(
+        0,
+        RouteStep {
+            geometry: first_step.geometry[..=5].to_vec(),
+            distance: 86.3,
+            ..first_step.clone()
+        },
+    );
+    route.steps[1] = RouteStep {
+        geometry: first_step.geometry[5..].to_vec(),
+        distance: 197.8,
+        ..first_step
+    };
+    let entrance = GeographicCoordinate {
+        lat: 60.5352,
+        lng: -149.5486,
+    };
+    let parking = ParkingSuggestion {
+        coordinate: GeographicCoordinate {
+            lat: 60.5348,
+            lng: -149.5484,
+        },
+        name: Some("Trailhead parking".to_string()),
+    };
+    route.destination_details = Some(DestinationDetails {
+        entrances: vec![entrance],
+        parking: vec![parking.clone()],
+    });
+    let now = SystemTime::now();
+    let location_at = |coordinates: GeographicCoordinate, seconds: u64| UserLocation {
+        coordinates,
+        horizontal_accuracy: 0.0,
+        course_over_ground: None,
+        timestamp: now + Duration::from_secs(seconds),
+        speed: None,
+        altitude: None,
+        vertical_accuracy: None,
+    };
+    let controller = SynchronizedNavigationController::new(
+        route.clone(),
+        NavigationControllerConfig {
+            step_advance: StepAdvanceMode::DistanceToEndOfStep {
+                distance: 10,
+                minimum_horizontal_accuracy: 16,
+            },
+            ..NavigationControllerConfig::from_preset(NavigationPreset::Driving)
+        },
+        location_at(route.steps[0].geometry[0], 0),
+    );
+
+    let update = controller.update_user_location_delta(location_at(route.steps[0].geometry[5], 5));
+    let [NavigationEvent::StepAdvanced { step }, NavigationEvent::FinalApproach { guidance }] =
+        update.events.as_slice()
+    else {
+        panic!("Unexpected events: {:?}", update.events);
+    };
+    assert_eq!(*step, route.steps[1]);
+    assert_eq!(guidance.target, entrance);
+    assert!(guidance.target_is_entrance);
+    assert!(guidance.distance_from_route_end < 30.0);
+    assert_eq!(guidance.parking, vec![parking]);
+
+    // The final approach is only reported once
+    let update = controller.update_user_location_delta(location_at(route.steps[1].geometry[2], 8));
+    assert!(update.events.is_empty());
+}
+
+#[test]
+fn stopping_near_the_destination_reports_a_nearby_arrival() {
+    let route = get_route_with_two_steps();
+    let now = SystemTime::now();
+    let location_at = |coordinates: GeographicCoordinate, speed: f64, seconds: u64| UserLocation {
+        coordinates,
+        horizontal_accuracy: 0.0,
+        course_over_ground: None,
+        timestamp: now + Duration::from_secs(seconds),
+        speed: Some(Speed {
+            value: speed,
+            accuracy: None,
+        }),
+        altitude: None,
+        vertical_accuracy: None,
+    };
+    let geometry = &route.steps[0].geometry;
+    let controller = SynchronizedNavigationController::new(
+        route.clone(),
+        NavigationControllerConfig {
+            step_advance: StepAdvanceMode::DistanceToEndOfStep {
+                distance: 10,
+                minimum_horizontal_accuracy: 16,
+            },
+            nearby_arrival_detection: NearbyArrivalDetection::WithinRadius {
+                max_distance_to_destination: 100.0,
+                max_speed: Some(1.0),
+            },
+            ..NavigationControllerConfig::from_preset(NavigationPreset::Driving)
+        },
+        location_at(geometry[0], 0.0, 0),
+    );
+
+    // Stopped, but too far from the destination (~132m)
+    let update = controller.update_user_location_delta(location_at(geometry[7], 0.0, 5));
+    assert!(update.events.is_empty());
+    // Close enough, but still moving
+    let update = controller.update_user_location_delta(location_at(geometry[8], 10.0, 8));
+    assert!(update.events.is_empty());
+
+    // Stopped ~90m before the end of the route (ex: at a locked gate)
+    let update = controller.update_user_location_delta(location_at(geometry[8], 0.0, 10));
+    let [NavigationEvent::ArrivedNearby { distance }] = update.events.as_slice() else {
+        panic!("Unexpected events: {:?}", update.events);
+    };
+    assert!((distance - 90.0).abs() < 1.0);
+    // The trip is not complete until the app decides to complete it
+    assert!(matches!(
+        controller.get_state(),
+        TripState::Navigating { .. }
+    ));
+
+    // The nearby arrival is only reported once
+    let update = controller.update_user_location_delta(location_at(geometry[8], 0.0, 12));
+    assert!(update.events.is_empty());
+}
+
+#[test]
+fn free_drive_transitions_into_navigation() {
+    let route = get_route_with_two_steps();
+    let start = route.steps[0].geometry[0];
+    let now = SystemTime::now();
+    let location_at = |seconds: u64| UserLocation {
+        coordinates: start,
+        horizontal_accuracy: 0.0,
+        course_over_ground: None,
+        timestamp: now + Duration::from_secs(seconds),
+        speed: None,
+        altitude: None,
+        vertical_accuracy: None,
+    };
+    let config = NavigationControllerConfig::from_preset(NavigationPreset::Driving);
+
+    let free_drive = FreeDriveController::new(config.clone(), None);
+    let state = free_drive.get_initial_state(location_at(0));
+    let state = free_drive.update_user_location(location_at(1), &state);
+    assert!(matches!(state, TripState::FreeDrive { .. }));
+
+    // A route arrives
+    let controller = NavigationController::new(route, config);
+    let state = controller.update_user_location(location_at(2), &state);
+    assert_eq!(state, controller.get_initial_state(location_at(2)));
+    assert!(matches!(state, TripState::Navigating { .. }));
+}
+
+#[test]
+fn trip_queue_starts_the_next_trip_on_arrival() {
+    let route = get_route_with_two_steps();
+    let initial_user_location = UserLocation {
+        coordinates: route.steps[0].geometry[0],
+        horizontal_accuracy: 0.0,
+        course_over_ground: None,
+        timestamp: SystemTime::now(),
+        speed: None,
+        altitude: None,
+        vertical_accuracy: None,
+    };
+    let queue = TripQueue::new(
+        route.clone(),
+        vec![route.clone()],
+        NavigationControllerConfig {
+            step_advance: StepAdvanceMode::Manual,
+            ..NavigationControllerConfig::from_preset(NavigationPreset::Driving)
+        },
+        initial_user_location,
+    );
+    assert_eq!(queue.get_upcoming_trip_count(), 1);
+
+    // There are only two steps, so advancing twice completes the first trip
+    queue.advance_to_next_step();
+    let update = queue.advance_to_next_step();
+    assert!(update.events.contains(&NavigationEvent::TripCompleted));
+    assert_eq!(
+        update.events.last(),
+        Some(&NavigationEvent::NextTripStarted {
+            route: route.clone(),
+            upcoming_trip_count: 0,
+        })
+    );
+    assert!(matches!(update.state, TripState::Navigating { .. }));
+    assert_eq!(queue.get_upcoming_trip_count(), 0);
+
+    // The last trip in the queue completes as usual
+    queue.advance_to_next_step();
+    let update = queue.advance_to_next_step();
+    assert_eq!(update.events.last(), Some(&NavigationEvent::TripCompleted));
+    assert_eq!(queue.get_state(), TripState::Complete);
+}
+
+/// Gets a route with two steps, where the first step is split so that there is a corner to wait at.
+fn get_route_with_a_corner() -> Route {
+    let mut route = get_route_with_two_steps();
+    let first_step = route.steps[0].clone();
+    route.steps.insert(
+        0,
+        RouteStep {
+            geometry: first_step.geometry[..=5].to_vec(),
+            distance: 86.3,
+            ..first_step.clone()
+        },
+    );
+    route.steps[1] = RouteStep {
+        geometry: first_step.geometry[5..].to_vec(),
+        distance: 197.8,
+        ..first_step
+    };
+    route
+}
+
+#[test]
+fn deviation_is_ignored_during_the_step_advance_cooldown() {
+    let route = get_route_with_a_corner();
+    let now = SystemTime::now();
+    let location_at = |coordinates: GeographicCoordinate, seconds: u64| UserLocation {
+        coordinates,
+        horizontal_accuracy: 0.0,
+        course_over_ground: None,
+        timestamp: now + Duration::from_secs(seconds),
+        speed: None,
+        altitude: None,
+        vertical_accuracy: None,
+    };
+    let corner = route.steps[0].geometry[5];
+    // About 40m north of the corner
+    let noisy_location = GeographicCoordinate {
+        lat: corner.lat + 0.00036,
+        ..corner
+    };
+    let deviation = |state: &TripState| match state {
+        TripState::Navigating { deviation, .. } => *deviation,
+        _ => panic!("Expected state to be navigating"),
+    };
+
+    for (cooldown, expect_off_route_while_waiting) in [
+        (StepAdvanceCooldown::Disabled, true),
+        (
+            StepAdvanceCooldown::Enabled {
+                duration: 10.0,
+                distance: 50.0,
+            },
+            false,
+        ),
+    ] {
+        let controller = NavigationController::new(
+            route.clone(),
+            NavigationControllerConfig {
+                step_advance: StepAdvanceMode::DistanceToEndOfStep {
+                    distance: 10,
+                    minimum_horizontal_accuracy: 16,
+                },
+                step_advance_cooldown: cooldown,
+                route_deviation_tracking: RouteDeviationTracking::StaticThreshold {
+                    minimum_horizontal_accuracy: 16,
+                    max_acceptable_deviation: 20.0,
+                },
+                ..NavigationControllerConfig::from_preset(NavigationPreset::Driving)
+            },
+        );
+        let state = controller.get_initial_state(location_at(route.steps[0].geometry[0], 0));
+        let state = controller.update_user_location(location_at(corner, 5), &state);
+        let TripState::Navigating {
+            remaining_steps, ..
+        } = &state
+        else {
+            panic!("Expected state to be navigating");
+        };
+        assert_eq!(remaining_steps.len(), 2, "Expected the step to advance");
+
+        // Waiting at the corner with a noisy position
+        let state = controller.update_user_location(location_at(noisy_location, 7), &state);
+        assert_eq!(
+            matches!(deviation(&state), RouteDeviation::OffRoute { .. }),
+            expect_off_route_while_waiting,
+            "{cooldown:?}"
+        );
+
+        // The cooldown has ended
+        let state = controller.update_user_location(location_at(noisy_location, 20), &state);
+        assert!(
+            matches!(deviation(&state), RouteDeviation::OffRoute { .. }),
+            "{cooldown:?}"
+        );
+    }
+}
+
+#[test]
+fn step_regression_is_suppressed_during_the_step_advance_cooldown() {
+    let route = get_route_with_a_corner();
+    let now = SystemTime::now();
+    let location_at = |coordinates: GeographicCoordinate, seconds: u64| UserLocation {
+        coordinates,
+        horizontal_accuracy: 0.0,
+        course_over_ground: None,
+        timestamp: now + Duration::from_secs(seconds),
+        speed: None,
+        altitude: None,
+        vertical_accuracy: None,
+    };
+    let corner = route.steps[0].geometry[5];
+    // About 25m back along the first step
+    let behind_corner = route.steps[0].geometry[4];
+    let step_count = |state: &TripState| match state {
+        TripState::Navigating {
+            remaining_steps, ..
+        } => remaining_steps.len(),
+        _ => panic!("Expected state to be navigating"),
+    };
+    let deviation = |state: &TripState| match state {
+        TripState::Navigating { deviation, .. } => *deviation,
+        _ => panic!("Expected state to be navigating"),
+    };
+
+    for (cooldown, expect_regression_after_cooldown) in [
+        (StepAdvanceCooldown::Disabled, false),
+        (
+            StepAdvanceCooldown::Enabled {
+                duration: 10.0,
+                distance: 50.0,
+            },
+            true,
+        ),
+    ] {
+        let controller = NavigationController::new(
+            route.clone(),
+            NavigationControllerConfig {
+                step_advance: StepAdvanceMode::DistanceToEndOfStep {
+                    distance: 10,
+                    minimum_horizontal_accuracy: 16,
+                },
+                step_advance_cooldown: cooldown,
+                route_deviation_tracking: RouteDeviationTracking::StaticThreshold {
+                    minimum_horizontal_accuracy: 16,
+                    max_acceptable_deviation: 20.0,
+                },
+                ..NavigationControllerConfig::from_preset(NavigationPreset::Driving)
+            },
+        );
+        let state = controller.get_initial_state(location_at(route.steps[0].geometry[0], 0));
+        let state = controller.update_user_location(location_at(corner, 5), &state);
+        assert_eq!(step_count(&state), 2, "Expected the step to advance");
+
+        // A noisy position behind the corner can't move navigation back during the cooldown
+        let state = controller.update_user_location(location_at(behind_corner, 7), &state);
+        assert_eq!(step_count(&state), 2, "{cooldown:?}");
+
+        // The cooldown has ended
+        let update = controller.update_user_locations(vec![location_at(behind_corner, 20)], &state);
+        if expect_regression_after_cooldown {
+            assert_eq!(step_count(&update.state), 3);
+            assert_eq!(deviation(&update.state), RouteDeviation::NoDeviation);
+            assert_eq!(
+                update.events,
+                vec![NavigationEvent::StepRegressed {
+                    step: route.steps[0].clone()
+                }]
+            );
+        } else {
+            assert_eq!(step_count(&update.state), 2);
+            assert!(matches!(
+                deviation(&update.state),
+                RouteDeviation::OffRoute { .. }
+            ));
+            assert!(update.events.is_empty());
+        }
+
+        // Reaching the corner again advances as usual
+        let state = controller.update_user_location(location_at(corner, 21), &update.state);
+        assert_eq!(step_count(&state), 2, "{cooldown:?}");
+    }
+}
@@ -0,0 +1,42 @@
+//! Scenario tests, which replay the GPS traces in `fixtures/scenarios`.
+//!
+//! See [`ferrostar::test_utils`] for the fixture format.
+
+extern crate ferrostar;
+
+use ferrostar::arrival_detection::ArrivalDetection;
+use ferrostar::navigation_controller::models::{NavigationControllerConfig, StepAdvanceMode};
+use ferrostar::navigation_controller::presets::NavigationPreset;
+use ferrostar::test_utils::Scenario;
+
+fn load_scenario(name: &str) -> Scenario {
+    Scenario::load(format!(
+        "{}/tests/fixtures/scenarios/{name}.json",
+        env!("CARGO_MANIFEST_DIR")
+    ))
+    .expect("Unable to load scenario")
+}
+
+#[test]
+fn two_step_arrival() {
+    load_scenario("two_step_arrival").assert_events(NavigationControllerConfig {
+        step_advance: StepAdvanceMode::DistanceToEndOfStep {
+            distance: 0,
+            minimum_horizontal_accuracy: 32,
+        },
+        arrival_detection: ArrivalDetection::StepCompletion,
+        ..NavigationControllerConfig::from_preset(NavigationPreset::Driving)
+    });
+}
+
+#[test]
+fn scripted_signal_loss() {
+    load_scenario("scripted_signal_loss").assert_events(NavigationControllerConfig {
+        step_advance: StepAdvanceMode::DistanceToEndOfStep {
+            distance: 0,
+            minimum_horizontal_accuracy: 32,
+        },
+        arrival_detection: ArrivalDetection::StepCompletion,
+        ..NavigationControllerConfig::from_preset(NavigationPreset::Driving)
+    });
+}